@@ -0,0 +1,136 @@
+use syntax::document::Document;
+use syntax::printer::print;
+use syntax::schema::Schema;
+use syntax::shape::response_shape;
+
+/// Prints the document in the canonical format.
+///
+/// Documents with comments are refused, since the printer would drop them.
+pub fn format(source: &str) -> Result<String, String> {
+    if has_comments(source) {
+        return Err("contains comments, which formatting would remove".into());
+    }
+    let document = syntax::parse(source).map_err(|error| error.to_string())?;
+    Ok(print(&document))
+}
+
+/// Parses and validates a document, and returns the issues found.
+///
+/// The type system definitions of the document are validated as a schema. Its operations are
+/// validated against `schema` when there is one.
+pub fn check(source: &str, schema: Option<&Schema>) -> Vec<String> {
+    let document = match syntax::parse(source) {
+        Ok(document) => document,
+        Err(error) => return vec![error.to_string()],
+    };
+    let mut issues: Vec<String> = Schema::new(&document)
+        .validate()
+        .into_iter()
+        .map(|error| error.message)
+        .collect();
+    if let Some(schema) = schema {
+        issues.extend(check_operations(&document, schema));
+    }
+    issues
+}
+
+fn check_operations(document: &Document, schema: &Schema) -> Vec<String> {
+    document
+        .operations()
+        .iter()
+        .filter_map(|operation| {
+            response_shape(schema, operation)
+                .err()
+                .map(|error| match operation.name {
+                    Some(name) => format!("{}: {}", name, error.message),
+                    None => error.message,
+                })
+        })
+        .collect()
+}
+
+/// Prints the syntax tree of the document as JSON.
+pub fn ast(source: &str) -> Result<String, String> {
+    let document = syntax::parse(source).map_err(|error| error.to_string())?;
+    serde_json::to_string_pretty(&document).map_err(|error| error.to_string())
+}
+
+/// Returns true if the source has a comment outside of its strings.
+fn has_comments(source: &str) -> bool {
+    let mut rest = source;
+    while let Some(index) = rest.find(['#', '"']) {
+        rest = &rest[index..];
+        if rest.starts_with('#') {
+            return true;
+        }
+        let (quote, escape) = if rest.starts_with("\"\"\"") {
+            ("\"\"\"", "\\\"\"\"")
+        } else {
+            ("\"", "\\\"")
+        };
+        rest = &rest[quote.len()..];
+        loop {
+            match rest.find(quote) {
+                Some(end) if rest[..end + quote.len()].ends_with(escape) => {
+                    rest = &rest[end + quote.len()..];
+                }
+                Some(end) if quote == "\"" && rest[..end].ends_with('\\') => {
+                    rest = &rest[end + 1..];
+                }
+                Some(end) => {
+                    rest = &rest[end + quote.len()..];
+                    break;
+                }
+                None => return false,
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_formats() {
+        assert_eq!(
+            format("type User{id:ID! name:String}").unwrap(),
+            "type User {\n  id: ID!\n  name: String\n}\n"
+        );
+        assert!(format("type User {").is_err());
+        assert!(format("# A user\ntype User { id: ID! }").is_err());
+        assert!(format("type User { \"# not a comment\" id: ID! }").is_ok());
+    }
+
+    #[test]
+    fn it_finds_comments() {
+        assert!(!has_comments(r##"{ a(b: "#") }"##));
+        assert!(!has_comments(r##"{ a(b: "\"#") }"##));
+        assert!(!has_comments(
+            "\"\"\"\n# \\\"\"\" #\n\"\"\"\ntype A { a: Int }"
+        ));
+        assert!(has_comments("{ a } # trailing"));
+        assert!(has_comments(r#"{ a(b: "c") } # trailing"#));
+    }
+
+    #[test]
+    fn it_checks_documents() {
+        let schema_document =
+            syntax::parse("type Query { me: User } type User { id: ID! }").unwrap();
+        let schema = Schema::new(&schema_document);
+        assert!(check("query Me { me { id } }", Some(&schema)).is_empty());
+        assert_eq!(check("query Me { me { name } }", Some(&schema)).len(), 1);
+        assert_eq!(check("type Query { me: Missing }", None).len(), 1);
+        assert_eq!(check("{ me", None).len(), 1);
+    }
+
+    #[test]
+    fn it_prints_the_ast() {
+        let json: serde_json::Value = serde_json::from_str(&ast("scalar Time").unwrap()).unwrap();
+        assert_eq!(
+            json["definitions"][0]["TypeSystem"]["Type"]["Scalar"]["name"]["value"],
+            "Time"
+        );
+    }
+}
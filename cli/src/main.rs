@@ -0,0 +1,196 @@
+use clap::{load_yaml, App, ArgMatches};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use syntax::config::{GraphQLConfig, ProjectConfig};
+use syntax::document::Document;
+use syntax::schema::Schema;
+
+mod commands;
+
+pub fn main() {
+    let clap_yaml = load_yaml!("../config/cli.yaml");
+    let matches = App::from_yaml(clap_yaml).get_matches();
+    let succeeded = match matches.subcommand() {
+        ("fmt", Some(matches)) => fmt(matches),
+        ("check", Some(matches)) => check(matches),
+        ("ast", Some(matches)) => ast(matches),
+        _ => unreachable!("clap requires a subcommand"),
+    };
+    if !succeeded {
+        process::exit(1);
+    }
+}
+
+/// The project of the GraphQL config file found from the current directory, if any.
+fn project(matches: &ArgMatches) -> Result<Option<ProjectConfig>, String> {
+    let directory = env::current_dir().map_err(|error| error.to_string())?;
+    let config = match GraphQLConfig::discover(&directory).map_err(|error| error.to_string())? {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    match config.project(matches.value_of("project")) {
+        Some(project) => Ok(Some(project.clone())),
+        None => Err(format!(
+            "{}: no such project",
+            matches.value_of("project").unwrap_or("default")
+        )),
+    }
+}
+
+/// The files given as arguments, or else those of the GraphQL config file.
+fn files(matches: &ArgMatches, project: Option<&ProjectConfig>) -> Result<Vec<PathBuf>, String> {
+    if let Some(files) = matches.values_of("files") {
+        return Ok(files.map(PathBuf::from).collect());
+    }
+    let project = project.ok_or("No file given, and no GraphQL config file found")?;
+    let mut files = project.schema_files().map_err(|error| error.to_string())?;
+    files.extend(
+        project
+            .document_files()
+            .map_err(|error| error.to_string())?,
+    );
+    Ok(files)
+}
+
+fn read(path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(source) => Some(source),
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            None
+        }
+    }
+}
+
+fn fmt(matches: &ArgMatches) -> bool {
+    let check_only = matches.is_present("check");
+    let files = match project(matches).and_then(|project| files(matches, project.as_ref())) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let mut succeeded = true;
+    for path in &files {
+        let path = &*path.to_string_lossy();
+        let source = match read(path) {
+            Some(source) => source,
+            None => {
+                succeeded = false;
+                continue;
+            }
+        };
+        let formatted = match commands::format(&source) {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                eprintln!("{}: {}", path, error);
+                succeeded = false;
+                continue;
+            }
+        };
+        if formatted == source {
+            continue;
+        }
+        if check_only {
+            println!("{}", path);
+            succeeded = false;
+        } else if let Err(error) = fs::write(path, formatted) {
+            eprintln!("{}: {}", path, error);
+            succeeded = false;
+        }
+    }
+    succeeded
+}
+
+fn check(matches: &ArgMatches) -> bool {
+    let project = match project(matches) {
+        Ok(project) => project,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let schema_document = match schema_document(matches, project.as_ref()) {
+        Ok(document) => document,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    let schema = schema_document.as_ref().map(Schema::new);
+    let mut succeeded = true;
+    // The files of a schema split across files are only valid together.
+    let files = if matches.values_of("files").is_none() && schema.is_some() {
+        for error in schema.as_ref().map(Schema::validate).unwrap_or_default() {
+            eprintln!("schema: {}", error.message);
+            succeeded = false;
+        }
+        project.map(|project| project.document_files().map_err(|error| error.to_string()))
+    } else {
+        Some(files(matches, project.as_ref()))
+    };
+    let files = match files.unwrap_or_else(|| Ok(Vec::new())) {
+        Ok(files) => files,
+        Err(error) => {
+            eprintln!("{}", error);
+            return false;
+        }
+    };
+    for path in &files {
+        let path = &*path.to_string_lossy();
+        let issues = match read(path) {
+            Some(source) => commands::check(&source, schema.as_ref()),
+            None => {
+                succeeded = false;
+                continue;
+            }
+        };
+        for issue in &issues {
+            eprintln!("{}: {}", path, issue);
+        }
+        succeeded &= issues.is_empty();
+    }
+    succeeded
+}
+
+/// The schema given as an argument, or else that of the GraphQL config file.
+fn schema_document(
+    matches: &ArgMatches,
+    project: Option<&ProjectConfig>,
+) -> Result<Option<Document>, String> {
+    match (matches.value_of("schema"), project) {
+        (Some(path), _) => {
+            let source =
+                fs::read_to_string(path).map_err(|error| format!("{}: {}", path, error))?;
+            syntax::parse(&source)
+                .map(Some)
+                .map_err(|error| format!("{}: {}", path, error))
+        }
+        (None, Some(project)) if !project.schema.is_empty() => project
+            .load_schema()
+            .map(Some)
+            .map_err(|error| error.to_string()),
+        (None, _) => Ok(None),
+    }
+}
+
+fn ast(matches: &ArgMatches) -> bool {
+    let path = matches.value_of("file").expect("The file is required");
+    let source = match read(path) {
+        Some(source) => source,
+        None => return false,
+    };
+    match commands::ast(&source) {
+        Ok(json) => {
+            println!("{}", json);
+            true
+        }
+        Err(error) => {
+            eprintln!("{}: {}", path, error);
+            false
+        }
+    }
+}
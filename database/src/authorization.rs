@@ -0,0 +1,270 @@
+//! The roles the fields of the schema require, declared with `@auth`.
+//!
+//! ```graphql
+//! type User {
+//!   name: String!
+//!   email: String! @auth(requires: ADMIN)
+//! }
+//!
+//! type AuditLog @auth(requires: ADMIN) {
+//!   entries: [String!]!
+//! }
+//! ```
+//!
+//! A field marked `@auth` is only answered for the clients holding the role it requires, and so
+//! are all the fields of a type marked `@auth`. For the other clients, anonymous ones included, the
+//! field is null with an error. The roles of a client are the ones its [identity] holds. The
+//! generated mutations of a type marked `@auth`, and those writing a field marked `@auth`, are
+//! refused the same way.
+//!
+//! The roles are the values of the `Role` enum. The [default schema] declares it, with `ADMIN`
+//! and `USER`, along with `@auth`, and the schema files are read on top of it.
+//!
+//! [identity]: ../../net/auth/struct.Identity.html
+//! [default schema]: ../../syntax/document/struct.DocumentBuilder.html
+
+use crate::mutation;
+use net::auth::Identity;
+use serde_json::{Map, Value};
+use syntax::nodes::*;
+use syntax::schema::Schema;
+
+/// The role of the clients administering the database, which may change its schema, follow it
+/// and promote its followers.
+pub(crate) const ADMIN: &str = "ADMIN";
+
+/// Whether a client holds the `ADMIN` role.
+pub(crate) fn is_admin(identity: Option<&Identity>) -> bool {
+    identity.is_some_and(|identity| identity.has_role(ADMIN))
+}
+
+/// Checks that a client may query a field of a type, failing with the error of the field when it
+/// does not hold a role the field or the type requires.
+pub(crate) fn authorize(
+    schema: &Schema,
+    type_name: &str,
+    field: &FieldDefinitionNode,
+    identity: Option<&Identity>,
+) -> Result<(), String> {
+    match missing(schema, type_name, Some(field), identity) {
+        Some(role) => Err(format!(
+            "Not authorized to query field `{}` on type `{}`, which requires the role `{}`",
+            field.name.value, type_name, role
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Checks that a client may run a generated mutation, failing when it does not hold a role the
+/// mutated type, or a field its input writes, requires. Other mutations are left to the fields.
+pub(crate) fn authorize_mutation(
+    schema: &Schema,
+    field: &str,
+    arguments: &Map<String, Value>,
+    identity: Option<&Identity>,
+) -> Result<(), String> {
+    let type_name = match mutation::target(schema, field) {
+        Some((_, type_name)) => type_name,
+        None => return Ok(()),
+    };
+    if let Some(role) = missing(schema, type_name, None, identity) {
+        return Err(format!(
+            "Not authorized to mutate type `{}`, which requires the role `{}`",
+            type_name, role
+        ));
+    }
+    let written = match arguments.get("input") {
+        Some(Value::Object(input)) => input.keys().collect(),
+        _ => Vec::new(),
+    };
+    for name in written {
+        let field = schema.field(type_name, name);
+        if let Some(role) =
+            field.and_then(|field| missing(schema, type_name, Some(field), identity))
+        {
+            return Err(format!(
+                "Not authorized to write field `{}` on type `{}`, which requires the role `{}`",
+                name, type_name, role
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The first role a type, or a field of it, requires that the client does not hold.
+fn missing<'s>(
+    schema: &'s Schema,
+    type_name: &str,
+    field: Option<&'s FieldDefinitionNode>,
+    identity: Option<&Identity>,
+) -> Option<&'s str> {
+    let type_directives = match schema.get_type(type_name) {
+        Some(TypeDefinitionNode::Object(object)) => object.directives.as_ref(),
+        _ => None,
+    };
+    let extension_directives = schema
+        .extensions_of(type_name)
+        .iter()
+        .filter_map(|extension| extension.directives.as_ref());
+    type_directives
+        .into_iter()
+        .chain(extension_directives)
+        .chain(field.and_then(|field| field.directives.as_ref()))
+        .flatten()
+        .filter_map(required)
+        .find(|role| !identity.is_some_and(|identity| identity.has_role(role)))
+}
+
+/// The role an `@auth` directive requires.
+fn required(directive: &DirectiveNode) -> Option<&str> {
+    if directive.name.value != "auth" {
+        return None;
+    }
+    let requires = directive
+        .arguments
+        .iter()
+        .flatten()
+        .find(|argument| argument.name.value == "requires")?;
+    match &requires.value {
+        ValueNode::Enum(role) => Some(&role.value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::executor;
+    use crate::resolver::Resolvers;
+    use crate::scalar::Scalars;
+    use crate::store::Store;
+    use net::request::Request;
+    use serde_json::json;
+    use std::sync::RwLock;
+    use syntax::document::Document;
+
+    #[test]
+    fn it_requires_the_roles_of_fields_and_types() {
+        let mut document = Document::default();
+        let types = syntax::parse(
+            r#"
+            type User { name: String! email: String! @auth(requires: ADMIN) }
+            type Audit @auth(requires: USER) { entries: [String!]! secret: String @auth(requires: ADMIN) }
+            "#,
+        )
+        .unwrap();
+        document.definitions.extend(types.definitions);
+        let schema = Schema::new(&document);
+        assert!(schema.get_directive("auth").is_some());
+        assert!(schema.has_type("Role"));
+        let check = |type_name: &str, field: &str, identity: Option<&Identity>| {
+            authorize(
+                &schema,
+                type_name,
+                schema.field(type_name, field).unwrap(),
+                identity,
+            )
+        };
+        let (user, admin) = (
+            Identity::with_roles("ada", &["USER"]),
+            Identity::with_roles("alan", &["ADMIN"]),
+        );
+        assert_eq!(check("User", "name", None), Ok(()));
+        assert_eq!(
+            check("User", "email", Some(&user)),
+            Err("Not authorized to query field `email` on type `User`, which requires the role `ADMIN`".into())
+        );
+        assert_eq!(check("User", "email", Some(&admin)), Ok(()));
+        assert_eq!(check("Audit", "entries", Some(&user)), Ok(()));
+        assert!(check("Audit", "entries", None).is_err());
+        assert!(check("Audit", "secret", Some(&user)).is_err());
+        assert!(check("Audit", "secret", Some(&admin)).is_err());
+    }
+
+    #[test]
+    fn it_answers_null_for_unauthorized_fields() {
+        let source = syntax::parse(
+            "type User { id: ID! name: String! email: String @auth(requires: ADMIN) }",
+        )
+        .unwrap();
+        let schema = Catalog::new(source).schema();
+        let schema = Schema::new(&schema);
+        let store = RwLock::new(
+            Store::from_json(
+                r#"{ "User": [{ "id": 1, "name": "Ada", "email": "ada@example.com" }] }"#,
+            )
+            .unwrap(),
+        );
+        let run = |identity: Option<Identity>| {
+            let query = "{ users { nodes { name email } } }";
+            let mut request = Request::new(query);
+            request.identity = identity;
+            let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+            let document = syntax::parse(query).unwrap();
+            executor::execute(&schema, &resolvers, &scalars, &store, &document, &request)
+        };
+        assert_eq!(
+            run(None),
+            json!({
+                "data": { "users": { "nodes": [{ "name": "Ada", "email": null }] } },
+                "errors": [{
+                    "message": "Not authorized to query field `email` on type `User`, which requires the role `ADMIN`",
+                    "locations": [{ "line": 1, "column": 24 }],
+                    "path": ["users", "nodes", 0, "email"],
+                }],
+            })
+        );
+        assert_eq!(
+            run(Some(Identity::with_roles("alan", &["ADMIN"]))),
+            json!({ "data": { "users": { "nodes": [{ "name": "Ada", "email": "ada@example.com" }] } } })
+        );
+    }
+
+    #[test]
+    fn it_refuses_the_mutations_of_unauthorized_types_and_fields() {
+        let source = syntax::parse(
+            r#"
+            type Secret @auth(requires: ADMIN) { id: ID! text: String! }
+            type User { id: ID! name: String! email: String @auth(requires: ADMIN) }
+            "#,
+        )
+        .unwrap();
+        let schema = Catalog::new(source).schema();
+        let schema = Schema::new(&schema);
+        let store = RwLock::new(Store::new());
+        let run = |query: &str, identity: Option<Identity>| {
+            let mut request = Request::new(query);
+            request.identity = identity;
+            let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+            let document = syntax::parse(query).unwrap();
+            executor::execute(&schema, &resolvers, &scalars, &store, &document, &request)
+        };
+        let create_secret = r#"mutation { createSecret(input: { text: "x" }) { __typename } }"#;
+        assert_eq!(
+            run(create_secret, None)["errors"][0]["message"],
+            "Not authorized to mutate type `Secret`, which requires the role `ADMIN`"
+        );
+        let create_user =
+            r#"mutation { createUser(input: { name: "Ada", email: "ada@example.com" }) { name } }"#;
+        assert_eq!(
+            run(create_user, None)["errors"][0]["message"],
+            "Not authorized to write field `email` on type `User`, which requires the role `ADMIN`"
+        );
+        assert!(store.read().unwrap().records("Secret").is_empty());
+        assert!(store.read().unwrap().records("User").is_empty());
+
+        let admin = Identity::with_roles("alan", &["ADMIN"]);
+        assert_eq!(
+            run(create_secret, Some(admin)),
+            json!({ "data": { "createSecret": { "__typename": "Secret" } } })
+        );
+        assert_eq!(
+            run(
+                r#"mutation { createUser(input: { name: "Ada" }) { name } }"#,
+                None
+            ),
+            json!({ "data": { "createUser": { "name": "Ada" } } })
+        );
+    }
+}
@@ -0,0 +1,778 @@
+//! The schema the database serves, and the changes made to it over the wire.
+//!
+//! The schema starts as the default schema extended with the `--schema` file. A request whose
+//! document holds type-system definitions instead of operations changes it, when its client holds
+//! the `ADMIN` role: its types, directives and extensions are checked against the current schema,
+//! then applied at once, so that every request sees either the schema before the change or the
+//! one after it. The generated fields are
+//! generated again for the new types, and the new `@index` fields are indexed.
+//!
+//! Every change makes a new version of the schema, the `--schema` file alone being version 0.
+//! The versions are listed by the `__schemaHistory` query field, and the
+//! `__rollbackSchema(version: Int!)` mutation field makes a new version with the schema of an
+//! earlier one, for the clients holding the `ADMIN` role. Like introspection, these fields are
+//! answered outside the schema, and only when an operation selects nothing else:
+//!
+//! ```graphql
+//! { __schemaHistory { version change rollbackTo } }
+//! mutation { __rollbackSchema(version: 2) }
+//! ```
+//!
+//! A rollback is refused when the records stored do not fit the earlier schema: when it would
+//! break a type or a field some records hold values of.
+//!
+//! With a data directory, every version is kept in its `schema` directory, a file per version,
+//! and made again on startup.
+
+use crate::authorization;
+use crate::coercion::Coercion;
+use crate::executor;
+use crate::incremental;
+use crate::index;
+use crate::mutation;
+use crate::query;
+use crate::resolver::Resolvers;
+use crate::scalar::Scalars;
+use crate::store::{Record, Store};
+use crate::subscription;
+use net::request::Request;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use syntax::diff::{diff, Criticality};
+use syntax::document::Document;
+use syntax::nodes::*;
+use syntax::printer::print;
+use syntax::schema::Schema;
+
+/// The schema the history fields are answered against.
+const HISTORY_SCHEMA: &str = r#"
+type Query {
+  "The versions of the schema, or the one with the given number"
+  __schemaHistory(version: Int): [SchemaVersion!]!
+}
+
+type Mutation {
+  "Makes a new version with the schema of an earlier version, and returns its number"
+  __rollbackSchema(version: Int!): Int!
+}
+
+type SchemaVersion {
+  version: Int!
+  "The schema change sent, unless the version is the first or a rollback"
+  change: String
+  "The version rolled back to, when the version is a rollback"
+  rollbackTo: Int
+  "The schema of the version, as it was written"
+  schema: String!
+}
+"#;
+
+/// How a version of the schema was made from the one before.
+#[derive(Clone, Debug, PartialEq)]
+enum Revision {
+    Change(Document),
+    Rollback(usize),
+}
+
+pub(crate) struct Catalog {
+    /// The schema of every version as it was written, without the generated definitions
+    sources: Vec<Document>,
+    /// How every version but the first was made
+    revisions: Vec<Revision>,
+    /// The schema requests are executed against
+    served: Arc<Document>,
+    /// Where the versions are kept, with a data directory
+    dir: Option<PathBuf>,
+}
+
+/// A request for the history of the schema rather than for the records.
+pub(crate) enum Command<'q> {
+    History,
+    /// The operation selecting the rollback, and the field
+    Rollback(&'q QueryDefinitionNode, &'q FieldNode),
+}
+
+impl Catalog {
+    pub fn new(source: Document) -> Catalog {
+        Catalog {
+            served: Arc::new(generate(&source)),
+            sources: vec![source],
+            revisions: Vec::new(),
+            dir: None,
+        }
+    }
+
+    /// Makes again the versions kept in a data directory, and keeps the later ones there.
+    pub fn open(source: Document, data_dir: &Path) -> io::Result<Catalog> {
+        let dir = data_dir.join("schema");
+        fs::create_dir_all(&dir)?;
+        let mut files: Vec<PathBuf> = fs::read_dir(&dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<_>>()?;
+        files.retain(|file| {
+            file.extension()
+                .is_some_and(|extension| extension == "graphql" || extension == "rollback")
+        });
+        files.sort();
+        let mut catalog = Catalog::new(source);
+        for (number, file) in files.iter().enumerate() {
+            let invalid = |e: String| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", file.display(), e),
+                )
+            };
+            let version: Option<usize> = file
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse().ok());
+            if version != Some(number + 1) {
+                return Err(invalid(format!("Expected version {}", number + 1)));
+            }
+            let contents = fs::read_to_string(file)?;
+            let revision = if file
+                .extension()
+                .is_some_and(|extension| extension == "rollback")
+            {
+                match contents.trim().parse() {
+                    Ok(version) if version <= number => Revision::Rollback(version),
+                    _ => return Err(invalid("Not an earlier version".into())),
+                }
+            } else {
+                Revision::Change(syntax::parse(&contents).map_err(|e| invalid(e.to_string()))?)
+            };
+            catalog.revise(revision);
+        }
+        catalog.served = Arc::new(generate(catalog.source()));
+        catalog.dir = Some(dir);
+        Ok(catalog)
+    }
+
+    /// The schema requests are executed against.
+    pub fn schema(&self) -> Arc<Document> {
+        self.served.clone()
+    }
+
+    /// The number of the current version of the schema.
+    pub fn version(&self) -> usize {
+        self.revisions.len()
+    }
+
+    /// The schema of the current version, as it was written.
+    pub fn source(&self) -> &Document {
+        self.sources.last().expect("There is a first version")
+    }
+
+    /// Applies a change to the schema, indexing the records of the store it declares indexes on,
+    /// and returns the new version of the schema. Returns the conflicts of the change with the
+    /// current schema instead when there are any, leaving the schema as it was.
+    pub fn apply(&mut self, change: &Document, store: &mut Store) -> Result<usize, Vec<String>> {
+        let mut conflicts = conflicts(self.source(), change);
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        let mut source = self.source().clone();
+        source
+            .definitions
+            .extend(change.definitions.iter().cloned());
+        let known: HashSet<String> = Schema::new(self.source())
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        conflicts.extend(
+            Schema::new(&source)
+                .validate()
+                .into_iter()
+                .map(|error| error.message)
+                .filter(|message| !known.contains(message)),
+        );
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        self.make(Revision::Change(change.clone()), generate(&source), store)
+    }
+
+    /// Makes a new version of the schema with the schema of an earlier version, and returns its
+    /// number. Returns the reasons it cannot instead, when the version does not exist or the
+    /// records of the store do not fit its schema.
+    pub fn rollback(&mut self, version: usize, store: &mut Store) -> Result<usize, Vec<String>> {
+        if version >= self.version() {
+            return Err(vec![format!(
+                "There is no version {} before version {}",
+                version,
+                self.version()
+            )]);
+        }
+        let served = generate(&self.sources[version]);
+        let conflicts: Vec<String> = diff(&Schema::new(&self.served), &Schema::new(&served))
+            .into_iter()
+            .filter(|change| {
+                change.criticality == Criticality::Breaking
+                    && holds_records(store, &change.coordinate)
+            })
+            .map(|change| {
+                format!(
+                    "The records do not fit version {}: {}",
+                    version, change.message
+                )
+            })
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        self.make(Revision::Rollback(version), served, store)
+    }
+
+    /// Serves the schema of the files the schema was read from, after they changed, with the
+    /// changes made since applied again. Returns the conflicts of the files with these changes
+    /// instead when there are any, leaving the schema as it was.
+    pub fn reload(&mut self, source: Document, store: &mut Store) -> Result<(), Vec<String>> {
+        let known: HashSet<String> = Schema::new(self.source())
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        let mut reloaded = Catalog {
+            sources: vec![source],
+            revisions: Vec::new(),
+            served: self.served.clone(),
+            dir: None,
+        };
+        for (made, revision) in self.revisions.iter().enumerate() {
+            if let Revision::Change(change) = revision {
+                let conflicts = conflicts(reloaded.source(), change);
+                if !conflicts.is_empty() {
+                    return Err(conflicts
+                        .into_iter()
+                        .map(|conflict| format!("Version {}: {}", made + 1, conflict))
+                        .collect());
+                }
+            }
+            reloaded.revise(revision.clone());
+        }
+        let conflicts: Vec<String> = Schema::new(reloaded.source())
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .filter(|message| !known.contains(message))
+            .collect();
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        self.serve(generate(reloaded.source()), store, None)?;
+        self.sources = reloaded.sources;
+        Ok(())
+    }
+
+    /// Keeps the revision in the data directory, and serves its schema.
+    fn make(
+        &mut self,
+        revision: Revision,
+        served: Document,
+        store: &mut Store,
+    ) -> Result<usize, Vec<String>> {
+        self.serve(served, store, Some(&revision))?;
+        self.revise(revision);
+        Ok(self.version())
+    }
+
+    /// Indexes the fields the new schema declares indexes on, drops the indexes it no longer
+    /// declares, keeps the revision making it in the data directory and serves the new schema.
+    fn serve(
+        &mut self,
+        served: Document,
+        store: &mut Store,
+        revision: Option<&Revision>,
+    ) -> Result<(), Vec<String>> {
+        {
+            let indexed = index::declared(&Schema::new(&self.served));
+            let schema = Schema::new(&served);
+            let declared = index::declared(&schema);
+            let mut added = declared.clone();
+            added.retain(|index| !indexed.contains(index));
+            index::build(store, &added).map_err(|e| vec![e])?;
+            if let (Some(dir), Some(revision)) = (&self.dir, revision) {
+                if let Err(e) = persist(dir, self.version() + 1, revision) {
+                    for (type_name, field, _) in added {
+                        store.unindex(type_name, field);
+                    }
+                    return Err(vec![format!("Unable to keep the schema version: {}", e)]);
+                }
+            }
+            for (type_name, field, _) in indexed {
+                let kept = declared.iter().any(|(kept_type, kept_field, _)| {
+                    (*kept_type, *kept_field) == (type_name, field)
+                });
+                if !kept {
+                    store.unindex(type_name, field);
+                }
+            }
+        }
+        self.served = Arc::new(served);
+        Ok(())
+    }
+
+    /// Adds a version, without generating its schema.
+    fn revise(&mut self, revision: Revision) {
+        let source = match &revision {
+            Revision::Change(change) => {
+                let mut source = self.source().clone();
+                source
+                    .definitions
+                    .extend(change.definitions.iter().cloned());
+                source
+            }
+            Revision::Rollback(version) => self.sources[*version].clone(),
+        };
+        self.sources.push(source);
+        self.revisions.push(revision);
+    }
+
+    /// Answers an operation selecting the history of the schema.
+    pub fn history(&self, document: &Document, request: &Request) -> Value {
+        let versions: Vec<Record> = self
+            .sources
+            .iter()
+            .enumerate()
+            .map(|(version, source)| {
+                let revision = version.checked_sub(1).map(|made| &self.revisions[made]);
+                let record = json!({
+                    "version": version,
+                    "change": match revision {
+                        Some(Revision::Change(change)) => Some(print(change)),
+                        _ => None,
+                    },
+                    "rollbackTo": match revision {
+                        Some(Revision::Rollback(version)) => Some(version),
+                        _ => None,
+                    },
+                    "schema": print(source),
+                });
+                record.as_object().cloned().expect("A version is an object")
+            })
+            .collect();
+        let tables = json!({ "SchemaVersion": versions }).to_string();
+        let versions = Store::from_json(&tables).expect("The versions are records");
+        let versions = RwLock::new(versions);
+        let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+        let schema = Schema::new(history_schema());
+        executor::execute(&schema, &resolvers, &scalars, &versions, document, request)
+    }
+
+    /// Answers the `__rollbackSchema` field of an operation, which only the clients holding the
+    /// `ADMIN` role may select.
+    pub fn answer_rollback(
+        &mut self,
+        operation: &QueryDefinitionNode,
+        field: &FieldNode,
+        request: &Request,
+        store: &RwLock<Store>,
+    ) -> Value {
+        if !authorization::is_admin(request.identity.as_ref()) {
+            return errors(vec![format!(
+                "Rolling the schema back requires the role `{}`",
+                authorization::ADMIN
+            )]);
+        }
+        let key = field
+            .alias
+            .as_ref()
+            .unwrap_or(&field.name)
+            .value
+            .to_string();
+        let schema = Schema::new(history_schema());
+        let rolled_back = rollback_version(&schema, operation, field, request)
+            .map_err(|e| vec![e])
+            .and_then(|version| {
+                self.rollback(
+                    version,
+                    &mut store.write().expect("The store lock is poisoned"),
+                )
+            });
+        match rolled_back {
+            Ok(version) => json!({ "data": { key: version } }),
+            Err(messages) => errors(messages),
+        }
+    }
+}
+
+/// The schema the history fields are answered against, parsed once.
+fn history_schema() -> &'static Document {
+    static HISTORY: OnceLock<Document> = OnceLock::new();
+    HISTORY.get_or_init(|| syntax::parse(HISTORY_SCHEMA).expect("The history schema is valid"))
+}
+
+/// Whether a document changes the schema rather than running operations.
+pub(crate) fn changes_schema(document: &Document) -> bool {
+    document
+        .definitions
+        .iter()
+        .any(|definition| !matches!(definition, DefinitionNode::Executable(_)))
+}
+
+/// The request for the history of the schema a document makes, when the operation it runs
+/// selects nothing but the history fields.
+pub(crate) fn command<'q>(document: &'q Document, request: &Request) -> Option<Command<'q>> {
+    match root_fields(document, request)? {
+        (Operation::Query, _, fields)
+            if !fields.is_empty()
+                && fields
+                    .iter()
+                    .all(|field| field.name.value == "__schemaHistory") =>
+        {
+            Some(Command::History)
+        }
+        (Operation::Mutation, definition, fields) if fields.len() == 1 => {
+            let field = fields[0];
+            if field.name.value == "__rollbackSchema" {
+                Some(Command::Rollback(definition, field))
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The kind of the operation a document runs, with its definition and its fields, when it selects
+/// nothing but fields.
+pub(crate) fn root_fields<'q>(
+    document: &'q Document,
+    request: &Request,
+) -> Option<(Operation, &'q QueryDefinitionNode, Vec<&'q FieldNode>)> {
+    let mut operations = document.operations();
+    let operation = match &request.operation_name {
+        Some(name) => operations
+            .drain(..)
+            .find(|operation| operation.name == Some(name.as_str()))?,
+        None if operations.len() == 1 => operations.remove(0),
+        None => return None,
+    };
+    let definition = operation.operation.definition();
+    let fields: Vec<&FieldNode> = definition
+        .selections
+        .iter()
+        .map(|selection| match selection {
+            Selection::Field(field) => Some(field),
+            Selection::Fragment(_) => None,
+        })
+        .collect::<Option<_>>()?;
+    Some((operation.operation.operation(), definition, fields))
+}
+
+/// A response with the given errors.
+pub(crate) fn errors(messages: Vec<String>) -> Value {
+    let errors: Vec<Value> = messages
+        .into_iter()
+        .map(|message| json!({ "message": message }))
+        .collect();
+    json!({ "errors": errors })
+}
+
+/// The version the `__rollbackSchema` field asks for.
+fn rollback_version(
+    schema: &Schema,
+    operation: &QueryDefinitionNode,
+    field: &FieldNode,
+    request: &Request,
+) -> Result<usize, String> {
+    let provided = request.variables.clone().unwrap_or_default();
+    let scalars = Scalars::default();
+    let variables = Coercion::variables(schema, &scalars, &operation.variables, &provided)?;
+    let definition = schema
+        .field("Mutation", "__rollbackSchema")
+        .expect("The history schema defines the rollback");
+    let arguments = Coercion::new(schema, &scalars, &variables).arguments(field, definition)?;
+    match arguments.get("version").and_then(Value::as_u64) {
+        Some(version) => Ok(version as usize),
+        None => Err("`version` must be a version number".into()),
+    }
+}
+
+/// Whether the records of a store hold values of the type or the field at a schema coordinate.
+fn holds_records(store: &Store, coordinate: &str) -> bool {
+    if coordinate.starts_with('@') || coordinate.contains('(') {
+        return false;
+    }
+    match coordinate.split_once('.') {
+        None => !store.records(coordinate).is_empty(),
+        Some((type_name, field)) => store
+            .records(type_name)
+            .iter()
+            .any(|record| record.get(field).is_some_and(|value| !value.is_null())),
+    }
+}
+
+/// The schema served for a schema as it was written: with the index, defer and stream directives,
+/// and the generated mutations, query fields and subscriptions.
+fn generate(source: &Document) -> Document {
+    let mut schema = source.clone();
+    index::generate(&mut schema);
+    incremental::generate(&mut schema);
+    // The mutations come first, so that no inputs are generated for the connection types.
+    mutation::generate(&mut schema);
+    query::generate(&mut schema);
+    subscription::generate(&mut schema);
+    schema
+}
+
+/// The definitions of a change that clash with the schema, or with each other.
+fn conflicts(source: &Document, change: &Document) -> Vec<String> {
+    let schema = Schema::new(source);
+    let mut types = HashSet::new();
+    let mut directives = HashSet::new();
+    let mut conflicts = Vec::new();
+    for definition in &change.definitions {
+        match definition {
+            DefinitionNode::Executable(_) => {
+                conflicts.push("A schema change cannot hold operations or fragments".into())
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(_)) => {
+                conflicts.push("The schema definition cannot be changed".into())
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(definition)) => {
+                let name = definition.name();
+                if schema.has_type(name) || !types.insert(name) {
+                    conflicts.push(format!("The type `{}` is already defined", name));
+                }
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(definition)) => {
+                let name = definition.name.value.as_str();
+                if schema.get_directive(name).is_some() || !directives.insert(name) {
+                    conflicts.push(format!("The directive `@{}` is already defined", name));
+                }
+            }
+            DefinitionNode::Extension(_) => (),
+        }
+    }
+    conflicts
+}
+
+/// Writes a version to the schema directory: the change as SDL, or the version rolled back to.
+/// The file is written beside its place first, so that it is whole if the machine goes down.
+fn persist(dir: &Path, version: usize, revision: &Revision) -> io::Result<()> {
+    let (extension, contents) = match revision {
+        Revision::Change(change) => ("graphql", print(change)),
+        Revision::Rollback(version) => ("rollback", format!("{}\n", version)),
+    };
+    let file = dir.join(format!("{:04}.{}", version, extension));
+    let written = file.with_extension(format!("{}.tmp", extension));
+    fs::write(&written, contents)?;
+    fs::File::open(&written)?.sync_all()?;
+    fs::rename(&written, &file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use net::auth::Identity;
+
+    fn catalog() -> Catalog {
+        let mut source = Document::default();
+        let types = syntax::parse("type Query { version: Int } type User { id: ID! }").unwrap();
+        source.definitions.extend(types.definitions);
+        Catalog::new(source)
+    }
+
+    fn record(value: Value) -> Record {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn it_applies_schema_changes() {
+        let mut catalog = catalog();
+        let mut store = Store::new();
+        let change = syntax::parse(
+            r#"
+            type Post { id: ID! title: String @index author: User }
+            extend type User { name: String posts: [Post!] }
+            "#,
+        )
+        .unwrap();
+        assert!(changes_schema(&change));
+        assert_eq!(catalog.apply(&change, &mut store), Ok(1));
+        let served = catalog.schema();
+        let schema = Schema::new(&served);
+        assert!(schema.field("User", "name").is_some());
+        assert!(schema.field("Mutation", "createPost").is_some());
+        assert!(schema.field("Query", "posts").is_some());
+        assert!(store.lookup("Post", "title", &"a".into()).is_some());
+    }
+
+    #[test]
+    fn it_reports_conflicts() {
+        let mut catalog = catalog();
+        let mut store = Store::new();
+        let mut conflicts = |sdl: &str| {
+            catalog
+                .apply(&syntax::parse(sdl).unwrap(), &mut store)
+                .unwrap_err()
+        };
+        assert_eq!(
+            conflicts("type User { id: ID! } directive @index on FIELD_DEFINITION"),
+            vec!["The type `User` is already defined"]
+        );
+        assert_eq!(
+            conflicts("type Post { author: Author } extend type User { id: ID! }"),
+            vec![
+                "Unknown type Author referred to by Post",
+                "Invalid Extension: Cannot redefine field(s) id",
+            ]
+        );
+        assert_eq!(
+            conflicts("schema { query: User } type A { a: Int } type A { b: Int }"),
+            vec![
+                "The schema definition cannot be changed",
+                "The type `A` is already defined",
+            ]
+        );
+        assert_eq!(catalog.version(), 0);
+        assert!(!changes_schema(&syntax::parse("{ version }").unwrap()));
+    }
+
+    #[test]
+    fn it_rolls_back_when_the_records_fit() {
+        let mut catalog = catalog();
+        let mut store = Store::new();
+        let change = syntax::parse("type Post { id: ID! title: String @index }").unwrap();
+        assert_eq!(catalog.apply(&change, &mut store), Ok(1));
+        let change = syntax::parse("extend type User { name: String }").unwrap();
+        assert_eq!(catalog.apply(&change, &mut store), Ok(2));
+        store
+            .insert("User", record(json!({ "name": "alice" })))
+            .unwrap();
+
+        assert_eq!(
+            catalog.rollback(1, &mut store),
+            Err(vec![
+                "The records do not fit version 1: The field `User.name` was removed".to_string()
+            ])
+        );
+        assert_eq!(
+            catalog.rollback(2, &mut store),
+            Err(vec!["There is no version 2 before version 2".to_string()])
+        );
+        store
+            .update("User", &json!("1"), record(json!({ "name": null })))
+            .unwrap();
+        assert_eq!(catalog.rollback(1, &mut store), Ok(3));
+        assert_eq!(catalog.rollback(0, &mut store), Ok(4));
+        let served = catalog.schema();
+        assert!(!Schema::new(&served).has_type("Post"));
+        assert!(store.lookup("Post", "title", &"a".into()).is_none());
+    }
+
+    #[test]
+    fn it_reloads_the_first_version() {
+        let mut catalog = catalog();
+        let mut store = Store::new();
+        let change = syntax::parse("type Post { id: ID! author: User }").unwrap();
+        assert_eq!(catalog.apply(&change, &mut store), Ok(1));
+        let source = |sdl: &str| {
+            let mut source = Document::default();
+            source
+                .definitions
+                .extend(syntax::parse(sdl).unwrap().definitions);
+            source
+        };
+
+        let reloaded = source("type Query { version: Int } type User { id: ID! name: String }");
+        assert_eq!(catalog.reload(reloaded, &mut store), Ok(()));
+        let served = catalog.schema();
+        assert!(Schema::new(&served).field("User", "name").is_some());
+        assert!(Schema::new(&served).has_type("Post"));
+        assert_eq!(catalog.version(), 1);
+
+        let reloaded = source("type Query { version: Int } type Post { id: ID! }");
+        assert_eq!(
+            catalog.reload(reloaded, &mut store),
+            Err(vec![
+                "Version 1: The type `Post` is already defined".to_string()
+            ])
+        );
+        let reloaded = source("type Query { version: Int } type Account { id: ID! }");
+        assert_eq!(
+            catalog.reload(reloaded, &mut store),
+            Err(vec!["Unknown type User referred to by Post".to_string()])
+        );
+        assert!(Schema::new(&catalog.schema()).has_type("User"));
+    }
+
+    #[test]
+    fn it_answers_the_history_fields() {
+        let mut catalog = catalog();
+        let store = RwLock::new(Store::new());
+        let change = syntax::parse("type Post { id: ID! }").unwrap();
+        catalog.apply(&change, &mut store.write().unwrap()).unwrap();
+        let mut answer_as = |query: &str, variables: Value, identity: Option<Identity>| {
+            let mut request = Request::new(query);
+            request.variables = variables.as_object().cloned();
+            request.identity = identity;
+            let document = syntax::parse(query).unwrap();
+            match command(&document, &request).expect("A history command") {
+                Command::History => catalog.history(&document, &request),
+                Command::Rollback(operation, field) => {
+                    catalog.answer_rollback(operation, field, &request, &store)
+                }
+            }
+        };
+        assert_eq!(
+            answer_as("mutation { __rollbackSchema(version: 0) }", json!({}), None),
+            json!({ "errors": [{ "message": "Rolling the schema back requires the role `ADMIN`" }] })
+        );
+        let admin = Identity::with_roles("alan", &["ADMIN"]);
+        let mut answer =
+            |query: &str, variables: Value| answer_as(query, variables, Some(admin.clone()));
+        assert_eq!(
+            answer(
+                "mutation($v: Int!) { back: __rollbackSchema(version: $v) }",
+                json!({ "v": 0 })
+            ),
+            json!({ "data": { "back": 2 } })
+        );
+        assert_eq!(
+            answer(
+                "{ __schemaHistory { version change rollbackTo } }",
+                json!({})
+            ),
+            json!({ "data": { "__schemaHistory": [
+                { "version": 0, "change": null, "rollbackTo": null },
+                { "version": 1, "change": "type Post {\n  id: ID!\n}\n", "rollbackTo": null },
+                { "version": 2, "change": null, "rollbackTo": 0 },
+            ] } })
+        );
+        assert_eq!(
+            answer("mutation { __rollbackSchema(version: 7) }", json!({})),
+            json!({ "errors": [{ "message": "There is no version 7 before version 2" }] })
+        );
+        let document = syntax::parse("{ __schemaHistory { version } version }").unwrap();
+        assert!(command(&document, &Request::new("")).is_none());
+    }
+
+    #[test]
+    fn it_keeps_versions_in_the_data_directory() {
+        let dir = std::env::temp_dir().join(format!("database-catalog-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut catalog = Catalog::open(Document::default(), &dir).unwrap();
+        let mut store = Store::new();
+        let change = syntax::parse("type User { id: ID! }").unwrap();
+        assert_eq!(catalog.apply(&change, &mut store), Ok(1));
+        let change = syntax::parse("extend type User { name: String }").unwrap();
+        assert_eq!(catalog.apply(&change, &mut store), Ok(2));
+        assert_eq!(catalog.rollback(1, &mut store), Ok(3));
+
+        let catalog = Catalog::open(Document::default(), &dir).unwrap();
+        assert_eq!(catalog.version(), 3);
+        assert_eq!(catalog.revisions[2], Revision::Rollback(1));
+        let served = catalog.schema();
+        let schema = Schema::new(&served);
+        assert!(schema.has_type("User"));
+        assert!(schema.field("User", "name").is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
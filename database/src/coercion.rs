@@ -0,0 +1,492 @@
+//! Coerces the input values of operations to the types the schema gives them, following the
+//! Input Coercion sections of the GraphQL Spec.
+//!
+//! Values are either literals of the query document, whose variables have already been coerced,
+//! or JSON values, as the values of variables are sent. Both are coerced to JSON. The values of
+//! scalars are parsed by their [coercion].
+//!
+//! [coercion]: ../scalar/index.html
+
+use crate::scalar::Scalars;
+use serde_json::{json, Map, Value};
+use syntax::nodes::*;
+use syntax::schema::Schema;
+
+/// An input value to coerce.
+#[derive(Clone, Copy)]
+enum Input<'v> {
+    Literal(&'v ValueNode),
+    Json(&'v Value),
+}
+
+pub(crate) struct Coercion<'a, 'v> {
+    schema: &'a Schema<'a>,
+    scalars: &'a Scalars,
+    variables: &'v Map<String, Value>,
+}
+
+impl<'a, 'v> Coercion<'a, 'v> {
+    /// Coerces the literals of a document whose variables have the given coerced values.
+    pub fn new(
+        schema: &'a Schema<'a>,
+        scalars: &'a Scalars,
+        variables: &'v Map<String, Value>,
+    ) -> Self {
+        Coercion {
+            schema,
+            scalars,
+            variables,
+        }
+    }
+
+    /// Coerces the values sent for the variables of an operation, applying their defaults.
+    pub fn variables(
+        schema: &'a Schema<'a>,
+        scalars: &'a Scalars,
+        definitions: &Option<Variables>,
+        provided: &Map<String, Value>,
+    ) -> Result<Map<String, Value>, String> {
+        let none = Map::new();
+        let coercion = Coercion::new(schema, scalars, &none);
+        let mut variables = Map::new();
+        for definition in definitions.iter().flatten() {
+            let name = definition.variable.name.value.as_str();
+            let input = match (provided.get(name), &definition.default_value) {
+                (Some(value), _) => Input::Json(value),
+                (None, Some(default)) => Input::Literal(default),
+                (None, None) => match definition.variable_type {
+                    TypeNode::NonNull(_) => {
+                        return Err(format!(
+                            "Variable `${}` of required type `{}` was not provided",
+                            name,
+                            type_name(&definition.variable_type)
+                        ))
+                    }
+                    _ => continue,
+                },
+            };
+            let value = coercion
+                .coerce(input, &definition.variable_type)
+                .map_err(|e| format!("Variable `${}` got an invalid value: {}", name, e))?;
+            variables.insert(name.into(), value);
+        }
+        Ok(variables)
+    }
+
+    /// Coerces a literal of the document.
+    pub fn literal(&self, value: &ValueNode, input_type: &TypeNode) -> Result<Value, String> {
+        self.coerce(Input::Literal(value), input_type)
+    }
+
+    /// Coerces the arguments of a field, applying their defaults. Arguments set to variables
+    /// that were not provided are left out, unless they have a default.
+    pub fn arguments(
+        &self,
+        field: &FieldNode,
+        definition: &FieldDefinitionNode,
+    ) -> Result<Map<String, Value>, String> {
+        let values: Vec<(&str, Input)> = field
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| {
+                (
+                    argument.name.value.as_str(),
+                    Input::Literal(&argument.value),
+                )
+            })
+            .collect();
+        self.fields(
+            &values,
+            definition.arguments.as_deref().unwrap_or_default(),
+            "Argument",
+            &format!("field `{}`", definition.name.as_str()),
+        )
+    }
+
+    /// Coerces the arguments of a directive, applying their defaults.
+    pub fn directive_arguments(
+        &self,
+        directive: &DirectiveNode,
+        definition: &DirectiveDefinitionNode,
+    ) -> Result<Map<String, Value>, String> {
+        let values: Vec<(&str, Input)> = directive
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| {
+                (
+                    argument.name.value.as_str(),
+                    Input::Literal(&argument.value),
+                )
+            })
+            .collect();
+        self.fields(
+            &values,
+            definition.arguments.as_deref().unwrap_or_default(),
+            "Argument",
+            &format!("directive `@{}`", definition.name.as_str()),
+        )
+    }
+
+    fn coerce(&self, input: Input, input_type: &TypeNode) -> Result<Value, String> {
+        if let Input::Literal(ValueNode::Variable(variable)) = input {
+            let value = self
+                .variables
+                .get(variable.name.value.as_str())
+                .cloned()
+                .unwrap_or(Value::Null);
+            if let (TypeNode::NonNull(_), Value::Null) = (input_type, &value) {
+                return Err(format!(
+                    "Expected a value of type `{}`, found null",
+                    type_name(input_type)
+                ));
+            }
+            return Ok(value);
+        }
+        let null = matches!(
+            input,
+            Input::Literal(ValueNode::Null) | Input::Json(Value::Null)
+        );
+        match input_type {
+            TypeNode::NonNull(inner) => {
+                if null {
+                    Err(format!(
+                        "Expected a value of type `{}`, found null",
+                        type_name(input_type)
+                    ))
+                } else {
+                    self.coerce(input, inner)
+                }
+            }
+            _ if null => Ok(Value::Null),
+            TypeNode::List(list) => match input {
+                Input::Literal(ValueNode::List(items)) => items
+                    .values
+                    .iter()
+                    .map(|item| self.coerce(Input::Literal(item), &list.list_type))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array),
+                Input::Json(Value::Array(items)) => items
+                    .iter()
+                    .map(|item| self.coerce(Input::Json(item), &list.list_type))
+                    .collect::<Result<_, _>>()
+                    .map(Value::Array),
+                _ => Ok(Value::Array(vec![self.coerce(input, &list.list_type)?])),
+            },
+            TypeNode::Named(named) => self.named(input, named.name.value.as_str()),
+        }
+    }
+
+    fn named(&self, input: Input, type_name: &str) -> Result<Value, String> {
+        let invalid = || {
+            format!(
+                "Expected a value of type `{}`, found {}",
+                type_name,
+                found(input)
+            )
+        };
+        match self.schema.get_type(type_name) {
+            Some(TypeDefinitionNode::Input(definition)) => {
+                let values: Vec<(&str, Input)> = match input {
+                    Input::Literal(ValueNode::Object(object)) => object
+                        .fields
+                        .iter()
+                        .map(|field| (field.name.value.as_str(), Input::Literal(&field.value)))
+                        .collect(),
+                    Input::Json(Value::Object(object)) => object
+                        .iter()
+                        .map(|(name, value)| (name.as_str(), Input::Json(value)))
+                        .collect(),
+                    _ => return Err(invalid()),
+                };
+                let fields = self.fields(
+                    &values,
+                    &definition.fields,
+                    "Field",
+                    &format!("input type `{}`", type_name),
+                )?;
+                let given: Vec<&Value> = fields.values().collect();
+                if self.schema.is_one_of(type_name)
+                    && !matches!(given[..], [value] if !value.is_null())
+                {
+                    return Err(format!(
+                        "Exactly one field of the OneOf input type `{}` must be given, and not be null",
+                        type_name
+                    ));
+                }
+                Ok(Value::Object(fields))
+            }
+            Some(TypeDefinitionNode::Enum(definition)) => {
+                let value = match input {
+                    Input::Literal(ValueNode::Enum(value)) => value.value.as_str(),
+                    Input::Json(Value::String(value)) => value.as_str(),
+                    _ => return Err(invalid()),
+                };
+                if definition.values.iter().any(|v| v.name.as_str() == value) {
+                    Ok(json!(value))
+                } else {
+                    Err(invalid())
+                }
+            }
+            Some(TypeDefinitionNode::Scalar(_)) => {
+                self.scalar(type_name, input).ok_or_else(invalid)
+            }
+            _ => Err(format!("`{}` is not an input type", type_name)),
+        }
+    }
+
+    /// Coerces the fields of an input object, or the arguments of a field.
+    fn fields(
+        &self,
+        values: &[(&str, Input)],
+        definitions: &[InputValueDefinitionNode],
+        noun: &str,
+        owner: &str,
+    ) -> Result<Map<String, Value>, String> {
+        if let Some((name, _)) = values
+            .iter()
+            .find(|(name, _)| !definitions.iter().any(|d| d.name.as_str() == *name))
+        {
+            return Err(format!("{} `{}` is not defined on {}", noun, name, owner));
+        }
+        let mut coerced = Map::new();
+        for definition in definitions {
+            let name = definition.name.as_str();
+            let value = values
+                .iter()
+                .find(|(provided, _)| *provided == name)
+                .map(|(_, value)| *value)
+                .filter(|value| match value {
+                    Input::Literal(ValueNode::Variable(variable)) => {
+                        self.variables.contains_key(variable.name.value.as_str())
+                    }
+                    _ => true,
+                });
+            let input = match (value, &definition.default_value) {
+                (Some(value), _) => value,
+                (None, Some(default)) => Input::Literal(default),
+                (None, None) => match definition.input_type {
+                    TypeNode::NonNull(_) => {
+                        return Err(format!("{} `{}` of {} is required", noun, name, owner))
+                    }
+                    _ => continue,
+                },
+            };
+            let value = self
+                .coerce(input, &definition.input_type)
+                .map_err(|e| format!("{} `{}` of {}: {}", noun, name, owner, e))?;
+            coerced.insert(name.into(), value);
+        }
+        Ok(coerced)
+    }
+
+    /// Coerces the value of a scalar. Scalars without a coercion are kept as they are.
+    fn scalar(&self, type_name: &str, input: Input) -> Option<Value> {
+        let coercion = match self.scalars.get(type_name) {
+            Some(coercion) => coercion,
+            None => {
+                return match input {
+                    Input::Literal(literal) => literal_json(literal),
+                    Input::Json(value) => Some(value.clone()),
+                }
+            }
+        };
+        match input {
+            Input::Literal(ValueNode::Enum(_))
+            | Input::Literal(ValueNode::List(_))
+            | Input::Literal(ValueNode::Object(_)) => None,
+            Input::Literal(literal) => coercion.parse(&literal_json(literal)?),
+            Input::Json(value) => coercion.parse(value),
+        }
+    }
+}
+
+/// The JSON value of a literal without variables.
+fn literal_json(literal: &ValueNode) -> Option<Value> {
+    Some(match literal {
+        ValueNode::Variable(_) => return None,
+        ValueNode::Int(int) => int.to_json(),
+        ValueNode::Float(float) => json!(float.value),
+        ValueNode::Str(string) => json!(string.value),
+        ValueNode::Bool(boolean) => json!(boolean.value),
+        ValueNode::Null => Value::Null,
+        ValueNode::Enum(value) => json!(value.value),
+        ValueNode::List(list) => Value::Array(
+            list.values
+                .iter()
+                .map(literal_json)
+                .collect::<Option<_>>()?,
+        ),
+        ValueNode::Object(object) => Value::Object(
+            object
+                .fields
+                .iter()
+                .map(|field| {
+                    Some((
+                        field.name.value.as_str().into(),
+                        literal_json(&field.value)?,
+                    ))
+                })
+                .collect::<Option<_>>()?,
+        ),
+    })
+}
+
+fn found(input: Input) -> String {
+    match input {
+        Input::Literal(ValueNode::Enum(value)) => value.value.clone(),
+        Input::Literal(literal) => literal_json(literal)
+            .map(|value| value.to_string())
+            .unwrap_or_else(|| "a variable".into()),
+        Input::Json(value) => value.to_string(),
+    }
+}
+
+/// Prints a type as it is written in a document.
+pub(crate) fn type_name(type_node: &TypeNode) -> String {
+    match type_node {
+        TypeNode::Named(named) => named.name.value.as_str().into(),
+        TypeNode::List(list) => format!("[{}]", type_name(&list.list_type)),
+        TypeNode::NonNull(inner) => format!("{}!", type_name(inner)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntax::document::Document;
+
+    fn schema() -> Document {
+        let mut document = Document::default();
+        document.definitions.extend(
+            syntax::parse(
+                r#"
+                enum Color { RED GREEN }
+                scalar Json
+                input Paint { color: Color! shade: Int = 1 tags: [String!] }
+                input Pick @oneOf { id: ID name: String }
+                type Query { paint(with: Paint!, count: TinyUint): Int }
+                "#,
+            )
+            .unwrap()
+            .definitions,
+        );
+        document
+    }
+
+    fn argument_type(source: &str) -> TypeNode {
+        let document = syntax::parse(&format!("type T {{ f(a: {}): Int }}", source)).unwrap();
+        let schema = Schema::new(&document);
+        let field = schema.field("T", "f").unwrap();
+        field.arguments.as_ref().unwrap()[0].input_type.clone()
+    }
+
+    fn literal(source: &str) -> ValueNode {
+        let document = syntax::parse(&format!("{{ f(a: {}) }}", source)).unwrap();
+        let operations = document.operations();
+        match &operations[0].operation.definition().selections[0] {
+            Selection::Field(field) => field.arguments.as_ref().unwrap()[0].value.clone(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn it_coerces_literals() {
+        let document = schema();
+        let schema = Schema::new(&document);
+        let scalars = Scalars::default();
+        let variables = Map::new();
+        let coercion = Coercion::new(&schema, &scalars, &variables);
+        let coerce = |value: &str, input_type: &str| {
+            coercion.literal(&literal(value), &argument_type(input_type))
+        };
+        assert_eq!(coerce("1", "ID"), Ok(json!("1")));
+        assert_eq!(coerce("1", "Float"), Ok(json!(1.0)));
+        assert_eq!(coerce("\"a\"", "[String!]"), Ok(json!(["a"])));
+        assert_eq!(
+            coerce("{ color: RED }", "Paint"),
+            Ok(json!({ "color": "RED", "shade": 1 }))
+        );
+        assert!(coerce("3000000000", "Int").is_err());
+        assert!(coerce("256", "TinyUint").is_err());
+        assert_eq!(
+            coerce("18446744073709551615", "LongUint"),
+            Ok(json!(18446744073709551615u64))
+        );
+        assert_eq!(
+            coerce("9223372036854775808", "BigInt"),
+            Ok(json!("9223372036854775808"))
+        );
+        assert_eq!(
+            coerce("340282366920938463463374607431768211455", "BigUint"),
+            Ok(json!("340282366920938463463374607431768211455"))
+        );
+        assert!(coerce("18446744073709551616", "LongUint").is_err());
+        assert!(coerce("null", "Int!").is_err());
+        assert!(coerce("\"RED\"", "Color").is_err());
+        assert!(coerce("BLUE", "Color").is_err());
+        assert_eq!(coerce("\"2020-01-31\"", "Date"), Ok(json!("2020-01-31")));
+        assert_eq!(
+            coerce("\"31/01/2020\"", "Date"),
+            Err("Expected a value of type `Date`, found \"31/01/2020\"".into())
+        );
+        assert_eq!(coerce("{ a: [B] }", "Json"), Ok(json!({ "a": ["B"] })));
+        assert_eq!(
+            coerce("{ shade: 2 }", "Paint"),
+            Err("Field `color` of input type `Paint` is required".into())
+        );
+        assert_eq!(
+            coerce("{ color: RED, gloss: true }", "Paint"),
+            Err("Field `gloss` is not defined on input type `Paint`".into())
+        );
+        assert_eq!(coerce("{ id: 1 }", "Pick"), Ok(json!({ "id": "1" })));
+        for invalid in ["{}", "{ id: 1, name: \"a\" }", "{ name: null }"] {
+            assert_eq!(
+                coerce(invalid, "Pick"),
+                Err(
+                    "Exactly one field of the OneOf input type `Pick` must be given, and not be null"
+                        .into()
+                ),
+            );
+        }
+    }
+
+    #[test]
+    fn it_coerces_variables() {
+        let document = schema();
+        let schema = Schema::new(&document);
+        let scalars = Scalars::default();
+        let query =
+            syntax::parse("query Q($p: Paint!, $n: TinyUint = 2, $c: Color) { a }").unwrap();
+        let operations = query.operations();
+        let definitions = &operations[0].operation.definition().variables;
+        let provided = json!({ "p": { "color": "GREEN", "tags": "x" } });
+        assert_eq!(
+            Coercion::variables(
+                &schema,
+                &scalars,
+                definitions,
+                provided.as_object().unwrap()
+            ),
+            Ok(
+                json!({ "p": { "color": "GREEN", "shade": 1, "tags": ["x"] }, "n": 2 })
+                    .as_object()
+                    .unwrap()
+                    .clone()
+            )
+        );
+        assert_eq!(
+            Coercion::variables(&schema, &scalars, definitions, &Map::new()),
+            Err("Variable `$p` of required type `Paint!` was not provided".into())
+        );
+        let provided = json!({ "p": { "color": "GREEN" }, "n": -1 });
+        assert!(Coercion::variables(
+            &schema,
+            &scalars,
+            definitions,
+            provided.as_object().unwrap()
+        )
+        .is_err());
+    }
+}
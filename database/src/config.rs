@@ -1,32 +1,644 @@
-use clap::{load_yaml, App};
+//! The settings of the database server, from its command line flags and an optional config file.
+//!
+//! Embedders and tests build a [`Config`] with [`Config::builder`] instead, starting from the
+//! defaults of the flags, without reading the arguments of the process.
+//!
+//! The config file, given with `--config`, is written in YAML, or in TOML when its name ends with
+//! `.toml`. Its keys are the names of the flags, with underscores, and a flag given on the command
+//! line overrides the key of the file:
+//!
+//! ```yaml
+//! threads: 4
+//! protocols: [http, ws]
+//! schema: "schema/*.graphql"
+//! data_dir: /var/lib/gql
+//! max_connections: 512
+//! execute_timeout: 10
+//! ```
+//!
+//! Only the flat tables of keys set to strings, integers, booleans and lists of them are read
+//! from TOML files, which is all the settings need.
+//!
+//! [`Config`]: struct.Config.html
+//! [`Config::builder`]: struct.Config.html#method.builder
 
+use crate::logging::LogFormat;
+use crate::wal::Fsync;
+use clap::{load_yaml, App, ArgMatches, ErrorKind};
+use net::supervisor::Limits;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The keys a config file may set, the names of the flags but `config`.
+const KEYS: &[&str] = &[
+    "log_config",
+    "log_format",
+    "threads",
+    "query_cache",
+    "result_cache",
+    "queue_capacity",
+    "schema",
+    "schema_poll",
+    "data",
+    "data_dir",
+    "fsync",
+    "snapshot_interval",
+    "persisted_operations",
+    "auth_tokens",
+    "max_connections",
+    "idle_timeout",
+    "read_timeout",
+    "execute_timeout",
+    "grace_period",
+    "protocols",
+    "graphs",
+    "replication_port",
+    "replication_host",
+    "follow",
+    "follow_token",
+];
+
+const PROTOCOLS: &[&str] = &["tcp", "http", "udp", "ws", "rpc"];
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Config {
     pub num_threads: usize,
     pub logging_config: String,
+    pub log_format: LogFormat,
     pub protocols: Vec<String>,
+    pub query_cache_size: usize,
+    pub result_cache_size: usize,
+    pub queue_capacity: usize,
+    pub schema: Option<String>,
+    pub schema_poll: Duration,
+    pub data: Option<String>,
+    pub data_dir: Option<String>,
+    pub fsync: Fsync,
+    pub snapshot_interval: Duration,
+    pub persisted_operations: Option<String>,
+    pub auth_tokens: Option<String>,
+    pub limits: Limits,
+    pub grace_period: Duration,
+    /// The graphs held besides the default one
+    pub graphs: Vec<String>,
+    /// The port followers connect to, to be sent the changes of the default graph
+    pub replication_port: Option<u16>,
+    /// The host the replication port is bound to, the loopback interface by default
+    pub replication_host: String,
+    /// The address of the primary the database follows
+    pub follow: Option<String>,
+    /// The token the database authenticates to the primary it follows with
+    pub follow_token: Option<String>,
 }
 
+/// The settings of a database held in memory, without listeners, which the flags start from.
 impl Default for Config {
     fn default() -> Self {
+        Self {
+            num_threads: 2,
+            logging_config: String::from("database/config/logging.yaml"),
+            log_format: LogFormat::Text,
+            protocols: Vec::new(),
+            query_cache_size: 1024,
+            result_cache_size: 1024,
+            queue_capacity: 64,
+            schema: None,
+            schema_poll: Duration::from_secs(2),
+            data: None,
+            data_dir: None,
+            fsync: Fsync::Always,
+            snapshot_interval: Duration::from_secs(300),
+            persisted_operations: None,
+            auth_tokens: None,
+            limits: Limits::default(),
+            grace_period: Duration::from_secs(30),
+            graphs: Vec::new(),
+            replication_port: None,
+            replication_host: String::from("127.0.0.1"),
+            follow: None,
+            follow_token: None,
+        }
+    }
+}
+
+impl Config {
+    /// A builder of a config, starting from the [defaults].
+    ///
+    /// [defaults]: #impl-Default
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// The settings of the command line flags of the process and of the config file they name,
+    /// exiting with the usage of the flags when they are bad.
+    pub fn from_args() -> Config {
         let clap_yaml = load_yaml!("../config/cli.yaml");
         let matches = App::from_yaml(clap_yaml).get_matches();
-        let num_threads = matches
-            .value_of("threads")
-            .unwrap_or("2")
-            .parse::<usize>()
-            .expect("Bad Value: Thread command line option must be an integer between 1 and 16");
-
-        let logging_config = matches
-            .value_of("log_config")
-            .unwrap_or("database/config/logging.yaml");
-        let protocols = matches
-            .value_of("protocols")
-            .expect("No protocols where provided");
+        Config::from_matches(&matches)
+            .unwrap_or_else(|e| clap::Error::with_description(&e, ErrorKind::InvalidValue).exit())
+    }
 
-        Self {
+    fn from_matches(matches: &ArgMatches) -> Result<Config, String> {
+        let file = match matches.value_of("config") {
+            Some(path) => Some(ConfigFile::read(path)?),
+            None => None,
+        };
+        let settings = Settings { matches, file };
+        let defaults = Config::default();
+
+        let num_threads = settings
+            .parse::<usize>("threads", "an integer between 1 and 16")?
+            .unwrap_or(defaults.num_threads);
+        let logging_config = settings
+            .value("log_config")
+            .map(String::from)
+            .unwrap_or(defaults.logging_config);
+        let log_format = settings
+            .parse::<LogFormat>("log_format", "text or json")?
+            .unwrap_or(defaults.log_format);
+        let query_cache_size = settings
+            .parse::<usize>("query_cache", "a positive integer")?
+            .unwrap_or(defaults.query_cache_size);
+        let result_cache_size = settings
+            .parse::<usize>("result_cache", "a positive integer")?
+            .unwrap_or(defaults.result_cache_size);
+        let queue_capacity = settings
+            .parse::<usize>("queue_capacity", "a positive integer")?
+            .unwrap_or(defaults.queue_capacity);
+        if queue_capacity == 0 {
+            return Err(settings.bad_value("queue_capacity", "a positive integer"));
+        }
+
+        let schema = settings.value("schema").map(String::from);
+        let schema_poll = settings
+            .seconds("schema_poll")?
+            .unwrap_or(defaults.schema_poll);
+        let data = settings.value("data").map(String::from);
+        let data_dir = settings.value("data_dir").map(String::from);
+        let fsync = settings
+            .parse::<Fsync>("fsync", "always, never or a number of seconds")?
+            .unwrap_or(defaults.fsync);
+        let snapshot_interval = settings
+            .seconds("snapshot_interval")?
+            .unwrap_or(defaults.snapshot_interval);
+        let persisted_operations = settings.value("persisted_operations").map(String::from);
+        let auth_tokens = settings.value("auth_tokens").map(String::from);
+
+        let limits = Limits {
+            max_connections: settings
+                .parse::<usize>("max_connections", "a positive integer")?
+                .unwrap_or(defaults.limits.max_connections),
+            idle_timeout: settings
+                .seconds("idle_timeout")?
+                .unwrap_or(defaults.limits.idle_timeout),
+            read_timeout: settings
+                .seconds("read_timeout")?
+                .unwrap_or(defaults.limits.read_timeout),
+            execute_timeout: settings
+                .seconds("execute_timeout")?
+                .unwrap_or(defaults.limits.execute_timeout),
+        };
+
+        let grace_period = settings
+            .seconds("grace_period")?
+            .unwrap_or(defaults.grace_period);
+
+        let protocols: Vec<String> = settings
+            .value("protocols")
+            .ok_or_else(|| String::from("No protocols where provided"))?
+            .split(',')
+            .map(String::from)
+            .collect();
+        if let Some(unknown) = protocols
+            .iter()
+            .find(|protocol| !PROTOCOLS.contains(&protocol.as_str()))
+        {
+            return Err(settings.bad_value(
+                "protocols",
+                &format!("a list of {}, not {}", PROTOCOLS.join(", "), unknown),
+            ));
+        }
+
+        let graphs: Vec<String> = settings
+            .value("graphs")
+            .into_iter()
+            .flat_map(|graphs| graphs.split(','))
+            .map(String::from)
+            .collect();
+        let named = |graph: &String| {
+            !graph.is_empty()
+                && graph
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if let Some(bad) = graphs.iter().find(|graph| !named(graph)) {
+            return Err(settings.bad_value(
+                "graphs",
+                &format!(
+                    "a list of names made of letters, digits, - and _, not `{}`",
+                    bad
+                ),
+            ));
+        }
+
+        let replication_port = settings.parse::<u16>("replication_port", "a port number")?;
+        let replication_host = settings
+            .value("replication_host")
+            .map_or(defaults.replication_host, String::from);
+        let follow = settings.value("follow").map(String::from);
+        let follow_token = settings.value("follow_token").map(String::from);
+
+        Ok(Self {
             num_threads,
-            logging_config: String::from(logging_config),
-            protocols: protocols.split(",").map(|s| s.into()).collect(),
+            logging_config,
+            log_format,
+            protocols,
+            query_cache_size,
+            result_cache_size,
+            queue_capacity,
+            schema,
+            schema_poll,
+            data,
+            data_dir,
+            fsync,
+            snapshot_interval,
+            persisted_operations,
+            auth_tokens,
+            limits,
+            grace_period,
+            graphs,
+            replication_port,
+            replication_host,
+            follow,
+            follow_token,
+        })
+    }
+}
+
+/// Builds the settings of a database, starting from the [defaults].
+///
+/// [defaults]: struct.Config.html#impl-Default
+#[derive(Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn new() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+
+    /// The minimum number of threads of the runtime.
+    pub fn with_threads(&mut self, threads: usize) -> &mut Self {
+        self.config.num_threads = threads;
+        self
+    }
+
+    /// The protocols listened to, none by default.
+    pub fn with_protocols(&mut self, protocols: &[&str]) -> &mut Self {
+        self.config.protocols = protocols
+            .iter()
+            .map(|protocol| String::from(*protocol))
+            .collect();
+        self
+    }
+
+    /// The GraphQL document, or glob pattern of documents, of the types the database holds.
+    pub fn with_schema(&mut self, pattern: &str) -> &mut Self {
+        self.config.schema = Some(pattern.into());
+        self
+    }
+
+    /// A JSON file of the records the database starts with.
+    pub fn with_data(&mut self, path: &str) -> &mut Self {
+        self.config.data = Some(path.into());
+        self
+    }
+
+    /// The directory the records are kept in across restarts, with the fsync policy of its log.
+    pub fn with_data_dir(&mut self, dir: &str, fsync: Fsync) -> &mut Self {
+        self.config.data_dir = Some(dir.into());
+        self.config.fsync = fsync;
+        self
+    }
+
+    /// The number of parsed queries and of query responses kept, 0 to keep none.
+    pub fn with_caches(&mut self, queries: usize, results: usize) -> &mut Self {
+        self.config.query_cache_size = queries;
+        self.config.result_cache_size = results;
+        self
+    }
+
+    /// A JSON manifest of the only operations accepted.
+    pub fn with_persisted_operations(&mut self, path: &str) -> &mut Self {
+        self.config.persisted_operations = Some(path.into());
+        self
+    }
+
+    /// A JSON map of the tokens clients authenticate with.
+    pub fn with_auth_tokens(&mut self, path: &str) -> &mut Self {
+        self.config.auth_tokens = Some(path.into());
+        self
+    }
+
+    /// The graphs held besides the default one.
+    pub fn with_graphs(&mut self, graphs: &[&str]) -> &mut Self {
+        self.config.graphs = graphs.iter().map(|graph| String::from(*graph)).collect();
+        self
+    }
+
+    /// Follows the primary at an address, authenticating with a token, only answering reads
+    /// until promoted.
+    pub fn with_primary(&mut self, address: &str, token: Option<&str>) -> &mut Self {
+        self.config.follow = Some(address.into());
+        self.config.follow_token = token.map(String::from);
+        self
+    }
+
+    /// Sends the changes to the followers connecting to a port of a host.
+    pub fn with_replication(&mut self, host: &str, port: u16) -> &mut Self {
+        self.config.replication_host = host.into();
+        self.config.replication_port = Some(port);
+        self
+    }
+
+    /// The limits put on the connections of each protocol.
+    pub fn with_limits(&mut self, limits: Limits) -> &mut Self {
+        self.config.limits = limits;
+        self
+    }
+
+    pub fn build(&self) -> Config {
+        self.config.clone()
+    }
+}
+
+/// The settings of the command line flags, over the ones of the config file.
+struct Settings<'a> {
+    matches: &'a ArgMatches<'a>,
+    file: Option<ConfigFile>,
+}
+
+impl Settings<'_> {
+    fn value(&self, name: &str) -> Option<&str> {
+        self.matches.value_of(name).or_else(|| {
+            self.file
+                .as_ref()
+                .and_then(|file| file.values.get(name))
+                .map(String::as_str)
+        })
+    }
+
+    fn parse<T: FromStr>(&self, name: &str, expected: &str) -> Result<Option<T>, String> {
+        self.value(name)
+            .map(|value| {
+                value
+                    .parse::<T>()
+                    .map_err(|_| self.bad_value(name, expected))
+            })
+            .transpose()
+    }
+
+    fn seconds(&self, name: &str) -> Result<Option<Duration>, String> {
+        Ok(self
+            .parse::<u64>(name, "a number of seconds")?
+            .map(Duration::from_secs))
+    }
+
+    /// The error of a setting, naming the flag or the key of the config file it came from.
+    fn bad_value(&self, name: &str, expected: &str) -> String {
+        match &self.file {
+            Some(file) if self.matches.value_of(name).is_none() => format!(
+                "Bad Value: `{}` in {} must be {}",
+                name, file.path, expected
+            ),
+            _ => format!("Bad Value: `{}` must be {}", name, expected),
         }
     }
 }
+
+/// The settings of a config file, as the flags they stand for would be written.
+#[derive(Debug)]
+struct ConfigFile {
+    path: String,
+    values: HashMap<String, String>,
+}
+
+impl ConfigFile {
+    fn read(path: &str) -> Result<ConfigFile, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read the config file {}: {}", path, e))?;
+        let values = if path.ends_with(".toml") {
+            toml_values(&source)
+        } else {
+            yaml_values(&source)
+        }
+        .map_err(|e| format!("Bad config file {}: {}", path, e))?;
+        Ok(ConfigFile {
+            path: path.into(),
+            values,
+        })
+    }
+}
+
+fn yaml_values(source: &str) -> Result<HashMap<String, String>, String> {
+    let document: Value = serde_yaml::from_str(source).map_err(|e| e.to_string())?;
+    let mapping = match document {
+        Value::Mapping(mapping) => mapping,
+        Value::Null => return Ok(HashMap::new()),
+        _ => return Err("The settings must be a map of keys to values".into()),
+    };
+    let mut values = HashMap::new();
+    for (key, value) in mapping {
+        let key = match key {
+            Value::String(key) => key,
+            _ => return Err("The keys must be strings".into()),
+        };
+        let value = match value {
+            Value::Sequence(items) => items
+                .into_iter()
+                .map(|item| yaml_scalar(&key, item))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(","),
+            value => yaml_scalar(&key, value)?,
+        };
+        insert(&mut values, key, value)?;
+    }
+    Ok(values)
+}
+
+fn yaml_scalar(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Number(value) => Ok(value.to_string()),
+        Value::Bool(value) => Ok(value.to_string()),
+        _ => Err(format!(
+            "`{}` must be a string, a number, a boolean or a list of them",
+            key
+        )),
+    }
+}
+
+fn toml_values(source: &str) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {} is not a `key = value` pair", number + 1))?;
+        let key = key.trim().to_owned();
+        let value = value.trim();
+        let value = match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(items) => items
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml_scalar(&key, item))
+                .collect::<Result<Vec<_>, _>>()?
+                .join(","),
+            None => toml_scalar(&key, value)?,
+        };
+        insert(&mut values, key, value)?;
+    }
+    Ok(values)
+}
+
+fn toml_scalar(key: &str, value: &str) -> Result<String, String> {
+    let unquoted = value
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .or_else(|| {
+            value
+                .strip_prefix('\'')
+                .and_then(|value| value.strip_suffix('\''))
+        });
+    match unquoted {
+        Some(value) => Ok(value.to_owned()),
+        None if value == "true" || value == "false" || value.parse::<i64>().is_ok() => {
+            Ok(value.to_owned())
+        }
+        None => Err(format!(
+            "`{}` must be a string, an integer, a boolean or a list of them",
+            key
+        )),
+    }
+}
+
+fn insert(values: &mut HashMap<String, String>, key: String, value: String) -> Result<(), String> {
+    if !KEYS.contains(&key.as_str()) {
+        return Err(format!("Unknown key `{}`", key));
+    }
+    if values.insert(key.clone(), value).is_some() {
+        return Err(format!("`{}` is set twice", key));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn load(args: &[&str], file: Option<(&str, &str)>) -> Result<Config, String> {
+        let mut args = args.to_vec();
+        let path = file.map(|(name, source)| {
+            let path = env::temp_dir().join(format!("gql-config-{}-{}", std::process::id(), name));
+            fs::write(&path, source).unwrap();
+            path.to_string_lossy().into_owned()
+        });
+        if let Some(path) = &path {
+            args.extend(["--config", path.as_str()]);
+        }
+        let clap_yaml = load_yaml!("../config/cli.yaml");
+        let matches = App::from_yaml(clap_yaml).get_matches_from(args);
+        let config = Config::from_matches(&matches);
+        if let Some(path) = path {
+            fs::remove_file(path).unwrap();
+        }
+        config
+    }
+
+    #[test]
+    fn it_reads_config_files_under_the_flags() {
+        let yaml =
+            "threads: 4\nprotocols: [http, ws]\ndata_dir: /var/lib/gql\nmax_connections: 512\n";
+        let config = load(&["gql", "--threads", "8"], Some(("settings.yaml", yaml))).unwrap();
+        assert_eq!(config.num_threads, 8);
+        assert_eq!(config.protocols, ["http", "ws"]);
+        assert_eq!(config.data_dir.as_deref(), Some("/var/lib/gql"));
+        assert_eq!(config.limits.max_connections, 512);
+        assert_eq!(config.queue_capacity, 64);
+        assert_eq!(config.replication_host, "127.0.0.1");
+
+        let toml = "# The server\nthreads = 3\nprotocols = [\"tcp\"]\nfsync = 'never'\nexecute_timeout = 10\n";
+        let config = load(&["gql"], Some(("settings.toml", toml))).unwrap();
+        assert_eq!(config.num_threads, 3);
+        assert_eq!(config.protocols, ["tcp"]);
+        assert_eq!(config.fsync, Fsync::Never);
+        assert_eq!(config.limits.execute_timeout, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn it_builds_configs_from_the_defaults() {
+        let config = Config::builder()
+            .with_threads(4)
+            .with_protocols(&["http"])
+            .with_caches(0, 16)
+            .build();
+        assert_eq!(config.num_threads, 4);
+        assert_eq!(config.protocols, ["http"]);
+        assert_eq!((config.query_cache_size, config.result_cache_size), (0, 16));
+        assert_eq!(config.data_dir, None);
+        assert_eq!(
+            load(
+                &[
+                    "gql",
+                    "-p",
+                    "http",
+                    "--threads",
+                    "4",
+                    "--query-cache",
+                    "0",
+                    "--result-cache",
+                    "16"
+                ],
+                None
+            ),
+            Ok(config)
+        );
+        crate::database::Database::new(&Config::default());
+    }
+
+    #[test]
+    fn it_names_the_bad_keys() {
+        let error = |args: &[&str], file| match load(args, file) {
+            Ok(_) => panic!("The config is valid"),
+            Err(e) => e,
+        };
+        assert!(error(
+            &["gql"],
+            Some(("unknown.yaml", "protocols: http\nthread: 4\n"))
+        )
+        .ends_with("Unknown key `thread`"));
+        assert!(error(
+            &["gql"],
+            Some(("threads.yaml", "protocols: http\nthreads: many\n"))
+        )
+        .contains("`threads` in "));
+        assert_eq!(
+            error(&["gql", "-p", "http", "--threads", "many"], None),
+            "Bad Value: `threads` must be an integer between 1 and 16"
+        );
+        assert!(error(
+            &["gql"],
+            Some(("protocols.toml", "protocols = [\"smtp\"]\n"))
+        )
+        .ends_with("must be a list of tcp, http, udp, ws, rpc, not smtp"));
+    }
+}
@@ -1,32 +1,629 @@
+use crate::authorization;
+use crate::catalog::{self, Catalog, Command};
 use crate::config::Config;
-use log::info;
-use syntax;
+use crate::executor::{self, request_error};
+use crate::explain;
+use crate::index;
+use crate::listener;
+use crate::replication::{self, Following, REPLICATION_BUFFER};
+use crate::resolver::{Resolver, Resolvers};
+use crate::result_cache::ResultCache;
+use crate::scalar::{ScalarCoercion, Scalars};
+use crate::schema_files::SchemaFiles;
+use crate::store::Store;
+use crate::subscription::{Event, EVENT_BUFFER};
+use crate::wal::{Change, Fsync};
+use net::auth::SharedAuthenticator;
+use net::db::{CacheMetrics, DbCommand, Responder};
+use net::request::Request;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use syntax::cache::DocumentCache;
 use syntax::document::Document;
-use tokio::sync::{mpsc::Receiver, oneshot};
+use syntax::error::ParseResult;
+use syntax::nodes::Operation;
+use syntax::persisted::PersistedOperations;
+use syntax::schema::Schema;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc::Receiver;
+use tokio::sync::watch;
+use tokio::task::JoinSet;
+use tracing::{debug, error, info, info_span, warn, Instrument};
 
-pub(crate) struct Database {
-    schema: Document,
-    // graph
+pub struct Database {
+    /// The schema, and the changes made to it. Taken before the store when both are locked.
+    catalog: Arc<RwLock<Catalog>>,
+    store: Arc<RwLock<Store>>,
+    /// The fields answered by Rust code rather than by the store
+    resolvers: Arc<Resolvers>,
+    /// How the values of scalars are read and written
+    scalars: Arc<Scalars>,
+    /// The changes made to the store, for the subscriptions
+    events: broadcast::Sender<Arc<Event>>,
+    queries: Arc<DocumentCache>,
+    /// The responses to queries, until the records they read change
+    results: Arc<ResultCache>,
+    allowed: Option<Arc<PersistedOperations>>,
+    /// When the store is kept in a data directory, the fsync policy and snapshot interval
+    persistence: Option<(Fsync, Duration)>,
+    /// The files the schema is read from, and how often they are checked for changes
+    schema_files: Option<(SchemaFiles, Duration)>,
+    /// When followers are sent the changes, the host and port they connect to, the authenticator
+    /// of the followers and the changes
+    replication: Option<Replication>,
+    /// The address of the primary and the token to authenticate to it with, when the database
+    /// follows one
+    primary: Option<(String, Option<String>)>,
+    following: Arc<Following>,
 }
 
 impl Database {
-    pub fn new(_config: &Config) -> Self {
-        Self {
-            schema: Document::default(),
+    /// The database of a config, panicking when its files cannot be read.
+    pub fn new(config: &Config) -> Self {
+        Database::open(config).unwrap_or_else(|e| panic!("Bad Value: {}", e))
+    }
+
+    /// The database of a config, or why its schema, records or persisted operations cannot be
+    /// read.
+    pub fn open(config: &Config) -> Result<Self, String> {
+        Database::open_sharing(config, Arc::new(CacheMetrics::default()))
+    }
+
+    /// The database of a config, counting the hits and misses of its result cache in `cache`.
+    pub(crate) fn open_sharing(config: &Config, cache: Arc<CacheMetrics>) -> Result<Self, String> {
+        let mut schema_files = config.schema.as_deref().map(SchemaFiles::new);
+        let schema = match &mut schema_files {
+            Some(files) => files
+                .load()
+                .map_err(|e| format!("Unable to read the schema: {}", e))?,
+            None => Document::default(),
+        };
+        let catalog = match &config.data_dir {
+            Some(dir) => Catalog::open(schema, Path::new(dir)).map_err(|e| {
+                format!(
+                    "Unable to read the schema changes of the data directory: {}",
+                    e
+                )
+            })?,
+            None => Catalog::new(schema),
+        };
+        debug!(version = catalog.version(), "Schema loaded");
+        let mut store = match &config.data {
+            Some(path) => {
+                let json = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read the data: {}", e))?;
+                Store::from_json(&json).map_err(|e| {
+                    format!("The data must be a JSON map of types to records: {}", e)
+                })?
+            }
+            None => Store::new(),
+        };
+        if let Some(dir) = &config.data_dir {
+            store = Store::open(Path::new(dir), config.fsync, store)
+                .map_err(|e| format!("Unable to read the records of the data directory: {}", e))?;
+        }
+        let schema = catalog.schema();
+        index::build(&mut store, &index::declared(&Schema::new(&schema)))
+            .map_err(|e| format!("A unique index holds the same value twice: {}", e))?;
+        let allowed = match &config.persisted_operations {
+            Some(path) => {
+                let manifest = std::fs::read_to_string(path)
+                    .map_err(|e| format!("Unable to read the persisted operations: {}", e))?;
+                let operations = PersistedOperations::from_json(&manifest).map_err(|e| {
+                    format!(
+                        "Persisted operations must be a JSON map of operations: {}",
+                        e
+                    )
+                })?;
+                Some(Arc::new(operations))
+            }
+            None => None,
+        };
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        store.publish(events.clone());
+        let replication = match config.replication_port {
+            Some(port) => {
+                let authenticator = listener::authenticator(config)?;
+                let (changes, _) = broadcast::channel(REPLICATION_BUFFER);
+                store.replicate(changes.clone());
+                Some(Replication {
+                    host: config.replication_host.clone(),
+                    port,
+                    authenticator,
+                    changes,
+                })
+            }
+            None => None,
+        };
+        Ok(Self {
+            catalog: Arc::new(RwLock::new(catalog)),
+            store: Arc::new(RwLock::new(store)),
+            resolvers: Arc::new(Resolvers::default()),
+            scalars: Arc::new(Scalars::default()),
+            events,
+            queries: Arc::new(DocumentCache::new(config.query_cache_size)),
+            results: Arc::new(ResultCache::new(config.result_cache_size, cache)),
+            allowed,
+            persistence: config
+                .data_dir
+                .as_ref()
+                .map(|_| (config.fsync, config.snapshot_interval)),
+            schema_files: schema_files
+                .filter(|_| !config.schema_poll.is_zero())
+                .map(|files| (files, config.schema_poll)),
+            replication,
+            primary: config
+                .follow
+                .clone()
+                .map(|primary| (primary, config.follow_token.clone())),
+            following: Arc::new(Following::new(config.follow.is_some())),
+        })
+    }
+
+    /// Answers a request in place, as a request that is not streamed is answered over the
+    /// network.
+    pub fn execute(&self, request: &Request) -> Value {
+        let parsed = self.queries.get_or_parse(&request.query);
+        self.answering()
+            .answer(&parsed, request, false)
+            .expect("Requests that are not streamed are answered")
+    }
+
+    /// Answers a field of a type with a resolver, in place of the one registered before, for the
+    /// requests answered from then on.
+    pub fn register_resolver<R>(&mut self, type_name: &str, field: &str, resolver: R)
+    where
+        R: Resolver + 'static,
+    {
+        Arc::make_mut(&mut self.resolvers).register(type_name, field, resolver);
+    }
+
+    /// Coerces the values of a scalar with a coercion, in place of the one registered before, for
+    /// the requests answered from then on.
+    pub fn register_scalar<C>(&mut self, name: &str, coercion: C)
+    where
+        C: ScalarCoercion + 'static,
+    {
+        Arc::make_mut(&mut self.scalars).register(name, coercion);
+    }
+
+    /// Applies a change to the schema, returning its new version or the conflicts of the change.
+    pub fn apply_schema(&self, change: &Document) -> Result<usize, Vec<String>> {
+        apply_schema(&self.catalog, &self.store, change)
+    }
+
+    fn answering(&self) -> Answering<'_> {
+        Answering {
+            catalog: &self.catalog,
+            store: &self.store,
+            resolvers: &self.resolvers,
+            scalars: &self.scalars,
+            results: &self.results,
+            allowed: self.allowed.as_deref(),
+            following: &self.following,
         }
     }
 
-    pub async fn run(&mut self, mut command: Receiver<(String, oneshot::Sender<String>)>) {
-        while let Some((gql_str, response)) = command.recv().await {
+    /// The counts of the hits, misses and invalidations of the result cache.
+    pub fn cache_metrics(&self) -> Arc<CacheMetrics> {
+        self.results.metrics()
+    }
+
+    /// Answers requests until every sender is dropped, then flushes.
+    pub async fn run(&mut self, mut command: Receiver<DbCommand>) {
+        let mut in_flight = JoinSet::new();
+        let persist = self
+            .persistence
+            .map(|(fsync, interval)| tokio::spawn(persist(self.store.clone(), fsync, interval)));
+        let reload = self.schema_files.take().map(|(files, interval)| {
+            tokio::spawn(reload(
+                files,
+                interval,
+                self.catalog.clone(),
+                self.store.clone(),
+            ))
+        });
+        let replicate = match self.replication.clone() {
+            Some(replication) => {
+                let Replication {
+                    host,
+                    port,
+                    authenticator,
+                    changes,
+                } = replication;
+                match TcpListener::bind((host.as_str(), port)).await {
+                    Ok(listener) => {
+                        info!(%host, port, "Serving followers");
+                        Some(tokio::spawn(replication::serve(
+                            listener,
+                            authenticator,
+                            self.catalog.clone(),
+                            self.store.clone(),
+                            changes,
+                        )))
+                    }
+                    Err(e) => {
+                        error!(%host, port, %e, "Unable to serve followers");
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+        let follow = self.primary.clone().map(|(primary, token)| {
+            tokio::spawn(replication::follow(
+                primary,
+                token,
+                self.catalog.clone(),
+                self.store.clone(),
+                self.following.clone(),
+            ))
+        });
+        // Tells the subscriptions to end once the requests stop coming
+        let (stop, stopped) = watch::channel(false);
+        while let Some((request, mut response)) = command.recv().await {
+            // Reap the requests already answered
+            while in_flight.try_join_next().is_some() {}
             // handle connection
-            tokio::spawn(async move {
-                let parsed = syntax::parse(&gql_str);
-                println!("Parsed: {:?}", parsed);
-                match response.send("Received input".into()) {
-                    Ok(()) => info!("Response sent successfully"),
-                    Err(e) => info!("Response from db failed: {}", e),
-                };
+            let catalog = self.catalog.clone();
+            let store = self.store.clone();
+            let resolvers = self.resolvers.clone();
+            let scalars = self.scalars.clone();
+            let events = self.events.clone();
+            let stopped = stopped.clone();
+            let queries = self.queries.clone();
+            let results = self.results.clone();
+            let allowed = self.allowed.clone();
+            let following = self.following.clone();
+            let span = info_span!(
+                "execute",
+                id = request.id.as_ref().map(tracing::field::display),
+                operation = request.operation_name.as_deref()
+            );
+            in_flight.spawn(
+                async move {
+                    let parsed = queries.get_or_parse(&request.query);
+                    debug!(?parsed, "Query parsed");
+                    let answering = Answering {
+                        catalog: &catalog,
+                        store: &store,
+                        resolvers: &resolvers,
+                        scalars: &scalars,
+                        results: &results,
+                        allowed: allowed.as_deref(),
+                        following: &following,
+                    };
+                    let reply = match answering.answer(&parsed, &request, response.is_stream()) {
+                        Some(reply) => reply,
+                        None => {
+                            let document = parsed.as_ref().expect("Streamed requests are parsed");
+                            let schema = catalog
+                                .read()
+                                .expect("The catalog lock is poisoned")
+                                .schema();
+                            if executor::operation_type(document, &request)
+                                == Some(Operation::Subscription)
+                            {
+                                let subscription = Subscription {
+                                    schema: &Schema::new(&schema),
+                                    resolvers: &resolvers,
+                                    scalars: &scalars,
+                                    store: &store,
+                                    document,
+                                    request: &request,
+                                };
+                                subscription
+                                    .run(&mut response, events.subscribe(), stopped)
+                                    .await;
+                                return;
+                            }
+                            let mut payloads = executor::execute_incremental(
+                                &Schema::new(&schema),
+                                &resolvers,
+                                &scalars,
+                                &store,
+                                document,
+                                &request,
+                            );
+                            let last = payloads.pop().expect("There is a first payload");
+                            for payload in payloads {
+                                if !response.send(payload.to_string()).await {
+                                    info!("The request was dropped before its response was sent");
+                                    return;
+                                }
+                            }
+                            last
+                        }
+                    };
+                    if !response.finish(reply.to_string()).await {
+                        info!("The request was dropped before its response was sent");
+                    }
+                }
+                .instrument(span),
+            );
+        }
+        let _ = stop.send(true);
+        while in_flight.join_next().await.is_some() {}
+        for task in persist
+            .iter()
+            .chain(reload.iter())
+            .chain(replicate.iter())
+            .chain(follow.iter())
+        {
+            task.abort();
+        }
+        self.flush();
+    }
+
+    /// Writes out what the database holds, as a snapshot of its data directory.
+    pub(crate) fn flush(&mut self) {
+        match self
+            .store
+            .write()
+            .expect("The store lock is poisoned")
+            .snapshot()
+        {
+            Ok(()) => info!("Database flushed"),
+            Err(e) => error!(%e, "Unable to write a snapshot of the records"),
+        }
+    }
+}
+
+/// Where followers connect to be sent the changes, and who may.
+#[derive(Clone)]
+struct Replication {
+    host: String,
+    port: u16,
+    authenticator: SharedAuthenticator,
+    changes: broadcast::Sender<Arc<Change>>,
+}
+
+/// What answering a request reads and changes of the database.
+struct Answering<'a> {
+    catalog: &'a RwLock<Catalog>,
+    store: &'a RwLock<Store>,
+    resolvers: &'a Arc<Resolvers>,
+    scalars: &'a Arc<Scalars>,
+    results: &'a ResultCache,
+    allowed: Option<&'a PersistedOperations>,
+    following: &'a Following,
+}
+
+impl Answering<'_> {
+    /// The response to a request, or `None` when it is a subscription or an operation that is
+    /// answered in several payloads over a `stream`.
+    fn answer(
+        &self,
+        parsed: &ParseResult<Arc<Document>>,
+        request: &Request,
+        stream: bool,
+    ) -> Option<Value> {
+        let document = match parsed {
+            Ok(document) => document,
+            Err(e) => return Some(request_error(e.to_string())),
+        };
+        if let Some(allowed) = self.allowed {
+            if catalog::changes_schema(document) {
+                return Some(request_error("Schema changes are not allowed".into()));
+            }
+            if !allowed.allows(document) {
+                return Some(request_error("Operation not allowed".into()));
+            }
+        }
+        if replication::promotes(document, request) {
+            return Some(replication::promote(
+                self.following,
+                request.identity.as_ref(),
+            ));
+        }
+        if self.following.get() {
+            if let Some(refusal) = replication::refuse(document, request) {
+                return Some(refusal);
+            }
+        }
+        if catalog::changes_schema(document) {
+            if !authorization::is_admin(request.identity.as_ref()) {
+                return Some(request_error(format!(
+                    "Changing the schema requires the role `{}`",
+                    authorization::ADMIN
+                )));
+            }
+            return Some(change_schema(self.catalog, self.store, document));
+        }
+        if let Some(command) = catalog::command(document, request) {
+            return Some(match command {
+                Command::History => self
+                    .catalog
+                    .read()
+                    .expect("The catalog lock is poisoned")
+                    .history(document, request),
+                Command::Rollback(operation, field) => self
+                    .catalog
+                    .write()
+                    .expect("The catalog lock is poisoned")
+                    .answer_rollback(operation, field, request, self.store),
             });
         }
+        let (schema, version) = {
+            let catalog = self.catalog.read().expect("The catalog lock is poisoned");
+            (catalog.schema(), catalog.version())
+        };
+        if explain::requested(document, request) {
+            return Some(explain::answer(
+                schema,
+                self.resolvers.clone(),
+                self.scalars.clone(),
+                self.store,
+                document,
+                request,
+            ));
+        }
+        if stream {
+            return None;
+        }
+        Some(self.results.execute(
+            version,
+            &Schema::new(&schema),
+            self.resolvers,
+            self.scalars,
+            self.store,
+            document,
+            request,
+        ))
+    }
+}
+
+/// Applies a change to the schema, answering with its new version or the conflicts of the change.
+fn change_schema(catalog: &RwLock<Catalog>, store: &RwLock<Store>, change: &Document) -> Value {
+    match apply_schema(catalog, store, change) {
+        Ok(version) => json!({ "data": { "version": version } }),
+        Err(conflicts) => catalog::errors(conflicts),
+    }
+}
+
+fn apply_schema(
+    catalog: &RwLock<Catalog>,
+    store: &RwLock<Store>,
+    change: &Document,
+) -> Result<usize, Vec<String>> {
+    let mut catalog = catalog.write().expect("The catalog lock is poisoned");
+    let version = catalog.apply(
+        change,
+        &mut store.write().expect("The store lock is poisoned"),
+    )?;
+    info!(version, "Schema changed");
+    Ok(version)
+}
+
+/// A subscription being answered.
+struct Subscription<'a> {
+    schema: &'a Schema<'a>,
+    resolvers: &'a Resolvers,
+    scalars: &'a Scalars,
+    store: &'a RwLock<Store>,
+    document: &'a Document,
+    request: &'a Request,
+}
+
+impl Subscription<'_> {
+    /// Answers the subscription with every change it is interested in, until the requester stops
+    /// listening or the database stops.
+    async fn run(
+        &self,
+        response: &mut Responder,
+        mut events: broadcast::Receiver<Arc<Event>>,
+        mut stopped: watch::Receiver<bool>,
+    ) {
+        if let Err(error) = executor::subscribe(self.schema, self.document, self.request) {
+            response.send(error.to_string()).await;
+            return;
+        }
+        info!("Subscribed");
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => event,
+                _ = stopped.changed() => break,
+                _ = response.closed() => break,
+            };
+            let reply = match event {
+                Ok(event) => {
+                    let reply = executor::execute_event(
+                        self.schema,
+                        self.resolvers,
+                        self.scalars,
+                        self.store,
+                        self.document,
+                        self.request,
+                        &event,
+                    );
+                    match reply {
+                        Some(reply) => reply,
+                        None => continue,
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(missed, "The subscription fell behind");
+                    request_error(format!(
+                        "The subscription fell behind and missed {} changes",
+                        missed
+                    ))
+                }
+                Err(RecvError::Closed) => break,
+            };
+            if !response.send(reply.to_string()).await {
+                break;
+            }
+        }
+        info!("Unsubscribed");
+    }
+}
+
+/// Writes snapshots of the store every interval, and flushes its log to disk as often as the
+/// fsync policy asks, when it does not flush after every change.
+async fn persist(store: Arc<RwLock<Store>>, fsync: Fsync, interval: Duration) {
+    let mut snapshots = (!interval.is_zero()).then(|| tokio::time::interval(interval));
+    let mut syncs = match fsync {
+        Fsync::Every(every) => Some(tokio::time::interval(every)),
+        Fsync::Always | Fsync::Never => None,
+    };
+    // The first tick of an interval is immediate, and there is nothing to write yet
+    for interval in snapshots.iter_mut().chain(syncs.iter_mut()) {
+        interval.tick().await;
+    }
+    loop {
+        tokio::select! {
+            _ = tick(&mut snapshots) => {
+                let written = store.write().expect("The store lock is poisoned").snapshot();
+                match written {
+                    Ok(()) => debug!("Snapshot written"),
+                    Err(e) => error!(%e, "Unable to write a snapshot of the records"),
+                }
+            }
+            _ = tick(&mut syncs) => {
+                let synced = store.write().expect("The store lock is poisoned").sync();
+                if let Err(e) = synced {
+                    error!(%e, "Unable to flush the write-ahead log");
+                }
+            }
+        }
+    }
+}
+
+/// Checks the schema files for changes every interval, and serves their new schema when they
+/// changed and it is valid.
+async fn reload(
+    mut files: SchemaFiles,
+    interval: Duration,
+    catalog: Arc<RwLock<Catalog>>,
+    store: Arc<RwLock<Store>>,
+) {
+    let mut interval = tokio::time::interval(interval);
+    loop {
+        interval.tick().await;
+        if !files.changed() {
+            continue;
+        }
+        let reloaded = files.load().map_err(|e| vec![e]).and_then(|source| {
+            let mut catalog = catalog.write().expect("The catalog lock is poisoned");
+            let mut store = store.write().expect("The store lock is poisoned");
+            catalog.reload(source, &mut store)
+        });
+        match reloaded {
+            Ok(()) => info!("Schema reloaded"),
+            Err(conflicts) => warn!(?conflicts, "The changed schema files were not loaded"),
+        }
+    }
+}
+
+/// Waits for the next tick of an interval, forever when there is none.
+async fn tick(interval: &mut Option<tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
     }
 }
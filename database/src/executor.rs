@@ -0,0 +1,1593 @@
+//! Executes the operations of requests against the schema and the records of the [`Store`].
+//!
+//! The fields of the query root type read the records of their type: fields of a list type
+//! return every record whose fields equal the arguments of the field, the others the first one.
+//! The fields of a record hold its values. A field of an object type holds either the object
+//! itself or the `id` of a record of that type, which it refers to.
+//!
+//! The fields of the mutation root type are the [generated mutations], run one after the other
+//! while the store is locked for writing. Each returns the record it changed.
+//!
+//! A subscription selects a single field of the subscription root type, one of the [generated
+//! subscriptions]. It is answered for every change to the records it is interested in, with the
+//! changed record as the value of that field.
+//!
+//! When the responses are streamed, the fragments marked `@defer` and the items held back by
+//! `@stream` are [delivered] in payloads after the first one.
+//!
+//! Fields and types marked `@auth` are only answered for the clients holding the role they
+//! [require], the others getting an error for each such field.
+//!
+//! Fields with a [resolver] are answered by it instead, wherever they are selected. The values of
+//! scalars are written in the response by their [coercion].
+//!
+//! The result is the JSON response of the GraphQL Spec, `{ "data": ... }`, with an `errors` list
+//! when some fields could not be answered. Requests that cannot be executed at all are answered
+//! with only an `errors` list.
+//!
+//! The errors of fields have the `path` of the field in the response, and the `locations` of the
+//! field in the query. A field that fails is null. When it is non-null, the null propagates to
+//! its parent field instead, up to the nearest nullable one, or to `data` itself.
+//!
+//! An operation can also be [explained] instead of executed.
+//!
+//! [`Store`]: ../store/struct.Store.html
+//! [generated mutations]: ../mutation/index.html
+//! [generated subscriptions]: ../subscription/index.html
+//! [delivered]: ../incremental/index.html
+//! [require]: ../authorization/index.html
+//! [resolver]: ../resolver/index.html
+//! [coercion]: ../scalar/index.html
+//! [explained]: ../explain/index.html
+
+use crate::authorization;
+use crate::coercion::{self, Coercion};
+use crate::incremental;
+use crate::index;
+use crate::locations::Locations;
+use crate::mutation;
+use crate::query;
+use crate::resolver::{Context, Resolver, Resolvers};
+use crate::scalar::Scalars;
+use crate::store::{same_value, Record, Store};
+use crate::subscription::Event;
+use net::request::Request;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::sync::RwLock;
+use syntax::document::{Document, OperationWithFragments};
+use syntax::merge::{merge_selection_sets, response_key};
+use syntax::nodes::*;
+use syntax::schema::{named_type, Schema};
+
+/// Executes the operation of `query` that `request` names and returns the JSON response.
+/// Subscriptions are instead answered by [`execute_event`] for every change they are interested
+/// in.
+///
+/// [`execute_event`]: fn.execute_event.html
+pub(crate) fn execute(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &RwLock<Store>,
+    query: &Document,
+    request: &Request,
+) -> Value {
+    execute_reading(schema, resolvers, scalars, store, query, request).0
+}
+
+/// Executes like [`execute`], and tells which types a query read the records of, with the
+/// [version] of their records then. They are not told for other operations, nor when a resolver
+/// answered a field, as it may read anything.
+///
+/// [`execute`]: fn.execute.html
+/// [version]: ../store/struct.Store.html#method.version
+pub(crate) fn execute_reading(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &RwLock<Store>,
+    query: &Document,
+    request: &Request,
+) -> (Value, Option<HashMap<String, u64>>) {
+    let (mut payloads, reads) = run(schema, resolvers, scalars, store, query, request, false);
+    (payloads.remove(0), reads)
+}
+
+/// Executes like [`execute`], but delivers the fragments marked `@defer` and the items held back
+/// by `@stream` in payloads of their own, returned in order after the first one.
+///
+/// [`execute`]: fn.execute.html
+pub(crate) fn execute_incremental(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &RwLock<Store>,
+    query: &Document,
+    request: &Request,
+) -> Vec<Value> {
+    run(schema, resolvers, scalars, store, query, request, true).0
+}
+
+fn run(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &RwLock<Store>,
+    query: &Document,
+    request: &Request,
+    incremental: bool,
+) -> (Vec<Value>, Option<HashMap<String, u64>>) {
+    let prepared = prepare(schema, resolvers, scalars, query, request, incremental);
+    let (mut executor, operation, root) = match prepared {
+        Ok(prepared) => prepared,
+        Err(response) => return (vec![response], None),
+    };
+    let selections = &operation.definition().selections;
+    let data = match operation {
+        OperationTypeNode::Mutation(_) => {
+            let mut store = store.write().expect("The store lock is poisoned");
+            executor.reads = None;
+            executor.execute_mutation(&mut store, root, selections)
+        }
+        OperationTypeNode::Query(_) => {
+            let store = store.read().expect("The store lock is poisoned");
+            executor.execute_selections(&store, root, &[selections], &mut Vec::new())
+        }
+        OperationTypeNode::Subscription(_) => {
+            let response = request_error("Subscriptions are only answered over a stream".into());
+            return (vec![response], None);
+        }
+    };
+    let reads = executor.reads.take();
+    (executor.payloads(data), reads)
+}
+
+/// The kind of the operation of `query` that `request` names, when there is one.
+pub(crate) fn operation_type(query: &Document, request: &Request) -> Option<Operation> {
+    select_operation(query, request)
+        .ok()
+        .map(|operation| operation.operation.operation())
+}
+
+/// Checks that the subscription of `query` that `request` names can be answered, and returns
+/// the error response when it cannot.
+pub(crate) fn subscribe(schema: &Schema, query: &Document, request: &Request) -> Result<(), Value> {
+    let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+    let (mut executor, operation, root) =
+        prepare(schema, &resolvers, &scalars, query, request, false)?;
+    let subscribed = executor.subscription_field(root, &operation.definition().selections);
+    match subscribed {
+        Some(_) if executor.errors.is_empty() => Ok(()),
+        _ => Err(json!({ "errors": executor.errors })),
+    }
+}
+
+/// Answers the subscription of `query` that `request` names for a change to the records, or
+/// returns `None` when the subscription is not interested in that change.
+pub(crate) fn execute_event(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &RwLock<Store>,
+    query: &Document,
+    request: &Request,
+    event: &Event,
+) -> Option<Value> {
+    let (mut executor, operation, root) =
+        prepare(schema, resolvers, scalars, query, request, false).ok()?;
+    let (key, fields, definition, arguments) =
+        executor.subscription_field(root, &operation.definition().selections)?;
+    if !event.concerns(fields[0].name.value.as_str(), &arguments) {
+        return None;
+    }
+    let object = Object {
+        type_name: &event.type_name,
+        record: Some(&event.record),
+    };
+    let store = store.read().expect("The store lock is poisoned");
+    let value = executor.complete(
+        &store,
+        &definition.field_type,
+        Resolved::Object(object),
+        &fields,
+        &mut vec![json!(key)],
+    );
+    let data = value.map(|value| {
+        let mut data = Map::new();
+        data.insert(key.into(), value);
+        Value::Object(data)
+    });
+    Some(executor.response(data.unwrap_or(Value::Null)))
+}
+
+/// The plan of the operation of `query` that `request` names, without executing it: its fields,
+/// in the order they are resolved, with how each is answered and an estimate of its cost. Fails
+/// with the messages of the errors that would keep it from being executed.
+///
+/// Every field costs 1, and a field of an object type the cost of its selections besides, once
+/// for every object it is estimated to return. A collection returns the records its indexed
+/// filter field narrows them to, or every record of its entity, at most `first`. Other lists of
+/// objects may hold every record of their type.
+pub(crate) fn explain(
+    schema: &Schema,
+    resolvers: &Resolvers,
+    scalars: &Scalars,
+    store: &Store,
+    query: &Document,
+    request: &Request,
+) -> Result<Value, String> {
+    let (mut executor, operation, root) =
+        prepare(schema, resolvers, scalars, query, request, false).map_err(messages)?;
+    let kind = match operation.operation() {
+        Operation::Query => "query",
+        Operation::Mutation => "mutation",
+        Operation::Subscription => "subscription",
+    };
+    let selections = &operation.definition().selections;
+    let fields =
+        executor.plan_selections(store, root.type_name, &[selections], &mut Vec::new(), None);
+    if !executor.errors.is_empty() {
+        return Err(messages(json!({ "errors": executor.errors })));
+    }
+    let cost: u64 = fields
+        .iter()
+        .filter_map(|field| field["cost"].as_u64())
+        .sum();
+    Ok(json!({
+        "operation": kind,
+        "name": operation.definition().name.as_ref().map(|name| name.value.as_str()),
+        // The fields of a mutation are resolved one after the other
+        "serial": kind == "mutation",
+        "cost": cost,
+        "fields": fields,
+    }))
+}
+
+/// The messages of the errors of a response, one per line.
+fn messages(response: Value) -> String {
+    let errors = response["errors"].as_array().cloned().unwrap_or_default();
+    let messages: Vec<&str> = errors
+        .iter()
+        .filter_map(|error| error["message"].as_str())
+        .collect();
+    messages.join("\n")
+}
+
+/// The operation of `query` that `request` names: the only one, unless it names another.
+fn select_operation<'q>(
+    query: &'q Document,
+    request: &Request,
+) -> Result<OperationWithFragments<'q>, Value> {
+    let mut operations = query.operations();
+    match &request.operation_name {
+        Some(name) => match operations.drain(..).find(|o| o.name == Some(name.as_str())) {
+            Some(operation) => Ok(operation),
+            None => Err(request_error(format!("Unknown operation `{}`", name))),
+        },
+        None => match operations.len() {
+            1 => Ok(operations.remove(0)),
+            0 => Err(request_error(
+                "The document does not contain any operation".into(),
+            )),
+            _ => Err(request_error(
+                "The document contains several operations, an operationName is required".into(),
+            )),
+        },
+    }
+}
+
+/// Selects the operation to execute, finds its root type and coerces its variables. `@defer` and
+/// `@stream` are only followed when the result is `incremental`.
+fn prepare<'a>(
+    schema: &'a Schema<'a>,
+    resolvers: &'a Resolvers,
+    scalars: &'a Scalars,
+    query: &'a Document,
+    request: &'a Request,
+    incremental: bool,
+) -> Result<(Executor<'a>, &'a OperationTypeNode, Object<'a>), Value> {
+    let selected = select_operation(query, request)?;
+    let misplaced = incremental::validate(schema, &selected);
+    if !misplaced.is_empty() {
+        let errors: Vec<Value> = misplaced
+            .into_iter()
+            .map(|message| json!({ "message": message }))
+            .collect();
+        return Err(json!({ "errors": errors }));
+    }
+    let operation = selected.operation;
+    let (root, kind) = match operation.operation() {
+        Operation::Query => (schema.query_type(), "query"),
+        Operation::Mutation => (schema.mutation_type(), "mutation"),
+        Operation::Subscription => (schema.subscription_type(), "subscription"),
+    };
+    let root = root
+        .filter(|root| schema.has_type(root))
+        .ok_or_else(|| request_error(format!("The schema does not define a {} type", kind)))?;
+    let provided = request.variables.clone().unwrap_or_default();
+    let variables = Coercion::variables(
+        schema,
+        scalars,
+        &operation.definition().variables,
+        &provided,
+    )
+    .map_err(request_error)?;
+    let executor = Executor {
+        schema,
+        resolvers,
+        scalars,
+        request,
+        document: query,
+        fragments: selected
+            .fragments
+            .iter()
+            .map(|fragment| (fragment.name.value.as_str(), *fragment))
+            .collect(),
+        variables,
+        errors: Vec::new(),
+        field: None,
+        locations: None,
+        reads: Some(HashMap::new()),
+        deferring: Vec::new(),
+        pending: if incremental { Some(Vec::new()) } else { None },
+    };
+    let root = Object {
+        type_name: root,
+        record: None,
+    };
+    Ok((executor, operation, root))
+}
+
+/// A response with only an error, for requests that cannot be executed.
+pub(crate) fn request_error(message: String) -> Value {
+    json!({ "errors": [{ "message": message }] })
+}
+
+/// An object being executed: a record, or the root type when there is none.
+#[derive(Clone, Copy)]
+struct Object<'s> {
+    type_name: &'s str,
+    record: Option<&'s Record>,
+}
+
+/// The value of a field, before it is completed according to the type of the field.
+enum Resolved<'s> {
+    Null,
+    Value(&'s Value),
+    Object(Object<'s>),
+    List(Vec<Resolved<'s>>),
+}
+
+impl<'s> From<Option<&'s Value>> for Resolved<'s> {
+    fn from(value: Option<&'s Value>) -> Self {
+        match value {
+            None | Some(Value::Null) => Resolved::Null,
+            Some(value) => Resolved::Value(value),
+        }
+    }
+}
+
+/// Executes an operation. The store is handed to each step rather than kept, so that mutations
+/// can change it between the fields they execute.
+struct Executor<'a> {
+    schema: &'a Schema<'a>,
+    resolvers: &'a Resolvers,
+    scalars: &'a Scalars,
+    request: &'a Request,
+    document: &'a Document,
+    fragments: HashMap<&'a str, &'a FragmentDefinitionNode>,
+    variables: Map<String, Value>,
+    errors: Vec<Value>,
+    /// The field being executed, which errors are raised for
+    field: Option<&'a FieldNode>,
+    /// Where the fields are written in the query, once an error needs it
+    locations: Option<Locations>,
+    /// The types whose records were read, with their version then, until a resolver answers a
+    /// field
+    reads: Option<HashMap<String, u64>>,
+    /// The fragments marked `@defer` found by the last `collect_fields`, with their label
+    deferring: Vec<(Option<String>, &'a [Selection])>,
+    /// The payloads delivered after the first one, when `@defer` and `@stream` are followed
+    pending: Option<Vec<Value>>,
+}
+
+impl<'a> Executor<'a> {
+    fn execute_selections<'s>(
+        &mut self,
+        store: &'s Store,
+        object: Object<'s>,
+        selection_sets: &[&'a [Selection]],
+        path: &mut Vec<Value>,
+    ) -> Value
+    where
+        'a: 's,
+    {
+        let mut grouped = Vec::new();
+        for selections in selection_sets {
+            self.collect_fields(object.type_name, selections, &mut grouped);
+        }
+        let deferred = std::mem::take(&mut self.deferring);
+        let mut result = Map::new();
+        for (key, fields) in grouped {
+            path.push(json!(key));
+            let value = self.execute_field(store, object, &fields, path);
+            path.pop();
+            match value {
+                Some(value) => result.insert(key.into(), value),
+                // A non-null field is null, so the object is
+                None => return Value::Null,
+            };
+        }
+        for (label, selections) in deferred {
+            let slot = self.hold();
+            let errors = self.errors.len();
+            let data = self.execute_selections(store, object, &[selections], path);
+            let payload = json!({ "data": data, "path": path });
+            self.deliver(slot, payload, label, errors);
+        }
+        Value::Object(result)
+    }
+
+    /// Keeps the place of a payload delivered after the first one, before it is executed, so
+    /// that the payloads it holds back come after it.
+    fn hold(&mut self) -> usize {
+        let pending = self
+            .pending
+            .as_mut()
+            .expect("Payloads are only held back when delivered");
+        pending.push(Value::Null);
+        pending.len() - 1
+    }
+
+    /// Fills the place of a payload, with its label and the errors raised since `errors` were.
+    fn deliver(&mut self, slot: usize, mut payload: Value, label: Option<String>, errors: usize) {
+        if let Some(label) = label {
+            payload["label"] = json!(label);
+        }
+        if self.errors.len() > errors {
+            payload["errors"] = Value::Array(self.errors.split_off(errors));
+        }
+        if let Some(pending) = &mut self.pending {
+            pending[slot] = payload;
+        }
+    }
+
+    /// Runs the mutations of the selections one after the other, each being completed before
+    /// the next one runs.
+    fn execute_mutation(
+        &mut self,
+        store: &mut Store,
+        root: Object,
+        selections: &'a [Selection],
+    ) -> Value {
+        let mut grouped = Vec::new();
+        self.collect_fields(root.type_name, selections, &mut grouped);
+        let mut result = Map::new();
+        for (key, fields) in grouped {
+            let mut path = vec![json!(key)];
+            let field = fields[0];
+            let name = field.name.value.as_str();
+            self.field = Some(field);
+            let definition = match name {
+                "__typename" => {
+                    result.insert(key.into(), json!(root.type_name));
+                    continue;
+                }
+                _ => match self.field_definition(root.type_name, field, &path) {
+                    Some(definition) => definition,
+                    None => {
+                        result.insert(key.into(), Value::Null);
+                        continue;
+                    }
+                },
+            };
+            if !self.authorized(root.type_name, definition, &path) {
+                result.insert(key.into(), Value::Null);
+                continue;
+            }
+            let value = if let Some(resolver) = self.resolvers.get(root.type_name, name) {
+                match self.resolve(resolver, store, None, field, definition, &path) {
+                    Some(value) => self.complete(
+                        store,
+                        &definition.field_type,
+                        Some(&value).into(),
+                        &fields,
+                        &mut path,
+                    ),
+                    None => failed(&definition.field_type),
+                }
+            } else {
+                let identity = self.request.identity.as_ref();
+                let changed = Coercion::new(self.schema, self.scalars, &self.variables)
+                    .arguments(field, definition)
+                    .and_then(|arguments| {
+                        authorization::authorize_mutation(self.schema, name, &arguments, identity)?;
+                        mutation::apply(store, self.schema, name, &arguments)
+                    });
+                match changed {
+                    Ok(Some(record)) => {
+                        let object = Object {
+                            type_name: named_type(&definition.field_type),
+                            record: Some(&record),
+                        };
+                        self.complete(
+                            store,
+                            &definition.field_type,
+                            Resolved::Object(object),
+                            &fields,
+                            &mut path,
+                        )
+                    }
+                    Ok(None) => self.complete(
+                        store,
+                        &definition.field_type,
+                        Resolved::Null,
+                        &fields,
+                        &mut path,
+                    ),
+                    Err(e) => {
+                        self.error(e, &path);
+                        failed(&definition.field_type)
+                    }
+                }
+            };
+            match value {
+                Some(value) => result.insert(key.into(), value),
+                // A non-null mutation is null: so is the data, and the next ones do not run
+                None => return Value::Null,
+            };
+        }
+        Value::Object(result)
+    }
+
+    /// The value a resolver gives a field, or nothing when it fails.
+    fn resolve(
+        &mut self,
+        resolver: &dyn Resolver,
+        store: &Store,
+        parent: Option<&Record>,
+        field: &FieldNode,
+        definition: &FieldDefinitionNode,
+        path: &[Value],
+    ) -> Option<Value> {
+        self.reads = None;
+        let context = Context {
+            request: self.request,
+            store,
+            path,
+        };
+        let resolved = Coercion::new(self.schema, self.scalars, &self.variables)
+            .arguments(field, definition)
+            .and_then(|arguments| {
+                resolver.resolve(parent, &field.name.value, &arguments, &context)
+            });
+        match resolved {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.error(e, path);
+                None
+            }
+        }
+    }
+
+    /// Groups the fields that apply to a type by response key, flattening fragment spreads and
+    /// leaving out the selections skipped by `@skip` and `@include`.
+    fn collect_fields(
+        &mut self,
+        type_name: &str,
+        selections: &'a [Selection],
+        grouped: &mut Vec<(&'a str, Vec<&'a FieldNode>)>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    if !self.included(&field.directives) {
+                        continue;
+                    }
+                    let key = response_key(field);
+                    match grouped.iter_mut().find(|(existing, _)| *existing == key) {
+                        Some((_, fields)) => fields.push(field),
+                        None => grouped.push((key, vec![field])),
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let applies = match &inline.node_type {
+                        Some(node_type) => self.applies(&node_type.name.value, type_name),
+                        None => true,
+                    };
+                    if !applies || !self.included(&inline.directives) {
+                        continue;
+                    }
+                    match self.incremental(&inline.directives, "defer") {
+                        Some(defer) => self.deferring.push((label(&defer), &inline.selections)),
+                        None => self.collect_fields(type_name, &inline.selections, grouped),
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    if !self.included(&spread.directives) {
+                        continue;
+                    }
+                    match self.fragments.get(spread.name.value.as_str()) {
+                        Some(fragment) => {
+                            if !self.applies(&fragment.node_type.name.value, type_name) {
+                                continue;
+                            }
+                            match self.incremental(&spread.directives, "defer") {
+                                Some(defer) => {
+                                    self.deferring.push((label(&defer), &fragment.selections))
+                                }
+                                None => {
+                                    self.collect_fields(type_name, &fragment.selections, grouped)
+                                }
+                            }
+                        }
+                        None => {
+                            self.error(format!("Unknown fragment `{}`", spread.name.value), &[])
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether a fragment with the type condition `condition` applies to objects of a type.
+    fn applies(&self, condition: &str, type_name: &str) -> bool {
+        self.schema.is_subtype(type_name, condition)
+    }
+
+    fn included(&self, directives: &Option<Directives>) -> bool {
+        let coercion = Coercion::new(self.schema, self.scalars, &self.variables);
+        let boolean = TypeNode::Named(NamedTypeNode::from("Boolean"));
+        directives.iter().flatten().all(|directive| {
+            let condition = directive
+                .arguments
+                .iter()
+                .flatten()
+                .find(|argument| argument.name.value == "if")
+                .and_then(|argument| coercion.literal(&argument.value, &boolean).ok());
+            !matches!(
+                (directive.name.value.as_str(), condition),
+                ("skip", Some(Value::Bool(true))) | ("include", Some(Value::Bool(false)))
+            )
+        })
+    }
+
+    /// The arguments of the `@defer` or `@stream` directive `name` among the directives, when it
+    /// is followed: the result is incremental and its `if` argument is not false.
+    fn incremental(
+        &self,
+        directives: &Option<Directives>,
+        name: &str,
+    ) -> Option<Map<String, Value>> {
+        self.pending.as_ref()?;
+        let directive = directives
+            .iter()
+            .flatten()
+            .find(|directive| directive.name.value == name)?;
+        let definition = self.schema.get_directive(name);
+        let arguments = definition
+            .and_then(|definition| {
+                Coercion::new(self.schema, self.scalars, &self.variables)
+                    .directive_arguments(directive, definition)
+                    .ok()
+            })
+            .unwrap_or_default();
+        if arguments.get("if") == Some(&Value::Bool(false)) {
+            return None;
+        }
+        Some(arguments)
+    }
+
+    fn field_definition(
+        &mut self,
+        type_name: &str,
+        field: &FieldNode,
+        path: &[Value],
+    ) -> Option<&'a FieldDefinitionNode> {
+        let name = field.name.value.as_str();
+        let definition = self.schema.field(type_name, name);
+        if definition.is_none() {
+            self.error(
+                format!("Cannot query field `{}` on type `{}`", name, type_name),
+                path,
+            );
+        }
+        definition
+    }
+
+    /// Checks that the client holds the roles a field requires, raising the error of the field
+    /// when it does not.
+    fn authorized(
+        &mut self,
+        type_name: &str,
+        definition: &FieldDefinitionNode,
+        path: &[Value],
+    ) -> bool {
+        let identity = self.request.identity.as_ref();
+        match authorization::authorize(self.schema, type_name, definition, identity) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error(e, path);
+                false
+            }
+        }
+    }
+
+    /// The value of a field, or nothing when it is non-null but null, its parent then being null
+    /// in its place.
+    fn execute_field<'s>(
+        &mut self,
+        store: &'s Store,
+        object: Object<'s>,
+        fields: &[&'a FieldNode],
+        path: &mut Vec<Value>,
+    ) -> Option<Value>
+    where
+        'a: 's,
+    {
+        let parent = self.field.replace(fields[0]);
+        let value = self.field_value(store, object, fields, path);
+        self.field = parent;
+        value
+    }
+
+    fn field_value<'s>(
+        &mut self,
+        store: &'s Store,
+        object: Object<'s>,
+        fields: &[&'a FieldNode],
+        path: &mut Vec<Value>,
+    ) -> Option<Value>
+    where
+        'a: 's,
+    {
+        let field = fields[0];
+        let name = field.name.value.as_str();
+        if name == "__typename" {
+            return Some(json!(object.type_name));
+        }
+        if object.record.is_none() && (name == "__schema" || name == "__type") {
+            return Some(self.introspect(field, path));
+        }
+        let definition = match self.field_definition(object.type_name, field, path) {
+            Some(definition) => definition,
+            None => return Some(Value::Null),
+        };
+        if !self.authorized(object.type_name, definition, path) {
+            return failed(&definition.field_type);
+        }
+        if let Some(resolver) = self.resolvers.get(object.type_name, name) {
+            return match self.resolve(resolver, store, object.record, field, definition, path) {
+                Some(value) => self.complete(
+                    store,
+                    &definition.field_type,
+                    Some(&value).into(),
+                    fields,
+                    path,
+                ),
+                None => failed(&definition.field_type),
+            };
+        }
+        let resolved = match object.record {
+            Some(record) => record.get(name).into(),
+            None => {
+                let arguments = Coercion::new(self.schema, self.scalars, &self.variables)
+                    .arguments(field, definition);
+                let collection = query::collection_of(self.schema, &definition.field_type);
+                match (arguments, collection) {
+                    (Ok(arguments), Some(type_name)) => {
+                        self.read(store, type_name);
+                        return match query::page(store, type_name, &arguments) {
+                            Ok(connection) => self.complete(
+                                store,
+                                &definition.field_type,
+                                Resolved::Value(&connection),
+                                fields,
+                                path,
+                            ),
+                            Err(e) => {
+                                self.error(e, path);
+                                failed(&definition.field_type)
+                            }
+                        };
+                    }
+                    (Ok(arguments), None) => {
+                        self.resolve_root(store, &definition.field_type, &arguments)
+                    }
+                    (Err(e), _) => {
+                        self.error(e, path);
+                        return failed(&definition.field_type);
+                    }
+                }
+            }
+        };
+        self.complete(store, &definition.field_type, resolved, fields, path)
+    }
+
+    /// Reads the records a field of the query root type returns.
+    fn resolve_root<'s>(
+        &mut self,
+        store: &'s Store,
+        field_type: &TypeNode,
+        arguments: &Map<String, Value>,
+    ) -> Resolved<'s>
+    where
+        'a: 's,
+    {
+        let (list, type_name) = match field_type {
+            TypeNode::NonNull(inner) => return self.resolve_root(store, inner, arguments),
+            TypeNode::List(list) => (true, named_type(&list.list_type)),
+            TypeNode::Named(named) => (false, named.name.value.as_str()),
+        };
+        self.read(store, type_name);
+        let mut records = self.records_of(store, type_name).filter(|object| {
+            let record = object.record.expect("Stored objects have a record");
+            arguments.iter().all(|(name, value)| {
+                record
+                    .get(name)
+                    .map_or(value.is_null(), |field| same_value(field, value))
+            })
+        });
+        if list {
+            Resolved::List(records.map(Resolved::Object).collect())
+        } else {
+            records.next().map_or(Resolved::Null, Resolved::Object)
+        }
+    }
+
+    /// The stored records of a type, or of the types implementing it when it is abstract.
+    fn records_of<'s>(
+        &self,
+        store: &'s Store,
+        type_name: &str,
+    ) -> impl Iterator<Item = Object<'s>> + 's
+    where
+        'a: 's,
+    {
+        self.concrete_types(type_name)
+            .into_iter()
+            .flat_map(move |type_name| {
+                store.records(type_name).iter().map(move |record| Object {
+                    type_name,
+                    record: Some(record),
+                })
+            })
+    }
+
+    /// Notes that the records of a type, or of the types implementing it, were read.
+    fn read(&mut self, store: &Store, type_name: &str) {
+        let concrete = self.concrete_types(type_name);
+        if let Some(reads) = &mut self.reads {
+            for type_name in concrete {
+                reads.insert(type_name.into(), store.version(type_name));
+            }
+        }
+    }
+
+    /// The object types whose records a field of a type may hold.
+    fn concrete_types(&self, type_name: &str) -> Vec<&'a str> {
+        match self.schema.get_type(type_name) {
+            Some(TypeDefinitionNode::Object(object)) => vec![object.name.value.as_str()],
+            Some(TypeDefinitionNode::Interface(_)) | Some(TypeDefinitionNode::Union(_)) => {
+                self.schema.possible_types(type_name)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Completes the value of a field according to its type, or returns nothing when a non-null
+    /// value is null.
+    fn complete<'s>(
+        &mut self,
+        store: &'s Store,
+        field_type: &'a TypeNode,
+        resolved: Resolved<'s>,
+        fields: &[&'a FieldNode],
+        path: &mut Vec<Value>,
+    ) -> Option<Value>
+    where
+        'a: 's,
+    {
+        let named = match field_type {
+            TypeNode::NonNull(inner) => {
+                let errors = self.errors.len();
+                let value = self.complete(store, inner, resolved, fields, path)?;
+                if !value.is_null() {
+                    return Some(value);
+                }
+                // Unless the null comes from an error raised already
+                if self.errors.len() == errors {
+                    let name = fields[0].name.value.as_str();
+                    self.error(
+                        format!("Cannot return null for non-nullable field `{}`", name),
+                        path,
+                    );
+                }
+                return None;
+            }
+            TypeNode::List(list) => {
+                let items = match resolved {
+                    Resolved::Null => return Some(Value::Null),
+                    Resolved::List(items) => items,
+                    Resolved::Value(Value::Array(values)) => {
+                        values.iter().map(|value| Some(value).into()).collect()
+                    }
+                    _ => {
+                        self.error("Expected a list".into(), path);
+                        return Some(Value::Null);
+                    }
+                };
+                // Only the list of the field is streamed, not the lists it holds
+                let stream = match path.last() {
+                    Some(Value::String(_)) => self.incremental(&fields[0].directives, "stream"),
+                    _ => None,
+                };
+                let initial = stream
+                    .as_ref()
+                    .and_then(|stream| stream.get("initialCount"))
+                    .and_then(Value::as_u64)
+                    .map_or(items.len(), |count| count as usize);
+                let mut values = Vec::with_capacity(items.len());
+                for (index, item) in items.into_iter().enumerate() {
+                    path.push(json!(index));
+                    let errors = self.errors.len();
+                    let slot = match stream {
+                        Some(_) if index >= initial => Some(self.hold()),
+                        _ => None,
+                    };
+                    let value = self.complete(store, &list.list_type, item, fields, path);
+                    match (slot, value) {
+                        (Some(slot), value) => {
+                            let items = value.map(|value| vec![value]);
+                            let payload = json!({ "items": items, "path": path });
+                            let label = stream.as_ref().and_then(label);
+                            self.deliver(slot, payload, label, errors);
+                        }
+                        (None, Some(value)) => values.push(value),
+                        // A non-null item is null, so the list is
+                        (None, None) => {
+                            path.pop();
+                            return Some(Value::Null);
+                        }
+                    }
+                    path.pop();
+                }
+                return Some(Value::Array(values));
+            }
+            TypeNode::Named(named) => named.name.value.as_str(),
+        };
+        let value = match self.schema.get_type(named) {
+            Some(TypeDefinitionNode::Object(_))
+            | Some(TypeDefinitionNode::Interface(_))
+            | Some(TypeDefinitionNode::Union(_)) => {
+                let object = match resolved {
+                    Resolved::Null => return Some(Value::Null),
+                    Resolved::Object(object) => object,
+                    Resolved::Value(value) => match self.object(store, named, value, path) {
+                        Some(object) => object,
+                        None => return Some(Value::Null),
+                    },
+                    Resolved::List(_) => {
+                        self.error(format!("Expected an object of type `{}`", named), path);
+                        return Some(Value::Null);
+                    }
+                };
+                let selections = merge_selection_sets(fields);
+                self.execute_selections(store, object, &selections, path)
+            }
+            _ => match resolved {
+                Resolved::Null => Value::Null,
+                Resolved::Value(value) => match self.scalars.serialize(named, value) {
+                    Some(value) => value,
+                    None => {
+                        self.error(format!("Expected a value of type `{}`", named), path);
+                        Value::Null
+                    }
+                },
+                _ => {
+                    self.error(format!("Expected a value of type `{}`", named), path);
+                    Value::Null
+                }
+            },
+        };
+        Some(value)
+    }
+
+    /// The object a stored value of an object type stands for: the object itself, or the record
+    /// of that type it refers to by `id`.
+    fn object<'s>(
+        &mut self,
+        store: &'s Store,
+        type_name: &'a str,
+        value: &'s Value,
+        path: &[Value],
+    ) -> Option<Object<'s>>
+    where
+        'a: 's,
+    {
+        match value {
+            Value::Object(record) => {
+                let concrete = match self.schema.get_type(type_name) {
+                    Some(TypeDefinitionNode::Object(_)) => Some(type_name),
+                    _ => record
+                        .get("__typename")
+                        .and_then(Value::as_str)
+                        .filter(|concrete| self.applies(type_name, concrete)),
+                };
+                match concrete {
+                    Some(concrete) => Some(Object {
+                        type_name: concrete,
+                        record: Some(record),
+                    }),
+                    None => {
+                        self.error(
+                            format!("Cannot tell the type of an object of type `{}`", type_name),
+                            path,
+                        );
+                        None
+                    }
+                }
+            }
+            Value::String(_) | Value::Number(_) => {
+                self.read(store, type_name);
+                self.concrete_types(type_name)
+                    .into_iter()
+                    .find_map(|concrete| {
+                        store.get(concrete, value).map(|record| Object {
+                            type_name: concrete,
+                            record: Some(record),
+                        })
+                    })
+            }
+            _ => {
+                self.error(format!("Expected an object of type `{}`", type_name), path);
+                None
+            }
+        }
+    }
+
+    /// Answers `__schema` and `__type` from the introspection of the schema.
+    fn introspect(&mut self, field: &FieldNode, path: &[Value]) -> Value {
+        let mut definitions = vec![DefinitionNode::Executable(
+            ExecutableDefinitionNode::Operation(OperationTypeNode::Query(QueryDefinitionNode {
+                name: None,
+                variables: None,
+                selections: vec![Selection::Field(field.clone())],
+            })),
+        )];
+        definitions.extend(self.fragments.values().map(|fragment| {
+            DefinitionNode::Executable(ExecutableDefinitionNode::Fragment((*fragment).clone()))
+        }));
+        let mut response = self.schema.introspect(&Document::new(definitions));
+        if let Some(Value::Array(errors)) = response.get_mut("errors") {
+            for error in errors.drain(..) {
+                self.error(error["message"].as_str().unwrap_or_default().into(), path);
+            }
+        }
+        response["data"][response_key(field)].take()
+    }
+
+    /// The single root field a subscription selects, with its response key, definition and
+    /// arguments.
+    #[allow(clippy::type_complexity)]
+    fn subscription_field(
+        &mut self,
+        root: Object,
+        selections: &'a [Selection],
+    ) -> Option<(
+        &'a str,
+        Vec<&'a FieldNode>,
+        &'a FieldDefinitionNode,
+        Map<String, Value>,
+    )> {
+        let mut grouped = Vec::new();
+        self.collect_fields(root.type_name, selections, &mut grouped);
+        if grouped.len() != 1 {
+            self.error("A subscription must select exactly one field".into(), &[]);
+            return None;
+        }
+        let (key, fields) = grouped.remove(0);
+        self.field = Some(fields[0]);
+        let path = [json!(key)];
+        let definition = self.field_definition(root.type_name, fields[0], &path)?;
+        if !self.authorized(root.type_name, definition, &path) {
+            return None;
+        }
+        match Coercion::new(self.schema, self.scalars, &self.variables)
+            .arguments(fields[0], definition)
+        {
+            Ok(arguments) => Some((key, fields, definition, arguments)),
+            Err(e) => {
+                self.error(e, &path);
+                None
+            }
+        }
+    }
+
+    /// The plans of the fields the selections make on a type, on each object type it may be.
+    /// `page` is the number of records the lists of a connection are estimated to hold, when the
+    /// type is the connection a collection returns.
+    fn plan_selections(
+        &mut self,
+        store: &Store,
+        type_name: &str,
+        selection_sets: &[&'a [Selection]],
+        path: &mut Vec<Value>,
+        page: Option<usize>,
+    ) -> Vec<Value> {
+        let mut planned: Vec<&str> = Vec::new();
+        let mut plans = Vec::new();
+        for concrete in self.concrete_types(type_name) {
+            let mut grouped = Vec::new();
+            for selections in selection_sets {
+                self.collect_fields(concrete, selections, &mut grouped);
+            }
+            for (key, fields) in grouped {
+                if planned.contains(&key) {
+                    continue;
+                }
+                planned.push(key);
+                path.push(json!(key));
+                let parent = self.field.replace(fields[0]);
+                let plan = self.plan_field(store, concrete, &fields, path, page);
+                self.field = parent;
+                path.pop();
+                plans.extend(plan);
+            }
+        }
+        plans
+    }
+
+    /// The plan of a field, or nothing when it is not a field of the type.
+    fn plan_field(
+        &mut self,
+        store: &Store,
+        type_name: &'a str,
+        fields: &[&'a FieldNode],
+        path: &mut Vec<Value>,
+        page: Option<usize>,
+    ) -> Option<Value> {
+        let field = fields[0];
+        let name = field.name.value.as_str();
+        let root = path.len() == 1;
+        let answered = match name {
+            "__typename" => Some(("String!", "typename")),
+            "__schema" if root => Some(("__Schema!", "introspection")),
+            "__type" if root => Some(("__Type", "introspection")),
+            _ => None,
+        };
+        if let Some((field_type, source)) = answered {
+            return Some(json!({
+                "path": path,
+                "type": field_type,
+                "source": source,
+                "index": null,
+                "count": 1,
+                "cost": 1,
+                "fields": [],
+            }));
+        }
+        let definition = self.field_definition(type_name, field, path)?;
+        let named = named_type(&definition.field_type);
+        let list = is_list(&definition.field_type);
+        let estimate = |executor: &Self| {
+            if list {
+                executor.records_of(store, named).count()
+            } else {
+                1
+            }
+        };
+        let mut index = Value::Null;
+        let mut lists = None;
+        let (source, count) = if self.resolvers.get(type_name, name).is_some() {
+            ("resolver", estimate(self))
+        } else if !root {
+            (
+                "record",
+                page.filter(|_| list).unwrap_or_else(|| estimate(self)),
+            )
+        } else if Some(type_name) == self.schema.mutation_type() {
+            ("mutation", 1)
+        } else if Some(type_name) == self.schema.subscription_type() {
+            ("subscription", 1)
+        } else if let Some(entity) = query::collection_of(self.schema, &definition.field_type) {
+            let arguments = Coercion::new(self.schema, self.scalars, &self.variables)
+                .arguments(field, definition)
+                .unwrap_or_default();
+            let narrowed = match arguments.get("filter") {
+                Some(Value::Object(filter)) => query::narrowed(store, entity, filter),
+                _ => None,
+            };
+            let records = match narrowed {
+                Some((field, records)) => {
+                    let unique = index::declared(self.schema)
+                        .into_iter()
+                        .any(|declared| declared == (entity, field, true));
+                    index = json!({ "field": field, "unique": unique });
+                    records.len()
+                }
+                None => store.records(entity).len(),
+            };
+            let first = arguments.get("first").and_then(Value::as_u64);
+            lists = Some(first.map_or(records, |first| records.min(first as usize)));
+            ("collection", 1)
+        } else {
+            ("scan", estimate(self))
+        };
+        let selections = merge_selection_sets(fields);
+        let planned = if selections.is_empty() {
+            Vec::new()
+        } else {
+            self.plan_selections(store, named, &selections, path, lists)
+        };
+        let selected: u64 = planned
+            .iter()
+            .filter_map(|plan| plan["cost"].as_u64())
+            .sum();
+        Some(json!({
+            "path": path,
+            "type": coercion::type_name(&definition.field_type),
+            "source": source,
+            "index": index,
+            "count": count,
+            "cost": 1 + count as u64 * selected,
+            "fields": planned,
+        }))
+    }
+
+    /// The payloads of the response: the first one with the data, then those held back by
+    /// `@defer` and `@stream`.
+    fn payloads(mut self, data: Value) -> Vec<Value> {
+        let pending = self.pending.take().unwrap_or_default();
+        let mut first = self.response(data);
+        if pending.is_empty() {
+            return vec![first];
+        }
+        first["hasNext"] = json!(true);
+        let count = pending.len();
+        let mut payloads = vec![first];
+        for (index, payload) in pending.into_iter().enumerate() {
+            payloads.push(json!({ "incremental": [payload], "hasNext": index + 1 < count }));
+        }
+        payloads
+    }
+
+    /// The response to the operation, with the errors met while executing it.
+    fn response(self, data: Value) -> Value {
+        if self.errors.is_empty() {
+            json!({ "data": data })
+        } else {
+            json!({ "data": data, "errors": self.errors })
+        }
+    }
+
+    /// Raises an error, for the field being executed when it has a path.
+    fn error(&mut self, message: String, path: &[Value]) {
+        let mut error = json!({ "message": message });
+        if let (false, Some(field)) = (path.is_empty(), self.field) {
+            let (source, document) = (self.request.query.as_str(), self.document);
+            let locations = self
+                .locations
+                .get_or_insert_with(|| Locations::new(source, document));
+            if let Some(locations) = locations.of(field) {
+                error["locations"] = locations;
+            }
+        }
+        if !path.is_empty() {
+            error["path"] = json!(path);
+        }
+        self.errors.push(error);
+    }
+}
+
+/// The value of a field whose error was raised: null, or nothing when the field is non-null, its
+/// parent then being null in its place.
+fn failed(field_type: &TypeNode) -> Option<Value> {
+    match field_type {
+        TypeNode::NonNull(_) => None,
+        _ => Some(Value::Null),
+    }
+}
+
+/// The label of a `@defer` or `@stream`, from its arguments.
+fn label(arguments: &Map<String, Value>) -> Option<String> {
+    arguments
+        .get("label")
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+}
+
+fn is_list(field_type: &TypeNode) -> bool {
+    match field_type {
+        TypeNode::NonNull(inner) => is_list(inner),
+        TypeNode::List(_) => true,
+        TypeNode::Named(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        schema { query: Query mutation: Mutation }
+        type Query { users(name: String): [User!]! user(id: ID!): User node(id: ID): Node }
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name: String friends: [User!] address: Address }
+        type Address { city: String }
+    "#;
+
+    const DATA: &str = r#"{
+        "User": [
+            { "id": 1, "name": "alice", "friends": [2], "address": { "city": "Paris" } },
+            { "id": 2, "name": "bob", "friends": [] }
+        ]
+    }"#;
+
+    fn run(query: &str, variables: Value, operation_name: Option<&str>) -> Value {
+        run_on(
+            &RwLock::new(Store::from_json(DATA).unwrap()),
+            query,
+            variables,
+            operation_name,
+        )
+    }
+
+    fn run_on(
+        store: &RwLock<Store>,
+        query: &str,
+        variables: Value,
+        operation_name: Option<&str>,
+    ) -> Value {
+        let mut schema = syntax::parse(SCHEMA).unwrap();
+        mutation::generate(&mut schema);
+        query::generate(&mut schema);
+        let mut request = Request::new(query);
+        request.variables = variables.as_object().cloned();
+        request.operation_name = operation_name.map(String::from);
+        let query = syntax::parse(query).unwrap();
+        execute(
+            &Schema::new(&schema),
+            &Resolvers::default(),
+            &Scalars::default(),
+            store,
+            &query,
+            &request,
+        )
+    }
+
+    #[test]
+    fn it_executes_queries() {
+        assert_eq!(
+            run(
+                "{ users { id name friends { name } address { city } } }",
+                Value::Null,
+                None
+            ),
+            json!({ "data": { "users": [
+                { "id": 1, "name": "alice", "friends": [{ "name": "bob" }], "address": { "city": "Paris" } },
+                { "id": 2, "name": "bob", "friends": [], "address": null },
+            ] } })
+        );
+    }
+
+    #[test]
+    fn it_applies_arguments_and_variables() {
+        assert_eq!(
+            run(
+                "query User($id: ID!) { user(id: $id) { name } bob: users(name: \"bob\") { id } }",
+                json!({ "id": "1" }),
+                None
+            ),
+            json!({ "data": { "user": { "name": "alice" }, "bob": [{ "id": 2 }] } })
+        );
+        assert_eq!(
+            run(
+                "query User($id: ID!) { user(id: $id) { name } }",
+                json!({}),
+                None
+            ),
+            json!({ "errors": [{ "message": "Variable `$id` of required type `ID!` was not provided" }] })
+        );
+    }
+
+    #[test]
+    fn it_follows_fragments_and_directives() {
+        assert_eq!(
+            run(
+                r#"
+                query A($skip: Boolean = true) {
+                    node(id: 2) { __typename ...on User { name } }
+                    user(id: 1) { ...Names id @skip(if: $skip) }
+                }
+                fragment Names on User { name friends { name } }
+                query B { user(id: 1) { id } }
+                "#,
+                Value::Null,
+                Some("A")
+            ),
+            json!({ "data": {
+                "node": { "__typename": "User", "name": "bob" },
+                "user": { "name": "alice", "friends": [{ "name": "bob" }] },
+            } })
+        );
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        assert_eq!(
+            run("{ user(id: 1) { name age } }", Value::Null, None),
+            json!({
+                "data": { "user": { "name": "alice", "age": null } },
+                "errors": [{
+                    "message": "Cannot query field `age` on type `User`",
+                    "locations": [{ "line": 1, "column": 22 }],
+                    "path": ["user", "age"],
+                }],
+            })
+        );
+        assert_eq!(
+            run(
+                "query A { users { id } } query B { users { id } }",
+                Value::Null,
+                None
+            ),
+            json!({ "errors": [{ "message": "The document contains several operations, an operationName is required" }] })
+        );
+    }
+
+    #[test]
+    fn it_runs_mutations() {
+        let store = RwLock::new(Store::from_json(DATA).unwrap());
+        assert_eq!(
+            run_on(
+                &store,
+                r#"
+                mutation Add($name: String) {
+                    carol: createUser(input: { name: $name, friends: [1, 2] }) { id friends { name } }
+                    updateUser(id: 1, input: { address: { city: "Lyon" } }) { name address { city } }
+                    deleteUser(id: 2) { name }
+                    nobody: deleteUser(id: 2) { name }
+                }
+                "#,
+                json!({ "name": "carol" }),
+                None
+            ),
+            json!({ "data": {
+                "carol": { "id": "3", "friends": [{ "name": "alice" }, { "name": "bob" }] },
+                "updateUser": { "name": "alice", "address": { "city": "Lyon" } },
+                "deleteUser": { "name": "bob" },
+                "nobody": null,
+            } })
+        );
+        assert_eq!(
+            run_on(
+                &store,
+                "{ users { name friends { name } } }",
+                Value::Null,
+                None
+            ),
+            json!({
+                "data": { "users": [
+                    { "name": "alice", "friends": null },
+                    { "name": "carol", "friends": null },
+                ] },
+                "errors": [
+                    {
+                        "message": "Cannot return null for non-nullable field `friends`",
+                        "locations": [{ "line": 1, "column": 16 }],
+                        "path": ["users", 0, "friends", 0],
+                    },
+                    {
+                        "message": "Cannot return null for non-nullable field `friends`",
+                        "locations": [{ "line": 1, "column": 16 }],
+                        "path": ["users", 1, "friends", 1],
+                    },
+                ],
+            })
+        );
+        assert_eq!(
+            run_on(
+                &store,
+                "mutation { createUser(input: { name: 1 }) { id } }",
+                Value::Null,
+                None
+            ),
+            json!({
+                "data": { "createUser": null },
+                "errors": [{
+                    "message": "Argument `input` of field `createUser`: Field `name` of input type `UserInput`: Expected a value of type `String`, found 1",
+                    "locations": [{ "line": 1, "column": 12 }],
+                    "path": ["createUser"],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn it_answers_introspection() {
+        assert_eq!(
+            run(
+                "{ __typename __type(name: \"User\") { name } }",
+                Value::Null,
+                None
+            ),
+            json!({ "data": { "__typename": "Query", "__type": { "name": "User" } } })
+        );
+    }
+
+    #[test]
+    fn it_delivers_deferred_fragments_and_streamed_items() {
+        let mut schema = syntax::parse(SCHEMA).unwrap();
+        incremental::generate(&mut schema);
+        query::generate(&mut schema);
+        let store = RwLock::new(Store::from_json(DATA).unwrap());
+        let deliver = |query: &str, incremental: bool| {
+            let document = syntax::parse(query).unwrap();
+            let request = Request::new(query);
+            let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+            let schema = Schema::new(&schema);
+            super::run(
+                &schema,
+                &resolvers,
+                &scalars,
+                &store,
+                &document,
+                &request,
+                incremental,
+            )
+            .0
+        };
+        let query = r#"{
+            users @stream(initialCount: 1, label: "users") {
+                name
+                ... @defer(label: "more") { address { city } friends { name } }
+            }
+        }"#;
+        assert_eq!(
+            deliver(query, true),
+            vec![
+                json!({ "data": { "users": [{ "name": "alice" }] }, "hasNext": true }),
+                json!({ "incremental": [{
+                    "data": { "address": { "city": "Paris" }, "friends": [{ "name": "bob" }] },
+                    "path": ["users", 0],
+                    "label": "more",
+                }], "hasNext": true }),
+                json!({ "incremental": [{
+                    "items": [{ "name": "bob" }],
+                    "path": ["users", 1],
+                    "label": "users",
+                }], "hasNext": true }),
+                json!({ "incremental": [{
+                    "data": { "address": null, "friends": [] },
+                    "path": ["users", 1],
+                    "label": "more",
+                }], "hasNext": false }),
+            ]
+        );
+        assert_eq!(
+            deliver(query, false),
+            vec![json!({ "data": { "users": [
+                { "name": "alice", "address": { "city": "Paris" }, "friends": [{ "name": "bob" }] },
+                { "name": "bob", "address": null, "friends": [] },
+            ] } })]
+        );
+        assert_eq!(
+            deliver(
+                "{ users @stream(initialCount: 2) { name ... @defer(if: false) { id } } }",
+                true
+            ),
+            vec![json!({ "data": { "users": [
+                { "name": "alice", "id": 1 },
+                { "name": "bob", "id": 2 },
+            ] } })]
+        );
+        assert_eq!(
+            deliver("{ users { name @stream } }", true),
+            vec![json!({ "errors": [{
+                "message": "`@stream` can only be used on fields of a list type, `name` is of type `String`"
+            }] })]
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Explains how an operation would be executed, without executing it.
+//!
+//! ```graphql
+//! query Explain($query: String!) {
+//!   __explain(query: $query)
+//! }
+//! ```
+//!
+//! The `__explain` query field returns the [plan] of the operation of `query`, the one named by
+//! `operationName` when it has several, with the given `variables`:
+//!
+//! ```json
+//! {
+//!   "operation": "query",
+//!   "name": null,
+//!   "serial": false,
+//!   "cost": 4,
+//!   "fields": [{
+//!     "path": ["users"],
+//!     "type": "UserConnection!",
+//!     "source": "collection",
+//!     "index": { "field": "email", "unique": true },
+//!     "count": 1,
+//!     "cost": 3,
+//!     "fields": [{ "path": ["users", "nodes"], "type": "[User!]!", "source": "record", ... }]
+//!   }]
+//! }
+//! ```
+//!
+//! The fields are listed in the order they are resolved, each with where its value comes from:
+//! `collection` and `scan` read the records of a type, `mutation` and `subscription` are the
+//! generated operations, `record` is a value of the record it is selected on and `resolver` the
+//! value of a [resolver]. `index` is the index the records of a collection are found with, and
+//! `count` the number of values the field is estimated to return.
+//!
+//! Like the history fields of the [catalog], `__explain` is answered outside the schema, and only
+//! when an operation selects nothing else.
+//!
+//! [plan]: ../executor/fn.explain.html
+//! [resolver]: ../resolver/index.html
+//! [catalog]: ../catalog/index.html
+
+use crate::catalog;
+use crate::executor;
+use crate::resolver::{Context, Resolvers};
+use crate::scalar::Scalars;
+use crate::store::{Record, Store};
+use net::request::Request;
+use serde_json::{Map, Value};
+use std::sync::{Arc, RwLock};
+use syntax::document::Document;
+use syntax::nodes::Operation;
+use syntax::schema::Schema;
+
+const EXPLAIN_SCHEMA: &str = r#"
+type Query {
+  "The plan of an operation of a query"
+  __explain(query: String!, operationName: String, variables: JSON): JSON!
+}
+
+scalar JSON
+"#;
+
+/// Whether the operation a document runs selects nothing but `__explain`.
+pub(crate) fn requested(document: &Document, request: &Request) -> bool {
+    match catalog::root_fields(document, request) {
+        Some((Operation::Query, _, fields)) => {
+            !fields.is_empty() && fields.iter().all(|field| field.name.value == "__explain")
+        }
+        _ => false,
+    }
+}
+
+/// Answers an operation selecting `__explain`, with the plans of the operations against a
+/// schema.
+pub(crate) fn answer(
+    schema: Arc<Document>,
+    resolvers: Arc<Resolvers>,
+    scalars: Arc<Scalars>,
+    store: &RwLock<Store>,
+    document: &Document,
+    request: &Request,
+) -> Value {
+    let explain = syntax::parse(EXPLAIN_SCHEMA).expect("The explain schema is valid");
+    let mut explaining = Resolvers::default();
+    explaining.register(
+        "Query",
+        "__explain",
+        move |_: Option<&Record>, _: &str, arguments: &Map<String, Value>, context: &Context| {
+            let query = arguments["query"].as_str().unwrap_or_default();
+            let explained = syntax::parse(query).map_err(|e| e.to_string())?;
+            let mut request = Request::new(query);
+            request.operation_name = arguments
+                .get("operationName")
+                .and_then(Value::as_str)
+                .map(String::from);
+            request.variables = arguments
+                .get("variables")
+                .and_then(Value::as_object)
+                .cloned();
+            request.identity = context.request.identity.clone();
+            executor::explain(
+                &Schema::new(&schema),
+                &resolvers,
+                &scalars,
+                context.store,
+                &explained,
+                &request,
+            )
+        },
+    );
+    executor::execute(
+        &Schema::new(&explain),
+        &explaining,
+        &Scalars::default(),
+        store,
+        document,
+        request,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use crate::index;
+    use serde_json::json;
+
+    #[test]
+    fn it_explains_operations() {
+        let source = syntax::parse(
+            r#"
+            type User { id: ID! email: String! @index(unique: true) friends: [User!] }
+            "#,
+        )
+        .unwrap();
+        let schema = Catalog::new(source).schema();
+        let mut store = Store::from_json(
+            r#"{ "User": [
+                { "id": 1, "email": "ada@example.com", "friends": [2] },
+                { "id": 2, "email": "alan@example.com" },
+                { "id": 3, "email": "grace@example.com" }
+            ] }"#,
+        )
+        .unwrap();
+        index::build(&mut store, &index::declared(&Schema::new(&schema))).unwrap();
+        let store = RwLock::new(store);
+        let explain = |query: &str, variables: Value| {
+            let mut request = Request::new(query);
+            request.variables = variables.as_object().cloned();
+            let document = syntax::parse(query).unwrap();
+            assert!(requested(&document, &request));
+            answer(
+                schema.clone(),
+                Arc::new(Resolvers::default()),
+                Arc::new(Scalars::default()),
+                &store,
+                &document,
+                &request,
+            )
+        };
+        let query = r#"query Find($email: String) {
+            users(filter: { email: $email }) { nodes { email friends { id } } }
+            all: users(first: 2) { totalCount }
+        }"#;
+        let plan = explain(
+            "query ($query: String!) { __explain(query: $query, variables: { email: \"ada@example.com\" }) }",
+            json!({ "query": query }),
+        );
+        let plan = &plan["data"]["__explain"];
+        assert_eq!(plan["operation"], "query");
+        assert_eq!(plan["name"], "Find");
+        assert_eq!(plan["serial"], false);
+        let users = &plan["fields"][0];
+        assert_eq!(users["source"], "collection");
+        assert_eq!(users["index"], json!({ "field": "email", "unique": true }));
+        let nodes = &users["fields"][0];
+        assert_eq!(nodes["path"], json!(["users", "nodes"]));
+        assert_eq!(nodes["count"], 1);
+        assert_eq!(nodes["fields"][1]["count"], 3);
+        // nodes, email, friends and the id of every user
+        assert_eq!(users["cost"], 1 + (1 + 1 + (1 + 3)));
+        let all = &plan["fields"][1];
+        assert_eq!(
+            (&all["source"], &all["index"]),
+            (&json!("collection"), &Value::Null)
+        );
+        assert_eq!(all["fields"][0]["path"], json!(["all", "totalCount"]));
+        assert_eq!(plan["cost"], users["cost"].as_u64().unwrap() + 2);
+
+        assert!(!requested(
+            &syntax::parse("{ __explain(query: \"{ users }\") users { totalCount } }").unwrap(),
+            &Request::new("")
+        ));
+        assert_eq!(
+            explain("{ __explain(query: \"{ users { name } }\") }", Value::Null)["errors"][0]
+                ["message"],
+            "Cannot query field `name` on type `UserConnection`"
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! The database embedded in a Rust application, answering requests in the process rather than
+//! over the network.
+//!
+//! ```no_run
+//! use database::{Config, Gql};
+//! use serde_json::json;
+//!
+//! let gql = Gql::open(&Config::default()).expect("The database opens");
+//! gql.apply_schema("type User { id: ID! name: String! }")
+//!     .expect("The schema applies");
+//! gql.execute(r#"mutation { createUser(input: { name: "Ada" }) { id } }"#, None);
+//! let response = gql.execute(
+//!     "query Users($first: Int) { users(first: $first) { nodes { name } } }",
+//!     json!({ "first": 10 }).as_object().cloned(),
+//! );
+//! assert_eq!(
+//!     response.data,
+//!     Some(json!({ "users": { "nodes": [{ "name": "Ada" }] } }))
+//! );
+//! ```
+//!
+//! Requests are answered as they are over the network, schema changes and commands included,
+//! but for subscriptions, which need a stream to be answered on. No protocol is listened to, and
+//! the data directory of the config, when it has one, is flushed when the database is dropped.
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::resolver::Resolver;
+use crate::scalar::ScalarCoercion;
+use net::request::Request;
+use serde_json::{Map, Value};
+
+/// A database answering requests in the process.
+pub struct Gql {
+    database: Database,
+}
+
+/// The response to a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    /// The result of the operation, `None` when it could not be executed at all
+    pub data: Option<Value>,
+    /// The errors of the request, and of the fields that could not be answered
+    pub errors: Vec<Value>,
+}
+
+impl Gql {
+    /// Opens the database of a config, reading its schema, records and data directory.
+    pub fn open(config: &Config) -> Result<Gql, String> {
+        Ok(Gql {
+            database: Database::open(config)?,
+        })
+    }
+
+    /// Answers a query with the values of its variables.
+    pub fn execute(&self, query: &str, variables: Option<Map<String, Value>>) -> Response {
+        let mut request = Request::new(query);
+        request.variables = variables;
+        self.execute_request(&request)
+    }
+
+    /// Answers a request, which may name its operation and the identity of its client.
+    pub fn execute_request(&self, request: &Request) -> Response {
+        Response::from(self.database.execute(request))
+    }
+
+    /// Answers a field of a type with a resolver, in place of the one registered before.
+    pub fn register_resolver<R>(&mut self, type_name: &str, field: &str, resolver: R)
+    where
+        R: Resolver + 'static,
+    {
+        self.database.register_resolver(type_name, field, resolver);
+    }
+
+    /// Coerces the values of a scalar with a coercion, in place of the one registered before.
+    pub fn register_scalar<C>(&mut self, name: &str, coercion: C)
+    where
+        C: ScalarCoercion + 'static,
+    {
+        self.database.register_scalar(name, coercion);
+    }
+
+    /// Adds the definitions of a GraphQL document to the schema, returning the new version of the
+    /// schema, or why the document cannot be added.
+    pub fn apply_schema(&self, sdl: &str) -> Result<usize, Vec<String>> {
+        let change = syntax::parse(sdl).map_err(|e| vec![e.to_string()])?;
+        self.database.apply_schema(&change)
+    }
+}
+
+impl Drop for Gql {
+    fn drop(&mut self) {
+        self.database.flush();
+    }
+}
+
+impl Response {
+    /// Whether the request was answered without errors.
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The JSON response of the GraphQL Spec.
+    pub fn to_json(&self) -> Value {
+        let mut response = Map::new();
+        if let Some(data) = &self.data {
+            response.insert("data".into(), data.clone());
+        }
+        if !self.errors.is_empty() {
+            response.insert("errors".into(), Value::Array(self.errors.clone()));
+        }
+        Value::Object(response)
+    }
+}
+
+impl From<Value> for Response {
+    fn from(mut response: Value) -> Response {
+        let errors = match response.get_mut("errors").map(Value::take) {
+            Some(Value::Array(errors)) => errors,
+            _ => Vec::new(),
+        };
+        Response {
+            data: response.get_mut("data").map(Value::take),
+            errors,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::Context;
+    use crate::store::Record;
+    use net::auth::Identity;
+    use serde_json::json;
+
+    #[test]
+    fn it_answers_requests_in_the_process() {
+        let gql = Gql::open(&Config::default()).unwrap();
+        assert_eq!(
+            gql.apply_schema("type User { id: ID! name: String! }"),
+            Ok(1)
+        );
+        assert!(gql.apply_schema("type User { id: ID! }").is_err());
+        assert!(gql.apply_schema("type {").is_err());
+        let created = gql.execute(
+            "mutation Add($name: String!) { createUser(input: { name: $name }) { name } }",
+            json!({ "name": "Ada" }).as_object().cloned(),
+        );
+        assert!(created.is_ok());
+        let response = gql.execute("{ users { nodes { name } } }", None);
+        assert_eq!(
+            response.to_json(),
+            json!({ "data": { "users": { "nodes": [{ "name": "Ada" }] } } })
+        );
+        let response = gql.execute("{ users { names } }", None);
+        assert_eq!(response.data, Some(json!({ "users": { "names": null } })));
+        assert_eq!(response.errors.len(), 1);
+    }
+
+    #[test]
+    fn it_answers_fields_with_the_resolvers_it_registers() {
+        let mut gql = Gql::open(&Config::default()).unwrap();
+        gql.apply_schema("type User { id: ID! name: String! friends: Int }")
+            .unwrap();
+        gql.register_resolver(
+            "User",
+            "friends",
+            |_: Option<&Record>, _: &str, _: &Map<String, Value>, context: &Context| {
+                Ok(json!(context.records("User").len() - 1))
+            },
+        );
+        for name in ["Ada", "Alan"] {
+            let mut request = Request::new(
+                "mutation($name: String!) { createUser(input: { name: $name }) { id } }",
+            );
+            request.variables = json!({ "name": name }).as_object().cloned();
+            assert!(gql.execute_request(&request).is_ok());
+        }
+        assert_eq!(
+            gql.execute("{ users { nodes { name friends } } }", None)
+                .to_json(),
+            json!({ "data": { "users": { "nodes": [
+                { "name": "Ada", "friends": 1 },
+                { "name": "Alan", "friends": 1 },
+            ] } } })
+        );
+    }
+
+    #[test]
+    fn it_coerces_the_scalars_it_registers() {
+        /// Email addresses, kept in lowercase.
+        struct Email;
+
+        impl ScalarCoercion for Email {
+            fn parse(&self, value: &Value) -> Option<Value> {
+                let email = value.as_str().filter(|email| email.contains('@'))?;
+                Some(json!(email.to_lowercase()))
+            }
+        }
+
+        let mut gql = Gql::open(&Config::default()).unwrap();
+        gql.apply_schema("scalar Email type User { id: ID! email: Email! }")
+            .unwrap();
+        gql.register_scalar("Email", Email);
+        let create = "mutation($email: Email!) { createUser(input: { email: $email }) { email } }";
+        let created = gql.execute(
+            create,
+            json!({ "email": "Ada@Example.com" }).as_object().cloned(),
+        );
+        assert_eq!(
+            created.to_json(),
+            json!({ "data": { "createUser": { "email": "ada@example.com" } } })
+        );
+        let refused = gql.execute(
+            r#"mutation { createUser(input: { email: "ada" }) { id } }"#,
+            None,
+        );
+        assert_eq!(refused.data, Some(json!({ "createUser": null })));
+        assert_eq!(
+            refused.errors[0]["message"],
+            "Argument `input` of field `createUser`: Field `email` of input type `UserInput`: \
+             Expected a value of type `Email`, found \"ada\""
+        );
+        assert_eq!(
+            gql.execute("{ users { nodes { email } } }", None).to_json(),
+            json!({ "data": { "users": { "nodes": [{ "email": "ada@example.com" }] } } })
+        );
+    }
+
+    #[test]
+    fn it_only_lets_admins_change_the_schema() {
+        let gql = Gql::open(&Config::default()).unwrap();
+        let response = gql.execute("type Bar { id: ID! }", None);
+        assert_eq!(
+            response.to_json(),
+            json!({ "errors": [{ "message": "Changing the schema requires the role `ADMIN`" }] })
+        );
+        assert!(!gql.execute("{ bars { totalCount } }", None).is_ok());
+
+        let mut request = Request::new("type Bar { id: ID! }");
+        request.identity = Some(Identity::with_roles("alan", &["ADMIN"]));
+        assert_eq!(
+            gql.execute_request(&request).to_json(),
+            json!({ "data": { "version": 1 } })
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! The graphs a server holds, each an independent [database] with its own schema and records.
+//!
+//! Besides the default graph, a server holds the graphs its config names, so that one deployment
+//! can serve a staging graph and a graph per team. A named graph starts from the default schema,
+//! and its schema changes and records are kept in `graphs/<name>` under the data directory, apart
+//! from those of the other graphs. The transports route each request to the graph the client
+//! named, the default one when it named none. Only the default graph is replicated.
+//!
+//! [database]: ../database/index.html
+
+use crate::config::Config;
+use crate::database::Database;
+use crate::executor::request_error;
+use net::db::{CacheMetrics, DbCommand};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+pub struct Graphs {
+    default: Database,
+    named: HashMap<String, Database>,
+    /// The number of requests each graph holds before the others wait for it
+    capacity: usize,
+    cache: Arc<CacheMetrics>,
+}
+
+impl Graphs {
+    /// The graphs of a config, panicking when the files of one cannot be read.
+    pub fn new(config: &Config) -> Self {
+        Graphs::open(config).unwrap_or_else(|e| panic!("Bad Value: {}", e))
+    }
+
+    /// The graphs of a config, or why the files of one cannot be read.
+    pub fn open(config: &Config) -> Result<Self, String> {
+        let cache = Arc::new(CacheMetrics::default());
+        let default = Database::open_sharing(config, cache.clone())?;
+        let mut named = HashMap::new();
+        for name in &config.graphs {
+            let graph = Config {
+                schema: None,
+                data: None,
+                replication_port: None,
+                follow: None,
+                follow_token: None,
+                data_dir: config.data_dir.as_ref().map(|dir| {
+                    Path::new(dir)
+                        .join("graphs")
+                        .join(name)
+                        .to_string_lossy()
+                        .into_owned()
+                }),
+                ..config.clone()
+            };
+            let database = Database::open_sharing(&graph, cache.clone())
+                .map_err(|e| format!("In the graph {}: {}", name, e))?;
+            named.insert(name.clone(), database);
+        }
+        Ok(Graphs {
+            default,
+            named,
+            capacity: config.queue_capacity,
+            cache,
+        })
+    }
+
+    /// The counts of the hits, misses and invalidations of the result caches of every graph.
+    pub fn cache_metrics(&self) -> Arc<CacheMetrics> {
+        self.cache.clone()
+    }
+
+    /// Hands each request to its graph until every sender is dropped, then flushes them all.
+    pub async fn run(self, mut command: Receiver<DbCommand>) {
+        let mut running = JoinSet::new();
+        let capacity = self.capacity;
+        let mut start = |mut database: Database| -> Sender<DbCommand> {
+            let (sender, receiver) = mpsc::channel(capacity);
+            running.spawn(async move { database.run(receiver).await });
+            sender
+        };
+        let default = start(self.default);
+        let named: HashMap<String, Sender<DbCommand>> = self
+            .named
+            .into_iter()
+            .map(|(name, database)| (name, start(database)))
+            .collect();
+        info!(graphs = named.len() + 1, "Graphs started");
+        while let Some((request, response)) = command.recv().await {
+            let graph = match request.graph.as_deref() {
+                None => &default,
+                Some(name) => match named.get(name) {
+                    Some(graph) => graph,
+                    None => {
+                        let error = request_error(format!("Unknown graph `{}`", name));
+                        response.finish(error.to_string()).await;
+                        continue;
+                    }
+                },
+            };
+            if graph.send((request, response)).await.is_err() {
+                warn!("A graph stopped before the server");
+            }
+        }
+        drop((default, named));
+        while running.join_next().await.is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use net::auth::Identity;
+    use net::db;
+    use net::request::Request;
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn it_keeps_the_graphs_apart() {
+        let config = Config::builder().with_graphs(&["staging"]).build();
+        let graphs = Graphs::open(&config).unwrap();
+        let (sender, receiver) = db::channel(8);
+        let running = tokio::spawn(graphs.run(receiver));
+        let send = |query: &str, graph: Option<&str>| {
+            let request = Request {
+                graph: graph.map(String::from),
+                identity: Some(Identity::with_roles("alan", &["ADMIN"])),
+                ..Request::new(query)
+            };
+            let sender = sender.clone();
+            async move {
+                let reply = sender.query(request).await.unwrap();
+                serde_json::from_str::<Value>(&reply).unwrap()
+            }
+        };
+        send("type User { id: ID! name: String! }", Some("staging")).await;
+        send(
+            "mutation { createUser(input: { name: \"Ada\" }) { id } }",
+            Some("staging"),
+        )
+        .await;
+        assert_eq!(
+            send("{ users { nodes { name } } }", Some("staging")).await,
+            json!({ "data": { "users": { "nodes": [{ "name": "Ada" }] } } })
+        );
+        let default = send("{ users { nodes { name } } }", None).await;
+        assert_eq!(
+            default["errors"][0]["message"],
+            "The schema does not define a query type"
+        );
+        assert_eq!(
+            send("{ users { nodes { name } } }", Some("production")).await,
+            json!({ "errors": [{ "message": "Unknown graph `production`" }] })
+        );
+        drop(sender);
+        running.await.unwrap();
+    }
+}
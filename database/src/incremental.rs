@@ -0,0 +1,242 @@
+//! The `@defer` and `@stream` directives, which deliver parts of a result after the rest.
+//!
+//! ```graphql
+//! query {
+//!   user(id: 1) {
+//!     name
+//!     ... @defer(label: "friends") { friends { name } }
+//!     posts @stream(initialCount: 2) { title }
+//!   }
+//! }
+//! ```
+//!
+//! A fragment marked `@defer` is answered in a payload of its own, after the first one, and the
+//! items of a list field marked `@stream` past the first `initialCount` in a payload each. Every
+//! payload says with `hasNext` whether more follow. The directives only apply when the responses
+//! are streamed, as they are over WebSocket, and are otherwise ignored, the whole result being
+//! sent at once.
+
+use crate::coercion;
+use std::collections::HashSet;
+use syntax::document::{Document, OperationWithFragments};
+use syntax::nodes::*;
+use syntax::schema::{named_type, Schema};
+
+const DIRECTIVES: &str = r#"
+"Delivers the fragment after the rest of the result."
+directive @defer(if: Boolean! = true, label: String) on FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Delivers the items of the list past the first `initialCount` after the rest of the result."
+directive @stream(if: Boolean! = true, label: String, initialCount: Int! = 0) on FIELD
+"#;
+
+/// Declares the `@defer` and `@stream` directives in a schema, unless they already are.
+pub(crate) fn generate(document: &mut Document) {
+    let declared = Schema::new(document)
+        .directives()
+        .any(|directive| matches!(directive.name.value.as_str(), "defer" | "stream"));
+    if !declared {
+        let generated = syntax::parse(DIRECTIVES).expect("The incremental directives are valid");
+        document.definitions.extend(generated.definitions);
+    }
+}
+
+/// Checks where an operation uses `@defer` and `@stream`: `@defer` on fragments, `@stream` on
+/// fields of a list type, neither on the root fields of a mutation or a subscription, and each
+/// with a label of its own.
+pub(crate) fn validate(schema: &Schema, operation: &OperationWithFragments) -> Vec<String> {
+    let (root, kind) = match operation.operation.operation() {
+        Operation::Query => (schema.query_type(), None),
+        Operation::Mutation => (schema.mutation_type(), Some("mutation")),
+        Operation::Subscription => (schema.subscription_type(), Some("subscription")),
+    };
+    let mut placement = Placement {
+        schema,
+        operation,
+        labels: HashSet::new(),
+        visited: HashSet::new(),
+        errors: Vec::new(),
+    };
+    if let Some(root) = root {
+        let selections = &operation.operation.definition().selections;
+        placement.selections(root, selections, kind);
+    }
+    placement.errors
+}
+
+struct Placement<'a, 'o> {
+    schema: &'a Schema<'a>,
+    operation: &'o OperationWithFragments<'o>,
+    labels: HashSet<&'o str>,
+    /// The fragments already checked
+    visited: HashSet<&'o str>,
+    errors: Vec<String>,
+}
+
+impl<'o> Placement<'_, 'o> {
+    /// Checks the selections on a type, `root` being the kind of operation when they are those
+    /// of the root type of a mutation or a subscription.
+    fn selections(&mut self, type_name: &str, selections: &'o [Selection], root: Option<&str>) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => self.field(type_name, field, root),
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    self.fragment(&inline.directives, root);
+                    let condition = inline.node_type.as_ref().map(|t| t.name.value.as_str());
+                    let condition = condition.unwrap_or(type_name);
+                    self.selections(condition, &inline.selections, root);
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    self.fragment(&spread.directives, root);
+                    let name = spread.name.value.as_str();
+                    let fragment = self
+                        .operation
+                        .fragments
+                        .iter()
+                        .find(|fragment| fragment.name.value == name);
+                    if let Some(fragment) = fragment {
+                        if self.visited.insert(name) {
+                            let condition = fragment.node_type.name.value.as_str();
+                            self.selections(condition, &fragment.selections, root);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn field(&mut self, type_name: &str, field: &'o FieldNode, root: Option<&str>) {
+        let name = field.name.value.as_str();
+        let definition = self.schema.field(type_name, name);
+        if find(&field.directives, "defer").is_some() {
+            self.errors.push(format!(
+                "`@defer` cannot be used on the field `{}`, only on fragments",
+                name
+            ));
+        }
+        if let Some(stream) = find(&field.directives, "stream") {
+            if let Some(kind) = root {
+                self.errors.push(format!(
+                    "`@stream` cannot be used on the root fields of a {}",
+                    kind
+                ));
+            }
+            if let Some(definition) = definition {
+                if !is_list(&definition.field_type) {
+                    self.errors.push(format!(
+                        "`@stream` can only be used on fields of a list type, `{}` is of type `{}`",
+                        name,
+                        coercion::type_name(&definition.field_type)
+                    ));
+                }
+            }
+            self.label(stream);
+        }
+        if let (Some(definition), Some(selections)) = (definition, &field.selections) {
+            let field_type = named_type(&definition.field_type);
+            self.selections(field_type, selections, None);
+        }
+    }
+
+    fn fragment(&mut self, directives: &'o Option<Directives>, root: Option<&str>) {
+        if find(directives, "stream").is_some() {
+            self.errors.push(
+                "`@stream` cannot be used on fragments, only on fields of a list type".into(),
+            );
+        }
+        if let Some(defer) = find(directives, "defer") {
+            if let Some(kind) = root {
+                self.errors.push(format!(
+                    "`@defer` cannot be used on the root fields of a {}",
+                    kind
+                ));
+            }
+            self.label(defer);
+        }
+    }
+
+    fn label(&mut self, directive: &'o DirectiveNode) {
+        let label = directive
+            .arguments
+            .iter()
+            .flatten()
+            .find(|argument| argument.name.value == "label");
+        match label.map(|argument| &argument.value) {
+            Some(ValueNode::Str(label)) if !self.labels.insert(&label.value) => {
+                self.errors.push(format!(
+                    "The label `{}` is used by more than one `@defer` or `@stream`",
+                    label.value
+                ))
+            }
+            Some(ValueNode::Variable(_)) => self.errors.push(format!(
+                "The label of `@{}` must be a string, not a variable",
+                directive.name.value
+            )),
+            _ => {}
+        }
+    }
+}
+
+fn find<'o>(directives: &'o Option<Directives>, name: &str) -> Option<&'o DirectiveNode> {
+    directives
+        .iter()
+        .flatten()
+        .find(|directive| directive.name.value == name)
+}
+
+fn is_list(field_type: &TypeNode) -> bool {
+    match field_type {
+        TypeNode::NonNull(inner) => is_list(inner),
+        TypeNode::List(_) => true,
+        TypeNode::Named(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_validates_the_placement_of_the_directives() {
+        let schema = syntax::parse(
+            r#"
+            schema { query: Query mutation: Mutation }
+            type Query { users: [User!]! me: User }
+            type Mutation { addUser(name: String!): User }
+            type User { name: String! friends: [User!]! }
+            "#,
+        )
+        .unwrap();
+        let schema = Schema::new(&schema);
+        let validate = |query: &str| {
+            let document = syntax::parse(query).unwrap();
+            let operations = document.operations();
+            validate(&schema, &operations[0])
+        };
+        assert!(validate(
+            r#"{ users @stream(initialCount: 1) { name } ... @defer(label: "me") { me { ...F } } }
+            fragment F on User { friends @stream(label: "friends") { name } }"#
+        )
+        .is_empty());
+        assert_eq!(
+            validate(
+                r#"{ me @defer { name @stream ... @stream { name } } users @stream(label: $l) { name } }"#
+            ),
+            vec![
+                "`@defer` cannot be used on the field `me`, only on fragments",
+                "`@stream` can only be used on fields of a list type, `name` is of type `String!`",
+                "`@stream` cannot be used on fragments, only on fields of a list type",
+                "The label of `@stream` must be a string, not a variable",
+            ]
+        );
+        assert_eq!(
+            validate(
+                r#"mutation { ... @defer(label: "a") { addUser(name: "Ada") { ... @defer(label: "a") { name } } } }"#
+            ),
+            vec![
+                "`@defer` cannot be used on the root fields of a mutation",
+                "The label `a` is used by more than one `@defer` or `@stream`",
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! Secondary indexes over the fields of records, declared in the schema with `@index`.
+//!
+//! ```graphql
+//! type User {
+//!   id: ID!
+//!   email: String! @index(unique: true)
+//!   team: String @index
+//! }
+//! ```
+//!
+//! An index maps every value of a field to the records holding it, so that `filter` arguments on
+//! that field read those records instead of scanning every record of the type. A unique index
+//! also turns away a record holding a value another record of its type holds. Null values are
+//! not indexed.
+
+use crate::store::Store;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use syntax::document::Document;
+use syntax::nodes::*;
+use syntax::schema::Schema;
+
+const INDEX_DIRECTIVE: &str = "directive @index(unique: Boolean = false) on FIELD_DEFINITION\n";
+
+/// Declares the `@index` directive in a schema, unless it already is.
+pub(crate) fn generate(document: &mut Document) {
+    let declared = Schema::new(document)
+        .directives()
+        .any(|directive| directive.name.value == "index");
+    if !declared {
+        let generated = syntax::parse(INDEX_DIRECTIVE).expect("The index directive is valid");
+        document.definitions.extend(generated.definitions);
+    }
+}
+
+/// The fields of a schema with an `@index`, as their type, name and whether the index is unique.
+pub(crate) fn declared<'a>(schema: &Schema<'a>) -> Vec<(&'a str, &'a str, bool)> {
+    let mut declared = Vec::new();
+    for definition in schema.types() {
+        let type_name = definition.name();
+        for field in schema.fields(type_name) {
+            let index = field
+                .directives
+                .iter()
+                .flatten()
+                .find(|directive| directive.name.value == "index");
+            if let Some(index) = index {
+                let unique = index.arguments.iter().flatten().any(|argument| {
+                    argument.name.value == "unique"
+                        && matches!(
+                            argument.value,
+                            ValueNode::Bool(BooleanValueNode { value: true })
+                        )
+                });
+                declared.push((type_name, field.name.as_str(), unique));
+            }
+        }
+    }
+    declared
+}
+
+/// Indexes the records of a store on the given fields. Fails when a unique index holds the same
+/// value twice, leaving the store with none of the given indexes.
+pub(crate) fn build(store: &mut Store, indexes: &[(&str, &str, bool)]) -> Result<(), String> {
+    for (built, (type_name, field, unique)) in indexes.iter().enumerate() {
+        if let Err(e) = store.index(type_name, field, *unique) {
+            for (type_name, field, _) in &indexes[..built] {
+                store.unindex(type_name, field);
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+/// The positions of the records of a type, by the value of one of their fields.
+#[derive(Debug)]
+pub(crate) struct Index {
+    unique: bool,
+    entries: BTreeMap<String, Vec<usize>>,
+}
+
+impl Index {
+    pub fn new(unique: bool) -> Self {
+        Index {
+            unique,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    pub fn is_unique(&self) -> bool {
+        self.unique
+    }
+
+    /// The positions of the records whose field may hold a value. Values GraphQL compares as
+    /// equal, the `ID` `"1"` and `1`, share an entry, as may a few that are not.
+    pub fn get(&self, value: &Value) -> &[usize] {
+        key(value)
+            .and_then(|key| self.entries.get(&key))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn insert(&mut self, value: &Value, position: usize) {
+        if let Some(key) = key(value) {
+            self.entries.entry(key).or_default().push(position);
+        }
+    }
+
+    pub fn remove(&mut self, value: &Value, position: usize) {
+        if let Some(key) = key(value) {
+            if let Some(positions) = self.entries.get_mut(&key) {
+                positions.retain(|entry| *entry != position);
+                if positions.is_empty() {
+                    self.entries.remove(&key);
+                }
+            }
+        }
+    }
+
+    /// Moves the records after a removed one back by a position.
+    pub fn shift(&mut self, removed: usize) {
+        for positions in self.entries.values_mut() {
+            for position in positions.iter_mut().filter(|position| **position > removed) {
+                *position -= 1;
+            }
+        }
+    }
+}
+
+fn key(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_finds_declared_indexes() {
+        let mut document = syntax::parse(
+            r#"
+            type User { id: ID! email: String! @index(unique: true) team: String @index }
+            "#,
+        )
+        .unwrap();
+        generate(&mut document);
+        generate(&mut document);
+        let schema = Schema::new(&document);
+        let indexes = schema
+            .directives()
+            .filter(|directive| directive.name.value == "index");
+        assert_eq!(indexes.count(), 1);
+        assert_eq!(
+            declared(&schema),
+            vec![("User", "email", true), ("User", "team", false)]
+        );
+    }
+
+    #[test]
+    fn it_maps_values_to_positions() {
+        let mut index = Index::new(false);
+        index.insert(&json!("a"), 0);
+        index.insert(&json!(1), 1);
+        index.insert(&json!("a"), 2);
+        index.insert(&Value::Null, 3);
+        assert_eq!(index.get(&json!("a")), &[0, 2]);
+        assert_eq!(index.get(&json!("1")), &[1]);
+        assert!(index.get(&Value::Null).is_empty());
+        index.remove(&json!("a"), 0);
+        index.shift(0);
+        assert_eq!(index.get(&json!("a")), &[1]);
+        assert_eq!(index.get(&json!(1)), &[0]);
+    }
+}
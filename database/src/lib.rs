@@ -0,0 +1,39 @@
+//! A GraphQL native database, served over the network by the `database` binary or embedded in a
+//! Rust application with [`Gql`].
+//!
+//! [`Gql`]: struct.Gql.html
+
+mod authorization;
+mod catalog;
+mod coercion;
+mod config;
+mod database;
+mod executor;
+mod explain;
+mod gql;
+mod graphs;
+mod incremental;
+mod index;
+mod listener;
+mod locations;
+pub mod logging;
+mod mutation;
+mod query;
+mod replication;
+mod resolver;
+mod result_cache;
+mod scalar;
+mod schema_files;
+mod store;
+mod subscription;
+mod wal;
+
+pub use crate::config::{Config, ConfigBuilder};
+pub use crate::database::Database;
+pub use gql::{Gql, Response};
+pub use graphs::Graphs;
+pub use listener::listen;
+pub use resolver::{Context, Resolver, Resolvers};
+pub use scalar::{ScalarCoercion, Scalars};
+pub use store::Record;
+pub use wal::Fsync;
@@ -1,52 +1,144 @@
 use crate::config::Config;
-use crate::database::Database;
+use crate::graphs::Graphs;
 use futures::future;
-use log::info;
-use net::handlers;
+use net::auth::{Anonymous, SharedAuthenticator, Tokens};
+use net::shutdown::{self, Shutdown, Trigger};
+use net::{db, handlers};
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::runtime::Builder;
-use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-pub(crate) fn listen(
-    mut database: Database,
-    config: &Config,
-) -> Result<(), Box<dyn std::error::Error>> {
+/// Serves the graphs over the protocols of the config, until the process is told to stop.
+pub fn listen(graphs: Graphs, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
     let runtime = Builder::new_multi_thread()
         .worker_threads(config.num_threads)
         .thread_name("gql-worker")
-        .enable_io()
+        .enable_all()
         .build()
         .expect("Unable to create runtime");
 
     let mut sockets: Vec<JoinHandle<Result<(), std::io::Error>>> = Vec::new();
 
-    let (db_command, db_receiver) = mpsc::channel::<(String, oneshot::Sender<String>)>(64);
-    let _handle = runtime.handle().spawn(async move {
-        database.run(db_receiver).await;
+    let (db_command, db_receiver) = db::channel(config.queue_capacity);
+    let db_command = db_command.with_cache_metrics(graphs.cache_metrics());
+    let database_handle = runtime.handle().spawn(async move {
+        graphs.run(db_receiver).await;
     });
+    let (trigger, shutdown) = shutdown::channel();
+    runtime.handle().spawn(wait_for_signal(trigger));
+    let authenticator = authenticator(config).unwrap_or_else(|e| panic!("Bad Value: {}", e));
 
     for protocol in &config.protocols {
-        info!("setting up protocol: {}", protocol);
+        info!(%protocol, "Setting up protocol");
         match protocol.as_str() {
             "tcp" => {
                 let sender = db_command.clone();
                 let handle = runtime.handle();
-                let join_handle =
-                    handle.spawn(async move { handlers::handle_tcp(9874, sender).await });
+                let limits = config.limits;
+                let authenticator = authenticator.clone();
+                let shutdown = shutdown.clone();
+                let join_handle = handle.spawn(async move {
+                    handlers::handle_tcp(9874, sender, limits, authenticator, shutdown).await
+                });
                 sockets.push(join_handle);
             }
-            _ => println!("Protocol not supported: {}", protocol),
+            "http" => {
+                let sender = db_command.clone();
+                let handle = runtime.handle();
+                let authenticator = authenticator.clone();
+                let shutdown = shutdown.clone();
+                let join_handle = handle.spawn(async move {
+                    handlers::handle_http(9875, sender, authenticator, shutdown).await
+                });
+                sockets.push(join_handle);
+            }
+            "ws" => {
+                let sender = db_command.clone();
+                let handle = runtime.handle();
+                let authenticator = authenticator.clone();
+                let shutdown = shutdown.clone();
+                let join_handle = handle.spawn(async move {
+                    handlers::handle_ws(9876, sender, authenticator, shutdown).await
+                });
+                sockets.push(join_handle);
+            }
+            _ => warn!(%protocol, "Protocol not supported"),
         }
     }
 
-    info!("joining");
+    // The database stops once every handler has dropped its sender
+    drop(db_command);
 
-    runtime.block_on(async {
-        let results = future::try_join_all(sockets).await;
-        info!("Results from blocking: {:?}", results);
+    let grace_period = config.grace_period;
+    runtime.block_on(async move {
+        let stopped = async {
+            for result in future::join_all(sockets).await {
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => warn!(error = %e, "A protocol handler failed"),
+                    Err(e) => warn!(error = %e, "A protocol handler panicked"),
+                }
+            }
+            let _ = database_handle.await;
+        };
+        tokio::select! {
+            _ = stopped => info!("Shut down cleanly"),
+            _ = grace_period_elapsed(shutdown, grace_period) => {
+                warn!("Grace period elapsed, dropping the requests left");
+            }
+        }
     });
-    info!("Ending...");
-    runtime.shutdown_timeout(Duration::from_secs(300));
+    runtime.shutdown_timeout(Duration::from_secs(1));
     Ok(())
 }
+
+/// Lets in the clients holding one of the configured tokens, or everyone when none are, or tells
+/// why the tokens cannot be read.
+pub(crate) fn authenticator(config: &Config) -> Result<SharedAuthenticator, String> {
+    match &config.auth_tokens {
+        Some(path) => {
+            let tokens = std::fs::read_to_string(path)
+                .map_err(|e| format!("Unable to read the authentication tokens: {}", e))?;
+            let tokens = Tokens::from_json(&tokens).map_err(|e| {
+                format!(
+                    "Authentication tokens must be a JSON map of principals: {}",
+                    e
+                )
+            })?;
+            Ok(Arc::new(tokens))
+        }
+        None => Ok(Arc::new(Anonymous)),
+    }
+}
+
+/// Starts the shutdown on SIGINT or SIGTERM.
+async fn wait_for_signal(trigger: Trigger) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = terminate.recv() => {},
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+    info!("Shutting down");
+    trigger.trigger();
+}
+
+async fn grace_period_elapsed(mut shutdown: Shutdown, grace_period: Duration) {
+    shutdown.wait().await;
+    tokio::time::sleep(grace_period).await;
+}
@@ -0,0 +1,246 @@
+//! Where the fields of a query are written, for the `locations` of the errors raised for them.
+//!
+//! The nodes of a parsed document do not keep their position in the source, so the source is
+//! lexed again and its fields are found in the order they are written, which is the order the
+//! parser builds them in. Their lines and columns are counted from their offsets. When the two
+//! do not line up, as for documents that also hold type system definitions, no field has a
+//! location.
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use syntax::document::Document;
+use syntax::lexer;
+use syntax::line_index::LineIndex;
+use syntax::nodes::*;
+use syntax::token::{Location, Token, TokenKind};
+
+pub(crate) struct Locations {
+    /// The location of every field, by the address of its node
+    fields: HashMap<usize, Location>,
+}
+
+impl Locations {
+    /// Finds the fields of a document in its source.
+    pub fn new(source: &str, document: &Document) -> Locations {
+        let mut nodes = Vec::new();
+        for definition in &document.definitions {
+            match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    field_nodes(&operation.definition().selections, &mut nodes)
+                }
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    field_nodes(&fragment.selections, &mut nodes)
+                }
+                DefinitionNode::TypeSystem(_) | DefinitionNode::Extension(_) => {}
+            }
+        }
+        let found = lexer::tokenize(source).ok().and_then(|tokens| {
+            let mut scan = Scan {
+                tokens: tokens
+                    .into_iter()
+                    .filter(|token| {
+                        !matches!(
+                            token.kind,
+                            TokenKind::Start | TokenKind::End | TokenKind::Comment
+                        )
+                    })
+                    .collect(),
+                next: 0,
+                fields: Vec::new(),
+            };
+            scan.document().map(|_| scan.fields)
+        });
+        let index = LineIndex::new(source);
+        let found = found.and_then(|found| {
+            found
+                .iter()
+                .map(|location| index.location(location.absolute_position))
+                .collect::<Option<Vec<Location>>>()
+        });
+        let fields = match found {
+            Some(found) if found.len() == nodes.len() => nodes.into_iter().zip(found).collect(),
+            _ => HashMap::new(),
+        };
+        Locations { fields }
+    }
+
+    /// The `locations` of an error raised for a field, as the GraphQL Spec writes them.
+    pub fn of(&self, field: &FieldNode) -> Option<Value> {
+        let location = self.fields.get(&(field as *const FieldNode as usize))?;
+        Some(json!([{ "line": location.line, "column": location.column }]))
+    }
+}
+
+/// The addresses of the field nodes of selections, in the order they are written.
+fn field_nodes(selections: &[Selection], nodes: &mut Vec<usize>) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => {
+                nodes.push(field as *const FieldNode as usize);
+                field_nodes(field.selections.as_deref().unwrap_or_default(), nodes);
+            }
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                field_nodes(&inline.selections, nodes)
+            }
+            Selection::Fragment(FragmentSpread::Node(_)) => {}
+        }
+    }
+}
+
+/// Reads the tokens of an executable document, noting where its fields start. Every step
+/// returns `None` when the tokens are not those of an executable document.
+struct Scan<'t> {
+    tokens: Vec<Token<'t>>,
+    next: usize,
+    fields: Vec<Location>,
+}
+
+impl<'t> Scan<'t> {
+    fn document(&mut self) -> Option<()> {
+        while let Some(token) = self.peek() {
+            match (token.kind, token.text) {
+                // The descriptions of operations
+                (TokenKind::Str, _) | (TokenKind::BlockStr, _) => self.next += 1,
+                (TokenKind::OpenBrace, _) => self.selection_set()?,
+                (TokenKind::Name, "fragment") => {
+                    self.next += 4; // fragment Name on Type
+                    self.directives();
+                    self.selection_set()?;
+                }
+                (TokenKind::Name, "query")
+                | (TokenKind::Name, "mutation")
+                | (TokenKind::Name, "subscription") => {
+                    self.next += 1;
+                    if self.peek_kind() == Some(TokenKind::Name) {
+                        self.next += 1;
+                    }
+                    self.parenthesized()?;
+                    self.directives();
+                    self.selection_set()?;
+                }
+                _ => return None,
+            }
+        }
+        Some(())
+    }
+
+    fn selection_set(&mut self) -> Option<()> {
+        if self.advance()?.kind != TokenKind::OpenBrace {
+            return None;
+        }
+        loop {
+            let token = self.advance()?;
+            match token.kind {
+                TokenKind::CloseBrace => return Some(()),
+                TokenKind::Spread => match self.peek()? {
+                    next if next.kind == TokenKind::Name && next.text == "on" => {
+                        self.next += 2;
+                        self.directives();
+                        self.selection_set()?;
+                    }
+                    next if next.kind == TokenKind::Name => {
+                        self.next += 1;
+                        self.directives();
+                    }
+                    _ => {
+                        self.directives();
+                        self.selection_set()?;
+                    }
+                },
+                TokenKind::Name => {
+                    self.fields.push(token.location());
+                    if self.peek_kind() == Some(TokenKind::Colon) {
+                        self.next += 2;
+                    }
+                    self.parenthesized()?;
+                    self.directives();
+                    if self.peek_kind() == Some(TokenKind::OpenBrace) {
+                        self.selection_set()?;
+                    }
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn directives(&mut self) {
+        while self.peek_kind() == Some(TokenKind::At) {
+            self.next += 2;
+            if self.parenthesized().is_none() {
+                return;
+            }
+        }
+    }
+
+    /// Skips the arguments or variable definitions that follow, if any.
+    fn parenthesized(&mut self) -> Option<()> {
+        if self.peek_kind() != Some(TokenKind::OpenParen) {
+            return Some(());
+        }
+        let mut depth = 0;
+        loop {
+            match self.advance()?.kind {
+                TokenKind::OpenParen => depth += 1,
+                TokenKind::CloseParen if depth == 1 => return Some(()),
+                TokenKind::CloseParen => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn peek(&self) -> Option<Token<'t>> {
+        self.tokens.get(self.next).copied()
+    }
+
+    fn peek_kind(&self) -> Option<TokenKind> {
+        self.peek().map(|token| token.kind)
+    }
+
+    fn advance(&mut self) -> Option<Token<'t>> {
+        self.next += 1;
+        self.tokens.get(self.next - 1).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_fields_in_the_source() {
+        let source = r#"
+query Users($id: ID = 1) {
+  users(filter: { name: "a)" }) { id ...Friends ... on User @skip(if: false) { email } }
+  first: user(id: $id) { id }
+}
+# fragment Ignored on User { name }
+fragment Friends on User { friends { id } }
+"#;
+        let document = syntax::parse(source).unwrap();
+        let locations = Locations::new(source, &document);
+        let mut found: Vec<(usize, usize)> = locations
+            .fields
+            .values()
+            .map(|location| (location.line, location.column))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![(3, 3), (3, 35), (3, 80), (4, 3), (4, 26), (7, 28), (7, 38)]
+        );
+        let operations = document.operations();
+        match &operations[0].operation.definition().selections[1] {
+            Selection::Field(first) => assert_eq!(
+                locations.of(first),
+                Some(json!([{ "line": 4, "column": 3 }]))
+            ),
+            _ => unreachable!(),
+        }
+        assert_eq!(locations.of(&FieldNode::from("first")), None);
+
+        let document = syntax::parse("type Query { a: Int } { a }").unwrap();
+        assert!(Locations::new("type Query { a: Int } { a }", &document)
+            .fields
+            .is_empty());
+    }
+}
@@ -1,6 +1,47 @@
-use log4rs::{self, Error};
+//! Where and how the database logs.
+
 use std::default::Default;
+use std::error::Error;
+use std::str::FromStr;
+use tracing_subscriber::EnvFilter;
+
+/// How log events are written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    /// Lines of text, written by the appenders of the logging config file
+    Text,
+    /// A JSON object per line on stdout, holding the fields of the event and of the spans it
+    /// happened in, such as the request id and operation name. The level is read from
+    /// `RUST_LOG`, and defaults to `info`.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(format!("Unknown log format: {}", format)),
+        }
+    }
+}
 
-pub fn setup(config_file_path: &str) -> Result<(), Error> {
-    log4rs::init_file(config_file_path, Default::default())
+/// Sets up the logging of the process, with the logging config file when the format is text.
+pub fn setup(
+    config_file_path: &str,
+    format: LogFormat,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match format {
+        LogFormat::Text => Ok(log4rs::init_file(config_file_path, Default::default())?),
+        LogFormat::Json => tracing_subscriber::fmt()
+            .json()
+            .with_current_span(true)
+            .with_span_list(true)
+            .with_env_filter(
+                EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
+            )
+            .try_init(),
+    }
 }
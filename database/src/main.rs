@@ -1,16 +1,10 @@
-use config::Config;
-use database::Database;
-
-mod config;
-mod database;
-mod listener;
-mod logging;
+use database::{logging, Config, Graphs};
 
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::default();
+    let config = Config::from_args();
 
-    logging::setup(&config.logging_config).expect("Error setting up logging");
+    logging::setup(&config.logging_config, config.log_format).expect("Error setting up logging");
 
-    let database = Database::new(&config);
-    listener::listen(database, &config)
+    let graphs = Graphs::new(&config);
+    database::listen(graphs, &config)
 }
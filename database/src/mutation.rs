@@ -0,0 +1,194 @@
+//! The mutations the database generates from its schema.
+//!
+//! Every object type with an `id` field, an entity, gets three fields on the mutation root type,
+//! `createUser(input: UserInput!): User`, `updateUser(id: ID!, input: UserInput!): User` and
+//! `deleteUser(id: ID!): User` for a `User` type. The `UserInput` input type holds the fields of
+//! `User`, all of them nullable so that `updateUser` only changes the fields its input holds.
+//! Entities are referred to by their `id` in inputs, other objects by their own input type.
+//!
+//! Fields and types the schema already defines are left as they are.
+
+use crate::coercion::type_name;
+use crate::store::{Record, Store};
+use serde_json::{Map, Value};
+use syntax::document::Document;
+use syntax::nodes::*;
+use syntax::schema::Schema;
+
+/// Adds the generated mutations and their input types to a schema.
+pub(crate) fn generate(document: &mut Document) {
+    let sdl = {
+        let schema = Schema::new(document);
+        let roots = [
+            schema.query_type(),
+            schema.mutation_type(),
+            schema.subscription_type(),
+        ];
+        let mutation = schema.mutation_type().unwrap_or("Mutation");
+        let mut sdl = String::new();
+        let mut fields = String::new();
+        for definition in schema.types() {
+            let object = match definition {
+                TypeDefinitionNode::Object(object) => object,
+                _ => continue,
+            };
+            let name = object.name.value.as_str();
+            if roots.contains(&Some(name)) || name.starts_with("__") {
+                continue;
+            }
+            let input = format!("{}Input", name);
+            if !schema.has_type(&input) {
+                sdl.push_str(&format!("input {} {{\n", input));
+                for field in schema.fields(name) {
+                    sdl.push_str(&format!(
+                        "  {}: {}\n",
+                        field.name.as_str(),
+                        input_type(&schema, nullable(&field.field_type))
+                    ));
+                }
+                sdl.push_str("}\n");
+            }
+            if !is_entity(&schema, name) {
+                continue;
+            }
+            let generated = [
+                (format!("create{}", name), format!("input: {}!", input)),
+                (
+                    format!("update{}", name),
+                    format!("id: ID!, input: {}!", input),
+                ),
+                (format!("delete{}", name), String::from("id: ID!")),
+            ];
+            for (field, arguments) in generated.iter() {
+                if schema.field(mutation, field).is_none() {
+                    fields.push_str(&format!("  {}({}): {}\n", field, arguments, name));
+                }
+            }
+        }
+        if !fields.is_empty() {
+            let keyword = if schema.has_type(mutation) {
+                "extend type"
+            } else {
+                "type"
+            };
+            sdl.push_str(&format!("{} {} {{\n{}}}\n", keyword, mutation, fields));
+        }
+        sdl
+    };
+    if !sdl.is_empty() {
+        let generated = syntax::parse(&sdl).expect("The generated mutations are valid");
+        document.definitions.extend(generated.definitions);
+    }
+}
+
+/// Runs a generated mutation against the store. Returns the record it created, updated or
+/// deleted, `None` when there was no record to update or delete.
+pub(crate) fn apply(
+    store: &mut Store,
+    schema: &Schema,
+    field: &str,
+    arguments: &Map<String, Value>,
+) -> Result<Option<Record>, String> {
+    let (action, type_name) = target(schema, field)
+        .ok_or_else(|| format!("The database cannot run the mutation `{}`", field))?;
+    let input = || match arguments.get("input") {
+        Some(Value::Object(input)) => input.clone(),
+        _ => Map::new(),
+    };
+    let id = arguments.get("id").unwrap_or(&Value::Null);
+    match action {
+        "create" => {
+            let input = input();
+            for field in schema.fields(type_name) {
+                let name = field.name.as_str();
+                let required = matches!(field.field_type, TypeNode::NonNull(_));
+                if required && name != "id" && input.get(name).is_none_or(Value::is_null) {
+                    return Err(format!(
+                        "Field `{}` is required to create a `{}`",
+                        name, type_name
+                    ));
+                }
+            }
+            store
+                .insert(type_name, input)
+                .map(|record| Some(record.clone()))
+        }
+        "update" => Ok(store.update(type_name, id, input())?.cloned()),
+        _ => store.delete(type_name, id),
+    }
+}
+
+/// What a generated mutation does, `create`, `update` or `delete`, and the entity it changes.
+pub(crate) fn target<'f>(schema: &Schema, field: &'f str) -> Option<(&'static str, &'f str)> {
+    ["create", "update", "delete"].iter().find_map(|action| {
+        field
+            .strip_prefix(action)
+            .filter(|type_name| is_entity(schema, type_name))
+            .map(|type_name| (*action, type_name))
+    })
+}
+
+/// Whether the records of an object type have an `id`.
+pub(crate) fn is_entity(schema: &Schema, type_name: &str) -> bool {
+    matches!(
+        schema.get_type(type_name),
+        Some(TypeDefinitionNode::Object(_))
+    ) && schema.field(type_name, "id").is_some()
+}
+
+fn nullable(field_type: &TypeNode) -> &TypeNode {
+    match field_type {
+        TypeNode::NonNull(inner) => inner,
+        _ => field_type,
+    }
+}
+
+/// The type of the input field standing for a field of an object type.
+fn input_type(schema: &Schema, field_type: &TypeNode) -> String {
+    match field_type {
+        TypeNode::NonNull(inner) => format!("{}!", input_type(schema, inner)),
+        TypeNode::List(list) => format!("[{}]", input_type(schema, &list.list_type)),
+        TypeNode::Named(named) => {
+            let name = named.name.value.as_str();
+            match schema.get_type(name) {
+                Some(TypeDefinitionNode::Object(_)) if !is_entity(schema, name) => {
+                    format!("{}Input", name)
+                }
+                Some(TypeDefinitionNode::Object(_))
+                | Some(TypeDefinitionNode::Interface(_))
+                | Some(TypeDefinitionNode::Union(_)) => "ID".into(),
+                _ => type_name(field_type),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntax::printer::print;
+
+    #[test]
+    fn it_generates_mutations() {
+        let mut document = syntax::parse(
+            r#"
+            schema { query: Query mutation: Mutation }
+            type Query { users: [User!]! }
+            type Mutation { deleteUser(id: ID!): Boolean }
+            type User { id: ID! name: String! friends: [User!]! address: Address }
+            type Address { city: String }
+            "#,
+        )
+        .unwrap();
+        generate(&mut document);
+        let printed = print(&document);
+        assert!(printed.contains("input UserInput {\n  id: ID\n  name: String\n  friends: [ID!]\n  address: AddressInput\n}"), "{}", printed);
+        assert!(
+            printed.contains("input AddressInput {\n  city: String\n}"),
+            "{}",
+            printed
+        );
+        assert!(printed.contains("extend type Mutation {\n  createUser(input: UserInput!): User\n  updateUser(id: ID!, input: UserInput!): User\n}"), "{}", printed);
+        assert!(!printed.contains("createAddress"));
+    }
+}
@@ -0,0 +1,469 @@
+//! The query fields the database generates from its schema.
+//!
+//! The query root type gets a `node(id: ID!): Node` field finding a record of any type by its
+//! `id`, the entities implementing the `Node` interface. Every entity also gets a collection
+//! field, `users` for a `User` type:
+//!
+//! ```graphql
+//! users(filter: UserFilter, orderBy: [UserOrder!], first: Int, after: String): UserConnection!
+//! ```
+//!
+//! `filter` keeps the records whose fields equal the ones it holds, `orderBy` sorts them by their
+//! fields and `first` and `after` return a page of them. The cursor of a record is its `id`, so
+//! `after` takes the `endCursor` of the previous page.
+//!
+//! Fields and types the schema already defines are left as they are. The ids the database gives
+//! records are unique across types, which `node` relies on.
+
+use crate::mutation::is_entity;
+use crate::store::{same_value, Record, Store};
+use serde_json::{json, Map, Value};
+use std::cmp::Ordering;
+use syntax::document::Document;
+use syntax::nodes::*;
+use syntax::schema::{named_type, Schema};
+
+/// Adds the generated query fields and the types they return to a schema.
+pub(crate) fn generate(document: &mut Document) {
+    let sdl = {
+        let schema = Schema::new(document);
+        let roots = [
+            schema.query_type(),
+            schema.mutation_type(),
+            schema.subscription_type(),
+        ];
+        let query = schema.query_type().unwrap_or("Query");
+        let mut sdl = String::new();
+        let mut fields = String::new();
+        for definition in schema.types() {
+            let name = definition.name();
+            if roots.contains(&Some(name)) || !is_entity(&schema, name) {
+                continue;
+            }
+            let comparable: Vec<&FieldDefinitionNode> = schema
+                .fields(name)
+                .into_iter()
+                .filter(|field| comparable(&schema, &field.field_type))
+                .collect();
+            if comparable.is_empty() {
+                continue;
+            }
+            if is_node_id(&schema, name) && !schema.interfaces(name).contains(&"Node") {
+                sdl.push_str(&format!("extend type {} implements Node\n", name));
+            }
+            let mut filter = format!("input {}Filter {{\n", name);
+            let mut field_enum = format!("enum {}Field {{\n", name);
+            for field in comparable {
+                let field_name = field.name.as_str();
+                filter.push_str(&format!(
+                    "  {}: {}\n",
+                    field_name,
+                    named_type(&field.field_type)
+                ));
+                field_enum.push_str(&format!("  {}\n", field_name));
+            }
+            filter.push_str("}\n");
+            field_enum.push_str("}\n");
+            let generated = [
+                (format!("{}Filter", name), filter),
+                (format!("{}Field", name), field_enum),
+                (
+                    format!("{}Order", name),
+                    format!(
+                        "input {}Order {{\n  field: {}Field!\n  direction: OrderDirection = ASC\n}}\n",
+                        name, name
+                    ),
+                ),
+                (
+                    format!("{}Edge", name),
+                    format!(
+                        "type {}Edge {{\n  cursor: String!\n  node: {}!\n}}\n",
+                        name, name
+                    ),
+                ),
+                (
+                    format!("{}Connection", name),
+                    format!(
+                        "type {0}Connection {{\n  edges: [{0}Edge!]!\n  nodes: [{0}!]!\n  pageInfo: PageInfo!\n  totalCount: Int!\n}}\n",
+                        name
+                    ),
+                ),
+            ];
+            for (type_name, definition) in generated.iter() {
+                if !schema.has_type(type_name) {
+                    sdl.push_str(definition);
+                }
+            }
+            let collection = plural(name);
+            if schema.field(query, &collection).is_none() {
+                fields.push_str(&format!(
+                    "  {}(filter: {1}Filter, orderBy: [{1}Order!], first: Int, after: String): {1}Connection!\n",
+                    collection, name
+                ));
+            }
+        }
+        if sdl.is_empty() && fields.is_empty() {
+            return;
+        }
+        let shared = [
+            ("Node", "interface Node {\n  id: ID!\n}\n"),
+            (
+                "PageInfo",
+                "type PageInfo {\n  hasNextPage: Boolean!\n  endCursor: String\n}\n",
+            ),
+            (
+                "OrderDirection",
+                "enum OrderDirection {\n  ASC\n  DESC\n}\n",
+            ),
+        ];
+        for (type_name, definition) in shared.iter() {
+            if !schema.has_type(type_name) {
+                sdl.push_str(definition);
+            }
+        }
+        if schema.field(query, "node").is_none() {
+            fields.insert_str(0, "  node(id: ID!): Node\n");
+        }
+        if !fields.is_empty() {
+            let keyword = if schema.has_type(query) {
+                "extend type"
+            } else {
+                "type"
+            };
+            sdl.push_str(&format!("{} {} {{\n{}}}\n", keyword, query, fields));
+        }
+        sdl
+    };
+    let generated = syntax::parse(&sdl).expect("The generated query fields are valid");
+    document.definitions.extend(generated.definitions);
+}
+
+/// The entity whose collection a field returns, when it returns a generated connection type.
+pub(crate) fn collection_of<'a>(schema: &Schema<'a>, field_type: &'a TypeNode) -> Option<&'a str> {
+    let type_name = match field_type {
+        TypeNode::NonNull(inner) => match inner.as_ref() {
+            TypeNode::Named(named) => named.name.value.as_str(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    type_name
+        .strip_suffix("Connection")
+        .filter(|entity| is_entity(schema, entity))
+}
+
+/// The first indexed field of a filter, with the records its index narrows the records of an
+/// entity to: the ones holding its value.
+pub(crate) fn narrowed<'s, 'f>(
+    store: &'s Store,
+    type_name: &str,
+    filter: &'f Map<String, Value>,
+) -> Option<(&'f str, Vec<&'s Record>)> {
+    filter.iter().find_map(|(name, value)| {
+        store
+            .lookup(type_name, name, value)
+            .map(|records| (name.as_str(), records))
+    })
+}
+
+/// Reads a page of the records of an entity, as the connection a collection field returns.
+pub(crate) fn page(
+    store: &Store,
+    type_name: &str,
+    arguments: &Map<String, Value>,
+) -> Result<Value, String> {
+    let filter = match arguments.get("filter") {
+        Some(Value::Object(filter)) => Some(filter),
+        _ => None,
+    };
+    let mut records: Vec<&Record> = filter
+        .and_then(|filter| narrowed(store, type_name, filter))
+        .map(|(_, records)| records)
+        .unwrap_or_else(|| store.records(type_name).iter().collect());
+    if let Some(filter) = filter {
+        records.retain(|record| {
+            filter.iter().all(|(name, value)| match record.get(name) {
+                Some(field) => same_value(field, value),
+                None => value.is_null(),
+            })
+        });
+    }
+    if let Some(Value::Array(order)) = arguments.get("orderBy") {
+        let keys: Vec<(&str, bool)> = order
+            .iter()
+            .map(|key| {
+                (
+                    key["field"].as_str().unwrap_or_default(),
+                    key["direction"] == "DESC",
+                )
+            })
+            .collect();
+        records.sort_by(|a, b| {
+            keys.iter()
+                .map(|(field, descending)| {
+                    let ordering = compare(
+                        a.get(*field).unwrap_or(&Value::Null),
+                        b.get(*field).unwrap_or(&Value::Null),
+                    );
+                    if *descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                })
+                .find(|ordering| *ordering != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+    let total = records.len();
+    let start = match arguments.get("after") {
+        Some(Value::String(cursor)) => {
+            let cursor = Value::String(cursor.clone());
+            1 + records
+                .iter()
+                .position(|record| record.get("id").is_some_and(|id| same_value(id, &cursor)))
+                .ok_or_else(|| format!("Unknown cursor `{}`", cursor))?
+        }
+        _ => 0,
+    };
+    let end = match arguments.get("first").and_then(Value::as_i64) {
+        Some(first) if first < 0 => return Err("`first` cannot be negative".into()),
+        Some(first) => total.min(start + first as usize),
+        None => total,
+    };
+    let ids: Vec<Value> = records[start..end]
+        .iter()
+        .map(|record| record.get("id").cloned().unwrap_or(Value::Null))
+        .collect();
+    let cursor = |id: &Value| match id {
+        Value::String(id) => id.clone(),
+        id => id.to_string(),
+    };
+    Ok(json!({
+        "edges": ids
+            .iter()
+            .map(|id| json!({ "cursor": cursor(id), "node": id }))
+            .collect::<Vec<Value>>(),
+        "nodes": ids,
+        "pageInfo": {
+            "hasNextPage": end < total,
+            "endCursor": ids.last().map(cursor),
+        },
+        "totalCount": total,
+    }))
+}
+
+/// Orders values as `orderBy` sorts them: nulls first, then booleans, numbers and strings.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => a
+            .as_f64()
+            .partial_cmp(&b.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+/// Whether the records of a type can be filtered and sorted by a field of this type: scalars
+/// and enums that are not lists.
+fn comparable(schema: &Schema, field_type: &TypeNode) -> bool {
+    match field_type {
+        TypeNode::NonNull(inner) => comparable(schema, inner),
+        TypeNode::List(_) => false,
+        TypeNode::Named(named) => matches!(
+            schema.get_type(named.name.value.as_str()),
+            Some(TypeDefinitionNode::Scalar(_)) | Some(TypeDefinitionNode::Enum(_))
+        ),
+    }
+}
+
+/// Whether the `id` of a type can implement the one of `Node`.
+fn is_node_id(schema: &Schema, type_name: &str) -> bool {
+    match schema.field(type_name, "id").map(|field| &field.field_type) {
+        Some(TypeNode::NonNull(inner)) => {
+            matches!(inner.as_ref(), TypeNode::Named(named) if named.name.value == "ID")
+        }
+        _ => false,
+    }
+}
+
+/// The name of the collection of a type: `users` for `User`, `categories` for `Category`.
+fn plural(type_name: &str) -> String {
+    let mut chars = type_name.chars();
+    let mut name: String = match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    };
+    let consonant_y = name.ends_with('y')
+        && !name
+            .chars()
+            .rev()
+            .nth(1)
+            .is_some_and(|c| "aeiou".contains(c));
+    if consonant_y {
+        name.pop();
+        name.push_str("ies");
+    } else if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|end| name.ends_with(end))
+    {
+        name.push_str("es");
+    } else {
+        name.push('s');
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::execute;
+    use crate::mutation;
+    use crate::resolver::Resolvers;
+    use crate::scalar::Scalars;
+    use net::request::Request;
+    use std::sync::RwLock;
+    use syntax::printer::print;
+
+    const SCHEMA: &str = r#"
+        schema { query: Query mutation: Mutation }
+        type Query { users: [User!]! }
+        type User { id: ID! name: String! age: Int friends: [User!] }
+        type Category { id: ID! label: String }
+    "#;
+
+    fn run(query: &str, variables: Value) -> Value {
+        let mut schema = syntax::parse(SCHEMA).unwrap();
+        mutation::generate(&mut schema);
+        generate(&mut schema);
+        let store = Store::from_json(
+            r#"{
+                "User": [
+                    { "id": "1", "name": "alice", "age": 30 },
+                    { "id": "2", "name": "bob", "age": 25 },
+                    { "id": "3", "name": "carol", "age": 30 }
+                ],
+                "Category": [{ "id": "4", "label": "news" }]
+            }"#,
+        )
+        .unwrap();
+        let mut request = Request::new(query);
+        request.variables = variables.as_object().cloned();
+        let query = syntax::parse(query).unwrap();
+        execute(
+            &Schema::new(&schema),
+            &Resolvers::default(),
+            &Scalars::default(),
+            &RwLock::new(store),
+            &query,
+            &request,
+        )
+    }
+
+    #[test]
+    fn it_generates_query_fields() {
+        let mut document = syntax::parse(SCHEMA).unwrap();
+        generate(&mut document);
+        let printed = print(&document);
+        assert!(
+            printed.contains("extend type User implements Node"),
+            "{}",
+            printed
+        );
+        assert!(
+            printed.contains("input UserFilter {\n  id: ID\n  name: String\n  age: Int\n}"),
+            "{}",
+            printed
+        );
+        assert!(
+            printed.contains("enum CategoryField {\n  id\n  label\n}"),
+            "{}",
+            printed
+        );
+        assert!(printed.contains("type CategoryConnection {\n  edges: [CategoryEdge!]!\n  nodes: [Category!]!\n  pageInfo: PageInfo!\n  totalCount: Int!\n}"), "{}", printed);
+        assert!(printed.contains("extend type Query {\n  node(id: ID!): Node\n  categories(filter: CategoryFilter, orderBy: [CategoryOrder!], first: Int, after: String): CategoryConnection!\n}"), "{}", printed);
+        assert!(!printed.contains("users("), "{}", printed);
+        assert!(!printed.contains("UserConnectionFilter"), "{}", printed);
+    }
+
+    #[test]
+    fn it_names_collections() {
+        assert_eq!(plural("User"), "users");
+        assert_eq!(plural("Category"), "categories");
+        assert_eq!(plural("Day"), "days");
+        assert_eq!(plural("Address"), "addresses");
+        assert_eq!(plural("Branch"), "branches");
+    }
+
+    #[test]
+    fn it_pages_through_collections() {
+        let query = r#"{
+            categories(first: 1) { totalCount nodes { label } pageInfo { hasNextPage endCursor } }
+        }"#;
+        assert_eq!(
+            run(query, Value::Null),
+            json!({ "data": { "categories": {
+                "totalCount": 1,
+                "nodes": [{ "label": "news" }],
+                "pageInfo": { "hasNextPage": false, "endCursor": "4" }
+            } } })
+        );
+    }
+
+    #[test]
+    fn it_filters_sorts_and_pages_records() {
+        let mut store = Store::from_json(
+            r#"{ "User": [
+                { "id": "1", "name": "alice", "age": 30 },
+                { "id": "2", "name": "bob", "age": 25 },
+                { "id": "3", "name": "carol", "age": 30 }
+            ] }"#,
+        )
+        .unwrap();
+        let arguments = |value: Value| value.as_object().unwrap().clone();
+        let ids = |connection: Value| connection["nodes"].clone();
+        let sorted = arguments(json!({
+            "orderBy": [{ "field": "age", "direction": "DESC" }, { "field": "name", "direction": "ASC" }]
+        }));
+        assert_eq!(
+            ids(page(&store, "User", &sorted).unwrap()),
+            json!(["1", "3", "2"])
+        );
+        let filtered = arguments(json!({ "filter": { "age": 30 }, "first": 1, "after": "1" }));
+        let connection = page(&store, "User", &filtered).unwrap();
+        assert_eq!(ids(connection.clone()), json!(["3"]));
+        assert_eq!(connection["totalCount"], 2);
+        assert_eq!(connection["pageInfo"]["hasNextPage"], false);
+        store.index("User", "age", false).unwrap();
+        assert_eq!(page(&store, "User", &filtered).unwrap(), connection);
+        assert!(page(&store, "User", &arguments(json!({ "after": "9" }))).is_err());
+        assert!(page(&store, "User", &arguments(json!({ "first": -1 }))).is_err());
+    }
+
+    #[test]
+    fn it_finds_nodes() {
+        let query = r#"query($id: ID!) {
+            node(id: $id) { __typename id ... on Category { label } }
+        }"#;
+        assert_eq!(
+            run(query, json!({ "id": "4" })),
+            json!({ "data": { "node": { "__typename": "Category", "id": "4", "label": "news" } } })
+        );
+        assert_eq!(
+            run(query, json!({ "id": "9" })),
+            json!({ "data": { "node": null } })
+        );
+    }
+}
@@ -0,0 +1,407 @@
+//! The replication of a primary database to followers answering its reads.
+//!
+//! A primary started with `--replication-port` sends its schema and its records to every follower
+//! that connects there, then every change to them. The port is bound to `--replication-host`, the
+//! loopback interface unless told otherwise. A follower first authenticates, sending a line like
+//! the handshake of TCP clients, `{"auth": {"token": "..."}}`, which the [authenticator] of the
+//! primary must know as a client holding the `ADMIN` role, as it is sent every record whatever
+//! the roles its fields require. A follower that does not is sent `{"error": "..."}` and
+//! disconnected.
+//!
+//! The stream is then a JSON object per line: the schema as it was written,
+//! `{"schema": "type User { ... }"}`, again whenever it changes, the records,
+//! `{"records": {"User": [...]}}`, in the format of `--data`, and every change made since, in the
+//! format of the [write-ahead log].
+//!
+//! A follower started with `--follow` connects to its primary with the token of `--follow-token`,
+//! serves the schema and the records it is sent, and answers queries and subscriptions but not
+//! mutations or schema changes. When the connection drops, it connects again and is sent
+//! everything anew. A follower whose primary is gone is promoted with the `__promote` mutation,
+//! sent by a client holding the `ADMIN` role, after which it stops following and answers every
+//! request:
+//!
+//! ```graphql
+//! mutation { __promote }
+//! ```
+//!
+//! [authenticator]: ../../net/auth/trait.Authenticator.html
+//! [write-ahead log]: ../wal/index.html
+
+use crate::authorization::ADMIN;
+use crate::catalog::{self, Catalog};
+use crate::executor::request_error;
+use crate::index;
+use crate::store::Store;
+use crate::wal::Change;
+use net::auth::{Credentials, Identity, SharedAuthenticator};
+use net::request::Request;
+use serde_json::{json, Value};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use syntax::document::Document;
+use syntax::nodes::Operation;
+use syntax::printer::print;
+use syntax::schema::Schema;
+use tokio::io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, info, warn};
+
+/// The number of changes a follower may fall behind before it is sent everything anew.
+pub(crate) const REPLICATION_BUFFER: usize = 1024;
+
+/// How often a follower is sent the schema when it changed, and tries to connect again.
+const INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a follower may take to send its credentials once connected.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether a database follows a primary, and so only answers reads.
+#[derive(Debug, Default)]
+pub(crate) struct Following(AtomicBool);
+
+impl Following {
+    pub fn new(following: bool) -> Following {
+        Following(AtomicBool::new(following))
+    }
+
+    pub fn get(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Stops following, and tells whether the database was.
+    fn promote(&self) -> bool {
+        self.0.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Whether the operation of a request is the promotion of a follower.
+pub(crate) fn promotes(document: &Document, request: &Request) -> bool {
+    matches!(
+        catalog::root_fields(document, request),
+        Some((Operation::Mutation, _, fields))
+            if !fields.is_empty() && fields.iter().all(|field| field.name.value == "__promote")
+    )
+}
+
+/// Promotes a follower, answering the `__promote` mutation of a client, which must hold the
+/// `ADMIN` role.
+pub(crate) fn promote(following: &Following, identity: Option<&Identity>) -> Value {
+    if !identity.is_some_and(|identity| identity.has_role(ADMIN)) {
+        return request_error(format!(
+            "Promoting a follower requires the role `{}`",
+            ADMIN
+        ));
+    }
+    if !following.promote() {
+        return request_error("The database does not follow a primary".into());
+    }
+    info!("Promoted, no longer following the primary");
+    json!({ "data": { "__promote": true } })
+}
+
+/// Turns away the requests a follower does not answer, mutations and schema changes.
+pub(crate) fn refuse(document: &Document, request: &Request) -> Option<Value> {
+    let writes = catalog::changes_schema(document)
+        || crate::executor::operation_type(document, request) == Some(Operation::Mutation);
+    writes.then(|| request_error("The database follows a primary and only answers reads".into()))
+}
+
+/// Sends the schema and the records to the followers connecting, once authenticated, then the
+/// changes to them.
+pub(crate) async fn serve(
+    listener: TcpListener,
+    authenticator: SharedAuthenticator,
+    catalog: Arc<RwLock<Catalog>>,
+    store: Arc<RwLock<Store>>,
+    changes: broadcast::Sender<Arc<Change>>,
+) {
+    loop {
+        let (stream, address) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!(%e, "Unable to accept a follower");
+                continue;
+            }
+        };
+        info!(%address, "Follower connected");
+        let (catalog, store, changes) = (catalog.clone(), store.clone(), changes.clone());
+        let authenticator = authenticator.clone();
+        tokio::spawn(async move {
+            let (reader, writer) = stream.into_split();
+            let mut writer = BufWriter::new(writer);
+            let replicated = match authenticate(reader, &authenticator).await {
+                Ok(identity) => {
+                    info!(%address, principal = %identity.principal, "Follower authenticated");
+                    replicate(&mut writer, &catalog, &store, &changes).await
+                }
+                Err(refusal) => {
+                    warn!(%address, %refusal, "Follower refused");
+                    let _ = write(&mut writer, json!({ "error": refusal })).await;
+                    return;
+                }
+            };
+            match replicated {
+                Ok(()) => info!(%address, "Follower disconnected"),
+                Err(e) => info!(%address, %e, "Follower dropped"),
+            }
+        });
+    }
+}
+
+/// The identity of a follower, from the credentials it sends first, or why it is refused: it
+/// sent none in time, they were rejected, or it does not hold the `ADMIN` role.
+async fn authenticate<R>(reader: R, authenticator: &SharedAuthenticator) -> Result<Identity, String>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    let mut lines = BufReader::new(reader).lines();
+    let line = match tokio::time::timeout(AUTH_TIMEOUT, lines.next_line()).await {
+        Ok(Ok(Some(line))) => line,
+        Ok(Ok(None)) => return Err("The follower sent no credentials".into()),
+        Ok(Err(e)) => return Err(e.to_string()),
+        Err(_) => return Err("The follower did not send its credentials in time".into()),
+    };
+    let message: Value = serde_json::from_str(&line).map_err(|e| e.to_string())?;
+    let credentials = Credentials::from_value(message.get("auth").unwrap_or(&Value::Null))?;
+    match authenticator.authenticate(credentials.as_ref()) {
+        Ok(Some(identity)) if identity.has_role(ADMIN) => Ok(identity),
+        Ok(_) => Err(format!("Following requires the role `{}`", ADMIN)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Sends everything to a follower, then the changes made since, until it falls behind or
+/// disconnects.
+async fn replicate<W: AsyncWrite + Unpin>(
+    writer: &mut BufWriter<W>,
+    catalog: &RwLock<Catalog>,
+    store: &RwLock<Store>,
+    changes: &broadcast::Sender<Arc<Change>>,
+) -> io::Result<()> {
+    let (mut sent, schema, records, mut changes) = {
+        let catalog = catalog.read().expect("The catalog lock is poisoned");
+        let store = store.read().expect("The store lock is poisoned");
+        // Subscribed while the store is locked, so that no change is missed or sent twice
+        let changes = changes.subscribe();
+        (
+            catalog.version(),
+            print(catalog.source()),
+            store.to_json(),
+            changes,
+        )
+    };
+    write(writer, json!({ "schema": schema })).await?;
+    write(writer, json!({ "records": records })).await?;
+    let mut interval = tokio::time::interval(INTERVAL);
+    loop {
+        let change = tokio::select! {
+            change = changes.recv() => Some(change),
+            _ = interval.tick() => None,
+        };
+        let schema = {
+            let catalog = catalog.read().expect("The catalog lock is poisoned");
+            (catalog.version() != sent).then(|| (catalog.version(), print(catalog.source())))
+        };
+        if let Some((version, schema)) = schema {
+            write(writer, json!({ "schema": schema })).await?;
+            sent = version;
+        }
+        match change {
+            Some(Ok(change)) => write(writer, change.to_json()).await?,
+            Some(Err(RecvError::Lagged(missed))) => {
+                return Err(io::Error::other(format!(
+                    "The follower fell behind by {} changes",
+                    missed
+                )))
+            }
+            Some(Err(RecvError::Closed)) => return Ok(()),
+            None => {}
+        }
+    }
+}
+
+async fn write<W: AsyncWrite + Unpin>(writer: &mut BufWriter<W>, line: Value) -> io::Result<()> {
+    let mut line = line.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    writer.flush().await
+}
+
+/// Follows the primary at an address, authenticating with a token, until promoted, connecting
+/// again whenever the connection drops.
+pub(crate) async fn follow(
+    primary: String,
+    token: Option<String>,
+    catalog: Arc<RwLock<Catalog>>,
+    store: Arc<RwLock<Store>>,
+    following: Arc<Following>,
+) {
+    while following.get() {
+        match sync(&primary, token.as_deref(), &catalog, &store, &following).await {
+            Ok(()) => info!(%primary, "Primary disconnected"),
+            Err(e) => warn!(%primary, %e, "Unable to follow the primary"),
+        }
+        tokio::time::sleep(INTERVAL).await;
+    }
+}
+
+/// Makes the schema, the records and the changes the primary sends, until the connection drops
+/// or the follower is promoted.
+async fn sync(
+    primary: &str,
+    token: Option<&str>,
+    catalog: &RwLock<Catalog>,
+    store: &RwLock<Store>,
+    following: &Following,
+) -> io::Result<()> {
+    let (reader, writer) = TcpStream::connect(primary).await?.into_split();
+    let auth = token.map(|token| json!({ "token": token }));
+    write(&mut BufWriter::new(writer), json!({ "auth": auth })).await?;
+    info!(%primary, "Following the primary");
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines.next_line().await? {
+        if !following.get() {
+            return Ok(());
+        }
+        let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        let message: Value = serde_json::from_str(&line).map_err(|e| invalid(e.to_string()))?;
+        if let Some(refusal) = message.get("error").and_then(Value::as_str) {
+            return Err(io::Error::new(io::ErrorKind::PermissionDenied, refusal));
+        }
+        let mut catalog = catalog.write().expect("The catalog lock is poisoned");
+        let mut store = store.write().expect("The store lock is poisoned");
+        if let Some(schema) = message.get("schema").and_then(Value::as_str) {
+            let source = syntax::parse(schema).map_err(|e| invalid(e.to_string()))?;
+            if let Err(conflicts) = catalog.reload(source, &mut store) {
+                error!(?conflicts, "Unable to serve the schema of the primary");
+            }
+        } else if let Some(records) = message.get("records") {
+            store.restore(&records.to_string()).map_err(invalid)?;
+            let schema = catalog.schema();
+            index::build(&mut store, &index::declared(&Schema::new(&schema))).map_err(invalid)?;
+        } else {
+            let change = Change::from_json(&line)
+                .ok_or_else(|| invalid(format!("Not a change: {}", line)))?;
+            if let Err(e) = store.replay(change) {
+                error!(%e, "Unable to make a change of the primary");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use net::auth::Tokens;
+
+    fn authenticator() -> SharedAuthenticator {
+        let mut tokens = Tokens::new();
+        tokens.insert("admin".into(), Identity::with_roles("alan", &[ADMIN]));
+        tokens.insert("user".into(), Identity::with_roles("ada", &["USER"]));
+        Arc::new(tokens)
+    }
+
+    #[tokio::test]
+    async fn it_follows_the_primary_until_promoted() {
+        let schema = syntax::parse("type User { id: ID! name: String! }").unwrap();
+        let primary = (
+            Arc::new(RwLock::new(Catalog::new(schema))),
+            Arc::new(RwLock::new(
+                Store::from_json(r#"{ "User": [{ "id": "1", "name": "Ada" }] }"#).unwrap(),
+            )),
+        );
+        let (changes, _) = broadcast::channel(REPLICATION_BUFFER);
+        primary.1.write().unwrap().replicate(changes.clone());
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        tokio::spawn(serve(
+            listener,
+            authenticator(),
+            primary.0.clone(),
+            primary.1.clone(),
+            changes,
+        ));
+
+        let follower = (
+            Arc::new(RwLock::new(Catalog::new(Document::default()))),
+            Arc::new(RwLock::new(Store::new())),
+        );
+        let following = Arc::new(Following::new(true));
+        for token in [None, Some("user"), Some("unknown")] {
+            let (reader, writer) = TcpStream::connect(&address).await.unwrap().into_split();
+            let auth = token.map(|token| json!({ "token": token }));
+            write(&mut BufWriter::new(writer), json!({ "auth": auth }))
+                .await
+                .unwrap();
+            let mut lines = BufReader::new(reader).lines();
+            let refusal: Value =
+                serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+            assert!(refusal["error"].is_string(), "{}", refusal);
+            assert_eq!(lines.next_line().await.unwrap(), None);
+        }
+
+        let followed = tokio::spawn(follow(
+            address,
+            Some("admin".into()),
+            follower.0.clone(),
+            follower.1.clone(),
+            following.clone(),
+        ));
+        let names = || {
+            let store = follower.1.read().unwrap();
+            store
+                .records("User")
+                .iter()
+                .map(|record| record["name"].clone())
+                .collect::<Vec<_>>()
+        };
+        let caught_up = |expected: Vec<Value>| async move {
+            for _ in 0..100 {
+                if names() == expected {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            panic!("The follower holds {:?}", names());
+        };
+        caught_up(vec![json!("Ada")]).await;
+        let schema = follower.0.read().unwrap().schema();
+        assert!(Schema::new(&schema).has_type("User"));
+
+        let mut record = serde_json::Map::new();
+        record.insert("name".into(), json!("Alan"));
+        primary.1.write().unwrap().insert("User", record).unwrap();
+        caught_up(vec![json!("Ada"), json!("Alan")]).await;
+
+        let request = Request::new("mutation { __promote }");
+        let document = syntax::parse(&request.query).unwrap();
+        assert!(promotes(&document, &request));
+        assert!(refuse(&document, &request).is_some());
+        assert!(refuse(&syntax::parse("{ users { id } }").unwrap(), &request).is_none());
+        let (user, admin) = (
+            Identity::with_roles("ada", &["USER"]),
+            Identity::with_roles("alan", &[ADMIN]),
+        );
+        assert!(promote(&following, None)["errors"].is_array());
+        assert!(promote(&following, Some(&user))["errors"].is_array());
+        assert!(following.get());
+        assert_eq!(
+            promote(&following, Some(&admin)),
+            json!({ "data": { "__promote": true } })
+        );
+        assert!(promote(&following, Some(&admin))["errors"].is_array());
+        // The follower stops with the next change it is sent
+        let mut record = serde_json::Map::new();
+        record.insert("name".into(), json!("Grace"));
+        primary.1.write().unwrap().insert("User", record).unwrap();
+        tokio::time::timeout(Duration::from_secs(5), followed)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(names(), [json!("Ada"), json!("Alan")]);
+    }
+}
@@ -0,0 +1,224 @@
+//! Fields answered by Rust code instead of the records of the store.
+//!
+//! A [`Resolver`] registered in the [`Resolvers`] for a field of a type answers that field
+//! wherever it is selected, on the root types as on records. The value it returns is completed
+//! like a stored value: objects are selected from and ids refer to the records of their type.
+//! Every other field keeps the generated behavior.
+//!
+//! Embedders register resolvers with [`Gql::register_resolver`] or
+//! [`Database::register_resolver`]. Any closure taking the arguments of [`Resolver::resolve`] is
+//! a resolver:
+//!
+//! ```
+//! use database::{Config, Context, Gql, Record};
+//! use serde_json::{json, Map, Value};
+//!
+//! let mut gql = Gql::open(&Config::default()).unwrap();
+//! gql.apply_schema("type User { id: ID! name: String! shout: String! }")
+//!     .unwrap();
+//! gql.register_resolver(
+//!     "User",
+//!     "shout",
+//!     |user: Option<&Record>, _: &str, _: &Map<String, Value>, _: &Context| {
+//!         let name = user.and_then(|user| user["name"].as_str()).unwrap_or_default();
+//!         Ok(json!(name.to_uppercase()))
+//!     },
+//! );
+//! ```
+//!
+//! [`Resolver`]: trait.Resolver.html
+//! [`Resolver::resolve`]: trait.Resolver.html#tymethod.resolve
+//! [`Resolvers`]: struct.Resolvers.html
+//! [`Gql::register_resolver`]: ../struct.Gql.html#method.register_resolver
+//! [`Database::register_resolver`]: ../struct.Database.html#method.register_resolver
+
+use crate::store::{Record, Store};
+use net::request::Request;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// What a resolver is told of the request it answers a field for.
+pub struct Context<'r> {
+    pub request: &'r Request,
+    /// The records, as they are when the field is answered
+    pub(crate) store: &'r Store,
+    /// The response keys and list indices leading to the field
+    pub path: &'r [Value],
+}
+
+impl Context<'_> {
+    /// The records of a type, as they are when the field is answered.
+    pub fn records(&self, type_name: &str) -> &[Record] {
+        self.store.records(type_name)
+    }
+
+    /// The record of a type with an id, when there is one.
+    pub fn get(&self, type_name: &str, id: &Value) -> Option<&Record> {
+        self.store.get(type_name, id)
+    }
+}
+
+/// Answers a field of a type.
+pub trait Resolver: Send + Sync {
+    /// The value of the field for the record it is selected on, or for no record on a root type,
+    /// with the coerced arguments of the field. An error is reported for the field, which is
+    /// then null.
+    fn resolve(
+        &self,
+        parent: Option<&Record>,
+        field: &str,
+        arguments: &Map<String, Value>,
+        context: &Context,
+    ) -> Result<Value, String>;
+}
+
+impl<F> Resolver for F
+where
+    F: Fn(Option<&Record>, &str, &Map<String, Value>, &Context) -> Result<Value, String>
+        + Send
+        + Sync,
+{
+    fn resolve(
+        &self,
+        parent: Option<&Record>,
+        field: &str,
+        arguments: &Map<String, Value>,
+        context: &Context,
+    ) -> Result<Value, String> {
+        self(parent, field, arguments, context)
+    }
+}
+
+/// The resolvers of the fields, by type and field name.
+#[derive(Clone, Default)]
+pub struct Resolvers {
+    resolvers: HashMap<(String, String), Arc<dyn Resolver>>,
+}
+
+impl Resolvers {
+    /// Answers a field of a type with a resolver, in place of the one registered before.
+    pub fn register<R>(&mut self, type_name: &str, field: &str, resolver: R)
+    where
+        R: Resolver + 'static,
+    {
+        self.resolvers
+            .insert((type_name.into(), field.into()), Arc::new(resolver));
+    }
+
+    /// The resolver of a field of a type, when it has one.
+    pub fn get(&self, type_name: &str, field: &str) -> Option<&dyn Resolver> {
+        if self.resolvers.is_empty() {
+            return None;
+        }
+        self.resolvers
+            .get(&(type_name.to_owned(), field.to_owned()))
+            .map(Arc::as_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::execute;
+    use crate::scalar::Scalars;
+    use serde_json::json;
+    use std::sync::RwLock;
+    use syntax::schema::Schema;
+
+    #[test]
+    fn it_answers_fields_with_resolvers() {
+        let schema = syntax::parse(
+            r#"
+            schema { query: Query mutation: Mutation }
+            type Query { users: [User!]! count: Int! best: User }
+            type Mutation { ping(times: Int!): [String!]! }
+            type User { id: ID! name: String! initials: String! friends: [User!] }
+            "#,
+        )
+        .unwrap();
+        let store = Store::from_json(
+            r#"{ "User": [{ "id": 1, "name": "Ada Lovelace", "friends": [2] }, { "id": 2, "name": "Alan Turing" }] }"#,
+        )
+        .unwrap();
+        let mut resolvers = Resolvers::default();
+        resolvers.register(
+            "User",
+            "initials",
+            |user: Option<&Record>, _: &str, _: &Map<String, Value>, _: &Context| {
+                let name = user
+                    .and_then(|user| user["name"].as_str())
+                    .unwrap_or_default();
+                let initials: String = name.split(' ').filter_map(|w| w.chars().next()).collect();
+                Ok(Value::String(initials))
+            },
+        );
+        resolvers.register(
+            "Query",
+            "count",
+            |_: Option<&Record>, _: &str, _: &Map<String, Value>, context: &Context| {
+                Ok(json!(context.store.records("User").len()))
+            },
+        );
+        resolvers.register(
+            "Query",
+            "best",
+            |_: Option<&Record>, _: &str, _: &Map<String, Value>, _: &Context| Ok(json!(2)),
+        );
+        resolvers.register(
+            "Mutation",
+            "ping",
+            |_: Option<&Record>, field: &str, arguments: &Map<String, Value>, context: &Context| {
+                match arguments["times"].as_u64() {
+                    Some(times) if times <= 2 => Ok(json!(vec![field; times as usize])),
+                    _ => Err(format!("Too many pings at {}", context.path[0])),
+                }
+            },
+        );
+        let run = |query: &str| {
+            let document = syntax::parse(query).unwrap();
+            execute(
+                &Schema::new(&schema),
+                &resolvers,
+                &Scalars::default(),
+                &RwLock::new(Store::from_json("{}").unwrap()),
+                &document,
+                &Request::new(query),
+            )
+        };
+        let store = RwLock::new(store);
+        let document = syntax::parse(
+            "{ count best { initials } users { initials friends { name initials } } }",
+        )
+        .unwrap();
+        assert_eq!(
+            execute(
+                &Schema::new(&schema),
+                &resolvers,
+                &Scalars::default(),
+                &store,
+                &document,
+                &Request::new("")
+            ),
+            json!({ "data": {
+                "count": 2,
+                "best": { "initials": "AT" },
+                "users": [
+                    { "initials": "AL", "friends": [{ "name": "Alan Turing", "initials": "AT" }] },
+                    { "initials": "AT", "friends": null },
+                ],
+            } })
+        );
+        assert_eq!(
+            run("mutation { ping(times: 2) again: ping(times: 3) }"),
+            json!({
+                "data": null,
+                "errors": [{
+                    "message": "Too many pings at \"again\"",
+                    "locations": [{ "line": 1, "column": 27 }],
+                    "path": ["again"],
+                }],
+            })
+        );
+    }
+}
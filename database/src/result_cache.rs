@@ -0,0 +1,260 @@
+//! The responses to queries, kept so that the same query is not executed again until the records
+//! it read change.
+//!
+//! A response is cached under the version of the schema, the hash of its operation as
+//! [persisted operations] hash them, so that formatting, comments and unused fragments do not
+//! matter, its variables, and the roles of the client, which decide the fields it may query.
+//! With it are kept the types whose records the query read, with the [version] of their records
+//! then. A response whose types have changed since is dropped when it is looked up, and the query
+//! executed again.
+//!
+//! Only the responses to queries without errors are cached. Those with a field answered by a
+//! [resolver] are not, as a resolver may read anything. When the cache is full, the least
+//! recently used response makes room for the new one.
+//!
+//! [persisted operations]: ../../syntax/persisted/index.html
+//! [version]: ../store/struct.Store.html#method.version
+//! [resolver]: ../resolver/index.html
+
+use crate::executor;
+use crate::resolver::Resolvers;
+use crate::scalar::Scalars;
+use crate::store::Store;
+use net::db::CacheMetrics;
+use net::request::Request;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, RwLock};
+use syntax::document::Document;
+use syntax::nodes::Operation;
+use syntax::persisted::operation_hash;
+use syntax::schema::Schema;
+
+struct Entry {
+    response: Value,
+    /// The types whose records the query read, with their version then
+    reads: HashMap<String, u64>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, Entry>,
+    clock: u64,
+}
+
+pub(crate) struct ResultCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    metrics: Arc<CacheMetrics>,
+}
+
+impl ResultCache {
+    /// A cache keeping at most `capacity` responses, none when it is 0, and counting its hits and
+    /// misses in `metrics`.
+    pub fn new(capacity: usize, metrics: Arc<CacheMetrics>) -> ResultCache {
+        ResultCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+            metrics,
+        }
+    }
+
+    /// The counts of the hits, misses and invalidations of the cache.
+    pub fn metrics(&self) -> Arc<CacheMetrics> {
+        self.metrics.clone()
+    }
+
+    /// The response to the operation of `query` that `request` names, from the cache when the
+    /// records it read have not changed since, executed otherwise.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute(
+        &self,
+        version: usize,
+        schema: &Schema,
+        resolvers: &Resolvers,
+        scalars: &Scalars,
+        store: &RwLock<Store>,
+        query: &Document,
+        request: &Request,
+    ) -> Value {
+        let key = match self.key(version, query, request) {
+            Some(key) => key,
+            None => return executor::execute(schema, resolvers, scalars, store, query, request),
+        };
+        if let Some(response) = self.get(&key, store) {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+            return response;
+        }
+        self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        let (response, reads) =
+            executor::execute_reading(schema, resolvers, scalars, store, query, request);
+        if let (Some(reads), None) = (reads, response.get("errors")) {
+            self.insert(key, response.clone(), reads);
+        }
+        response
+    }
+
+    /// The key of the response to a query, or `None` when the operation is not a query or the
+    /// cache keeps nothing.
+    fn key(&self, version: usize, query: &Document, request: &Request) -> Option<String> {
+        if self.capacity == 0 {
+            return None;
+        }
+        let operations = query.separate_operations();
+        let operation = match &request.operation_name {
+            Some(name) => operations
+                .into_iter()
+                .find(|separated| separated.operations()[0].name == Some(name.as_str()))?,
+            None if operations.len() == 1 => operations.into_iter().next()?,
+            None => return None,
+        };
+        if operation.operations()[0].operation.operation() != Operation::Query {
+            return None;
+        }
+        let variables = request
+            .variables
+            .as_ref()
+            .map(|variables| Value::Object(variables.clone()).to_string())
+            .unwrap_or_default();
+        let mut roles = request
+            .identity
+            .as_ref()
+            .map(|identity| identity.roles.clone())
+            .unwrap_or_default();
+        roles.sort();
+        Some(format!(
+            "{}:{}:{}:{}",
+            version,
+            operation_hash(&operation),
+            variables,
+            roles.join(",")
+        ))
+    }
+
+    /// The cached response under a key, unless the records it read changed since, in which case
+    /// it is dropped.
+    fn get(&self, key: &str, store: &RwLock<Store>) -> Option<Value> {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("The result cache lock is poisoned");
+        let entries = &mut *entries;
+        let entry = entries.by_key.get_mut(key)?;
+        let changed = {
+            let store = store.read().expect("The store lock is poisoned");
+            entry
+                .reads
+                .iter()
+                .any(|(type_name, version)| store.version(type_name) != *version)
+        };
+        if changed {
+            entries.by_key.remove(key);
+            self.metrics.invalidations.fetch_add(1, Ordering::Relaxed);
+            return None;
+        }
+        entries.clock += 1;
+        entry.last_used = entries.clock;
+        Some(entry.response.clone())
+    }
+
+    fn insert(&self, key: String, response: Value, reads: HashMap<String, u64>) {
+        let mut entries = self
+            .entries
+            .lock()
+            .expect("The result cache lock is poisoned");
+        if entries.by_key.len() >= self.capacity && !entries.by_key.contains_key(&key) {
+            let least_used = entries
+                .by_key
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone());
+            if let Some(least_used) = least_used {
+                entries.by_key.remove(&least_used);
+            }
+        }
+        entries.clock += 1;
+        let last_used = entries.clock;
+        entries.by_key.insert(
+            key,
+            Entry {
+                response,
+                reads,
+                last_used,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::Catalog;
+    use serde_json::json;
+
+    #[test]
+    fn it_caches_responses_until_their_records_change() {
+        let source = syntax::parse(
+            r#"
+            type User { id: ID! name: String! }
+            type Post { id: ID! title: String! }
+            "#,
+        )
+        .unwrap();
+        let schema = Catalog::new(source).schema();
+        let schema = Schema::new(&schema);
+        let store = RwLock::new(
+            Store::from_json(
+                r#"{ "User": [{ "id": 1, "name": "Ada" }], "Post": [{ "id": 2, "title": "Notes" }] }"#,
+            )
+            .unwrap(),
+        );
+        let cache = ResultCache::new(2, Arc::default());
+        let (resolvers, scalars) = (Resolvers::default(), Scalars::default());
+        let run = |query: &str, variables: Value| {
+            let mut request = Request::new(query);
+            request.variables = variables.as_object().cloned();
+            let document = syntax::parse(query).unwrap();
+            cache.execute(
+                0, &schema, &resolvers, &scalars, &store, &document, &request,
+            )
+        };
+        let count = |counter: &std::sync::atomic::AtomicU64| counter.load(Ordering::Relaxed);
+        let users = "query Users($first: Int) { users(first: $first) { nodes { name } } }";
+        let response = json!({ "data": { "users": { "nodes": [{ "name": "Ada" }] } } });
+        assert_eq!(run(users, json!({ "first": 1 })), response);
+        assert_eq!(
+            run(
+                "# The same query\nquery Users($first: Int) {\n  users(first: $first) { nodes { name } }\n}",
+                json!({ "first": 1 })
+            ),
+            response
+        );
+        run(users, json!({ "first": 2 }));
+        let metrics = cache.metrics();
+        assert_eq!((count(&metrics.hits), count(&metrics.misses)), (1, 2));
+
+        // A change to the posts leaves the users cached, and one to the users does not
+        run(
+            "mutation { createPost(input: { title: \"More\" }) { id } }",
+            Value::Null,
+        );
+        run(users, json!({ "first": 1 }));
+        assert_eq!(count(&metrics.hits), 2);
+        run(
+            "mutation { createUser(input: { name: \"Alan\" }) { id } }",
+            Value::Null,
+        );
+        assert_eq!(
+            run(users, json!({ "first": 2 })),
+            json!({ "data": { "users": { "nodes": [{ "name": "Ada" }, { "name": "Alan" }] } } })
+        );
+        assert_eq!(count(&metrics.invalidations), 1);
+
+        // Responses with errors are not cached
+        run("{ users { names } }", Value::Null);
+        run("{ users { names } }", Value::Null);
+        assert_eq!((count(&metrics.hits), count(&metrics.misses)), (2, 5));
+    }
+}
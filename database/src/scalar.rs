@@ -0,0 +1,288 @@
+//! How the values of scalars are read from requests and written in responses.
+//!
+//! A [`ScalarCoercion`] registered in the [`Scalars`] for a scalar parses the values sent for it,
+//! as literals or variables, into the values stored, and serializes the stored values into the
+//! values of responses. The scalars of the default schema are registered from the start:
+//!
+//! - the integers are JSON numbers within the bounds of their size, except `BigInt` and
+//!   `BigUint`, which are written as strings of digits and also read from numbers,
+//! - `DateTime` is an RFC 3339 string, such as `2020-01-31T12:30:00Z`,
+//! - `Date` is written `2020-01-31` and `Time` is written `12:30:00` or `12:30:00.250`.
+//!
+//! The values of scalars without a coercion are kept as they are sent. Embedders coerce the
+//! scalars of their schema with [`Gql::register_scalar`] or [`Database::register_scalar`].
+//!
+//! [`ScalarCoercion`]: trait.ScalarCoercion.html
+//! [`Scalars`]: struct.Scalars.html
+//! [`Gql::register_scalar`]: ../struct.Gql.html#method.register_scalar
+//! [`Database::register_scalar`]: ../struct.Database.html#method.register_scalar
+
+use chrono::{DateTime, NaiveDate, NaiveTime, SecondsFormat};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Parses and serializes the values of a scalar.
+pub trait ScalarCoercion: Send + Sync {
+    /// The value stored for a value sent for the scalar, or `None` when it is not a value of
+    /// the scalar.
+    fn parse(&self, value: &Value) -> Option<Value>;
+
+    /// The value written in responses for a stored value of the scalar, or `None` when it is
+    /// not a value of the scalar. Stored values are written as they are by default.
+    fn serialize(&self, value: &Value) -> Option<Value> {
+        Some(value.clone())
+    }
+}
+
+/// The coercions of the scalars, by scalar name.
+#[derive(Clone)]
+pub struct Scalars {
+    coercions: HashMap<String, Arc<dyn ScalarCoercion>>,
+}
+
+impl Default for Scalars {
+    /// The coercions of the scalars of the default schema.
+    fn default() -> Self {
+        let mut scalars = Scalars {
+            coercions: HashMap::new(),
+        };
+        let integers = [
+            ("Int", i32::MIN.into(), i32::MAX.into()),
+            ("TinyInt", i8::MIN.into(), i8::MAX.into()),
+            ("ShortInt", i16::MIN.into(), i16::MAX.into()),
+            ("LongInt", i64::MIN.into(), i64::MAX.into()),
+            ("Uint", 0, u32::MAX.into()),
+            ("TinyUint", 0, u8::MAX.into()),
+            ("ShortUint", 0, u16::MAX.into()),
+            ("LongUint", 0, u64::MAX.into()),
+        ];
+        for (name, min, max) in integers.iter() {
+            scalars.register(
+                name,
+                Integer {
+                    min: *min,
+                    max: *max,
+                },
+            );
+        }
+        scalars.register("BigInt", BigInteger { signed: true });
+        scalars.register("BigUint", BigInteger { signed: false });
+        scalars.register("Float", Float);
+        scalars.register("Double", Float);
+        scalars.register("String", Text { max_len: None });
+        scalars.register("TinyString", Text { max_len: Some(255) });
+        scalars.register("Boolean", Boolean);
+        scalars.register("ID", Id);
+        scalars.register("DateTime", Timestamp);
+        scalars.register("Date", Date);
+        scalars.register("Time", Time);
+        scalars
+    }
+}
+
+impl Scalars {
+    /// Coerces the values of a scalar with a coercion, in place of the one registered before.
+    pub fn register<C>(&mut self, name: &str, coercion: C)
+    where
+        C: ScalarCoercion + 'static,
+    {
+        self.coercions.insert(name.into(), Arc::new(coercion));
+    }
+
+    /// The coercion of a scalar, when it has one.
+    pub fn get(&self, name: &str) -> Option<&dyn ScalarCoercion> {
+        self.coercions.get(name).map(Arc::as_ref)
+    }
+
+    /// The value written in responses for a stored value of a type that is not an object type,
+    /// or `None` when it is not a value of that type.
+    pub fn serialize(&self, type_name: &str, value: &Value) -> Option<Value> {
+        match self.get(type_name) {
+            Some(coercion) => coercion.serialize(value),
+            None => Some(value.clone()),
+        }
+    }
+}
+
+/// Whole numbers between two bounds.
+struct Integer {
+    min: i128,
+    max: i128,
+}
+
+impl ScalarCoercion for Integer {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        let int = value
+            .as_i64()
+            .map(i128::from)
+            .or_else(|| value.as_u64().map(i128::from))?;
+        if (self.min..=self.max).contains(&int) {
+            Some(value.clone())
+        } else {
+            None
+        }
+    }
+}
+
+/// 128-bit whole numbers, read from numbers or strings of digits and kept as strings, which JSON
+/// numbers are too small for.
+struct BigInteger {
+    signed: bool,
+}
+
+impl ScalarCoercion for BigInteger {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        let digits = match value {
+            Value::Number(number) if number.is_i64() || number.is_u64() => number.to_string(),
+            Value::String(digits) => digits.clone(),
+            _ => return None,
+        };
+        let int = if self.signed {
+            digits.parse::<i128>().ok()?.to_string()
+        } else {
+            digits.parse::<u128>().ok()?.to_string()
+        };
+        Some(Value::String(int))
+    }
+
+    fn serialize(&self, value: &Value) -> Option<Value> {
+        self.parse(value)
+    }
+}
+
+struct Float;
+
+impl ScalarCoercion for Float {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        value.as_f64().map(|float| json!(float))
+    }
+}
+
+/// Strings of at most a number of bytes.
+struct Text {
+    max_len: Option<usize>,
+}
+
+impl ScalarCoercion for Text {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        value
+            .as_str()
+            .filter(|string| self.max_len.is_none_or(|max| string.len() <= max))
+            .map(|string| json!(string))
+    }
+}
+
+struct Boolean;
+
+impl ScalarCoercion for Boolean {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        value.as_bool().map(Value::Bool)
+    }
+}
+
+/// Identifiers, sent as strings or integers and kept as strings.
+struct Id;
+
+impl ScalarCoercion for Id {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        match value {
+            Value::String(_) => Some(value.clone()),
+            Value::Number(number) if number.is_i64() || number.is_u64() => {
+                Some(json!(number.to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// RFC 3339 dates and times, kept with their offset.
+struct Timestamp;
+
+impl ScalarCoercion for Timestamp {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        let timestamp = DateTime::parse_from_rfc3339(value.as_str()?).ok()?;
+        Some(json!(timestamp.to_rfc3339_opts(SecondsFormat::AutoSi, true)))
+    }
+
+    fn serialize(&self, value: &Value) -> Option<Value> {
+        self.parse(value)
+    }
+}
+
+struct Date;
+
+impl ScalarCoercion for Date {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        let date = NaiveDate::parse_from_str(value.as_str()?, "%Y-%m-%d").ok()?;
+        Some(json!(date.format("%Y-%m-%d").to_string()))
+    }
+
+    fn serialize(&self, value: &Value) -> Option<Value> {
+        self.parse(value)
+    }
+}
+
+/// Times of the day, with an optional fraction of a second.
+struct Time;
+
+impl ScalarCoercion for Time {
+    fn parse(&self, value: &Value) -> Option<Value> {
+        let time = NaiveTime::parse_from_str(value.as_str()?, "%H:%M:%S%.f").ok()?;
+        Some(json!(time.format("%H:%M:%S%.f").to_string()))
+    }
+
+    fn serialize(&self, value: &Value) -> Option<Value> {
+        self.parse(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_coerces_the_scalars_of_the_default_schema() {
+        let scalars = Scalars::default();
+        let parse = |name: &str, value: Value| scalars.get(name).unwrap().parse(&value);
+        assert_eq!(parse("TinyInt", json!(-128)), Some(json!(-128)));
+        assert_eq!(parse("TinyInt", json!(128)), None);
+        assert_eq!(parse("LongUint", json!(u64::MAX)), Some(json!(u64::MAX)));
+        assert_eq!(parse("Uint", json!(-1)), None);
+        assert_eq!(
+            parse("BigInt", json!("-170141183460469231731687303715884105728")),
+            Some(json!("-170141183460469231731687303715884105728"))
+        );
+        assert_eq!(parse("BigInt", json!(12)), Some(json!("12")));
+        assert_eq!(parse("BigUint", json!("-1")), None);
+        assert_eq!(parse("BigUint", json!("1.5")), None);
+        assert_eq!(parse("TinyString", json!("a".repeat(256))), None);
+        assert_eq!(
+            parse("DateTime", json!("2020-01-31T12:30:00+00:00")),
+            Some(json!("2020-01-31T12:30:00Z"))
+        );
+        assert_eq!(
+            parse("DateTime", json!("2020-01-31T12:30:00.5+02:00")),
+            Some(json!("2020-01-31T12:30:00.500+02:00"))
+        );
+        assert_eq!(parse("DateTime", json!("2020-01-31")), None);
+        assert_eq!(
+            parse("Date", json!("2020-02-29")),
+            Some(json!("2020-02-29"))
+        );
+        assert_eq!(parse("Date", json!("2021-02-29")), None);
+        assert_eq!(parse("Time", json!("23:59:59")), Some(json!("23:59:59")));
+        assert_eq!(
+            parse("Time", json!("08:15:00.25")),
+            Some(json!("08:15:00.250"))
+        );
+        assert_eq!(parse("Time", json!("24:00:00")), None);
+
+        assert_eq!(scalars.serialize("BigUint", &json!(7)), Some(json!("7")));
+        assert_eq!(scalars.serialize("Date", &json!("yesterday")), None);
+        assert_eq!(
+            scalars.serialize("Color", &json!("RED")),
+            Some(json!("RED"))
+        );
+    }
+}
@@ -0,0 +1,99 @@
+//! The files the schema is read from, and the changes made to them.
+//!
+//! `--schema` names a GraphQL document, or a glob pattern of documents such as
+//! `schema/*.graphql`, read in the order of their paths. The files are checked every so often,
+//! and read again when one of them was added, removed or modified since they were last read.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use syntax::document::Document;
+
+pub(crate) struct SchemaFiles {
+    pattern: String,
+    /// The files last read, with the time they were modified then
+    read: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl SchemaFiles {
+    pub fn new(pattern: &str) -> SchemaFiles {
+        SchemaFiles {
+            pattern: pattern.to_owned(),
+            read: Vec::new(),
+        }
+    }
+
+    /// Reads the files, returning the default schema extended with their definitions. Files that
+    /// cannot be read are not read again until they change.
+    pub fn load(&mut self) -> Result<Document, String> {
+        self.read = self.stamps()?;
+        if self.read.is_empty() {
+            return Err(format!("No schema file matches {}", self.pattern));
+        }
+        let mut schema = Document::default();
+        for (path, _) in &self.read {
+            let sdl = fs::read_to_string(path)
+                .map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+            let types = syntax::parse(&sdl).map_err(|e| format!("{}: {}", path.display(), e))?;
+            schema.definitions.extend(types.definitions);
+        }
+        Ok(schema)
+    }
+
+    /// Whether a file was added, removed or modified since the files were last read.
+    pub fn changed(&self) -> bool {
+        self.stamps().is_ok_and(|files| files != self.read)
+    }
+
+    /// The files matching the pattern, in the order of their paths, with the time they were
+    /// last modified.
+    fn stamps(&self) -> Result<Vec<(PathBuf, Option<SystemTime>)>, String> {
+        let paths = glob::glob(&self.pattern).map_err(|e| format!("{}: {}", self.pattern, e))?;
+        let mut files: Vec<(PathBuf, Option<SystemTime>)> = paths
+            .filter_map(Result::ok)
+            .map(|path| {
+                let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                (path, modified)
+            })
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syntax::schema::Schema;
+
+    #[test]
+    fn it_reads_the_files_matching_a_pattern() {
+        let dir =
+            std::env::temp_dir().join(format!("database-schema-files-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.graphql"), "type User { id: ID! }").unwrap();
+        fs::write(dir.join("b.graphql"), "extend type User { name: String }").unwrap();
+        fs::write(dir.join("notes.txt"), "not a schema").unwrap();
+
+        let mut files = SchemaFiles::new(&format!("{}/*.graphql", dir.display()));
+        assert!(files.changed());
+        let schema = files.load().unwrap();
+        assert!(Schema::new(&schema).field("User", "name").is_some());
+        assert!(!files.changed());
+
+        fs::remove_file(dir.join("b.graphql")).unwrap();
+        assert!(files.changed());
+        let schema = files.load().unwrap();
+        assert!(Schema::new(&schema).field("User", "name").is_none());
+
+        fs::write(dir.join("c.graphql"), "type {").unwrap();
+        assert!(files.load().unwrap_err().contains("c.graphql"));
+        assert!(!files.changed());
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            files.load().unwrap_err(),
+            format!("No schema file matches {}/*.graphql", dir.display())
+        );
+    }
+}
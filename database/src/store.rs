@@ -0,0 +1,519 @@
+//! The records the database holds, in memory.
+//!
+//! A record is a JSON object, stored under the name of its type. Records refer to each other by
+//! their `id` field. A store opened on a data directory also writes its changes to the write-ahead
+//! log there, before making them. The fields declared with `@index` are indexed, see
+//! [`index`](../index/index.html). Every change is also published as an [`Event`] to the
+//! subscriptions, and to the followers the database is [replicated] to.
+//!
+//! [`Event`]: ../subscription/struct.Event.html
+//! [replicated]: ../replication/index.html
+
+use crate::index::Index;
+use crate::subscription::{Action, Event};
+use crate::wal::{Change, Fsync, Wal};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// The fields of a record.
+pub type Record = Map<String, Value>;
+
+#[derive(Debug, Default)]
+pub(crate) struct Store {
+    tables: HashMap<String, Vec<Record>>,
+    wal: Option<Wal>,
+    /// The indexes of each type, by the field they index
+    indexes: HashMap<String, HashMap<String, Index>>,
+    events: Option<broadcast::Sender<Arc<Event>>>,
+    /// The changes sent to the followers, as they are written to the log
+    replicas: Option<broadcast::Sender<Arc<Change>>>,
+    /// The number of changes made to the records of each type
+    versions: HashMap<String, u64>,
+}
+
+impl Store {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads records from a JSON object mapping the name of each type to a list of records.
+    pub fn from_json(json: &str) -> Result<Store, String> {
+        let tables: HashMap<String, Vec<Record>> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Ok(Store {
+            tables,
+            ..Store::default()
+        })
+    }
+
+    /// Reads back the records kept in a data directory, or starts from `initial` when it holds
+    /// none yet, and keeps every later change there.
+    pub fn open(dir: &Path, fsync: Fsync, initial: Store) -> io::Result<Store> {
+        let wal = Wal::open(dir, fsync)?;
+        let snapshot = wal.read_snapshot()?;
+        let mut store = match &snapshot {
+            Some(json) => {
+                Store::from_json(json).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+            }
+            None => Store {
+                tables: initial.tables,
+                ..Store::default()
+            },
+        };
+        for change in wal.read_changes()? {
+            store
+                .replay(change)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        }
+        store.wal = Some(wal);
+        if snapshot.is_none() {
+            store.snapshot()?;
+        }
+        Ok(store)
+    }
+
+    /// Publishes the changes made from now on to a channel.
+    pub fn publish(&mut self, events: broadcast::Sender<Arc<Event>>) {
+        self.events = Some(events);
+    }
+
+    /// Sends the changes made from now on to the followers, as they are logged.
+    pub fn replicate(&mut self, replicas: broadcast::Sender<Arc<Change>>) {
+        self.replicas = Some(replicas);
+    }
+
+    /// The records, as a JSON map of types to records, the format of `--data`.
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(&self.tables).expect("Records are JSON")
+    }
+
+    /// Replaces every record with the ones of a JSON map of types to records, as a follower does
+    /// with the records of its primary. The indexes are dropped, to be built again.
+    pub fn restore(&mut self, json: &str) -> Result<(), String> {
+        let restored = Store::from_json(json)?;
+        for type_name in self.tables.keys().chain(restored.tables.keys()) {
+            *self.versions.entry(type_name.clone()).or_default() += 1;
+        }
+        self.tables = restored.tables;
+        self.indexes.clear();
+        self.snapshot().map_err(|e| e.to_string())
+    }
+
+    /// Indexes a field of the records of a type. Fails when the index is unique and two records
+    /// hold the same value.
+    pub fn index(&mut self, type_name: &str, field: &str, unique: bool) -> Result<(), String> {
+        let mut index = Index::new(unique);
+        for (position, record) in self.records(type_name).iter().enumerate() {
+            let value = record.get(field).unwrap_or(&Value::Null);
+            if unique && !index.get(value).is_empty() {
+                return Err(duplicate(type_name, field, value));
+            }
+            index.insert(value, position);
+        }
+        self.indexes
+            .entry(type_name.to_owned())
+            .or_default()
+            .insert(field.to_owned(), index);
+        Ok(())
+    }
+
+    /// Drops the index of a field of the records of a type.
+    pub fn unindex(&mut self, type_name: &str, field: &str) {
+        if let Some(indexes) = self.indexes.get_mut(type_name) {
+            indexes.remove(field);
+        }
+    }
+
+    /// The records of a type whose field holds a value, in the order they were inserted, when the
+    /// field is indexed.
+    pub fn lookup(&self, type_name: &str, field: &str, value: &Value) -> Option<Vec<&Record>> {
+        let index = self.indexes.get(type_name)?.get(field)?;
+        if value.is_null() {
+            return None;
+        }
+        let records = self.records(type_name);
+        let mut positions = index.get(value).to_vec();
+        positions.sort_unstable();
+        Some(
+            positions
+                .into_iter()
+                .map(|position| &records[position])
+                .filter(|record| {
+                    record
+                        .get(field)
+                        .is_some_and(|field| same_value(field, value))
+                })
+                .collect(),
+        )
+    }
+
+    /// The records of a type, in the order they were inserted.
+    pub fn records(&self, type_name: &str) -> &[Record] {
+        self.tables
+            .get(type_name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Adds a record, giving it an `id` no other record has unless it has one, and returns it.
+    pub fn insert(&mut self, type_name: &str, mut record: Record) -> Result<&Record, String> {
+        match record.get("id").filter(|id| !id.is_null()) {
+            Some(id) => {
+                if self.get(type_name, id).is_some() {
+                    return Err(format!(
+                        "A `{}` with the id {} already exists",
+                        type_name, id
+                    ));
+                }
+            }
+            None => {
+                let id = self.next_id();
+                record.insert("id".into(), Value::String(id.to_string()));
+            }
+        }
+        self.check_unique(type_name, &record, None)?;
+        self.log(|| Change::Insert(type_name.to_owned(), record.clone()))?;
+        let records = self.tables.entry(type_name.to_owned()).or_default();
+        for (field, index) in self.indexes.get_mut(type_name).into_iter().flatten() {
+            index.insert(record.get(field).unwrap_or(&Value::Null), records.len());
+        }
+        records.push(record);
+        let record = records.last().expect("The record was just added");
+        *self.versions.entry(type_name.to_owned()).or_default() += 1;
+        publish(&self.events, Action::Created, type_name, record);
+        Ok(record)
+    }
+
+    /// Sets the given fields of the record of a type with the given `id`, and returns it.
+    pub fn update(
+        &mut self,
+        type_name: &str,
+        id: &Value,
+        fields: Record,
+    ) -> Result<Option<&Record>, String> {
+        let record = match self.position(type_name, id) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        self.check_unique(type_name, &fields, Some(record))?;
+        self.log(|| Change::Update(type_name.to_owned(), id.clone(), fields.clone()))?;
+        let position = record;
+        let record = &mut self
+            .tables
+            .get_mut(type_name)
+            .expect("The type has records")[position];
+        let mut indexes = self.indexes.get_mut(type_name);
+        for (name, value) in fields {
+            if name == "id" {
+                continue;
+            }
+            if let Some(index) = indexes.as_mut().and_then(|indexes| indexes.get_mut(&name)) {
+                index.remove(record.get(&name).unwrap_or(&Value::Null), position);
+                index.insert(&value, position);
+            }
+            record.insert(name, value);
+        }
+        *self.versions.entry(type_name.to_owned()).or_default() += 1;
+        publish(&self.events, Action::Updated, type_name, record);
+        Ok(Some(record))
+    }
+
+    /// Removes the record of a type with the given `id`, and returns it.
+    pub fn delete(&mut self, type_name: &str, id: &Value) -> Result<Option<Record>, String> {
+        let record = match self.position(type_name, id) {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        self.log(|| Change::Delete(type_name.to_owned(), id.clone()))?;
+        let position = record;
+        let record = self
+            .tables
+            .get_mut(type_name)
+            .expect("The type has records")
+            .remove(position);
+        for (field, index) in self.indexes.get_mut(type_name).into_iter().flatten() {
+            index.remove(record.get(field).unwrap_or(&Value::Null), position);
+            index.shift(position);
+        }
+        *self.versions.entry(type_name.to_owned()).or_default() += 1;
+        publish(&self.events, Action::Deleted, type_name, &record);
+        Ok(Some(record))
+    }
+
+    /// The number of changes made to the records of a type, which changes whenever they do.
+    pub fn version(&self, type_name: &str) -> u64 {
+        self.versions.get(type_name).copied().unwrap_or_default()
+    }
+
+    /// Finds the record of a type with the given `id`.
+    pub fn get(&self, type_name: &str, id: &Value) -> Option<&Record> {
+        self.records(type_name)
+            .iter()
+            .find(|record| record.get("id").is_some_and(|value| same_value(value, id)))
+    }
+
+    /// Writes the records to the snapshot of the data directory, emptying its log.
+    pub fn snapshot(&mut self) -> io::Result<()> {
+        match &mut self.wal {
+            Some(wal) => wal.snapshot(&self.tables),
+            None => Ok(()),
+        }
+    }
+
+    /// Flushes the changes logged in the data directory to disk.
+    pub fn sync(&mut self) -> io::Result<()> {
+        match &mut self.wal {
+            Some(wal) => wal.sync(),
+            None => Ok(()),
+        }
+    }
+
+    /// Fails when a unique index already holds one of the values of a record, for a record other
+    /// than the one at the given position.
+    fn check_unique(
+        &self,
+        type_name: &str,
+        fields: &Record,
+        position: Option<usize>,
+    ) -> Result<(), String> {
+        let records = self.records(type_name);
+        for (field, index) in self.indexes.get(type_name).into_iter().flatten() {
+            let value = match fields.get(field) {
+                Some(value) if index.is_unique() && !value.is_null() => value,
+                _ => continue,
+            };
+            let taken = index.get(value).iter().any(|other| {
+                Some(*other) != position
+                    && records[*other]
+                        .get(field)
+                        .is_some_and(|other| same_value(other, value))
+            });
+            if taken {
+                return Err(duplicate(type_name, field, value));
+            }
+        }
+        Ok(())
+    }
+
+    fn log(&mut self, change: impl FnOnce() -> Change) -> Result<(), String> {
+        if self.wal.is_none() && self.replicas.is_none() {
+            return Ok(());
+        }
+        let change = change();
+        if let Some(wal) = &mut self.wal {
+            wal.append(&change)
+                .map_err(|e| format!("Unable to write the change to the log: {}", e))?;
+        }
+        if let Some(replicas) = &self.replicas {
+            // Nobody listens while no follower is connected
+            let _ = replicas.send(Arc::new(change));
+        }
+        Ok(())
+    }
+
+    /// Makes a change read back from a log, or sent by the primary.
+    pub fn replay(&mut self, change: Change) -> Result<(), String> {
+        match change {
+            Change::Insert(type_name, record) => self.insert(&type_name, record).map(|_| ()),
+            Change::Update(type_name, id, fields) => {
+                self.update(&type_name, &id, fields).map(|_| ())
+            }
+            Change::Delete(type_name, id) => self.delete(&type_name, &id).map(|_| ()),
+        }
+    }
+
+    fn position(&self, type_name: &str, id: &Value) -> Option<usize> {
+        self.records(type_name)
+            .iter()
+            .position(|record| record.get("id").is_some_and(|value| same_value(value, id)))
+    }
+
+    /// One more than the highest numeric `id` of any record, so that the ids the store gives
+    /// are unique across types.
+    fn next_id(&self) -> u64 {
+        self.tables
+            .values()
+            .flatten()
+            .filter_map(|record| match record.get("id") {
+                Some(Value::Number(id)) => id.as_u64(),
+                Some(Value::String(id)) => id.parse().ok(),
+                _ => None,
+            })
+            .max()
+            .map_or(1, |id| id + 1)
+    }
+}
+
+/// Sends an event to the subscriptions, if any are listening.
+fn publish(
+    events: &Option<broadcast::Sender<Arc<Event>>>,
+    action: Action,
+    type_name: &str,
+    record: &Record,
+) {
+    if let Some(events) = events.as_ref().filter(|events| events.receiver_count() > 0) {
+        let _ = events.send(Arc::new(Event {
+            action,
+            type_name: type_name.to_owned(),
+            record: record.clone(),
+        }));
+    }
+}
+
+fn duplicate(type_name: &str, field: &str, value: &Value) -> String {
+    format!(
+        "A `{}` with the {} {} already exists",
+        type_name, field, value
+    )
+}
+
+/// Compares two values as GraphQL does arguments, where the `ID` `"1"` is the same as `1`.
+pub(crate) fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::Number(b)) | (Value::Number(b), Value::String(a)) => {
+            *a == b.to_string()
+        }
+        _ => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_finds_records_by_id() {
+        let store = Store::from_json(
+            r#"{"User": [{"id": 1, "name": "alice"}, {"id": "2", "name": "bob"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(store.records("User").len(), 2);
+        assert!(store.records("Post").is_empty());
+        assert_eq!(store.get("User", &json!("1")).unwrap()["name"], "alice");
+        assert_eq!(store.get("User", &json!(2)).unwrap()["name"], "bob");
+        assert!(store.get("User", &json!(3)).is_none());
+    }
+
+    #[test]
+    fn it_changes_records() {
+        let mut store = Store::from_json(r#"{"User": [{"id": 1, "name": "alice"}]}"#).unwrap();
+        let record = |value: Value| value.as_object().unwrap().clone();
+        assert_eq!(
+            store.insert("User", record(json!({ "name": "bob" }))),
+            Ok(&record(json!({ "name": "bob", "id": "2" })))
+        );
+        assert!(store.insert("User", record(json!({ "id": "1" }))).is_err());
+        assert_eq!(
+            store.insert("Post", Record::new()).unwrap()["id"],
+            json!("3")
+        );
+        assert_eq!(
+            store.update(
+                "User",
+                &json!(2),
+                record(json!({ "id": 3, "name": "carol" }))
+            ),
+            Ok(Some(&record(json!({ "name": "carol", "id": "2" }))))
+        );
+        assert_eq!(store.update("User", &json!(3), Record::new()), Ok(None));
+        assert_eq!(
+            store.delete("User", &json!("1")).unwrap().unwrap()["name"],
+            "alice"
+        );
+        assert_eq!(store.records("User").len(), 1);
+        assert_eq!(store.delete("User", &json!(1)), Ok(None));
+    }
+
+    #[test]
+    fn it_keeps_indexes() {
+        let mut store = Store::from_json(
+            r#"{"User": [
+                {"id": 1, "email": "alice@example.com", "team": "red"},
+                {"id": 2, "email": "bob@example.com", "team": "blue"}
+            ]}"#,
+        )
+        .unwrap();
+        store.index("User", "email", true).unwrap();
+        store.index("User", "team", false).unwrap();
+        assert!(store.index("User", "id", true).is_ok());
+        let record = |value: Value| value.as_object().unwrap().clone();
+        let ids = |records: Option<Vec<&Record>>| {
+            records
+                .unwrap()
+                .iter()
+                .map(|record| record["id"].clone())
+                .collect::<Vec<Value>>()
+        };
+        assert!(store.lookup("User", "name", &json!("alice")).is_none());
+        assert_eq!(
+            store.insert(
+                "User",
+                record(json!({ "email": "bob@example.com", "team": "red" }))
+            ),
+            Err("A `User` with the email \"bob@example.com\" already exists".into())
+        );
+        store
+            .insert(
+                "User",
+                record(json!({ "email": "carol@example.com", "team": "red" })),
+            )
+            .unwrap();
+        assert_eq!(
+            ids(store.lookup("User", "team", &json!("red"))),
+            vec![json!(1), json!("3")]
+        );
+        assert!(store
+            .update(
+                "User",
+                &json!(3),
+                record(json!({ "email": "alice@example.com" }))
+            )
+            .is_err());
+        store
+            .update("User", &json!(1), record(json!({ "team": "blue" })))
+            .unwrap();
+        store.delete("User", &json!(2)).unwrap();
+        assert_eq!(
+            ids(store.lookup("User", "team", &json!("blue"))),
+            vec![json!(1)]
+        );
+        assert_eq!(
+            ids(store.lookup("User", "email", &json!("carol@example.com"))),
+            vec![json!("3")]
+        );
+        assert!(ids(store.lookup("User", "email", &json!("bob@example.com"))).is_empty());
+    }
+
+    #[test]
+    fn it_publishes_changes() {
+        let mut store = Store::new();
+        let (events, mut received) = broadcast::channel(8);
+        store.publish(events);
+        let record = |value: Value| value.as_object().unwrap().clone();
+        store
+            .insert("User", record(json!({ "name": "alice" })))
+            .unwrap();
+        store
+            .update("User", &json!(1), record(json!({ "name": "alicia" })))
+            .unwrap();
+        store.delete("User", &json!(1)).unwrap();
+        store.delete("User", &json!(1)).unwrap();
+        let mut published = Vec::new();
+        while let Ok(event) = received.try_recv() {
+            published.push((event.action, event.record["name"].clone()));
+        }
+        assert_eq!(
+            published,
+            vec![
+                (Action::Created, json!("alice")),
+                (Action::Updated, json!("alicia")),
+                (Action::Deleted, json!("alicia")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! The subscriptions the database generates from its schema, and the changes they answer.
+//!
+//! Every entity gets three fields on the subscription root type, `userCreated(id: ID): User!`,
+//! `userUpdated(id: ID): User!` and `userDeleted(id: ID): User!` for a `User` type. The store
+//! publishes an [`Event`] for every record it creates, updates or deletes, and a subscription
+//! selecting one of those fields is answered with the changed record, through its selection set,
+//! for every event of that kind on that type. The `id` argument only keeps the events of one
+//! record.
+//!
+//! Fields and types the schema already defines are left as they are.
+//!
+//! [`Event`]: struct.Event.html
+
+use crate::mutation::is_entity;
+use crate::store::{same_value, Record};
+use serde_json::{Map, Value};
+use syntax::document::Document;
+use syntax::nodes::*;
+use syntax::schema::Schema;
+
+/// The number of events kept for a subscription that has not answered the earlier ones yet.
+pub(crate) const EVENT_BUFFER: usize = 256;
+
+/// What happened to a record.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Action {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Action {
+    const ALL: [Action; 3] = [Action::Created, Action::Updated, Action::Deleted];
+
+    fn suffix(self) -> &'static str {
+        match self {
+            Action::Created => "Created",
+            Action::Updated => "Updated",
+            Action::Deleted => "Deleted",
+        }
+    }
+}
+
+/// A change made to the records of the store.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Event {
+    pub action: Action,
+    pub type_name: String,
+    /// The record as it is after the change, or as it was before being deleted
+    pub record: Record,
+}
+
+impl Event {
+    /// Whether a subscription to a field, with the given arguments, is answered for this event.
+    pub fn concerns(&self, field: &str, arguments: &Map<String, Value>) -> bool {
+        field == subscription_field(&self.type_name, self.action)
+            && match arguments.get("id") {
+                None | Some(Value::Null) => true,
+                Some(id) => self
+                    .record
+                    .get("id")
+                    .is_some_and(|record| same_value(record, id)),
+            }
+    }
+}
+
+/// Adds the generated subscriptions to a schema, and names their root type in the schema
+/// definition.
+pub(crate) fn generate(document: &mut Document) {
+    let (subscription, sdl) = {
+        let schema = Schema::new(document);
+        let roots = [
+            schema.query_type(),
+            schema.mutation_type(),
+            schema.subscription_type(),
+        ];
+        let subscription = schema.subscription_type().unwrap_or("Subscription");
+        let mut fields = String::new();
+        for definition in schema.types() {
+            let name = definition.name();
+            if roots.contains(&Some(name)) || !is_entity(&schema, name) {
+                continue;
+            }
+            for action in Action::ALL.iter() {
+                let field = subscription_field(name, *action);
+                if schema.field(subscription, &field).is_none() {
+                    fields.push_str(&format!("  {}(id: ID): {}!\n", field, name));
+                }
+            }
+        }
+        if fields.is_empty() {
+            return;
+        }
+        let keyword = if schema.has_type(subscription) {
+            "extend type"
+        } else {
+            "type"
+        };
+        let sdl = format!("{} {} {{\n{}}}\n", keyword, subscription, fields);
+        let named = schema.subscription_type().is_some();
+        (
+            if named {
+                None
+            } else {
+                Some(subscription.to_owned())
+            },
+            sdl,
+        )
+    };
+    let generated = syntax::parse(&sdl).expect("The generated subscriptions are valid");
+    document.definitions.extend(generated.definitions);
+    if let Some(subscription) = subscription {
+        for definition in document.definitions.iter_mut() {
+            if let DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(schema)) = definition
+            {
+                schema.operations.push(OperationTypeDefinitionNode {
+                    operation: Operation::Subscription,
+                    node_type: NamedTypeNode::from(subscription.as_str()),
+                });
+            }
+        }
+    }
+}
+
+/// The subscription field answered for the changes of a type: `userCreated` for a created `User`.
+fn subscription_field(type_name: &str, action: Action) -> String {
+    let mut chars = type_name.chars();
+    match chars.next() {
+        Some(first) => first
+            .to_lowercase()
+            .chain(chars)
+            .chain(action.suffix().chars())
+            .collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::{execute, execute_event, subscribe};
+    use crate::mutation;
+    use crate::resolver::Resolvers;
+    use crate::scalar::Scalars;
+    use crate::store::Store;
+    use net::request::Request;
+    use serde_json::json;
+    use std::sync::RwLock;
+    use syntax::printer::print;
+    use tokio::sync::broadcast;
+
+    #[test]
+    fn it_generates_subscriptions() {
+        let mut document = syntax::parse(
+            r#"
+            schema { query: Query }
+            type Query { users: [User!]! }
+            type User { id: ID! name: String! address: Address }
+            type Address { city: String }
+            "#,
+        )
+        .unwrap();
+        generate(&mut document);
+        let schema = Schema::new(&document);
+        assert_eq!(schema.subscription_type(), Some("Subscription"));
+        let printed = print(&document);
+        assert!(printed.contains("type Subscription {\n  userCreated(id: ID): User!\n  userUpdated(id: ID): User!\n  userDeleted(id: ID): User!\n}"), "{}", printed);
+        assert!(!printed.contains("addressCreated"), "{}", printed);
+    }
+
+    #[test]
+    fn it_matches_events_to_subscriptions() {
+        let event = Event {
+            action: Action::Updated,
+            type_name: "User".into(),
+            record: json!({ "id": "1", "name": "alice" })
+                .as_object()
+                .unwrap()
+                .clone(),
+        };
+        let arguments = |value: Value| value.as_object().unwrap().clone();
+        assert!(event.concerns("userUpdated", &Map::new()));
+        assert!(event.concerns("userUpdated", &arguments(json!({ "id": 1 }))));
+        assert!(event.concerns("userUpdated", &arguments(json!({ "id": null }))));
+        assert!(!event.concerns("userUpdated", &arguments(json!({ "id": "2" }))));
+        assert!(!event.concerns("userCreated", &Map::new()));
+    }
+
+    #[test]
+    fn it_answers_subscriptions_with_changes() {
+        let mut schema = syntax::parse(
+            r#"
+            schema { query: Query mutation: Mutation }
+            type Query { users: [User!]! }
+            type User { id: ID! name: String! friends: [User!] }
+            "#,
+        )
+        .unwrap();
+        mutation::generate(&mut schema);
+        generate(&mut schema);
+        let schema = Schema::new(&schema);
+        let mut store =
+            Store::from_json(r#"{ "User": [{ "id": "1", "name": "alice" }] }"#).unwrap();
+        let (events, mut received) = broadcast::channel(EVENT_BUFFER);
+        store.publish(events);
+        let store = RwLock::new(store);
+
+        let request = Request::new(
+            r#"subscription($id: ID) { changed: userUpdated(id: $id) { name friends { name } } }"#,
+        );
+        let subscription = syntax::parse(&request.query).unwrap();
+        assert_eq!(subscribe(&schema, &subscription, &request), Ok(()));
+        let mutation = syntax::parse(
+            r#"mutation {
+                createUser(input: { name: "bob", friends: [1] }) { id }
+                updateUser(id: 2, input: { name: "robert" }) { id }
+            }"#,
+        )
+        .unwrap();
+        execute(
+            &schema,
+            &Resolvers::default(),
+            &Scalars::default(),
+            &store,
+            &mutation,
+            &Request::new(""),
+        );
+
+        let created = received.try_recv().unwrap();
+        assert_eq!(
+            execute_event(
+                &schema,
+                &Resolvers::default(),
+                &Scalars::default(),
+                &store,
+                &subscription,
+                &request,
+                &created
+            ),
+            None
+        );
+        let updated = received.try_recv().unwrap();
+        assert_eq!(
+            execute_event(
+                &schema,
+                &Resolvers::default(),
+                &Scalars::default(),
+                &store,
+                &subscription,
+                &request,
+                &updated
+            ),
+            Some(
+                json!({ "data": { "changed": { "name": "robert", "friends": [{ "name": "alice" }] } } })
+            )
+        );
+        let mut other = request.clone();
+        other.variables = json!({ "id": "1" }).as_object().cloned();
+        assert_eq!(
+            execute_event(
+                &schema,
+                &Resolvers::default(),
+                &Scalars::default(),
+                &store,
+                &subscription,
+                &other,
+                &updated
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn it_rejects_subscriptions_it_cannot_answer() {
+        let mut schema = syntax::parse("type Query { a: Int } type User { id: ID! }").unwrap();
+        generate(&mut schema);
+        let schema = Schema::new(&schema);
+        let errors = |query: &str| {
+            let request = Request::new(query);
+            let document = syntax::parse(query).unwrap();
+            subscribe(&schema, &document, &request).unwrap_err()["errors"][0]["message"].clone()
+        };
+        assert_eq!(
+            errors("subscription { userCreated { id } userDeleted { id } }"),
+            "A subscription must select exactly one field"
+        );
+        assert_eq!(
+            errors("subscription { postCreated { id } }"),
+            "Cannot query field `postCreated` on type `Subscription`"
+        );
+        assert_eq!(
+            execute(
+                &schema,
+                &Resolvers::default(),
+                &Scalars::default(),
+                &RwLock::new(Store::new()),
+                &syntax::parse("subscription { userCreated { id } }").unwrap(),
+                &Request::new("")
+            ),
+            json!({ "errors": [{ "message": "Subscriptions are only answered over a stream" }] })
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! The write-ahead log keeping the records of the database on disk.
+//!
+//! A data directory holds `snapshot.json`, the records as they were at the last snapshot in the
+//! format of `--data`, and `wal.log`, a JSON object per line for every change made since. Changes
+//! are appended to the log before they are made to the records in memory, and replayed over the
+//! snapshot on startup. Taking a snapshot empties the log.
+
+use crate::store::Record;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+const SNAPSHOT: &str = "snapshot.json";
+const LOG: &str = "wal.log";
+
+/// When the changes appended to the log are flushed to disk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fsync {
+    /// After every change, before it is made. No change is lost when the machine goes down.
+    Always,
+    /// Every so often. The changes of the last interval may be lost when the machine goes down.
+    Every(Duration),
+    /// When the system gets to it, and at every snapshot.
+    Never,
+}
+
+impl FromStr for Fsync {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "always" => Ok(Fsync::Always),
+            "never" => Ok(Fsync::Never),
+            seconds => seconds
+                .parse::<u64>()
+                .ok()
+                .filter(|seconds| *seconds > 0)
+                .map(|seconds| Fsync::Every(Duration::from_secs(seconds)))
+                .ok_or_else(|| format!("Unknown fsync policy: {}", policy)),
+        }
+    }
+}
+
+/// A change to the records, as it is written to the log.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Change {
+    /// A record added to a type, with its `id`.
+    Insert(String, Record),
+    /// Fields set on the record of a type with the given `id`.
+    Update(String, Value, Record),
+    /// The record of a type with the given `id` removed.
+    Delete(String, Value),
+}
+
+impl Change {
+    pub fn to_json(&self) -> Value {
+        match self {
+            Change::Insert(type_name, record) => {
+                json!({ "op": "insert", "type": type_name, "record": record })
+            }
+            Change::Update(type_name, id, fields) => {
+                json!({ "op": "update", "type": type_name, "id": id, "fields": fields })
+            }
+            Change::Delete(type_name, id) => {
+                json!({ "op": "delete", "type": type_name, "id": id })
+            }
+        }
+    }
+
+    pub fn from_json(line: &str) -> Option<Change> {
+        let mut change: HashMap<String, Value> = serde_json::from_str(line).ok()?;
+        let type_name = match change.remove("type")? {
+            Value::String(type_name) => type_name,
+            _ => return None,
+        };
+        let op = match change.remove("op")? {
+            Value::String(op) => op,
+            _ => return None,
+        };
+        let id = change.remove("id");
+        let mut object = |name: &str| match change.remove(name) {
+            Some(Value::Object(record)) => Some(record),
+            _ => None,
+        };
+        match op.as_str() {
+            "insert" => Some(Change::Insert(type_name, object("record")?)),
+            "update" => Some(Change::Update(type_name, id?, object("fields")?)),
+            "delete" => Some(Change::Delete(type_name, id?)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Wal {
+    dir: PathBuf,
+    log: File,
+    fsync: Fsync,
+}
+
+impl Wal {
+    /// Opens the log of a data directory, creating the directory when it does not exist.
+    pub fn open(dir: &Path, fsync: Fsync) -> io::Result<Wal> {
+        fs::create_dir_all(dir)?;
+        let log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG))?;
+        Ok(Wal {
+            dir: dir.to_owned(),
+            log,
+            fsync,
+        })
+    }
+
+    /// The records of the last snapshot, as a JSON map of types to records.
+    pub fn read_snapshot(&self) -> io::Result<Option<String>> {
+        match fs::read_to_string(self.dir.join(SNAPSHOT)) {
+            Ok(json) => Ok(Some(json)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The changes logged since the last snapshot, in the order they were made. A last line cut
+    /// short, by the machine going down while it was written, is left out.
+    pub fn read_changes(&self) -> io::Result<Vec<Change>> {
+        let lines = BufReader::new(File::open(self.dir.join(LOG))?)
+            .lines()
+            .collect::<io::Result<Vec<String>>>()?;
+        let mut changes = Vec::with_capacity(lines.len());
+        for (number, line) in lines.iter().enumerate() {
+            match Change::from_json(line) {
+                Some(change) => changes.push(change),
+                None if number + 1 == lines.len() => break,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Line {} of the write-ahead log is not a change", number + 1),
+                    ))
+                }
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Appends a change to the log, flushing it to disk when every change is.
+    pub fn append(&mut self, change: &Change) -> io::Result<()> {
+        let mut line = change.to_json().to_string();
+        line.push('\n');
+        self.log.write_all(line.as_bytes())?;
+        if self.fsync == Fsync::Always {
+            self.log.sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the changes appended to the log to disk.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.log.sync_data()
+    }
+
+    /// Replaces the snapshot with the given records and empties the log. The new snapshot is
+    /// written beside the old one first, so that one of them is whole if the machine goes down.
+    pub fn snapshot(&mut self, tables: &HashMap<String, Vec<Record>>) -> io::Result<()> {
+        let written = self.dir.join(format!("{}.tmp", SNAPSHOT));
+        let mut file = File::create(&written)?;
+        serde_json::to_writer(&mut file, tables)?;
+        file.sync_all()?;
+        fs::rename(&written, self.dir.join(SNAPSHOT))?;
+        self.log.set_len(0)?;
+        self.log.sync_all()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::Store;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("database-wal-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn record(value: Value) -> Record {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn it_parses_fsync_policies() {
+        assert_eq!("always".parse(), Ok(Fsync::Always));
+        assert_eq!("never".parse(), Ok(Fsync::Never));
+        assert_eq!("5".parse(), Ok(Fsync::Every(Duration::from_secs(5))));
+        assert!("0".parse::<Fsync>().is_err());
+        assert!("sometimes".parse::<Fsync>().is_err());
+    }
+
+    #[test]
+    fn it_recovers_changes_from_the_log() {
+        let dir = data_dir("recover");
+        let initial = Store::from_json(r#"{"User": [{"id": 1, "name": "alice"}]}"#).unwrap();
+        let mut store = Store::open(&dir, Fsync::Always, initial).unwrap();
+        store
+            .insert("User", record(json!({ "name": "bob" })))
+            .unwrap();
+        store
+            .update("User", &json!(1), record(json!({ "name": "alicia" })))
+            .unwrap();
+        store.delete("User", &json!("2")).unwrap();
+        store
+            .insert("Post", record(json!({ "title": "hello" })))
+            .unwrap();
+        drop(store);
+
+        let store = Store::open(&dir, Fsync::Always, Store::new()).unwrap();
+        assert_eq!(
+            store.records("User"),
+            &[record(json!({ "id": 1, "name": "alicia" }))]
+        );
+        assert_eq!(store.records("Post")[0]["id"], "2");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn it_empties_the_log_on_snapshots() {
+        let dir = data_dir("snapshot");
+        let mut store = Store::open(&dir, Fsync::Never, Store::new()).unwrap();
+        store
+            .insert("User", record(json!({ "name": "alice" })))
+            .unwrap();
+        store.snapshot().unwrap();
+        assert_eq!(fs::read_to_string(dir.join(LOG)).unwrap(), "");
+        store
+            .insert("User", record(json!({ "name": "bob" })))
+            .unwrap();
+        drop(store);
+
+        let store = Store::open(&dir, Fsync::Never, Store::new()).unwrap();
+        assert_eq!(store.records("User").len(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn it_skips_a_torn_last_change() {
+        let dir = data_dir("torn");
+        let mut wal = Wal::open(&dir, Fsync::Always).unwrap();
+        let change = Change::Delete("User".into(), json!("1"));
+        wal.append(&change).unwrap();
+        wal.log.write_all(b"{\"op\": \"ins").unwrap();
+        assert_eq!(wal.read_changes().unwrap(), vec![change.clone()]);
+        wal.log.write_all(b"\n").unwrap();
+        wal.append(&change).unwrap();
+        assert!(wal.read_changes().is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,250 @@
+//! Authentication of the clients of the transports.
+//!
+//! An [`Authenticator`] checks the credentials a client sends when it opens a connection: in the
+//! handshake on TCP, `{"handshake": {"auth": {"token": "..."}}}`, in the payload of
+//! `connection_init` on WebSocket, and in the `Authorization: Bearer` header of every HTTP
+//! request. The [`Identity`] it returns is attached to each request sent to the database, so that
+//! the database has a principal to authorize them against. Clients it rejects are answered with
+//! an error and disconnected.
+//!
+//! [`Authenticator`]: trait.Authenticator.html
+//! [`Identity`]: struct.Identity.html
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Who sent a request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// The name the authenticator knows the client by
+    pub principal: String,
+    /// The roles the client holds, which the fields of the schema may require
+    pub roles: Vec<String>,
+}
+
+impl Identity {
+    pub fn new(principal: &str) -> Identity {
+        Identity {
+            principal: principal.to_owned(),
+            roles: Vec::new(),
+        }
+    }
+
+    /// The identity of a client holding the given roles.
+    pub fn with_roles(principal: &str, roles: &[&str]) -> Identity {
+        Identity {
+            principal: principal.to_owned(),
+            roles: roles.iter().map(|role| String::from(*role)).collect(),
+        }
+    }
+
+    /// Whether the client holds a role.
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|held| held == role)
+    }
+}
+
+/// What a client sent to prove who it is.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials {
+    /// A bearer token
+    Token(String),
+    Password {
+        username: String,
+        password: String,
+    },
+}
+
+impl Credentials {
+    /// Reads credentials from a JSON object holding either a `token`, or a `username` and a
+    /// `password`. Objects holding neither carry no credentials.
+    pub fn from_value(value: &Value) -> Result<Option<Credentials>, String> {
+        let fields = match value {
+            Value::Null => return Ok(None),
+            Value::Object(fields) => fields,
+            _ => return Err("Credentials must be a JSON object".into()),
+        };
+        let string = |fields: &Map<String, Value>, name: &str| match fields.get(name) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(value)) => Ok(Some(value.clone())),
+            Some(_) => Err(format!("{} must be a string", name)),
+        };
+        match (
+            string(fields, "token")?,
+            string(fields, "username")?,
+            string(fields, "password")?,
+        ) {
+            (Some(token), None, None) => Ok(Some(Credentials::Token(token))),
+            (None, Some(username), Some(password)) => {
+                Ok(Some(Credentials::Password { username, password }))
+            }
+            (None, None, None) => Ok(None),
+            _ => Err("Credentials hold either a token, or a username and a password".into()),
+        }
+    }
+}
+
+/// Why a client was not let in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AuthError {
+    /// The client sent no credentials
+    Missing,
+    /// The credentials of the client were refused
+    Rejected(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AuthError::Missing => write!(f, "Credentials are required"),
+            AuthError::Rejected(reason) => write!(f, "Authentication failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Checks the credentials of clients.
+pub trait Authenticator: Send + Sync {
+    /// Returns the identity of a client from its credentials, `None` when it sent none. Clients
+    /// let in without an identity are anonymous.
+    fn authenticate(
+        &self,
+        credentials: Option<&Credentials>,
+    ) -> Result<Option<Identity>, AuthError>;
+}
+
+/// The authenticator shared by the connections of the transports.
+pub type SharedAuthenticator = Arc<dyn Authenticator>;
+
+/// Lets every client in anonymously, whatever it sends.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Anonymous;
+
+impl Authenticator for Anonymous {
+    fn authenticate(&self, _: Option<&Credentials>) -> Result<Option<Identity>, AuthError> {
+        Ok(None)
+    }
+}
+
+/// Only lets in the clients holding one of a fixed set of tokens.
+#[derive(Debug, Clone, Default)]
+pub struct Tokens {
+    identities: HashMap<String, Identity>,
+}
+
+impl Tokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads the tokens from a JSON object mapping each token to its principal, or to an object
+    /// holding its `principal` and the `roles` it holds.
+    pub fn from_json(json: &str) -> Result<Tokens, String> {
+        let tokens: HashMap<String, Value> =
+            serde_json::from_str(json).map_err(|e| e.to_string())?;
+        let mut authenticator = Tokens::new();
+        for (token, identity) in tokens {
+            let identity = match &identity {
+                Value::String(principal) => Identity::new(principal),
+                Value::Object(fields) => {
+                    let principal = fields
+                        .get("principal")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| format!("The principal of {} must be a string", token))?;
+                    let roles = match fields.get("roles") {
+                        None | Some(Value::Null) => Vec::new(),
+                        Some(Value::Array(roles)) => roles
+                            .iter()
+                            .map(Value::as_str)
+                            .collect::<Option<Vec<&str>>>()
+                            .ok_or_else(|| format!("The roles of {} must be strings", token))?,
+                        Some(_) => return Err(format!("The roles of {} must be a list", token)),
+                    };
+                    Identity::with_roles(principal, &roles)
+                }
+                _ => {
+                    return Err(format!(
+                        "{} must map to a principal, or an object holding one",
+                        token
+                    ))
+                }
+            };
+            authenticator.insert(token, identity);
+        }
+        Ok(authenticator)
+    }
+
+    pub fn insert(&mut self, token: String, identity: Identity) {
+        self.identities.insert(token, identity);
+    }
+}
+
+impl Authenticator for Tokens {
+    fn authenticate(
+        &self,
+        credentials: Option<&Credentials>,
+    ) -> Result<Option<Identity>, AuthError> {
+        match credentials {
+            None => Err(AuthError::Missing),
+            Some(Credentials::Token(token)) => match self.identities.get(token) {
+                Some(identity) => Ok(Some(identity.clone())),
+                None => Err(AuthError::Rejected("Unknown token".into())),
+            },
+            Some(Credentials::Password { .. }) => {
+                Err(AuthError::Rejected("Only tokens are accepted".into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn it_reads_credentials() {
+        assert_eq!(
+            Credentials::from_value(&json!({ "token": "abc" })),
+            Ok(Some(Credentials::Token("abc".into())))
+        );
+        assert_eq!(
+            Credentials::from_value(&json!({ "username": "a", "password": "b" })),
+            Ok(Some(Credentials::Password {
+                username: "a".into(),
+                password: "b".into()
+            }))
+        );
+        assert_eq!(Credentials::from_value(&json!({})), Ok(None));
+        assert_eq!(Credentials::from_value(&Value::Null), Ok(None));
+        assert!(Credentials::from_value(&json!({ "token": 1 })).is_err());
+        assert!(Credentials::from_value(&json!({ "username": "a" })).is_err());
+        assert!(Credentials::from_value(&json!("abc")).is_err());
+    }
+
+    #[test]
+    fn it_only_lets_known_tokens_in() {
+        let tokens = Tokens::from_json(
+            r#"{"abc": "alice", "def": {"principal": "bob", "roles": ["ADMIN"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            tokens.authenticate(Some(&Credentials::Token("abc".into()))),
+            Ok(Some(Identity::new("alice")))
+        );
+        let bob = tokens.authenticate(Some(&Credentials::Token("def".into())));
+        assert_eq!(bob, Ok(Some(Identity::with_roles("bob", &["ADMIN"]))));
+        assert!(bob.unwrap().unwrap().has_role("ADMIN"));
+        assert!(Tokens::from_json(r#"{"abc": {"principal": "bob", "roles": "ADMIN"}}"#).is_err());
+        assert!(Tokens::from_json(r#"{"abc": 1}"#).is_err());
+        assert!(matches!(
+            tokens.authenticate(Some(&Credentials::Token("abd".into()))),
+            Err(AuthError::Rejected(_))
+        ));
+        assert_eq!(tokens.authenticate(None), Err(AuthError::Missing));
+        assert_eq!(Anonymous.authenticate(None), Ok(None));
+    }
+}
@@ -0,0 +1,106 @@
+//! Compression of the messages of a connection.
+//!
+//! A client asks for compression with a handshake, sent as the first message of its connection:
+//! `{"handshake": {"compression": ["zstd", "gzip"]}}`, listing the algorithms it supports in the
+//! order it prefers them. The server answers with the one it picked, or `null`:
+//! `{"handshake": {"compression": "zstd"}}`. Both messages are sent uncompressed, and every
+//! message after them is compressed on its own before being framed. Connections framed with
+//! newlines are never compressed, since compressed messages may hold newlines.
+
+use std::io::{self, Read, Write};
+
+/// An algorithm messages can be compressed with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// The name of the algorithm in handshakes.
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Compression> {
+        match name {
+            "gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Picks the first of the algorithms a client offered that the server supports.
+    pub fn negotiate<'a>(offered: impl IntoIterator<Item = &'a str>) -> Option<Compression> {
+        offered.into_iter().find_map(Compression::from_name)
+    }
+
+    pub fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Compression::Zstd => zstd::stream::encode_all(data, 0),
+        }
+    }
+
+    /// Decompresses a message, reading at most one byte more than `limit` so that a small
+    /// message cannot expand to fill the memory of the server. Callers tell messages over the
+    /// limit by their length.
+    pub fn decompress(self, data: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+        let mut content = Vec::new();
+        let limit = limit as u64 + 1;
+        match self {
+            Compression::Gzip => {
+                flate2::read::GzDecoder::new(data)
+                    .take(limit)
+                    .read_to_end(&mut content)?;
+            }
+            Compression::Zstd => {
+                zstd::stream::read::Decoder::new(data)?
+                    .take(limit)
+                    .read_to_end(&mut content)?;
+            }
+        }
+        Ok(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_negotiates_the_algorithm() {
+        assert_eq!(
+            Compression::negotiate(vec!["br", "zstd", "gzip"]),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(
+            Compression::negotiate(vec!["gzip", "zstd"]),
+            Some(Compression::Gzip)
+        );
+        assert_eq!(Compression::negotiate(vec!["br"]), None);
+    }
+
+    #[test]
+    fn it_compresses_messages() {
+        let message = r#"{"data": {"users": [{"name": "a"}, {"name": "a"}, {"name": "a"}]}}"#;
+        for &compression in &[Compression::Gzip, Compression::Zstd] {
+            let compressed = compression.compress(message.as_bytes()).unwrap();
+            assert_ne!(compressed, message.as_bytes());
+            let decompressed = compression.decompress(&compressed, 1024).unwrap();
+            assert_eq!(decompressed, message.as_bytes());
+
+            let truncated = compression.decompress(&compressed, 10).unwrap();
+            assert_eq!(truncated.len(), 11);
+            assert!(compression.decompress(b"not compressed", 1024).is_err());
+        }
+    }
+}
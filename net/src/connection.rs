@@ -1,15 +1,43 @@
-use crate::message::{self, Message};
+use crate::auth::{Anonymous, AuthError, Credentials, Identity, SharedAuthenticator};
+use crate::compression::Compression;
+use crate::message::{self, Framing, Message};
+use crate::request::{envelope, Batch, Request, RequestId};
 use bytes::{Buf, BytesMut};
-use log::{debug, info};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{
     self, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter, ReadHalf,
     WriteHalf,
 };
+use tokio::time::{self, Instant};
+use tracing::{debug, info, trace};
 
 pub struct Connection<T> {
     reader: BufReader<ReadHalf<T>>,
     writer: BufWriter<WriteHalf<T>>,
     buffer: BytesMut,
+    /// Detected from the first byte received
+    framing: Option<Framing>,
+    /// How long the rest of a message may take once its first bytes are in the buffer
+    read_timeout: Option<Duration>,
+    /// When the first bytes of the message in the buffer arrived
+    partial_since: Option<Instant>,
+    /// Negotiated in the handshake
+    compression: Option<Compression>,
+    /// Whether a message was read, after which handshakes are no longer accepted
+    started: bool,
+    /// An answer of the connection itself, to the handshake or to a message that is not a
+    /// request, until it is written
+    reply: Option<String>,
+    /// Checks the credentials of the first message
+    authenticator: SharedAuthenticator,
+    /// Attached to every request read once the client is authenticated
+    identity: Option<Identity>,
+    /// The graph named in the handshake, which every request is routed to
+    graph: Option<String>,
+    /// Why the client was turned away, after which nothing more is read
+    rejection: Option<AuthError>,
 }
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
@@ -21,21 +49,56 @@ impl<T: AsyncRead + AsyncWrite> Connection<T> {
             reader: BufReader::new(read),
             writer: BufWriter::new(write),
             buffer: BytesMut::with_capacity(4 * 1024),
+            framing: None,
+            read_timeout: None,
+            partial_since: None,
+            compression: None,
+            started: false,
+            reply: None,
+            authenticator: Arc::new(Anonymous),
+            identity: None,
+            graph: None,
+            rejection: None,
         }
     }
 
-    pub async fn read_message(&mut self) -> Result<Option<String>, Error> {
+    /// Sets how the client is authenticated. Connections let clients in anonymously otherwise.
+    pub fn set_authenticator(&mut self, authenticator: SharedAuthenticator) {
+        self.authenticator = authenticator;
+    }
+
+    pub fn set_read_timeout(&mut self, timeout: Duration) {
+        self.read_timeout = Some(timeout);
+    }
+
+    /// Reads the next request, or returns `None` once the peer has closed the connection. Empty
+    /// messages are skipped, a handshake sent as the first message is answered, and so are
+    /// messages that are not requests, with a request error. Clients the authenticator rejects
+    /// are answered with the reason, then get an error.
+    pub async fn read_message(&mut self) -> Result<Option<Batch>, Error> {
         loop {
-            debug!("start of loop");
             if let Some(message) = self.parse_message()? {
-                debug!("Got message: {}", message);
-                if message == "" {
-                    return Ok(None);
+                debug!(?message, "Message read");
+                if matches!(&message, Batch::Single(request) if request.query.is_empty()) {
+                    continue;
                 }
                 return Ok(Some(message));
             }
-            let bytes_read = self.reader.read_buf(&mut self.buffer).await?;
-            debug!("Bytes read: {}", bytes_read);
+            if let Some(reply) = self.reply.take() {
+                self.write_chunk(reply.as_bytes(), true).await?;
+                continue;
+            }
+            let read = self.reader.read_buf(&mut self.buffer);
+            let bytes_read = match (self.read_timeout, self.partial_since) {
+                (Some(timeout), Some(since)) => time::timeout_at(since + timeout, read)
+                    .await
+                    .map_err(|_| "Timed out reading a message")??,
+                _ => read.await?,
+            };
+            if self.partial_since.is_none() && !self.buffer.is_empty() {
+                self.partial_since = Some(Instant::now());
+            }
+            trace!(bytes_read, "Read from connection");
             if 0 == bytes_read {
                 if self.buffer.is_empty() {
                     return Ok(None);
@@ -46,32 +109,164 @@ impl<T: AsyncRead + AsyncWrite> Connection<T> {
         }
     }
 
-    fn parse_message(&mut self) -> Result<Option<String>, Error> {
-        let is_ready = Message::ready(&self.buffer);
-        info!("is ready?: {:?}", is_ready);
-        match is_ready {
-            Ok(_) => match Message::parse(&self.buffer) {
-                Ok(Message::Document { content, byte_len }) => {
-                    // self.advance_buffer(byte_len);
-                    self.buffer.advance(byte_len);
-                    info!("Content pulled from connection:\n{}", content);
-                    Ok(Some(content))
-                }
-                Err(message::Error::Incomplete(m)) => {
-                    info!("Parsing incomplete: {}", m);
-                    Ok(None)
+    /// Splits the next message off the buffer. Messages are JSON objects holding a request, JSON
+    /// arrays holding a batch of them, or, as clients did before requests had variables, bare
+    /// GraphQL documents.
+    fn parse_message(&mut self) -> Result<Option<Batch>, Error> {
+        if let Some(rejection) = &self.rejection {
+            return Err(rejection.clone().into());
+        }
+        let framing = match (self.framing, self.buffer.first()) {
+            (Some(framing), _) => framing,
+            (None, Some(&first)) => *self.framing.insert(Framing::detect(first)),
+            (None, None) => return Ok(None),
+        };
+        match Message::parse(&self.buffer, framing, self.compression) {
+            Ok(Message::Document { content, byte_len }) => {
+                self.buffer.advance(byte_len);
+                self.partial_since = if self.buffer.is_empty() {
+                    None
+                } else {
+                    Some(Instant::now())
+                };
+                trace!(%content, "Message split off the buffer");
+                let value = serde_json::from_str::<Value>(&content).ok();
+                if !std::mem::replace(&mut self.started, true) {
+                    let handshake = value.as_ref().and_then(|value| value.get("handshake"));
+                    if let Err(rejection) = self.authenticate(handshake) {
+                        self.reject(rejection, handshake.is_some());
+                        return Ok(None);
+                    }
+                    if let Some(handshake) = handshake {
+                        self.handshake(framing, handshake);
+                        return Ok(None);
+                    }
                 }
-                Err(message::Error::System(e)) => Err(e),
-            },
-            Err(_) => Ok(None),
+                let mut batch = match value {
+                    Some(value) if value.is_object() || value.is_array() => {
+                        match Batch::from_value(&value) {
+                            Ok(batch) => batch,
+                            Err(message) => {
+                                self.refuse(&value, message);
+                                return Ok(None);
+                            }
+                        }
+                    }
+                    _ => Batch::Single(Request::new(&content)),
+                };
+                batch.identify(self.identity.as_ref());
+                batch.route(self.graph.as_deref());
+                Ok(Some(batch))
+            }
+            Err(message::Error::Incomplete(m)) => {
+                trace!(reason = %m, "Message incomplete");
+                Ok(None)
+            }
+            Err(message::Error::TooLarge(length)) => Err(format!(
+                "Messages are at most {} bytes, not {}",
+                message::MAX_LENGTH,
+                length
+            )
+            .into()),
+            Err(message::Error::System(e)) => Err(e),
         }
     }
 
+    /// Identifies the client from the credentials in its handshake, if it sent one.
+    fn authenticate(&mut self, handshake: Option<&Value>) -> Result<(), AuthError> {
+        let credentials = match handshake {
+            Some(handshake) => {
+                Credentials::from_value(&handshake["auth"]).map_err(AuthError::Rejected)?
+            }
+            None => None,
+        };
+        self.identity = self.authenticator.authenticate(credentials.as_ref())?;
+        let principal = self
+            .identity
+            .as_ref()
+            .map(|identity| identity.principal.as_str());
+        debug!(principal, "Client authenticated");
+        Ok(())
+    }
+
+    /// Queues the answer to a client turned away: an error in the answer to its handshake, or in
+    /// the reply to its first request.
+    fn reject(&mut self, rejection: AuthError, handshake: bool) {
+        info!(reason = %rejection, "Client rejected");
+        let message = rejection.to_string();
+        self.reply = Some(if handshake {
+            json!({ "handshake": { "error": message } }).to_string()
+        } else {
+            envelope(
+                None,
+                json!({ "errors": [{ "message": message }] }).to_string(),
+            )
+        });
+        self.rejection = Some(rejection);
+    }
+
+    /// Queues the answer to a message that is not a request: a request error, with the id of the
+    /// message if it has one.
+    fn refuse(&mut self, value: &Value, message: String) {
+        debug!(reason = %message, "Message refused");
+        let id = value.get("id").and_then(RequestId::from_value);
+        let reply = json!({ "errors": [{ "message": message }] }).to_string();
+        self.reply = Some(envelope(id.as_ref(), reply));
+    }
+
+    /// Picks the compression of the connection among the ones the client offered, and the graph
+    /// it asked for, and queues the answer. Connections framed with newlines stay uncompressed.
+    fn handshake(&mut self, framing: Framing, handshake: &Value) {
+        self.graph = handshake["graph"].as_str().map(String::from);
+        let offered = handshake["compression"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str);
+        self.compression = match framing {
+            Framing::LengthPrefixed => Compression::negotiate(offered),
+            Framing::Lines => None,
+        };
+        info!(compression = ?self.compression, "Handshake received");
+        let compression = self.compression.map(Compression::name);
+        self.reply = Some(json!({ "handshake": { "compression": compression } }).to_string());
+    }
+
+    /// Writes a message, framed like the messages received and compressed if the connection is.
+    /// Messages longer than [`CHUNK_SIZE`](../message/constant.CHUNK_SIZE.html) are written as a
+    /// sequence of chunks, so the peer gets the start of a large reply without it being framed in
+    /// one buffer.
     pub async fn write_message(&mut self, message: &str) -> io::Result<()> {
-        let res = self.writer.write_all(message.as_bytes()).await;
-        info!("Write_all response: {:?}", res);
-        let flush_res = self.writer.flush().await;
-        info!("flush response: {:?}", flush_res);
+        let compressed;
+        let bytes = match self.compression {
+            Some(compression) => {
+                compressed = compression.compress(message.as_bytes())?;
+                &compressed[..]
+            }
+            None => message.as_bytes(),
+        };
+        if bytes.len() <= message::CHUNK_SIZE {
+            return self.write_chunk(bytes, true).await;
+        }
+        let mut chunks = bytes.chunks(message::CHUNK_SIZE).peekable();
+        while let Some(chunk) = chunks.next() {
+            self.write_chunk(chunk, chunks.peek().is_none()).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes part of a message, `last` telling whether the message ends with it. Chunks are
+    /// flushed as they are written, so a message can be sent as its parts become ready. They are
+    /// written as they are: on a compressed connection, they must be parts of the compressed
+    /// message.
+    pub async fn write_chunk(&mut self, chunk: &[u8], last: bool) -> io::Result<()> {
+        let frame = self
+            .framing
+            .unwrap_or(Framing::LengthPrefixed)
+            .encode_chunk(chunk, last);
+        self.writer.write_all(&frame).await?;
+        self.writer.flush().await?;
+        trace!(bytes = frame.len(), "Wrote frame");
         Ok(())
     }
 }
@@ -79,10 +274,11 @@ impl<T: AsyncRead + AsyncWrite> Connection<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::auth::Tokens;
     use bytes::BufMut;
     use core::pin::Pin;
     use core::task::{Context, Poll};
-    use tokio::io::{self, ReadBuf};
+    use tokio::io::{self, AsyncBufReadExt, ReadBuf};
     // use tokio::stream::{Stream, StreamExt};
 
     #[derive(Debug)]
@@ -143,7 +339,7 @@ mod tests {
         pretty_env_logger::init();
     }
 
-    fn create_connection(input: Vec<&[u8]>) -> Connection<MockStream> {
+    fn create_connection(input: Vec<&[u8]>) -> Connection<MockStream<'_>> {
         let inner = MockStream {
             reader: input,
             writer: vec![],
@@ -183,9 +379,25 @@ mod tests {
         let res = conn.read_message().await;
         assert!(res.is_ok());
         assert!(res.unwrap().is_some());
+        assert_eq!(conn.framing, Some(Framing::Lines));
 
         let res = conn.read_message().await;
-        assert!(res.is_err());
+        assert!(res.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_reads_a_length_prefixed_message() {
+        let inner = MockStream {
+            reader: vec![b" b }\n", &[0, 0, 0, 8, b'{', b' ', b'a']],
+            writer: vec![],
+        };
+        let mut conn = Connection::new(inner);
+        let res = conn.read_message().await;
+        assert_eq!(res.unwrap(), Some(Batch::Single(Request::new("{ a b }\n"))));
+        assert_eq!(conn.framing, Some(Framing::LengthPrefixed));
+
+        conn.buffer.put(&[0x7f, 0, 0, 0][..]);
+        assert!(conn.read_message().await.is_err());
     }
 
     #[test]
@@ -209,10 +421,73 @@ mod tests {
         assert!(opt_message.is_some());
         assert_eq!(
             opt_message.unwrap(),
-            String::from("type Object { name: String }"),
+            Batch::Single(Request::new("type Object { name: String }")),
         )
     }
 
+    #[test]
+    fn it_parses_requests() {
+        let mut conn = create_connection(vec![]);
+
+        conn.buffer.put(
+            &b"{\"id\": \"1\", \"query\": \"{ a }\", \"variables\": {\"b\": 2}}\n{\"query\": 3}\n"
+                [..],
+        );
+        let request = match conn.parse_message().unwrap().unwrap() {
+            Batch::Single(request) => request,
+            batch => panic!("Expected a single request, got {:?}", batch),
+        };
+        assert_eq!(request.id, Some(RequestId::from("1")));
+        assert_eq!(request.query, "{ a }");
+        assert_eq!(request.variables.unwrap()["b"], 2);
+
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(
+            conn.reply.take().as_deref(),
+            Some(r#"{"id":null,"payload":{"errors":[{"message":"query must be a string"}]}}"#)
+        );
+
+        conn.buffer
+            .put(&b"[{\"query\": \"{ a }\"}, {\"query\": \"{ b }\"}]\n[]\n"[..]);
+        assert_eq!(
+            conn.parse_message().unwrap(),
+            Some(Batch::Many(vec![
+                Request::new("{ a }"),
+                Request::new("{ b }")
+            ]))
+        );
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(
+            conn.reply.as_deref(),
+            Some(
+                r#"{"id":null,"payload":{"errors":[{"message":"A batch must hold at least one request"}]}}"#
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn it_answers_messages_that_are_not_requests_and_reads_on() {
+        let (mut client, server) = io::duplex(1024);
+        let mut conn = Connection::new(server);
+        client
+            .write_all(b"{\"id\": \"1\", \"query\": 3}\n[]\n{\"query\": \"{ a }\"}\n")
+            .await
+            .unwrap();
+        assert_eq!(
+            conn.read_message().await.unwrap(),
+            Some(Batch::Single(Request::new("{ a }")))
+        );
+
+        let mut lines = BufReader::new(client).lines();
+        let errors = [
+            r#"{"id":"1","payload":{"errors":[{"message":"query must be a string"}]}}"#,
+            r#"{"id":null,"payload":{"errors":[{"message":"A batch must hold at least one request"}]}}"#,
+        ];
+        for error in &errors {
+            assert_eq!(lines.next_line().await.unwrap().as_deref(), Some(*error));
+        }
+    }
+
     #[tokio::test]
     async fn it_can_write_messages() {
         let inner = vec![];
@@ -220,6 +495,171 @@ mod tests {
         assert!(conn.write_message("OK").await.is_ok());
         println!("What is writer? {:?}", conn.writer);
         // The buffer should be flushed
-        assert_eq!(conn.writer.buffer(), []);
+        assert_eq!(conn.writer.buffer(), b"");
+    }
+
+    #[tokio::test]
+    async fn it_fails_to_write_once_the_peer_is_gone() {
+        let (client, server) = io::duplex(1024);
+        let mut conn = Connection::new(server);
+        drop(client);
+        assert!(conn.write_message("OK").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn it_writes_large_messages_in_chunks() {
+        let (client, server) = io::duplex(1024);
+        let query = "{ a }".repeat(message::CHUNK_SIZE / 2);
+        let writing = tokio::spawn({
+            let query = query.clone();
+            async move {
+                let mut conn = Connection::new(server);
+                conn.write_message(&query).await.unwrap();
+            }
+        });
+
+        let mut conn = Connection::new(client);
+        assert_eq!(
+            conn.read_message().await.unwrap(),
+            Some(Batch::Single(Request::new(&query)))
+        );
+        writing.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn it_compresses_messages_once_negotiated() {
+        let (mut client, server) = io::duplex(1024);
+        let reply = "{ a }".repeat(1000);
+        let serving = tokio::spawn({
+            let reply = reply.clone();
+            async move {
+                let mut conn = Connection::new(server);
+                let request = conn.read_message().await.unwrap();
+                assert_eq!(request, Some(Batch::Single(Request::new("{ a }"))));
+                conn.write_message(&reply).await.unwrap();
+            }
+        });
+
+        async fn read_frame(client: &mut io::DuplexStream) -> Vec<u8> {
+            let length = client.read_u32().await.unwrap();
+            let mut frame = vec![0; length as usize];
+            client.read_exact(&mut frame).await.unwrap();
+            frame
+        }
+        let handshake = br#"{"handshake": {"compression": ["br", "gzip"]}}"#;
+        client
+            .write_all(&Framing::LengthPrefixed.encode_chunk(handshake, true))
+            .await
+            .unwrap();
+        assert_eq!(
+            read_frame(&mut client).await,
+            br#"{"handshake":{"compression":"gzip"}}"#
+        );
+
+        let request = Compression::Gzip.compress(b"{ a }").unwrap();
+        client
+            .write_all(&Framing::LengthPrefixed.encode_chunk(&request, true))
+            .await
+            .unwrap();
+        let frame = read_frame(&mut client).await;
+        assert!(frame.len() < reply.len());
+        assert_eq!(
+            Compression::Gzip
+                .decompress(&frame, message::MAX_LENGTH)
+                .unwrap(),
+            reply.as_bytes()
+        );
+        serving.await.unwrap();
+    }
+
+    #[test]
+    fn it_does_not_compress_lines() {
+        let mut conn = create_connection(vec![]);
+
+        conn.buffer
+            .put(&b"{\"handshake\": {\"compression\": [\"gzip\"]}}\n{\"handshake\": {}}\n"[..]);
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(conn.compression, None);
+        assert_eq!(
+            conn.reply.as_deref(),
+            Some(r#"{"handshake":{"compression":null}}"#)
+        );
+        // Only the first message can be a handshake, later ones are answered as bad requests
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(
+            conn.reply.as_deref(),
+            Some(r#"{"id":null,"payload":{"errors":[{"message":"query must be a string"}]}}"#)
+        );
+    }
+
+    #[test]
+    fn it_attaches_the_identity_of_the_client() {
+        let mut conn = create_connection(vec![]);
+        let mut tokens = Tokens::new();
+        tokens.insert("abc".into(), Identity::new("alice"));
+        conn.set_authenticator(Arc::new(tokens));
+
+        conn.buffer.put(
+            &b"{\"handshake\": {\"auth\": {\"token\": \"abc\"}}}\n{\"query\": \"{ a }\"}\n"[..],
+        );
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(
+            conn.reply.as_deref(),
+            Some(r#"{"handshake":{"compression":null}}"#)
+        );
+        assert_eq!(
+            conn.parse_message().unwrap(),
+            Some(Batch::Single(Request {
+                identity: Some(Identity::new("alice")),
+                ..Request::new("{ a }")
+            }))
+        );
+    }
+
+    #[test]
+    fn it_routes_requests_to_the_graph_of_the_handshake() {
+        let mut conn = create_connection(vec![]);
+        conn.buffer
+            .put(&b"{\"handshake\": {\"graph\": \"staging\"}}\n{\"query\": \"{ a }\"}\n"[..]);
+        assert_eq!(conn.parse_message().unwrap(), None);
+        assert_eq!(
+            conn.parse_message().unwrap(),
+            Some(Batch::Single(Request {
+                graph: Some("staging".into()),
+                ..Request::new("{ a }")
+            }))
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_unknown_clients() {
+        let mut tokens = Tokens::new();
+        tokens.insert("abc".into(), Identity::new("alice"));
+        let tokens: SharedAuthenticator = Arc::new(tokens);
+
+        for (first, reply) in &[
+            (
+                r#"{"handshake": {"auth": {"token": "abd"}}}"#,
+                r#"{"handshake":{"error":"Authentication failed: Unknown token"}}"#,
+            ),
+            (
+                r#"{"query": "{ a }"}"#,
+                r#"{"id":null,"payload":{"errors":[{"message":"Credentials are required"}]}}"#,
+            ),
+        ] {
+            let (mut client, server) = io::duplex(1024);
+            let mut conn = Connection::new(server);
+            conn.set_authenticator(tokens.clone());
+            client
+                .write_all(format!("{}\n{{ a }}\n", first).as_bytes())
+                .await
+                .unwrap();
+            assert!(conn.read_message().await.is_err());
+            assert!(conn.read_message().await.is_err());
+
+            let mut written = vec![0; reply.len() + 1];
+            client.read_exact(&mut written).await.unwrap();
+            assert_eq!(String::from_utf8(written).unwrap(), format!("{}\n", reply));
+        }
     }
 }
@@ -0,0 +1,257 @@
+//! The channel the transports send requests to the database through.
+//!
+//! The channel has a fixed capacity. When it is full, requests are turned away with
+//! [`DbError::Busy`] rather than queued behind it, so that a slow database answers the requests
+//! it has instead of falling further behind. [`DbSender::queue_depth`] and [`DbSender::shed`]
+//! tell how loaded it is, and [`DbSender::cache`] how often the database answers from the cache
+//! of its results.
+//!
+//! Subscriptions, and operations whose results are delivered in parts with `@defer` and
+//! `@stream`, are sent with [`DbSender::subscribe`], and answered with a stream of responses
+//! rather than a single one.
+//!
+//! [`DbError::Busy`]: enum.DbError.html#variant.Busy
+//! [`DbSender::queue_depth`]: struct.DbSender.html#method.queue_depth
+//! [`DbSender::shed`]: struct.DbSender.html#method.shed
+//! [`DbSender::cache`]: struct.DbSender.html#method.cache
+//! [`DbSender::subscribe`]: struct.DbSender.html#method.subscribe
+
+use crate::request::Request;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+use tracing::{info, info_span, warn, Instrument};
+
+/// The number of responses to a subscription waiting for the transport to send them.
+const STREAM_BUFFER: usize = 16;
+
+/// A request to the database, along with the channel for its response.
+pub type DbCommand = (Request, Responder);
+
+/// Where the database sends the response to a request.
+#[derive(Debug)]
+pub enum Responder {
+    /// The response to a query or a mutation
+    Once(oneshot::Sender<String>),
+    /// The responses to a subscription, one per event it is answered for, until the receiver is
+    /// dropped. Queries and mutations get a response per part of their result, a single one
+    /// unless they use `@defer` or `@stream`.
+    Stream(mpsc::Sender<String>),
+}
+
+impl Responder {
+    /// Whether the responses are streamed, as those to subscriptions are.
+    pub fn is_stream(&self) -> bool {
+        matches!(self, Responder::Stream(_))
+    }
+
+    /// Waits until the requester stops listening.
+    pub async fn closed(&mut self) {
+        match self {
+            Responder::Once(once) => once.closed().await,
+            Responder::Stream(stream) => stream.closed().await,
+        }
+    }
+
+    /// Sends a response, and tells whether the requester still listens for more.
+    pub async fn send(&self, response: String) -> bool {
+        match self {
+            Responder::Once(_) => false,
+            Responder::Stream(stream) => stream.send(response).await.is_ok(),
+        }
+    }
+
+    /// Sends the last response to the request, and tells whether the requester received it.
+    pub async fn finish(self, response: String) -> bool {
+        match self {
+            Responder::Once(once) => once.send(response).is_ok(),
+            Responder::Stream(stream) => stream.send(response).await.is_ok(),
+        }
+    }
+}
+
+/// Sends GraphQL requests to the database.
+#[derive(Debug, Clone)]
+pub struct DbSender {
+    sender: Sender<DbCommand>,
+    shed: Arc<AtomicUsize>,
+    cache: Arc<CacheMetrics>,
+}
+
+/// Counts how the database answered requests from the cache of its results, updated by the
+/// database and read by the transports.
+#[derive(Debug, Default)]
+pub struct CacheMetrics {
+    /// Requests answered from the cache
+    pub hits: AtomicU64,
+    /// Requests that were executed, as their result was not cached
+    pub misses: AtomicU64,
+    /// Results dropped from the cache as the records they read changed
+    pub invalidations: AtomicU64,
+}
+
+/// Why the database did not answer a request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DbError {
+    /// The queue of the database is full
+    Busy,
+    /// The database has stopped
+    Unavailable,
+    /// The database dropped the request
+    NoResponse,
+}
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbError::Busy => write!(f, "The server is busy"),
+            DbError::Unavailable => write!(f, "The database is not available"),
+            DbError::NoResponse => write!(f, "The database did not respond"),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+/// Creates a channel to the database holding at most `capacity` requests.
+pub fn channel(capacity: usize) -> (DbSender, Receiver<DbCommand>) {
+    let (sender, receiver) = mpsc::channel(capacity);
+    (DbSender::new(sender), receiver)
+}
+
+impl DbSender {
+    pub fn new(sender: Sender<DbCommand>) -> Self {
+        DbSender {
+            sender,
+            shed: Arc::new(AtomicUsize::new(0)),
+            cache: Arc::new(CacheMetrics::default()),
+        }
+    }
+
+    /// Reports the given counters of the cache of the database, in place of ones never updated.
+    pub fn with_cache_metrics(mut self, cache: Arc<CacheMetrics>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Sends a request and waits for the response, or fails at once if the queue is full. The
+    /// request runs in a `request` span, closed with its duration and outcome.
+    pub async fn query(&self, request: Request) -> Result<String, DbError> {
+        let span = info_span!(
+            "request",
+            id = request.id.as_ref().map(tracing::field::display),
+            operation = request.operation_name.as_deref()
+        );
+        let started = Instant::now();
+        let result = self.send(request).instrument(span.clone()).await;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| match &result {
+            Ok(_) => info!(duration_ms, outcome = "ok", "Request answered"),
+            Err(e) => info!(duration_ms, outcome = ?e, "Request failed: {}", e),
+        });
+        result
+    }
+
+    /// Sends a request whose responses are streamed, or fails at once if the queue is full. The
+    /// database answers a subscription with a response for every event it is interested in,
+    /// until the receiver is dropped or the database stops, and other operations with a response
+    /// for every part of their result.
+    pub fn subscribe(&self, request: Request) -> Result<Receiver<String>, DbError> {
+        let (send_many, receive_many) = mpsc::channel(STREAM_BUFFER);
+        self.enqueue(request, Responder::Stream(send_many))?;
+        Ok(receive_many)
+    }
+
+    async fn send(&self, request: Request) -> Result<String, DbError> {
+        let (send_one, receive_one) = oneshot::channel();
+        self.enqueue(request, Responder::Once(send_one))?;
+        receive_one.await.map_err(|_| DbError::NoResponse)
+    }
+
+    fn enqueue(&self, request: Request, responder: Responder) -> Result<(), DbError> {
+        match self.sender.try_send((request, responder)) {
+            Ok(()) => Ok(()),
+            Err(TrySendError::Full(_)) => {
+                self.shed.fetch_add(1, Ordering::Relaxed);
+                warn!(queue_depth = self.queue_depth(), "Database queue full");
+                Err(DbError::Busy)
+            }
+            Err(TrySendError::Closed(_)) => Err(DbError::Unavailable),
+        }
+    }
+
+    /// The number of requests waiting for the database.
+    pub fn queue_depth(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+
+    /// The number of requests the queue holds at most.
+    pub fn queue_capacity(&self) -> usize {
+        self.sender.max_capacity()
+    }
+
+    /// The number of requests turned away because the queue was full.
+    pub fn shed(&self) -> usize {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    /// How the database answered requests from the cache of its results.
+    pub fn cache(&self) -> &CacheMetrics {
+        &self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_sheds_load_when_the_queue_is_full() {
+        let (send, mut receive) = channel(1);
+        let first = tokio::spawn({
+            let send = send.clone();
+            async move { send.query(Request::new("{ a }")).await }
+        });
+        while send.queue_depth() == 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(send.queue_capacity(), 1);
+        assert_eq!(send.query(Request::new("{ b }")).await, Err(DbError::Busy));
+        assert_eq!(send.shed(), 1);
+
+        let (request, response) = receive.recv().await.unwrap();
+        assert_eq!(request.query, "{ a }");
+        assert_eq!(send.queue_depth(), 0);
+        assert!(response.finish(String::from("a")).await);
+        assert_eq!(first.await.unwrap(), Ok(String::from("a")));
+
+        drop(receive);
+        assert_eq!(
+            send.query(Request::new("{ c }")).await,
+            Err(DbError::Unavailable)
+        );
+    }
+
+    #[tokio::test]
+    async fn it_streams_the_responses_to_subscriptions() {
+        let (send, mut receive) = channel(1);
+        let mut responses = send.subscribe(Request::new("subscription { a }")).unwrap();
+        let (_, responder) = receive.recv().await.unwrap();
+        assert!(responder.is_stream());
+        assert!(responder.send(String::from("1")).await);
+        assert!(responder.finish(String::from("2")).await);
+        assert_eq!(responses.recv().await, Some(String::from("1")));
+        assert_eq!(responses.recv().await, Some(String::from("2")));
+        assert_eq!(responses.recv().await, None);
+
+        let responses = send.subscribe(Request::new("subscription { b }")).unwrap();
+        let (_, responder) = receive.recv().await.unwrap();
+        drop(responses);
+        let mut responder = responder;
+        responder.closed().await;
+        assert!(!responder.send(String::from("3")).await);
+    }
+}
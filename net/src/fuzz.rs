@@ -0,0 +1,14 @@
+//! Entry points for fuzzing. They take arbitrary bytes and must never panic.
+
+use crate::compression::Compression;
+use crate::message::{Framing, Message};
+use bytes::BytesMut;
+
+/// Splits a message off the bytes, as a connection does with its read buffer.
+pub fn message(data: &[u8]) {
+    let buffer = BytesMut::from(data);
+    let _ = Message::parse(&buffer, Framing::LengthPrefixed, None);
+    let _ = Message::parse(&buffer, Framing::Lines, None);
+    let _ = Message::parse(&buffer, Framing::LengthPrefixed, Some(Compression::Gzip));
+    let _ = Message::parse(&buffer, Framing::LengthPrefixed, Some(Compression::Zstd));
+}
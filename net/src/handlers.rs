@@ -1 +1,5 @@
+#[cfg(feature = "http")]
+pub use crate::http::handler::handle_http;
 pub use crate::tcp::handler::handle_tcp;
+#[cfg(feature = "ws")]
+pub use crate::ws::handler::handle_ws;
@@ -0,0 +1,595 @@
+//! GraphQL over HTTP, following the GraphQL-over-HTTP spec.
+//!
+//! `POST /graphql` takes a JSON body with `query`, and optionally `variables` and
+//! `operationName`. `GET /graphql` takes the same parameters in the query string, with
+//! `variables` encoded as JSON, and only accepts documents made of queries and fragments, since
+//! GET requests must be safe. The response of the database is returned as the body. A POST body
+//! may also hold an array of requests, which run concurrently and whose responses are returned
+//! as an array in the same order. Clients are authenticated by the bearer token in the
+//! `Authorization` header of each request, and routed to the graph named by its `X-Gql-Graph`
+//! header.
+
+use bytes::Bytes;
+use hyper::header::{
+    HeaderValue, ACCEPT, ALLOW, AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER, WWW_AUTHENTICATE,
+};
+use hyper::server::conn::AddrStream;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use syntax::nodes::{DefinitionNode, ExecutableDefinitionNode};
+use tokio::io;
+use tracing::{debug, info_span, Instrument};
+
+use crate::auth::{AuthError, Authenticator, Credentials, Identity, SharedAuthenticator};
+use crate::message::CHUNK_SIZE;
+use crate::request::{self, Batch, Request as GraphQLRequest};
+use crate::shutdown::Shutdown;
+use crate::{DbError, DbSender};
+
+const GRAPH_HEADER: &str = "x-gql-graph";
+const GRAPHQL_PATH: &str = "/graphql";
+const GRAPHQL_RESPONSE_JSON: &str = "application/graphql-response+json";
+const JSON: &str = "application/json";
+const METRICS_PATH: &str = "/metrics";
+const TEXT: &str = "text/plain; version=0.0.4";
+
+/// Serves GraphQL over HTTP on `port` until the shutdown, then waits for the requests in flight.
+pub async fn handle_http(
+    port: u32,
+    send: DbSender,
+    authenticator: SharedAuthenticator,
+    mut shutdown: Shutdown,
+) -> io::Result<()> {
+    let address: SocketAddr = format!("127.0.0.1:{}", port)
+        .parse()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let send = send.clone();
+        let authenticator = authenticator.clone();
+        let span = info_span!("connection", protocol = "http", peer = %connection.remote_addr());
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                respond(request, send.clone(), authenticator.clone()).instrument(span.clone())
+            }))
+        }
+    });
+    Server::bind(&address)
+        .serve(make_service)
+        .with_graceful_shutdown(async move { shutdown.wait().await })
+        .await
+        .map_err(io::Error::other)
+}
+
+async fn respond(
+    request: Request<Body>,
+    send: DbSender,
+    authenticator: SharedAuthenticator,
+) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() == METRICS_PATH && request.method() == Method::GET {
+        return Ok(response(StatusCode::OK, TEXT, metrics(&send)));
+    }
+    if request.uri().path() != GRAPHQL_PATH {
+        return Ok(error(StatusCode::NOT_FOUND, JSON, "Not found"));
+    }
+    let content_type = match response_content_type(&request) {
+        Some(content_type) => content_type,
+        None => return Ok(error(
+            StatusCode::NOT_ACCEPTABLE,
+            JSON,
+            "Responses are only available as application/graphql-response+json or application/json",
+        )),
+    };
+    let identity = match authenticate(&request, &*authenticator) {
+        Ok(identity) => identity,
+        Err(e) => {
+            let mut response = error(StatusCode::UNAUTHORIZED, content_type, &e.to_string());
+            response
+                .headers_mut()
+                .insert(WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            return Ok(response);
+        }
+    };
+    let graph = request
+        .headers()
+        .get(GRAPH_HEADER)
+        .and_then(|graph| graph.to_str().ok())
+        .map(String::from);
+    let batch = match *request.method() {
+        Method::GET => from_query_string(request.uri().query().unwrap_or_default()).and_then(
+            |graphql_request| {
+                ensure_safe(&graphql_request.query)?;
+                Ok(Batch::Single(graphql_request))
+            },
+        ),
+        Method::POST => from_body(request).await,
+        _ => {
+            let mut response = error(
+                StatusCode::METHOD_NOT_ALLOWED,
+                content_type,
+                "Only GET and POST are allowed",
+            );
+            response
+                .headers_mut()
+                .insert(ALLOW, HeaderValue::from_static("GET, POST"));
+            return Ok(response);
+        }
+    };
+    let mut batch = match batch {
+        Ok(batch) => batch,
+        Err((status, message)) => return Ok(error(status, content_type, &message)),
+    };
+    batch.identify(identity.as_ref());
+    batch.route(graph.as_deref());
+    let graphql_request = match batch {
+        Batch::Single(graphql_request) => graphql_request,
+        Batch::Many(requests) => {
+            debug!(requests = requests.len(), "Batch received");
+            let body = run_batch(requests, &send).await;
+            return Ok(response(StatusCode::OK, content_type, body));
+        }
+    };
+    match send.query(graphql_request).await {
+        Ok(body) => Ok(response(StatusCode::OK, content_type, body)),
+        Err(e @ DbError::Busy) => {
+            let mut response = error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                content_type,
+                &e.to_string(),
+            );
+            response
+                .headers_mut()
+                .insert(RETRY_AFTER, HeaderValue::from_static("1"));
+            Ok(response)
+        }
+        Err(e @ DbError::Unavailable) => Ok(error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            content_type,
+            &e.to_string(),
+        )),
+        Err(e @ DbError::NoResponse) => Ok(error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            content_type,
+            &e.to_string(),
+        )),
+    }
+}
+
+/// Runs the requests of a batch concurrently, and returns their responses as an array in the
+/// order of the requests. Requests the database does not answer get an error in their place.
+async fn run_batch(requests: Vec<GraphQLRequest>, send: &DbSender) -> String {
+    let running: Vec<_> = requests
+        .into_iter()
+        .map(|graphql_request| {
+            let send = send.clone();
+            tokio::spawn(async move { send.query(graphql_request).await }.in_current_span())
+        })
+        .collect();
+    let mut responses = Vec::with_capacity(running.len());
+    for graphql_request in running {
+        let response = match graphql_request.await {
+            Ok(Ok(body)) => request::payload(body),
+            Ok(Err(e)) => json!({ "errors": [{ "message": e.to_string() }] }),
+            Err(e) => json!({ "errors": [{ "message": e.to_string() }] }),
+        };
+        responses.push(response);
+    }
+    Value::Array(responses).to_string()
+}
+
+/// The load of the database queue and the use of its result cache, in the Prometheus text
+/// format.
+fn metrics(send: &DbSender) -> String {
+    let cache = send.cache();
+    format!(
+        "# HELP gql_queue_depth Requests waiting for the database.\n\
+         # TYPE gql_queue_depth gauge\n\
+         gql_queue_depth {}\n\
+         # HELP gql_queue_capacity Requests the database queue holds at most.\n\
+         # TYPE gql_queue_capacity gauge\n\
+         gql_queue_capacity {}\n\
+         # HELP gql_requests_shed_total Requests turned away because the database queue was full.\n\
+         # TYPE gql_requests_shed_total counter\n\
+         gql_requests_shed_total {}\n\
+         # HELP gql_result_cache_hits_total Requests answered from the result cache.\n\
+         # TYPE gql_result_cache_hits_total counter\n\
+         gql_result_cache_hits_total {}\n\
+         # HELP gql_result_cache_misses_total Requests executed as their result was not cached.\n\
+         # TYPE gql_result_cache_misses_total counter\n\
+         gql_result_cache_misses_total {}\n\
+         # HELP gql_result_cache_invalidations_total Cached results dropped as the records they read changed.\n\
+         # TYPE gql_result_cache_invalidations_total counter\n\
+         gql_result_cache_invalidations_total {}\n",
+        send.queue_depth(),
+        send.queue_capacity(),
+        send.shed(),
+        cache.hits.load(Ordering::Relaxed),
+        cache.misses.load(Ordering::Relaxed),
+        cache.invalidations.load(Ordering::Relaxed)
+    )
+}
+
+/// The media type of the response, from the `Accept` header of the request. Clients that do not
+/// say get the one of the spec.
+fn response_content_type(request: &Request<Body>) -> Option<&'static str> {
+    let accept = match request.headers().get(ACCEPT) {
+        Some(accept) => accept.to_str().unwrap_or_default(),
+        None => return Some(GRAPHQL_RESPONSE_JSON),
+    };
+    let accepts = |media_type: &str| {
+        accept.split(',').any(|accepted| {
+            let accepted = accepted.split(';').next().unwrap_or_default().trim();
+            accepted == media_type || accepted == "*/*" || accepted == "application/*"
+        })
+    };
+    if accepts(GRAPHQL_RESPONSE_JSON) {
+        Some(GRAPHQL_RESPONSE_JSON)
+    } else if accepts(JSON) {
+        Some(JSON)
+    } else {
+        None
+    }
+}
+
+/// The identity of the client, from the bearer token in the `Authorization` header.
+fn authenticate(
+    request: &Request<Body>,
+    authenticator: &dyn Authenticator,
+) -> Result<Option<Identity>, AuthError> {
+    let credentials = match request.headers().get(AUTHORIZATION) {
+        Some(authorization) => {
+            let token = authorization
+                .to_str()
+                .ok()
+                .and_then(|authorization| authorization.strip_prefix("Bearer "))
+                .ok_or_else(|| AuthError::Rejected("Only bearer tokens are accepted".into()))?;
+            Some(Credentials::Token(token.trim().to_owned()))
+        }
+        None => None,
+    };
+    authenticator.authenticate(credentials.as_ref())
+}
+
+type RequestError = (StatusCode, String);
+
+async fn from_body(request: Request<Body>) -> Result<Batch, RequestError> {
+    let is_json = request
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|content_type| content_type.to_str().ok())
+        .is_some_and(|content_type| {
+            content_type.split(';').next().unwrap_or_default().trim() == JSON
+        });
+    if !is_json {
+        return Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "POST requests must be application/json".into(),
+        ));
+    }
+    let body = hyper::body::to_bytes(request.into_body())
+        .await
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let body: Value = serde_json::from_slice(&body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("The body is not JSON: {}", e),
+        )
+    })?;
+    Batch::from_value(&body).map_err(|message| bad_request(&message))
+}
+
+fn from_query_string(query_string: &str) -> Result<GraphQLRequest, RequestError> {
+    let mut query = None;
+    let mut variables = None;
+    let mut operation_name = None;
+    for (key, value) in form_urlencoded::parse(query_string.as_bytes()) {
+        match &*key {
+            "query" => query = Some(value.into_owned()),
+            "variables" => match serde_json::from_str(&value) {
+                Ok(Value::Object(parsed)) => variables = Some(parsed),
+                Ok(_) => return Err(bad_request("variables must be an object")),
+                Err(_) => return Err(bad_request("variables must be JSON")),
+            },
+            "operationName" => operation_name = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    Ok(GraphQLRequest {
+        variables,
+        operation_name,
+        ..GraphQLRequest::new(&query.ok_or_else(|| bad_request("query is required"))?)
+    })
+}
+
+/// Checks that a document sent with GET only reads: it must hold nothing but queries and
+/// fragments.
+fn ensure_safe(query: &str) -> Result<(), RequestError> {
+    let document = syntax::parse(query).map_err(|e| bad_request(&e.to_string()))?;
+    let safe = document.definitions.iter().all(|definition| {
+        matches!(
+            definition,
+            DefinitionNode::Executable(ExecutableDefinitionNode::Operation(_))
+                | DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(_))
+        )
+    });
+    if safe {
+        Ok(())
+    } else {
+        Err((
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only queries can be sent with GET".into(),
+        ))
+    }
+}
+
+fn bad_request(message: &str) -> RequestError {
+    (StatusCode::BAD_REQUEST, message.into())
+}
+
+fn error(status: StatusCode, content_type: &'static str, message: &str) -> Response<Body> {
+    let body = json!({ "errors": [{ "message": message }] });
+    response(status, content_type, body.to_string())
+}
+
+fn response(status: StatusCode, content_type: &'static str, body: String) -> Response<Body> {
+    let mut response = Response::new(stream(body));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+/// Makes a body of `content`. Bodies longer than a chunk are sent with the chunked transfer
+/// encoding, a chunk at a time.
+fn stream(content: String) -> Body {
+    if content.len() <= CHUNK_SIZE {
+        return Body::from(content);
+    }
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(
+        async move {
+            let content = Bytes::from(content);
+            let mut start = 0;
+            while start < content.len() {
+                let end = usize::min(start + CHUNK_SIZE, content.len());
+                if sender.send_data(content.slice(start..end)).await.is_err() {
+                    debug!("Client went away while streaming a response");
+                    return;
+                }
+                start = end;
+            }
+        }
+        .in_current_span(),
+    );
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Anonymous, Tokens};
+    use crate::db::{self, CacheMetrics};
+    use std::sync::Arc;
+
+    fn anonymous() -> SharedAuthenticator {
+        Arc::new(Anonymous)
+    }
+
+    /// A database that answers every document with its length.
+    fn database() -> DbSender {
+        let (send, mut receive) = db::channel(8);
+        tokio::spawn(async move {
+            while let Some((request, response)) = receive.recv().await {
+                response
+                    .finish(format!("{{\"data\":{}}}", request.query.len()))
+                    .await;
+            }
+        });
+        send
+    }
+
+    async fn body(response: Response<Body>) -> String {
+        let bytes = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    fn post(body: &str) -> Request<Body> {
+        Request::post(GRAPHQL_PATH)
+            .header(CONTENT_TYPE, JSON)
+            .body(Body::from(body.to_owned()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn it_answers_posts() {
+        let request = post(r#"{"query": "{ me }", "variables": {"a": 1}, "operationName": null}"#);
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], GRAPHQL_RESPONSE_JSON);
+        assert_eq!(body(response).await, r#"{"data":6}"#);
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_clients() {
+        let (send, mut receive) = db::channel(8);
+        tokio::spawn(async move {
+            while let Some((request, response)) = receive.recv().await {
+                let principal = request.identity.map(|identity| identity.principal);
+                response
+                    .finish(json!({ "data": principal }).to_string())
+                    .await;
+            }
+        });
+        let mut tokens = Tokens::new();
+        tokens.insert("abc".into(), Identity::new("alice"));
+        let tokens: SharedAuthenticator = Arc::new(tokens);
+
+        let mut request = post(r#"{"query": "{ me }"}"#);
+        request
+            .headers_mut()
+            .insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+        let response = respond(request, send.clone(), tokens.clone())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, r#"{"data":"alice"}"#);
+
+        for authorization in &[None, Some("Bearer abd"), Some("Basic YTpi")] {
+            let mut request = post(r#"{"query": "{ me }"}"#);
+            if let Some(authorization) = authorization {
+                request
+                    .headers_mut()
+                    .insert(AUTHORIZATION, HeaderValue::from_static(authorization));
+            }
+            let response = respond(request, send.clone(), tokens.clone())
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+            assert_eq!(response.headers()[WWW_AUTHENTICATE], "Bearer");
+        }
+    }
+
+    #[tokio::test]
+    async fn it_answers_batches() {
+        let request = post(r#"[{"query": "{ me }"}, {"query": "{ you }"}]"#);
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body(response).await, r#"[{"data":6},{"data":7}]"#);
+
+        let response = respond(post("[]"), database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let (send, receive) = db::channel(1);
+        drop(receive);
+        let response = respond(post(r#"[{"query": "{ me }"}]"#), send, anonymous())
+            .await
+            .unwrap();
+        assert_eq!(
+            body(response).await,
+            r#"[{"errors":[{"message":"The database is not available"}]}]"#
+        );
+    }
+
+    #[tokio::test]
+    async fn it_rejects_bad_requests() {
+        let response = respond(post(r#"{"variables": {}}"#), database(), anonymous())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            body(response).await,
+            r#"{"errors":[{"message":"query must be a string"}]}"#
+        );
+
+        let response = respond(post("{ me }"), database(), anonymous())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let request = Request::post(GRAPHQL_PATH)
+            .body(Body::from(r#"{"query": "{ me }"}"#))
+            .unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let request = Request::put(GRAPHQL_PATH).body(Body::empty()).unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[ALLOW], "GET, POST");
+
+        let request = Request::get("/other").body(Body::empty()).unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn it_streams_large_bodies() {
+        use hyper::body::HttpBody;
+
+        assert_eq!(stream("{}".into()).size_hint().exact(), Some(2));
+        let content = "a".repeat(2 * CHUNK_SIZE + 1);
+        let body = stream(content.clone());
+        assert_eq!(body.size_hint().exact(), None);
+        assert_eq!(hyper::body::to_bytes(body).await.unwrap(), content);
+    }
+
+    #[tokio::test]
+    async fn it_reports_the_load_of_the_database() {
+        let (send, _receive) = db::channel(4);
+        let cache = Arc::new(CacheMetrics::default());
+        cache.hits.store(3, Ordering::Relaxed);
+        let send = send.with_cache_metrics(cache);
+        let request = Request::get(METRICS_PATH).body(Body::empty()).unwrap();
+        let response = respond(request, send, anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let metrics = body(response).await;
+        assert!(metrics.contains("\ngql_queue_depth 0\n"));
+        assert!(metrics.contains("\ngql_queue_capacity 4\n"));
+        assert!(metrics.contains("\ngql_requests_shed_total 0\n"));
+        assert!(metrics.contains("\ngql_result_cache_hits_total 3\n"));
+        assert!(metrics.contains("\ngql_result_cache_misses_total 0\n"));
+    }
+
+    #[tokio::test]
+    async fn it_sheds_load() {
+        let (send, _receive) = db::channel(1);
+        let _queued = tokio::spawn({
+            let send = send.clone();
+            async move { send.query(GraphQLRequest::new("{ a }")).await }
+        });
+        while send.queue_depth() == 0 {
+            tokio::task::yield_now().await;
+        }
+        let response = respond(post(r#"{"query": "{ b }"}"#), send, anonymous())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[RETRY_AFTER], "1");
+        assert_eq!(
+            body(response).await,
+            r#"{"errors":[{"message":"The server is busy"}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn it_only_answers_safe_gets() {
+        let request = Request::get("/graphql?query=%7B%20me%20%7D&variables=%7B%7D")
+            .header(ACCEPT, "application/json")
+            .body(Body::empty())
+            .unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[CONTENT_TYPE], JSON);
+
+        let request = Request::get("/graphql?query=scalar%20Date")
+            .body(Body::empty())
+            .unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let request = Request::get("/graphql?query=%7B%20me%20%7D")
+            .header(ACCEPT, "text/html")
+            .body(Body::empty())
+            .unwrap();
+        let response = respond(request, database(), anonymous()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_ACCEPTABLE);
+    }
+
+    #[test]
+    fn it_reads_query_strings() {
+        assert_eq!(
+            from_query_string("query=%7B+me+%7D&operationName=Me&variables=%7B%22a%22%3A1%7D"),
+            Ok(GraphQLRequest {
+                variables: json!({ "a": 1 }).as_object().cloned(),
+                operation_name: Some("Me".into()),
+                ..GraphQLRequest::new("{ me }")
+            })
+        );
+        assert!(from_query_string("operationName=Me").is_err());
+        assert!(from_query_string("query=%7B+me+%7D&variables=1").is_err());
+    }
+}
@@ -1,7 +1,22 @@
+pub mod auth;
+mod compression;
 mod connection;
+pub mod db;
+#[doc(hidden)]
+pub mod fuzz;
 pub mod handlers;
+#[cfg(feature = "http")]
+pub mod http;
 mod message;
+pub mod request;
+pub mod shutdown;
+pub mod supervisor;
 pub mod tcp;
+#[cfg(feature = "ws")]
+pub mod ws;
+
+pub use db::{DbError, DbSender};
+pub use request::Request;
 
 #[cfg(test)]
 mod tests {
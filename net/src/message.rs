@@ -1,85 +1,142 @@
+use crate::compression::Compression;
 use bytes::BytesMut;
-use log::info;
+use std::borrow::Cow;
+use tracing::trace;
 
-#[derive(Debug, PartialEq)]
-pub enum Message {
-    Document { content: String, byte_len: usize },
-}
+/// The longest message a connection accepts, in bytes.
+pub const MAX_LENGTH: usize = 16 * 1024 * 1024;
+/// The size of the chunks large messages are written in.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+/// Set in the length prefix of a frame when the message goes on in the next frame.
+const MORE: u32 = 1 << 31;
 
-#[derive(Debug)]
-pub enum Error {
-    Incomplete(String),
-    System(crate::connection::Error),
+/// How the messages of a connection are delimited.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Framing {
+    /// Every message is preceded by its length in bytes, as a 4 byte big endian integer. A
+    /// message may be split over several frames, whose lengths have their highest bit set but for
+    /// the last one
+    LengthPrefixed,
+    /// Every message ends with a newline, as older clients send them
+    Lines,
 }
 
-impl Message {
-    pub fn ready(cursor: &BytesMut) -> Result<(), Error> {
-        if cursor.iter().find(|&&b| b == b'{').is_some() {
-            Message::check_balanced_braces(cursor)
-        // } else if cursor.iter().find(|&&b| b == b'\n').is_some() {
-        //     Ok(())
+impl Framing {
+    /// Tells the framing of a connection from the first byte it receives. Messages are shorter
+    /// than 16MiB, so the first byte of a length prefix is zero but for the continuation bit,
+    /// which no line of text starts with.
+    pub fn detect(first: u8) -> Framing {
+        if first & !(MORE >> 24) as u8 == 0 {
+            Framing::LengthPrefixed
         } else {
-            Err(Error::Incomplete(String::from(
-                "Message currently not ready",
-            )))
+            Framing::Lines
         }
     }
 
-    fn check_balanced_braces(cursor: &BytesMut) -> Result<(), Error> {
-        let mut stop_flag = false;
-        let unmatched_braces = cursor.iter().fold(0, |count, b| {
-            if stop_flag {
-                count
-            } else if *b == b'{' {
-                count + 1
-            } else if *b == b'}' {
-                let new_count = count - 1;
-                if new_count == 0 {
-                    stop_flag = true;
+    /// Frames a chunk of a message to be written, `last` telling whether the message ends with
+    /// it. Chunks must be shorter than 2GiB.
+    pub fn encode_chunk(self, chunk: &[u8], last: bool) -> Vec<u8> {
+        match self {
+            Framing::LengthPrefixed => {
+                let mut frame = Vec::with_capacity(4 + chunk.len());
+                let more = if last { 0 } else { MORE };
+                frame.extend_from_slice(&(chunk.len() as u32 | more).to_be_bytes());
+                frame.extend_from_slice(chunk);
+                frame
+            }
+            Framing::Lines => {
+                let mut frame = Vec::with_capacity(chunk.len() + 1);
+                frame.extend_from_slice(chunk);
+                if last {
+                    frame.push(b'\n');
                 }
-                count - 1
-            } else {
-                count
+                frame
             }
-        });
-        if unmatched_braces > 0 || !stop_flag {
-            Err(Error::Incomplete(String::from(
-                "Unmatched braces. Message currently not ready",
-            )))
-        } else {
-            Ok(())
         }
     }
+}
 
-    pub fn parse(cursor: &BytesMut) -> Result<Message, Error> {
-        let mut last_closed: usize = 0;
-        let mut first_closed: usize = 0;
-        cursor.iter().fold((0, 0), |(index, unmatched), b| {
-            if *b == b'{' {
-                (index + 1, unmatched + 1)
-            } else if *b == b'}' {
-                let new_unmatched = unmatched - 1;
-                if new_unmatched == 0 {
-                    last_closed = index + 1;
-                    if first_closed == 0 {
-                        first_closed = last_closed;
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Document { content: String, byte_len: usize },
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Incomplete(String),
+    TooLarge(usize),
+    System(crate::connection::Error),
+}
+
+impl Message {
+    /// Splits the first message off the buffer, decompressing it if the connection is
+    /// compressed. `byte_len` counts its framing too.
+    pub fn parse(
+        cursor: &BytesMut,
+        framing: Framing,
+        compression: Option<Compression>,
+    ) -> Result<Message, Error> {
+        let (slice, byte_len) = match framing {
+            Framing::LengthPrefixed => {
+                let mut content = Vec::new();
+                let mut offset = 0;
+                loop {
+                    if cursor.len() < offset + 4 {
+                        return Err(Error::Incomplete(String::from("Length not received")));
+                    }
+                    let prefix = u32::from_be_bytes([
+                        cursor[offset],
+                        cursor[offset + 1],
+                        cursor[offset + 2],
+                        cursor[offset + 3],
+                    ]);
+                    let length = (prefix & !MORE) as usize;
+                    if content.len() + length > MAX_LENGTH {
+                        return Err(Error::TooLarge(content.len() + length));
+                    }
+                    let start = offset + 4;
+                    if cursor.len() < start + length {
+                        return Err(Error::Incomplete(String::from(
+                            "Message currently not ready",
+                        )));
+                    }
+                    content.extend_from_slice(&cursor[start..start + length]);
+                    offset = start + length;
+                    if prefix & MORE == 0 {
+                        break;
                     }
                 }
-                (index + 1, new_unmatched)
-            } else {
-                (index + 1, unmatched)
+                (Cow::Owned(content), offset)
             }
-        });
-        let slice = match cursor[0] {
-            b'{' => &cursor[..first_closed],
-            _ => &cursor[..last_closed],
+            Framing::Lines => match cursor.iter().position(|&b| b == b'\n') {
+                Some(end) => {
+                    let line = &cursor[..end];
+                    let line = line.strip_suffix(b"\r").unwrap_or(line);
+                    (Cow::Borrowed(line), end + 1)
+                }
+                None if cursor.len() > MAX_LENGTH => return Err(Error::TooLarge(cursor.len())),
+                None => {
+                    return Err(Error::Incomplete(String::from(
+                        "No newline. Message currently not ready",
+                    )))
+                }
+            },
         };
-        info!("Last index of closed brace: {}", last_closed);
-        info!("Slice: {:?}", slice);
-        match std::str::from_utf8(slice) {
+        trace!(bytes = slice.len(), "Frame split off the buffer");
+        let slice = match compression {
+            Some(compression) => match compression.decompress(&slice, MAX_LENGTH) {
+                Ok(content) if content.len() > MAX_LENGTH => {
+                    return Err(Error::TooLarge(content.len()))
+                }
+                Ok(content) => Cow::Owned(content),
+                Err(e) => return Err(Error::System(e.into())),
+            },
+            None => slice,
+        };
+        match std::str::from_utf8(&slice) {
             Ok(content) => Ok(Message::Document {
                 content: String::from(content),
-                byte_len: slice.len(),
+                byte_len,
             }),
             Err(e) => Err(Error::System(e.into())),
         }
@@ -92,109 +149,129 @@ mod tests {
     use bytes::BytesMut;
 
     #[test]
-    fn it_checks_for_an_open_brace() {
-        let buf = BytesMut::from("{}");
-        assert!(Message::ready(&buf).is_ok());
-
-        let buf = BytesMut::from("type Object");
-        assert!(Message::ready(&buf).is_err());
-    }
-
-    // #[test]
-    // fn it_checks_for_a_new_line_if_no_brace() {
-    //     let buf = BytesMut::from("scalar Date\n");
-    //     assert!(Message::ready(&buf).is_ok());
-
-    //     let buf = BytesMut::from("union Pet = Dog | Cat |");
-    //     assert!(Message::ready(&buf).is_err());
-    // }
-
-    #[test]
-    fn it_checks_that_all_braces_are_paired() {
-        let buf = BytesMut::from("{ user { }");
-        assert!(Message::ready(&buf).is_err());
+    fn it_detects_the_framing() {
+        assert_eq!(Framing::detect(0), Framing::LengthPrefixed);
+        assert_eq!(Framing::detect(0x80), Framing::LengthPrefixed);
+        assert_eq!(Framing::detect(b'{'), Framing::Lines);
     }
 
     #[test]
-    fn it_checks_that_only_first_brace_must_be_paired() {
-        let buf = BytesMut::from("type User { name: String, email: Address }\ntype Address {\n");
-        assert!(Message::ready(&buf).is_ok());
+    fn it_does_not_parse_an_empty_buffer() {
+        let buf = BytesMut::new();
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, None),
+            Err(Error::Incomplete(_))
+        ));
+        assert!(matches!(
+            Message::parse(&buf, Framing::Lines, None),
+            Err(Error::Incomplete(_))
+        ));
     }
 
     #[test]
-    fn it_parses_a_message() {
-        let buf = BytesMut::from("type User {\n name: String,\n email: Email,\n}");
-        let parsed = Message::parse(&buf);
-        assert!(parsed.is_ok());
+    fn it_parses_a_length_prefixed_message() {
+        let mut buf =
+            BytesMut::from(&Framing::LengthPrefixed.encode_chunk(br#"{ a(b: "}") }"#, true)[..]);
+        buf.extend_from_slice(&[0, 0, 0]);
         assert_eq!(
-            parsed.unwrap(),
+            Message::parse(&buf, Framing::LengthPrefixed, None).unwrap(),
             Message::Document {
-                content: String::from_utf8(buf.to_vec()).unwrap(),
-                byte_len: buf.len(),
+                content: String::from(r#"{ a(b: "}") }"#),
+                byte_len: 17,
             }
         );
     }
 
     #[test]
-    fn it_only_parses_complete_messages() {
-        let buf = BytesMut::from(
-            r#"
-type User {
-    name: String
-    email: Email
-}
+    fn it_only_parses_complete_frames() {
+        let buf = BytesMut::from(&[0, 0, 0, 5, b'{', b' ', b'a'][..]);
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, None),
+            Err(Error::Incomplete(_))
+        ));
 
-type Admin {
-    user: User
-    priveledges: [Priviledges]!
-}
+        let buf = BytesMut::from(&[1, 0, 0, 1][..]);
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, None),
+            Err(Error::TooLarge(16_777_217))
+        ));
+    }
 
-type Incomplete {
-"#,
-        );
-        let parsed = Message::parse(&buf);
-        assert!(parsed.is_ok());
+    #[test]
+    fn it_parses_messages_split_over_frames() {
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&Framing::LengthPrefixed.encode_chunk(b"{ a ", false));
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, None),
+            Err(Error::Incomplete(_))
+        ));
+        buf.extend_from_slice(&Framing::LengthPrefixed.encode_chunk(b"", false));
+        buf.extend_from_slice(&Framing::LengthPrefixed.encode_chunk(b"b }", true));
+        buf.extend_from_slice(&Framing::LengthPrefixed.encode_chunk(b"{ c }", true));
         assert_eq!(
-            parsed.unwrap(),
+            Message::parse(&buf, Framing::LengthPrefixed, None).unwrap(),
             Message::Document {
-                content: String::from(
-                    r#"
-type User {
-    name: String
-    email: Email
-}
+                content: String::from("{ a b }"),
+                byte_len: 19,
+            }
+        );
 
-type Admin {
-    user: User
-    priveledges: [Priviledges]!
-}"#
-                ),
-                byte_len: 111
+        let mut buf = BytesMut::new();
+        buf.extend_from_slice(&Framing::Lines.encode_chunk(b"{ a ", false));
+        buf.extend_from_slice(&Framing::Lines.encode_chunk(b"b }", true));
+        assert_eq!(
+            Message::parse(&buf, Framing::Lines, None).unwrap(),
+            Message::Document {
+                content: String::from("{ a b }"),
+                byte_len: 8,
             }
         );
     }
 
     #[test]
-    fn it_only_parses_a_query() {
-        let buf = BytesMut::from(
-            r#"{ user { name, email, permissions(role: "admin") { home, isSudo, } } }
-
-type Login {
-    user: User,
-    expiry: DateTime,
-}
-"#,
+    fn it_decompresses_messages() {
+        let compressed = Compression::Zstd.compress(b"{ a }").unwrap();
+        let buf = BytesMut::from(&Framing::LengthPrefixed.encode_chunk(&compressed, true)[..]);
+        assert_eq!(
+            Message::parse(&buf, Framing::LengthPrefixed, Some(Compression::Zstd)).unwrap(),
+            Message::Document {
+                content: String::from("{ a }"),
+                byte_len: 4 + compressed.len(),
+            }
         );
-        let parsed = Message::parse(&buf);
-        assert!(parsed.is_ok());
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, Some(Compression::Gzip)),
+            Err(Error::System(_))
+        ));
+
+        let bomb = Compression::Gzip
+            .compress(&vec![b' '; MAX_LENGTH + 1])
+            .unwrap();
+        let buf = BytesMut::from(&Framing::LengthPrefixed.encode_chunk(&bomb, true)[..]);
+        assert!(matches!(
+            Message::parse(&buf, Framing::LengthPrefixed, Some(Compression::Gzip)),
+            Err(Error::TooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn it_parses_lines() {
+        let buf = BytesMut::from("type User { description: \"{not a block}\" }\r\n{ user }\n");
         assert_eq!(
-            parsed.unwrap(),
+            Message::parse(&buf, Framing::Lines, None).unwrap(),
             Message::Document {
-                content: String::from(
-                    "{ user { name, email, permissions(role: \"admin\") { home, isSudo, } } }"
-                ),
-                byte_len: 70,
+                content: String::from("type User { description: \"{not a block}\" }"),
+                byte_len: 44,
             }
         );
+
+        let buf = BytesMut::from("{ user {\n");
+        assert!(Message::parse(&buf, Framing::Lines, None).is_ok());
+
+        let buf = BytesMut::from("{ user }");
+        assert!(matches!(
+            Message::parse(&buf, Framing::Lines, None),
+            Err(Error::Incomplete(_))
+        ));
     }
 }
@@ -0,0 +1,263 @@
+//! The requests the transports hand to the database.
+//!
+//! Every transport reads the same parameters, the ones of GraphQL over HTTP: a `query`, and
+//! optionally `variables` and an `operationName`. Transports that can have several requests in
+//! flight also give each one an `id`. On TCP a request is sent as a JSON object holding these
+//! fields, and its reply comes back as a JSON object with the same `id` and the reply of the
+//! database as `payload`.
+//!
+//! Several requests can also be sent together, as a JSON array of them in a single message or
+//! HTTP body, as Apollo batches them. The database runs them concurrently, and their replies come
+//! back in the same order, as a JSON array.
+//!
+//! A server may hold several graphs, each with its own schema and records. The transports route
+//! requests to one of them: the graph named in the handshake on TCP, `{"handshake": {"graph":
+//! "staging"}}`, in the payload of `connection_init` on WebSocket, and in the `X-Gql-Graph` header
+//! of every HTTP request. Requests naming no graph go to the default one.
+
+use crate::auth::Identity;
+use serde_json::{json, Map, Number, Value};
+use std::fmt;
+
+/// The id of a request, sent back in its reply with the JSON type it was sent with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RequestId {
+    /// An id sent as a string
+    String(String),
+    /// An id sent as a number
+    Number(Number),
+}
+
+impl RequestId {
+    /// The id held by a JSON value, if it is a string or a number.
+    pub fn from_value(value: &Value) -> Option<RequestId> {
+        match value {
+            Value::String(id) => Some(RequestId::String(id.clone())),
+            Value::Number(id) => Some(RequestId::Number(id.clone())),
+            _ => None,
+        }
+    }
+
+    /// The id as JSON, as it was sent.
+    pub fn to_value(&self) -> Value {
+        match self {
+            RequestId::String(id) => Value::String(id.clone()),
+            RequestId::Number(id) => Value::Number(id.clone()),
+        }
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(id: &str) -> Self {
+        RequestId::String(id.to_owned())
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestId::String(id) => f.write_str(id),
+            RequestId::Number(id) => write!(f, "{}", id),
+        }
+    }
+}
+
+/// A GraphQL request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    /// Identifies the request among the ones in flight on a connection
+    pub id: Option<RequestId>,
+    /// The GraphQL document
+    pub query: String,
+    /// The values of the variables of the operation
+    pub variables: Option<Map<String, Value>>,
+    /// The operation to run, when the document has several
+    pub operation_name: Option<String>,
+    /// Who sent the request, unless it is anonymous. Set by the transport, never read from the
+    /// request itself
+    pub identity: Option<Identity>,
+    /// The graph the request is for, the default one when not set. Set by the transport, like
+    /// the identity
+    pub graph: Option<String>,
+}
+
+impl Request {
+    /// A request for a document, without variables.
+    pub fn new(query: &str) -> Request {
+        Request {
+            id: None,
+            query: query.to_owned(),
+            variables: None,
+            operation_name: None,
+            identity: None,
+            graph: None,
+        }
+    }
+
+    /// Reads a request from a JSON object. `null` fields count as missing.
+    pub fn from_value(value: &Value) -> Result<Request, String> {
+        let parameters = value
+            .as_object()
+            .ok_or_else(|| String::from("The request must be a JSON object"))?;
+        let query = match parameters.get("query") {
+            Some(Value::String(query)) => query.clone(),
+            _ => return Err("query must be a string".into()),
+        };
+        let id = match parameters.get("id") {
+            None | Some(Value::Null) => None,
+            Some(id) => Some(
+                RequestId::from_value(id)
+                    .ok_or_else(|| String::from("id must be a string or a number"))?,
+            ),
+        };
+        let variables = match parameters.get("variables") {
+            None | Some(Value::Null) => None,
+            Some(Value::Object(variables)) => Some(variables.clone()),
+            Some(_) => return Err("variables must be an object".into()),
+        };
+        let operation_name = match parameters.get("operationName") {
+            None | Some(Value::Null) => None,
+            Some(Value::String(name)) => Some(name.clone()),
+            Some(_) => return Err("operationName must be a string".into()),
+        };
+        Ok(Request {
+            id,
+            query,
+            variables,
+            operation_name,
+            identity: None,
+            graph: None,
+        })
+    }
+
+    /// Reads a request from JSON text.
+    pub fn from_json(json: &str) -> Result<Request, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        Request::from_value(&value)
+    }
+}
+
+/// The requests sent in one message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Batch {
+    /// A request sent on its own
+    Single(Request),
+    /// Requests sent as an array, whose replies are sent back as an array in the same order
+    Many(Vec<Request>),
+}
+
+impl Batch {
+    /// Reads a request from a JSON object, or a batch of them from an array.
+    pub fn from_value(value: &Value) -> Result<Batch, String> {
+        match value {
+            Value::Array(requests) if requests.is_empty() => {
+                Err("A batch must hold at least one request".into())
+            }
+            Value::Array(requests) => requests
+                .iter()
+                .map(Request::from_value)
+                .collect::<Result<_, _>>()
+                .map(Batch::Many),
+            _ => Request::from_value(value).map(Batch::Single),
+        }
+    }
+
+    /// Attaches the identity of the client to every request.
+    pub fn identify(&mut self, identity: Option<&Identity>) {
+        match self {
+            Batch::Single(request) => request.identity = identity.cloned(),
+            Batch::Many(requests) => {
+                for request in requests {
+                    request.identity = identity.cloned();
+                }
+            }
+        }
+    }
+
+    /// Routes every request to a graph, the default one when `None`.
+    pub fn route(&mut self, graph: Option<&str>) {
+        match self {
+            Batch::Single(request) => request.graph = graph.map(String::from),
+            Batch::Many(requests) => {
+                for request in requests {
+                    request.graph = graph.map(String::from);
+                }
+            }
+        }
+    }
+}
+
+/// The reply of the database as JSON: replies that are JSON are embedded as they are, others as
+/// a string.
+pub fn payload(reply: String) -> Value {
+    serde_json::from_str(&reply).unwrap_or(Value::String(reply))
+}
+
+/// Wraps the reply of the database to the request with the id `id` in a JSON object carrying
+/// that id. Replies that are JSON are embedded as they are, others as a string.
+pub fn envelope(id: Option<&RequestId>, reply: String) -> String {
+    let id = id.map_or(Value::Null, RequestId::to_value);
+    json!({ "id": id, "payload": payload(reply) }).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_reads_requests() {
+        let request = Request::from_json(
+            r#"{"id": 3, "query": "query Q($a: Int) { a(a: $a) }", "variables": {"a": 1}, "operationName": "Q"}"#,
+        )
+        .unwrap();
+        assert_eq!(request.id, Some(RequestId::Number(3.into())));
+        assert_eq!(request.query, "query Q($a: Int) { a(a: $a) }");
+        assert_eq!(request.variables.unwrap()["a"], 1);
+        assert_eq!(request.operation_name, Some("Q".into()));
+
+        let request = Request::from_json(r#"{"query": "{ a }", "variables": null}"#).unwrap();
+        assert_eq!(request, Request::new("{ a }"));
+
+        assert!(Request::from_json(r#"{"variables": {}}"#).is_err());
+        assert!(Request::from_json(r#"{"query": "{ a }", "variables": []}"#).is_err());
+        assert!(Request::from_json(r#"{"query": "{ a }", "id": {}}"#).is_err());
+        let request = Request::from_json(r#"{"query": "{ a }", "id": "3"}"#).unwrap();
+        assert_eq!(request.id, Some(RequestId::from("3")));
+        assert!(Request::from_json("{ a }").is_err());
+    }
+
+    #[test]
+    fn it_reads_batches() {
+        let value = serde_json::from_str(r#"{"query": "{ a }"}"#).unwrap();
+        assert_eq!(
+            Batch::from_value(&value),
+            Ok(Batch::Single(Request::new("{ a }")))
+        );
+
+        let value = serde_json::from_str(r#"[{"query": "{ a }"}, {"query": "{ b }"}]"#).unwrap();
+        assert_eq!(
+            Batch::from_value(&value).unwrap(),
+            Batch::Many(vec![Request::new("{ a }"), Request::new("{ b }")])
+        );
+
+        let value = serde_json::from_str(r#"[{"query": "{ a }"}, {}]"#).unwrap();
+        assert!(Batch::from_value(&value).is_err());
+        assert!(Batch::from_value(&Value::Array(vec![])).is_err());
+    }
+
+    #[test]
+    fn it_wraps_replies() {
+        assert_eq!(
+            envelope(None, "Received input".into()),
+            r#"{"id":null,"payload":"Received input"}"#
+        );
+        assert_eq!(
+            envelope(Some(&"1".into()), r#"{"data": {"a": 1}}"#.into()),
+            r#"{"id":"1","payload":{"data":{"a":1}}}"#
+        );
+        assert_eq!(
+            envelope(Some(&RequestId::Number(1.into())), "{}".into()),
+            r#"{"id":1,"payload":{}}"#
+        );
+    }
+}
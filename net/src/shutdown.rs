@@ -0,0 +1,73 @@
+//! Tells handlers when the server is shutting down.
+//!
+//! [`channel`] returns a [`Trigger`] and a [`Shutdown`] to clone into every handler. Once the
+//! trigger fires, or is dropped, handlers stop accepting connections, let their connections
+//! finish the requests in flight, and return.
+//!
+//! [`channel`]: fn.channel.html
+//! [`Trigger`]: struct.Trigger.html
+//! [`Shutdown`]: struct.Shutdown.html
+
+use tokio::sync::watch;
+
+/// Starts the shutdown.
+#[derive(Debug)]
+pub struct Trigger(watch::Sender<bool>);
+
+/// Waits for the shutdown.
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+pub fn channel() -> (Trigger, Shutdown) {
+    let (send, receive) = watch::channel(false);
+    (Trigger(send), Shutdown(receive))
+}
+
+impl Trigger {
+    pub fn trigger(&self) {
+        let _ = self.0.send(true);
+    }
+}
+
+impl Shutdown {
+    /// Returns true once the shutdown has started.
+    pub fn is_triggered(&self) -> bool {
+        *self.0.borrow() || self.0.has_changed().is_err()
+    }
+
+    /// Waits until the shutdown starts. Waiting can be cancelled and started again.
+    pub async fn wait(&mut self) {
+        while !*self.0.borrow_and_update() {
+            if self.0.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn it_waits_for_the_trigger() {
+        let (trigger, mut shutdown) = channel();
+        let mut other = shutdown.clone();
+        assert!(!shutdown.is_triggered());
+        assert!(timeout(Duration::from_millis(10), shutdown.wait())
+            .await
+            .is_err());
+
+        trigger.trigger();
+        assert!(shutdown.is_triggered());
+        shutdown.wait().await;
+        other.wait().await;
+
+        let (trigger, mut shutdown) = channel();
+        drop(trigger);
+        assert!(shutdown.is_triggered());
+        shutdown.wait().await;
+    }
+}
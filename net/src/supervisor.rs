@@ -0,0 +1,109 @@
+//! Keeps connections from holding on to the server forever.
+//!
+//! A [`Supervisor`] admits at most [`Limits::max_connections`] connections at a time. Handlers
+//! wait for a permit before accepting a connection, and hold it until the connection is closed,
+//! so that clients over the limit wait in the backlog of the listener. The other limits are the
+//! timeouts handlers apply to each connection.
+//!
+//! [`Supervisor`]: struct.Supervisor.html
+//! [`Limits::max_connections`]: struct.Limits.html#structfield.max_connections
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// The limits put on the connections of a handler.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// The number of connections open at once
+    pub max_connections: usize,
+    /// How long a connection may stay open without a request in flight
+    pub idle_timeout: Duration,
+    /// How long the rest of a message may take to arrive once its first bytes did
+    pub read_timeout: Duration,
+    /// How long the database may take to answer a request
+    pub execute_timeout: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_connections: 1024,
+            idle_timeout: Duration::from_secs(300),
+            read_timeout: Duration::from_secs(30),
+            execute_timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Admits connections up to a limit.
+#[derive(Debug, Clone)]
+pub struct Supervisor {
+    limits: Limits,
+    connections: Arc<Semaphore>,
+}
+
+/// Held by a connection while it is open.
+pub type Admission = OwnedSemaphorePermit;
+
+impl Supervisor {
+    pub fn new(limits: Limits) -> Self {
+        Supervisor {
+            limits,
+            connections: Arc::new(Semaphore::new(limits.max_connections)),
+        }
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Waits until a connection may be opened.
+    pub async fn admit(&self) -> Admission {
+        self.connections
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("The semaphore of a supervisor is never closed")
+    }
+
+    /// Waits until every connection admitted has been closed.
+    pub async fn drain(&self) {
+        let all = self.limits.max_connections as u32;
+        let _ = self.connections.acquire_many(all).await;
+    }
+
+    /// The number of connections currently admitted.
+    pub fn open_connections(&self) -> usize {
+        self.limits.max_connections - self.connections.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn it_admits_connections_up_to_the_limit() {
+        let supervisor = Supervisor::new(Limits {
+            max_connections: 2,
+            ..Limits::default()
+        });
+        let first = supervisor.admit().await;
+        let second = supervisor.admit().await;
+        assert_eq!(supervisor.open_connections(), 2);
+
+        let waiting = tokio::time::timeout(Duration::from_millis(10), supervisor.admit()).await;
+        assert!(waiting.is_err());
+
+        drop(first);
+        assert_eq!(supervisor.open_connections(), 1);
+        let third = tokio::time::timeout(Duration::from_millis(10), supervisor.admit()).await;
+        assert!(third.is_ok());
+
+        let draining = tokio::time::timeout(Duration::from_millis(10), supervisor.drain()).await;
+        assert!(draining.is_err());
+        drop((second, third));
+        supervisor.drain().await;
+    }
+}
@@ -1,55 +1,339 @@
-use log::{debug, info};
 use tokio;
-use tokio::io;
-use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{mpsc::Sender, oneshot};
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::{self, Duration, Instant};
+use tracing::{debug, info, info_span, Instrument};
 
+use crate::auth::SharedAuthenticator;
 use crate::connection::Connection;
+use crate::request::{envelope, Batch, Request};
+use crate::shutdown::Shutdown;
+use crate::supervisor::{Limits, Supervisor};
+use crate::DbSender;
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Result<T> = std::result::Result<T, Error>;
 
-type DbSender = Sender<(String, oneshot::Sender<String>)>;
+/// The number of messages a connection may have in flight before their replies are written.
+const IN_FLIGHT: usize = 64;
 
-async fn handle_connection(mut conn: Connection<TcpStream>, send: DbSender) -> io::Result<()> {
+/// Serves the requests of a connection. Requests are read while earlier ones are still running,
+/// and each reply is written as soon as it is ready, so replies may come back in another order
+/// than their requests: clients with several requests in flight tell them apart by `id`. The
+/// replies to a batch come back together, in the order of its requests.
+///
+/// The connection is closed once it has gone `idle_timeout` without a request in flight, or once
+/// the server shuts down and the requests in flight are answered.
+async fn handle_connection<T>(
+    mut conn: Connection<T>,
+    send: DbSender,
+    limits: Limits,
+    mut shutdown: Shutdown,
+) -> io::Result<()>
+where
+    T: AsyncRead + AsyncWrite,
+{
+    conn.set_read_timeout(limits.read_timeout);
+    let (replies_send, mut replies) = mpsc::channel::<String>(IN_FLIGHT);
+    let mut in_flight = 0;
+    let mut reading = true;
+    let mut last_activity = Instant::now();
     loop {
-        match conn.read_message().await {
-            Ok(Some(content)) => {
-                let (send_one, receive_one) = oneshot::channel();
-                match send.send((content, send_one)).await.ok() {
-                    Some(()) => info!("Sent to database successfully"),
-                    None => info!("Send was unsuccessful"),
-                };
-                match receive_one.await {
-                    Ok(response) => {
-                        conn.write_message(&response).await?;
-                    }
-                    Err(e) => info!("Error from db: {}", e),
-                };
+        tokio::select! {
+            message = conn.read_message(), if reading && in_flight < IN_FLIGHT => match message {
+                Ok(Some(batch)) => {
+                    in_flight += 1;
+                    last_activity = Instant::now();
+                    let replies = replies_send.clone();
+                    let running = run_batch(batch, send.clone(), replies, limits.execute_timeout);
+                    tokio::spawn(running.in_current_span());
+                }
+                Ok(None) => {
+                    debug!("Connection closed");
+                    reading = false;
+                }
+                Err(e) => {
+                    info!(error = %e, "Error reading from connection");
+                    reading = false;
+                }
+            },
+            Some(reply) = replies.recv(), if in_flight > 0 => {
+                in_flight -= 1;
+                last_activity = Instant::now();
+                conn.write_message(&reply).await?;
             }
-            Ok(None) => {
-                debug!("Message not read");
+            _ = time::sleep_until(last_activity + limits.idle_timeout), if reading && in_flight == 0 => {
+                info!("Closing idle connection");
+                break;
             }
-            Err(_) => break,
-        };
+            _ = shutdown.wait(), if reading => {
+                debug!(in_flight, "Shutting down");
+                reading = false;
+            }
+            else => break,
+        }
     }
     Ok(())
 }
 
-pub async fn handle_tcp(port: u32, send: DbSender) -> io::Result<()> {
+/// Sends the requests of a message to the database, and hands their replies back to the
+/// connection, wrapped with the ids of the requests. The requests of a batch run concurrently.
+async fn run_batch(batch: Batch, send: DbSender, replies: mpsc::Sender<String>, timeout: Duration) {
+    let reply = match batch {
+        Batch::Single(request) => run_request(request, send, timeout).await,
+        Batch::Many(requests) => {
+            let running: Vec<_> = requests
+                .into_iter()
+                .map(|request| {
+                    tokio::spawn(run_request(request, send.clone(), timeout).in_current_span())
+                })
+                .collect();
+            let mut envelopes = Vec::with_capacity(running.len());
+            for request in running {
+                match request.await {
+                    Ok(envelope) => envelopes.push(envelope),
+                    Err(e) => envelopes.push(envelope(None, error(&e.to_string()))),
+                }
+            }
+            format!("[{}]", envelopes.join(","))
+        }
+    };
+    let _ = replies.send(reply).await;
+}
+
+/// Sends a request to the database, and wraps its reply with the id of the request. Requests the
+/// database does not answer within `timeout` get an error.
+async fn run_request(request: Request, send: DbSender, timeout: Duration) -> String {
+    let id = request.id.clone();
+    let reply = match time::timeout(timeout, send.query(request)).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(e)) => error(&e.to_string()),
+        Err(_) => {
+            info!(
+                id = id.as_ref().map(tracing::field::display),
+                "Request timed out"
+            );
+            error("The request timed out")
+        }
+    };
+    envelope(id.as_ref(), reply)
+}
+
+/// A reply holding a single GraphQL error.
+fn error(message: &str) -> String {
+    serde_json::json!({ "errors": [{ "message": message }] }).to_string()
+}
+
+/// Serves TCP connections on `port` until the shutdown, then waits for them to be closed. Clients
+/// are authenticated by `authenticator` from their first message.
+pub async fn handle_tcp(
+    port: u32,
+    send: DbSender,
+    limits: Limits,
+    authenticator: SharedAuthenticator,
+    mut shutdown: Shutdown,
+) -> io::Result<()> {
     let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
+    let supervisor = Supervisor::new(limits);
 
     loop {
-        match listener.accept().await {
-            Ok((stream, _)) => {
+        let accepted = tokio::select! {
+            admission = supervisor.admit() => tokio::select! {
+                accepted = listener.accept() => accepted.map(|accepted| (accepted, admission)),
+                _ = shutdown.wait() => break,
+            },
+            _ = shutdown.wait() => break,
+        };
+        match accepted {
+            Ok(((stream, peer), admission)) => {
                 let sender = send.clone();
+                let shutdown = shutdown.clone();
+                let authenticator = authenticator.clone();
+                let span = info_span!("connection", protocol = "tcp", %peer);
                 tokio::spawn(
-                    async move { handle_connection(Connection::new(stream), sender).await },
+                    async move {
+                        debug!("Connection opened");
+                        let mut connection = Connection::new(stream);
+                        connection.set_authenticator(authenticator);
+                        let result = handle_connection(connection, sender, limits, shutdown).await;
+                        drop(admission);
+                        result
+                    }
+                    .instrument(span),
                 );
             }
             Err(e) => {
-                info!("Error getting connection: {}", e);
+                info!(error = %e, "Error getting connection");
             }
         }
     }
+    info!("Stopped accepting TCP connections");
+    drop(listener);
+    supervisor.drain().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+    use crate::shutdown;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// A database that answers every request with its query, after waiting as many milliseconds
+    /// as the query is long.
+    fn database() -> DbSender {
+        let (send, mut receive) = db::channel(8);
+        tokio::spawn(async move {
+            while let Some((request, response)) = receive.recv().await {
+                tokio::spawn(async move {
+                    let delay = request.query.len() as u64;
+                    tokio::time::sleep(Duration::from_millis(delay)).await;
+                    response.finish(request.query).await;
+                });
+            }
+        });
+        send
+    }
+
+    /// A shutdown that never starts.
+    fn running() -> Shutdown {
+        let (trigger, shutdown) = shutdown::channel();
+        std::mem::forget(trigger);
+        shutdown
+    }
+
+    #[tokio::test]
+    async fn it_answers_requests_as_they_are_ready() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let connection = tokio::spawn(handle_connection(
+            Connection::new(server),
+            database(),
+            Limits::default(),
+            running(),
+        ));
+
+        let slow =
+            r#"{"id": "slow", "query": "{ a b c d e f g h i j k l m n o p q r s t u v w x y z }"}"#;
+        let fast = r#"{"id": 2, "query": "{ a }"}"#;
+        // Empty messages, like the leading blank line, are skipped
+        let requests = format!("\n{}\n{}\n", slow, fast);
+        client.write_all(requests.as_bytes()).await.unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut replies = String::new();
+        client.read_to_string(&mut replies).await.unwrap();
+        assert_eq!(
+            replies,
+            concat!(
+                r#"{"id":2,"payload":"{ a }"}"#,
+                "\n",
+                r#"{"id":"slow","payload":"{ a b c d e f g h i j k l m n o p q r s t u v w x y z }"}"#,
+                "\n"
+            )
+        );
+        assert!(connection.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_answers_batches_in_order() {
+        let (mut client, server) = tokio::io::duplex(1024);
+        let connection = tokio::spawn(handle_connection(
+            Connection::new(server),
+            database(),
+            Limits::default(),
+            running(),
+        ));
+
+        let batch =
+            r#"[{"id": 1, "query": "{ a b c d e f g h i j k }"}, {"id": 2, "query": "{ a }"}]"#;
+        client
+            .write_all(format!("{}\n", batch).as_bytes())
+            .await
+            .unwrap();
+        client.shutdown().await.unwrap();
+
+        let mut replies = String::new();
+        client.read_to_string(&mut replies).await.unwrap();
+        assert_eq!(
+            replies,
+            concat!(
+                r#"[{"id":1,"payload":"{ a b c d e f g h i j k }"},"#,
+                r#"{"id":2,"payload":"{ a }"}]"#,
+                "\n"
+            )
+        );
+        assert!(connection.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_times_out() {
+        let limits = Limits {
+            idle_timeout: Duration::from_millis(20),
+            read_timeout: Duration::from_millis(20),
+            execute_timeout: Duration::from_millis(20),
+            ..Limits::default()
+        };
+
+        let (_idle, server) = tokio::io::duplex(1024);
+        let connection = handle_connection(Connection::new(server), database(), limits, running());
+        assert!(time::timeout(Duration::from_secs(1), connection)
+            .await
+            .is_ok());
+
+        let (mut partial, server) = tokio::io::duplex(1024);
+        partial.write_all(b"{ a").await.unwrap();
+        let limits = Limits {
+            idle_timeout: Duration::from_secs(60),
+            ..limits
+        };
+        let connection = handle_connection(Connection::new(server), database(), limits, running());
+        assert!(time::timeout(Duration::from_secs(1), connection)
+            .await
+            .is_ok());
+
+        let (mut client, server) = tokio::io::duplex(1024);
+        tokio::spawn(handle_connection(
+            Connection::new(server),
+            database(),
+            limits,
+            running(),
+        ));
+        let slow =
+            r#"{"id": 1, "query": "{ a b c d e f g h i j k l m n o p q r s t u v w x y z }"}"#;
+        client
+            .write_all(format!("{}\n", slow).as_bytes())
+            .await
+            .unwrap();
+        let mut reply = vec![0; 67];
+        client.read_exact(&mut reply).await.unwrap();
+        assert_eq!(
+            String::from_utf8(reply).unwrap(),
+            r#"{"id":1,"payload":{"errors":[{"message":"The request timed out"}]}}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn it_answers_requests_in_flight_before_shutting_down() {
+        let (trigger, shutdown) = shutdown::channel();
+        let (mut client, server) = tokio::io::duplex(1024);
+        let connection = tokio::spawn(handle_connection(
+            Connection::new(server),
+            database(),
+            Limits::default(),
+            shutdown,
+        ));
+        client
+            .write_all(b"{\"id\": 1, \"query\": \"{ a b c d e f g }\"}\n")
+            .await
+            .unwrap();
+        time::sleep(Duration::from_millis(5)).await;
+        trigger.trigger();
+
+        let mut replies = String::new();
+        client.read_to_string(&mut replies).await.unwrap();
+        assert_eq!(replies, "{\"id\":1,\"payload\":\"{ a b c d e f g }\"}\n");
+        assert!(connection.await.unwrap().is_ok());
+    }
 }
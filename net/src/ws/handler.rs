@@ -0,0 +1,566 @@
+//! GraphQL over WebSocket, with the `graphql-transport-ws` subprotocol used by Apollo and urql.
+//!
+//! A client opens the connection with `connection_init`, which the server acknowledges with
+//! `connection_ack`. It then starts operations with `subscribe`, each under an id of its choosing.
+//! The server sends their results with `next`, followed by `complete` once an operation is done,
+//! or `error` when it cannot run it. A client stops an operation early with `complete`.
+//!
+//! Clients send their credentials in the payload of `connection_init`, as `{"token": "..."}`.
+//! Those the authenticator rejects are disconnected with `4403: Forbidden`. The payload may also
+//! name the graph the operations are for, as `{"graph": "staging"}`.
+//!
+//! An operation gets a `next` for every response the database streams for it: a single one for
+//! queries and mutations, one more for every fragment marked `@defer` and every item held back by
+//! `@stream`, and one for every event a subscription is answered for.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{self, AsyncRead, AsyncWrite};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode};
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{Error as WsError, Message};
+use tokio_tungstenite::WebSocketStream;
+use tracing::{debug, info, info_span, Instrument};
+
+use crate::auth::{AuthError, Credentials, SharedAuthenticator};
+use crate::request::Request as GraphQLRequest;
+use crate::shutdown::Shutdown;
+use crate::{DbError, DbSender};
+
+const SUBPROTOCOL: &str = "graphql-transport-ws";
+const SEC_WEBSOCKET_PROTOCOL: &str = "Sec-WebSocket-Protocol";
+/// How long a client has to send `connection_init` after connecting.
+const CONNECTION_INIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A message sent by the client.
+#[derive(Debug, PartialEq)]
+enum ClientMessage {
+    ConnectionInit { payload: Value },
+    Ping,
+    Pong,
+    Subscribe { id: String, request: GraphQLRequest },
+    Complete { id: String },
+}
+
+impl ClientMessage {
+    /// Reads a message, or returns `None` if it is not one of the protocol.
+    fn parse(text: &str) -> Option<ClientMessage> {
+        let message: Value = serde_json::from_str(text).ok()?;
+        let id = || message.get("id").and_then(Value::as_str).map(str::to_owned);
+        match message.get("type")?.as_str()? {
+            "connection_init" => Some(ClientMessage::ConnectionInit {
+                payload: message.get("payload").cloned().unwrap_or(Value::Null),
+            }),
+            "ping" => Some(ClientMessage::Ping),
+            "pong" => Some(ClientMessage::Pong),
+            "subscribe" => {
+                let id = id()?;
+                let request = GraphQLRequest::from_value(message.get("payload")?).ok()?;
+                Some(ClientMessage::Subscribe {
+                    request: GraphQLRequest {
+                        id: Some(id.as_str().into()),
+                        ..request
+                    },
+                    id,
+                })
+            }
+            "complete" => Some(ClientMessage::Complete { id: id()? }),
+            _ => None,
+        }
+    }
+}
+
+/// What a subscription task hands back to its connection.
+enum Outcome {
+    Next(Value),
+    Complete,
+    Error(String),
+}
+
+/// Serves WebSocket connections on `port` until the shutdown, then waits for them to be closed.
+pub async fn handle_ws(
+    port: u32,
+    send: DbSender,
+    authenticator: SharedAuthenticator,
+    mut shutdown: Shutdown,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(format!("127.0.0.1:{}", port)).await?;
+    let mut connections = JoinSet::new();
+
+    loop {
+        let accepted = tokio::select! {
+            accepted = listener.accept() => accepted,
+            Some(_) = connections.join_next() => continue,
+            _ = shutdown.wait() => break,
+        };
+        match accepted {
+            Ok((stream, peer)) => {
+                let sender = send.clone();
+                let shutdown = shutdown.clone();
+                let authenticator = authenticator.clone();
+                let span = info_span!("connection", protocol = "ws", %peer);
+                connections.spawn(
+                    async move {
+                        match tokio_tungstenite::accept_hdr_async(stream, negotiate).await {
+                            Ok(socket) => {
+                                let handled =
+                                    handle_connection(socket, sender, authenticator, shutdown);
+                                if let Err(e) = handled.await {
+                                    info!(error = %e, "WebSocket connection failed");
+                                }
+                            }
+                            Err(e) => info!(error = %e, "WebSocket handshake failed"),
+                        }
+                    }
+                    .instrument(span),
+                );
+            }
+            Err(e) => {
+                info!(error = %e, "Error getting connection");
+            }
+        }
+    }
+    info!("Stopped accepting WebSocket connections");
+    drop(listener);
+    while connections.join_next().await.is_some() {}
+    Ok(())
+}
+
+/// Accepts the handshakes of clients offering the `graphql-transport-ws` subprotocol.
+#[allow(clippy::result_large_err)]
+fn negotiate(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+    let offered = request
+        .headers()
+        .get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|protocol| protocol.trim() == SUBPROTOCOL);
+    if !offered {
+        let mut error = ErrorResponse::new(Some(format!("Only {} is supported", SUBPROTOCOL)));
+        *error.status_mut() = StatusCode::BAD_REQUEST;
+        return Err(error);
+    }
+    response.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static(SUBPROTOCOL),
+    );
+    Ok(response)
+}
+
+/// Runs the protocol on a connection. Once the server shuts down, new operations are refused,
+/// and the connection is closed when the operations in flight are done.
+async fn handle_connection<S>(
+    mut socket: WebSocketStream<S>,
+    send: DbSender,
+    authenticator: SharedAuthenticator,
+    mut shutdown: Shutdown,
+) -> Result<(), WsError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (outcomes_send, mut outcomes) = mpsc::channel::<(String, Outcome)>(16);
+    let mut subscriptions: HashMap<String, JoinHandle<()>> = HashMap::new();
+    let mut acknowledged = false;
+    let mut identity = None;
+    let mut graph = None;
+    let mut shutting_down = false;
+    let init_timeout = tokio::time::sleep(CONNECTION_INIT_TIMEOUT);
+    tokio::pin!(init_timeout);
+
+    let result = loop {
+        let message = tokio::select! {
+            _ = &mut init_timeout, if !acknowledged => {
+                break close(&mut socket, 4408, "Connection initialisation timeout").await;
+            }
+            _ = shutdown.wait(), if !shutting_down => {
+                shutting_down = true;
+                if subscriptions.is_empty() {
+                    break close(&mut socket, 1001, "Server shutting down").await;
+                }
+                continue;
+            }
+            Some((id, outcome)) = outcomes.recv() => {
+                // Operations the client completed in the meantime get nothing more
+                if !subscriptions.contains_key(&id) {
+                    continue;
+                }
+                let sent = match outcome {
+                    Outcome::Next(payload) => {
+                        write(&mut socket, json!({ "type": "next", "id": id, "payload": payload })).await
+                    }
+                    Outcome::Complete => {
+                        subscriptions.remove(&id);
+                        write(&mut socket, json!({ "type": "complete", "id": id })).await
+                    }
+                    Outcome::Error(message) => {
+                        subscriptions.remove(&id);
+                        let payload = json!([{ "message": message }]);
+                        write(&mut socket, json!({ "type": "error", "id": id, "payload": payload })).await
+                    }
+                };
+                match sent {
+                    Ok(()) if shutting_down && subscriptions.is_empty() => {
+                        break close(&mut socket, 1001, "Server shutting down").await;
+                    }
+                    Ok(()) => continue,
+                    Err(e) => break Err(e),
+                }
+            }
+            message = socket.next() => message,
+        };
+        let text = match message {
+            Some(Ok(Message::Text(text))) => text,
+            Some(Ok(Message::Close(_))) | None => break Ok(()),
+            // Control frames are answered by tungstenite itself
+            Some(Ok(Message::Ping(_))) | Some(Ok(Message::Pong(_))) => continue,
+            Some(Ok(_)) => break close(&mut socket, 4400, "Messages must be text").await,
+            Some(Err(e)) => break Err(e),
+        };
+        debug!(%text, "Message received");
+        let written = match ClientMessage::parse(&text) {
+            None => break close(&mut socket, 4400, "Invalid message received").await,
+            Some(ClientMessage::ConnectionInit { payload }) => {
+                if acknowledged {
+                    break close(&mut socket, 4429, "Too many initialisation requests").await;
+                }
+                let authenticated = Credentials::from_value(&payload)
+                    .map_err(AuthError::Rejected)
+                    .and_then(|credentials| authenticator.authenticate(credentials.as_ref()));
+                graph = payload["graph"].as_str().map(String::from);
+                match authenticated {
+                    Ok(authenticated) => identity = authenticated,
+                    Err(e) => {
+                        info!(reason = %e, "Client rejected");
+                        break close(&mut socket, 4403, "Forbidden").await;
+                    }
+                }
+                acknowledged = true;
+                write(&mut socket, json!({ "type": "connection_ack" })).await
+            }
+            Some(ClientMessage::Ping) => write(&mut socket, json!({ "type": "pong" })).await,
+            Some(ClientMessage::Pong) => Ok(()),
+            Some(ClientMessage::Subscribe { .. }) if !acknowledged => {
+                break close(&mut socket, 4401, "Unauthorized").await;
+            }
+            Some(ClientMessage::Subscribe { id, .. }) if shutting_down => {
+                let payload = json!([{ "message": "The server is shutting down" }]);
+                write(
+                    &mut socket,
+                    json!({ "type": "error", "id": id, "payload": payload }),
+                )
+                .await
+            }
+            Some(ClientMessage::Subscribe { id, request }) => {
+                if subscriptions.contains_key(&id) {
+                    let reason = format!("Subscriber for {} already exists", id);
+                    break close(&mut socket, 4409, &reason).await;
+                }
+                let request = GraphQLRequest {
+                    identity: identity.clone(),
+                    graph: graph.clone(),
+                    ..request
+                };
+                let outcomes = outcomes_send.clone();
+                let running = execute(id.clone(), request, send.clone(), outcomes);
+                let task = tokio::spawn(running.in_current_span());
+                subscriptions.insert(id, task);
+                Ok(())
+            }
+            Some(ClientMessage::Complete { id }) => {
+                if let Some(task) = subscriptions.remove(&id) {
+                    task.abort();
+                }
+                Ok(())
+            }
+        };
+        if let Err(e) = written {
+            break Err(e);
+        }
+    };
+    for task in subscriptions.values() {
+        task.abort();
+    }
+    result
+}
+
+/// Runs an operation against the database, and hands every response it streams back to the
+/// connection, then the outcome of the operation.
+async fn execute(
+    id: String,
+    request: GraphQLRequest,
+    send: DbSender,
+    outcomes: mpsc::Sender<(String, Outcome)>,
+) {
+    let started = match syntax::parse(&request.query) {
+        Err(e) => Err(e.to_string()),
+        Ok(_) => send.subscribe(request).map_err(|e| e.to_string()),
+    };
+    let mut replies = match started {
+        Ok(replies) => replies,
+        Err(message) => {
+            let _ = outcomes.send((id, Outcome::Error(message))).await;
+            return;
+        }
+    };
+    let mut answered = false;
+    while let Some(reply) = replies.recv().await {
+        answered = true;
+        // Replies that are not execution results yet are passed on as they are
+        let payload = serde_json::from_str(&reply).unwrap_or(Value::String(reply));
+        if outcomes
+            .send((id.clone(), Outcome::Next(payload)))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+    let outcome = if answered {
+        Outcome::Complete
+    } else {
+        Outcome::Error(DbError::NoResponse.to_string())
+    };
+    let _ = outcomes.send((id, outcome)).await;
+}
+
+async fn write<S>(socket: &mut WebSocketStream<S>, message: Value) -> Result<(), WsError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    socket.send(Message::Text(message.to_string())).await
+}
+
+async fn close<S>(socket: &mut WebSocketStream<S>, code: u16, reason: &str) -> Result<(), WsError>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    info!(code, reason, "Closing WebSocket connection");
+    socket
+        .close(Some(CloseFrame {
+            code: CloseCode::from(code),
+            reason: reason.to_owned().into(),
+        }))
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{Anonymous, Identity, Tokens};
+    use crate::db;
+    use crate::shutdown;
+    use std::sync::Arc;
+    use tokio::io::DuplexStream;
+    use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+
+    /// A database that answers every document with its length.
+    fn database() -> DbSender {
+        let (send, mut receive) = db::channel(8);
+        tokio::spawn(async move {
+            while let Some((request, response)) = receive.recv().await {
+                if request.query.contains("@defer") {
+                    response.send("{\"data\":{},\"hasNext\":true}".into()).await;
+                }
+                response
+                    .finish(format!("{{\"data\":{}}}", request.query.len()))
+                    .await;
+            }
+        });
+        send
+    }
+
+    /// Connects a client offering `protocol` to a server running on the other end of a pipe,
+    /// authenticating clients with `authenticator` until `shutdown`.
+    async fn connect_with(
+        protocol: &str,
+        authenticator: SharedAuthenticator,
+        shutdown: Shutdown,
+    ) -> Result<WebSocketStream<DuplexStream>, WsError> {
+        let (client, server) = tokio::io::duplex(4096);
+        tokio::spawn(async move {
+            if let Ok(socket) = tokio_tungstenite::accept_hdr_async(server, negotiate).await {
+                let _ = handle_connection(socket, database(), authenticator, shutdown).await;
+            }
+        });
+        let mut request = "ws://localhost/graphql".into_client_request().unwrap();
+        request.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_str(protocol).unwrap(),
+        );
+        let (socket, response) = tokio_tungstenite::client_async(request, client).await?;
+        assert_eq!(response.headers()[SEC_WEBSOCKET_PROTOCOL], SUBPROTOCOL);
+        Ok(socket)
+    }
+
+    async fn connect(protocol: &str) -> Result<WebSocketStream<DuplexStream>, WsError> {
+        let (trigger, shutdown) = shutdown::channel();
+        std::mem::forget(trigger);
+        connect_with(protocol, Arc::new(Anonymous), shutdown).await
+    }
+
+    async fn send(socket: &mut WebSocketStream<DuplexStream>, message: Value) {
+        socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .unwrap();
+    }
+
+    async fn receive(socket: &mut WebSocketStream<DuplexStream>) -> Value {
+        match socket.next().await {
+            Some(Ok(Message::Text(text))) => serde_json::from_str(&text).unwrap(),
+            other => panic!("Expected a text message, got {:?}", other),
+        }
+    }
+
+    async fn closed_with(socket: &mut WebSocketStream<DuplexStream>) -> u16 {
+        match socket.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => frame.code.into(),
+            other => panic!("Expected a close frame, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_parses_client_messages() {
+        assert_eq!(
+            ClientMessage::parse(r#"{"type": "connection_init", "payload": {}}"#),
+            Some(ClientMessage::ConnectionInit { payload: json!({}) })
+        );
+        assert_eq!(
+            ClientMessage::parse(
+                r#"{"type": "subscribe", "id": "1", "payload": {"query": "{ me }", "variables": {}}}"#
+            ),
+            Some(ClientMessage::Subscribe {
+                id: "1".into(),
+                request: GraphQLRequest {
+                    id: Some("1".into()),
+                    variables: Some(Default::default()),
+                    ..GraphQLRequest::new("{ me }")
+                }
+            })
+        );
+        assert_eq!(
+            ClientMessage::parse(r#"{"type": "subscribe", "id": "1"}"#),
+            None
+        );
+        assert_eq!(ClientMessage::parse(r#"{"type": "complete"}"#), None);
+        assert_eq!(ClientMessage::parse(r#"{"type": "start"}"#), None);
+        assert_eq!(ClientMessage::parse("ping"), None);
+    }
+
+    #[tokio::test]
+    async fn it_runs_operations() {
+        let mut socket = connect("graphql-ws, graphql-transport-ws").await.unwrap();
+        send(&mut socket, json!({ "type": "connection_init" })).await;
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "connection_ack" })
+        );
+        send(&mut socket, json!({ "type": "ping" })).await;
+        assert_eq!(receive(&mut socket).await, json!({ "type": "pong" }));
+
+        let subscribe = json!({ "type": "subscribe", "id": "a", "payload": { "query": "{ me }" } });
+        send(&mut socket, subscribe).await;
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "next", "id": "a", "payload": { "data": 6 } })
+        );
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "complete", "id": "a" })
+        );
+
+        let query = "{ ... @defer { me } }";
+        let subscribe = json!({ "type": "subscribe", "id": "c", "payload": { "query": query } });
+        send(&mut socket, subscribe).await;
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "next", "id": "c", "payload": { "data": {}, "hasNext": true } })
+        );
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "next", "id": "c", "payload": { "data": 21 } })
+        );
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "complete", "id": "c" })
+        );
+
+        let subscribe = json!({ "type": "subscribe", "id": "b", "payload": { "query": "{ me" } });
+        send(&mut socket, subscribe).await;
+        let error = receive(&mut socket).await;
+        assert_eq!(error["type"], "error");
+        assert_eq!(error["id"], "b");
+        assert!(error["payload"][0]["message"].is_string());
+    }
+
+    #[tokio::test]
+    async fn it_closes_on_protocol_errors() {
+        let mut socket = connect(SUBPROTOCOL).await.unwrap();
+        let subscribe = json!({ "type": "subscribe", "id": "a", "payload": { "query": "{ me }" } });
+        send(&mut socket, subscribe).await;
+        assert_eq!(closed_with(&mut socket).await, 4401);
+
+        let mut socket = connect(SUBPROTOCOL).await.unwrap();
+        send(&mut socket, json!({ "type": "connection_init" })).await;
+        receive(&mut socket).await;
+        send(&mut socket, json!({ "type": "connection_init" })).await;
+        assert_eq!(closed_with(&mut socket).await, 4429);
+
+        let mut socket = connect(SUBPROTOCOL).await.unwrap();
+        send(&mut socket, json!({ "type": "start" })).await;
+        assert_eq!(closed_with(&mut socket).await, 4400);
+
+        assert!(connect("graphql-ws").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn it_authenticates_clients() {
+        let mut tokens = Tokens::new();
+        tokens.insert("abc".into(), Identity::new("alice"));
+        let tokens: SharedAuthenticator = Arc::new(tokens);
+        let running = || {
+            let (trigger, shutdown) = shutdown::channel();
+            std::mem::forget(trigger);
+            shutdown
+        };
+
+        let mut socket = connect_with(SUBPROTOCOL, tokens.clone(), running())
+            .await
+            .unwrap();
+        let init = json!({ "type": "connection_init", "payload": { "token": "abc" } });
+        send(&mut socket, init).await;
+        assert_eq!(
+            receive(&mut socket).await,
+            json!({ "type": "connection_ack" })
+        );
+
+        for payload in &[json!({ "token": "abd" }), Value::Null] {
+            let mut socket = connect_with(SUBPROTOCOL, tokens.clone(), running())
+                .await
+                .unwrap();
+            let init = json!({ "type": "connection_init", "payload": payload });
+            send(&mut socket, init).await;
+            assert_eq!(closed_with(&mut socket).await, 4403);
+        }
+    }
+
+    #[tokio::test]
+    async fn it_closes_when_shutting_down() {
+        let (trigger, shutdown) = shutdown::channel();
+        let mut socket = connect_with(SUBPROTOCOL, Arc::new(Anonymous), shutdown)
+            .await
+            .unwrap();
+        send(&mut socket, json!({ "type": "connection_init" })).await;
+        receive(&mut socket).await;
+        trigger.trigger();
+        assert_eq!(closed_with(&mut socket).await, 1001);
+    }
+}
@@ -0,0 +1,191 @@
+//! The `GraphQLType` derive.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, Data, DeriveInput, Error, Expr, Fields, Lit, Meta};
+
+pub(crate) fn graphql_type(input: DeriveInput) -> Result<TokenStream, Error> {
+    let ident = &input.ident;
+    let name = ident.to_string();
+    let type_description = description(&input.attrs);
+    let definition = match &input.data {
+        Data::Struct(data) => {
+            let fields = match &data.fields {
+                Fields::Named(fields) if !fields.named.is_empty() => &fields.named,
+                _ => {
+                    return Err(Error::new_spanned(
+                        ident,
+                        "GraphQLType can only be derived for structs with named fields",
+                    ))
+                }
+            };
+            let fields = fields.iter().map(|field| {
+                let name = camel_case(&field.ident.as_ref().unwrap().to_string());
+                let description = description(&field.attrs);
+                let field_type = &field.ty;
+                quote! {
+                    ::syntax::nodes::FieldDefinitionNode {
+                        description: #description,
+                        name: ::syntax::nodes::NameNode::from(#name),
+                        arguments: ::std::option::Option::None,
+                        field_type: <#field_type as ::syntax::graphql_type::GraphQLType>::type_node(),
+                        directives: ::std::option::Option::None,
+                    }
+                }
+            });
+            quote! {
+                ::syntax::nodes::TypeDefinitionNode::Object(
+                    ::syntax::nodes::ObjectTypeDefinitionNode {
+                        description: #type_description,
+                        name: ::syntax::nodes::NameNode::from(#name),
+                        interfaces: ::std::option::Option::None,
+                        directives: ::std::option::Option::None,
+                        fields: ::std::vec![#(#fields),*],
+                    }
+                )
+            }
+        }
+        Data::Enum(data) => {
+            let mut values = Vec::new();
+            for variant in &data.variants {
+                if !matches!(variant.fields, Fields::Unit) {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "GraphQLType can only be derived for enums of unit variants",
+                    ));
+                }
+                let name = screaming_snake_case(&variant.ident.to_string());
+                let description = description(&variant.attrs);
+                values.push(quote! {
+                    ::syntax::nodes::EnumValueDefinitionNode {
+                        description: #description,
+                        name: ::syntax::nodes::NameNode::from(#name),
+                        directives: ::std::option::Option::None,
+                    }
+                });
+            }
+            if values.is_empty() {
+                return Err(Error::new_spanned(
+                    ident,
+                    "GraphQLType cannot be derived for an enum without variants",
+                ));
+            }
+            quote! {
+                ::syntax::nodes::TypeDefinitionNode::Enum(
+                    ::syntax::nodes::EnumTypeDefinitionNode {
+                        description: #type_description,
+                        name: ::syntax::nodes::NameNode::from(#name),
+                        directives: ::std::option::Option::None,
+                        values: ::std::vec![#(#values),*],
+                    }
+                )
+            }
+        }
+        Data::Union(_) => {
+            return Err(Error::new_spanned(
+                ident,
+                "GraphQLType cannot be derived for unions",
+            ))
+        }
+    };
+
+    let mut generics = input.generics.clone();
+    for parameter in generics.type_params_mut() {
+        parameter
+            .bounds
+            .push(syn::parse_quote!(::syntax::graphql_type::GraphQLType));
+    }
+    let (impl_generics, type_generics, where_clause) = generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::syntax::graphql_type::GraphQLType for #ident #type_generics #where_clause {
+            fn type_node() -> ::syntax::nodes::TypeNode {
+                ::syntax::graphql_type::non_null(#name)
+            }
+
+            fn definition() -> ::std::option::Option<::syntax::nodes::TypeDefinitionNode> {
+                ::std::option::Option::Some(#definition)
+            }
+        }
+    })
+}
+
+/// The doc comments of an item, as the expression of its description.
+fn description(attributes: &[Attribute]) -> TokenStream {
+    let lines: Vec<String> = attributes
+        .iter()
+        .filter(|attribute| attribute.path().is_ident("doc"))
+        .filter_map(|attribute| match &attribute.meta {
+            Meta::NameValue(doc) => match &doc.value {
+                Expr::Lit(literal) => match &literal.lit {
+                    Lit::Str(line) => Some(line.value()),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        })
+        .map(|line| line.strip_prefix(' ').unwrap_or(&line).to_owned())
+        .collect();
+    let text = lines.join("\n");
+    let text = text.trim();
+    if text.is_empty() {
+        return quote! { ::std::option::Option::None };
+    }
+    // Block strings keep their raw text, as the lexer does, so they print on their own lines.
+    let (text, block) = if text.contains('\n') {
+        (format!("\n{}\n", text), true)
+    } else {
+        (text.to_owned(), false)
+    };
+    quote! {
+        ::std::option::Option::Some(::syntax::nodes::StringValueNode::from(#text, #block))
+    }
+}
+
+/// `first_name` to `firstName`.
+fn camel_case(name: &str) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = !camel.is_empty();
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// `NewHope` to `NEW_HOPE`.
+fn screaming_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            snake.push('_');
+        }
+        snake.extend(c.to_uppercase());
+        previous = Some(c);
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_converts_names() {
+        assert_eq!(camel_case("id"), "id");
+        assert_eq!(camel_case("first_name"), "firstName");
+        assert_eq!(camel_case("r#type"), "type");
+        assert_eq!(camel_case("_private_field"), "privateField");
+        assert_eq!(screaming_snake_case("Jedi"), "JEDI");
+        assert_eq!(screaming_snake_case("NewHope"), "NEW_HOPE");
+        assert_eq!(screaming_snake_case("HTTPStatus2Ok"), "HTTPSTATUS2_OK");
+    }
+}
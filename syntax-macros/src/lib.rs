@@ -0,0 +1,184 @@
+//! Procedural macros for the `syntax` crate.
+//!
+//! [`gql!`] parses a GraphQL document while the crate using it compiles. A document that does
+//! not parse is a compile error pointing at the string literal, and a document that does is
+//! embedded as the Rust expression building its nodes, so it is never parsed at runtime.
+//!
+//! [`include_schema!`] does the same for a schema read from a `.graphql` file, and also fails
+//! the build when the schema is not valid.
+//!
+//! [`GraphQLType`] derives the GraphQL type of a Rust struct or enum.
+//!
+//! The macros generate code referring to the `syntax` crate, which must be a dependency of the
+//! crate using them. They live in their own crate because they use `syntax` to parse, so `syntax`
+//! cannot depend on them to re-export them.
+//!
+//! [`gql!`]: macro.gql.html
+//! [`include_schema!`]: macro.include_schema.html
+//! [`GraphQLType`]: derive.GraphQLType.html
+
+#![forbid(unsafe_code, missing_docs)]
+
+extern crate proc_macro;
+
+mod derive;
+mod tokens;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use std::path::PathBuf;
+use syn::{parse_macro_input, DeriveInput, LitStr};
+use syntax::document::Document;
+use syntax::schema::Schema;
+use tokens::ToExpr;
+
+/// Parses a GraphQL document at compile time, and evaluates to a `&'static Document`.
+///
+/// The document is built the first time the expression is evaluated, and shared afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use syntax_macros::gql;
+///
+/// let document = gql!("{ hero { name friends { name } } }");
+/// assert_eq!(document.definitions.len(), 1);
+/// assert_eq!(document, &syntax::parse("{ hero { name friends { name } } }").unwrap());
+/// ```
+///
+/// A syntax error fails the build:
+///
+/// ```compile_fail
+/// use syntax_macros::gql;
+///
+/// let document = gql!("{ hero { name }");
+/// ```
+#[proc_macro]
+pub fn gql(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let document = match syntax::parse(&literal.value()) {
+        Ok(document) => document,
+        Err(error) => {
+            return syn::Error::new(literal.span(), error)
+                .to_compile_error()
+                .into()
+        }
+    };
+    let document = static_document(&document);
+    quote! {{ #document }}.into()
+}
+
+/// Reads, parses and validates a schema at compile time, and evaluates to a `&'static Schema`.
+///
+/// The path is relative to the directory of the `Cargo.toml` of the crate being built. The
+/// schema is built the first time the expression is evaluated, and shared afterwards. The crate
+/// is rebuilt when the file changes.
+///
+/// # Examples
+///
+/// ```
+/// use syntax_macros::include_schema;
+///
+/// let schema = include_schema!("tests/fixtures/schema.graphql");
+/// assert_eq!(schema.query_type(), Some("Query"));
+/// assert!(schema.has_type("Character"));
+/// ```
+///
+/// A schema referring to a type it does not define fails the build:
+///
+/// ```compile_fail
+/// use syntax_macros::include_schema;
+///
+/// let schema = include_schema!("tests/fixtures/invalid_schema.graphql");
+/// ```
+#[proc_macro]
+pub fn include_schema(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let error = |message: String| -> TokenStream {
+        syn::Error::new(literal.span(), message)
+            .to_compile_error()
+            .into()
+    };
+    let path = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(directory) => PathBuf::from(directory).join(literal.value()),
+        None => PathBuf::from(literal.value()),
+    };
+    let source = match std::fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(read_error) => {
+            return error(format!("Unable to read {}: {}", path.display(), read_error))
+        }
+    };
+    let document = match syntax::parse(&source) {
+        Ok(document) => document,
+        Err(parse_error) => return error(format!("{}: {}", path.display(), parse_error)),
+    };
+    let errors = Schema::new(&document).validate();
+    if !errors.is_empty() {
+        let messages: Vec<String> = errors.into_iter().map(|error| error.message).collect();
+        return error(format!("{}: {}", path.display(), messages.join("\n")));
+    }
+
+    let path = path.to_string_lossy();
+    let document = static_document(&document);
+    quote! {{
+        // Rebuilds the crate when the schema changes.
+        const _: &str = ::std::include_str!(#path);
+        static SCHEMA: ::std::sync::OnceLock<::syntax::schema::Schema<'static>> =
+            ::std::sync::OnceLock::new();
+        SCHEMA.get_or_init(|| ::syntax::schema::Schema::new({ #document }))
+    }}
+    .into()
+}
+
+/// Implements `syntax::graphql_type::GraphQLType` for a struct with named fields, as an object
+/// type, or for an enum of unit variants, as an enum type.
+///
+/// The type keeps the name of the Rust type. Field names are converted to camelCase and variant
+/// names to SCREAMING_SNAKE_CASE. Doc comments become descriptions. The type of every field must
+/// implement `GraphQLType` too; `Option<T>` fields are nullable and `Vec<T>` fields are lists.
+///
+/// # Examples
+///
+/// ```
+/// use syntax::graphql_type::sdl;
+/// use syntax_macros::GraphQLType;
+///
+/// /// A character of the trilogy
+/// #[derive(GraphQLType)]
+/// struct Character {
+///     name: String,
+///     home_planet: Option<String>,
+///     appears_in: Vec<Episode>,
+/// }
+///
+/// #[derive(GraphQLType)]
+/// enum Episode {
+///     NewHope,
+///     Empire,
+///     Jedi,
+/// }
+///
+/// assert_eq!(
+///     sdl::<Character>(),
+///     "\"A character of the trilogy\"\ntype Character {\n  name: String!\n  homePlanet: String\n  appearsIn: [Episode!]!\n}\n"
+/// );
+/// assert_eq!(sdl::<Episode>(), "enum Episode {\n  NEW_HOPE\n  EMPIRE\n  JEDI\n}\n");
+/// ```
+#[proc_macro_derive(GraphQLType)]
+pub fn derive_graphql_type(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::graphql_type(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// The statements evaluating to a `&'static Document` built on first use.
+fn static_document(document: &Document) -> proc_macro2::TokenStream {
+    let document = document.to_expr();
+    quote! {
+        static DOCUMENT: ::std::sync::OnceLock<::syntax::document::Document> =
+            ::std::sync::OnceLock::new();
+        DOCUMENT.get_or_init(|| #document)
+    }
+}
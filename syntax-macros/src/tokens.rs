@@ -0,0 +1,596 @@
+//! Turns a parsed document back into the Rust expression that builds it.
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+use syntax::document::Document;
+use syntax::nodes::*;
+
+/// A node that can be written as the Rust expression building it.
+pub(crate) trait ToExpr {
+    fn to_expr(&self) -> TokenStream;
+}
+
+impl<T: ToExpr> ToExpr for Vec<T> {
+    fn to_expr(&self) -> TokenStream {
+        let items = self.iter().map(ToExpr::to_expr);
+        quote! { ::std::vec![#(#items),*] }
+    }
+}
+
+impl<T: ToExpr> ToExpr for Option<T> {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            Some(value) => {
+                let value = value.to_expr();
+                quote! { ::std::option::Option::Some(#value) }
+            }
+            None => quote! { ::std::option::Option::None },
+        }
+    }
+}
+
+impl ToExpr for Document {
+    fn to_expr(&self) -> TokenStream {
+        let definitions = self.definitions.to_expr();
+        quote! { ::syntax::document::Document { definitions: #definitions } }
+    }
+}
+
+impl ToExpr for DefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            DefinitionNode::Executable(node) => {
+                let node = node.to_expr();
+                quote! { ::syntax::nodes::DefinitionNode::Executable(#node) }
+            }
+            DefinitionNode::TypeSystem(node) => {
+                let node = node.to_expr();
+                quote! { ::syntax::nodes::DefinitionNode::TypeSystem(#node) }
+            }
+            DefinitionNode::Extension(node) => {
+                let node = node.to_expr();
+                quote! { ::syntax::nodes::DefinitionNode::Extension(#node) }
+            }
+        }
+    }
+}
+
+impl ToExpr for ExecutableDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            ExecutableDefinitionNode::Operation(operation) => {
+                let query = operation.definition();
+                let name = query.name.to_expr();
+                let variables = query.variables.to_expr();
+                let selections = query.selections.to_expr();
+                let kind = match operation {
+                    OperationTypeNode::Query(_) => quote! { Query },
+                    OperationTypeNode::Mutation(_) => quote! { Mutation },
+                    OperationTypeNode::Subscription(_) => quote! { Subscription },
+                };
+                quote! {
+                    ::syntax::nodes::ExecutableDefinitionNode::Operation(
+                        ::syntax::nodes::OperationTypeNode::#kind(
+                            ::syntax::nodes::QueryDefinitionNode {
+                                name: #name,
+                                variables: #variables,
+                                selections: #selections,
+                            }
+                        )
+                    )
+                }
+            }
+            ExecutableDefinitionNode::Fragment(fragment) => {
+                let name = fragment.name.to_expr();
+                let variables = fragment.variables.to_expr();
+                let node_type = fragment.node_type.to_expr();
+                let directives = fragment.directives.to_expr();
+                let selections = fragment.selections.to_expr();
+                quote! {
+                    ::syntax::nodes::ExecutableDefinitionNode::Fragment(
+                        ::syntax::nodes::FragmentDefinitionNode {
+                            name: #name,
+                            variables: #variables,
+                            node_type: #node_type,
+                            directives: #directives,
+                            selections: #selections,
+                        }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for Selection {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            Selection::Field(field) => {
+                let name = field.name.to_expr();
+                let alias = field.alias.to_expr();
+                let arguments = field.arguments.to_expr();
+                let directives = field.directives.to_expr();
+                let selections = field.selections.to_expr();
+                quote! {
+                    ::syntax::nodes::Selection::Field(::syntax::nodes::FieldNode {
+                        name: #name,
+                        alias: #alias,
+                        arguments: #arguments,
+                        directives: #directives,
+                        selections: #selections,
+                    })
+                }
+            }
+            Selection::Fragment(FragmentSpread::Node(spread)) => {
+                let name = spread.name.to_expr();
+                let directives = spread.directives.to_expr();
+                quote! {
+                    ::syntax::nodes::Selection::Fragment(
+                        ::syntax::nodes::FragmentSpread::Node(
+                            ::syntax::nodes::FragmentSpreadNode {
+                                name: #name,
+                                directives: #directives,
+                            }
+                        )
+                    )
+                }
+            }
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                let node_type = inline.node_type.to_expr();
+                let directives = inline.directives.to_expr();
+                let selections = inline.selections.to_expr();
+                quote! {
+                    ::syntax::nodes::Selection::Fragment(
+                        ::syntax::nodes::FragmentSpread::Inline(
+                            ::syntax::nodes::InlineFragmentSpreadNode {
+                                node_type: #node_type,
+                                directives: #directives,
+                                selections: #selections,
+                            }
+                        )
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for TypeSystemDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            TypeSystemDefinitionNode::Schema(schema) => {
+                let description = schema.description.to_expr();
+                let directives = schema.directives.to_expr();
+                let operations = schema.operations.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeSystemDefinitionNode::Schema(
+                        ::syntax::nodes::SchemaDefinitionNode {
+                            description: #description,
+                            directives: #directives,
+                            operations: #operations,
+                        }
+                    )
+                }
+            }
+            TypeSystemDefinitionNode::Type(definition) => {
+                let definition = definition.to_expr();
+                quote! { ::syntax::nodes::TypeSystemDefinitionNode::Type(#definition) }
+            }
+            TypeSystemDefinitionNode::Directive(directive) => {
+                let description = directive.description.to_expr();
+                let name = directive.name.to_expr();
+                let arguments = directive.arguments.to_expr();
+                let repeatable = directive.repeatable;
+                let locations = directive.locations.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeSystemDefinitionNode::Directive(
+                        ::syntax::nodes::DirectiveDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            arguments: #arguments,
+                            repeatable: #repeatable,
+                            locations: #locations,
+                        }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for OperationTypeDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        let operation = match self.operation {
+            Operation::Query => quote! { ::syntax::nodes::Operation::Query },
+            Operation::Mutation => quote! { ::syntax::nodes::Operation::Mutation },
+            Operation::Subscription => quote! { ::syntax::nodes::Operation::Subscription },
+        };
+        let node_type = self.node_type.to_expr();
+        quote! {
+            ::syntax::nodes::OperationTypeDefinitionNode {
+                operation: #operation,
+                node_type: #node_type,
+            }
+        }
+    }
+}
+
+impl ToExpr for TypeDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            TypeDefinitionNode::Scalar(scalar) => {
+                let description = scalar.description.to_expr();
+                let name = scalar.name.to_expr();
+                let directives = scalar.directives.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Scalar(
+                        ::syntax::nodes::ScalarTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            directives: #directives,
+                        }
+                    )
+                }
+            }
+            TypeDefinitionNode::Object(object) => {
+                let description = object.description.to_expr();
+                let name = object.name.to_expr();
+                let interfaces = object.interfaces.to_expr();
+                let directives = object.directives.to_expr();
+                let fields = object.fields.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Object(
+                        ::syntax::nodes::ObjectTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            interfaces: #interfaces,
+                            directives: #directives,
+                            fields: #fields,
+                        }
+                    )
+                }
+            }
+            TypeDefinitionNode::Interface(interface) => {
+                let description = interface.description.to_expr();
+                let name = interface.name.to_expr();
+                let directives = interface.directives.to_expr();
+                let fields = interface.fields.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Interface(
+                        ::syntax::nodes::InterfaceTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            directives: #directives,
+                            fields: #fields,
+                        }
+                    )
+                }
+            }
+            TypeDefinitionNode::Union(union) => {
+                let description = union.description.to_expr();
+                let name = union.name.to_expr();
+                let directives = union.directives.to_expr();
+                let types = union.types.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Union(
+                        ::syntax::nodes::UnionTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            directives: #directives,
+                            types: #types,
+                        }
+                    )
+                }
+            }
+            TypeDefinitionNode::Enum(enum_type) => {
+                let description = enum_type.description.to_expr();
+                let name = enum_type.name.to_expr();
+                let directives = enum_type.directives.to_expr();
+                let values = enum_type.values.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Enum(
+                        ::syntax::nodes::EnumTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            directives: #directives,
+                            values: #values,
+                        }
+                    )
+                }
+            }
+            TypeDefinitionNode::Input(input) => {
+                let description = input.description.to_expr();
+                let name = input.name.to_expr();
+                let directives = input.directives.to_expr();
+                let fields = input.fields.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeDefinitionNode::Input(
+                        ::syntax::nodes::InputTypeDefinitionNode {
+                            description: #description,
+                            name: #name,
+                            directives: #directives,
+                            fields: #fields,
+                        }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for TypeSystemExtensionNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            TypeSystemExtensionNode::Schema(extension) => {
+                let directives = extension.directives.to_expr();
+                let operations = extension.operations.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeSystemExtensionNode::Schema(
+                        ::syntax::nodes::SchemaExtensionNode {
+                            directives: #directives,
+                            operations: #operations,
+                        }
+                    )
+                }
+            }
+            TypeSystemExtensionNode::Object(extension) => {
+                let description = extension.description.to_expr();
+                let name = extension.name.to_expr();
+                let interfaces = extension.interfaces.to_expr();
+                let directives = extension.directives.to_expr();
+                let fields = extension.fields.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeSystemExtensionNode::Object(
+                        ::syntax::nodes::object_type_extension::ObjectTypeExtensionNode {
+                            description: #description,
+                            name: #name,
+                            interfaces: #interfaces,
+                            directives: #directives,
+                            fields: #fields,
+                        }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for FieldDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        let description = self.description.to_expr();
+        let name = self.name.to_expr();
+        let arguments = self.arguments.to_expr();
+        let field_type = self.field_type.to_expr();
+        let directives = self.directives.to_expr();
+        quote! {
+            ::syntax::nodes::FieldDefinitionNode {
+                description: #description,
+                name: #name,
+                arguments: #arguments,
+                field_type: #field_type,
+                directives: #directives,
+            }
+        }
+    }
+}
+
+impl ToExpr for InputValueDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        let description = self.description.to_expr();
+        let name = self.name.to_expr();
+        let input_type = self.input_type.to_expr();
+        let default_value = self.default_value.to_expr();
+        let directives = self.directives.to_expr();
+        quote! {
+            ::syntax::nodes::InputValueDefinitionNode {
+                description: #description,
+                name: #name,
+                input_type: #input_type,
+                default_value: #default_value,
+                directives: #directives,
+            }
+        }
+    }
+}
+
+impl ToExpr for EnumValueDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        let description = self.description.to_expr();
+        let name = self.name.to_expr();
+        let directives = self.directives.to_expr();
+        quote! {
+            ::syntax::nodes::EnumValueDefinitionNode {
+                description: #description,
+                name: #name,
+                directives: #directives,
+            }
+        }
+    }
+}
+
+impl ToExpr for VariableDefinitionNode {
+    fn to_expr(&self) -> TokenStream {
+        let variable = self.variable.to_expr();
+        let variable_type = self.variable_type.to_expr();
+        let default_value = self.default_value.to_expr();
+        quote! {
+            ::syntax::nodes::VariableDefinitionNode {
+                variable: #variable,
+                variable_type: #variable_type,
+                default_value: #default_value,
+            }
+        }
+    }
+}
+
+impl ToExpr for DirectiveNode {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.name.to_expr();
+        let arguments = self.arguments.to_expr();
+        quote! {
+            ::syntax::nodes::DirectiveNode {
+                name: #name,
+                arguments: #arguments,
+            }
+        }
+    }
+}
+
+impl ToExpr for DirectiveLocation {
+    fn to_expr(&self) -> TokenStream {
+        let variant = Ident::new(&format!("{:?}", self), Span::call_site());
+        quote! { ::syntax::nodes::DirectiveLocation::#variant }
+    }
+}
+
+impl ToExpr for Argument {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.name.to_expr();
+        let value = self.value.to_expr();
+        quote! {
+            ::syntax::nodes::Argument {
+                name: #name,
+                value: #value,
+            }
+        }
+    }
+}
+
+impl ToExpr for ValueNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            ValueNode::Variable(variable) => {
+                let variable = variable.to_expr();
+                quote! { ::syntax::nodes::ValueNode::Variable(#variable) }
+            }
+            ValueNode::Int(int) => {
+                let value = &int.value;
+                quote! {
+                    ::syntax::nodes::ValueNode::Int(
+                        ::syntax::nodes::IntValueNode { value: #value.into() }
+                    )
+                }
+            }
+            ValueNode::Float(float) => {
+                // The bits keep the exact value, and infinities have no literal.
+                let bits = float.value.to_bits();
+                let lexeme = match &float.lexeme {
+                    Some(lexeme) => quote! { ::std::option::Option::Some(#lexeme.into()) },
+                    None => quote! { ::std::option::Option::None },
+                };
+                quote! {
+                    ::syntax::nodes::ValueNode::Float(
+                        ::syntax::nodes::FloatValueNode {
+                            value: ::std::primitive::f64::from_bits(#bits),
+                            lexeme: #lexeme,
+                        }
+                    )
+                }
+            }
+            ValueNode::Str(string) => {
+                let string = string.to_expr();
+                quote! { ::syntax::nodes::ValueNode::Str(#string) }
+            }
+            ValueNode::Bool(boolean) => {
+                let value = boolean.value;
+                quote! {
+                    ::syntax::nodes::ValueNode::Bool(
+                        ::syntax::nodes::BooleanValueNode { value: #value }
+                    )
+                }
+            }
+            ValueNode::Null => quote! { ::syntax::nodes::ValueNode::Null },
+            ValueNode::Enum(enum_value) => {
+                let value = &enum_value.value;
+                quote! {
+                    ::syntax::nodes::ValueNode::Enum(
+                        ::syntax::nodes::EnumValueNode {
+                            value: ::std::string::String::from(#value),
+                        }
+                    )
+                }
+            }
+            ValueNode::List(list) => {
+                let values = list.values.to_expr();
+                quote! {
+                    ::syntax::nodes::ValueNode::List(
+                        ::syntax::nodes::ListValueNode { values: #values }
+                    )
+                }
+            }
+            ValueNode::Object(object) => {
+                let fields = object.fields.to_expr();
+                quote! {
+                    ::syntax::nodes::ValueNode::Object(
+                        ::syntax::nodes::ObjectValueNode { fields: #fields }
+                    )
+                }
+            }
+        }
+    }
+}
+
+impl ToExpr for ObjectFieldNode {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.name.to_expr();
+        let value = self.value.to_expr();
+        quote! {
+            ::syntax::nodes::ObjectFieldNode {
+                name: #name,
+                value: #value,
+            }
+        }
+    }
+}
+
+impl ToExpr for TypeNode {
+    fn to_expr(&self) -> TokenStream {
+        match self {
+            TypeNode::Named(named) => {
+                let named = named.to_expr();
+                quote! { ::syntax::nodes::TypeNode::Named(#named) }
+            }
+            TypeNode::List(list) => {
+                let inner = list.list_type.to_expr();
+                quote! {
+                    ::syntax::nodes::TypeNode::List(::syntax::nodes::ListTypeNode {
+                        list_type: ::std::sync::Arc::new(#inner),
+                    })
+                }
+            }
+            TypeNode::NonNull(inner) => {
+                let inner = inner.to_expr();
+                quote! { ::syntax::nodes::TypeNode::NonNull(::std::sync::Arc::new(#inner)) }
+            }
+        }
+    }
+}
+
+impl ToExpr for VariableNode {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.name.value.as_str();
+        quote! { ::syntax::nodes::VariableNode::from(#name) }
+    }
+}
+
+impl ToExpr for NamedTypeNode {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.name.value.as_str();
+        quote! { ::syntax::nodes::NamedTypeNode::from(#name) }
+    }
+}
+
+impl ToExpr for NameNode {
+    fn to_expr(&self) -> TokenStream {
+        let name = self.value.as_str();
+        quote! { ::syntax::nodes::NameNode::from(#name) }
+    }
+}
+
+impl ToExpr for StringValueNode {
+    fn to_expr(&self) -> TokenStream {
+        let value = &self.value;
+        let block = self.is_block();
+        quote! { ::syntax::nodes::StringValueNode::from(#value, #block) }
+    }
+}
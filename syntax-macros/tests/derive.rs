@@ -0,0 +1,85 @@
+use syntax::graphql_type::{sdl, GraphQLType};
+use syntax_macros::GraphQLType;
+
+/// A character of the trilogy.
+///
+/// Either a human or a droid.
+#[derive(GraphQLType)]
+#[allow(dead_code)]
+struct Character {
+    /// The name of the character
+    name: String,
+    friends: Option<Vec<Option<Box<Character>>>>,
+    appears_in: Vec<Episode>,
+    height: f64,
+    r#type: Kind,
+}
+
+#[derive(GraphQLType)]
+#[allow(dead_code)]
+enum Episode {
+    /// Released in 1977
+    NewHope,
+    Empire,
+    Jedi,
+}
+
+#[derive(GraphQLType)]
+#[allow(dead_code)]
+enum Kind {
+    Human,
+    Droid,
+}
+
+#[derive(GraphQLType)]
+#[allow(dead_code)]
+struct Page<T> {
+    items: Vec<T>,
+    total: i32,
+}
+
+#[test]
+fn it_derives_an_object_type() {
+    assert_eq!(
+        sdl::<Character>(),
+        r#""""
+A character of the trilogy.
+
+Either a human or a droid.
+"""
+type Character {
+  "The name of the character"
+  name: String!
+  friends: [Character]
+  appearsIn: [Episode!]!
+  height: Float!
+  type: Kind!
+}
+"#
+    );
+}
+
+#[test]
+fn it_derives_an_enum_type() {
+    assert_eq!(
+        sdl::<Episode>(),
+        "enum Episode {\n  \"Released in 1977\"\n  NEW_HOPE\n  EMPIRE\n  JEDI\n}\n"
+    );
+}
+
+#[test]
+fn it_derives_generic_types() {
+    assert_eq!(
+        sdl::<Page<Kind>>(),
+        "type Page {\n  items: [Kind!]!\n  total: Int!\n}\n"
+    );
+}
+
+#[test]
+fn the_sdl_parses() {
+    let sdl = [sdl::<Character>(), sdl::<Episode>(), sdl::<Kind>()].join("\n");
+    let document = syntax::parse(&sdl).unwrap();
+    let schema = syntax::schema::Schema::new(&document);
+    assert!(schema.validate().is_empty());
+    assert!(Character::definition().is_some());
+}
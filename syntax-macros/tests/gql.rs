@@ -0,0 +1,94 @@
+use syntax_macros::gql;
+
+#[test]
+fn it_builds_the_parsed_document() {
+    let document = gql!(
+        r#"
+query Hero($episode: Episode = JEDI, $first: Int = -3) {
+  hero(episode: $episode, filter: { ratio: 0.5, tags: ["a", null], active: true }) {
+    name
+    ...Friends
+    ... on Droid @include(if: true) { primaryFunction }
+  }
+}
+
+fragment Friends on Character {
+  friends(first: $first) { name }
+}
+"#
+    );
+    assert_eq!(
+        document,
+        &syntax::parse(
+            r#"
+query Hero($episode: Episode = JEDI, $first: Int = -3) {
+  hero(episode: $episode, filter: { ratio: 0.5, tags: ["a", null], active: true }) {
+    name
+    ...Friends
+    ... on Droid @include(if: true) { primaryFunction }
+  }
+}
+
+fragment Friends on Character {
+  friends(first: $first) { name }
+}
+"#
+        )
+        .unwrap()
+    );
+}
+
+#[test]
+fn it_builds_type_system_definitions() {
+    const SDL: &str = r#"
+"The root"
+schema { query: Query }
+
+"""
+A character
+"""
+type Character implements Node @key(fields: "id") {
+  id: ID!
+  friends(first: Int = 10, after: [String!], ratio: Float = 1.5): [Character]
+}
+
+interface Node { id: ID! }
+union Result = Character | Error
+scalar Time
+enum Episode { "New hope" NEWHOPE JEDI @deprecated }
+input Filter { ratio: Float }
+directive @key(fields: String!) repeatable on OBJECT | INTERFACE
+extend type Character @shareable
+"#;
+    let document = gql!(
+        r#"
+"The root"
+schema { query: Query }
+
+"""
+A character
+"""
+type Character implements Node @key(fields: "id") {
+  id: ID!
+  friends(first: Int = 10, after: [String!], ratio: Float = 1.5): [Character]
+}
+
+interface Node { id: ID! }
+union Result = Character | Error
+scalar Time
+enum Episode { "New hope" NEWHOPE JEDI @deprecated }
+input Filter { ratio: Float }
+directive @key(fields: String!) repeatable on OBJECT | INTERFACE
+extend type Character @shareable
+"#
+    );
+    assert_eq!(document, &syntax::parse(SDL).unwrap());
+}
+
+#[test]
+fn it_shares_the_document() {
+    fn document() -> &'static syntax::document::Document {
+        gql!("{ a }")
+    }
+    assert!(std::ptr::eq(document(), document()));
+}
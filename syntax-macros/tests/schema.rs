@@ -0,0 +1,27 @@
+use syntax_macros::include_schema;
+
+#[test]
+fn it_includes_the_schema() {
+    let schema = include_schema!("tests/fixtures/schema.graphql");
+    let source = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/schema.graphql"
+    ))
+    .unwrap();
+    let document = syntax::parse(&source).unwrap();
+    let expected = syntax::schema::Schema::new(&document);
+    assert_eq!(schema.query_type(), expected.query_type());
+    assert_eq!(
+        schema.types().collect::<Vec<_>>(),
+        expected.types().collect::<Vec<_>>()
+    );
+    assert_eq!(schema.possible_types("Character"), vec!["Human", "Droid"]);
+}
+
+#[test]
+fn it_shares_the_schema() {
+    fn schema() -> &'static syntax::schema::Schema<'static> {
+        include_schema!("tests/fixtures/schema.graphql")
+    }
+    assert!(std::ptr::eq(schema(), schema()));
+}
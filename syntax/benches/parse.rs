@@ -0,0 +1,189 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use syntax::lexer::tokenize;
+use syntax::parse;
+
+const STAR_WARS: &str = include_str!("fixtures/star_wars.graphql");
+const STAR_WARS_QUERIES: &str = include_str!("fixtures/star_wars_queries.graphql");
+
+/// A schema shaped like the GitHub API: nodes with connections, edges, page info, enums of
+/// states and orderings, and input objects for ordering arguments.
+fn github_like_schema(objects: usize) -> String {
+    let mut sdl = String::from(
+        r#""An object with an ID."
+interface Node {
+  "ID of the object."
+  id: ID!
+}
+
+"Information about pagination in a connection."
+type PageInfo {
+  "When paginating forwards, the cursor to continue."
+  endCursor: String
+  "When paginating forwards, are there more items?"
+  hasNextPage: Boolean!
+  "When paginating backwards, are there more items?"
+  hasPreviousPage: Boolean!
+  "When paginating backwards, the cursor to continue."
+  startCursor: String
+}
+
+"Possible directions in which to order a list of items when provided an `orderBy` argument."
+enum OrderDirection {
+  "Specifies an ascending order for a given `orderBy` argument."
+  ASC
+  "Specifies a descending order for a given `orderBy` argument."
+  DESC
+}
+
+"#,
+    );
+    let mut query = String::from("type Query {\n");
+    for index in 0..objects {
+        let name = format!("Object{}", index);
+        let next = format!("Object{}", (index + 1) % objects);
+        sdl.push_str(&format!(
+            r#""""
+A {name} in the generated schema.
+
+Used to exercise block descriptions over several lines.
+"""
+type {name} implements Node {{
+  "Identifies the date and time when the object was created."
+  createdAt: String!
+  "The HTTP URL for this object"
+  url: String!
+  id: ID!
+  "The state of the object."
+  state: {name}State!
+  "A list of related objects."
+  related(
+    "Returns the elements in the list that come after the specified cursor."
+    after: String
+    "Returns the first _n_ elements from the list."
+    first: Int = 30
+    "Ordering options for the returned objects."
+    orderBy: {name}Order = {{field: CREATED_AT, direction: DESC}}
+    "Filters the objects by state."
+    states: [{name}State!]
+  ): {next}Connection!
+}}
+
+"The connection type for {name}."
+type {name}Connection {{
+  "A list of edges."
+  edges: [{name}Edge]
+  "A list of nodes."
+  nodes: [{name}]
+  "Information to aid in pagination."
+  pageInfo: PageInfo!
+  "Identifies the total count of items in the connection."
+  totalCount: Int!
+}}
+
+"An edge in a connection."
+type {name}Edge {{
+  "A cursor for use in pagination."
+  cursor: String!
+  "The item at the end of the edge."
+  node: {name}
+}}
+
+"The possible states of a {name}."
+enum {name}State {{
+  OPEN
+  CLOSED
+  MERGED
+}}
+
+"Ordering options for {name} connections."
+input {name}Order {{
+  "The field to order by."
+  field: {name}OrderField!
+  "The ordering direction."
+  direction: OrderDirection!
+}}
+
+enum {name}OrderField {{
+  CREATED_AT
+  UPDATED_AT
+}}
+
+"#,
+            name = name,
+            next = next
+        ));
+        query.push_str(&format!("  object{}(id: ID!): Object{}\n", index, index));
+    }
+    query.push_str("}\n");
+    sdl.push_str(&query);
+    sdl
+}
+
+/// Inputs that stress a single part of the lexer or the parser.
+fn pathological_inputs() -> Vec<(&'static str, String)> {
+    let depth = 64;
+    let nested_lists = format!(
+        "query Q {{ a(b: {}1{}) }}",
+        "[".repeat(depth),
+        "]".repeat(depth)
+    );
+    let nested_selections = format!("query Q {}{}", "{ a ".repeat(depth), "}".repeat(depth));
+    let wide_selection = format!(
+        "query Q {{ {} }}",
+        (0..5000)
+            .map(|index| format!("field{}", index))
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    let long_block_string = format!(
+        "\"\"\"\n{}\n\"\"\"\nscalar Long",
+        "A long description line. ".repeat(4000)
+    );
+    let comments = format!("{}scalar Commented", "# A comment line\n".repeat(5000));
+    let wide_union = format!(
+        "union Wide = {}",
+        (0..5000)
+            .map(|index| format!("Member{}", index))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    );
+    vec![
+        ("nested_lists", nested_lists),
+        ("nested_selections", nested_selections),
+        ("wide_selection", wide_selection),
+        ("long_block_string", long_block_string),
+        ("comments", comments),
+        ("wide_union", wide_union),
+    ]
+}
+
+fn bench_documents(c: &mut Criterion) {
+    let github = github_like_schema(200);
+    let mut documents = vec![
+        ("star_wars_schema", STAR_WARS.to_string()),
+        ("star_wars_queries", STAR_WARS_QUERIES.to_string()),
+        ("github_like_schema", github),
+    ];
+    documents.extend(pathological_inputs());
+
+    let mut lex = c.benchmark_group("tokenize");
+    for (name, input) in &documents {
+        lex.throughput(Throughput::Bytes(input.len() as u64));
+        lex.bench_with_input(BenchmarkId::from_parameter(name), input, |b, input| {
+            b.iter(|| tokenize(input).unwrap())
+        });
+    }
+    lex.finish();
+
+    let mut group = c.benchmark_group("parse");
+    for (name, input) in &documents {
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), input, |b, input| {
+            b.iter(|| parse(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_documents);
+criterion_main!(benches);
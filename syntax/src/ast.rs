@@ -1,43 +1,144 @@
 use crate::document::Document;
-use crate::error::{ParseError, ParseResult};
+use crate::error::{LexError, ParseError, ParseResult};
 use crate::lexer::Lexer;
 use crate::nodes::object_type_extension::ObjectTypeExtensionNode;
 use crate::nodes::*;
-use crate::token::{Location, Token};
+use crate::options::ParseOptions;
+use crate::token::{Token, TokenKind};
+use std::cell::Cell;
+use std::convert::TryFrom;
 use std::iter::{Iterator, Peekable};
+use std::rc::Rc;
 use std::sync::Arc;
 
-pub struct AST<'i> {
-    lexer: Peekable<Lexer<'i>>,
+/// How deeply lists, input objects, list types and selection sets may nest. The parser does not
+/// recurse into them, but dropping, printing and walking the resulting nodes does.
+pub const MAX_DEPTH: usize = 128;
+
+pub struct Ast<'i> {
+    lexer: Peekable<Tokens<'i>>,
+    options: ParseOptions,
+    tokens: Rc<Cell<usize>>,
+}
+
+/// The tokens of the lexer, stopping with an error once there are more than `max_tokens`.
+struct Tokens<'i> {
+    lexer: Lexer<'i>,
+    max_tokens: Option<usize>,
+    count: Rc<Cell<usize>>,
+}
+
+impl<'i> Iterator for Tokens<'i> {
+    type Item = ParseResult<Token<'i>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = match self.lexer.next()? {
+            Ok(token) => token,
+            Err(lex_error) => return Some(Err(ParseError::LexError(lex_error))),
+        };
+        if !matches!(token.kind, TokenKind::Start | TokenKind::End) {
+            self.count.set(self.count.get() + 1);
+            if self.max_tokens.is_some_and(|max| self.count.get() > max) {
+                return Some(Err(ParseError::TooManyTokens(token.location())));
+            }
+        }
+        Some(Ok(token))
+    }
+}
+
+/// A list or an input object value whose elements are being parsed, along with the name of the
+/// input object field whose value comes next.
+enum OpenValue {
+    List(Vec<ValueNode>),
+    Object(Vec<ObjectFieldNode>, Option<NameNode>),
+}
+
+/// The part of a selection that comes before its selection set.
+enum SelectionHead {
+    Field(FieldNode),
+    Inline(Option<NamedTypeNode>, Option<Directives>),
+    Spread(FragmentSpreadNode),
+}
+
+impl SelectionHead {
+    fn into_selection(self, selections: Option<Vec<Selection>>) -> Selection {
+        match self {
+            SelectionHead::Field(mut field) => {
+                if let Some(selections) = selections {
+                    field.with_selections(selections);
+                }
+                Selection::Field(field)
+            }
+            SelectionHead::Inline(node_type, directives) => {
+                Selection::Fragment(FragmentSpread::Inline(InlineFragmentSpreadNode {
+                    node_type,
+                    directives,
+                    selections: selections.unwrap_or_default(),
+                }))
+            }
+            SelectionHead::Spread(spread) => Selection::Fragment(FragmentSpread::Node(spread)),
+        }
+    }
 }
 
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
-impl<'i> Display for AST<'i> {
+impl<'i> Display for Ast<'i> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "AST")
+        write!(f, "Ast")
     }
 }
-impl<'i> Debug for AST<'i> {
+impl<'i> Debug for Ast<'i> {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "AST")
+        write!(f, "Ast")
     }
 }
 
-impl<'i> AST<'i> {
-    pub fn new(input: &'i str) -> ParseResult<AST<'i>> {
-        let lexer = Lexer::new(input).peekable();
-        Ok(AST { lexer })
+impl<'i> Ast<'i> {
+    pub fn new(input: &'i str) -> ParseResult<Ast<'i>> {
+        Ok(Ast::from_lexer(Lexer::new(input)))
     }
 
-    pub fn parse(&'i mut self) -> ParseResult<Document> {
+    pub fn from_lexer(lexer: Lexer<'i>) -> Ast<'i> {
+        Ast::with_options(lexer, ParseOptions::default())
+    }
+
+    pub fn with_options(lexer: Lexer<'i>, options: ParseOptions) -> Ast<'i> {
+        let count = Rc::new(Cell::new(0));
+        let tokens = Tokens {
+            lexer,
+            max_tokens: options.max_tokens,
+            count: count.clone(),
+        };
+        Ast {
+            lexer: tokens.peekable(),
+            options,
+            tokens: count,
+        }
+    }
+
+    /// The number of tokens read so far, not counting the start and end of the token stream.
+    pub fn token_count(&self) -> usize {
+        self.tokens.get()
+    }
+
+    pub fn parse(&mut self) -> ParseResult<Document> {
         let definitions = self.parse_definitions()?;
         Ok(Document::new(definitions))
     }
 
+    /// Parses an input consisting of a single value literal, such as a default value.
+    #[cfg_attr(not(feature = "json"), allow(dead_code))]
+    pub fn parse_standalone_value(&mut self) -> ParseResult<ValueNode> {
+        self.expect_token(TokenKind::Start)?;
+        let value = self.parse_value()?;
+        self.expect_end()?;
+        Ok(value)
+    }
+
     fn parse_description(&mut self) -> ParseResult<Description> {
-        match self.unwrap_peeked_token()? {
-            Token::BlockStr(_, _) | Token::Str(_, _) => {
+        match self.unwrap_peeked_token()?.kind {
+            TokenKind::BlockStr | TokenKind::Str => {
                 let tok = self.unwrap_next_token()?;
                 Ok(Some(StringValueNode::new(tok)?))
             }
@@ -48,7 +149,7 @@ impl<'i> AST<'i> {
     fn parse_input_value(&mut self) -> ParseResult<InputValueDefinitionNode> {
         let description = self.parse_description()?;
         let name_tok = self.unwrap_next_token()?;
-        self.expect_token(Token::Colon(Location::ignored()))?;
+        self.expect_token(TokenKind::Colon)?;
         let type_node = self.parse_field_type()?;
         let default_value = self.parse_default_value()?;
         let directives = self.parse_directives()?;
@@ -59,19 +160,15 @@ impl<'i> AST<'i> {
     }
 
     fn parse_arguments_definition(&mut self) -> ParseResult<Option<ArgumentDefinitions>> {
-        match self.expect_optional_token(&Token::OpenParen(Location::ignored())) {
+        match self.expect_optional_token(TokenKind::OpenParen) {
             Some(_) => {
-                if let Some(token) =
-                    self.expect_optional_token(&Token::CloseParen(Location::ignored()))
-                {
+                if let Some(token) = self.expect_optional_token(TokenKind::CloseParen) {
                     return Err(ParseError::ArgumentEmpty(token.location()));
                 }
                 let mut args: ArgumentDefinitions = Vec::new();
                 loop {
                     args.push(self.parse_input_value()?);
-                    if let Some(_) =
-                        self.expect_optional_token(&Token::CloseParen(Location::ignored()))
-                    {
+                    if self.expect_optional_token(TokenKind::CloseParen).is_some() {
                         break;
                     }
                 }
@@ -83,7 +180,7 @@ impl<'i> AST<'i> {
 
     fn parse_argument(&mut self) -> ParseResult<Argument> {
         let name = self.unwrap_next_token()?;
-        self.expect_token(Token::Colon(Location::ignored()))?;
+        self.expect_token(TokenKind::Colon)?;
         let value = self.parse_value()?;
         Ok(Argument {
             name: NameNode::new(name)?,
@@ -92,13 +189,11 @@ impl<'i> AST<'i> {
     }
 
     fn parse_arguments(&mut self) -> ParseResult<Option<Arguments>> {
-        match self.expect_optional_token(&Token::OpenParen(Location::ignored())) {
+        match self.expect_optional_token(TokenKind::OpenParen) {
             Some(_) => {
                 let mut args: Arguments = Vec::new();
                 loop {
-                    if let Some(token) =
-                        self.expect_optional_token(&Token::CloseParen(Location::ignored()))
-                    {
+                    if let Some(token) = self.expect_optional_token(TokenKind::CloseParen) {
                         if args.is_empty() {
                             return Err(ParseError::ArgumentEmpty(token.location()));
                         }
@@ -113,20 +208,16 @@ impl<'i> AST<'i> {
     }
 
     fn parse_directive(&mut self) -> ParseResult<DirectiveNode> {
-        self.expect_token(Token::At(Location::ignored()))?;
+        self.expect_token(TokenKind::At)?;
         let name = self.unwrap_next_token()?;
         let arguments = self.parse_arguments()?;
-        Ok(DirectiveNode::new(name, arguments)?)
+        DirectiveNode::new(name, arguments)
     }
 
     fn parse_directives(&mut self) -> ParseResult<Option<Vec<DirectiveNode>>> {
         let mut directives: Vec<DirectiveNode> = Vec::new();
-        loop {
-            if let Token::At(_) = self.unwrap_peeked_token()? {
-                directives.push(self.parse_directive()?);
-            } else {
-                break;
-            }
+        while self.unwrap_peeked_token()?.kind == TokenKind::At {
+            directives.push(self.parse_directive()?);
         }
         if !directives.is_empty() {
             Ok(Some(directives))
@@ -135,15 +226,21 @@ impl<'i> AST<'i> {
         }
     }
 
-    fn parse_definitions(&'i mut self) -> ParseResult<Vec<DefinitionNode>> {
-        self.expect_token(Token::Start)?;
-        if let Some(_) = self.expect_optional_token(&Token::End) {
+    fn parse_definitions(&mut self) -> ParseResult<Vec<DefinitionNode>> {
+        self.expect_token(TokenKind::Start)?;
+        if self.expect_optional_token(TokenKind::End).is_some() {
             Err(ParseError::DocumentEmpty)
         } else {
             let mut nodes: Vec<DefinitionNode> = Vec::new();
             loop {
                 nodes.push(self.parse_definition()?);
-                if let Some(_) = self.expect_optional_token(&Token::End) {
+                let next = self.unwrap_peeked_token()?.kind;
+                // Only a name, a selection set or a description begins another definition
+                if !matches!(
+                    next,
+                    TokenKind::Name | TokenKind::OpenBrace | TokenKind::Str | TokenKind::BlockStr
+                ) {
+                    self.expect_end()?;
                     break;
                 }
             }
@@ -151,11 +248,34 @@ impl<'i> AST<'i> {
         }
     }
 
+    /// Consumes the start of the token stream, before parsing definitions one at a time.
+    pub fn parse_start(&mut self) -> ParseResult<()> {
+        self.expect_token(TokenKind::Start).map(|_| ())
+    }
+
+    /// Parses the next definition, or returns `None` at the end of the token stream.
+    pub fn parse_next_definition(&mut self) -> ParseResult<Option<DefinitionNode>> {
+        if self.expect_optional_token(TokenKind::End).is_some() {
+            return Ok(None);
+        }
+        self.parse_definition().map(Some)
+    }
+
+    /// The position in the input of the next token, or `None` at the end of the token stream.
+    pub fn peek_position(&mut self) -> Option<usize> {
+        match self.lexer.peek() {
+            Some(Ok(token)) if token.kind == TokenKind::End => None,
+            Some(Err(_)) | None => None,
+            Some(Ok(token)) => Some(token.location().absolute_position),
+        }
+    }
+
     fn parse_definition(&mut self) -> ParseResult<DefinitionNode> {
+        let description_location = self.unwrap_peeked_token()?.location();
         let description = self.parse_description()?;
-        let tok = self.unwrap_peeked_token()?;
-        match tok {
-            Token::Name(loc, val) => match *val {
+        let tok = *self.unwrap_peeked_token()?;
+        match tok.kind {
+            TokenKind::Name => match tok.text {
                 "type" | "enum" | "union" | "interface" | "input" | "scalar" => {
                     Ok(DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
                         self.parse_type(description)?,
@@ -164,17 +284,29 @@ impl<'i> AST<'i> {
                 "schema" => Ok(DefinitionNode::TypeSystem(
                     TypeSystemDefinitionNode::Schema(self.parse_schema(description)?),
                 )),
+                "directive" => Ok(DefinitionNode::TypeSystem(
+                    TypeSystemDefinitionNode::Directive(
+                        self.parse_directive_definition(description)?,
+                    ),
+                )),
                 "extend" => Ok(DefinitionNode::Extension(
                     self.parse_type_extension(description)?,
                 )),
-                "query" | "fragment" => Ok(DefinitionNode::Executable(self.parse_executable()?)),
-                name => Err(ParseError::UnexpectedKeyword {
-                    expected: "A valid GraphQL keyword".into(),
-                    received: name.into(),
-                    location: *loc,
+                "query" | "mutation" | "subscription" | "fragment" if description.is_some() => {
+                    Err(ParseError::DescriptionNotAllowed(description_location))
+                }
+                "query" | "mutation" | "subscription" | "fragment" => {
+                    Ok(DefinitionNode::Executable(self.parse_executable()?))
+                }
+                keyword => Err(ParseError::UnknownDefinitionKeyword {
+                    keyword: keyword.into(),
+                    location: tok.location(),
                 }),
             },
-            Token::OpenBrace(_) => Ok(DefinitionNode::Executable(self.parse_executable()?)),
+            TokenKind::OpenBrace if description.is_some() => {
+                Err(ParseError::DescriptionNotAllowed(description_location))
+            }
+            TokenKind::OpenBrace => Ok(DefinitionNode::Executable(self.parse_executable()?)),
             _ => Err(ParseError::UnexpectedToken {
                 expected: "Token<Name> or Token<OpenBrace>".into(),
                 received: tok.to_string(),
@@ -185,8 +317,8 @@ impl<'i> AST<'i> {
 
     fn parse_type(&mut self, description: Description) -> ParseResult<TypeDefinitionNode> {
         let tok = self.unwrap_next_token()?;
-        if let Token::Name(_, val) = tok {
-            match val {
+        if tok.kind == TokenKind::Name {
+            match tok.text {
                 "type" => Ok(TypeDefinitionNode::Object(
                     self.parse_object_type(description)?,
                 )),
@@ -203,7 +335,10 @@ impl<'i> AST<'i> {
                 "scalar" => Ok(TypeDefinitionNode::Scalar(
                     self.parse_scalar_type(description)?,
                 )),
-                _ => Err(ParseError::BadValue),
+                keyword => Err(ParseError::UnknownDefinitionKeyword {
+                    keyword: keyword.into(),
+                    location: tok.location(),
+                }),
             }
         } else {
             Err(ParseError::UnexpectedToken {
@@ -214,15 +349,65 @@ impl<'i> AST<'i> {
         }
     }
 
+    fn parse_directive_definition(
+        &mut self,
+        description: Description,
+    ) -> ParseResult<DirectiveDefinitionNode> {
+        self.unwrap_next_token()?; // Discard "directive"
+        self.expect_token(TokenKind::At)?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
+        let arguments = self.parse_arguments_definition()?;
+        let repeatable = self.unwrap_peeked_token()?.text == "repeatable";
+        if repeatable {
+            self.unwrap_next_token()?;
+        }
+        let locations = self.parse_directive_locations()?;
+        let mut directive = DirectiveDefinitionNode::new(name_tok, description, locations)?;
+        directive
+            .with_arguments(arguments)
+            .with_repeatable(repeatable);
+        Ok(directive)
+    }
+
+    fn parse_directive_locations(&mut self) -> ParseResult<Vec<DirectiveLocation>> {
+        let tok = self.expect_token(TokenKind::Name)?;
+        if tok.text != "on" {
+            return Err(ParseError::UnexpectedKeyword {
+                expected: "on".into(),
+                received: tok.to_string(),
+                location: tok.location(),
+            });
+        }
+        // First Pipe is truely optional
+        self.expect_optional_token(TokenKind::Pipe);
+        let mut locations = vec![self.parse_directive_location()?];
+        while self.expect_optional_token(TokenKind::Pipe).is_some() {
+            locations.push(self.parse_directive_location()?);
+        }
+        Ok(locations)
+    }
+
+    fn parse_directive_location(&mut self) -> ParseResult<DirectiveLocation> {
+        let tok = self.expect_token(TokenKind::Name)?;
+        DirectiveLocation::try_from(tok.text).map_err(|_| ParseError::UnexpectedKeyword {
+            expected: "A directive location".into(),
+            received: tok.text.into(),
+            location: tok.location(),
+        })
+    }
+
     fn parse_type_extension(
         &mut self,
         description: Description,
     ) -> ParseResult<TypeSystemExtensionNode> {
         self.unwrap_next_token()?; // Discard "extend"
         match self.unwrap_next_token()? {
-            Token::Name(_, "type") => Ok(TypeSystemExtensionNode::Object(
-                self.parse_object_type_extension(description)?,
-            )),
+            tok if tok.kind == TokenKind::Name && tok.text == "type" => Ok(
+                TypeSystemExtensionNode::Object(self.parse_object_type_extension(description)?),
+            ),
+            tok if tok.kind == TokenKind::Name && tok.text == "schema" => Ok(
+                TypeSystemExtensionNode::Schema(self.parse_schema_extension()?),
+            ),
             tok => Err(ParseError::UnexpectedToken {
                 expected: String::from("Token::Name"),
                 received: tok.to_string().to_owned(),
@@ -235,7 +420,7 @@ impl<'i> AST<'i> {
         &mut self,
         description: Description,
     ) -> ParseResult<ObjectTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), ""))?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
         let interfaces = self.parse_object_interfaces()?;
         let directives = self.parse_directives()?;
         let fields = self.parse_fields()?;
@@ -258,7 +443,7 @@ impl<'i> AST<'i> {
         type_extension.with_interfaces(interfaces);
         type_extension.with_directives(directives);
 
-        if let Token::OpenBrace(_) = self.unwrap_peeked_token()? {
+        if self.unwrap_peeked_token()?.kind == TokenKind::OpenBrace {
             let fields = self.parse_fields()?;
             type_extension.with_fields(fields);
         }
@@ -270,7 +455,7 @@ impl<'i> AST<'i> {
         &mut self,
         description: Description,
     ) -> ParseResult<InterfaceTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), ""))?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
         let directives = self.parse_directives()?;
         let fields = self.parse_fields()?;
 
@@ -284,9 +469,11 @@ impl<'i> AST<'i> {
         &mut self,
         description: Description,
     ) -> ParseResult<InputTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), ""))?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
+        let directives = self.parse_directives()?;
         let mut input_type = InputTypeDefinitionNode::new(name_tok, description)?;
         let fields = self.parse_input_fields()?;
+        input_type.with_directives(directives);
         input_type.with_fields(fields);
         Ok(input_type)
     }
@@ -295,7 +482,7 @@ impl<'i> AST<'i> {
         &mut self,
         description: Description,
     ) -> ParseResult<ScalarTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), ""))?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
         let directives = self.parse_directives()?;
         let mut scalar_type = ScalarTypeDefinitionNode::new(name_tok, description)?;
         scalar_type.with_directives(directives);
@@ -303,65 +490,47 @@ impl<'i> AST<'i> {
     }
 
     fn parse_enum_type(&mut self, description: Description) -> ParseResult<EnumTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), "enum"))?;
-        if name_tok == Token::Name(Location::ignored(), "true")
-            || name_tok == Token::Name(Location::ignored(), "false")
-            || name_tok == Token::Name(Location::ignored(), "null")
-        {
-            return Err(ParseError::BadValue);
+        let name_tok = self.expect_token(TokenKind::Name)?;
+        if matches!(name_tok.text, "true" | "false" | "null") {
+            return Err(ParseError::ReservedName {
+                name: name_tok.text.into(),
+                location: name_tok.location(),
+            });
         }
         let directives = self.parse_directives()?;
         let values = self.parse_enum_values()?;
-        Ok(EnumTypeDefinitionNode::new(
-            name_tok,
-            description,
-            directives,
-            values,
-        )?)
+        EnumTypeDefinitionNode::new(name_tok, description, directives, values)
     }
 
     fn parse_union_type(
         &mut self,
         description: Description,
     ) -> ParseResult<UnionTypeDefinitionNode> {
-        let name_tok = self.expect_token(Token::Name(Location::ignored(), "union"))?;
+        let name_tok = self.expect_token(TokenKind::Name)?;
         let directives = self.parse_directives()?;
-        self.expect_token(Token::Equals(Location::ignored()))?;
+        self.expect_token(TokenKind::Equals)?;
         let types = self.parse_union_types()?;
-        Ok(UnionTypeDefinitionNode::new(
-            name_tok,
-            description,
-            directives,
-            types,
-        )?)
+        UnionTypeDefinitionNode::new(name_tok, description, directives, types)
     }
 
     fn parse_object_interfaces(&mut self) -> ParseResult<Option<Vec<NamedTypeNode>>> {
-        if let Some(name_tok) = self.expect_optional_token(&Token::Name(Location::ignored(), "")) {
-            match name_tok {
-                Token::Name(_, "implements") => {
-                    let mut interface_names: Vec<NamedTypeNode> = Vec::new();
-                    loop {
-                        let interface_name =
-                            self.expect_token(Token::Name(Location::ignored(), ""))?;
-                        interface_names.push(NamedTypeNode::new(interface_name)?);
-                        if let None = self.expect_optional_token(&Token::Amp(Location::ignored())) {
-                            break;
-                        }
-                    }
-                    Ok(Some(interface_names))
-                }
-                Token::Name(_, keyword) => Err(ParseError::UnexpectedKeyword {
+        if let Some(name_tok) = self.expect_optional_token(TokenKind::Name) {
+            if name_tok.text != "implements" {
+                return Err(ParseError::UnexpectedKeyword {
                     expected: String::from("implements"),
-                    received: keyword.to_owned(),
+                    received: name_tok.text.to_owned(),
                     location: name_tok.location(),
-                }),
-                tok => Err(ParseError::UnexpectedToken {
-                    expected: String::from("Token<Name>"),
-                    received: tok.to_string(),
-                    location: tok.location(),
-                }),
+                });
             }
+            let mut interface_names: Vec<NamedTypeNode> = Vec::new();
+            loop {
+                let interface_name = self.expect_token(TokenKind::Name)?;
+                interface_names.push(NamedTypeNode::new(interface_name)?);
+                if self.expect_optional_token(TokenKind::Amp).is_none() {
+                    break;
+                }
+            }
+            Ok(Some(interface_names))
         } else {
             Ok(None)
         }
@@ -369,9 +538,9 @@ impl<'i> AST<'i> {
 
     fn parse_fields(&mut self) -> ParseResult<Vec<FieldDefinitionNode>> {
         let mut fields: Vec<FieldDefinitionNode> = Vec::new();
-        self.expect_token(Token::OpenBrace(Location::ignored()))?;
+        self.expect_token(TokenKind::OpenBrace)?;
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
+            if self.expect_optional_token(TokenKind::CloseBrace).is_some() {
                 break;
             }
             fields.push(self.parse_field()?);
@@ -381,35 +550,44 @@ impl<'i> AST<'i> {
 
     fn parse_field(&mut self) -> ParseResult<FieldDefinitionNode> {
         let description = self.parse_description()?;
-        let name = self.expect_token(Token::Name(Location::ignored(), ""))?;
+        let name = self.expect_token(TokenKind::Name)?;
         let arguments = self.parse_arguments_definition()?;
-        println!("arguments, {:?}", arguments);
-        self.expect_token(Token::Colon(Location::ignored()))?;
+        self.expect_token(TokenKind::Colon)?;
         let field_type = self.parse_field_type()?;
-        FieldDefinitionNode::new(name, field_type, description, arguments)
+        let directives = self.parse_directives()?;
+        let mut field = FieldDefinitionNode::new(name, field_type, description, arguments)?;
+        field.with_directives(directives);
+        Ok(field)
     }
 
     fn parse_field_type(&mut self) -> ParseResult<TypeNode> {
-        let mut field_type: TypeNode;
-        if let Some(_) = self.expect_optional_token(&Token::OpenSquare(Location::ignored())) {
-            field_type = TypeNode::List(ListTypeNode::new(self.parse_field_type()?));
-            self.expect_token(Token::CloseSquare(Location::ignored()))?;
-        } else {
-            field_type = TypeNode::Named(NamedTypeNode::new(
-                self.expect_token(Token::Name(Location::ignored(), ""))?,
-            )?);
+        let mut lists = 0;
+        while let Some(open) = self.expect_optional_token(TokenKind::OpenSquare) {
+            if lists == MAX_DEPTH {
+                return Err(ParseError::TooDeep(open.location()));
+            }
+            lists += 1;
         }
-        if let Some(_) = self.expect_optional_token(&Token::Bang(Location::ignored())) {
-            field_type = TypeNode::NonNull(Arc::new(field_type));
+        let mut field_type =
+            TypeNode::Named(NamedTypeNode::new(self.expect_token(TokenKind::Name)?)?);
+        loop {
+            if self.expect_optional_token(TokenKind::Bang).is_some() {
+                field_type = TypeNode::NonNull(Arc::new(field_type));
+            }
+            if lists == 0 {
+                return Ok(field_type);
+            }
+            self.expect_token(TokenKind::CloseSquare)?;
+            field_type = TypeNode::List(ListTypeNode::new(field_type));
+            lists -= 1;
         }
-        Ok(field_type)
     }
 
     fn parse_input_fields(&mut self) -> ParseResult<Vec<InputValueDefinitionNode>> {
         let mut fields: Vec<InputValueDefinitionNode> = Vec::new();
-        let tok = self.expect_token(Token::OpenBrace(Location::ignored()))?;
+        let tok = self.expect_token(TokenKind::OpenBrace)?;
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
+            if self.expect_optional_token(TokenKind::CloseBrace).is_some() {
                 break;
             }
             fields.push(self.parse_input_value()?);
@@ -423,13 +601,19 @@ impl<'i> AST<'i> {
 
     fn parse_enum_values(&mut self) -> ParseResult<Vec<EnumValueDefinitionNode>> {
         let mut values: Vec<EnumValueDefinitionNode> = Vec::new();
-        self.expect_token(Token::OpenBrace(Location::ignored()))?;
+        self.expect_token(TokenKind::OpenBrace)?;
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
+            if self.expect_optional_token(TokenKind::CloseBrace).is_some() {
                 break;
             }
             let description = self.parse_description()?;
-            let name = self.expect_token(Token::Name(Location::ignored(), ""))?;
+            let name = self.expect_token(TokenKind::Name)?;
+            if matches!(name.text, "true" | "false" | "null") || name.text.starts_with("__") {
+                return Err(ParseError::InvalidEnumValue {
+                    name: name.text.to_owned(),
+                    location: name.location(),
+                });
+            }
             let directives = self.parse_directives()?;
             values.push(EnumValueDefinitionNode::new(name, description, directives)?);
         }
@@ -439,10 +623,10 @@ impl<'i> AST<'i> {
     fn parse_union_types(&mut self) -> ParseResult<Vec<NamedTypeNode>> {
         let mut types: Vec<NamedTypeNode> = Vec::new();
         // First Pipe is truely optional
-        self.expect_optional_token(&Token::Pipe(Location::ignored()));
+        self.expect_optional_token(TokenKind::Pipe);
         types.push(NamedTypeNode::new(self.unwrap_next_token()?)?);
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::Pipe(Location::ignored())) {
+            if self.expect_optional_token(TokenKind::Pipe).is_some() {
                 types.push(NamedTypeNode::new(self.unwrap_next_token()?)?);
             } else {
                 break;
@@ -452,92 +636,122 @@ impl<'i> AST<'i> {
     }
 
     fn parse_default_value(&mut self) -> ParseResult<Option<ValueNode>> {
-        match self.expect_optional_token(&Token::Equals(Location::ignored())) {
+        match self.expect_optional_token(TokenKind::Equals) {
             Some(_) => Ok(Some(self.parse_value()?)),
             None => Ok(None),
         }
     }
 
+    /// Parses a value, keeping the lists and input objects being parsed on a stack rather than
+    /// recursing into them.
     fn parse_value(&mut self) -> ParseResult<ValueNode> {
-        let tok = self.unwrap_peeked_token()?;
-        match *tok {
-            Token::Name(_, value) => {
+        let mut open: Vec<OpenValue> = Vec::new();
+        loop {
+            let tok = *self.unwrap_peeked_token()?;
+            let mut value = match tok.kind {
+                TokenKind::OpenSquare | TokenKind::OpenBrace if open.len() == MAX_DEPTH => {
+                    return Err(ParseError::TooDeep(tok.location()));
+                }
+                TokenKind::OpenSquare => {
+                    self.unwrap_next_token()?;
+                    open.push(OpenValue::List(Vec::new()));
+                    None
+                }
+                TokenKind::OpenBrace => {
+                    self.unwrap_next_token()?;
+                    open.push(OpenValue::Object(Vec::new(), None));
+                    None
+                }
+                _ => Some(self.parse_scalar_value()?),
+            };
+            loop {
+                if let Some(value) = value.take() {
+                    match open.last_mut() {
+                        None => return Ok(value),
+                        Some(OpenValue::List(values)) => values.push(value),
+                        Some(OpenValue::Object(fields, name)) => {
+                            if let Some(name) = name.take() {
+                                fields.push(ObjectFieldNode { name, value });
+                            }
+                        }
+                    }
+                }
+                match open.last_mut() {
+                    Some(OpenValue::List(_)) => {
+                        if self.expect_optional_token(TokenKind::CloseSquare).is_none() {
+                            break;
+                        }
+                        if let Some(OpenValue::List(values)) = open.pop() {
+                            value = Some(ValueNode::List(ListValueNode { values }));
+                        }
+                    }
+                    Some(OpenValue::Object(_, name)) => {
+                        if self.expect_optional_token(TokenKind::CloseBrace).is_none() {
+                            let field_name = self.unwrap_next_token()?;
+                            self.expect_token(TokenKind::Colon)?;
+                            *name = Some(NameNode::new(field_name)?);
+                            break;
+                        }
+                        if let Some(OpenValue::Object(fields, _)) = open.pop() {
+                            value = Some(ValueNode::Object(ObjectValueNode { fields }));
+                        }
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+
+    fn parse_scalar_value(&mut self) -> ParseResult<ValueNode> {
+        let tok = *self.unwrap_peeked_token()?;
+        match tok.kind {
+            TokenKind::Name => {
                 self.unwrap_next_token()?;
-                match value {
+                match tok.text {
                     "true" => Ok(ValueNode::Bool(BooleanValueNode { value: true })),
                     "false" => Ok(ValueNode::Bool(BooleanValueNode { value: false })),
                     "null" => Ok(ValueNode::Null),
-                    _ => Ok(ValueNode::Enum(EnumValueNode {
+                    value => Ok(ValueNode::Enum(EnumValueNode {
                         value: value.to_owned(),
                     })),
                 }
             }
-            Token::Int(_, value) => {
+            TokenKind::Int => {
                 self.unwrap_next_token()?;
-                Ok(ValueNode::Int(IntValueNode { value }))
+                Ok(ValueNode::Int(IntValueNode {
+                    value: tok.text.to_owned(),
+                }))
             }
-            Token::Float(_, value) => {
+            TokenKind::Float => {
                 self.unwrap_next_token()?;
-                Ok(ValueNode::Float(FloatValueNode { value }))
+                let value = tok.text.parse().map_err(|_| {
+                    ParseError::LexError(LexError::UnableToConvert(tok.location(), "Float"))
+                })?;
+                Ok(ValueNode::Float(FloatValueNode {
+                    value,
+                    lexeme: Some(tok.text.to_owned()),
+                }))
             }
-            Token::Str(_, _) | Token::BlockStr(_, _) => {
+            TokenKind::Str | TokenKind::BlockStr => {
                 let str_tok = self.unwrap_next_token()?;
                 Ok(ValueNode::Str(StringValueNode::new(str_tok)?))
             }
-            Token::Dollar(_) => {
+            TokenKind::Dollar => {
                 let variable = self.parse_variable()?;
                 Ok(ValueNode::Variable(variable))
             }
-            Token::OpenSquare(_) => {
-                let list_value = self.parse_list_value()?;
-                Ok(ValueNode::List(list_value))
-            }
-            Token::OpenBrace(_) => {
-                let obj_value = self.parse_object_value()?;
-                Ok(ValueNode::Object(obj_value))
-            }
             _ => Err(ParseError::UnexpectedToken {
                 expected: String::from(
                     "One of (Name, Int, Float, Str, Dollar, OpenSquare, OpenBrace)",
                 ),
-                received: tok.to_owned().to_string(),
+                received: tok.to_string(),
                 location: tok.location(),
             }),
         }
     }
 
-    fn parse_list_value(&mut self) -> ParseResult<ListValueNode> {
-        self.expect_token(Token::OpenSquare(Location::ignored()))?;
-        let mut values: Vec<ValueNode> = Vec::new();
-        loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseSquare(Location::ignored())) {
-                break;
-            }
-            values.push(self.parse_value()?);
-        }
-        Ok(ListValueNode { values })
-    }
-
-    fn parse_object_value(&mut self) -> ParseResult<ObjectValueNode> {
-        self.expect_token(Token::OpenBrace(Location::ignored()))?;
-        let mut fields: Vec<ObjectFieldNode> = Vec::new();
-        loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
-                break;
-            }
-            let name = self.unwrap_next_token()?;
-            self.expect_token(Token::Colon(Location::ignored()))?;
-            let value = self.parse_value()?;
-            fields.push(ObjectFieldNode {
-                name: NameNode::new(name)?,
-                value,
-            });
-        }
-        Ok(ObjectValueNode { fields })
-    }
-
     fn parse_variable(&mut self) -> ParseResult<VariableNode> {
-        self.expect_token(Token::Dollar(Location::ignored()))?;
+        self.expect_token(TokenKind::Dollar)?;
         let name = self.unwrap_next_token()?;
         Ok(VariableNode {
             name: NameNode::new(name)?,
@@ -546,7 +760,7 @@ impl<'i> AST<'i> {
 
     fn parse_schema(&mut self, description: Description) -> ParseResult<SchemaDefinitionNode> {
         match self.unwrap_next_token()? {
-            Token::Name(location, keyword) => match keyword {
+            tok if tok.kind == TokenKind::Name => match tok.text {
                 "schema" => {
                     let directives = self.parse_directives()?;
                     let operations = self.parse_schema_operation_types()?;
@@ -556,10 +770,10 @@ impl<'i> AST<'i> {
                         operations,
                     })
                 }
-                _ => Err(ParseError::UnexpectedKeyword {
+                keyword => Err(ParseError::UnexpectedKeyword {
                     expected: "Keyword `schema`".into(),
                     received: keyword.into(),
-                    location,
+                    location: tok.location(),
                 }),
             },
             tok => Err(ParseError::UnexpectedToken {
@@ -570,16 +784,29 @@ impl<'i> AST<'i> {
         }
     }
 
+    fn parse_schema_extension(&mut self) -> ParseResult<SchemaExtensionNode> {
+        let directives = self.parse_directives()?;
+        let operations = if self.unwrap_peeked_token()?.kind == TokenKind::OpenBrace {
+            Some(self.parse_schema_operation_types()?)
+        } else {
+            None
+        };
+        Ok(SchemaExtensionNode {
+            directives,
+            operations,
+        })
+    }
+
     fn parse_schema_operation_types(&mut self) -> ParseResult<Vec<OperationTypeDefinitionNode>> {
-        self.expect_token(Token::OpenBrace(Location::ignored()))?;
+        self.expect_token(TokenKind::OpenBrace)?;
         let mut operations = Vec::new();
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
+            if self.expect_optional_token(TokenKind::CloseBrace).is_some() {
                 break;
             }
 
             let operation = self.parse_schema_operation()?;
-            self.expect_token(Token::Colon(Location::ignored()))?;
+            self.expect_token(TokenKind::Colon)?;
             operations.push(OperationTypeDefinitionNode {
                 operation,
                 node_type: NamedTypeNode::new(self.unwrap_next_token()?)?,
@@ -590,15 +817,15 @@ impl<'i> AST<'i> {
 
     fn parse_schema_operation(&mut self) -> ParseResult<Operation> {
         match self.unwrap_next_token()? {
-            Token::Name(loc, name) => match name {
+            tok if tok.kind == TokenKind::Name => match tok.text {
                 "query" => Ok(Operation::Query),
                 "mutation" => Ok(Operation::Mutation),
                 "subscription" => Ok(Operation::Subscription),
-                _ => Err(ParseError::UnexpectedKeyword {
+                name => Err(ParseError::UnexpectedKeyword {
                     expected: "one of `query`, `mutation`, or `subscription` as schema operations"
                         .into(),
                     received: name.into(),
-                    location: loc,
+                    location: tok.location(),
                 }),
             },
             tok => Err(ParseError::UnexpectedToken {
@@ -610,23 +837,25 @@ impl<'i> AST<'i> {
     }
 
     fn parse_executable(&mut self) -> ParseResult<ExecutableDefinitionNode> {
-        let tok = self.unwrap_peeked_token()?;
-        match tok {
-            Token::Name(location, val) => match *val {
-                "query" /* | "mutation" | "subscription" */ => Ok(ExecutableDefinitionNode::Operation(self.parse_operation_type()?)),
-                "fragment" =>
-                    Ok(ExecutableDefinitionNode::Fragment(self.parse_fragment_definition()?))
-                ,
+        let tok = *self.unwrap_peeked_token()?;
+        match tok.kind {
+            TokenKind::Name => match tok.text {
+                "query" | "mutation" | "subscription" => Ok(ExecutableDefinitionNode::Operation(
+                    self.parse_operation_type()?,
+                )),
+                "fragment" => Ok(ExecutableDefinitionNode::Fragment(
+                    self.parse_fragment_definition()?,
+                )),
                 keyword => Err(ParseError::UnexpectedKeyword {
-                    expected: "One of `query` or `fragment`".into(),
+                    expected: "One of `query`, `mutation`, `subscription` or `fragment`".into(),
                     received: keyword.into(),
-                    location: *location,
+                    location: tok.location(),
                 }),
             },
-            Token::OpenBrace(_) => Ok(ExecutableDefinitionNode::Operation(
+            TokenKind::OpenBrace => Ok(ExecutableDefinitionNode::Operation(
                 OperationTypeNode::Query(self.parse_anonymous_query()?),
             )),
-            tok => Err(ParseError::UnexpectedToken {
+            _ => Err(ParseError::UnexpectedToken {
                 expected: String::from(
                     "One of 'query', 'mutation', 'subscription', 'fragment', or anonymous query",
                 ),
@@ -638,13 +867,14 @@ impl<'i> AST<'i> {
 
     fn parse_operation_type(&mut self) -> ParseResult<OperationTypeNode> {
         let keyword = self.unwrap_next_token()?;
-        if let Token::Name(loc, name) = keyword {
-            match name {
+        if keyword.kind == TokenKind::Name {
+            match keyword.text {
                 "query" => Ok(OperationTypeNode::Query(self.parse_query()?)),
-                _ => Err(ParseError::UnexpectedKeyword {
-                    expected: "One of 'query'".into(),
-                    received: "name".into(),
-                    location: loc,
+                "mutation" => Ok(OperationTypeNode::Mutation(self.parse_query()?)),
+                "subscription" => Ok(OperationTypeNode::Subscription(self.parse_query()?)),
+                operation => Err(ParseError::UnsupportedOperation {
+                    operation: operation.into(),
+                    location: keyword.location(),
                 }),
             }
         } else {
@@ -656,12 +886,17 @@ impl<'i> AST<'i> {
         }
     }
 
+    /// Parses what follows the keyword of an operation: its name, unless it is anonymous, its
+    /// variables and its selections.
     fn parse_query(&mut self) -> ParseResult<QueryDefinitionNode> {
-        let name = self.unwrap_next_token()?;
+        let name = match self.unwrap_peeked_token()?.kind {
+            TokenKind::OpenBrace | TokenKind::OpenParen => None,
+            _ => Some(NameNode::new(self.unwrap_next_token()?)?),
+        };
         let variables = self.parse_variables()?;
         let selections = self.parse_selection_set()?;
         Ok(QueryDefinitionNode {
-            name: Some(NameNode::new(name)?),
+            name,
             variables,
             selections,
         })
@@ -669,10 +904,9 @@ impl<'i> AST<'i> {
 
     fn parse_variables(&mut self) -> ParseResult<Option<Variables>> {
         let mut variables = Vec::new();
-        if let Some(_) = self.expect_optional_token(&Token::OpenParen(Location::ignored())) {
+        if self.expect_optional_token(TokenKind::OpenParen).is_some() {
             loop {
-                if let Some(_) = self.expect_optional_token(&Token::CloseParen(Location::ignored()))
-                {
+                if self.expect_optional_token(TokenKind::CloseParen).is_some() {
                     break;
                 }
                 variables.push(self.parse_variable_definition()?);
@@ -687,14 +921,14 @@ impl<'i> AST<'i> {
 
     fn parse_variable_definition(&mut self) -> ParseResult<VariableDefinitionNode> {
         let variable = self.parse_variable()?;
-        self.expect_token(Token::Colon(Location::ignored()))?;
+        self.expect_token(TokenKind::Colon)?;
         let variable_type = self.parse_field_type()?;
         let mut var = VariableDefinitionNode {
             variable,
             variable_type,
             default_value: None,
         };
-        if let Some(_) = self.expect_optional_token(&Token::Equals(Location::ignored())) {
+        if self.expect_optional_token(TokenKind::Equals).is_some() {
             let value = self.parse_value()?;
             var.default_value = Some(value);
         }
@@ -710,31 +944,58 @@ impl<'i> AST<'i> {
         })
     }
 
+    /// Parses a selection set, keeping the selections whose own selection sets are being parsed on
+    /// a stack rather than recursing into them.
     fn parse_selection_set(&mut self) -> ParseResult<Vec<Selection>> {
-        self.expect_token(Token::OpenBrace(Location::ignored()))?;
-        let mut selections = Vec::new();
+        self.expect_token(TokenKind::OpenBrace)?;
+        let mut open: Vec<(Option<SelectionHead>, Vec<Selection>)> = vec![(None, Vec::new())];
         loop {
-            if let Some(_) = self.expect_optional_token(&Token::CloseBrace(Location::ignored())) {
-                break;
+            if self.expect_optional_token(TokenKind::CloseBrace).is_some() {
+                let (head, selections) = match open.pop() {
+                    Some((Some(head), selections)) => (head, selections),
+                    Some((None, selections)) => return Ok(selections),
+                    None => return Ok(Vec::new()),
+                };
+                let selection = head.into_selection(Some(selections));
+                if let Some((_, selections)) = open.last_mut() {
+                    selections.push(selection);
+                }
+                continue;
             }
-            selections.push(self.parse_selection()?);
+            let head = self.parse_selection_head()?;
+            let opens = match &head {
+                SelectionHead::Field(_) => self.unwrap_peeked_token()?.kind == TokenKind::OpenBrace,
+                SelectionHead::Inline(_, _) => true,
+                SelectionHead::Spread(_) => false,
+            };
+            if !opens {
+                if let Some((_, selections)) = open.last_mut() {
+                    selections.push(head.into_selection(None));
+                }
+                continue;
+            }
+            let brace = self.expect_token(TokenKind::OpenBrace)?;
+            if open.len() == MAX_DEPTH {
+                return Err(ParseError::TooDeep(brace.location()));
+            }
+            open.push((Some(head), Vec::new()));
         }
-        Ok(selections)
     }
 
-    fn parse_selection(&mut self) -> ParseResult<Selection> {
-        match self.unwrap_peeked_token()? {
-            Token::Name(_, _) => Ok(Selection::Field(self.parse_field_node()?)),
-            Token::Spread(_) => Ok(Selection::Fragment(self.parse_fragment_spread()?)),
+    fn parse_selection_head(&mut self) -> ParseResult<SelectionHead> {
+        match self.unwrap_peeked_token()?.kind {
+            TokenKind::Name => Ok(SelectionHead::Field(self.parse_field_head()?)),
+            TokenKind::Spread => self.parse_fragment_spread_head(),
             _ => Err(ParseError::NotImplemented),
         }
     }
 
-    fn parse_field_node(&mut self) -> ParseResult<FieldNode> {
+    /// Parses a field up to its selection set.
+    fn parse_field_head(&mut self) -> ParseResult<FieldNode> {
         let mut field: FieldNode;
 
         let name = self.unwrap_next_token()?;
-        if let Some(_) = self.expect_optional_token(&Token::Colon(Location::ignored())) {
+        if self.expect_optional_token(TokenKind::Colon).is_some() {
             let root = self.unwrap_next_token()?;
             field = FieldNode::new(root)?;
             field.with_alias(name)?;
@@ -748,32 +1009,40 @@ impl<'i> AST<'i> {
         let directives = self.parse_directives()?;
         field.with_directives(directives);
 
-        if let &Token::OpenBrace(_) = self.unwrap_peeked_token()? {
-            let selections = self.parse_selection_set()?;
-            field.with_selections(selections);
-        }
-
         Ok(field)
     }
 
     fn parse_fragment_definition(&mut self) -> ParseResult<FragmentDefinitionNode> {
         let keyword = self.unwrap_next_token()?;
-        if let Token::Name(loc, name) = keyword {
-            match name {
+        if keyword.kind == TokenKind::Name {
+            match keyword.text {
                 "fragment" => {
                     let name = self.unwrap_next_token()?;
-                    let _on = self.unwrap_next_token()?;
+                    let variables = if self.options.allow_legacy_fragment_variables {
+                        self.parse_variables()?
+                    } else {
+                        None
+                    };
+                    let tok = self.unwrap_next_token()?;
+                    if tok.kind != TokenKind::Name || tok.text != "on" {
+                        return Err(ParseError::UnexpectedKeyword {
+                            expected: "on".into(),
+                            received: tok.to_string(),
+                            location: tok.location(),
+                        });
+                    }
                     let node_type = self.unwrap_next_token()?;
                     let frag_def = FragmentDefinitionNode::new(name, node_type)?
+                        .with_variables(variables)
                         .with_directives(self.parse_directives()?)
                         .with_selections(self.parse_selection_set()?);
 
                     Ok(frag_def)
                 }
-                _ => Err(ParseError::UnexpectedKeyword {
+                name => Err(ParseError::UnexpectedKeyword {
                     expected: "fragment".into(),
                     received: name.into(),
-                    location: loc,
+                    location: keyword.location(),
                 }),
             }
         } else {
@@ -785,17 +1054,23 @@ impl<'i> AST<'i> {
         }
     }
 
-    fn parse_fragment_spread(&mut self) -> ParseResult<FragmentSpread> {
-        self.expect_token(Token::Spread(Location::ignored()))?;
-        match self.unwrap_peeked_token()? {
-            &Token::Name(_, "on") => {
-                Ok(FragmentSpread::Inline(self.parse_inline_fragment_spread()?))
+    /// Parses a fragment spread, or an inline fragment up to its selection set.
+    fn parse_fragment_spread_head(&mut self) -> ParseResult<SelectionHead> {
+        self.expect_token(TokenKind::Spread)?;
+        let tok = *self.unwrap_peeked_token()?;
+        match tok.kind {
+            TokenKind::Name if tok.text == "on" => {
+                let _on_tok = self.unwrap_next_token()?;
+                let name = self.unwrap_next_token()?;
+                let directives = self.parse_directives()?;
+                Ok(SelectionHead::Inline(
+                    Some(NamedTypeNode::new(name)?),
+                    directives,
+                ))
             }
-            &Token::At(_) => Ok(FragmentSpread::Inline(
-                self.parse_anonymous_inline_fragmen_spread()?,
-            )),
-            &Token::Name(_, _) => Ok(FragmentSpread::Node(self.parse_fragment_spread_node()?)),
-            tok => Err(ParseError::UnexpectedToken {
+            TokenKind::At => Ok(SelectionHead::Inline(None, self.parse_directives()?)),
+            TokenKind::Name => Ok(SelectionHead::Spread(self.parse_fragment_spread_node()?)),
+            _ => Err(ParseError::UnexpectedToken {
                 location: tok.location(),
                 expected: "One of Token::Name or Token::At".into(),
                 received: tok.to_string(),
@@ -812,63 +1087,44 @@ impl<'i> AST<'i> {
         })
     }
 
-    fn parse_inline_fragment_spread(&mut self) -> ParseResult<InlineFragmentSpreadNode> {
-        let _on_tok = self.unwrap_next_token()?;
-        let name = self.unwrap_next_token()?;
-        let directives = self.parse_directives()?;
-        let selections = self.parse_selection_set()?;
-        Ok(InlineFragmentSpreadNode {
-            node_type: Some(NamedTypeNode::new(name)?),
-            directives,
-            selections,
-        })
-    }
-
-    fn parse_anonymous_inline_fragmen_spread(&mut self) -> ParseResult<InlineFragmentSpreadNode> {
-        let directives = self.parse_directives()?;
-        let selections = self.parse_selection_set()?;
-        Ok(InlineFragmentSpreadNode {
-            node_type: None,
-            directives,
-            selections,
-        })
+    fn expect_token(&mut self, kind: TokenKind) -> ParseResult<Token<'i>> {
+        match self.lexer.next() {
+            Some(Ok(actual)) if actual.kind == kind => Ok(actual),
+            Some(Ok(actual)) => Err(ParseError::UnexpectedToken {
+                expected: kind.to_string(),
+                received: actual.to_string(),
+                location: actual.location(),
+            }),
+            Some(Err(e)) => Err(e),
+            None => Err(ParseError::EOF),
+        }
     }
 
-    fn expect_token(&mut self, tok: Token<'i>) -> ParseResult<Token<'i>> {
-        if let Some(next) = self.lexer.next() {
-            match next {
-                Ok(actual) => {
-                    if actual.is_same_type(&tok) {
-                        Ok(actual)
-                    } else {
-                        Err(ParseError::UnexpectedToken {
-                            expected: tok.to_string(),
-                            received: actual.to_string(),
-                            location: actual.location(),
-                        })
-                    }
-                }
-                Err(e) => Err(ParseError::LexError(e)),
+    /// Consumes the end of the token stream, or reports the first of the tokens left before it,
+    /// with how many of them there are, up to the end or to a character that cannot be lexed.
+    fn expect_end(&mut self) -> ParseResult<()> {
+        let first = self.unwrap_next_token()?;
+        if first.kind == TokenKind::End {
+            return Ok(());
+        }
+        let mut count = 1;
+        while let Some(Ok(token)) = self.lexer.next() {
+            if token.kind == TokenKind::End {
+                break;
             }
-        } else {
-            Err(ParseError::EOF)
+            count += 1;
         }
+        Err(ParseError::TrailingTokens {
+            received: first.to_string(),
+            count,
+            location: first.location(),
+        })
     }
 
-    fn expect_optional_token(&mut self, tok: &Token<'i>) -> Option<Token<'i>> {
-        if let Some(next) = self.lexer.peek() {
-            match next {
-                Ok(actual) => {
-                    if actual.is_same_type(tok) {
-                        Some(self.lexer.next().unwrap().unwrap())
-                    } else {
-                        None
-                    }
-                }
-                Err(_) => None,
-            }
-        } else {
-            None
+    fn expect_optional_token(&mut self, kind: TokenKind) -> Option<Token<'i>> {
+        match self.lexer.peek() {
+            Some(Ok(actual)) if actual.kind == kind => self.lexer.next()?.ok(),
+            _ => None,
         }
     }
 
@@ -876,7 +1132,7 @@ impl<'i> AST<'i> {
         match self.lexer.peek() {
             Some(res) => match res {
                 Ok(tok) => Ok(tok),
-                Err(lex_error) => Err(ParseError::LexError(*lex_error)),
+                Err(error) => Err(error.clone()),
             },
             None => Err(ParseError::EOF),
         }
@@ -884,10 +1140,7 @@ impl<'i> AST<'i> {
 
     fn unwrap_next_token(&mut self) -> ParseResult<Token<'i>> {
         match self.lexer.next() {
-            Some(res) => match res {
-                Ok(tok) => Ok(tok),
-                Err(lex_error) => Err(ParseError::LexError(lex_error)),
-            },
+            Some(res) => res,
             None => Err(ParseError::EOF),
         }
     }
@@ -901,69 +1154,132 @@ impl<'i> AST<'i> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::Location;
 
     #[test]
     fn it_constructs() {
-        let ast = AST::new("test");
+        let ast = Ast::new("test");
         assert!(ast.is_ok());
     }
 
+    #[test]
+    fn it_parses_directive_definitions() {
+        let mut ast =
+            Ast::new("directive @key(fields: String!) repeatable on | OBJECT | INTERFACE").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
+        let definition = ast.parse_definition().unwrap();
+        if let DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) =
+            definition
+        {
+            assert_eq!(directive.name.value, "key");
+            assert!(directive.repeatable);
+            assert_eq!(directive.arguments.unwrap().len(), 1);
+            assert_eq!(
+                directive.locations,
+                vec![DirectiveLocation::Object, DirectiveLocation::Interface]
+            );
+        } else {
+            panic!("Expected a directive definition");
+        }
+
+        let mut ast = Ast::new("directive @key on OBJECT | NOWHERE").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
+        assert!(matches!(
+            ast.parse_definition(),
+            Err(ParseError::UnexpectedKeyword { received, .. }) if received == "NOWHERE"
+        ));
+    }
+
     #[test]
     fn it_parses_int_value() {
-        let mut ast = AST::new("42").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("42").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         println!("IntValue: {:?}", value);
         assert!(value.is_ok());
-        assert_eq!(value.unwrap(), ValueNode::Int(IntValueNode { value: 42 }));
+        assert_eq!(value.unwrap(), ValueNode::Int(IntValueNode::from(42)));
     }
 
     #[test]
     fn it_parses_float_value() {
-        let mut ast = AST::new("3.1415926").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("12.375").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         println!("FloatValue: {:?}", value);
         assert!(value.is_ok());
         assert_eq!(
             value.unwrap(),
-            ValueNode::Float(FloatValueNode { value: 3.1415926 })
+            ValueNode::Float(FloatValueNode::from(12.375))
         );
     }
 
+    #[test]
+    fn it_compares_floats_by_value() {
+        use std::collections::HashSet;
+
+        let parse = |input| {
+            let mut ast = Ast::new(input).unwrap();
+            ast.expect_token(TokenKind::Start).unwrap();
+            ast.parse_value().unwrap()
+        };
+        let float = parse("1.10");
+        assert_eq!(
+            float,
+            ValueNode::Float(FloatValueNode {
+                value: 1.1,
+                lexeme: Some("1.10".into()),
+            })
+        );
+        assert_eq!(float, parse("1.100"));
+        assert_eq!(parse("-0.0"), parse("0.0"));
+        assert_ne!(parse("1.5"), parse("2.5"));
+        let nan = ValueNode::Float(FloatValueNode::from(f64::NAN));
+        assert_eq!(nan, ValueNode::Float(FloatValueNode::from(-f64::NAN)));
+        let values: HashSet<ValueNode> =
+            vec![float, parse("1.1"), parse("-0.0"), parse("0.0"), nan]
+                .into_iter()
+                .collect();
+        assert_eq!(values.len(), 3);
+    }
+
     #[test]
     fn it_parses_block_string_values() {
-        let mut ast = AST::new(r#""""BlockStrValue""""#).unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new(r#""""BlockStrValue""""#).unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
             value.unwrap(),
             ValueNode::Str(
-                StringValueNode::new(Token::BlockStr(Location::ignored(), "BlockStrValue"))
-                    .unwrap()
+                StringValueNode::new(Token::new(
+                    TokenKind::BlockStr,
+                    Location::ignored(),
+                    "BlockStrValue"
+                ))
+                .unwrap()
             )
         );
     }
 
     #[test]
     fn it_parses_string_values() {
-        let mut ast = AST::new(r#""StrValue""#).unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new(r#""StrValue""#).unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
             value.unwrap(),
             ValueNode::Str(
-                StringValueNode::new(Token::Str(Location::ignored(), "StrValue")).unwrap()
+                StringValueNode::new(Token::new(TokenKind::Str, Location::ignored(), "StrValue"))
+                    .unwrap()
             )
         );
     }
 
     #[test]
     fn it_parses_bool_values() {
-        let mut ast = AST::new("true, false").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("true, false").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
@@ -980,8 +1296,8 @@ mod tests {
 
     #[test]
     fn it_parses_null_value() {
-        let mut ast = AST::new("null").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("null").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(value.unwrap(), ValueNode::Null);
@@ -989,8 +1305,8 @@ mod tests {
 
     #[test]
     fn it_parses_list_value() {
-        let mut ast = AST::new("[true, false], [[1,2,3],[4,5,6]]").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("[true, false], [[1,2,3],[4,5,6]]").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
@@ -1010,16 +1326,16 @@ mod tests {
                 values: vec![
                     ValueNode::List(ListValueNode {
                         values: vec![
-                            ValueNode::Int(IntValueNode { value: 1 }),
-                            ValueNode::Int(IntValueNode { value: 2 }),
-                            ValueNode::Int(IntValueNode { value: 3 }),
+                            ValueNode::Int(IntValueNode::from(1)),
+                            ValueNode::Int(IntValueNode::from(2)),
+                            ValueNode::Int(IntValueNode::from(3)),
                         ]
                     }),
                     ValueNode::List(ListValueNode {
                         values: vec![
-                            ValueNode::Int(IntValueNode { value: 4 }),
-                            ValueNode::Int(IntValueNode { value: 5 }),
-                            ValueNode::Int(IntValueNode { value: 6 }),
+                            ValueNode::Int(IntValueNode::from(4)),
+                            ValueNode::Int(IntValueNode::from(5)),
+                            ValueNode::Int(IntValueNode::from(6)),
                         ]
                     })
                 ]
@@ -1029,8 +1345,8 @@ mod tests {
 
     #[test]
     fn it_parses_object_value() {
-        let mut ast = AST::new(r#"{}, { id: 42, name: "Obj"}"#).unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new(r#"{}, { id: 42, name: "Obj"}"#).unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
@@ -1046,12 +1362,17 @@ mod tests {
                 fields: vec![
                     ObjectFieldNode {
                         name: NameNode::from("id"),
-                        value: ValueNode::Int(IntValueNode { value: 42 }),
+                        value: ValueNode::Int(IntValueNode::from(42)),
                     },
                     ObjectFieldNode {
                         name: NameNode::from("name"),
                         value: ValueNode::Str(
-                            StringValueNode::new(Token::Str(Location::ignored(), "Obj")).unwrap()
+                            StringValueNode::new(Token::new(
+                                TokenKind::Str,
+                                Location::ignored(),
+                                "Obj"
+                            ))
+                            .unwrap()
                         ),
                     }
                 ]
@@ -1061,8 +1382,8 @@ mod tests {
 
     #[test]
     fn parses_a_variable() {
-        let mut ast = AST::new("$myVariable").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("$myVariable").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_value();
         assert!(value.is_ok());
         assert_eq!(
@@ -1075,8 +1396,8 @@ mod tests {
 
     #[test]
     fn parses_a_directive() {
-        let mut ast = AST::new("@deprecated").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("@deprecated").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_directives();
         assert!(value.is_ok());
         assert_eq!(
@@ -1090,8 +1411,8 @@ mod tests {
 
     #[test]
     fn parses_directive_with_arguments() {
-        let mut ast = AST::new("@include(if: true)").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("@include(if: true)").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_directives();
         assert!(value.is_ok());
         assert_eq!(
@@ -1108,8 +1429,8 @@ mod tests {
 
     #[test]
     fn parses_directive_with_multiple_arguments() {
-        let mut ast = AST::new("@size(height: 100, width: 50)").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("@size(height: 100, width: 50)").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_directives();
         assert!(value.is_ok());
         assert_eq!(
@@ -1119,21 +1440,48 @@ mod tests {
                 arguments: Some(vec![
                     Argument {
                         name: NameNode::from("height"),
-                        value: ValueNode::Int(IntValueNode { value: 100 })
+                        value: ValueNode::Int(IntValueNode::from(100))
                     },
                     Argument {
                         name: NameNode::from("width"),
-                        value: ValueNode::Int(IntValueNode { value: 50 })
+                        value: ValueNode::Int(IntValueNode::from(50))
                     }
                 ]),
             }]
         )
     }
 
+    #[test]
+    fn it_rejects_invalid_enum_values() {
+        let parse = |input| {
+            let mut ast = Ast::new(input).unwrap();
+            ast.expect_token(TokenKind::Start).unwrap();
+            ast.parse_type(None)
+        };
+        for (input, name, column) in [
+            ("enum Answer { YES true }", "true", 19),
+            ("enum Answer { null }", "null", 15),
+            ("enum Answer { YES \"Internal\" __NO }", "__NO", 30),
+        ] {
+            assert_eq!(
+                parse(input),
+                Err(ParseError::InvalidEnumValue {
+                    name: name.into(),
+                    location: Location::new(column - 1, 1, column),
+                })
+            );
+        }
+        assert_eq!(
+            parse("enum Answer { null }").unwrap_err().to_string(),
+            "Parse Error: Invalid enum value on line 1, column 15: \"null\", true, false and null are not enum values"
+        );
+        assert!(parse("enum Answer { TRUE False nullable _yes }").is_ok());
+    }
+
     #[test]
     fn parses_enum_with_directives() {
-        let mut ast = AST::new("enum BadDirection @depricated { NORTH SWEST @badValue EAST WOUTH @badValue(allow: true) }").unwrap();
-        ast.expect_token(Token::Start).unwrap();
+        let mut ast = Ast::new("enum BadDirection @depricated { NORTH SWEST @badValue EAST WOUTH @badValue(allow: true) }").unwrap();
+        ast.expect_token(TokenKind::Start).unwrap();
         let value = ast.parse_type(None);
         println!("Value: {:?}", value);
         assert!(value.is_ok());
@@ -1180,4 +1528,125 @@ mod tests {
             })
         )
     }
+
+    #[test]
+    fn it_parses_nesting_without_recursing() {
+        fn nested<T>(
+            open: &str,
+            inner: &str,
+            close: &str,
+            depth: usize,
+            parse: impl FnOnce(&mut Ast<'_>) -> ParseResult<T>,
+        ) -> ParseResult<T> {
+            let input = format!("{}{}{}", open.repeat(depth), inner, close.repeat(depth));
+            let mut ast = Ast::new(&input)?;
+            ast.expect_token(TokenKind::Start)?;
+            parse(&mut ast)
+        }
+        // The parser's own stack use no longer grows with the nesting.
+        std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(|| {
+                assert!(nested("[", "1", "]", MAX_DEPTH, |ast| ast.parse_value()).is_ok());
+                assert!(nested("{a:", "1", "}", MAX_DEPTH, |ast| ast.parse_value()).is_ok());
+                assert!(nested("[", "A!", "]!", MAX_DEPTH, |ast| ast.parse_field_type()).is_ok());
+                assert!(
+                    nested("{ a ", "", "}", MAX_DEPTH, |ast| ast.parse_selection_set()).is_ok()
+                );
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert!(matches!(
+            nested("[", "1", "]", MAX_DEPTH + 1, |ast| ast.parse_value()),
+            Err(ParseError::TooDeep(_))
+        ));
+        assert!(matches!(
+            nested("{ ... on A ", "", "}", MAX_DEPTH + 1, |ast| ast
+                .parse_selection_set()),
+            Err(ParseError::TooDeep(_))
+        ));
+    }
+
+    #[test]
+    fn it_rejects_descriptions_on_executable_definitions() {
+        let parse = |input| Ast::new(input).unwrap().parse_definitions();
+        assert_eq!(
+            parse("type A { a: Int }\n\"Gets a\" query { a }"),
+            Err(ParseError::DescriptionNotAllowed(Location::new(18, 2, 1)))
+        );
+        assert_eq!(
+            parse("\"Gets a\" { a }"),
+            Err(ParseError::DescriptionNotAllowed(Location::new(0, 1, 1)))
+        );
+        assert_eq!(
+            parse("\"A\" fragment F on A { a }"),
+            Err(ParseError::DescriptionNotAllowed(Location::new(0, 1, 1)))
+        );
+        assert!(parse("\"A type\" type A { a: Int } query { a }").is_ok());
+    }
+
+    #[test]
+    fn it_reports_the_text_of_bad_definitions() {
+        let parse = |input| Ast::new(input).unwrap().parse_definitions();
+        assert_eq!(
+            parse("type A { a: Int }\ntpye B { b: Int }"),
+            Err(ParseError::UnknownDefinitionKeyword {
+                keyword: "tpye".into(),
+                location: Location::new(18, 2, 1),
+            })
+        );
+        assert_eq!(
+            parse("enum null { A }"),
+            Err(ParseError::ReservedName {
+                name: "null".into(),
+                location: Location::new(5, 1, 6),
+            })
+        );
+        let mut ast = Ast::new("update { a }").unwrap();
+        ast.parse_start().unwrap();
+        assert_eq!(
+            ast.parse_operation_type(),
+            Err(ParseError::UnsupportedOperation {
+                operation: "update".into(),
+                location: Location::new(0, 1, 1),
+            })
+        );
+    }
+
+    #[test]
+    fn it_reports_tokens_trailing_a_document() {
+        let parse = |input| Ast::new(input).unwrap().parse_definitions();
+        assert_eq!(
+            parse("type A { id: ID } } ) ("),
+            Err(ParseError::TrailingTokens {
+                received: "Token<CloseBrace \"}\">".into(),
+                count: 3,
+                location: Location::new(18, 1, 19),
+            })
+        );
+        assert_eq!(
+            parse("{ a }\n1"),
+            Err(ParseError::TrailingTokens {
+                received: "Token<Int \"1\">".into(),
+                count: 1,
+                location: Location::new(6, 2, 1),
+            })
+        );
+        assert_eq!(
+            parse("type A { id: ID } %%%"),
+            Err(ParseError::LexError(LexError::UnknownCharacter(
+                Location::new(18, 1, 19)
+            )))
+        );
+        assert_eq!(
+            Ast::new("[1] 2").unwrap().parse_standalone_value(),
+            Err(ParseError::TrailingTokens {
+                received: "Token<Int \"2\">".into(),
+                count: 1,
+                location: Location::new(4, 1, 5),
+            })
+        );
+    }
 }
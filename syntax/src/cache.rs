@@ -0,0 +1,248 @@
+//! A cache of parsed documents, so that servers do not parse the same query on every request.
+//!
+//! [`DocumentCache`] keeps the most recently used documents, keyed by a hash of their source. The
+//! source is kept as well and compared on every hit, so two queries whose hashes collide are never
+//! confused. Documents that fail to parse are not cached.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::cache::DocumentCache;
+//!
+//! let cache = DocumentCache::new(100);
+//! let first = cache.get_or_parse("{ hero { name } }").unwrap();
+//! let second = cache.get_or_parse("{ hero { name } }").unwrap();
+//! assert!(std::sync::Arc::ptr_eq(&first, &second));
+//! assert_eq!(cache.stats().hits, 1);
+//! assert_eq!(cache.stats().misses, 1);
+//! ```
+//!
+//! [`DocumentCache`]: struct.DocumentCache.html
+
+use crate::document::Document;
+use crate::error::ParseResult;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Counters describing how well a [`DocumentCache`] is doing.
+///
+/// [`DocumentCache`]: struct.DocumentCache.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups that found a parsed document
+    pub hits: u64,
+    /// Lookups that had to parse the query
+    pub misses: u64,
+    /// Documents dropped to make room for others
+    pub evictions: u64,
+    /// Documents currently cached
+    pub len: usize,
+}
+
+struct Entry {
+    source: Box<str>,
+    document: Arc<Document>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_hash: HashMap<u64, Entry>,
+    /// The hashes of the entries, from the least to the most recently used
+    by_use: BTreeMap<u64, u64>,
+    clock: u64,
+}
+
+impl Entries {
+    fn touch(&mut self, hash: u64) {
+        self.clock += 1;
+        if let Some(entry) = self.by_hash.get_mut(&hash) {
+            self.by_use.remove(&entry.last_used);
+            entry.last_used = self.clock;
+            self.by_use.insert(self.clock, hash);
+        }
+    }
+}
+
+/// A thread-safe, least recently used cache of parsed documents.
+pub struct DocumentCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl DocumentCache {
+    /// Creates a cache holding at most `capacity` documents. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> DocumentCache {
+        DocumentCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the document parsed from `query`, parsing and caching it if it is not cached yet.
+    pub fn get_or_parse(&self, query: &str) -> ParseResult<Arc<Document>> {
+        let hash = hash(query);
+        if let Some(document) = self.get(hash, query) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(document);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        // Parse without holding the lock, so other lookups are not blocked by a slow parse.
+        let document = Arc::new(crate::parse(query)?);
+        self.insert(hash, query, document.clone());
+        Ok(document)
+    }
+
+    /// The counters of the cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+            len: self.len(),
+        }
+    }
+
+    /// The number of documents cached.
+    pub fn len(&self) -> usize {
+        self.lock().by_hash.len()
+    }
+
+    /// Returns true if no document is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops every cached document. The counters are kept.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.by_hash.clear();
+        entries.by_use.clear();
+    }
+
+    fn get(&self, hash: u64, query: &str) -> Option<Arc<Document>> {
+        let mut entries = self.lock();
+        let document = match entries.by_hash.get(&hash) {
+            Some(entry) if &*entry.source == query => entry.document.clone(),
+            _ => return None,
+        };
+        entries.touch(hash);
+        Some(document)
+    }
+
+    fn insert(&self, hash: u64, query: &str, document: Arc<Document>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        if let Some(previous) = entries.by_hash.remove(&hash) {
+            entries.by_use.remove(&previous.last_used);
+        }
+        while entries.by_hash.len() >= self.capacity {
+            let oldest = match entries.by_use.iter().next() {
+                Some((&last_used, &oldest)) => {
+                    entries.by_use.remove(&last_used);
+                    oldest
+                }
+                None => break,
+            };
+            entries.by_hash.remove(&oldest);
+            self.evictions.fetch_add(1, Ordering::Relaxed);
+        }
+        entries.by_hash.insert(
+            hash,
+            Entry {
+                source: query.into(),
+                document,
+                last_used: 0,
+            },
+        );
+        entries.touch(hash);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        // The entries are consistent between statements, so a panic elsewhere does not matter.
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl std::fmt::Debug for DocumentCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DocumentCache")
+            .field("capacity", &self.capacity)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+fn hash(query: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    query.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_evicts_the_least_recently_used_document() {
+        let cache = DocumentCache::new(2);
+        let a = cache.get_or_parse("{ a }").unwrap();
+        cache.get_or_parse("{ b }").unwrap();
+        assert!(Arc::ptr_eq(&a, &cache.get_or_parse("{ a }").unwrap()));
+        cache.get_or_parse("{ c }").unwrap();
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 3,
+                evictions: 1,
+                len: 2
+            }
+        );
+        // `b` was evicted, `a` was used more recently.
+        cache.get_or_parse("{ a }").unwrap();
+        cache.get_or_parse("{ b }").unwrap();
+        assert_eq!(cache.stats().hits, 2);
+        assert_eq!(cache.stats().misses, 4);
+    }
+
+    #[test]
+    fn it_does_not_cache_errors() {
+        let cache = DocumentCache::new(2);
+        assert!(cache.get_or_parse("{ a").is_err());
+        assert!(cache.get_or_parse("{ a").is_err());
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[test]
+    fn it_compares_the_source_on_a_hit() {
+        let cache = DocumentCache::new(2);
+        let a = cache.get_or_parse("{ a }").unwrap();
+        // Pretend `{ b }` collides with `{ a }`.
+        assert!(cache.get(hash("{ a }"), "{ b }").is_none());
+        assert!(Arc::ptr_eq(&a, &cache.get(hash("{ a }"), "{ a }").unwrap()));
+    }
+
+    #[test]
+    fn it_can_be_disabled() {
+        let cache = DocumentCache::new(0);
+        cache.get_or_parse("{ a }").unwrap();
+        cache.get_or_parse("{ a }").unwrap();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stats().hits, 0);
+    }
+}
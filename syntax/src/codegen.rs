@@ -0,0 +1,428 @@
+//! Generates Rust types for the operations of a client.
+//!
+//! [`generate`] takes a [`Schema`] and documents of named operations, and returns Rust source
+//! defining, for every operation `Name`:
+//!
+//! - `NameVariables`, a struct of the variables of the operation, when it has any, deriving
+//!   `serde::Serialize`;
+//! - `NameResponse`, a struct of the `data` of the response, deriving `serde::Deserialize`, with a
+//!   nested struct for every selection set, named after the path to it (`NameResponseMe`).
+//!
+//! The enums and input objects the operations use are generated once for all of them. Field
+//! names are converted to snake_case and renamed back for serde. Nullable types are `Option`s and
+//! lists are `Vec`s. Fields selected through a fragment on a narrower type are `Option`s too,
+//! since they are only present for some types. `Int` is `i32`, `Float` is `f64`, `String` and
+//! `ID` are `String` and `Boolean` is `bool`; custom scalars are `serde_json::Value`, so the crate
+//! using the generated code needs `serde` and `serde_json`.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::codegen::generate;
+//! use syntax::schema::Schema;
+//!
+//! let schema_document = syntax::parse("type Query { me: User } type User { id: ID! }").unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let operations = syntax::parse("query Me { me { id } }").unwrap();
+//! let code = generate(&schema, &[operations]).unwrap();
+//! assert!(code.contains("pub struct MeResponse {\n    pub me: Option<MeResponseMe>,\n}"));
+//! assert!(code.contains("pub struct MeResponseMe {\n    pub id: String,\n}"));
+//! ```
+//!
+//! [`generate`]: fn.generate.html
+//! [`Schema`]: ../schema/struct.Schema.html
+
+use crate::document::Document;
+use crate::error::ValidationError;
+use crate::nodes::*;
+use crate::schema::{named_type, Schema};
+use crate::shape::{response_shape, FieldShape};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+const HEADER: &str = "// Generated from GraphQL operations by syntax::codegen. Do not edit.\n";
+
+const KEYWORDS: [&str; 39] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "type", "where",
+];
+
+/// Generates the Rust types of every operation of the documents.
+///
+/// Operations must be named and valid against the schema.
+pub fn generate(schema: &Schema<'_>, documents: &[Document]) -> Result<String, ValidationError> {
+    let mut generator = Generator {
+        schema,
+        output: String::from(HEADER),
+        enums: BTreeSet::new(),
+        inputs: BTreeSet::new(),
+    };
+    for document in documents {
+        for operation in document.operations() {
+            let name = operation.name.ok_or_else(|| {
+                ValidationError::new("Code can only be generated for named operations")
+            })?;
+            let name = pascal_case(name);
+            let query = operation.operation.definition();
+            if let Some(variables) = query.variables.as_ref().filter(|v| !v.is_empty()) {
+                generator.variables(&format!("{}Variables", name), variables)?;
+            }
+            let shape = response_shape(schema, &operation)?;
+            generator.response(&format!("{}Response", name), shape);
+        }
+    }
+    generator.input_objects()?;
+    generator.enums();
+    Ok(generator.output)
+}
+
+struct Generator<'s, 'a> {
+    schema: &'s Schema<'a>,
+    output: String,
+    enums: BTreeSet<&'a str>,
+    inputs: BTreeSet<&'a str>,
+}
+
+/// A field of a response struct: the fields selected under the same response key, merged.
+struct Field<'a> {
+    shape: FieldShape<'a>,
+    /// The field is only present for some of the possible types.
+    conditional: bool,
+}
+
+impl<'s, 'a> Generator<'s, 'a> {
+    fn variables(
+        &mut self,
+        name: &str,
+        variables: &[VariableDefinitionNode],
+    ) -> Result<(), ValidationError> {
+        let mut fields = Vec::new();
+        for variable in variables {
+            let leaf = self.input_leaf(named_type(&variable.variable_type))?;
+            fields.push((
+                variable.variable.name.value.as_str(),
+                rust_type(&variable.variable_type, &leaf),
+            ));
+        }
+        self.input_struct(name, &fields);
+        Ok(())
+    }
+
+    fn input_objects(&mut self) -> Result<(), ValidationError> {
+        let mut generated = BTreeSet::new();
+        // Input objects may refer to more input objects, which are added while generating.
+        while let Some(name) = self.inputs.difference(&generated).next().copied() {
+            generated.insert(name);
+            let input = match self.schema.get_type(name) {
+                Some(TypeDefinitionNode::Input(input)) => input,
+                _ => unreachable!("Only input objects are collected"),
+            };
+            let mut fields = Vec::new();
+            for field in &input.fields {
+                let leaf = self.input_leaf(named_type(&field.input_type))?;
+                fields.push((
+                    field.name.value.as_str(),
+                    rust_type(&field.input_type, &leaf),
+                ));
+            }
+            self.input_struct(name, &fields);
+        }
+        Ok(())
+    }
+
+    fn input_struct(&mut self, name: &str, fields: &[(&str, String)]) {
+        self.output
+            .push_str("\n#[derive(Debug, Clone, PartialEq, serde::Serialize)]\n");
+        let _ = writeln!(self.output, "pub struct {} {{", name);
+        for (field_name, field_type) in fields {
+            if field_type.starts_with("Option<") {
+                self.output
+                    .push_str("    #[serde(skip_serializing_if = \"Option::is_none\")]\n");
+            }
+            self.field(field_name, field_type);
+        }
+        self.output.push_str("}\n");
+    }
+
+    /// The Rust type of a named input type, collecting the enums and input objects.
+    fn input_leaf(&mut self, name: &str) -> Result<String, ValidationError> {
+        match self.schema.get_type(name) {
+            Some(definition @ TypeDefinitionNode::Enum(_)) => {
+                self.enums.insert(definition.name());
+                Ok(pascal_case(name))
+            }
+            Some(definition @ TypeDefinitionNode::Input(_)) => {
+                self.inputs.insert(definition.name());
+                Ok(pascal_case(name))
+            }
+            Some(TypeDefinitionNode::Scalar(_)) => Ok(scalar(name)),
+            None if is_scalar(name) => Ok(scalar(name)),
+            Some(_) => Err(ValidationError::new(&format!(
+                "{} is not an input type",
+                name
+            ))),
+            None => Err(ValidationError::new(&format!("Unknown type {}", name))),
+        }
+    }
+
+    fn response(&mut self, name: &str, shape: Vec<FieldShape<'a>>) {
+        let fields = merge(shape);
+        let mut nested = Vec::new();
+        self.output
+            .push_str("\n#[derive(Debug, Clone, PartialEq, serde::Deserialize)]\n");
+        let _ = writeln!(self.output, "pub struct {} {{", name);
+        for Field { shape, conditional } in fields {
+            let type_name = shape.type_name();
+            let leaf = match self.schema.get_type(type_name) {
+                Some(TypeDefinitionNode::Enum(_)) => {
+                    self.enums.insert(type_name);
+                    pascal_case(type_name)
+                }
+                Some(TypeDefinitionNode::Object(_))
+                | Some(TypeDefinitionNode::Interface(_))
+                | Some(TypeDefinitionNode::Union(_)) => {
+                    format!("{}{}", name, pascal_case(shape.response_key))
+                }
+                _ => scalar(type_name),
+            };
+            let mut field_type = rust_type(shape.field_type, &leaf);
+            if conditional && !field_type.starts_with("Option<") {
+                field_type = format!("Option<{}>", field_type);
+            }
+            if conditional {
+                self.output.push_str("    #[serde(default)]\n");
+            }
+            self.field(shape.response_key, &field_type);
+            if !shape.children.is_empty() {
+                nested.push((leaf, shape.children));
+            }
+        }
+        self.output.push_str("}\n");
+        for (name, children) in nested {
+            self.response(&name, children);
+        }
+    }
+
+    fn enums(&mut self) {
+        for name in std::mem::take(&mut self.enums) {
+            let values = match self.schema.get_type(name) {
+                Some(TypeDefinitionNode::Enum(definition)) => &definition.values,
+                _ => unreachable!("Only enums are collected"),
+            };
+            self.output.push_str(
+                "\n#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]\n",
+            );
+            let _ = writeln!(self.output, "pub enum {} {{", pascal_case(name));
+            for value in values {
+                let value = value.name.value.as_str();
+                let _ = writeln!(self.output, "    #[serde(rename = \"{}\")]", value);
+                let _ = writeln!(self.output, "    {},", pascal_case(value));
+            }
+            self.output.push_str("}\n");
+        }
+    }
+
+    fn field(&mut self, name: &str, field_type: &str) {
+        let rust_name = snake_case(name);
+        if rust_name.trim_start_matches("r#") != name {
+            let _ = writeln!(self.output, "    #[serde(rename = \"{}\")]", name);
+        }
+        let _ = writeln!(self.output, "    pub {}: {},", rust_name, field_type);
+    }
+}
+
+/// Merges the fields selected under the same response key, which differ by type condition.
+fn merge(shape: Vec<FieldShape<'_>>) -> Vec<Field<'_>> {
+    let mut fields: Vec<Field<'_>> = Vec::new();
+    for field in shape {
+        match fields
+            .iter_mut()
+            .find(|existing| existing.shape.response_key == field.response_key)
+        {
+            Some(existing) => {
+                existing.conditional &= field.type_condition.is_some();
+                existing.shape.children.extend(field.children);
+            }
+            None => fields.push(Field {
+                conditional: field.type_condition.is_some(),
+                shape: field,
+            }),
+        }
+    }
+    fields
+}
+
+fn rust_type(type_node: &TypeNode, leaf: &str) -> String {
+    match type_node {
+        TypeNode::NonNull(inner) => required_type(inner, leaf),
+        _ => format!("Option<{}>", required_type(type_node, leaf)),
+    }
+}
+
+fn required_type(type_node: &TypeNode, leaf: &str) -> String {
+    match type_node {
+        TypeNode::NonNull(inner) => required_type(inner, leaf),
+        TypeNode::List(list) => format!("Vec<{}>", rust_type(&list.list_type, leaf)),
+        TypeNode::Named(_) => leaf.to_owned(),
+    }
+}
+
+fn is_scalar(name: &str) -> bool {
+    matches!(name, "Int" | "Float" | "String" | "Boolean" | "ID")
+}
+
+fn scalar(name: &str) -> String {
+    match name {
+        "Int" => "i32",
+        "Float" => "f64",
+        "String" | "ID" => "String",
+        "Boolean" => "bool",
+        _ => "serde_json::Value",
+    }
+    .to_owned()
+}
+
+/// `appearsIn` and `APPEARS_IN` to `appears_in`, escaping keywords.
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut previous: Option<char> = None;
+    for c in name.chars() {
+        if c.is_uppercase() && previous.is_some_and(|p| p.is_lowercase() || p.is_ascii_digit()) {
+            snake.push('_');
+        }
+        snake.extend(c.to_lowercase());
+        previous = Some(c);
+    }
+    match snake.as_str() {
+        "self" | "super" | "crate" => snake + "_",
+        _ if KEYWORDS.contains(&snake.as_str()) => format!("r#{}", snake),
+        _ => snake,
+    }
+}
+
+/// `appearsIn` and `APPEARS_IN` to `AppearsIn`.
+fn pascal_case(name: &str) -> String {
+    let all_caps = !name.chars().any(char::is_lowercase);
+    let mut pascal = String::with_capacity(name.len());
+    let mut upper = true;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            pascal.extend(c.to_uppercase());
+            upper = false;
+        } else if all_caps {
+            pascal.extend(c.to_lowercase());
+        } else {
+            pascal.push(c);
+        }
+    }
+    pascal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SCHEMA: &str = r#"
+type Query { hero(episode: Episode): Character, search(filter: Filter!): [Character!]! }
+interface Character { id: ID!, name: String!, appearsIn: [Episode]! }
+type Human implements Character { id: ID!, name: String!, appearsIn: [Episode]!, height: Float }
+type Droid implements Character { id: ID!, name: String!, appearsIn: [Episode]!, primaryFunction: String }
+enum Episode { NEW_HOPE, EMPIRE, JEDI }
+input Filter { text: String, type: Kind! }
+input Kind { episodes: [Episode!] }
+"#;
+
+    #[test]
+    fn it_generates_operation_types() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let operations = parse(
+            r#"
+query Hero($episode: Episode) {
+  hero(episode: $episode) {
+    name
+    appearsIn
+    ... on Human { height }
+  }
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            generate(&schema, &[operations]).unwrap(),
+            r#"// Generated from GraphQL operations by syntax::codegen. Do not edit.
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct HeroVariables {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub episode: Option<Episode>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct HeroResponse {
+    pub hero: Option<HeroResponseHero>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Deserialize)]
+pub struct HeroResponseHero {
+    pub name: String,
+    #[serde(rename = "appearsIn")]
+    pub appears_in: Vec<Option<Episode>>,
+    #[serde(default)]
+    pub height: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum Episode {
+    #[serde(rename = "NEW_HOPE")]
+    NewHope,
+    #[serde(rename = "EMPIRE")]
+    Empire,
+    #[serde(rename = "JEDI")]
+    Jedi,
+}
+"#
+        );
+    }
+
+    #[test]
+    fn it_generates_input_objects() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let operations =
+            parse("query Search($filter: Filter!) { search(filter: $filter) { id } }").unwrap();
+        let code = generate(&schema, &[operations]).unwrap();
+        assert!(code.contains("pub struct SearchVariables {\n    pub filter: Filter,\n}"));
+        assert!(code.contains("    pub text: Option<String>,\n    pub r#type: Kind,\n"));
+        assert!(code.contains("pub struct Kind {\n"));
+        assert!(code.contains("pub enum Episode {\n"));
+        assert!(code.contains("pub search: Vec<SearchResponseSearch>,"));
+    }
+
+    #[test]
+    fn it_requires_named_valid_operations() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        assert!(generate(&schema, &[parse("{ hero { name } }").unwrap()]).is_err());
+        assert!(generate(&schema, &[parse("query A { hero { mass } }").unwrap()]).is_err());
+        assert!(generate(
+            &schema,
+            &[parse("query A($c: Character) { hero { id } }").unwrap()]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_converts_names() {
+        assert_eq!(snake_case("appearsIn"), "appears_in");
+        assert_eq!(snake_case("type"), "r#type");
+        assert_eq!(snake_case("self"), "self_");
+        assert_eq!(pascal_case("NEW_HOPE"), "NewHope");
+        assert_eq!(pascal_case("heroName"), "HeroName");
+    }
+}
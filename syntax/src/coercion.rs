@@ -0,0 +1,410 @@
+//! Coerces the values sent for the variables of an operation to their types, following the
+//! `CoerceVariableValues` algorithm of the GraphQL Spec.
+//!
+//! Validating an operation only checks how its variables are used; the JSON values sent for them
+//! along with it are checked by [`coerce_variable_values`]. Variables that were not sent take
+//! their default values, required variables must be sent and not be null, and every value must
+//! fit its type: `Int` values are 32-bit integers, enum values are values of their enum, input
+//! objects only have the fields of their type, with all their required fields, and so on. The
+//! coerced values are what an executor reads the variables from.
+//!
+//! # Example
+//!
+//! ```
+//! use serde_json::{json, Map};
+//! use syntax::coercion::coerce_variable_values;
+//! use syntax::schema::Schema;
+//!
+//! let schema_document = syntax::parse("type Query { users(first: Int, ids: [ID!]): [ID] }").unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let document =
+//!     syntax::parse("query Users($first: Int = 10, $ids: [ID!]!) { users(first: $first, ids: $ids) }")
+//!         .unwrap();
+//! let operation = document.operations()[0].operation;
+//!
+//! let mut variables = Map::new();
+//! variables.insert("ids".into(), json!(1));
+//! let coerced = coerce_variable_values(&schema, operation, &variables).unwrap();
+//! assert_eq!(coerced["first"], json!(10));
+//! assert_eq!(coerced["ids"], json!(["1"]));
+//!
+//! let errors = coerce_variable_values(&schema, operation, &Map::new()).unwrap_err();
+//! assert_eq!(
+//!     errors[0].message,
+//!     r#"Variable "$ids" of required type "[ID!]!" was not provided."#
+//! );
+//! ```
+//!
+//! [`coerce_variable_values`]: fn.coerce_variable_values.html
+
+use crate::error::CoercionError;
+use crate::nodes::*;
+use crate::schema::Schema;
+use serde_json::{json, Map, Value};
+
+/// Coerces the values sent for the variables of an operation, giving the value of each variable
+/// that has one, or the issues with the values sent.
+///
+/// Variables neither sent nor with a default value are left out, and the value of a custom
+/// scalar is kept as it was sent. All the issues are reported, at most one per variable.
+pub fn coerce_variable_values(
+    schema: &Schema<'_>,
+    operation: &OperationTypeNode,
+    variables: &Map<String, Value>,
+) -> Result<Map<String, Value>, Vec<CoercionError>> {
+    let mut coerced = Map::new();
+    let mut errors = Vec::new();
+    for definition in operation.definition().variables.iter().flatten() {
+        let name = definition.variable.name.value.as_str();
+        let variable_type = &definition.variable_type;
+        if !is_input_type(schema, variable_type) {
+            errors.push(CoercionError::new(&format!(
+                "Variable \"${}\" expected value of type \"{}\" which cannot be used as an input type.",
+                name, variable_type
+            )));
+            continue;
+        }
+        let value = match (variables.get(name), &definition.default_value) {
+            (Some(value), _) => value.clone(),
+            (None, Some(default)) => default.to_json(&Map::new()),
+            (None, None) => {
+                if let TypeNode::NonNull(_) = variable_type {
+                    errors.push(CoercionError::new(&format!(
+                        "Variable \"${}\" of required type \"{}\" was not provided.",
+                        name, variable_type
+                    )));
+                }
+                continue;
+            }
+        };
+        if let (TypeNode::NonNull(_), Value::Null) = (variable_type, &value) {
+            errors.push(CoercionError::new(&format!(
+                "Variable \"${}\" of non-null type \"{}\" must not be null.",
+                name, variable_type
+            )));
+            continue;
+        }
+        let mut issues = Vec::new();
+        let value = coerce(schema, &value, variable_type, name, &mut issues);
+        match issues.into_iter().next() {
+            Some((path, reason)) => {
+                let at = if path == name {
+                    String::new()
+                } else {
+                    format!(" at \"{}\"", path)
+                };
+                errors.push(CoercionError::new(&format!(
+                    "Variable \"${}\" got invalid value {}{}; {}",
+                    name,
+                    variables.get(name).unwrap_or(&value),
+                    at,
+                    reason
+                )));
+            }
+            None => {
+                coerced.insert(name.to_string(), value);
+            }
+        }
+    }
+    if errors.is_empty() {
+        Ok(coerced)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Whether a type is a scalar, an enum or an input object, or a list of one of them.
+fn is_input_type(schema: &Schema<'_>, input_type: &TypeNode) -> bool {
+    match input_type {
+        TypeNode::NonNull(inner) => is_input_type(schema, inner),
+        TypeNode::List(list) => is_input_type(schema, &list.list_type),
+        TypeNode::Named(named) => {
+            is_builtin_scalar(&named.name.value)
+                || matches!(
+                    schema.get_type(&named.name.value),
+                    Some(TypeDefinitionNode::Scalar(_))
+                        | Some(TypeDefinitionNode::Enum(_))
+                        | Some(TypeDefinitionNode::Input(_))
+                )
+        }
+    }
+}
+
+fn is_builtin_scalar(name: &str) -> bool {
+    matches!(name, "Int" | "Float" | "String" | "Boolean" | "ID")
+}
+
+/// Coerces a value to an input type, adding the path to and the reason of each issue with it.
+fn coerce(
+    schema: &Schema<'_>,
+    value: &Value,
+    input_type: &TypeNode,
+    path: &str,
+    issues: &mut Vec<(String, String)>,
+) -> Value {
+    match (input_type, value) {
+        (TypeNode::NonNull(_), Value::Null) => {
+            issues.push((
+                path.to_string(),
+                format!(
+                    "Expected non-nullable type \"{}\" not to be null.",
+                    input_type
+                ),
+            ));
+            Value::Null
+        }
+        (TypeNode::NonNull(inner), value) => coerce(schema, value, inner, path, issues),
+        (_, Value::Null) => Value::Null,
+        (TypeNode::List(list), Value::Array(items)) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, item)| {
+                    let path = format!("{}[{}]", path, index);
+                    coerce(schema, item, &list.list_type, &path, issues)
+                })
+                .collect(),
+        ),
+        // A single value stands for a list of one
+        (TypeNode::List(list), value) => {
+            Value::Array(vec![coerce(schema, value, &list.list_type, path, issues)])
+        }
+        (TypeNode::Named(named), value) => {
+            match named_value(schema, value, &named.name.value, path, issues) {
+                Ok(value) => value,
+                Err(reason) => {
+                    issues.push((path.to_string(), reason));
+                    Value::Null
+                }
+            }
+        }
+    }
+}
+
+/// Coerces a value that is not null to a named input type.
+fn named_value(
+    schema: &Schema<'_>,
+    value: &Value,
+    type_name: &str,
+    path: &str,
+    issues: &mut Vec<(String, String)>,
+) -> Result<Value, String> {
+    match (type_name, value) {
+        ("Int", Value::Number(number)) => match number.as_f64() {
+            Some(int) if int.fract() != 0.0 => {
+                Err(format!("Int cannot represent non-integer value: {}", value))
+            }
+            Some(int) if int < f64::from(i32::MIN) || int > f64::from(i32::MAX) => Err(format!(
+                "Int cannot represent non 32-bit signed integer value: {}",
+                value
+            )),
+            Some(int) => Ok(json!(int as i32)),
+            None => Err(format!("Int cannot represent non-integer value: {}", value)),
+        },
+        ("Int", _) => Err(format!("Int cannot represent non-integer value: {}", value)),
+        ("Float", Value::Number(number)) => match number.as_f64() {
+            Some(float) => Ok(json!(float)),
+            None => Err(format!(
+                "Float cannot represent non numeric value: {}",
+                value
+            )),
+        },
+        ("Float", _) => Err(format!(
+            "Float cannot represent non numeric value: {}",
+            value
+        )),
+        ("String", Value::String(_)) => Ok(value.clone()),
+        ("String", _) => Err(format!(
+            "String cannot represent a non string value: {}",
+            value
+        )),
+        ("Boolean", Value::Bool(_)) => Ok(value.clone()),
+        ("Boolean", _) => Err(format!(
+            "Boolean cannot represent a non boolean value: {}",
+            value
+        )),
+        ("ID", Value::String(_)) => Ok(value.clone()),
+        ("ID", Value::Number(number)) if number.is_i64() || number.is_u64() => {
+            Ok(Value::String(number.to_string()))
+        }
+        ("ID", _) => Err(format!("ID cannot represent value: {}", value)),
+        _ => match schema.get_type(type_name) {
+            Some(TypeDefinitionNode::Enum(enum_type)) => match value {
+                Value::String(name)
+                    if enum_type
+                        .values
+                        .iter()
+                        .any(|enum_value| enum_value.name.value == name.as_str()) =>
+                {
+                    Ok(value.clone())
+                }
+                Value::String(name) => Err(format!(
+                    "Value \"{}\" does not exist in \"{}\" enum.",
+                    name, type_name
+                )),
+                _ => Err(format!(
+                    "Enum \"{}\" cannot represent non-string value: {}.",
+                    type_name, value
+                )),
+            },
+            Some(TypeDefinitionNode::Input(input)) => match value {
+                Value::Object(fields) => Ok(Value::Object(input_object(
+                    schema, input, fields, path, issues,
+                ))),
+                _ => Err(format!("Expected type \"{}\" to be an object.", type_name)),
+            },
+            _ => Ok(value.clone()),
+        },
+    }
+}
+
+/// Coerces the fields of an input object, applying their defaults.
+fn input_object(
+    schema: &Schema<'_>,
+    input: &InputTypeDefinitionNode,
+    fields: &Map<String, Value>,
+    path: &str,
+    issues: &mut Vec<(String, String)>,
+) -> Map<String, Value> {
+    let type_name = input.name.value.as_str();
+    let mut coerced = Map::new();
+    for name in fields.keys() {
+        if !input
+            .fields
+            .iter()
+            .any(|field| field.name.value == name.as_str())
+        {
+            issues.push((
+                path.to_string(),
+                format!(
+                    "Field \"{}\" is not defined by type \"{}\".",
+                    name, type_name
+                ),
+            ));
+        }
+    }
+    for field in &input.fields {
+        let name = field.name.value.as_str();
+        let value = match (fields.get(name), &field.default_value) {
+            (Some(value), _) => value.clone(),
+            (None, Some(default)) => default.to_json(&Map::new()),
+            (None, None) => {
+                if let TypeNode::NonNull(_) = field.input_type {
+                    issues.push((
+                        path.to_string(),
+                        format!(
+                            "Field \"{}\" of required type \"{}\" was not provided.",
+                            name, field.input_type
+                        ),
+                    ));
+                }
+                continue;
+            }
+        };
+        let path = format!("{}.{}", path, name);
+        coerced.insert(
+            name.to_string(),
+            coerce(schema, &value, &field.input_type, &path, issues),
+        );
+    }
+    if schema.is_one_of(type_name) && (fields.len() != 1 || coerced.values().any(Value::is_null)) {
+        issues.push((
+            path.to_string(),
+            format!(
+                "Exactly one key must be specified for OneOf type \"{}\".",
+                type_name
+            ),
+        ));
+    }
+    coerced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn coerce_json(schema: &str, query: &str, variables: Value) -> Result<Value, Vec<String>> {
+        let schema_document = parse(schema).unwrap();
+        let schema = Schema::new(&schema_document);
+        let document = parse(query).unwrap();
+        let variables = match variables {
+            Value::Object(variables) => variables,
+            _ => panic!("Expected an object of variables"),
+        };
+        coerce_variable_values(&schema, document.operations()[0].operation, &variables)
+            .map(Value::Object)
+            .map_err(|errors| errors.into_iter().map(|error| error.message).collect())
+    }
+
+    const SCHEMA: &str = "type Query { users(filter: Filter): [User] }
+        type User { id: ID! }
+        enum Role { ADMIN USER }
+        input Filter { role: Role! = USER tags: [String!] first: Int = 10 after: ID }
+        input Key @oneOf { id: ID name: String }";
+
+    #[test]
+    fn it_coerces_variables_and_applies_defaults() {
+        assert_eq!(
+            coerce_json(
+                SCHEMA,
+                "query Users($filter: Filter!, $role: Role = ADMIN, $ids: [ID], $ratio: Float,
+                    $unset: Int, $default: Filter = {tags: [\"a\"]}, $key: Key) { users }",
+                json!({
+                    "filter": {"tags": "one", "after": 5},
+                    "ids": [1, "2", null],
+                    "ratio": 1,
+                    "key": {"name": "Ann"},
+                }),
+            ),
+            Ok(json!({
+                "filter": {"role": "USER", "tags": ["one"], "first": 10, "after": "5"},
+                "role": "ADMIN",
+                "ids": ["1", "2", null],
+                "ratio": 1.0,
+                "default": {"role": "USER", "tags": ["a"], "first": 10},
+                "key": {"name": "Ann"},
+            }))
+        );
+    }
+
+    #[test]
+    fn it_reports_the_invalid_values_of_variables() {
+        assert_eq!(
+            coerce_json(
+                SCHEMA,
+                "query Users($filter: Filter, $first: Int, $name: String!, $required: ID!,
+                    $user: User, $roles: [Role!], $key: Key, $big: Int) { users }",
+                json!({
+                    "filter": {"role": "OWNER", "tags": ["a", 1], "other": true},
+                    "first": 1.5,
+                    "name": null,
+                    "user": {},
+                    "roles": ["ADMIN", null],
+                    "key": {"id": 1, "name": "Ann"},
+                    "big": 3_000_000_000u64,
+                }),
+            ),
+            Err(vec![
+                r#"Variable "$filter" got invalid value {"other":true,"role":"OWNER","tags":["a",1]}; Field "other" is not defined by type "Filter"."#.to_string(),
+                r#"Variable "$first" got invalid value 1.5; Int cannot represent non-integer value: 1.5"#.to_string(),
+                r#"Variable "$name" of non-null type "String!" must not be null."#.to_string(),
+                r#"Variable "$required" of required type "ID!" was not provided."#.to_string(),
+                r#"Variable "$user" expected value of type "User" which cannot be used as an input type."#.to_string(),
+                r#"Variable "$roles" got invalid value ["ADMIN",null] at "roles[1]"; Expected non-nullable type "Role!" not to be null."#.to_string(),
+                r#"Variable "$key" got invalid value {"id":1,"name":"Ann"}; Exactly one key must be specified for OneOf type "Key"."#.to_string(),
+                r#"Variable "$big" got invalid value 3000000000; Int cannot represent non 32-bit signed integer value: 3000000000"#.to_string(),
+            ])
+        );
+        assert_eq!(
+            coerce_json(
+                SCHEMA,
+                "query Users($filter: Filter) { users }",
+                json!({"filter": {"role": "OWNER"}})
+            ),
+            Err(vec![
+                r#"Variable "$filter" got invalid value {"role":"OWNER"} at "filter.role"; Value "OWNER" does not exist in "Role" enum."#.to_string()
+            ])
+        );
+    }
+}
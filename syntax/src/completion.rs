@@ -0,0 +1,177 @@
+//! What can be selected on a type, to drive completion in editors.
+//!
+//! [`possible_selections`] answers the question an editor asks inside a selection set: which
+//! fields the type has, which type conditions a fragment on it may use, and which values
+//! `__typename` can take. [`PossibleSelections::fragments`] then lists the fragments of a
+//! document that may be spread there.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::completion::possible_selections;
+//! use syntax::schema::Schema;
+//!
+//! let schema_document = syntax::parse(
+//!     "type Query { node: Node } interface Node { id: ID! } type User implements Node { id: ID!, name: String }",
+//! )
+//! .unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let selections = possible_selections(&schema, "Node").unwrap();
+//! assert_eq!(selections.field_names(), vec!["id", "__typename"]);
+//! assert_eq!(selections.type_conditions, vec!["Node", "User"]);
+//! assert_eq!(selections.typenames, vec!["User"]);
+//!
+//! let document = syntax::parse("fragment UserName on User { name } fragment Root on Query { node { id } }").unwrap();
+//! let fragments: Vec<&str> = selections
+//!     .fragments(&document)
+//!     .iter()
+//!     .map(|fragment| fragment.name.value.as_str())
+//!     .collect();
+//! assert_eq!(fragments, vec!["UserName"]);
+//! ```
+//!
+//! [`possible_selections`]: fn.possible_selections.html
+//! [`PossibleSelections::fragments`]: struct.PossibleSelections.html#method.fragments
+
+use crate::document::Document;
+use crate::nodes::*;
+use crate::schema::Schema;
+
+/// The selections possible on a composite type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PossibleSelections<'a> {
+    /// The name of the type
+    pub type_name: &'a str,
+    /// The fields of the type, including those added by extensions. Unions have none.
+    pub fields: Vec<&'a FieldDefinitionNode>,
+    /// The types a fragment selected on this type may have as type condition: the types that
+    /// share at least one possible object type with it, in schema order
+    pub type_conditions: Vec<&'a str>,
+    /// The values `__typename` can take: the object types the type can be, in schema order
+    pub typenames: Vec<&'a str>,
+}
+
+impl<'a> PossibleSelections<'a> {
+    /// The names of the fields, followed by `__typename`.
+    pub fn field_names(&self) -> Vec<&'a str> {
+        self.fields
+            .iter()
+            .map(|field| field.name.value.as_str())
+            .chain(std::iter::once("__typename"))
+            .collect()
+    }
+
+    /// The fragments of the document whose type condition applies to the type, in document
+    /// order.
+    pub fn fragments<'d>(&self, document: &'d Document) -> Vec<&'d FragmentDefinitionNode> {
+        document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    Some(fragment)
+                }
+                _ => None,
+            })
+            .filter(|fragment| {
+                self.type_conditions
+                    .contains(&fragment.node_type.name.value.as_str())
+            })
+            .collect()
+    }
+}
+
+/// The selections possible on the type named `type_name`, or `None` if it is not an object,
+/// interface or union type of the schema.
+pub fn possible_selections<'a>(
+    schema: &Schema<'a>,
+    type_name: &str,
+) -> Option<PossibleSelections<'a>> {
+    let definition = schema.get_type(type_name)?;
+    let type_name = definition.name();
+    let fields = match definition {
+        TypeDefinitionNode::Object(_) | TypeDefinitionNode::Interface(_) => {
+            schema.fields(type_name)
+        }
+        TypeDefinitionNode::Union(_) => Vec::new(),
+        _ => return None,
+    };
+    let typenames = object_types(schema, type_name);
+    let type_conditions = schema
+        .types()
+        .map(TypeDefinitionNode::name)
+        .filter(|candidate| {
+            object_types(schema, candidate)
+                .iter()
+                .any(|object| typenames.contains(object))
+        })
+        .collect();
+    Some(PossibleSelections {
+        type_name,
+        fields,
+        type_conditions,
+        typenames,
+    })
+}
+
+/// The object types a composite type can be, in schema order.
+fn object_types<'a>(schema: &Schema<'a>, type_name: &'a str) -> Vec<&'a str> {
+    match schema.get_type(type_name) {
+        Some(TypeDefinitionNode::Object(_)) => vec![type_name],
+        Some(TypeDefinitionNode::Interface(_)) | Some(TypeDefinitionNode::Union(_)) => {
+            let possible = schema.possible_types(type_name);
+            schema
+                .types()
+                .map(TypeDefinitionNode::name)
+                .filter(|name| possible.contains(name))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SCHEMA: &str = r#"
+type Query { search: [Result] }
+interface Node { id: ID! }
+type User implements Node { id: ID!, name: String }
+type Post implements Node { id: ID!, title: String }
+type Tag { label: String }
+union Result = User | Tag
+extend type User { email: String }
+"#;
+
+    #[test]
+    fn it_lists_the_selections_of_an_object() {
+        let document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&document);
+        let selections = possible_selections(&schema, "User").unwrap();
+        assert_eq!(
+            selections.field_names(),
+            vec!["id", "name", "email", "__typename"]
+        );
+        assert_eq!(selections.type_conditions, vec!["Node", "User", "Result"]);
+        assert_eq!(selections.typenames, vec!["User"]);
+    }
+
+    #[test]
+    fn it_lists_the_selections_of_abstract_types() {
+        let document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&document);
+        let result = possible_selections(&schema, "Result").unwrap();
+        assert_eq!(result.field_names(), vec!["__typename"]);
+        assert_eq!(result.typenames, vec!["User", "Tag"]);
+        assert_eq!(
+            result.type_conditions,
+            vec!["Node", "User", "Tag", "Result"]
+        );
+        let node = possible_selections(&schema, "Node").unwrap();
+        assert_eq!(node.type_conditions, vec!["Node", "User", "Post", "Result"]);
+        assert!(possible_selections(&schema, "String").is_none());
+        assert!(possible_selections(&schema, "Missing").is_none());
+    }
+}
@@ -0,0 +1,324 @@
+//! Finds and reads GraphQL config files, the way the rest of the GraphQL ecosystem does.
+//!
+//! A config file is one of [`FILE_NAMES`], written in YAML or JSON. It tells tools where the
+//! schema and the operations of a project are:
+//!
+//! ```yaml
+//! schema: schema.graphql
+//! documents: src/**/*.graphql
+//! ```
+//!
+//! `schema` and `documents` are a path or glob pattern, or a list of them, relative to the
+//! directory of the config file. A config file may also describe several named projects under
+//! `projects`, each with its own `schema` and `documents`.
+//!
+//! [`GraphQLConfig::discover`] looks for a config file in a directory and then in its ancestors.
+//!
+//! # Example
+//!
+//! ```
+//! use std::path::Path;
+//! use syntax::config::GraphQLConfig;
+//!
+//! let source = "schema: schema.graphql\ndocuments:\n  - queries/*.graphql\n";
+//! let config = GraphQLConfig::parse(source, Path::new("/app")).unwrap();
+//! let project = config.default_project().unwrap();
+//! assert_eq!(project.schema, vec!["schema.graphql"]);
+//! assert_eq!(project.documents, vec!["queries/*.graphql"]);
+//! assert_eq!(project.root, Path::new("/app"));
+//! ```
+//!
+//! [`FILE_NAMES`]: constant.FILE_NAMES.html
+//! [`GraphQLConfig::discover`]: struct.GraphQLConfig.html#method.discover
+
+use crate::document::Document;
+use crate::error::ConfigError;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use yaml_rust::{Yaml, YamlLoader};
+
+/// The names of the config files, in the order they are looked for in a directory.
+pub const FILE_NAMES: [&str; 7] = [
+    ".graphqlrc",
+    ".graphqlrc.yml",
+    ".graphqlrc.yaml",
+    ".graphqlrc.json",
+    "graphql.config.yml",
+    "graphql.config.yaml",
+    "graphql.config.json",
+];
+
+/// The contents of a GraphQL config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLConfig {
+    /// The file the config was read from, if any.
+    pub path: Option<PathBuf>,
+    /// The project described at the top level of the file.
+    pub project: ProjectConfig,
+    /// The projects described under `projects`, by name.
+    pub projects: BTreeMap<String, ProjectConfig>,
+}
+
+/// Where the schema and the operations of a project are.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ProjectConfig {
+    /// The directory the patterns are relative to, i.e. the directory of the config file.
+    pub root: PathBuf,
+    /// Paths or glob patterns of the files defining the schema. Entries that are URLs are kept,
+    /// but are not read by this module.
+    pub schema: Vec<String>,
+    /// Paths or glob patterns of the files containing operations and fragments.
+    pub documents: Vec<String>,
+}
+
+impl GraphQLConfig {
+    /// Looks for a config file in `directory`, then in its ancestors, and reads the first one
+    /// found.
+    pub fn discover(directory: &Path) -> Result<Option<GraphQLConfig>, ConfigError> {
+        for directory in directory.ancestors() {
+            for name in FILE_NAMES.iter() {
+                let path = directory.join(name);
+                if path.is_file() {
+                    return GraphQLConfig::from_file(&path).map(Some);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Reads a config file.
+    pub fn from_file(path: &Path) -> Result<GraphQLConfig, ConfigError> {
+        let source = fs::read_to_string(path).map_err(|error| {
+            ConfigError::new(&format!("Unable to read {}: {}", path.display(), error))
+        })?;
+        let root = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut config = GraphQLConfig::parse(&source, root)
+            .map_err(|error| ConfigError::new(&format!("{}: {}", path.display(), error.message)))?;
+        config.path = Some(path.to_owned());
+        Ok(config)
+    }
+
+    /// Reads the YAML or JSON source of a config file, whose patterns are relative to `root`.
+    pub fn parse(source: &str, root: &Path) -> Result<GraphQLConfig, ConfigError> {
+        let documents = YamlLoader::load_from_str(source)
+            .map_err(|error| ConfigError::new(&error.to_string()))?;
+        let yaml = match documents.into_iter().next() {
+            Some(yaml @ Yaml::Hash(_)) => yaml,
+            Some(Yaml::Null) | None => Yaml::Hash(Default::default()),
+            Some(_) => return Err(ConfigError::new("The config must be a map")),
+        };
+        let project = ProjectConfig::from_yaml(&yaml, root)?;
+        let mut projects = BTreeMap::new();
+        match &yaml["projects"] {
+            Yaml::Hash(entries) => {
+                for (name, project) in entries {
+                    let name = name.as_str().ok_or_else(|| {
+                        ConfigError::new("The names of the projects must be strings")
+                    })?;
+                    projects.insert(name.to_owned(), ProjectConfig::from_yaml(project, root)?);
+                }
+            }
+            Yaml::BadValue | Yaml::Null => {}
+            _ => return Err(ConfigError::new("projects must be a map")),
+        }
+        Ok(GraphQLConfig {
+            path: None,
+            project,
+            projects,
+        })
+    }
+
+    /// The project described at the top level, or else the project named `default`, like
+    /// graphql-config does.
+    pub fn default_project(&self) -> Option<&ProjectConfig> {
+        if self.project.is_empty() {
+            self.projects.get("default")
+        } else {
+            Some(&self.project)
+        }
+    }
+
+    /// The project named `name`, or the default project.
+    pub fn project(&self, name: Option<&str>) -> Option<&ProjectConfig> {
+        match name {
+            Some(name) => self.projects.get(name),
+            None => self.default_project(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    fn from_yaml(yaml: &Yaml, root: &Path) -> Result<ProjectConfig, ConfigError> {
+        Ok(ProjectConfig {
+            root: root.to_owned(),
+            schema: patterns(&yaml["schema"], "schema")?,
+            documents: patterns(&yaml["documents"], "documents")?,
+        })
+    }
+
+    /// Returns true if the project has neither a schema nor documents.
+    pub fn is_empty(&self) -> bool {
+        self.schema.is_empty() && self.documents.is_empty()
+    }
+
+    /// The files defining the schema, sorted. A pattern that matches no file is an error.
+    pub fn schema_files(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut files = Vec::new();
+        for pattern in self.schema.iter().filter(|pattern| !is_url(pattern)) {
+            let matched = self.expand(pattern)?;
+            if matched.is_empty() {
+                return Err(ConfigError::new(&format!(
+                    "No schema file matches {}",
+                    pattern
+                )));
+            }
+            files.extend(matched);
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// The files containing the operations and fragments of the project, sorted.
+    pub fn document_files(&self) -> Result<Vec<PathBuf>, ConfigError> {
+        let mut files = Vec::new();
+        for pattern in &self.documents {
+            files.extend(self.expand(pattern)?);
+        }
+        files.sort();
+        files.dedup();
+        Ok(files)
+    }
+
+    /// Reads and parses the schema files, and returns their definitions as a single document.
+    pub fn load_schema(&self) -> Result<Document, ConfigError> {
+        let mut definitions = Vec::new();
+        for path in self.schema_files()? {
+            let source = fs::read_to_string(&path).map_err(|error| {
+                ConfigError::new(&format!("Unable to read {}: {}", path.display(), error))
+            })?;
+            let document = crate::parse(&source)
+                .map_err(|error| ConfigError::new(&format!("{}: {}", path.display(), error)))?;
+            definitions.extend(document.definitions);
+        }
+        Ok(Document::new(definitions))
+    }
+
+    fn expand(&self, pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+        let pattern = self.root.join(pattern);
+        let paths = glob::glob(&pattern.to_string_lossy()).map_err(|error| {
+            ConfigError::new(&format!("Invalid pattern {}: {}", pattern.display(), error))
+        })?;
+        Ok(paths
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect())
+    }
+}
+
+/// The patterns of a field that is either a string or a list of strings.
+fn patterns(yaml: &Yaml, field: &str) -> Result<Vec<String>, ConfigError> {
+    let invalid = || ConfigError::new(&format!("{} must be a string or a list of strings", field));
+    match yaml {
+        Yaml::String(pattern) => Ok(vec![pattern.clone()]),
+        Yaml::Array(patterns) => patterns
+            .iter()
+            .map(|pattern| pattern.as_str().map(str::to_owned).ok_or_else(invalid))
+            .collect(),
+        Yaml::BadValue | Yaml::Null => Ok(Vec::new()),
+        _ => Err(invalid()),
+    }
+}
+
+fn is_url(pattern: &str) -> bool {
+    pattern.contains("://")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for a test, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path =
+                std::env::temp_dir().join(format!("syntax-config-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        fn write(&self, name: &str, contents: &str) {
+            let path = self.0.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn it_reads_projects() {
+        let source = r#"{
+            "projects": {
+                "app": { "schema": ["schema.graphql", "https://example.com/graphql"] },
+                "default": { "documents": "**/*.graphql" }
+            }
+        }"#;
+        let config = GraphQLConfig::parse(source, Path::new("root")).unwrap();
+        assert_eq!(config.projects.len(), 2);
+        assert_eq!(
+            config.project(Some("app")).unwrap().schema,
+            vec!["schema.graphql", "https://example.com/graphql"]
+        );
+        assert_eq!(
+            config.default_project().unwrap().documents,
+            vec!["**/*.graphql"]
+        );
+        assert!(GraphQLConfig::parse("schema: 42", Path::new("")).is_err());
+        assert!(GraphQLConfig::parse("- schema", Path::new("")).is_err());
+    }
+
+    #[test]
+    fn it_discovers_the_config_and_its_files() {
+        let directory = TempDir::new("discover");
+        directory.write(
+            ".graphqlrc.yml",
+            "schema: schema/*.graphql\ndocuments: src/**/*.graphql\n",
+        );
+        directory.write("schema/query.graphql", "type Query { me: User }");
+        directory.write("schema/user.graphql", "type User { id: ID! }");
+        directory.write("src/me.graphql", "query Me { me { id } }");
+        directory.write("src/nested/user.graphql", "fragment U on User { id }");
+        directory.write("src/other.txt", "");
+
+        let config = GraphQLConfig::discover(&directory.0.join("src/nested"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.path, Some(directory.0.join(".graphqlrc.yml")));
+        let project = config.default_project().unwrap();
+        assert_eq!(
+            project.document_files().unwrap(),
+            vec![
+                directory.0.join("src/me.graphql"),
+                directory.0.join("src/nested/user.graphql")
+            ]
+        );
+        let schema = project.load_schema().unwrap();
+        assert_eq!(schema.definitions.len(), 2);
+
+        let missing = ProjectConfig {
+            root: directory.0.clone(),
+            schema: vec!["missing.graphql".into()],
+            documents: Vec::new(),
+        };
+        assert!(missing.schema_files().is_err());
+    }
+}
@@ -0,0 +1,296 @@
+//! Schema coordinates, the names of the elements of a schema.
+//!
+//! A [`SchemaCoordinate`] names a type (`User`), a field of an object or interface, a field of an
+//! input object or a value of an enum (`User.name`, `Filter.first`, `Role.ADMIN`), an argument of
+//! a field (`Query.user(id:)`), a directive (`@cached`) or an argument of a directive
+//! (`@cached(ttl:)`). Coordinates are parsed from their text, and printed back to it, and
+//! [`Schema::resolve_coordinate`] finds the definition a coordinate names in a schema. As the
+//! GraphQL Spec defines them, coordinates hold no whitespace.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::coordinate::{SchemaCoordinate, SchemaElement};
+//! use syntax::schema::Schema;
+//!
+//! let document = syntax::parse("type Query { user(id: ID!): String }").unwrap();
+//! let schema = Schema::new(&document);
+//! let coordinate: SchemaCoordinate = "Query.user(id:)".parse().unwrap();
+//! assert_eq!(coordinate.to_string(), "Query.user(id:)");
+//! match schema.resolve_coordinate(&coordinate) {
+//!     Some(SchemaElement::Argument(argument)) => assert_eq!(argument.name.value, "id"),
+//!     _ => panic!("Query.user(id:) is an argument"),
+//! }
+//! assert!("Query.user (id:)".parse::<SchemaCoordinate>().is_err());
+//! ```
+//!
+//! [`SchemaCoordinate`]: enum.SchemaCoordinate.html
+//! [`Schema::resolve_coordinate`]: ../schema/struct.Schema.html#method.resolve_coordinate
+
+use crate::error::CoordinateError;
+use crate::nodes::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// The name of an element of a schema.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SchemaCoordinate {
+    /// A type: `User`
+    Type(String),
+    /// A field of an object, an interface or an input object, or a value of an enum:
+    /// `User.name`
+    Member {
+        /// The name of the type
+        type_name: String,
+        /// The name of the field or enum value
+        member: String,
+    },
+    /// An argument of a field: `Query.user(id:)`
+    Argument {
+        /// The name of the type
+        type_name: String,
+        /// The name of the field
+        field: String,
+        /// The name of the argument
+        argument: String,
+    },
+    /// A directive: `@cached`
+    Directive(String),
+    /// An argument of a directive: `@cached(ttl:)`
+    DirectiveArgument {
+        /// The name of the directive, without `@`
+        directive: String,
+        /// The name of the argument
+        argument: String,
+    },
+}
+
+/// The definition a schema coordinate names in a schema.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaElement<'a> {
+    /// A type definition
+    Type(&'a TypeDefinitionNode),
+    /// A field of an object or an interface
+    Field(&'a FieldDefinitionNode),
+    /// A field of an input object
+    InputField(&'a InputValueDefinitionNode),
+    /// A value of an enum
+    EnumValue(&'a EnumValueDefinitionNode),
+    /// An argument of a field or a directive
+    Argument(&'a InputValueDefinitionNode),
+    /// A directive definition
+    Directive(&'a DirectiveDefinitionNode),
+}
+
+impl FromStr for SchemaCoordinate {
+    type Err = CoordinateError;
+
+    fn from_str(coordinate: &str) -> Result<SchemaCoordinate, CoordinateError> {
+        let mut reader = Reader {
+            coordinate,
+            rest: coordinate,
+        };
+        let parsed = if reader.eat('@') {
+            let directive = reader.name()?;
+            match reader.argument()? {
+                Some(argument) => SchemaCoordinate::DirectiveArgument {
+                    directive,
+                    argument,
+                },
+                None => SchemaCoordinate::Directive(directive),
+            }
+        } else {
+            let type_name = reader.name()?;
+            if reader.eat('.') {
+                let member = reader.name()?;
+                match reader.argument()? {
+                    Some(argument) => SchemaCoordinate::Argument {
+                        type_name,
+                        field: member,
+                        argument,
+                    },
+                    None => SchemaCoordinate::Member { type_name, member },
+                }
+            } else {
+                SchemaCoordinate::Type(type_name)
+            }
+        };
+        match reader.rest.chars().next() {
+            Some(unexpected) => Err(reader.error(&format!("unexpected \"{}\"", unexpected))),
+            None => Ok(parsed),
+        }
+    }
+}
+
+impl fmt::Display for SchemaCoordinate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaCoordinate::Type(type_name) => write!(f, "{}", type_name),
+            SchemaCoordinate::Member { type_name, member } => {
+                write!(f, "{}.{}", type_name, member)
+            }
+            SchemaCoordinate::Argument {
+                type_name,
+                field,
+                argument,
+            } => write!(f, "{}.{}({}:)", type_name, field, argument),
+            SchemaCoordinate::Directive(directive) => write!(f, "@{}", directive),
+            SchemaCoordinate::DirectiveArgument {
+                directive,
+                argument,
+            } => write!(f, "@{}({}:)", directive, argument),
+        }
+    }
+}
+
+/// Reads a schema coordinate from its start.
+struct Reader<'c> {
+    coordinate: &'c str,
+    rest: &'c str,
+}
+
+impl Reader<'_> {
+    /// Skips a character when it is the next one.
+    fn eat(&mut self, expected: char) -> bool {
+        match self.rest.strip_prefix(expected) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn name(&mut self) -> Result<String, CoordinateError> {
+        let length = self
+            .rest
+            .find(|c: char| !(c == '_' || c.is_ascii_alphanumeric()))
+            .unwrap_or(self.rest.len());
+        let name = &self.rest[..length];
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(self.error("expected a name"));
+        }
+        self.rest = &self.rest[length..];
+        Ok(name.to_string())
+    }
+
+    /// Reads an argument, `(name:)`, when one follows.
+    fn argument(&mut self) -> Result<Option<String>, CoordinateError> {
+        if !self.eat('(') {
+            return Ok(None);
+        }
+        let argument = self.name()?;
+        if !(self.eat(':') && self.eat(')')) {
+            return Err(self.error("expected \":)\""));
+        }
+        Ok(Some(argument))
+    }
+
+    fn error(&self, issue: &str) -> CoordinateError {
+        let column = self.coordinate[..self.coordinate.len() - self.rest.len()]
+            .chars()
+            .count()
+            + 1;
+        CoordinateError::new(&format!(
+            "Invalid schema coordinate \"{}\", {} at column {}",
+            self.coordinate, issue, column
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Schema;
+
+    #[test]
+    fn it_parses_and_prints_coordinates() {
+        for coordinate in [
+            "User",
+            "User.name",
+            "Query.user(id:)",
+            "@cached",
+            "@cached(ttl:)",
+            "__Type.fields(includeDeprecated:)",
+        ] {
+            let parsed: SchemaCoordinate = coordinate.parse().unwrap();
+            assert_eq!(parsed.to_string(), coordinate);
+        }
+        assert_eq!(
+            "Query.user(id:)".parse::<SchemaCoordinate>().unwrap(),
+            SchemaCoordinate::Argument {
+                type_name: "Query".into(),
+                field: "user".into(),
+                argument: "id".into(),
+            }
+        );
+        assert_eq!(
+            "User(id:)".parse::<SchemaCoordinate>().unwrap_err().message,
+            "Invalid schema coordinate \"User(id:)\", unexpected \"(\" at column 5"
+        );
+        for invalid in [
+            "",
+            "@",
+            "User.",
+            "1User",
+            "User.name(id)",
+            "User .name",
+            "User.a.b",
+        ] {
+            assert!(invalid.parse::<SchemaCoordinate>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn it_resolves_coordinates() {
+        let document = crate::parse(
+            "type Query { user(id: ID!): User }
+            type User { name: String }
+            extend type User { role: Role }
+            enum Role { ADMIN }
+            input Filter { first: Int }
+            directive @cached(ttl: Int) on FIELD_DEFINITION",
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        let resolve = |coordinate: &str| schema.resolve_coordinate(&coordinate.parse().unwrap());
+        assert!(matches!(resolve("User"), Some(SchemaElement::Type(_))));
+        assert!(
+            matches!(resolve("User.role"), Some(SchemaElement::Field(field)) if field.name.value == "role")
+        );
+        assert!(matches!(
+            resolve("Filter.first"),
+            Some(SchemaElement::InputField(_))
+        ));
+        assert!(matches!(
+            resolve("Role.ADMIN"),
+            Some(SchemaElement::EnumValue(_))
+        ));
+        assert!(matches!(
+            resolve("Query.user(id:)"),
+            Some(SchemaElement::Argument(_))
+        ));
+        assert!(matches!(
+            resolve("@cached"),
+            Some(SchemaElement::Directive(_))
+        ));
+        assert!(matches!(
+            resolve("@cached(ttl:)"),
+            Some(SchemaElement::Argument(_))
+        ));
+        assert!(matches!(
+            resolve("@skip(if:)"),
+            Some(SchemaElement::Argument(_))
+        ));
+        for missing in [
+            "Unknown",
+            "User.id",
+            "Role.USER",
+            "Query.user(name:)",
+            "@unknown",
+            "String.length",
+        ] {
+            assert_eq!(resolve(missing), None, "{}", missing);
+        }
+    }
+}
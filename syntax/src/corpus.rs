@@ -0,0 +1,416 @@
+//! Generates random but valid queries against a schema, for fuzzing and load testing.
+//!
+//! A [`Corpus`] is an endless iterator of query documents, each a valid operation on the root
+//! query type of the schema: every field it selects is defined on its parent type, is given its
+//! required arguments, and has a selection set exactly when its type is an object, interface or
+//! union, with inline fragments on the possible types of abstract ones. The selection sets are
+//! nested at most [`CorpusOptions::max_depth`] deep and hold at most
+//! [`CorpusOptions::max_width`] fields each.
+//!
+//! The tokens of the documents are separated by random whitespace, commas and comments, and their
+//! strings written with escape sequences and as block strings, so a corpus exercises the lexer
+//! and the printer as much as the parser: printing a document parsed from one of them must give
+//! a document which prints the same once parsed again. The documents follow from the
+//! [`CorpusOptions::seed`], so a failing one can be generated again.
+//!
+//! Mutations and subscriptions are left out, so a corpus can be sent to a server as is.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::corpus::{Corpus, CorpusOptions};
+//! use syntax::schema::Schema;
+//! use syntax::validator::Validator;
+//!
+//! let schema_document =
+//!     syntax::parse("type Query { user(id: ID!): User } type User { name: String friend: User }")
+//!         .unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let options = CorpusOptions { seed: 42, ..CorpusOptions::default() };
+//! for source in Corpus::with_options(&schema, options).take(10) {
+//!     let document = syntax::parse(&source).unwrap();
+//!     assert!(Validator::new(&schema).validate(&source, &document).is_empty());
+//! }
+//! ```
+//!
+//! [`Corpus`]: struct.Corpus.html
+//! [`CorpusOptions::max_depth`]: struct.CorpusOptions.html#structfield.max_depth
+//! [`CorpusOptions::max_width`]: struct.CorpusOptions.html#structfield.max_width
+//! [`CorpusOptions::seed`]: struct.CorpusOptions.html#structfield.seed
+
+use crate::nodes::*;
+use crate::schema::{named_type, Schema};
+
+/// Options for a [`Corpus`].
+///
+/// [`Corpus`]: struct.Corpus.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorpusOptions {
+    /// The seed the documents follow from: other seeds give other documents
+    pub seed: u64,
+    /// The most selection sets nested within one another, the operation's included
+    pub max_depth: usize,
+    /// The most fields selected in a selection set, the fields of its fragments aside
+    pub max_width: usize,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        CorpusOptions {
+            seed: 0,
+            max_depth: 4,
+            max_width: 4,
+        }
+    }
+}
+
+/// The ignored tokens written between two tokens, one or more of them at a time.
+const IGNORED: [&str; 8] = [" ", "  ", "\n", "\n  ", "\t", ", ", "\r\n", " # comment\n"];
+
+/// The string literals written for `String` values.
+const STRINGS: [&str; 6] = [
+    r#""""#,
+    r#""text""#,
+    r#""with \"quotes\" and \\ \n""#,
+    r#""café é""#,
+    r#""""block "quoted" string""""#,
+    "\"\"\"\n    indented\n      block\n  \"\"\"",
+];
+
+/// An endless iterator of random queries against a schema, as GraphQL sources.
+///
+/// It ends at once when the schema has no root query type, or one with no fields.
+#[derive(Debug, Clone)]
+pub struct Corpus<'a> {
+    schema: &'a Schema<'a>,
+    options: CorpusOptions,
+    state: u64,
+}
+
+impl<'a> Corpus<'a> {
+    /// A corpus of queries against the schema, with the default options.
+    pub fn new(schema: &'a Schema<'a>) -> Corpus<'a> {
+        Corpus::with_options(schema, CorpusOptions::default())
+    }
+
+    /// A corpus of queries against the schema, with options.
+    pub fn with_options(schema: &'a Schema<'a>, options: CorpusOptions) -> Corpus<'a> {
+        Corpus {
+            schema,
+            options,
+            state: options.seed,
+        }
+    }
+
+    /// The next pseudorandom number, from the SplitMix64 generator.
+    fn random(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut random = self.state;
+        random = (random ^ (random >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        random = (random ^ (random >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        random ^ (random >> 31)
+    }
+
+    /// A pseudorandom number below a bound, which is not zero.
+    fn below(&mut self, bound: usize) -> usize {
+        (self.random() % bound as u64) as usize
+    }
+
+    /// Writes a token, after ignored tokens when it is not the first.
+    fn token(&mut self, output: &mut String, token: &str) {
+        if !output.is_empty() {
+            for _ in 0..=self.below(2) {
+                let ignored = IGNORED[self.below(IGNORED.len())];
+                output.push_str(ignored);
+            }
+        }
+        output.push_str(token);
+    }
+
+    /// Writes the selection set of a field of a composite type, at a depth, `1` being the
+    /// selection set of the operation.
+    fn selection_set(
+        &mut self,
+        output: &mut String,
+        type_name: &str,
+        depth: usize,
+        alias: &mut usize,
+    ) {
+        self.token(output, "{");
+        let mut fields = self.schema.fields(type_name);
+        if depth >= self.options.max_depth {
+            // The selection set is the last, so only fields without one fit in it
+            fields.retain(|field| !self.is_composite(named_type(&field.field_type)));
+        }
+        let width = match fields.len() {
+            0 => 0,
+            count => 1 + self.below(count.min(self.options.max_width.max(1))),
+        };
+        if width == 0 || self.below(4) == 0 {
+            self.token(output, "__typename");
+        }
+        // Each field once, so that no two fields are merged
+        for _ in 0..width {
+            let field = fields.remove(self.below(fields.len()));
+            self.field(output, field, None, depth, alias);
+        }
+        let is_abstract = matches!(
+            self.schema.get_type(type_name),
+            Some(TypeDefinitionNode::Interface(_)) | Some(TypeDefinitionNode::Union(_))
+        );
+        if is_abstract {
+            let mut possible_types = self.schema.possible_types(type_name);
+            for _ in 0..self.below(possible_types.len() + 1) {
+                let possible_type = possible_types.remove(self.below(possible_types.len()));
+                self.inline_fragment(output, possible_type, depth, alias);
+            }
+        }
+        self.token(output, "}");
+    }
+
+    /// Writes an inline fragment on a possible type of an abstract type, its fields given
+    /// aliases of their own, as they may not have the same types as the fields of other types.
+    fn inline_fragment(
+        &mut self,
+        output: &mut String,
+        type_name: &str,
+        depth: usize,
+        alias: &mut usize,
+    ) {
+        self.token(output, "...");
+        self.token(output, "on");
+        self.token(output, type_name);
+        if self.below(4) == 0 {
+            self.token(output, "@include");
+            self.token(output, "(");
+            self.token(output, "if");
+            self.token(output, ":");
+            self.token(output, "true");
+            self.token(output, ")");
+        }
+        self.token(output, "{");
+        let mut fields = self.schema.fields(type_name);
+        if depth >= self.options.max_depth {
+            fields.retain(|field| !self.is_composite(named_type(&field.field_type)));
+        }
+        self.token(output, "__typename");
+        for _ in 0..self.below(fields.len().min(self.options.max_width) + 1) {
+            let field = fields.remove(self.below(fields.len()));
+            *alias += 1;
+            let field_alias = format!("{}{}", field.name.value, alias);
+            self.field(output, field, Some(&field_alias), depth, alias);
+        }
+        self.token(output, "}");
+    }
+
+    fn field(
+        &mut self,
+        output: &mut String,
+        field: &FieldDefinitionNode,
+        field_alias: Option<&str>,
+        depth: usize,
+        alias: &mut usize,
+    ) {
+        if let Some(field_alias) = field_alias {
+            self.token(output, field_alias);
+            self.token(output, ":");
+        }
+        self.token(output, &field.name.value);
+        let required: Vec<&InputValueDefinitionNode> = field
+            .arguments
+            .iter()
+            .flatten()
+            .filter(|argument| is_required(argument))
+            .collect();
+        if !required.is_empty() {
+            self.token(output, "(");
+            for argument in required {
+                self.token(output, &argument.name.value);
+                self.token(output, ":");
+                self.value(output, &argument.input_type, 0);
+            }
+            self.token(output, ")");
+        }
+        if self.below(8) == 0 {
+            self.token(output, "@skip");
+            self.token(output, "(");
+            self.token(output, "if");
+            self.token(output, ":");
+            self.token(output, "false");
+            self.token(output, ")");
+        }
+        let type_name = named_type(&field.field_type);
+        if self.is_composite(type_name) {
+            self.selection_set(output, type_name, depth + 1, alias);
+        }
+    }
+
+    /// Writes a literal of an input type. Input objects are given their required fields, or the
+    /// one field of a `@oneOf` input object, and lists hold at most two items, nested at most as
+    /// deep as selection sets.
+    fn value(&mut self, output: &mut String, input_type: &TypeNode, depth: usize) {
+        match input_type {
+            TypeNode::NonNull(inner) => return self.value(output, inner, depth),
+            TypeNode::List(list) if depth < self.options.max_depth => {
+                self.token(output, "[");
+                for _ in 0..self.below(3) {
+                    self.value(output, &list.list_type, depth + 1);
+                }
+                self.token(output, "]");
+                return;
+            }
+            TypeNode::List(_) => {
+                self.token(output, "[");
+                self.token(output, "]");
+                return;
+            }
+            TypeNode::Named(_) => {}
+        }
+        let type_name = named_type(input_type);
+        match self.schema.get_type(type_name) {
+            Some(TypeDefinitionNode::Enum(enum_type)) if !enum_type.values.is_empty() => {
+                let value = &enum_type.values[self.below(enum_type.values.len())];
+                self.token(output, &value.name.value);
+            }
+            Some(TypeDefinitionNode::Input(input)) => {
+                let fields: Vec<&InputValueDefinitionNode> = if self.schema.is_one_of(type_name) {
+                    input
+                        .fields
+                        .get(self.below(input.fields.len().max(1)))
+                        .into_iter()
+                        .collect()
+                } else {
+                    input
+                        .fields
+                        .iter()
+                        .filter(|field| is_required(field))
+                        .collect()
+                };
+                self.token(output, "{");
+                for field in fields {
+                    self.token(output, &field.name.value);
+                    self.token(output, ":");
+                    self.value(output, &field.input_type, depth + 1);
+                }
+                self.token(output, "}");
+            }
+            _ => {
+                let literal = match type_name {
+                    "Int" => (self.below(2001) as i64 - 1000).to_string(),
+                    "Float" => format!("{}.{}e{}", self.below(100), self.below(100), self.below(5)),
+                    "Boolean" => (self.below(2) == 0).to_string(),
+                    "ID" if self.below(2) == 0 => self.below(10_000).to_string(),
+                    _ => STRINGS[self.below(STRINGS.len())].to_string(),
+                };
+                self.token(output, &literal);
+            }
+        }
+    }
+
+    fn is_composite(&self, type_name: &str) -> bool {
+        matches!(
+            self.schema.get_type(type_name),
+            Some(TypeDefinitionNode::Object(_))
+                | Some(TypeDefinitionNode::Interface(_))
+                | Some(TypeDefinitionNode::Union(_))
+        )
+    }
+}
+
+impl Iterator for Corpus<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let query = self.schema.query_type()?;
+        if self.schema.fields(query).is_empty() {
+            return None;
+        }
+        let mut output = String::new();
+        match self.below(3) {
+            0 => {}
+            1 => self.token(&mut output, "query"),
+            _ => {
+                self.token(&mut output, "query");
+                let name = format!("Fuzz{}", self.below(1000));
+                self.token(&mut output, &name);
+            }
+        }
+        self.selection_set(&mut output, query, 1, &mut 0);
+        output.push('\n');
+        Some(output)
+    }
+}
+
+/// Whether an argument or input field must be given a value: it is non-null, with no default.
+fn is_required(input: &InputValueDefinitionNode) -> bool {
+    matches!(input.input_type, TypeNode::NonNull(_)) && input.default_value.is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::print;
+    use crate::validator::Validator;
+
+    const SCHEMA: &str = "type Query { node(id: ID!): Node search(filter: Filter!): [Result!]! me: User }
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name(format: Format!): String friends: [User] best: Node }
+        type Admin implements Node { id: Int! level: Int! }
+        union Result = User | Admin
+        enum Format { SHORT LONG }
+        input Filter { text: String! tags: [String!]! ratio: Float! limit: Int = 10 by: By! }
+        input By @oneOf { id: ID format: Format }";
+
+    #[test]
+    fn it_generates_valid_documents() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let validator = Validator::new(&schema);
+        for seed in 0..20 {
+            let options = CorpusOptions {
+                seed,
+                ..CorpusOptions::default()
+            };
+            for source in Corpus::with_options(&schema, options).take(20) {
+                let document =
+                    parse(&source).unwrap_or_else(|error| panic!("{}: {}", error, source));
+                let errors = validator.validate(&source, &document);
+                assert!(errors.is_empty(), "{:?}: {}", errors, source);
+                let printed = print(&document);
+                assert_eq!(print(&parse(&printed).unwrap()), printed);
+            }
+        }
+    }
+
+    #[test]
+    fn it_bounds_the_documents_and_follows_the_seed() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let options = CorpusOptions {
+            seed: 3,
+            max_depth: 2,
+            max_width: 1,
+        };
+        let corpus: Vec<String> = Corpus::with_options(&schema, options).take(50).collect();
+        for source in &corpus {
+            let printed = print(&parse(source).unwrap());
+            let depth = printed
+                .lines()
+                .map(|line| line.len() - line.trim_start().len())
+                .max();
+            // Two selection sets, and the fields of an inline fragment in the second
+            assert!(depth.unwrap() <= 6, "{}", printed);
+        }
+        assert_eq!(
+            corpus,
+            Corpus::with_options(&schema, options)
+                .take(50)
+                .collect::<Vec<_>>()
+        );
+        assert_ne!(corpus, Corpus::new(&schema).take(50).collect::<Vec<_>>());
+
+        let no_query = parse("type User { id: ID }").unwrap();
+        assert_eq!(Corpus::new(&Schema::new(&no_query)).next(), None);
+    }
+}
@@ -0,0 +1,741 @@
+//! The changes between two versions of a schema, and what they mean for the clients of the old
+//! one.
+//!
+//! Every change is classified by its [`Criticality`]: adding a type or an optional argument is
+//! safe, adding a value to an enum or a member to a union is dangerous because clients may not
+//! handle it, and removing a field or making an argument required is breaking. Changes are found
+//! in the types, fields, arguments, enum values, union members, interfaces and directives of the
+//! schemas, and on its root types. Descriptions and deprecations are not compared.
+//!
+//! [`assert_compatible`] gates a new version of a schema, in a test or a build script, on the
+//! changes a [`CompatibilityPolicy`] allows, such as dangerous changes but not breaking ones.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::diff::{diff, Criticality};
+//! use syntax::schema::Schema;
+//!
+//! let old = syntax::parse("type Query { user(id: ID): User } type User { name: String }").unwrap();
+//! let new = syntax::parse("type Query { user(id: ID!): User } type User { name: String! }").unwrap();
+//! let changes = diff(&Schema::new(&old), &Schema::new(&new));
+//! assert_eq!(changes.len(), 2);
+//! assert_eq!(changes[0].coordinate, "Query.user(id:)");
+//! assert_eq!(changes[0].criticality, Criticality::Breaking);
+//! assert_eq!(changes[1].coordinate, "User.name");
+//! assert_eq!(changes[1].criticality, Criticality::Safe);
+//! ```
+//!
+//! [`Criticality`]: enum.Criticality.html
+//! [`assert_compatible`]: fn.assert_compatible.html
+//! [`CompatibilityPolicy`]: struct.CompatibilityPolicy.html
+
+use crate::nodes::*;
+use crate::printer::{print_type, print_value};
+use crate::schema::Schema;
+use std::fmt;
+
+/// How a change of the schema affects the clients of the old schema.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Criticality {
+    /// Every operation valid against the old schema is valid against the new one, with the same
+    /// results.
+    #[default]
+    Safe,
+    /// Operations stay valid, but may get results their clients do not expect.
+    Dangerous,
+    /// Operations valid against the old schema may not be valid against the new one.
+    Breaking,
+}
+
+/// A change between two versions of a schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaChange {
+    /// How the change affects the clients of the old schema
+    pub criticality: Criticality,
+    /// Where the change is, as a schema coordinate: `User`, `User.name`,
+    /// `Query.user(id:)`, `Role.ADMIN` or `@cached`
+    pub coordinate: String,
+    /// What changed
+    pub message: String,
+}
+
+impl fmt::Display for Criticality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Criticality::Safe => "safe",
+            Criticality::Dangerous => "dangerous",
+            Criticality::Breaking => "breaking",
+        })
+    }
+}
+
+impl fmt::Display for SchemaChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} change at {}: {}",
+            self.criticality, self.coordinate, self.message
+        )
+    }
+}
+
+/// The changes [`assert_compatible`] accepts.
+///
+/// The default policy only accepts safe changes.
+///
+/// [`assert_compatible`]: fn.assert_compatible.html
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatibilityPolicy {
+    /// The most critical changes accepted
+    pub allowed: Criticality,
+    /// The coordinates where any change is accepted, such as a field removed once its clients
+    /// stopped selecting it
+    pub exceptions: Vec<String>,
+}
+
+impl CompatibilityPolicy {
+    /// Whether the policy accepts a change.
+    pub fn allows(&self, change: &SchemaChange) -> bool {
+        change.criticality <= self.allowed || self.exceptions.contains(&change.coordinate)
+    }
+}
+
+/// Checks that the changes from the old schema to the new one are accepted by a policy,
+/// returning the ones that are not, in the order of [`diff`], as violations.
+///
+/// Each violation prints as a line telling its criticality, its coordinate and what changed, for
+/// the output of a test or a build script:
+///
+/// ```
+/// use syntax::diff::{assert_compatible, CompatibilityPolicy, Criticality};
+/// use syntax::schema::Schema;
+///
+/// let old = syntax::parse("type Query { user: String role: Role } enum Role { USER }").unwrap();
+/// let new = syntax::parse("type Query { role: Role } enum Role { USER ADMIN }").unwrap();
+/// let policy = CompatibilityPolicy {
+///     allowed: Criticality::Dangerous,
+///     ..CompatibilityPolicy::default()
+/// };
+/// let violations = assert_compatible(&Schema::new(&old), &Schema::new(&new), &policy).unwrap_err();
+/// assert_eq!(
+///     violations[0].to_string(),
+///     "breaking change at Query.user: The field `Query.user` was removed"
+/// );
+/// assert_eq!(violations.len(), 1);
+/// ```
+///
+/// [`diff`]: fn.diff.html
+pub fn assert_compatible(
+    old: &Schema,
+    new: &Schema,
+    policy: &CompatibilityPolicy,
+) -> Result<(), Vec<SchemaChange>> {
+    let violations: Vec<SchemaChange> = diff(old, new)
+        .into_iter()
+        .filter(|change| !policy.allows(change))
+        .collect();
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// The changes that turn the old schema into the new one, in the order of the old schema's
+/// definitions followed by the definitions only the new one has.
+pub fn diff(old: &Schema, new: &Schema) -> Vec<SchemaChange> {
+    let mut changes = Changes(Vec::new());
+    for (operation, old_root, new_root) in [
+        ("query", old.query_type(), new.query_type()),
+        ("mutation", old.mutation_type(), new.mutation_type()),
+        (
+            "subscription",
+            old.subscription_type(),
+            new.subscription_type(),
+        ),
+    ] {
+        match (old_root, new_root) {
+            (Some(old_root), Some(new_root)) if old_root == new_root => (),
+            (Some(old_root), _) => changes.push(
+                Criticality::Breaking,
+                old_root,
+                format!("The {} root type `{}` was replaced", operation, old_root),
+            ),
+            (None, Some(new_root)) => changes.push(
+                Criticality::Safe,
+                new_root,
+                format!("The {} root type `{}` was added", operation, new_root),
+            ),
+            (None, None) => (),
+        }
+    }
+    for old_type in old.types() {
+        let name = old_type.name();
+        match new.get_type(name) {
+            None => changes.push(
+                Criticality::Breaking,
+                name,
+                format!("The type `{}` was removed", name),
+            ),
+            Some(new_type) if kind(old_type) != kind(new_type) => changes.push(
+                Criticality::Breaking,
+                name,
+                format!(
+                    "`{}` changed from {} to {}",
+                    name,
+                    kind(old_type),
+                    kind(new_type)
+                ),
+            ),
+            Some(new_type) => changes.types(old, new, old_type, new_type),
+        }
+    }
+    for new_type in new.types() {
+        let name = new_type.name();
+        if !old.has_type(name) {
+            changes.push(
+                Criticality::Safe,
+                name,
+                format!("The type `{}` was added", name),
+            );
+        }
+    }
+    for old_directive in old.directives() {
+        let name = old_directive.name.value.as_str();
+        let coordinate = format!("@{}", name);
+        match new.get_directive(name) {
+            None => changes.push(
+                Criticality::Breaking,
+                &coordinate,
+                format!("The directive `{}` was removed", coordinate),
+            ),
+            Some(new_directive) => {
+                for location in &old_directive.locations {
+                    if !new_directive.locations.contains(location) {
+                        changes.push(
+                            Criticality::Breaking,
+                            &coordinate,
+                            format!(
+                                "The directive `{}` can no longer be used on {}",
+                                coordinate,
+                                location.as_str()
+                            ),
+                        );
+                    }
+                }
+                changes.arguments(
+                    &coordinate,
+                    &old_directive.arguments,
+                    &new_directive.arguments,
+                );
+            }
+        }
+    }
+    for new_directive in new.directives() {
+        let name = new_directive.name.value.as_str();
+        if old.get_directive(name).is_none() {
+            let coordinate = format!("@{}", name);
+            changes.push(
+                Criticality::Safe,
+                &coordinate,
+                format!("The directive `{}` was added", coordinate),
+            );
+        }
+    }
+    changes.0
+}
+
+struct Changes(Vec<SchemaChange>);
+
+impl Changes {
+    fn push(&mut self, criticality: Criticality, coordinate: &str, message: String) {
+        self.0.push(SchemaChange {
+            criticality,
+            coordinate: coordinate.to_owned(),
+            message,
+        });
+    }
+
+    fn types(
+        &mut self,
+        old: &Schema,
+        new: &Schema,
+        old_type: &TypeDefinitionNode,
+        new_type: &TypeDefinitionNode,
+    ) {
+        let name = old_type.name();
+        match (old_type, new_type) {
+            (TypeDefinitionNode::Object(_), TypeDefinitionNode::Object(_)) => {
+                self.names(
+                    name,
+                    "the interface",
+                    "implemented by",
+                    &old.interfaces(name),
+                    &new.interfaces(name),
+                );
+                self.fields(old, new, name);
+            }
+            (TypeDefinitionNode::Interface(_), TypeDefinitionNode::Interface(_)) => {
+                self.fields(old, new, name)
+            }
+            (TypeDefinitionNode::Union(old_union), TypeDefinitionNode::Union(new_union)) => self
+                .names(
+                    name,
+                    "the member",
+                    "of the union",
+                    &named_types(&old_union.types),
+                    &named_types(&new_union.types),
+                ),
+            (TypeDefinitionNode::Enum(old_enum), TypeDefinitionNode::Enum(new_enum)) => {
+                let values = |values: &[EnumValueDefinitionNode]| -> Vec<String> {
+                    values
+                        .iter()
+                        .map(|value| value.name.value.to_string())
+                        .collect()
+                };
+                let (old_values, new_values) = (values(&old_enum.values), values(&new_enum.values));
+                for value in old_values.iter().filter(|v| !new_values.contains(v)) {
+                    self.push(
+                        Criticality::Breaking,
+                        &format!("{}.{}", name, value),
+                        format!("The value `{}` was removed from the enum `{}`", value, name),
+                    );
+                }
+                for value in new_values.iter().filter(|v| !old_values.contains(v)) {
+                    self.push(
+                        Criticality::Dangerous,
+                        &format!("{}.{}", name, value),
+                        format!("The value `{}` was added to the enum `{}`", value, name),
+                    );
+                }
+            }
+            (TypeDefinitionNode::Input(old_input), TypeDefinitionNode::Input(new_input)) => {
+                for old_field in &old_input.fields {
+                    let coordinate = format!("{}.{}", name, old_field.name.value);
+                    match new_input.fields.iter().find(|f| f.name == old_field.name) {
+                        None => self.push(
+                            Criticality::Breaking,
+                            &coordinate,
+                            format!("The input field `{}` was removed", coordinate),
+                        ),
+                        Some(new_field) => {
+                            self.input_value(&coordinate, "input field", old_field, new_field)
+                        }
+                    }
+                }
+                for new_field in &new_input.fields {
+                    if !old_input.fields.iter().any(|f| f.name == new_field.name) {
+                        let coordinate = format!("{}.{}", name, new_field.name.value);
+                        self.added_input_value(&coordinate, "input field", new_field);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn fields(&mut self, old: &Schema, new: &Schema, type_name: &str) {
+        let new_fields = new.fields(type_name);
+        for old_field in old.fields(type_name) {
+            let coordinate = format!("{}.{}", type_name, old_field.name.value);
+            match new_fields.iter().find(|f| f.name == old_field.name) {
+                None => self.push(
+                    Criticality::Breaking,
+                    &coordinate,
+                    format!("The field `{}` was removed", coordinate),
+                ),
+                Some(new_field) => {
+                    if old_field.field_type != new_field.field_type {
+                        let criticality =
+                            if narrows_output(&old_field.field_type, &new_field.field_type) {
+                                Criticality::Safe
+                            } else {
+                                Criticality::Breaking
+                            };
+                        self.push(
+                            criticality,
+                            &coordinate,
+                            format!(
+                                "The field `{}` changed type from `{}` to `{}`",
+                                coordinate,
+                                print_type(&old_field.field_type),
+                                print_type(&new_field.field_type)
+                            ),
+                        );
+                    }
+                    self.arguments(&coordinate, &old_field.arguments, &new_field.arguments);
+                }
+            }
+        }
+        let old_fields = old.fields(type_name);
+        for new_field in new_fields {
+            if !old_fields.iter().any(|f| f.name == new_field.name) {
+                let coordinate = format!("{}.{}", type_name, new_field.name.value);
+                self.push(
+                    Criticality::Safe,
+                    &coordinate,
+                    format!("The field `{}` was added", coordinate),
+                );
+            }
+        }
+    }
+
+    fn arguments(
+        &mut self,
+        owner: &str,
+        old_arguments: &Option<ArgumentDefinitions>,
+        new_arguments: &Option<ArgumentDefinitions>,
+    ) {
+        let (old_arguments, new_arguments) = (
+            old_arguments.as_deref().unwrap_or_default(),
+            new_arguments.as_deref().unwrap_or_default(),
+        );
+        for old_argument in old_arguments {
+            let coordinate = format!("{}({}:)", owner, old_argument.name.value);
+            match new_arguments.iter().find(|a| a.name == old_argument.name) {
+                None => self.push(
+                    Criticality::Breaking,
+                    &coordinate,
+                    format!("The argument `{}` was removed", coordinate),
+                ),
+                Some(new_argument) => {
+                    self.input_value(&coordinate, "argument", old_argument, new_argument)
+                }
+            }
+        }
+        for new_argument in new_arguments {
+            if !old_arguments.iter().any(|a| a.name == new_argument.name) {
+                let coordinate = format!("{}({}:)", owner, new_argument.name.value);
+                self.added_input_value(&coordinate, "argument", new_argument);
+            }
+        }
+    }
+
+    fn input_value(
+        &mut self,
+        coordinate: &str,
+        what: &str,
+        old_value: &InputValueDefinitionNode,
+        new_value: &InputValueDefinitionNode,
+    ) {
+        if old_value.input_type != new_value.input_type {
+            let criticality = if widens_input(&old_value.input_type, &new_value.input_type) {
+                Criticality::Safe
+            } else {
+                Criticality::Breaking
+            };
+            self.push(
+                criticality,
+                coordinate,
+                format!(
+                    "The {} `{}` changed type from `{}` to `{}`",
+                    what,
+                    coordinate,
+                    print_type(&old_value.input_type),
+                    print_type(&new_value.input_type)
+                ),
+            );
+        }
+        if old_value.default_value != new_value.default_value {
+            let print = |value: &Option<ValueNode>| {
+                value
+                    .as_ref()
+                    .map(|value| format!("`{}`", print_value(value)))
+                    .unwrap_or_else(|| "none".into())
+            };
+            self.push(
+                Criticality::Dangerous,
+                coordinate,
+                format!(
+                    "The default value of the {} `{}` changed from {} to {}",
+                    what,
+                    coordinate,
+                    print(&old_value.default_value),
+                    print(&new_value.default_value)
+                ),
+            );
+        }
+    }
+
+    fn added_input_value(
+        &mut self,
+        coordinate: &str,
+        what: &str,
+        value: &InputValueDefinitionNode,
+    ) {
+        let required =
+            matches!(value.input_type, TypeNode::NonNull(_)) && value.default_value.is_none();
+        if required {
+            self.push(
+                Criticality::Breaking,
+                coordinate,
+                format!("The required {} `{}` was added", what, coordinate),
+            );
+        } else {
+            self.push(
+                Criticality::Safe,
+                coordinate,
+                format!("The {} `{}` was added", what, coordinate),
+            );
+        }
+    }
+
+    /// Compares the interfaces of an object, or the members of a union.
+    fn names(&mut self, type_name: &str, what: &str, relation: &str, old: &[&str], new: &[&str]) {
+        for name in old.iter().filter(|name| !new.contains(name)) {
+            self.push(
+                Criticality::Breaking,
+                type_name,
+                format!(
+                    "{} `{}` is no longer {} `{}`",
+                    capitalized(what),
+                    name,
+                    relation,
+                    type_name
+                ),
+            );
+        }
+        for name in new.iter().filter(|name| !old.contains(name)) {
+            self.push(
+                Criticality::Dangerous,
+                type_name,
+                format!(
+                    "{} `{}` is now {} `{}`",
+                    capitalized(what),
+                    name,
+                    relation,
+                    type_name
+                ),
+            );
+        }
+    }
+}
+
+/// Whether every value of the new output type is a value of the old one: the new type only
+/// adds non-null wrappers.
+fn narrows_output(old: &TypeNode, new: &TypeNode) -> bool {
+    match (old, new) {
+        (TypeNode::NonNull(old), TypeNode::NonNull(new)) => narrows_output(old, new),
+        (old, TypeNode::NonNull(new)) => narrows_output(old, new),
+        (TypeNode::List(old), TypeNode::List(new)) => {
+            narrows_output(&old.list_type, &new.list_type)
+        }
+        (TypeNode::Named(old), TypeNode::Named(new)) => old.name == new.name,
+        _ => false,
+    }
+}
+
+/// Whether every value of the old input type is a value of the new one: the new type only
+/// drops non-null wrappers.
+fn widens_input(old: &TypeNode, new: &TypeNode) -> bool {
+    match (old, new) {
+        (TypeNode::NonNull(old), TypeNode::NonNull(new)) => widens_input(old, new),
+        (TypeNode::NonNull(old), new) => widens_input(old, new),
+        (TypeNode::List(old), TypeNode::List(new)) => widens_input(&old.list_type, &new.list_type),
+        (TypeNode::Named(old), TypeNode::Named(new)) => old.name == new.name,
+        _ => false,
+    }
+}
+
+fn kind(definition: &TypeDefinitionNode) -> &'static str {
+    match definition {
+        TypeDefinitionNode::Scalar(_) => "a scalar",
+        TypeDefinitionNode::Object(_) => "an object type",
+        TypeDefinitionNode::Interface(_) => "an interface",
+        TypeDefinitionNode::Union(_) => "a union",
+        TypeDefinitionNode::Enum(_) => "an enum",
+        TypeDefinitionNode::Input(_) => "an input type",
+    }
+}
+
+fn named_types(types: &[NamedTypeNode]) -> Vec<&str> {
+    types
+        .iter()
+        .map(|named| named.name.value.as_str())
+        .collect()
+}
+
+fn capitalized(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn changes(old: &str, new: &str) -> Vec<(Criticality, String, String)> {
+        let (old, new) = (parse(old).unwrap(), parse(new).unwrap());
+        diff(&Schema::new(&old), &Schema::new(&new))
+            .into_iter()
+            .map(|change| (change.criticality, change.coordinate, change.message))
+            .collect()
+    }
+
+    fn change(
+        criticality: Criticality,
+        coordinate: &str,
+        message: &str,
+    ) -> (Criticality, String, String) {
+        (criticality, coordinate.into(), message.into())
+    }
+
+    #[test]
+    fn identical_schemas_have_no_changes() {
+        let sdl = r#"
+            type Query { users(first: Int = 10): [User!]! }
+            type User implements Node { id: ID! role: Role }
+            interface Node { id: ID! }
+            enum Role { ADMIN USER }
+            directive @cached(ttl: Int) on FIELD_DEFINITION
+        "#;
+        assert!(changes(sdl, sdl).is_empty());
+    }
+
+    #[test]
+    fn it_classifies_type_changes() {
+        use Criticality::*;
+        assert_eq!(
+            changes(
+                r#"
+                type Query { a: Int }
+                type User implements Node { id: ID! name: String }
+                interface Node { id: ID! }
+                union Result = User
+                enum Role { ADMIN USER }
+                scalar Date
+                "#,
+                r#"
+                type Query { a: Int }
+                type User { id: ID! name: String! email: String }
+                interface Node { id: ID! }
+                union Result = User | Query
+                enum Role { ADMIN GUEST }
+                input Date { year: Int }
+                type Post { id: ID! }
+                "#,
+            ),
+            vec![
+                change(
+                    Breaking,
+                    "User",
+                    "The interface `Node` is no longer implemented by `User`"
+                ),
+                change(
+                    Safe,
+                    "User.name",
+                    "The field `User.name` changed type from `String` to `String!`"
+                ),
+                change(Safe, "User.email", "The field `User.email` was added"),
+                change(
+                    Dangerous,
+                    "Result",
+                    "The member `Query` is now of the union `Result`"
+                ),
+                change(
+                    Breaking,
+                    "Role.USER",
+                    "The value `USER` was removed from the enum `Role`"
+                ),
+                change(
+                    Dangerous,
+                    "Role.GUEST",
+                    "The value `GUEST` was added to the enum `Role`"
+                ),
+                change(
+                    Breaking,
+                    "Date",
+                    "`Date` changed from a scalar to an input type"
+                ),
+                change(Safe, "Post", "The type `Post` was added"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_classifies_argument_and_input_changes() {
+        use Criticality::*;
+        assert_eq!(
+            changes(
+                r#"
+                type Query { users(first: Int = 10, name: String!, role: String): [String] }
+                input Filter { name: String! age: Int }
+                directive @cached(ttl: Int) on FIELD_DEFINITION | OBJECT
+                "#,
+                r#"
+                type Query { users(first: Int = 20, name: String, after: String, id: ID!): String }
+                input Filter { name: String age: Int! since: Int }
+                directive @cached(ttl: Int) on FIELD_DEFINITION
+                "#,
+            ),
+            vec![
+                change(Breaking, "Query.users", "The field `Query.users` changed type from `[String]` to `String`"),
+                change(Dangerous, "Query.users(first:)", "The default value of the argument `Query.users(first:)` changed from `10` to `20`"),
+                change(Safe, "Query.users(name:)", "The argument `Query.users(name:)` changed type from `String!` to `String`"),
+                change(Breaking, "Query.users(role:)", "The argument `Query.users(role:)` was removed"),
+                change(Safe, "Query.users(after:)", "The argument `Query.users(after:)` was added"),
+                change(Breaking, "Query.users(id:)", "The required argument `Query.users(id:)` was added"),
+                change(Safe, "Filter.name", "The input field `Filter.name` changed type from `String!` to `String`"),
+                change(Breaking, "Filter.age", "The input field `Filter.age` changed type from `Int` to `Int!`"),
+                change(Safe, "Filter.since", "The input field `Filter.since` was added"),
+                change(Breaking, "@cached", "The directive `@cached` can no longer be used on OBJECT"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_root_type_changes() {
+        use Criticality::*;
+        assert_eq!(
+            changes(
+                "schema { query: Query } type Query { a: Int } type Root { a: Int }",
+                "schema { query: Root mutation: Query } type Query { a: Int } type Root { a: Int }",
+            ),
+            vec![
+                change(
+                    Breaking,
+                    "Query",
+                    "The query root type `Query` was replaced"
+                ),
+                change(Safe, "Query", "The mutation root type `Query` was added"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_gates_changes_on_a_policy() {
+        let old = parse("type Query { a: Int b: Int c: Role } enum Role { USER }").unwrap();
+        let new = parse("type Query { c: Role d: Int } enum Role { USER ADMIN }").unwrap();
+        let (old, new) = (Schema::new(&old), Schema::new(&new));
+        let violations = |policy: &CompatibilityPolicy| match assert_compatible(&old, &new, policy)
+        {
+            Ok(()) => Vec::new(),
+            Err(violations) => violations
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+        };
+        let mut policy = CompatibilityPolicy::default();
+        assert_eq!(
+            violations(&policy),
+            [
+                "breaking change at Query.a: The field `Query.a` was removed",
+                "breaking change at Query.b: The field `Query.b` was removed",
+                "dangerous change at Role.ADMIN: The value `ADMIN` was added to the enum `Role`",
+            ]
+        );
+        policy.allowed = Criticality::Dangerous;
+        policy.exceptions.push("Query.a".into());
+        assert_eq!(
+            violations(&policy),
+            ["breaking change at Query.b: The field `Query.b` was removed"]
+        );
+        policy.allowed = Criticality::Breaking;
+        assert!(assert_compatible(&old, &new, &policy).is_ok());
+    }
+}
@@ -1,25 +1,231 @@
 //! A parsed GraphQL [`Document`].
 //!
 //! [`Document`]: ../struct.Document.html
-use crate::nodes::DefinitionNode;
-use log::debug;
+use crate::nodes::*;
+use crate::schema::BUILTIN_SCALARS;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+
+#[cfg(feature = "default-schema")]
+mod default_schema;
+#[cfg(feature = "default-schema")]
+pub use default_schema::{DefaultScalar, DocumentBuilder};
 
 /// The Document is the root of a GraphQL schema and/or query. It contains a list of GraphQL
 /// definitions. These can be anything from types, enums, unions, etc. to a query.
 ///
 /// This struct will also provide validation methods and other ways to manipulate the GraphQL
 /// syntax tree.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Document {
     /// A list of GraphQL definitions
     pub definitions: Vec<DefinitionNode>,
 }
 
+/// An operation of a [`Document`] together with the fragments it uses, directly or through other
+/// fragments.
+///
+/// [`Document`]: struct.Document.html
+#[derive(Debug)]
+pub struct OperationWithFragments<'a> {
+    /// The name of the operation, if any
+    pub name: Option<&'a str>,
+    /// The operation definition
+    pub operation: &'a OperationTypeNode,
+    /// The fragments used by the operation, in the order they appear in the document
+    pub fragments: Vec<&'a FragmentDefinitionNode>,
+}
+
 impl Document {
     /// Create a new document with the provided definitions
     pub fn new(definitions: Vec<DefinitionNode>) -> Document {
         Document { definitions }
     }
+
+    /// Lists every operation of the document with only the fragments it transitively uses.
+    /// Spreads of fragments the document does not define are ignored.
+    pub fn operations(&self) -> Vec<OperationWithFragments<'_>> {
+        let fragments: Vec<&FragmentDefinitionNode> = self
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    Some(fragment)
+                }
+                _ => None,
+            })
+            .collect();
+
+        self.definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    Some(operation)
+                }
+                _ => None,
+            })
+            .map(|operation| {
+                let query = operation.definition();
+                let mut used = HashSet::new();
+                let mut pending = Vec::new();
+                collect_spreads(&query.selections, &mut pending);
+                while let Some(name) = pending.pop() {
+                    if used.insert(name) {
+                        if let Some(fragment) = fragments.iter().find(|f| f.name.value == name) {
+                            collect_spreads(&fragment.selections, &mut pending);
+                        }
+                    }
+                }
+                OperationWithFragments {
+                    name: query.name.as_ref().map(|name| name.value.as_str()),
+                    operation,
+                    fragments: fragments
+                        .iter()
+                        .filter(|fragment| used.contains(fragment.name.value.as_str()))
+                        .copied()
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// A copy of the document in a canonical order, for schemas to be diffed and printed the
+    /// same way whatever order they were written in.
+    ///
+    /// Definitions are ordered by kind, the schema definition first, then directives, scalars,
+    /// objects, interfaces, unions, enums, input objects, type extensions, operations and
+    /// fragments, and by name within a kind. Fields, arguments, input fields, enum values,
+    /// interfaces and union members are ordered by name. Selections and applied directives keep
+    /// the order they are written in, which they depend on.
+    pub fn sorted(&self) -> Document {
+        let mut definitions = self.definitions.clone();
+        definitions.sort_by(|a, b| sort_key(a).cmp(&sort_key(b)));
+        for definition in &mut definitions {
+            match definition {
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(definition)) => {
+                    sort_type(definition)
+                }
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+                    if let Some(arguments) = &mut directive.arguments {
+                        arguments.sort_by(|a, b| by_name(&a.name, &b.name));
+                    }
+                }
+                DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                    if let Some(interfaces) = &mut extension.interfaces {
+                        interfaces.sort_by(|a, b| by_name(&a.name, &b.name));
+                    }
+                    if let Some(fields) = &mut extension.fields {
+                        sort_fields(fields);
+                    }
+                }
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(_))
+                | DefinitionNode::Extension(TypeSystemExtensionNode::Schema(_))
+                | DefinitionNode::Executable(_) => {}
+            }
+        }
+        Document::new(definitions)
+    }
+
+    /// Splits the document into one standalone document per operation, each containing the
+    /// operation followed by the fragments it uses. Type system definitions are left out.
+    pub fn separate_operations(&self) -> Vec<Document> {
+        self.operations()
+            .into_iter()
+            .map(|operation| {
+                let mut definitions = vec![DefinitionNode::Executable(
+                    ExecutableDefinitionNode::Operation(operation.operation.clone()),
+                )];
+                definitions.extend(operation.fragments.into_iter().map(|fragment| {
+                    DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment.clone()))
+                }));
+                Document::new(definitions)
+            })
+            .collect()
+    }
+}
+
+/// The rank of the kind of a definition, and its name.
+fn sort_key(definition: &DefinitionNode) -> (u8, &str) {
+    match definition {
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(_)) => (0, ""),
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+            (1, &directive.name.value)
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(definition)) => {
+            let rank = match definition {
+                TypeDefinitionNode::Scalar(_) => 2,
+                TypeDefinitionNode::Object(_) => 3,
+                TypeDefinitionNode::Interface(_) => 4,
+                TypeDefinitionNode::Union(_) => 5,
+                TypeDefinitionNode::Enum(_) => 6,
+                TypeDefinitionNode::Input(_) => 7,
+            };
+            (rank, definition.name())
+        }
+        DefinitionNode::Extension(TypeSystemExtensionNode::Schema(_)) => (8, ""),
+        DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+            (8, &extension.name.value)
+        }
+        DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => (
+            9,
+            operation
+                .definition()
+                .name
+                .as_ref()
+                .map_or("", |name| &name.value),
+        ),
+        DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+            (10, &fragment.name.value)
+        }
+    }
+}
+
+fn sort_type(definition: &mut TypeDefinitionNode) {
+    match definition {
+        TypeDefinitionNode::Object(object) => {
+            if let Some(interfaces) = &mut object.interfaces {
+                interfaces.sort_by(|a, b| by_name(&a.name, &b.name));
+            }
+            sort_fields(&mut object.fields);
+        }
+        TypeDefinitionNode::Interface(interface) => sort_fields(&mut interface.fields),
+        TypeDefinitionNode::Union(union) => union.types.sort_by(|a, b| by_name(&a.name, &b.name)),
+        TypeDefinitionNode::Enum(enum_type) => {
+            enum_type.values.sort_by(|a, b| by_name(&a.name, &b.name))
+        }
+        TypeDefinitionNode::Input(input) => input.fields.sort_by(|a, b| by_name(&a.name, &b.name)),
+        TypeDefinitionNode::Scalar(_) => {}
+    }
+}
+
+fn sort_fields(fields: &mut [FieldDefinitionNode]) {
+    fields.sort_by(|a, b| by_name(&a.name, &b.name));
+    for field in fields {
+        if let Some(arguments) = &mut field.arguments {
+            arguments.sort_by(|a, b| by_name(&a.name, &b.name));
+        }
+    }
+}
+
+fn by_name(a: &NameNode, b: &NameNode) -> Ordering {
+    a.value.as_str().cmp(b.value.as_str())
+}
+
+fn collect_spreads<'a>(selections: &'a [Selection], names: &mut Vec<&'a str>) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => {
+                if let Some(selections) = &field.selections {
+                    collect_spreads(selections, names);
+                }
+            }
+            Selection::Fragment(FragmentSpread::Node(spread)) => names.push(&spread.name.value),
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                collect_spreads(&inline.selections, names)
+            }
+        }
+    }
 }
 
 use std::fmt;
@@ -29,161 +235,170 @@ impl fmt::Display for Document {
     }
 }
 
-use crate::gql;
-use std::default::Default;
-impl Default for Document {
-    fn default() -> Self {
-        let doc = gql!(&format!(
+impl Document {
+    /// Creates a document without any definitions.
+    pub fn empty() -> Document {
+        Document::new(Vec::new())
+    }
+
+    /// Creates a document defining only the scalars of the GraphQL Spec: `Int`, `Float`,
+    /// `String`, `Boolean` and `ID`.
+    pub fn with_builtin_scalars() -> Document {
+        Document::new(
+            BUILTIN_SCALARS
+                .iter()
+                .map(|name| {
+                    DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+                        TypeDefinitionNode::Scalar(ScalarTypeDefinitionNode::from(*name)),
+                    ))
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::print;
+
+    const OPERATIONS: &str = r#"
+type Query { a: Int }
+
+query Users {
+  users { ...UserFields ... on Admin { ...AdminFields } }
+}
+
+fragment Unused on User { id }
+
+fragment UserFields on User { name friend { ...Friend } }
+
+query Me { me { ...Friend } }
+
+fragment AdminFields on Admin { level }
+
+fragment Friend on User { id ...Friend }
+"#;
+
+    #[test]
+    fn it_lists_operations_with_their_fragments() {
+        let document = parse(OPERATIONS).unwrap();
+        let operations = document.operations();
+        assert_eq!(operations.len(), 2);
+        let names = |operation: &OperationWithFragments| {
+            operation
+                .fragments
+                .iter()
+                .map(|fragment| fragment.name.value.to_string())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(operations[0].name, Some("Users"));
+        assert_eq!(
+            names(&operations[0]),
+            vec!["UserFields", "AdminFields", "Friend"]
+        );
+        assert_eq!(operations[1].name, Some("Me"));
+        assert_eq!(names(&operations[1]), vec!["Friend"]);
+    }
+
+    #[test]
+    fn it_creates_minimal_documents() {
+        assert!(Document::empty().definitions.is_empty());
+        let document = Document::with_builtin_scalars();
+        let schema = crate::schema::Schema::with_builtins(&document, false);
+        assert_eq!(
+            schema.types().map(|t| t.name()).collect::<Vec<&str>>(),
+            BUILTIN_SCALARS.to_vec()
+        );
+    }
+
+    #[test]
+    fn it_separates_operations() {
+        let document = parse(OPERATIONS).unwrap();
+        let separated = document.separate_operations();
+        assert_eq!(separated.len(), 2);
+        assert_eq!(
+            print(&separated[1]),
+            r#"query Me {
+  me {
+    ...Friend
+  }
+}
+
+fragment Friend on User {
+  id
+  ...Friend
+}
+"#
+        );
+        assert_eq!(separated[0].definitions.len(), 4);
+    }
+
+    #[test]
+    fn it_sorts_definitions_and_their_members() {
+        let document = parse(
             r#"
-"""Int
-A signed, 32-bit, non-fractional number.
-Min: {i32_min}
-Max:  {i32_max}
-"""
-scalar Int
-
-"""TinyInt
-A signed, 8-bit, non-fractional number.
-Min: {i8_min}
-Max:  {i8_max}
-"""
-scalar TinyInt
-
-"""ShortInt
-A signed, 16-bit, non-fractional number.
-Min: {i16_min}
-Max:  {i16_max}
-"""
-scalar ShortInt
-
-"""LongInt
-A signed, 64-bit, non-fractional number.
-Min: {i64_min}
-Max:  {i64_max}
-"""
-scalar LongInt
-
-"""BigInt
-A signed, 128-bit, non-fractional number.
-Min: {i128_min}
-Max:  {i128_max}
-"""
-scalar BigInt
-
-"""Uint
-An unsigned, 32-bit, non-fractional number.
-Min: {u32_min}
-Max:  {u32_max}
-"""
-scalar Uint
-
-"""TinyUint
-An unsigned, 8-bit, non-fractional number.
-Min: {u8_min}
-Max:  {u8_max}
-"""
-scalar TinyUint
-
-"""ShortUint
-An unsigned, 16-bit, non-fractional number.
-Min: {u16_min}
-Max:  {u16_max}
-"""
-scalar ShortUint
-
-"""LongUint
-An unsigned, 64-bit, non-fractional number.
-Min: {u64_min}
-Max:  {u64_max}
-"""
-scalar LongUint
-
-"""BigUint
-An unsigned, 128-bit, non-fractional number.
-Min: {u128_min}
-Max:  {u128_max}
-"""
-scalar BigUint
-
-"""Float
-A signed, 32-bit, fractional number.
-For more information see [f32 docs](https://doc.rust-lang.org/std/primitive.f32.html).
-"""
-scalar Float
-
-"""Double
-A signed, 64-bit, fractional number.
-For more information see [f64 docs](https://doc.rust-lang.org/std/primitive.f64.html).
-"""
-scalar Float
-
-"""TinyString
-A small string made up of 255 bytes.
-"""
-scalar TinyString
-
-"""String
-A string made up of a maximum of 65,535 bytes. This should be sufficient for most use cases.
-"""
-scalar String
-
-"""DateTime
-A field used to represent a date and time.
-"""
-scalar DateTime
-
-"""Date
-A field used to represent a date.
-"""
+query B { b a }
+extend type User implements Named { nickname: String }
+input Filter { role: Role, after: ID }
+enum Role { USER ADMIN }
+union Result = User | Admin
+type User implements Node & Admin { name: String id(format: String, at: Int): ID! }
+type Admin { id: ID! }
 scalar Date
+directive @cached(ttl: Int, scope: String) on FIELD_DEFINITION
+schema { query: Query }
+query A { a }
+"#,
+        )
+        .unwrap();
+        let sorted = document.sorted();
+        assert_eq!(
+            print(&sorted),
+            r#"schema {
+  query: Query
+}
+
+directive @cached(scope: String, ttl: Int) on FIELD_DEFINITION
 
-"""Time
-A field used to represent a time.
-"""
-scalar Time
+scalar Date
 
-"""Boolean
-Used to represent true and false
-"""
-scalar Boolean
+type Admin {
+  id: ID!
+}
 
-"""ID
-Used as a unique identifier.
-"""
-scalar ID
+type User implements Admin & Node {
+  id(at: Int, format: String): ID!
+  name: String
+}
 
+union Result = Admin | User
 
-"""Schema
-The root of any interaction with the database.
-"""
-schema {{
-  query: Query
-  mutation: Mutation
-}}
-"#,
-            i8_min = i8::MIN,
-            i8_max = i8::MAX,
-            i16_min = i16::MIN,
-            i16_max = i16::MAX,
-            i32_min = i32::MIN,
-            i32_max = i32::MAX,
-            i64_min = i64::MIN,
-            i64_max = i64::MAX,
-            i128_min = i128::MIN,
-            i128_max = i128::MAX,
-            u8_min = u8::MIN,
-            u8_max = u8::MAX,
-            u16_min = u16::MIN,
-            u16_max = u16::MAX,
-            u32_min = u32::MIN,
-            u32_max = u32::MAX,
-            u64_min = u64::MIN,
-            u64_max = u64::MAX,
-            u128_min = u128::MIN,
-            u128_max = u128::MAX,
-        ));
-
-        debug!("Debugging: {:?}", doc);
-        doc.expect("Default schema is invalid")
+enum Role {
+  ADMIN
+  USER
+}
+
+input Filter {
+  after: ID
+  role: Role
+}
+
+extend type User implements Named {
+  nickname: String
+}
+
+query A {
+  a
+}
+
+query B {
+  b
+  a
+}
+"#
+        );
+        assert_eq!(sorted.sorted(), sorted);
     }
 }
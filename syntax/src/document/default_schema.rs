@@ -0,0 +1,342 @@
+//! The configurable default schema, available with the `default-schema` feature.
+
+use super::Document;
+use crate::error::ParseResult;
+use log::debug;
+use std::fmt;
+
+/// The `@auth` directive and the `Role` enum of the default schema.
+const AUTH: &str = r#"
+"Only answers the field, or the fields of the type, for the clients holding the role."
+directive @auth(requires: Role!) on OBJECT | FIELD_DEFINITION
+
+"The roles clients may hold."
+enum Role {
+  ADMIN
+  USER
+}
+"#;
+
+/// A scalar that [`DocumentBuilder`] can add to the default schema.
+///
+/// [`DocumentBuilder`]: struct.DocumentBuilder.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefaultScalar {
+    /// A signed, 32-bit, non-fractional number
+    Int,
+    /// A signed, 8-bit, non-fractional number
+    TinyInt,
+    /// A signed, 16-bit, non-fractional number
+    ShortInt,
+    /// A signed, 64-bit, non-fractional number
+    LongInt,
+    /// A signed, 128-bit, non-fractional number
+    BigInt,
+    /// An unsigned, 32-bit, non-fractional number
+    Uint,
+    /// An unsigned, 8-bit, non-fractional number
+    TinyUint,
+    /// An unsigned, 16-bit, non-fractional number
+    ShortUint,
+    /// An unsigned, 64-bit, non-fractional number
+    LongUint,
+    /// An unsigned, 128-bit, non-fractional number
+    BigUint,
+    /// A signed, 32-bit, fractional number
+    Float,
+    /// A signed, 64-bit, fractional number
+    Double,
+    /// A string of at most 255 bytes
+    TinyString,
+    /// A string of at most 65,535 bytes
+    String,
+    /// A date and time
+    DateTime,
+    /// A date
+    Date,
+    /// A time
+    Time,
+    /// True or false
+    Boolean,
+    /// A unique identifier
+    ID,
+}
+
+impl DefaultScalar {
+    /// Every scalar of the default schema, in the order they are defined.
+    pub const ALL: [DefaultScalar; 19] = [
+        DefaultScalar::Int,
+        DefaultScalar::TinyInt,
+        DefaultScalar::ShortInt,
+        DefaultScalar::LongInt,
+        DefaultScalar::BigInt,
+        DefaultScalar::Uint,
+        DefaultScalar::TinyUint,
+        DefaultScalar::ShortUint,
+        DefaultScalar::LongUint,
+        DefaultScalar::BigUint,
+        DefaultScalar::Float,
+        DefaultScalar::Double,
+        DefaultScalar::TinyString,
+        DefaultScalar::String,
+        DefaultScalar::DateTime,
+        DefaultScalar::Date,
+        DefaultScalar::Time,
+        DefaultScalar::Boolean,
+        DefaultScalar::ID,
+    ];
+
+    /// The name of the scalar in the schema.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DefaultScalar::Int => "Int",
+            DefaultScalar::TinyInt => "TinyInt",
+            DefaultScalar::ShortInt => "ShortInt",
+            DefaultScalar::LongInt => "LongInt",
+            DefaultScalar::BigInt => "BigInt",
+            DefaultScalar::Uint => "Uint",
+            DefaultScalar::TinyUint => "TinyUint",
+            DefaultScalar::ShortUint => "ShortUint",
+            DefaultScalar::LongUint => "LongUint",
+            DefaultScalar::BigUint => "BigUint",
+            DefaultScalar::Float => "Float",
+            DefaultScalar::Double => "Double",
+            DefaultScalar::TinyString => "TinyString",
+            DefaultScalar::String => "String",
+            DefaultScalar::DateTime => "DateTime",
+            DefaultScalar::Date => "Date",
+            DefaultScalar::Time => "Time",
+            DefaultScalar::Boolean => "Boolean",
+            DefaultScalar::ID => "ID",
+        }
+    }
+
+    fn description(&self) -> String {
+        fn range(kind: &str, min: impl fmt::Display, max: impl fmt::Display) -> String {
+            format!(
+                "{}, non-fractional number.\nMin: {}\nMax:  {}",
+                kind, min, max
+            )
+        }
+        match self {
+            DefaultScalar::Int => range("A signed, 32-bit", i32::MIN, i32::MAX),
+            DefaultScalar::TinyInt => range("A signed, 8-bit", i8::MIN, i8::MAX),
+            DefaultScalar::ShortInt => range("A signed, 16-bit", i16::MIN, i16::MAX),
+            DefaultScalar::LongInt => range("A signed, 64-bit", i64::MIN, i64::MAX),
+            DefaultScalar::BigInt => range("A signed, 128-bit", i128::MIN, i128::MAX),
+            DefaultScalar::Uint => range("An unsigned, 32-bit", u32::MIN, u32::MAX),
+            DefaultScalar::TinyUint => range("An unsigned, 8-bit", u8::MIN, u8::MAX),
+            DefaultScalar::ShortUint => range("An unsigned, 16-bit", u16::MIN, u16::MAX),
+            DefaultScalar::LongUint => range("An unsigned, 64-bit", u64::MIN, u64::MAX),
+            DefaultScalar::BigUint => range("An unsigned, 128-bit", u128::MIN, u128::MAX),
+            DefaultScalar::Float => "A signed, 32-bit, fractional number.\nFor more information see [f32 docs](https://doc.rust-lang.org/std/primitive.f32.html).".into(),
+            DefaultScalar::Double => "A signed, 64-bit, fractional number.\nFor more information see [f64 docs](https://doc.rust-lang.org/std/primitive.f64.html).".into(),
+            DefaultScalar::TinyString => "A small string made up of 255 bytes.".into(),
+            DefaultScalar::String => "A string made up of a maximum of 65,535 bytes. This should be sufficient for most use cases.".into(),
+            DefaultScalar::DateTime => "A field used to represent a date and time.".into(),
+            DefaultScalar::Date => "A field used to represent a date.".into(),
+            DefaultScalar::Time => "A field used to represent a time.".into(),
+            DefaultScalar::Boolean => "Used to represent true and false".into(),
+            DefaultScalar::ID => "Used as a unique identifier.".into(),
+        }
+    }
+}
+
+/// Configures the schema returned by [`Document::default`]: which of the [`DefaultScalar`]s it
+/// defines, which root operation types its `schema` definition declares, and whether it declares
+/// the `@auth` directive and the `Role` enum of its values.
+///
+/// # Example
+///
+/// ```
+/// use syntax::document::{DefaultScalar, Document};
+///
+/// let document = Document::builder()
+///     .with_scalars(&[DefaultScalar::Int, DefaultScalar::String])
+///     .with_mutation(None)
+///     .with_subscription(Some("Events"))
+///     .with_auth(false)
+///     .build()
+///     .unwrap();
+/// assert_eq!(document.definitions.len(), 3);
+/// ```
+///
+/// [`Document::default`]: struct.Document.html#impl-Default
+/// [`DefaultScalar`]: enum.DefaultScalar.html
+#[derive(Debug, Clone)]
+pub struct DocumentBuilder {
+    scalars: Vec<DefaultScalar>,
+    query: Option<String>,
+    mutation: Option<String>,
+    subscription: Option<String>,
+    auth: bool,
+}
+
+impl Default for DocumentBuilder {
+    fn default() -> Self {
+        DocumentBuilder {
+            scalars: DefaultScalar::ALL.to_vec(),
+            query: Some("Query".into()),
+            mutation: Some("Mutation".into()),
+            subscription: None,
+            auth: true,
+        }
+    }
+}
+
+impl DocumentBuilder {
+    /// Starts from the default schema: every scalar, `Query` and `Mutation` root types, `@auth`
+    /// and `Role`.
+    pub fn new() -> DocumentBuilder {
+        DocumentBuilder::default()
+    }
+
+    /// Replaces the scalars to define.
+    pub fn with_scalars(&mut self, scalars: &[DefaultScalar]) -> &mut Self {
+        self.scalars = scalars.to_vec();
+        self
+    }
+
+    /// Leaves a scalar out of the schema.
+    pub fn without_scalar(&mut self, scalar: DefaultScalar) -> &mut Self {
+        self.scalars.retain(|defined| *defined != scalar);
+        self
+    }
+
+    /// Sets the name of the root query type, or leaves it out.
+    pub fn with_query(&mut self, name: Option<&str>) -> &mut Self {
+        self.query = name.map(String::from);
+        self
+    }
+
+    /// Sets the name of the root mutation type, or leaves it out.
+    pub fn with_mutation(&mut self, name: Option<&str>) -> &mut Self {
+        self.mutation = name.map(String::from);
+        self
+    }
+
+    /// Sets the name of the root subscription type, or leaves it out.
+    pub fn with_subscription(&mut self, name: Option<&str>) -> &mut Self {
+        self.subscription = name.map(String::from);
+        self
+    }
+
+    /// Declares the `@auth` directive, which restricts fields and types to the clients holding a
+    /// role, and the `Role` enum of the roles, `ADMIN` and `USER`, or leaves them out.
+    pub fn with_auth(&mut self, auth: bool) -> &mut Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Builds the document. The `schema` definition is omitted when no root type is set.
+    ///
+    /// Fails if a root type name is not a valid GraphQL name.
+    pub fn build(&self) -> ParseResult<Document> {
+        let mut sdl = String::new();
+        for scalar in &self.scalars {
+            sdl.push_str(&format!(
+                "\"\"\"{}\n{}\n\"\"\"\nscalar {}\n\n",
+                scalar.name(),
+                scalar.description(),
+                scalar.name()
+            ));
+        }
+        let roots: Vec<String> = [
+            ("query", &self.query),
+            ("mutation", &self.mutation),
+            ("subscription", &self.subscription),
+        ]
+        .iter()
+        .filter_map(|(operation, name)| {
+            name.as_ref()
+                .map(|name| format!("  {}: {}\n", operation, name))
+        })
+        .collect();
+        if !roots.is_empty() {
+            sdl.push_str("\"\"\"Schema\nThe root of any interaction with the database.\n\"\"\"\n");
+            sdl.push_str(&format!("schema {{\n{}}}\n", roots.concat()));
+        }
+        if self.auth {
+            sdl.push_str(AUTH);
+        }
+        if sdl.is_empty() {
+            return Ok(Document::new(Vec::new()));
+        }
+        let document = crate::parse(&sdl);
+        debug!("Built default schema: {:?}", document);
+        document
+    }
+}
+
+impl Document {
+    /// Starts configuring a default schema. See [`DocumentBuilder`].
+    ///
+    /// [`DocumentBuilder`]: struct.DocumentBuilder.html
+    pub fn builder() -> DocumentBuilder {
+        DocumentBuilder::new()
+    }
+
+    /// Builds the default schema, returning an error instead of panicking if it is invalid.
+    pub fn try_default() -> ParseResult<Document> {
+        DocumentBuilder::new().build()
+    }
+}
+
+impl Default for Document {
+    /// The default schema: every [`DefaultScalar`], a `schema` definition with `Query` and
+    /// `Mutation` root types, the `@auth` directive and the `Role` enum. Use [`Document::builder`]
+    /// to customize it.
+    ///
+    /// [`DefaultScalar`]: enum.DefaultScalar.html
+    /// [`Document::builder`]: struct.Document.html#method.builder
+    fn default() -> Self {
+        Document::try_default().expect("Default schema is invalid")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_default_schema() {
+        let document = Document::try_default().unwrap();
+        assert_eq!(document.definitions.len(), DefaultScalar::ALL.len() + 3);
+        assert_eq!(document, Document::default());
+        let schema = crate::schema::Schema::new(&document);
+        assert!(schema.get_type("Double").is_some());
+        assert!(schema.get_type("Role").is_some());
+        assert!(schema.get_directive("auth").is_some());
+        assert_eq!(schema.query_type(), Some("Query"));
+        assert_eq!(schema.mutation_type(), Some("Mutation"));
+    }
+
+    #[test]
+    fn it_customizes_the_default_schema() {
+        let document = Document::builder()
+            .without_scalar(DefaultScalar::BigInt)
+            .with_query(Some("Root"))
+            .with_mutation(None)
+            .build()
+            .unwrap();
+        let schema = crate::schema::Schema::with_builtins(&document, false);
+        assert!(schema.get_type("BigInt").is_none());
+        assert!(schema.get_type("BigUint").is_some());
+        assert_eq!(schema.query_type(), Some("Root"));
+        assert_eq!(schema.mutation_type(), None);
+
+        let document = Document::builder()
+            .with_scalars(&[])
+            .with_query(None)
+            .with_mutation(None)
+            .with_auth(false)
+            .build()
+            .unwrap();
+        assert!(document.definitions.is_empty());
+
+        assert!(Document::builder()
+            .with_query(Some("Not a name"))
+            .build()
+            .is_err());
+    }
+}
@@ -7,6 +7,14 @@
 //! an empty type, an unknown token, or an empty body are included in a
 //! ParseError
 //!
+//! ConversionError represents an issue turning another representation of a schema (e.g. an
+//! introspection result) into a Document.
+//!
+//! ConfigError represents an issue finding or reading a GraphQL config file.
+//!
+//! CoercionError represents a value sent for a variable of an operation that its type does not
+//! accept.
+//!
 //! ValidationError is a logical issue with the Document. This includes issues
 //! like an extension including duplicate field, redefining a type, etc.
 //!
@@ -87,11 +95,11 @@ pub enum LexError {
     EOF,
 }
 
-const EOF_MESSAGE: &'static str = "Parse Error: Encountered End of File unexpectedly";
-const UNMATCHED_QUOTE_MESSAGE: &'static str = "Parse Error: Unmatched quote found on";
-const UNKNOWN_CHARACTER_MESSAGE: &'static str = "Parse Error: Unknown character found on";
-const UNEXPECTED_CHARACTER_MESSAGE: &'static str = "Parse Error: Unexpected character found on";
-const UNABLE_TO_CONVERT_MESSAGE: &'static str = "Parse Error: Unable to convert value at";
+const EOF_MESSAGE: &str = "Parse Error: Encountered End of File unexpectedly";
+const UNMATCHED_QUOTE_MESSAGE: &str = "Parse Error: Unmatched quote found on";
+const UNKNOWN_CHARACTER_MESSAGE: &str = "Parse Error: Unknown character found on";
+const UNEXPECTED_CHARACTER_MESSAGE: &str = "Parse Error: Unexpected character found on";
+const UNABLE_TO_CONVERT_MESSAGE: &str = "Parse Error: Unable to convert value at";
 
 impl LexError {
     fn get_message(&self) -> String {
@@ -120,12 +128,8 @@ impl fmt::Display for LexError {
 }
 
 /// A collection of syntactically bad states that a parser can get into.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ParseError {
-    /// Used when the parser is in a bad state and the issue cannot be concretly
-    /// determined from the context.
-    BadValue,
-
     /// The GraphQL String was empty
     DocumentEmpty,
 
@@ -169,25 +173,93 @@ pub enum ParseError {
     /// Used to convey to the developer or user that this functionality
     /// is planned, but not currently implemented.
     NotImplemented,
+
+    /// Reading the GraphQL string from a reader failed
+    Io(String),
+
+    /// Lists, input objects, list types or selection sets were nested deeper than the parser
+    /// allows
+    TooDeep(Location),
+
+    /// The document has more tokens than the `max_tokens` parse option allows
+    TooManyTokens(Location),
+
+    /// A description was written before an operation or a fragment, which cannot have one
+    DescriptionNotAllowed(Location),
+
+    /// An enum value is named `true`, `false` or `null`, or its name begins with `__`, which is
+    /// reserved for introspection
+    InvalidEnumValue {
+        /// The name of the enum value
+        name: String,
+        /// The [`location`] of the enum value
+        /// [`location`]: ../token/struct.Location.html
+        location: Location,
+    },
+
+    /// A definition begins with a name that is not one of the keywords of a definition, such as
+    /// `type` or `query`
+    UnknownDefinitionKeyword {
+        /// The name the definition begins with
+        keyword: String,
+        /// The [`location`] of the name
+        /// [`location`]: ../token/struct.Location.html
+        location: Location,
+    },
+
+    /// A type is named `true`, `false` or `null`, which are reserved for values
+    ReservedName {
+        /// The name of the type
+        name: String,
+        /// The [`location`] of the name
+        /// [`location`]: ../token/struct.Location.html
+        location: Location,
+    },
+
+    /// An operation is not a `query`, a `mutation` or a `subscription`
+    UnsupportedOperation {
+        /// The keyword the operation begins with
+        operation: String,
+        /// The [`location`] of the keyword
+        /// [`location`]: ../token/struct.Location.html
+        location: Location,
+    },
+
+    /// Tokens were left after the end of a document or of a standalone value
+    TrailingTokens {
+        /// The first of the tokens left
+        received: String,
+        /// How many tokens were left, up to the end or to a character that cannot be lexed
+        count: usize,
+        /// The [`location`] of the first token left
+        /// [`location`]: ../token/struct.Location.html
+        location: Location,
+    },
 }
 
-const NOT_IMPLEMENTED_MESSAGE: &'static str =
+const NOT_IMPLEMENTED_MESSAGE: &str =
     "Parse Error: One or more operations/types specified is not implemented";
-const BAD_VALUE_MESSAGE: &'static str =
-    "Parse Error: Bad value received. Please check input and try again.";
-const DOCUMENT_EMPTY_MESSAGE: &'static str =
-    "Parse Error: Document is empty. Cannot parse an empty value";
-const ARGUMENT_EMPTY_MESSAGE: &'static str = "Parse Error: Argument empty on";
-const OBJECT_EMPTY_MESSAGE: &'static str = "Parse Error: Object empty on";
-
-const EXPECTED_TOKEN_MESSAGE: &'static str = "Parse Error: Unexpected token on";
-const EXPECTED_KEYWORD_MESSAGE: &'static str = "Parse Error: Unexpected keyword on";
+const DOCUMENT_EMPTY_MESSAGE: &str = "Parse Error: Document is empty. Cannot parse an empty value";
+const ARGUMENT_EMPTY_MESSAGE: &str = "Parse Error: Argument empty on";
+const OBJECT_EMPTY_MESSAGE: &str = "Parse Error: Object empty on";
+const IO_MESSAGE: &str = "Parse Error: Unable to read input:";
+const TOO_DEEP_MESSAGE: &str = "Parse Error: Nesting too deep on";
+const TOO_MANY_TOKENS_MESSAGE: &str = "Parse Error: Too many tokens, parsing aborted on";
+const DESCRIPTION_NOT_ALLOWED_MESSAGE: &str =
+    "Parse Error: Operations and fragments cannot have a description, found one on";
+const INVALID_ENUM_VALUE_MESSAGE: &str = "Parse Error: Invalid enum value on";
+const UNKNOWN_DEFINITION_KEYWORD_MESSAGE: &str = "Parse Error: Unknown definition keyword on";
+const RESERVED_NAME_MESSAGE: &str = "Parse Error: Reserved name on";
+const UNSUPPORTED_OPERATION_MESSAGE: &str = "Parse Error: Unsupported operation on";
+const TRAILING_TOKENS_MESSAGE: &str = "Parse Error: Tokens left after the end on";
+
+const EXPECTED_TOKEN_MESSAGE: &str = "Parse Error: Unexpected token on";
+const EXPECTED_KEYWORD_MESSAGE: &str = "Parse Error: Unexpected keyword on";
 
 impl ParseError {
     fn get_message(&self) -> String {
         match self {
             ParseError::NotImplemented => String::from(NOT_IMPLEMENTED_MESSAGE),
-            ParseError::BadValue => String::from(BAD_VALUE_MESSAGE),
             ParseError::DocumentEmpty => String::from(DOCUMENT_EMPTY_MESSAGE),
             ParseError::ArgumentEmpty(location) => {
                 format_location_message(ARGUMENT_EMPTY_MESSAGE, location)
@@ -196,6 +268,64 @@ impl ParseError {
                 format_location_message(OBJECT_EMPTY_MESSAGE, location)
             }
             ParseError::EOF => String::from(EOF_MESSAGE),
+            ParseError::Io(message) => format!("{} {}", IO_MESSAGE, message),
+            ParseError::TooDeep(location) => format_location_message(TOO_DEEP_MESSAGE, location),
+            ParseError::TooManyTokens(location) => {
+                format_location_message(TOO_MANY_TOKENS_MESSAGE, location)
+            }
+            ParseError::DescriptionNotAllowed(location) => {
+                format_location_message(DESCRIPTION_NOT_ALLOWED_MESSAGE, location)
+            }
+            ParseError::InvalidEnumValue { name, location } => {
+                let reason = if name.starts_with("__") {
+                    "names beginning with \"__\" are reserved for introspection"
+                } else {
+                    "true, false and null are not enum values"
+                };
+                format!(
+                    "{}: \"{}\", {}",
+                    format_location_message(INVALID_ENUM_VALUE_MESSAGE, location),
+                    name,
+                    reason
+                )
+            }
+            ParseError::UnknownDefinitionKeyword { keyword, location } => format!(
+                "{}: \"{}\"",
+                format_location_message(UNKNOWN_DEFINITION_KEYWORD_MESSAGE, location),
+                keyword
+            ),
+            ParseError::ReservedName { name, location } => format!(
+                "{}: \"{}\", true, false and null cannot name a type",
+                format_location_message(RESERVED_NAME_MESSAGE, location),
+                name
+            ),
+            ParseError::UnsupportedOperation {
+                operation,
+                location,
+            } => format!(
+                "{}: \"{}\", expected one of query, mutation or subscription",
+                format_location_message(UNSUPPORTED_OPERATION_MESSAGE, location),
+                operation
+            ),
+            ParseError::TrailingTokens {
+                received,
+                count: 1,
+                location,
+            } => format!(
+                "{}: Found \"{}\"",
+                format_location_message(TRAILING_TOKENS_MESSAGE, location),
+                received
+            ),
+            ParseError::TrailingTokens {
+                received,
+                count,
+                location,
+            } => format!(
+                "{}: Found \"{}\", the first of {} tokens",
+                format_location_message(TRAILING_TOKENS_MESSAGE, location),
+                received,
+                count
+            ),
             ParseError::LexError(lex_error) => lex_error.to_string(),
             ParseError::UnexpectedToken {
                 expected,
@@ -245,6 +375,8 @@ pub struct ValidationError {
     /// A description of the logical error encountered while validating
     /// the GraphQL Document.
     pub message: String,
+    /// Where the issue is in the source, when known.
+    pub locations: Vec<Location>,
 }
 
 impl ValidationError {
@@ -254,14 +386,110 @@ impl ValidationError {
     pub fn new(message: &str) -> ValidationError {
         ValidationError {
             message: String::from(message),
+            locations: Vec::new(),
+        }
+    }
+
+    /// Adds a location of the issue in the source.
+    pub fn at(mut self, location: Location) -> ValidationError {
+        self.locations.push(location);
+        self
+    }
+}
+
+/// An issue converting another representation of a schema, such as an introspection result,
+/// into a GraphQL Document.
+#[derive(Debug, PartialEq)]
+pub struct ConversionError {
+    /// A description of the part of the input that could not be converted.
+    pub message: String,
+}
+
+impl ConversionError {
+    /// Returns a ConversionError with a message of the issue.
+    pub fn new(message: &str) -> ConversionError {
+        ConversionError {
+            message: String::from(message),
         }
     }
 }
 
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conversion Error: {}", self.message)
+    }
+}
+
+/// An issue parsing a schema coordinate.
+#[derive(Debug, PartialEq)]
+pub struct CoordinateError {
+    /// A description of the issue, quoting the coordinate.
+    pub message: String,
+}
+
+impl CoordinateError {
+    /// Returns a CoordinateError with a message of the issue.
+    pub fn new(message: &str) -> CoordinateError {
+        CoordinateError {
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for CoordinateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Coordinate Error: {}", self.message)
+    }
+}
+
+/// An issue finding, reading or interpreting a GraphQL config file, or the files it refers to.
+#[derive(Debug, PartialEq)]
+pub struct ConfigError {
+    /// A description of the issue, naming the file involved.
+    pub message: String,
+}
+
+impl ConfigError {
+    /// Returns a ConfigError with a message of the issue.
+    pub fn new(message: &str) -> ConfigError {
+        ConfigError {
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Config Error: {}", self.message)
+    }
+}
+
+/// An issue coercing the values sent for the variables of an operation to their types.
+#[derive(Debug, PartialEq)]
+pub struct CoercionError {
+    /// A description of the issue, naming the variable.
+    pub message: String,
+}
+
+impl CoercionError {
+    /// Returns a CoercionError with a message of the issue.
+    pub fn new(message: &str) -> CoercionError {
+        CoercionError {
+            message: String::from(message),
+        }
+    }
+}
+
+impl fmt::Display for CoercionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Coercion Error: {}", self.message)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::token::Token;
+    use crate::token::{Token, TokenKind};
 
     #[test]
     fn creates_message_for_not_implemented() {
@@ -270,9 +498,41 @@ mod tests {
     }
 
     #[test]
-    fn creates_message_for_a_bad_value() {
-        let error = ParseError::BadValue;
-        assert_eq!(error.to_string(), BAD_VALUE_MESSAGE);
+    fn creates_messages_naming_the_offending_text() {
+        let location = Location::new(42, 4, 2);
+        let error = ParseError::UnknownDefinitionKeyword {
+            keyword: "tpye".into(),
+            location,
+        };
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{} line 4, column 2: \"tpye\"",
+                UNKNOWN_DEFINITION_KEYWORD_MESSAGE
+            )
+        );
+        let error = ParseError::ReservedName {
+            name: "null".into(),
+            location,
+        };
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{} line 4, column 2: \"null\", true, false and null cannot name a type",
+                RESERVED_NAME_MESSAGE
+            )
+        );
+        let error = ParseError::UnsupportedOperation {
+            operation: "update".into(),
+            location,
+        };
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{} line 4, column 2: \"update\", expected one of query, mutation or subscription",
+                UNSUPPORTED_OPERATION_MESSAGE
+            )
+        );
     }
 
     #[test]
@@ -316,8 +576,8 @@ mod tests {
     #[test]
     fn creates_unexpected_token_message() {
         let location = Location::new(42, 4, 2);
-        let expected = Token::Name(Location::new(42, 4, 2), "val");
-        let received = Token::Str(location, "Content of value");
+        let expected = Token::new(TokenKind::Name, Location::new(42, 4, 2), "val");
+        let received = Token::new(TokenKind::Str, location, "Content of value");
         let error = ParseError::UnexpectedToken {
             expected: expected.to_string(),
             received: received.to_string(),
@@ -327,11 +587,25 @@ mod tests {
             error.to_string(),
             format!(
                 "{} line {}, column {}: Expected \"{}\", but found \"{}\"",
-                EXPECTED_TOKEN_MESSAGE,
-                location.line,
-                location.column,
-                expected.to_string(),
-                received.to_string()
+                EXPECTED_TOKEN_MESSAGE, location.line, location.column, expected, received
+            )
+        );
+    }
+
+    #[test]
+    fn creates_trailing_tokens_message() {
+        let location = Location::new(18, 1, 19);
+        let received = Token::new(TokenKind::CloseBrace, location, "}");
+        let error = ParseError::TrailingTokens {
+            received: received.to_string(),
+            count: 3,
+            location,
+        };
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{} line 1, column 19: Found \"{}\", the first of 3 tokens",
+                TRAILING_TOKENS_MESSAGE, received
             )
         );
     }
@@ -339,7 +613,7 @@ mod tests {
     #[test]
     fn creates_unexpected_keyword_message() {
         let location = Location::new(42, 4, 2);
-        let received = Token::Name(location, "extends");
+        let received = Token::new(TokenKind::Name, location, "extends");
         let error = ParseError::UnexpectedKeyword {
             expected: String::from("implements"),
             received: String::from("extends"),
@@ -0,0 +1,246 @@
+//! Helpers for serving a [`Document`] as an Apollo Federation subgraph.
+//!
+//! Federation marks entities with `@key`, references fields owned by other subgraphs with
+//! `@external`, `@requires` and `@provides`, and extends types defined elsewhere with
+//! `extend type`. A gateway discovers the subgraph through the `_service { sdl }` field and
+//! resolves entities through `_entities(representations: [_Any!]!)`.
+//!
+//! [`subgraph_document`] adds those definitions to a user schema, and [`service_sdl`] prints the
+//! SDL a subgraph returns from `_service`.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::federation::{entities, service_sdl, subgraph_document};
+//! use syntax::schema::Schema;
+//!
+//! let document = syntax::parse(r#"
+//! type User @key(fields: "id") { id: ID! name: String }
+//! extend type Query { me: User }
+//! "#).unwrap();
+//! assert_eq!(entities(&document)[0].keys, vec!["id"]);
+//!
+//! let subgraph = subgraph_document(&document).unwrap();
+//! assert!(Schema::new(&subgraph).field("Query", "_entities").is_some());
+//! assert_eq!(service_sdl(&subgraph), service_sdl(&document));
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+//! [`subgraph_document`]: fn.subgraph_document.html
+//! [`service_sdl`]: fn.service_sdl.html
+
+use crate::document::Document;
+use crate::error::ParseResult;
+use crate::nodes::*;
+use crate::printer::{print, print_definitions};
+
+/// The scalars, types and directives every Federation subgraph defines.
+pub const FEDERATION_DEFINITIONS: &str = r#"scalar _Any
+
+scalar _FieldSet
+
+type _Service {
+  sdl: String
+}
+
+directive @external on FIELD_DEFINITION
+
+directive @requires(fields: _FieldSet!) on FIELD_DEFINITION
+
+directive @provides(fields: _FieldSet!) on FIELD_DEFINITION
+
+directive @key(fields: _FieldSet!) repeatable on OBJECT | INTERFACE
+
+directive @extends on OBJECT | INTERFACE
+"#;
+
+const FEDERATION_TYPES: [&str; 4] = ["_Any", "_FieldSet", "_Service", "_Entity"];
+const FEDERATION_DIRECTIVES: [&str; 5] = ["external", "requires", "provides", "key", "extends"];
+const FEDERATION_FIELDS: [&str; 2] = ["_service", "_entities"];
+
+/// An object type marked with at least one `@key` directive.
+#[derive(Debug, PartialEq)]
+pub struct Entity<'a> {
+    /// The name of the type
+    pub name: &'a str,
+    /// The `fields` argument of every `@key`, in the order they were declared
+    pub keys: Vec<&'a str>,
+}
+
+fn keys_of(directives: &Option<Directives>) -> impl Iterator<Item = &str> {
+    directives
+        .iter()
+        .flatten()
+        .filter(|directive| directive.name.value == "key")
+        .filter_map(|directive| {
+            directive
+                .arguments
+                .iter()
+                .flatten()
+                .find(|argument| argument.name.value == "fields")
+                .and_then(|argument| match &argument.value {
+                    ValueNode::Str(fields) => Some(fields.value.as_str()),
+                    _ => None,
+                })
+        })
+}
+
+/// Lists the entities of a document. Keys declared on extensions are merged with the keys of the
+/// definition.
+pub fn entities(document: &Document) -> Vec<Entity<'_>> {
+    let mut entities: Vec<Entity<'_>> = Vec::new();
+    for definition in &document.definitions {
+        let (name, directives) = match definition {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+                TypeDefinitionNode::Object(object),
+            )) => (object.name.value.as_str(), &object.directives),
+            DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                (extension.name.value.as_str(), &extension.directives)
+            }
+            _ => continue,
+        };
+        let keys: Vec<&str> = keys_of(directives).collect();
+        if keys.is_empty() {
+            continue;
+        }
+        match entities.iter_mut().find(|entity| entity.name == name) {
+            Some(entity) => entity.keys.extend(keys),
+            None => entities.push(Entity { name, keys }),
+        }
+    }
+    entities
+}
+
+fn is_federation_definition(definition: &DefinitionNode) -> bool {
+    match definition {
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+            FEDERATION_TYPES.contains(&type_definition.name())
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+            FEDERATION_DIRECTIVES.contains(&directive.name.value.as_str())
+        }
+        DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+            match &extension.fields {
+                Some(fields) => fields
+                    .iter()
+                    .all(|field| FEDERATION_FIELDS.contains(&field.name.value.as_str())),
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Prints the SDL a subgraph returns from `_service { sdl }`: the type system definitions of the
+/// document, leaving out the definitions added by [`subgraph_document`].
+///
+/// [`subgraph_document`]: fn.subgraph_document.html
+pub fn service_sdl(document: &Document) -> String {
+    print_definitions(document.definitions.iter().filter(|definition| {
+        !matches!(definition, DefinitionNode::Executable(_))
+            && !is_federation_definition(definition)
+    }))
+}
+
+/// Builds the schema a subgraph serves: the document, the [`FEDERATION_DEFINITIONS`], the
+/// `_Entity` union of all [`entities`] and the `_service` and `_entities` fields on `Query`.
+///
+/// `_entities` and `_Entity` are only added when the document declares at least one entity.
+///
+/// [`FEDERATION_DEFINITIONS`]: constant.FEDERATION_DEFINITIONS.html
+/// [`entities`]: fn.entities.html
+pub fn subgraph_document(document: &Document) -> ParseResult<Document> {
+    let entities = entities(document);
+    let mut sdl = print(document);
+    sdl.push('\n');
+    sdl.push_str(FEDERATION_DEFINITIONS);
+    sdl.push('\n');
+    if entities.is_empty() {
+        sdl.push_str("extend type Query {\n  _service: _Service!\n}\n");
+    } else {
+        let members: Vec<&str> = entities.iter().map(|entity| entity.name).collect();
+        sdl.push_str(&format!("union _Entity = {}\n\n", members.join(" | ")));
+        sdl.push_str(
+            "extend type Query {\n  _entities(representations: [_Any!]!): [_Entity]!\n  _service: _Service!\n}\n",
+        );
+    }
+    crate::parse(&sdl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::schema::Schema;
+
+    const SUBGRAPH: &str = r#"type Product @key(fields: "upc") @key(fields: "sku") {
+  upc: String!
+  sku: String!
+  name: String
+}
+
+extend type User @key(fields: "id") {
+  id: ID! @external
+  reviews: [Review] @requires(fields: "email")
+  email: String @external
+}
+
+type Review {
+  body: String
+  author: User @provides(fields: "username")
+}
+
+extend type Query {
+  topProducts(first: Int = 5): [Product]
+}
+"#;
+
+    #[test]
+    fn it_lists_entities() {
+        let document = parse(SUBGRAPH).unwrap();
+        assert_eq!(
+            entities(&document),
+            vec![
+                Entity {
+                    name: "Product",
+                    keys: vec!["upc", "sku"]
+                },
+                Entity {
+                    name: "User",
+                    keys: vec!["id"]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_adds_the_federation_definitions() {
+        let document = parse(SUBGRAPH).unwrap();
+        let subgraph = subgraph_document(&document).unwrap();
+        let schema = Schema::new(&subgraph);
+        assert!(schema.get_directive("key").unwrap().repeatable);
+        assert_eq!(schema.possible_types("_Entity"), vec!["Product", "User"]);
+        let entities_field = schema.field("Query", "_entities").unwrap();
+        assert_eq!(entities_field.arguments.as_ref().unwrap().len(), 1);
+        assert!(schema.field("Query", "_service").is_some());
+        assert!(schema.field("Query", "topProducts").is_some());
+    }
+
+    #[test]
+    fn it_prints_the_service_sdl() {
+        let document = parse(SUBGRAPH).unwrap();
+        assert_eq!(service_sdl(&document), SUBGRAPH);
+        let subgraph = subgraph_document(&document).unwrap();
+        assert_eq!(service_sdl(&subgraph), SUBGRAPH);
+    }
+
+    #[test]
+    fn it_skips_entities_when_there_are_none() {
+        let document = parse("type Query { a: Int }").unwrap();
+        let subgraph = subgraph_document(&document).unwrap();
+        let schema = Schema::new(&subgraph);
+        assert!(schema.get_type("_Entity").is_none());
+        assert!(schema.field("Query", "_entities").is_none());
+        assert!(schema.field("Query", "_service").is_some());
+    }
+}
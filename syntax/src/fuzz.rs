@@ -0,0 +1,53 @@
+//! Entry points for fuzzing. They take arbitrary bytes and must never panic.
+
+/// Tokenizes the bytes, if they are valid UTF-8.
+pub fn tokenize(data: &[u8]) {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = crate::lexer::tokenize(input);
+    }
+}
+
+/// Parses the bytes, if they are valid UTF-8, and prints the document back.
+pub fn parse(data: &[u8]) {
+    if let Ok(input) = std::str::from_utf8(data) {
+        if let Ok(document) = crate::parse(input) {
+            let _ = crate::printer::print(&document);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_survives_multibyte_names() {
+        tokenize("{a0$cé\".[:(|}7\\((9.e1$9".as_bytes());
+        parse("type Café { a: Int }".as_bytes());
+    }
+
+    #[test]
+    fn it_rejects_deep_nesting() {
+        let depth = 100_000;
+        let selections = format!("query Q {}{}", "{ a ".repeat(depth), "}".repeat(depth));
+        let lists = format!(
+            "query Q {{ a(b: {}1{}) }}",
+            "[".repeat(depth),
+            "]".repeat(depth)
+        );
+        let types = format!(
+            "type Q {{ a: {}A{} }}",
+            "[".repeat(depth),
+            "]".repeat(depth)
+        );
+        for input in &[selections, lists, types] {
+            match crate::parse(input) {
+                Err(crate::error::ParseError::TooDeep(_)) => {}
+                other => panic!("Expected a nesting error, got {:?}", other),
+            }
+            parse(input.as_bytes());
+        }
+        let nested = format!("query Q {}{}", "{ a ".repeat(100), "}".repeat(100));
+        assert!(crate::parse(&nested).is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+//! Maps Rust types to GraphQL types, to define a schema in Rust.
+//!
+//! [`GraphQLType`] is implemented for the Rust types that have a GraphQL counterpart: integers
+//! up to 32 bits are `Int`, floats are `Float`, `bool` is `Boolean` and `String` is `String`.
+//! Types are non-null, except for `Option<T>`, and `Vec<T>` is a list of `T`.
+//!
+//! The `GraphQLType` derive macro of the `syntax-macros` crate implements it for structs with
+//! named fields, which become object types, and for enums of unit variants, which become enum
+//! types. Doc comments become descriptions.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::graphql_type::{sdl, GraphQLType};
+//! use syntax::nodes::*;
+//!
+//! struct Point;
+//!
+//! impl GraphQLType for Point {
+//!     fn type_node() -> TypeNode {
+//!         TypeNode::NonNull(std::sync::Arc::new(TypeNode::Named(NamedTypeNode::from("Point"))))
+//!     }
+//!
+//!     fn definition() -> Option<TypeDefinitionNode> {
+//!         Some(TypeDefinitionNode::Scalar(ScalarTypeDefinitionNode::from("Point")))
+//!     }
+//! }
+//!
+//! assert_eq!(sdl::<Point>(), "scalar Point\n");
+//! assert_eq!(sdl::<Option<Vec<Point>>>(), "");
+//! ```
+//!
+//! [`GraphQLType`]: trait.GraphQLType.html
+
+use crate::nodes::*;
+use std::sync::Arc;
+
+/// A Rust type with a GraphQL counterpart.
+pub trait GraphQLType {
+    /// The type of a field holding a value of this Rust type, e.g. `Int!` for `i32`.
+    fn type_node() -> TypeNode;
+
+    /// The definition of the GraphQL type, for types that are not built in or wrappers of other
+    /// types.
+    fn definition() -> Option<TypeDefinitionNode> {
+        None
+    }
+}
+
+/// Prints the definition of the GraphQL type of `T` as SDL, or nothing if it has none.
+pub fn sdl<T: GraphQLType>() -> String {
+    match T::definition() {
+        Some(definition) => crate::printer::print_definitions(&[DefinitionNode::TypeSystem(
+            TypeSystemDefinitionNode::Type(definition),
+        )]),
+        None => String::new(),
+    }
+}
+
+/// The non-null type named `name`.
+pub fn non_null(name: &str) -> TypeNode {
+    TypeNode::NonNull(Arc::new(TypeNode::Named(NamedTypeNode::from(name))))
+}
+
+macro_rules! builtin {
+    ($name:expr => $($rust:ty),*) => {
+        $(
+            impl GraphQLType for $rust {
+                fn type_node() -> TypeNode {
+                    non_null($name)
+                }
+            }
+        )*
+    };
+}
+
+builtin!("Int" => i8, i16, i32, u8, u16);
+builtin!("Float" => f32, f64);
+builtin!("Boolean" => bool);
+builtin!("String" => String, str);
+
+impl<T: GraphQLType> GraphQLType for Option<T> {
+    fn type_node() -> TypeNode {
+        match T::type_node() {
+            TypeNode::NonNull(inner) => {
+                Arc::try_unwrap(inner).unwrap_or_else(|inner| (*inner).clone())
+            }
+            nullable => nullable,
+        }
+    }
+}
+
+impl<T: GraphQLType> GraphQLType for Vec<T> {
+    fn type_node() -> TypeNode {
+        TypeNode::NonNull(Arc::new(TypeNode::List(ListTypeNode::new(T::type_node()))))
+    }
+}
+
+impl<T: GraphQLType + ?Sized> GraphQLType for Box<T> {
+    fn type_node() -> TypeNode {
+        T::type_node()
+    }
+}
+
+impl<T: GraphQLType + ?Sized> GraphQLType for Arc<T> {
+    fn type_node() -> TypeNode {
+        T::type_node()
+    }
+}
+
+impl<T: GraphQLType + ?Sized> GraphQLType for &T {
+    fn type_node() -> TypeNode {
+        T::type_node()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printer::print_type;
+
+    #[test]
+    fn it_maps_builtin_types() {
+        assert_eq!(print_type(&i32::type_node()), "Int!");
+        assert_eq!(print_type(&f64::type_node()), "Float!");
+        assert_eq!(print_type(&bool::type_node()), "Boolean!");
+        assert_eq!(print_type(&<&str>::type_node()), "String!");
+        assert_eq!(print_type(&Option::<String>::type_node()), "String");
+        assert_eq!(print_type(&Vec::<Option<u8>>::type_node()), "[Int]!");
+        assert_eq!(
+            print_type(&Option::<Vec<Box<String>>>::type_node()),
+            "[String!]"
+        );
+        assert_eq!(sdl::<i32>(), "");
+    }
+}
@@ -0,0 +1,268 @@
+//! Re-parses a document after an edit, without parsing it all again.
+//!
+//! An [`IncrementalDocument`] keeps the source of a document along with the span of every
+//! top-level definition. [`IncrementalDocument::edit`] replaces a range of the source and only
+//! re-parses the definitions whose spans touch the edit, plus the one following them, since an
+//! edit at the end of a definition can change where the next one starts. The other definitions
+//! are kept as they were, and the spans after the edit are shifted.
+//!
+//! When the re-parsed definitions do not line up with the rest of the document, or do not parse,
+//! the whole document is parsed again, so the result and the errors are always those of
+//! [`parse`].
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::incremental::IncrementalDocument;
+//!
+//! let mut document = IncrementalDocument::parse("type Query { me: User }\ntype User { id: ID! }\n")
+//!     .unwrap();
+//! let offset = document.source().find("ID!").unwrap();
+//! let reparsed = document.edit(offset..offset + 3, "String").unwrap();
+//! assert_eq!(reparsed, 1..2);
+//! assert_eq!(document.source(), "type Query { me: User }\ntype User { id: String }\n");
+//! assert_eq!(
+//!     document.document(),
+//!     &syntax::parse("type Query { me: User }\ntype User { id: String }").unwrap()
+//! );
+//! ```
+//!
+//! [`IncrementalDocument`]: struct.IncrementalDocument.html
+//! [`IncrementalDocument::edit`]: struct.IncrementalDocument.html#method.edit
+//! [`parse`]: ../fn.parse.html
+
+use crate::ast::Ast;
+use crate::document::Document;
+use crate::error::{ParseError, ParseResult};
+use crate::intern::{self, Interner};
+use crate::lexer::Lexer;
+use crate::nodes::DefinitionNode;
+use std::ops::Range;
+
+/// A parsed document that can be edited and re-parsed in place.
+#[derive(Debug)]
+pub struct IncrementalDocument {
+    source: String,
+    document: Document,
+    /// The byte range of every definition. They cover the whole source: a definition owns the
+    /// ignored characters that follow it, and the first one those that precede it.
+    spans: Vec<Range<usize>>,
+    interner: Interner,
+}
+
+impl IncrementalDocument {
+    /// Parses a document that will be edited.
+    pub fn parse(source: impl Into<String>) -> ParseResult<IncrementalDocument> {
+        let mut document = IncrementalDocument {
+            source: source.into(),
+            document: Document::new(Vec::new()),
+            spans: Vec::new(),
+            interner: Interner::new(),
+        };
+        document.parse_all()?;
+        Ok(document)
+    }
+
+    /// The current source of the document.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// The document parsed from the source. After an edit that failed to parse, this is the last
+    /// document that parsed.
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+
+    /// The byte range in the source of every definition of the document.
+    pub fn spans(&self) -> &[Range<usize>] {
+        &self.spans
+    }
+
+    /// Replaces the `range` of the source with `text`, and re-parses the definitions it touches.
+    ///
+    /// Returns the indices of the definitions of the new document that were parsed again. On
+    /// error, the source is still edited and the next edit parses the whole document.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of the source or not on character boundaries.
+    pub fn edit(&mut self, range: Range<usize>, text: &str) -> ParseResult<Range<usize>> {
+        self.source.replace_range(range.clone(), text);
+        if self.spans.is_empty() {
+            return self.parse_all();
+        }
+
+        let last_index = self.spans.len() - 1;
+        let first = self
+            .spans
+            .iter()
+            .position(|span| span.end > range.start)
+            .unwrap_or(last_index);
+        let touched = self
+            .spans
+            .iter()
+            .rposition(|span| span.start <= range.end)
+            .map_or(first, |last| last.max(first));
+        let last = (touched + 1).min(last_index);
+        let shift = |offset: usize| offset + text.len() - range.len();
+        let start = self.spans[first].start;
+        let end = shift(self.spans[last].end);
+
+        let reparsed = match self.parse_region(start..end) {
+            Ok(reparsed) => reparsed,
+            Err(_) => return self.parse_all(),
+        };
+        // The region ends before the definition that followed the edit, which must be parsed as
+        // it was. Otherwise the edit changed more than the region, e.g. by opening a comment.
+        let aligned = last == touched
+            || reparsed.last().is_some_and(|(definition, span)| {
+                definition == &self.document.definitions[last]
+                    && span.len() == self.spans[last].len()
+            });
+        if !aligned {
+            return self.parse_all();
+        }
+
+        let count = reparsed.len();
+        let (definitions, spans): (Vec<_>, Vec<_>) = reparsed.into_iter().unzip();
+        self.document.definitions.splice(first..=last, definitions);
+        let following: Vec<Range<usize>> = self.spans[last + 1..]
+            .iter()
+            .map(|span| shift(span.start)..shift(span.end))
+            .collect();
+        self.spans.truncate(first);
+        self.spans.extend(spans);
+        self.spans.extend(following);
+        Ok(first..first + count)
+    }
+
+    fn parse_all(&mut self) -> ParseResult<Range<usize>> {
+        self.spans.clear();
+        let parsed = self.parse_region(0..self.source.len())?;
+        let (definitions, spans): (Vec<_>, Vec<_>) = parsed.into_iter().unzip();
+        self.document = Document::new(definitions);
+        self.spans = spans;
+        Ok(0..self.spans.len())
+    }
+
+    /// Parses the definitions of a region of the source, which must not be empty.
+    fn parse_region(
+        &mut self,
+        region: Range<usize>,
+    ) -> ParseResult<Vec<(DefinitionNode, Range<usize>)>> {
+        let source = &self.source[region.clone()];
+        intern::with_interner_in(&mut self.interner, || {
+            let mut ast = Ast::from_lexer(Lexer::new(source));
+            ast.parse_start()?;
+            let mut parsed = Vec::new();
+            let mut start = 0;
+            while let Some(definition) = ast.parse_next_definition()? {
+                let end = ast.peek_position().unwrap_or(source.len());
+                parsed.push((definition, region.start + start..region.start + end));
+                start = end;
+            }
+            if parsed.is_empty() {
+                return Err(ParseError::DocumentEmpty);
+            }
+            Ok(parsed)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "\
+# The schema
+type Query { me: User }
+
+type User {
+  id: ID!
+  name: String
+}
+
+query Me { me { id } }
+";
+
+    /// Applies the edit and checks the result against parsing the new source from scratch.
+    fn edit(document: &mut IncrementalDocument, range: Range<usize>, text: &str) -> Range<usize> {
+        let reparsed = document.edit(range, text).unwrap();
+        assert_eq!(
+            document.document(),
+            &crate::parse(document.source()).unwrap()
+        );
+        let spans = document.spans();
+        assert_eq!(spans.first().unwrap().start, 0);
+        assert_eq!(spans.last().unwrap().end, document.source().len());
+        assert!(spans.windows(2).all(|pair| pair[0].end == pair[1].start));
+        reparsed
+    }
+
+    #[test]
+    fn it_tracks_spans() {
+        let document = IncrementalDocument::parse(SOURCE).unwrap();
+        let spans = document.spans();
+        assert_eq!(spans.len(), 3);
+        assert!(SOURCE[spans[1].clone()].starts_with("type User"));
+        assert!(SOURCE[spans[2].clone()].starts_with("query Me"));
+        assert_eq!(spans[2].end, SOURCE.len());
+    }
+
+    #[test]
+    fn it_reparses_the_edited_definition() {
+        let mut document = IncrementalDocument::parse(SOURCE).unwrap();
+        let offset = SOURCE.find("name").unwrap();
+        assert_eq!(edit(&mut document, offset..offset + 4, "fullName"), 1..3);
+        let offset = document.source().find("Query").unwrap();
+        assert_eq!(edit(&mut document, offset..offset + 5, "Root"), 0..2);
+        let offset = document.source().find("{ id }").unwrap();
+        assert_eq!(edit(&mut document, offset + 1..offset + 1, " name"), 2..3);
+    }
+
+    #[test]
+    fn it_adds_and_removes_definitions() {
+        let mut document = IncrementalDocument::parse(SOURCE).unwrap();
+        let offset = SOURCE.find("type User").unwrap();
+        assert_eq!(
+            edit(&mut document, offset..offset, "scalar Time\nscalar Date\n"),
+            1..5
+        );
+        assert_eq!(document.document().definitions.len(), 5);
+        let span = document.spans()[2].clone();
+        edit(&mut document, span, "");
+        assert_eq!(document.document().definitions.len(), 4);
+        let end = document.source().len();
+        edit(&mut document, end..end, "fragment F on User { id }");
+        assert_eq!(document.document().definitions.len(), 5);
+    }
+
+    #[test]
+    fn it_reparses_what_the_edit_runs_into() {
+        let mut document = IncrementalDocument::parse("scalar A scalar B scalar C").unwrap();
+        // The comment swallows the definitions after it on the same line.
+        edit(&mut document, 8..8, " #");
+        assert_eq!(document.document().definitions.len(), 1);
+        let offset = document.source().find('#').unwrap();
+        edit(&mut document, offset..offset + 1, "\n");
+        assert_eq!(document.document().definitions.len(), 3);
+        // An edit across definitions.
+        let offset = document.source().find("A").unwrap();
+        edit(&mut document, offset..offset + 12, "D scalar E\n");
+        assert_eq!(document.document().definitions.len(), 3);
+    }
+
+    #[test]
+    fn it_recovers_from_errors() {
+        let mut document = IncrementalDocument::parse(SOURCE).unwrap();
+        let offset = SOURCE.find("name: String").unwrap();
+        assert_eq!(
+            document.edit(offset..offset + 5, "name("),
+            crate::parse(document.source()).map(|_| 0..0)
+        );
+        assert!(document.edit(offset..offset + 5, "name:").is_ok());
+        assert_eq!(document.document(), &crate::parse(SOURCE).unwrap());
+        assert!(document.edit(0..document.source().len(), "").is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Interned names.
+//!
+//! Large schemas repeat the same type and field names (`String`, `ID`, `id`, `name`, ...)
+//! thousands of times. While a document is parsed, every name goes through an [`Interner`], so
+//! all the occurrences of a name share a single allocation through a [`Symbol`].
+//!
+//! A [`Symbol`] dereferences to `str` and compares equal to string slices, so it can be used
+//! mostly like the `String` it replaces.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::intern::Interner;
+//!
+//! let mut interner = Interner::new();
+//! let first = interner.intern("User");
+//! let second = interner.intern("User");
+//! assert!(first.ptr_eq(&second));
+//! assert_eq!(first, "User");
+//! assert_eq!(interner.len(), 1);
+//! ```
+//!
+//! [`Interner`]: struct.Interner.html
+//! [`Symbol`]: struct.Symbol.html
+
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// A cheaply cloneable, immutable string shared by every occurrence of the same name.
+#[derive(Clone, PartialOrd, Ord)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Creates a symbol that is not shared with any other.
+    pub fn new(value: &str) -> Symbol {
+        Symbol(Arc::from(value))
+    }
+
+    /// The name as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns true if both symbols share the same allocation, i.e. they come from the same
+    /// interner.
+    pub fn ptr_eq(&self, other: &Symbol) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Symbol {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Symbol {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        self.ptr_eq(other) || self.0 == other.0
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialEq<str> for Symbol {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Symbol {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Symbol {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Symbol> for str {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Symbol> for &str {
+    fn eq(&self, other: &Symbol) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Symbol> for String {
+    fn eq(&self, other: &Symbol) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Symbol {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(value: &str) -> Symbol {
+        Symbol::new(value)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(value: String) -> Symbol {
+        Symbol(Arc::from(value))
+    }
+}
+
+/// Hands out one [`Symbol`] per distinct name.
+///
+/// [`Symbol`]: struct.Symbol.html
+#[derive(Debug, Default)]
+pub struct Interner {
+    symbols: HashSet<Symbol>,
+}
+
+impl Interner {
+    /// Creates an empty interner.
+    pub fn new() -> Interner {
+        Interner::default()
+    }
+
+    /// Returns the symbol for the name, allocating it the first time the name is seen.
+    pub fn intern(&mut self, value: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(value) {
+            return symbol.clone();
+        }
+        let symbol = Symbol::new(value);
+        self.symbols.insert(symbol.clone());
+        symbol
+    }
+
+    /// The number of distinct names interned.
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    /// Returns true if no name has been interned yet.
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Interner>> = const { RefCell::new(None) };
+}
+
+/// Interns the name with the interner of the parse running on this thread, if any.
+pub(crate) fn intern(value: &str) -> Symbol {
+    ACTIVE.with(|active| match active.borrow_mut().as_mut() {
+        Some(interner) => interner.intern(value),
+        None => Symbol::new(value),
+    })
+}
+
+/// Runs `f` with a fresh interner for [`intern`], restoring the previous one afterwards.
+///
+/// [`intern`]: fn.intern.html
+pub(crate) fn with_interner<T>(f: impl FnOnce() -> T) -> T {
+    with_interner_in(&mut Interner::new(), f)
+}
+
+/// Runs `f` with `interner` as the interner for [`intern`], so that names stay shared across
+/// several calls. The previous interner is restored afterwards.
+///
+/// [`intern`]: fn.intern.html
+pub(crate) fn with_interner_in<T>(interner: &mut Interner, f: impl FnOnce() -> T) -> T {
+    struct Restore<'i> {
+        interner: &'i mut Interner,
+        previous: Option<Interner>,
+    }
+    impl Drop for Restore<'_> {
+        fn drop(&mut self) {
+            let previous = self.previous.take();
+            let used = ACTIVE.with(|active| std::mem::replace(&mut *active.borrow_mut(), previous));
+            *self.interner = used.unwrap_or_default();
+        }
+    }
+
+    let taken = std::mem::take(interner);
+    let previous = ACTIVE.with(|active| active.borrow_mut().replace(taken));
+    let _restore = Restore { interner, previous };
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn symbols_compare_by_value() {
+        let a = Symbol::new("id");
+        let b = Symbol::from(String::from("id"));
+        assert!(!a.ptr_eq(&b));
+        assert_eq!(a, b);
+        assert_eq!(a, "id");
+        assert_eq!("id", a);
+        assert_eq!(format!("{} {:?}", a, a), "id \"id\"");
+    }
+
+    #[test]
+    fn a_parse_shares_symbols() {
+        let (first, second) = with_interner(|| (intern("String"), intern("String")));
+        assert!(first.ptr_eq(&second));
+        let outside = intern("String");
+        assert!(!outside.ptr_eq(&first));
+    }
+
+    #[test]
+    fn parsed_names_are_shared() {
+        use crate::nodes::*;
+        let document = crate::parse("type A { b: String } type B { a: String }").unwrap();
+        let field_types: Vec<&Symbol> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+                    TypeDefinitionNode::Object(object),
+                )) => Some(&object.fields),
+                _ => None,
+            })
+            .flatten()
+            .map(|field| match &field.field_type {
+                TypeNode::Named(named) => &named.name.value,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert!(field_types[0].ptr_eq(field_types[1]));
+    }
+}
@@ -0,0 +1,1296 @@
+//! Answers introspection queries (`__schema`, `__type` and `__typename`) from a type system
+//! [`Document`].
+//!
+//! The introspection types described in the GraphQL Spec are resolved lazily from a [`Schema`],
+//! so any selection over them can be answered, including the [`INTROSPECTION_QUERY`] sent by
+//! GraphiQL and most code generation tools. The result is the JSON response of the query.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::schema::Schema;
+//!
+//! let document = syntax::parse("type Query { hello: String }").unwrap();
+//! let schema = Schema::new(&document);
+//! let query = syntax::parse("{ __schema { queryType { name } } }").unwrap();
+//! let response = schema.introspect(&query);
+//! assert_eq!(
+//!     response["data"]["__schema"]["queryType"]["name"],
+//!     serde_json::json!("Query")
+//! );
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+//! [`Schema`]: ../schema/struct.Schema.html
+//! [`INTROSPECTION_QUERY`]: constant.INTROSPECTION_QUERY.html
+
+use crate::ast::Ast;
+use crate::document::Document;
+use crate::error::ConversionError;
+use crate::merge::response_key;
+use crate::nodes::*;
+use crate::printer::{print, print_value};
+use crate::schema::{Schema, BUILTIN_DIRECTIVES, BUILTIN_SCALARS};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+/// The introspection query used by GraphiQL and graphql-js to fetch a complete schema.
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    description
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      isRepeatable
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  specifiedByURL
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType { kind name }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// A reference to a type, either by name or through a list/non-null wrapper.
+#[derive(Clone, Copy)]
+enum TypeRef<'a> {
+    Named(&'a str),
+    List(&'a TypeNode),
+    NonNull(&'a TypeNode),
+}
+
+impl<'a> From<&'a TypeNode> for TypeRef<'a> {
+    fn from(type_node: &'a TypeNode) -> Self {
+        match type_node {
+            TypeNode::Named(named) => TypeRef::Named(&named.name.value),
+            TypeNode::List(list) => TypeRef::List(&list.list_type),
+            TypeNode::NonNull(inner) => TypeRef::NonNull(inner),
+        }
+    }
+}
+
+/// An instance of one of the introspection types.
+#[derive(Clone, Copy)]
+enum Meta<'a> {
+    Schema,
+    Type(TypeRef<'a>),
+    Field(&'a FieldDefinitionNode),
+    InputValue(&'a InputValueDefinitionNode),
+    EnumValue(&'a EnumValueDefinitionNode),
+    Directive(&'a DirectiveDefinitionNode),
+}
+
+impl<'a> Meta<'a> {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Meta::Schema => "__Schema",
+            Meta::Type(_) => "__Type",
+            Meta::Field(_) => "__Field",
+            Meta::InputValue(_) => "__InputValue",
+            Meta::EnumValue(_) => "__EnumValue",
+            Meta::Directive(_) => "__Directive",
+        }
+    }
+}
+
+/// The value of a field on an introspection type.
+enum Resolved<'a> {
+    Leaf(Value),
+    Object(Meta<'a>),
+    List(Vec<Meta<'a>>),
+}
+
+impl<'a> From<Option<Meta<'a>>> for Resolved<'a> {
+    fn from(meta: Option<Meta<'a>>) -> Self {
+        match meta {
+            Some(meta) => Resolved::Object(meta),
+            None => Resolved::Leaf(Value::Null),
+        }
+    }
+}
+
+fn description_value(description: &Description) -> Value {
+    match description {
+        Some(description) => Value::String(description.value.clone()),
+        None => Value::Null,
+    }
+}
+
+fn deprecation(directives: &Option<Directives>) -> Option<String> {
+    let directive = directives
+        .as_ref()?
+        .iter()
+        .find(|directive| directive.name.value == "deprecated")?;
+    let reason = directive.arguments.as_ref().and_then(|arguments| {
+        arguments
+            .iter()
+            .find(|argument| argument.name.value == "reason")
+            .and_then(|argument| match &argument.value {
+                ValueNode::Str(reason) => Some(reason.value.clone()),
+                _ => None,
+            })
+    });
+    Some(reason.unwrap_or_else(|| DEFAULT_DEPRECATION_REASON.into()))
+}
+
+fn argument<'n>(field: &'n FieldNode, name: &str) -> Option<&'n ValueNode> {
+    field
+        .arguments
+        .as_ref()?
+        .iter()
+        .find(|argument| argument.name.value == name)
+        .map(|argument| &argument.value)
+}
+
+fn include_deprecated(field: &FieldNode) -> bool {
+    matches!(
+        argument(field, "includeDeprecated"),
+        Some(ValueNode::Bool(BooleanValueNode { value: true }))
+    )
+}
+
+struct Executor<'a, 'q> {
+    schema: &'a Schema<'a>,
+    fragments: HashMap<&'q str, &'q FragmentDefinitionNode>,
+    errors: Vec<Value>,
+}
+
+impl<'a, 'q> Executor<'a, 'q> {
+    fn named_type(&self, name: &'a str) -> Option<Meta<'a>> {
+        if self.schema.has_type(name) {
+            Some(Meta::Type(TypeRef::Named(name)))
+        } else {
+            None
+        }
+    }
+
+    fn resolve_root(&mut self, field: &'q FieldNode) -> Option<Resolved<'a>> {
+        match field.name.value.as_str() {
+            "__schema" => Some(Resolved::Object(Meta::Schema)),
+            "__type" => match argument(field, "name") {
+                Some(ValueNode::Str(name)) => Some(
+                    self.schema
+                        .types()
+                        .find(|definition| definition.name() == name.value)
+                        .map(|definition| Meta::Type(TypeRef::Named(definition.name())))
+                        .into(),
+                ),
+                _ => {
+                    self.error("Field `__type` requires a String argument `name`".into());
+                    None
+                }
+            },
+            "__typename" => Some(Resolved::Leaf(
+                self.schema
+                    .query_type()
+                    .map(|name| Value::String(name.into()))
+                    .unwrap_or(Value::Null),
+            )),
+            name => {
+                self.error(format!(
+                    "Cannot query field `{}`: only introspection fields can be answered from a schema",
+                    name
+                ));
+                None
+            }
+        }
+    }
+
+    fn resolve(&mut self, meta: Meta<'a>, field: &'q FieldNode) -> Option<Resolved<'a>> {
+        let name = field.name.value.as_str();
+        if name == "__typename" {
+            return Some(Resolved::Leaf(Value::String(meta.type_name().into())));
+        }
+        let resolved = match meta {
+            Meta::Schema => self.resolve_schema(name),
+            Meta::Type(type_ref) => self.resolve_type(type_ref, field),
+            Meta::Field(definition) => Self::resolve_field(definition, name),
+            Meta::InputValue(definition) => Self::resolve_input_value(definition, name),
+            Meta::EnumValue(definition) => Self::resolve_enum_value(definition, name),
+            Meta::Directive(definition) => Self::resolve_directive(definition, name),
+        };
+        if resolved.is_none() {
+            self.error(format!(
+                "Cannot query field `{}` on type `{}`",
+                name,
+                meta.type_name()
+            ));
+        }
+        resolved
+    }
+
+    fn resolve_schema(&self, name: &str) -> Option<Resolved<'a>> {
+        let schema = self.schema;
+        Some(match name {
+            "description" => Resolved::Leaf(json!(schema.description())),
+            "types" => Resolved::List(
+                schema
+                    .types()
+                    .map(|definition| Meta::Type(TypeRef::Named(definition.name())))
+                    .collect(),
+            ),
+            "queryType" => schema.query_type().and_then(|n| self.named_type(n)).into(),
+            "mutationType" => schema
+                .mutation_type()
+                .and_then(|n| self.named_type(n))
+                .into(),
+            "subscriptionType" => schema
+                .subscription_type()
+                .and_then(|n| self.named_type(n))
+                .into(),
+            "directives" => Resolved::List(schema.directives().map(Meta::Directive).collect()),
+            _ => return None,
+        })
+    }
+
+    fn resolve_type(&self, type_ref: TypeRef<'a>, field: &FieldNode) -> Option<Resolved<'a>> {
+        let name = field.name.value.as_str();
+        let (type_name, definition) = match type_ref {
+            TypeRef::Named(type_name) => (type_name, self.schema.get_type(type_name)),
+            TypeRef::List(inner) | TypeRef::NonNull(inner) => {
+                return match name {
+                    "kind" => Some(Resolved::Leaf(json!(if let TypeRef::List(_) = type_ref {
+                        "LIST"
+                    } else {
+                        "NON_NULL"
+                    }))),
+                    "ofType" => Some(Resolved::Object(Meta::Type(inner.into()))),
+                    "name" | "description" | "specifiedByURL" | "fields" | "interfaces"
+                    | "possibleTypes" | "enumValues" | "inputFields" | "isOneOf" => {
+                        Some(Resolved::Leaf(Value::Null))
+                    }
+                    _ => None,
+                };
+            }
+        };
+        let null = Resolved::Leaf(Value::Null);
+        Some(match name {
+            "kind" => Resolved::Leaf(json!(match definition {
+                Some(TypeDefinitionNode::Object(_)) => "OBJECT",
+                Some(TypeDefinitionNode::Interface(_)) => "INTERFACE",
+                Some(TypeDefinitionNode::Union(_)) => "UNION",
+                Some(TypeDefinitionNode::Enum(_)) => "ENUM",
+                Some(TypeDefinitionNode::Input(_)) => "INPUT_OBJECT",
+                Some(TypeDefinitionNode::Scalar(_)) | None => "SCALAR",
+            })),
+            "name" => Resolved::Leaf(json!(type_name)),
+            "description" => match definition {
+                Some(definition) => Resolved::Leaf(description_value(definition.description())),
+                None => null,
+            },
+            "specifiedByURL" => match definition {
+                Some(TypeDefinitionNode::Scalar(_)) => {
+                    Resolved::Leaf(json!(self.schema.scalar_spec_url(type_name)))
+                }
+                _ => null,
+            },
+            "isOneOf" => match definition {
+                Some(TypeDefinitionNode::Input(_)) => {
+                    Resolved::Leaf(json!(self.schema.is_one_of(type_name)))
+                }
+                _ => null,
+            },
+            "ofType" => null,
+            "fields" => match definition {
+                Some(TypeDefinitionNode::Object(_)) | Some(TypeDefinitionNode::Interface(_)) => {
+                    let include_deprecated = include_deprecated(field);
+                    Resolved::List(
+                        self.schema
+                            .fields(type_name)
+                            .into_iter()
+                            .filter(|f| include_deprecated || deprecation(&f.directives).is_none())
+                            .map(Meta::Field)
+                            .collect(),
+                    )
+                }
+                _ => null,
+            },
+            "interfaces" => match definition {
+                Some(TypeDefinitionNode::Object(_)) | Some(TypeDefinitionNode::Interface(_)) => {
+                    Resolved::List(
+                        self.schema
+                            .interfaces(type_name)
+                            .into_iter()
+                            .map(|name| Meta::Type(TypeRef::Named(name)))
+                            .collect(),
+                    )
+                }
+                _ => null,
+            },
+            "possibleTypes" => match definition {
+                Some(TypeDefinitionNode::Union(_)) | Some(TypeDefinitionNode::Interface(_)) => {
+                    Resolved::List(
+                        self.schema
+                            .possible_types(type_name)
+                            .into_iter()
+                            .map(|name| Meta::Type(TypeRef::Named(name)))
+                            .collect(),
+                    )
+                }
+                _ => null,
+            },
+            "enumValues" => match definition {
+                Some(TypeDefinitionNode::Enum(enum_type)) => {
+                    let include_deprecated = include_deprecated(field);
+                    Resolved::List(
+                        enum_type
+                            .values
+                            .iter()
+                            .filter(|v| include_deprecated || deprecation(&v.directives).is_none())
+                            .map(Meta::EnumValue)
+                            .collect(),
+                    )
+                }
+                _ => null,
+            },
+            "inputFields" => match definition {
+                Some(TypeDefinitionNode::Input(input)) => {
+                    Resolved::List(input.fields.iter().map(Meta::InputValue).collect())
+                }
+                _ => null,
+            },
+            _ => return None,
+        })
+    }
+
+    fn resolve_field(definition: &'a FieldDefinitionNode, name: &str) -> Option<Resolved<'a>> {
+        Some(match name {
+            "name" => Resolved::Leaf(json!(definition.name.as_str())),
+            "description" => Resolved::Leaf(description_value(&definition.description)),
+            "args" => Resolved::List(match &definition.arguments {
+                Some(arguments) => arguments.iter().map(Meta::InputValue).collect(),
+                None => Vec::new(),
+            }),
+            "type" => Resolved::Object(Meta::Type((&definition.field_type).into())),
+            "isDeprecated" => Resolved::Leaf(json!(deprecation(&definition.directives).is_some())),
+            "deprecationReason" => Resolved::Leaf(json!(deprecation(&definition.directives))),
+            _ => return None,
+        })
+    }
+
+    fn resolve_input_value(
+        definition: &'a InputValueDefinitionNode,
+        name: &str,
+    ) -> Option<Resolved<'a>> {
+        Some(match name {
+            "name" => Resolved::Leaf(json!(definition.name.as_str())),
+            "description" => Resolved::Leaf(description_value(&definition.description)),
+            "type" => Resolved::Object(Meta::Type((&definition.input_type).into())),
+            "defaultValue" => {
+                Resolved::Leaf(json!(definition.default_value.as_ref().map(print_value)))
+            }
+            "isDeprecated" => Resolved::Leaf(json!(deprecation(&definition.directives).is_some())),
+            "deprecationReason" => Resolved::Leaf(json!(deprecation(&definition.directives))),
+            _ => return None,
+        })
+    }
+
+    fn resolve_enum_value(
+        definition: &'a EnumValueDefinitionNode,
+        name: &str,
+    ) -> Option<Resolved<'a>> {
+        Some(match name {
+            "name" => Resolved::Leaf(json!(definition.name.as_str())),
+            "description" => Resolved::Leaf(description_value(&definition.description)),
+            "isDeprecated" => Resolved::Leaf(json!(deprecation(&definition.directives).is_some())),
+            "deprecationReason" => Resolved::Leaf(json!(deprecation(&definition.directives))),
+            _ => return None,
+        })
+    }
+
+    fn resolve_directive(
+        definition: &'a DirectiveDefinitionNode,
+        name: &str,
+    ) -> Option<Resolved<'a>> {
+        Some(match name {
+            "name" => Resolved::Leaf(json!(definition.name.as_str())),
+            "description" => Resolved::Leaf(description_value(&definition.description)),
+            "locations" => Resolved::Leaf(json!(definition
+                .locations
+                .iter()
+                .map(DirectiveLocation::as_str)
+                .collect::<Vec<&str>>())),
+            "args" => Resolved::List(match &definition.arguments {
+                Some(arguments) => arguments.iter().map(Meta::InputValue).collect(),
+                None => Vec::new(),
+            }),
+            "isRepeatable" => Resolved::Leaf(json!(definition.repeatable)),
+            _ => return None,
+        })
+    }
+
+    /// Flattens fragment spreads into the list of fields that apply to the introspection type.
+    fn collect_fields(
+        &mut self,
+        type_name: &str,
+        selections: &'q [Selection],
+        fields: &mut Vec<&'q FieldNode>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => fields.push(field),
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let applies = match &inline.node_type {
+                        Some(node_type) => node_type.name.value == type_name,
+                        None => true,
+                    };
+                    if applies {
+                        self.collect_fields(type_name, &inline.selections, fields);
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    match self.fragments.get(spread.name.value.as_str()) {
+                        Some(fragment) => {
+                            if fragment.node_type.name.value == type_name {
+                                self.collect_fields(type_name, &fragment.selections, fields);
+                            }
+                        }
+                        None => self.error(format!("Unknown fragment `{}`", spread.name.value)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn complete(&mut self, resolved: Resolved<'a>, field: &'q FieldNode) -> Value {
+        match resolved {
+            Resolved::Leaf(value) => value,
+            Resolved::Object(meta) => self.complete_object(meta, field),
+            Resolved::List(items) => Value::Array(
+                items
+                    .into_iter()
+                    .map(|meta| self.complete_object(meta, field))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn complete_object(&mut self, meta: Meta<'a>, field: &'q FieldNode) -> Value {
+        let mut fields = Vec::new();
+        if let Some(selections) = &field.selections {
+            self.collect_fields(meta.type_name(), selections, &mut fields);
+        } else {
+            self.error(format!(
+                "Field `{}` of type `{}` must have a selection of subfields",
+                field.name.value,
+                meta.type_name()
+            ));
+        }
+        let mut object = Map::new();
+        for field in fields {
+            if let Some(resolved) = self.resolve(meta, field) {
+                let value = self.complete(resolved, field);
+                object.insert(response_key(field).into(), value);
+            }
+        }
+        Value::Object(object)
+    }
+
+    fn error(&mut self, message: String) {
+        self.errors.push(json!({ "message": message }));
+    }
+}
+
+impl<'a> Schema<'a> {
+    /// Executes the first operation of the query document against the introspection types of
+    /// this schema and returns the JSON response, `{ "data": ... }`, with an `errors` list if
+    /// some of the selections could not be answered.
+    ///
+    /// Only the introspection fields `__schema`, `__type` and `__typename` can be queried.
+    pub fn introspect(&self, query: &Document) -> Value {
+        let mut executor = Executor {
+            schema: self,
+            fragments: HashMap::new(),
+            errors: Vec::new(),
+        };
+        let mut operation = None;
+        for definition in &query.definitions {
+            match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    executor
+                        .fragments
+                        .insert(fragment.name.value.as_str(), fragment);
+                }
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(
+                    OperationTypeNode::Query(query),
+                )) => {
+                    operation.get_or_insert(query);
+                }
+                _ => (),
+            }
+        }
+
+        let data = match operation {
+            Some(operation) => {
+                let mut fields = Vec::new();
+                let root = self.query_type().unwrap_or("Query");
+                executor.collect_fields(root, &operation.selections, &mut fields);
+                let mut data = Map::new();
+                for field in fields {
+                    if let Some(resolved) = executor.resolve_root(field) {
+                        let value = executor.complete(resolved, field);
+                        data.insert(response_key(field).into(), value);
+                    }
+                }
+                Value::Object(data)
+            }
+            None => {
+                executor.error("The document does not contain a query operation".into());
+                Value::Null
+            }
+        };
+
+        if executor.errors.is_empty() {
+            json!({ "data": data })
+        } else {
+            json!({ "data": data, "errors": executor.errors })
+        }
+    }
+
+    /// Answers the standard [`INTROSPECTION_QUERY`].
+    ///
+    /// [`INTROSPECTION_QUERY`]: ../introspection/constant.INTROSPECTION_QUERY.html
+    pub fn introspection_result(&self) -> Value {
+        let query = crate::parse(INTROSPECTION_QUERY).expect("The introspection query is valid");
+        self.introspect(&query)
+    }
+}
+
+fn conversion_error(message: String) -> ConversionError {
+    ConversionError::new(&message)
+}
+
+fn get_str<'v>(object: &'v Value, key: &str) -> Result<&'v str, ConversionError> {
+    object[key]
+        .as_str()
+        .ok_or_else(|| conversion_error(format!("Expected `{}` to be a string in {}", key, object)))
+}
+
+fn get_optional_str<'v>(object: &'v Value, key: &str) -> Option<&'v str> {
+    object.get(key).and_then(Value::as_str)
+}
+
+fn get_list<'v>(object: &'v Value, key: &str) -> Result<&'v [Value], ConversionError> {
+    match &object[key] {
+        Value::Array(values) => Ok(values),
+        Value::Null => Ok(&[]),
+        _ => Err(conversion_error(format!(
+            "Expected `{}` to be a list in {}",
+            key, object
+        ))),
+    }
+}
+
+/// Builds a description the same way the lexer would have captured it from source.
+fn description_from(object: &Value) -> Description {
+    let description = get_optional_str(object, "description")?;
+    if description.contains('\n') {
+        Some(StringValueNode::from(
+            &description.replace("\"\"\"", "\\\"\"\""),
+            true,
+        ))
+    } else {
+        let mut escaped = String::with_capacity(description.len());
+        for c in description.chars() {
+            match c {
+                '"' => escaped.push_str("\\\""),
+                '\\' => escaped.push_str("\\\\"),
+                '\t' => escaped.push_str("\\t"),
+                '\r' => escaped.push_str("\\r"),
+                c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+                c => escaped.push(c),
+            }
+        }
+        Some(StringValueNode::from(&escaped, false))
+    }
+}
+
+fn deprecation_from(object: &Value) -> Option<Directives> {
+    if object["isDeprecated"] != Value::Bool(true) {
+        return None;
+    }
+    let arguments = get_optional_str(object, "deprecationReason")
+        .filter(|reason| *reason != DEFAULT_DEPRECATION_REASON)
+        .map(|reason| {
+            vec![Argument {
+                name: NameNode::from("reason"),
+                value: ValueNode::Str(StringValueNode::from(
+                    &reason.replace('\\', "\\\\").replace('"', "\\\""),
+                    false,
+                )),
+            }]
+        });
+    Some(vec![DirectiveNode {
+        name: NameNode::from("deprecated"),
+        arguments,
+    }])
+}
+
+fn type_from(type_ref: &Value) -> Result<TypeNode, ConversionError> {
+    match get_str(type_ref, "kind")? {
+        "NON_NULL" => Ok(TypeNode::NonNull(Arc::new(type_from(&type_ref["ofType"])?))),
+        "LIST" => Ok(TypeNode::List(ListTypeNode::new(type_from(
+            &type_ref["ofType"],
+        )?))),
+        _ => Ok(TypeNode::Named(NamedTypeNode::from(get_str(
+            type_ref, "name",
+        )?))),
+    }
+}
+
+fn named_types_from(object: &Value, key: &str) -> Result<Vec<NamedTypeNode>, ConversionError> {
+    get_list(object, key)?
+        .iter()
+        .map(|type_ref| Ok(NamedTypeNode::from(get_str(type_ref, "name")?)))
+        .collect()
+}
+
+fn input_value_from(object: &Value) -> Result<InputValueDefinitionNode, ConversionError> {
+    let default_value = match get_optional_str(object, "defaultValue") {
+        Some(literal) => Some(
+            Ast::new(literal)
+                .and_then(|mut ast| ast.parse_standalone_value())
+                .map_err(|e| {
+                    conversion_error(format!("Invalid default value `{}`: {}", literal, e))
+                })?,
+        ),
+        None => None,
+    };
+    Ok(InputValueDefinitionNode {
+        description: description_from(object),
+        name: NameNode::from(get_str(object, "name")?),
+        input_type: type_from(&object["type"])?,
+        default_value,
+        directives: deprecation_from(object),
+    })
+}
+
+fn field_from(object: &Value) -> Result<FieldDefinitionNode, ConversionError> {
+    let arguments = get_list(object, "args")?
+        .iter()
+        .map(input_value_from)
+        .collect::<Result<ArgumentDefinitions, ConversionError>>()?;
+    Ok(FieldDefinitionNode {
+        description: description_from(object),
+        name: NameNode::from(get_str(object, "name")?),
+        arguments: if arguments.is_empty() {
+            None
+        } else {
+            Some(arguments)
+        },
+        field_type: type_from(&object["type"])?,
+        directives: deprecation_from(object),
+    })
+}
+
+fn fields_from(object: &Value) -> Result<Vec<FieldDefinitionNode>, ConversionError> {
+    get_list(object, "fields")?.iter().map(field_from).collect()
+}
+
+fn type_definition_from(object: &Value) -> Result<TypeDefinitionNode, ConversionError> {
+    let name = NameNode::from(get_str(object, "name")?);
+    let description = description_from(object);
+    Ok(match get_str(object, "kind")? {
+        "SCALAR" => TypeDefinitionNode::Scalar(ScalarTypeDefinitionNode {
+            description,
+            name,
+            directives: get_optional_str(object, "specifiedByURL").map(|url| {
+                vec![DirectiveNode {
+                    name: NameNode::from("specifiedBy"),
+                    arguments: Some(vec![Argument {
+                        name: NameNode::from("url"),
+                        value: ValueNode::Str(StringValueNode::from(url, false)),
+                    }]),
+                }]
+            }),
+        }),
+        "OBJECT" => {
+            let interfaces = named_types_from(object, "interfaces")?;
+            TypeDefinitionNode::Object(ObjectTypeDefinitionNode {
+                description,
+                name,
+                interfaces: if interfaces.is_empty() {
+                    None
+                } else {
+                    Some(interfaces)
+                },
+                directives: None,
+                fields: fields_from(object)?,
+            })
+        }
+        "INTERFACE" => TypeDefinitionNode::Interface(InterfaceTypeDefinitionNode {
+            description,
+            name,
+            directives: None,
+            fields: fields_from(object)?,
+        }),
+        "UNION" => TypeDefinitionNode::Union(UnionTypeDefinitionNode {
+            description,
+            name,
+            directives: None,
+            types: named_types_from(object, "possibleTypes")?,
+        }),
+        "ENUM" => TypeDefinitionNode::Enum(EnumTypeDefinitionNode {
+            description,
+            name,
+            directives: None,
+            values: get_list(object, "enumValues")?
+                .iter()
+                .map(|value| {
+                    Ok(EnumValueDefinitionNode {
+                        description: description_from(value),
+                        name: NameNode::from(get_str(value, "name")?),
+                        directives: deprecation_from(value),
+                    })
+                })
+                .collect::<Result<Vec<EnumValueDefinitionNode>, ConversionError>>()?,
+        }),
+        "INPUT_OBJECT" => TypeDefinitionNode::Input(InputTypeDefinitionNode {
+            description,
+            name,
+            directives: (object["isOneOf"] == Value::Bool(true)).then(|| {
+                vec![DirectiveNode {
+                    name: NameNode::from("oneOf"),
+                    arguments: None,
+                }]
+            }),
+            fields: get_list(object, "inputFields")?
+                .iter()
+                .map(input_value_from)
+                .collect::<Result<Vec<InputValueDefinitionNode>, ConversionError>>()?,
+        }),
+        kind => {
+            return Err(conversion_error(format!(
+                "Unknown type kind `{}` for type `{}`",
+                kind, name.value
+            )))
+        }
+    })
+}
+
+fn directive_definition_from(object: &Value) -> Result<DirectiveDefinitionNode, ConversionError> {
+    let locations = get_list(object, "locations")?
+        .iter()
+        .map(|location| {
+            location
+                .as_str()
+                .and_then(|name| DirectiveLocation::try_from(name).ok())
+                .ok_or_else(|| conversion_error(format!("Unknown directive location {}", location)))
+        })
+        .collect::<Result<Vec<DirectiveLocation>, ConversionError>>()?;
+    let arguments = get_list(object, "args")?
+        .iter()
+        .map(input_value_from)
+        .collect::<Result<ArgumentDefinitions, ConversionError>>()?;
+    Ok(DirectiveDefinitionNode {
+        description: description_from(object),
+        name: NameNode::from(get_str(object, "name")?),
+        arguments: if arguments.is_empty() {
+            None
+        } else {
+            Some(arguments)
+        },
+        repeatable: object["isRepeatable"] == Value::Bool(true),
+        locations,
+    })
+}
+
+/// Builds a type system [`Document`] from an introspection result, such as the response of the
+/// [`INTROSPECTION_QUERY`] sent to another server.
+///
+/// The input can be the whole response (`{ "data": { "__schema": ... } }`), the `data` object or
+/// the `__schema` object itself. Introspection types, built-in scalars and built-in directives are
+/// skipped, and a `schema` definition is only added when the root types do not use the
+/// conventional names.
+///
+/// [`Document`]: ../document/struct.Document.html
+/// [`INTROSPECTION_QUERY`]: constant.INTROSPECTION_QUERY.html
+pub fn document_from_introspection(introspection: &Value) -> Result<Document, ConversionError> {
+    let schema = if !introspection["data"]["__schema"].is_null() {
+        &introspection["data"]["__schema"]
+    } else if !introspection["__schema"].is_null() {
+        &introspection["__schema"]
+    } else {
+        introspection
+    };
+    if !schema["types"].is_array() {
+        return Err(ConversionError::new(
+            "The introspection result does not contain a list of types",
+        ));
+    }
+
+    let mut definitions = Vec::new();
+    let root_name = |key: &str| get_optional_str(&schema[key], "name");
+    let roots = [
+        (Operation::Query, root_name("queryType"), "Query"),
+        (Operation::Mutation, root_name("mutationType"), "Mutation"),
+        (
+            Operation::Subscription,
+            root_name("subscriptionType"),
+            "Subscription",
+        ),
+    ];
+    let description = description_from(schema);
+    let conventional = roots
+        .iter()
+        .all(|(_, name, default)| name.is_none_or(|name| name == *default));
+    if description.is_some() || !conventional {
+        let mut schema_definition = SchemaDefinitionNode::new();
+        schema_definition.description = description;
+        for (operation, name, _) in roots {
+            if let Some(name) = name {
+                schema_definition
+                    .operations
+                    .push(OperationTypeDefinitionNode {
+                        operation,
+                        node_type: NamedTypeNode::from(name),
+                    });
+            }
+        }
+        definitions.push(DefinitionNode::TypeSystem(
+            TypeSystemDefinitionNode::Schema(schema_definition),
+        ));
+    }
+
+    for type_object in get_list(schema, "types")? {
+        let name = get_str(type_object, "name")?;
+        if name.starts_with("__") || BUILTIN_SCALARS.contains(&name) {
+            continue;
+        }
+        definitions.push(DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+            type_definition_from(type_object)?,
+        )));
+    }
+    for directive in get_list(schema, "directives")? {
+        if BUILTIN_DIRECTIVES.contains(&get_str(directive, "name")?) {
+            continue;
+        }
+        definitions.push(DefinitionNode::TypeSystem(
+            TypeSystemDefinitionNode::Directive(directive_definition_from(directive)?),
+        ));
+    }
+    Ok(Document::new(definitions))
+}
+
+/// Prints the SDL described by an introspection result. See [`document_from_introspection`].
+///
+/// [`document_from_introspection`]: fn.document_from_introspection.html
+pub fn sdl_from_introspection(introspection: &Value) -> Result<String, ConversionError> {
+    Ok(print(&document_from_introspection(introspection)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SCHEMA: &str = r#"
+"""The root query"""
+type Query {
+  "Find a user"
+  user(id: ID!, active: Boolean = true): User
+  users: [User!]! @deprecated(reason: "Use search")
+}
+
+interface Node { id: ID! }
+
+type User implements Node {
+  id: ID!
+  role: Role
+}
+
+enum Role { ADMIN USER @deprecated }
+
+input Filter { role: Role = ADMIN, limit: Int = 10 }
+
+union Result = User
+"#;
+
+    fn introspect(query: &str) -> Value {
+        let document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&document);
+        schema.introspect(&parse(query).unwrap())
+    }
+
+    #[test]
+    fn it_answers_the_introspection_query() {
+        let document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&document);
+        let response = schema.introspection_result();
+        assert!(response.get("errors").is_none(), "{}", response);
+        let types = response["data"]["__schema"]["types"].as_array().unwrap();
+        let names: Vec<&str> = types.iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Query", "Node", "User", "Role", "Filter", "Result", "Int", "Float", "String",
+                "Boolean", "ID"
+            ]
+        );
+        assert_eq!(
+            response["data"]["__schema"]["queryType"],
+            json!({ "name": "Query" })
+        );
+        assert_eq!(response["data"]["__schema"]["mutationType"], Value::Null);
+        assert_eq!(types[0]["description"], json!("The root query"));
+        assert_eq!(types[0]["fields"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn it_resolves_type_references() {
+        let response = introspect(
+            r#"{
+  __type(name: "Query") {
+    fields(includeDeprecated: true) {
+      name
+      type { kind name ofType { kind name ofType { kind name ofType { name } } } }
+      isDeprecated
+      deprecationReason
+    }
+  }
+}"#,
+        );
+        assert_eq!(
+            response,
+            json!({ "data": { "__type": { "fields": [
+                {
+                    "name": "user",
+                    "type": { "kind": "OBJECT", "name": "User", "ofType": null },
+                    "isDeprecated": false,
+                    "deprecationReason": null,
+                },
+                {
+                    "name": "users",
+                    "type": { "kind": "NON_NULL", "name": null, "ofType": {
+                        "kind": "LIST", "name": null, "ofType": {
+                            "kind": "NON_NULL", "name": null, "ofType": { "name": "User" }
+                        }
+                    } },
+                    "isDeprecated": true,
+                    "deprecationReason": "Use search",
+                },
+            ] } } })
+        );
+    }
+
+    #[test]
+    fn it_hides_deprecated_values_by_default() {
+        let response = introspect(
+            r#"{
+  query: __type(name: "Query") { fields { name } }
+  role: __type(name: "Role") { enumValues { name } }
+}"#,
+        );
+        assert_eq!(
+            response["data"],
+            json!({
+                "query": { "fields": [{ "name": "user" }] },
+                "role": { "enumValues": [{ "name": "ADMIN" }] },
+            })
+        );
+    }
+
+    #[test]
+    fn it_resolves_arguments_and_default_values() {
+        let response = introspect(
+            r#"{
+  query: __type(name: "Query") { fields { args { name defaultValue } } }
+  filter: __type(name: "Filter") { kind inputFields { name defaultValue } }
+}"#,
+        );
+        assert_eq!(
+            response["data"]["query"]["fields"][0]["args"],
+            json!([
+                { "name": "id", "defaultValue": null },
+                { "name": "active", "defaultValue": "true" },
+            ])
+        );
+        assert_eq!(
+            response["data"]["filter"],
+            json!({ "kind": "INPUT_OBJECT", "inputFields": [
+                { "name": "role", "defaultValue": "ADMIN" },
+                { "name": "limit", "defaultValue": "10" },
+            ] })
+        );
+    }
+
+    #[test]
+    fn it_resolves_abstract_types() {
+        let response = introspect(
+            r#"{
+  node: __type(name: "Node") { kind possibleTypes { name } }
+  result: __type(name: "Result") { kind possibleTypes { name } }
+  user: __type(name: "User") { interfaces { name } possibleTypes { name } }
+}"#,
+        );
+        assert_eq!(
+            response["data"],
+            json!({
+                "node": { "kind": "INTERFACE", "possibleTypes": [{ "name": "User" }] },
+                "result": { "kind": "UNION", "possibleTypes": [{ "name": "User" }] },
+                "user": { "interfaces": [{ "name": "Node" }], "possibleTypes": null },
+            })
+        );
+    }
+
+    #[test]
+    fn it_resolves_typename_and_unknown_types() {
+        let response = introspect(
+            r#"{ __typename missing: __type(name: "Missing") { name } id: __type(name: "ID") { __typename kind } }"#,
+        );
+        assert_eq!(
+            response,
+            json!({ "data": {
+                "__typename": "Query",
+                "missing": null,
+                "id": { "__typename": "__Type", "kind": "SCALAR" },
+            } })
+        );
+    }
+
+    #[test]
+    fn it_reports_unanswerable_fields() {
+        let response = introspect("{ user { id } __schema { bogus } }");
+        assert_eq!(response["data"], json!({ "__schema": {} }));
+        let errors = response["errors"].as_array().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0]["message"].as_str().unwrap().contains("user"));
+        assert!(errors[1]["message"].as_str().unwrap().contains("bogus"));
+    }
+
+    #[test]
+    fn it_builds_a_document_from_an_introspection_result() {
+        let document = parse(SCHEMA).unwrap();
+        let response = Schema::new(&document).introspection_result();
+        let rebuilt = document_from_introspection(&response).unwrap();
+        assert_eq!(
+            print(&rebuilt),
+            r#""The root query"
+type Query {
+  "Find a user"
+  user(id: ID!, active: Boolean = true): User
+  users: [User!]! @deprecated(reason: "Use search")
+}
+
+interface Node {
+  id: ID!
+}
+
+type User implements Node {
+  id: ID!
+  role: Role
+}
+
+enum Role {
+  ADMIN
+  USER @deprecated
+}
+
+input Filter {
+  role: Role = ADMIN
+  limit: Int = 10
+}
+
+union Result = User
+"#
+        );
+        // The rebuilt document answers introspection the same way
+        assert_eq!(Schema::new(&rebuilt).introspection_result(), response);
+    }
+
+    #[test]
+    fn it_adds_a_schema_definition_for_custom_roots() {
+        let introspection = json!({
+            "__schema": {
+                "queryType": { "name": "Root" },
+                "mutationType": null,
+                "types": [
+                    { "kind": "OBJECT", "name": "Root", "description": "Line one\nline two",
+                      "fields": [{ "name": "now", "args": [],
+                                   "type": { "kind": "SCALAR", "name": "Date" } }],
+                      "interfaces": [] },
+                    { "kind": "SCALAR", "name": "Date", "description": "A \"quoted\" date",
+                      "specifiedByURL": "https://example.com/date" },
+                    { "kind": "INPUT_OBJECT", "name": "Pick", "isOneOf": true, "inputFields": [
+                        { "name": "id", "type": { "kind": "SCALAR", "name": "String" } }
+                    ] },
+                    { "kind": "SCALAR", "name": "String" },
+                    { "kind": "OBJECT", "name": "__Schema", "fields": [] },
+                ]
+            }
+        });
+        assert_eq!(
+            sdl_from_introspection(&introspection).unwrap(),
+            r#"schema {
+  query: Root
+}
+
+"""Line one
+line two"""
+type Root {
+  now: Date
+}
+
+"A \"quoted\" date"
+scalar Date @specifiedBy(url: "https://example.com/date")
+
+input Pick @oneOf {
+  id: String
+}
+"#
+        );
+    }
+
+    #[test]
+    fn it_resolves_one_of_input_objects() {
+        let document =
+            parse("input Pick @oneOf { id: ID, name: String } input Filter { id: ID }").unwrap();
+        let schema = Schema::new(&document);
+        let is_one_of = |name: &str| {
+            let query = format!("{{ __type(name: \"{}\") {{ isOneOf }} }}", name);
+            schema.introspect(&parse(&query).unwrap())["data"]["__type"]["isOneOf"].clone()
+        };
+        assert_eq!(is_one_of("Pick"), json!(true));
+        assert_eq!(is_one_of("Filter"), json!(false));
+        assert_eq!(is_one_of("ID"), Value::Null);
+    }
+
+    #[test]
+    fn it_rejects_malformed_introspection_results() {
+        assert!(document_from_introspection(&json!({ "data": null })).is_err());
+        let error = document_from_introspection(&json!({
+            "types": [{ "kind": "MYSTERY", "name": "Thing" }]
+        }))
+        .unwrap_err();
+        assert!(error.message.contains("MYSTERY"));
+        let error = document_from_introspection(&json!({
+            "types": [{ "kind": "INPUT_OBJECT", "name": "In", "inputFields": [
+                { "name": "a", "type": { "kind": "SCALAR", "name": "Int" }, "defaultValue": "{" }
+            ] }]
+        }))
+        .unwrap_err();
+        assert!(error.message.contains("Invalid default value"));
+    }
+
+    #[test]
+    fn it_resolves_directives() {
+        let document = parse(
+            r#"
+type Query { a: Int }
+"Caches the result"
+directive @cached(ttl: Int = 60) repeatable on FIELD_DEFINITION | OBJECT
+"#,
+        )
+        .unwrap();
+        let response = Schema::new(&document).introspect(
+            &parse("{ __schema { directives { name locations isRepeatable args { name } } } }")
+                .unwrap(),
+        );
+        let directives = response["data"]["__schema"]["directives"]
+            .as_array()
+            .unwrap();
+        assert_eq!(
+            directives[0],
+            json!({
+                "name": "cached",
+                "locations": ["FIELD_DEFINITION", "OBJECT"],
+                "isRepeatable": true,
+                "args": [{ "name": "ttl" }],
+            })
+        );
+        let names: Vec<&str> = directives
+            .iter()
+            .map(|d| d["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "cached",
+                "include",
+                "skip",
+                "deprecated",
+                "specifiedBy",
+                "oneOf"
+            ]
+        );
+
+        let rebuilt =
+            sdl_from_introspection(&Schema::new(&document).introspection_result()).unwrap();
+        assert_eq!(
+            rebuilt,
+            r#"type Query {
+  a: Int
+}
+
+"Caches the result"
+directive @cached(ttl: Int = 60) repeatable on FIELD_DEFINITION | OBJECT
+"#
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! Converts values between GraphQL literals and JSON.
+//!
+//! [`ValueNode::to_json`] gives the JSON value of a literal, its variables replaced by the values
+//! sent for them, and its strings unescaped. [`ValueNode::from_json`] gives the literal of a JSON
+//! value, its strings escaped. As JSON has no enum values, an enum value becomes a string, and a
+//! string only becomes an enum value again where [`ValueNode::from_json_as`] is given the type of
+//! the value.
+//!
+//! # Example
+//!
+//! ```
+//! use serde_json::{json, Map};
+//! use syntax::nodes::{ValueNode, VariableNode};
+//!
+//! let literal = ValueNode::from_json(&json!({"name": "Ann\n", "tags": ["a"]}));
+//! assert_eq!(literal.to_string(), r#"{name: "Ann\n", tags: ["a"]}"#);
+//!
+//! let mut variables = Map::new();
+//! variables.insert("first".into(), json!(10));
+//! assert_eq!(
+//!     ValueNode::Variable(VariableNode::from("first")).to_json(&variables),
+//!     json!(10)
+//! );
+//! assert_eq!(literal.to_json(&variables), json!({"name": "Ann\n", "tags": ["a"]}));
+//! ```
+//!
+//! [`ValueNode::to_json`]: ../nodes/enum.ValueNode.html#method.to_json
+//! [`ValueNode::from_json`]: ../nodes/enum.ValueNode.html#method.from_json
+//! [`ValueNode::from_json_as`]: ../nodes/enum.ValueNode.html#method.from_json_as
+
+use crate::nodes::*;
+use crate::schema::{named_type, Schema};
+use serde_json::{json, Map, Value};
+
+impl ValueNode {
+    /// The JSON value of the literal, with the values of its variables.
+    ///
+    /// A variable without a value is `null`, or left out of the object it is a field of, as the
+    /// GraphQL Spec coerces input objects. Enum values are strings, and floats that JSON cannot
+    /// hold, infinite or not a number, are `null`.
+    pub fn to_json(&self, variables: &Map<String, Value>) -> Value {
+        match self {
+            ValueNode::Variable(variable) => variables
+                .get(variable.name.value.as_str())
+                .cloned()
+                .unwrap_or(Value::Null),
+            ValueNode::Int(int) => int.to_json(),
+            ValueNode::Float(float) => json!(float.value),
+            ValueNode::Str(string) => Value::String(string_value(string)),
+            ValueNode::Bool(boolean) => Value::Bool(boolean.value),
+            ValueNode::Null => Value::Null,
+            ValueNode::Enum(value) => Value::String(value.value.clone()),
+            ValueNode::List(list) => Value::Array(
+                list.values
+                    .iter()
+                    .map(|value| value.to_json(variables))
+                    .collect(),
+            ),
+            ValueNode::Object(object) => Value::Object(
+                object
+                    .fields
+                    .iter()
+                    .filter(|field| match &field.value {
+                        ValueNode::Variable(variable) => {
+                            variables.contains_key(variable.name.value.as_str())
+                        }
+                        _ => true,
+                    })
+                    .map(|field| (field.name.value.to_string(), field.value.to_json(variables)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// The literal of a JSON value, in which strings are always strings.
+    ///
+    /// Whole numbers are integers, other numbers floats.
+    pub fn from_json(value: &Value) -> ValueNode {
+        match value {
+            Value::Null => ValueNode::Null,
+            Value::Bool(value) => ValueNode::Bool(BooleanValueNode { value: *value }),
+            Value::Number(number) if number.is_i64() || number.is_u64() => {
+                ValueNode::Int(IntValueNode {
+                    value: number.to_string(),
+                })
+            }
+            Value::Number(number) => {
+                ValueNode::Float(FloatValueNode::from(number.as_f64().unwrap_or(0.0)))
+            }
+            Value::String(string) => ValueNode::Str(StringValueNode::from(&escape(string), false)),
+            Value::Array(values) => ValueNode::List(ListValueNode {
+                values: values.iter().map(ValueNode::from_json).collect(),
+            }),
+            Value::Object(fields) => ValueNode::Object(ObjectValueNode {
+                fields: fields
+                    .iter()
+                    .map(|(name, value)| ObjectFieldNode {
+                        name: NameNode::from(name.as_str()),
+                        value: ValueNode::from_json(value),
+                    })
+                    .collect(),
+            }),
+        }
+    }
+
+    /// The literal of a JSON value of an input type of a schema, in which the strings given to
+    /// enum types are enum values.
+    ///
+    /// The fields of input objects have the types the schema gives them. Values of types the
+    /// schema does not define are converted like [`from_json`] does.
+    ///
+    /// [`from_json`]: #method.from_json
+    pub fn from_json_as(value: &Value, input_type: &TypeNode, schema: &Schema<'_>) -> ValueNode {
+        let item_type = match input_type {
+            TypeNode::NonNull(inner) => return ValueNode::from_json_as(value, inner, schema),
+            TypeNode::List(list) => Some(&list.list_type),
+            TypeNode::Named(_) => None,
+        };
+        match (value, item_type) {
+            (Value::Array(values), Some(item_type)) => ValueNode::List(ListValueNode {
+                values: values
+                    .iter()
+                    .map(|value| ValueNode::from_json_as(value, item_type, schema))
+                    .collect(),
+            }),
+            // A single value stands for a list of one, as the GraphQL Spec coerces lists
+            (value, Some(item_type)) => ValueNode::from_json_as(value, item_type, schema),
+            (value, None) => match (value, schema.get_type(named_type(input_type))) {
+                (Value::String(name), Some(TypeDefinitionNode::Enum(_))) => {
+                    ValueNode::Enum(EnumValueNode {
+                        value: name.clone(),
+                    })
+                }
+                (Value::Object(fields), Some(TypeDefinitionNode::Input(input))) => {
+                    ValueNode::Object(ObjectValueNode {
+                        fields: fields
+                            .iter()
+                            .map(|(name, value)| {
+                                let field = input
+                                    .fields
+                                    .iter()
+                                    .find(|field| field.name.value == name.as_str());
+                                ObjectFieldNode {
+                                    name: NameNode::from(name.as_str()),
+                                    value: match field {
+                                        Some(field) => ValueNode::from_json_as(
+                                            value,
+                                            &field.input_type,
+                                            schema,
+                                        ),
+                                        None => ValueNode::from_json(value),
+                                    },
+                                }
+                            })
+                            .collect(),
+                    })
+                }
+                (value, _) => ValueNode::from_json(value),
+            },
+        }
+    }
+}
+
+impl IntValueNode {
+    /// The JSON value of the literal, a number when JSON numbers can hold it, or else its string
+    /// of digits.
+    pub fn to_json(&self) -> Value {
+        match (self.as_i64(), self.value.parse::<u64>()) {
+            (Some(value), _) => json!(value),
+            (None, Ok(value)) => json!(value),
+            (None, Err(_)) => Value::String(self.value.clone()),
+        }
+    }
+}
+
+/// The value of a string literal, its escape sequences replaced, or the common indentation and
+/// the blank first and last lines of a block string removed.
+fn string_value(string: &StringValueNode) -> String {
+    if string.is_block() {
+        block_string_value(&string.value.replace("\\\"\"\"", "\"\"\""))
+    } else {
+        unescape(&string.value)
+    }
+}
+
+fn unescape(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('b') => value.push('\u{8}'),
+            Some('f') => value.push('\u{c}'),
+            Some('n') => value.push('\n'),
+            Some('r') => value.push('\r'),
+            Some('t') => value.push('\t'),
+            Some('u') => {
+                let code = unicode_escape(&mut chars);
+                let code = match code {
+                    // A surrogate pair, escaped as two code units
+                    Some(high @ 0xD800..=0xDBFF) if chars.as_str().starts_with("\\u") => {
+                        let mut rest = chars.clone();
+                        rest.nth(1);
+                        match unicode_escape(&mut rest) {
+                            Some(low @ 0xDC00..=0xDFFF) => {
+                                chars = rest;
+                                Some(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00))
+                            }
+                            _ => Some(high),
+                        }
+                    }
+                    code => code,
+                };
+                value.push(code.and_then(std::char::from_u32).unwrap_or('\u{FFFD}'));
+            }
+            Some(escaped) => value.push(escaped),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// Reads the four hexadecimal digits of a `\u` escape sequence.
+fn unicode_escape(chars: &mut std::str::Chars<'_>) -> Option<u32> {
+    let digits: String = chars.by_ref().take(4).collect();
+    if digits.len() == 4 {
+        u32::from_str_radix(&digits, 16).ok()
+    } else {
+        None
+    }
+}
+
+/// The value of a block string, as the `BlockStringValue` algorithm of the GraphQL Spec gives it.
+fn block_string_value(raw: &str) -> String {
+    let raw = raw.replace("\r\n", "\n").replace('\r', "\n");
+    let mut lines: Vec<&str> = raw.split('\n').collect();
+    let indent = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let common_indent = lines
+        .iter()
+        .skip(1)
+        .filter(|line| indent(line) < line.len())
+        .map(|line| indent(line))
+        .min();
+    if let Some(common_indent) = common_indent {
+        for line in lines.iter_mut().skip(1) {
+            *line = &line[common_indent.min(line.len())..];
+        }
+    }
+    let blank = |line: &&str| line.trim_matches([' ', '\t']).is_empty();
+    let start = lines
+        .iter()
+        .position(|line| !blank(line))
+        .unwrap_or(lines.len());
+    let end = lines
+        .iter()
+        .rposition(|line| !blank(line))
+        .map_or(start, |end| end + 1);
+    lines[start..end].join("\n")
+}
+
+/// Escapes a string for a string literal.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::Ast;
+    use crate::parse;
+
+    fn value(source: &str) -> ValueNode {
+        Ast::new(source).unwrap().parse_standalone_value().unwrap()
+    }
+
+    #[test]
+    fn it_converts_literals_to_json() {
+        let mut variables = Map::new();
+        variables.insert("id".into(), json!("1"));
+        assert_eq!(
+            value(r#"{id: $id, other: $other, list: [$other, 1.5, -2], role: ADMIN, none: null}"#)
+                .to_json(&variables),
+            json!({"id": "1", "list": [null, 1.5, -2], "role": "ADMIN", "none": null})
+        );
+        assert_eq!(
+            value(r#""a \"b\" \\ é 😀 \n""#).to_json(&variables),
+            json!("a \"b\" \\ é 😀 \n")
+        );
+        assert_eq!(
+            value("\"\"\"\n    Hello,\n      World!\n\n    Bye\n  \"\"\"").to_json(&variables),
+            json!("Hello,\n  World!\n\nBye")
+        );
+    }
+
+    #[test]
+    fn it_converts_json_to_literals() {
+        let json =
+            json!({"name": "a \"b\"\n", "tags": ["x"], "count": 3, "ratio": 0.5, "on": true});
+        let literal = ValueNode::from_json(&json);
+        assert_eq!(
+            literal.to_string(),
+            r#"{count: 3, name: "a \"b\"\n", on: true, ratio: 0.5, tags: ["x"]}"#
+        );
+        assert_eq!(literal.to_json(&Map::new()), json);
+    }
+
+    #[test]
+    fn it_converts_strings_to_the_enum_values_of_a_type() {
+        let document = parse(
+            "enum Role { ADMIN USER }
+            input Filter { role: Role roles: [Role!] name: String }",
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        let filter = TypeNode::Named(NamedTypeNode::from("Filter"));
+        let json = json!({"role": "ADMIN", "roles": "USER", "name": "ADMIN", "other": "USER"});
+        assert_eq!(
+            ValueNode::from_json_as(&json, &filter, &schema).to_string(),
+            r#"{name: "ADMIN", other: "USER", role: ADMIN, roles: USER}"#
+        );
+        let roles = TypeNode::List(ListTypeNode::new(TypeNode::Named(NamedTypeNode::from(
+            "Role",
+        ))));
+        assert_eq!(
+            ValueNode::from_json_as(&json!(["USER", "ADMIN"]), &roles, &schema).to_string(),
+            "[USER, ADMIN]"
+        );
+    }
+}
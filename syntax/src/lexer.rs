@@ -19,7 +19,7 @@
 //!
 //! ```
 //! use syntax::lexer::Lexer;
-//! use syntax::token::{Token, Location};
+//! use syntax::token::{Location, Token, TokenKind};
 //!
 //! let mut lexer = Lexer::new(r#"
 //! schema Schema {
@@ -27,18 +27,18 @@
 //!   mutation: Mutation
 //! }
 //! "#);
-//! assert_eq!(lexer.next(), Some(Ok(Token::Start)));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(1, 2, 1), "schema"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(9, 2, 9), "Schema"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::OpenBrace(Location::new(11, 2, 11)))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(14, 3, 3), "query"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Colon(Location::new(19, 3, 8)))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(21, 3, 10), "Query"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(25, 4, 3), "mutation"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Colon(Location::new(34, 4, 12)))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::Name(Location::new(36, 4, 14), "Mutation"))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::CloseBrace(Location::new(38, 5, 1)))));
-//! assert_eq!(lexer.next(), Some(Ok(Token::End)));
+//! assert_eq!(lexer.next(), Some(Ok(Token::START)));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(1, 2, 1), "schema"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(9, 2, 9), "Schema"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::OpenBrace, Location::new(11, 2, 11), "{"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(14, 3, 3), "query"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Colon, Location::new(19, 3, 8), ":"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(21, 3, 10), "Query"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(25, 4, 3), "mutation"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Colon, Location::new(34, 4, 12), ":"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::Name, Location::new(36, 4, 14), "Mutation"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::new(TokenKind::CloseBrace, Location::new(38, 5, 1), "}"))));
+//! assert_eq!(lexer.next(), Some(Ok(Token::END)));
 //! assert_eq!(lexer.next(), None);
 //! ```
 //!
@@ -47,10 +47,10 @@
 //! ```
 //! use syntax::lexer::Lexer;
 //! use syntax::error::LexError;
-//! use syntax::token::{Token, Location};
+//! use syntax::token::{Location, Token, TokenKind};
 //!
 //! let mut lexer = Lexer::new(r#""unmatched"#);
-//! assert_eq!(lexer.next(), Some(Ok(Token::Start)));
+//! assert_eq!(lexer.next(), Some(Ok(Token::START)));
 //! assert_eq!(lexer.next(), Some(Err(LexError::UnmatchedQuote(Location {
 //!   absolute_position: 0,
 //!   line: 1,
@@ -59,17 +59,42 @@
 //! assert_eq!(lexer.next(), None);
 //! ```
 //!
+//! # Lexing input as it arrives
+//!
+//! A [`PushLexer`] lexes input fed to it in chunks, such as the reads of a socket, without waiting
+//! for all of it. A token cut by the end of a chunk is only returned once the next chunk completes
+//! it, or once the input is finished, and until then the lexer reports it needs more input.
+//!
+//! ```
+//! use syntax::lexer::{Lexed, PushLexer};
+//! use syntax::token::{Token, TokenKind};
+//!
+//! let mut lexer = PushLexer::new();
+//! lexer.feed("{ her");
+//! assert_eq!(lexer.next_token(), Lexed::Token(Token::START));
+//! assert!(matches!(lexer.next_token(), Lexed::Token(token) if token.kind == TokenKind::OpenBrace));
+//! assert_eq!(lexer.next_token(), Lexed::NeedMoreInput);
+//! lexer.feed("o }");
+//! assert!(matches!(lexer.next_token(), Lexed::Token(token) if token.text == "hero"));
+//! assert!(matches!(lexer.next_token(), Lexed::Token(token) if token.kind == TokenKind::CloseBrace));
+//! assert_eq!(lexer.next_token(), Lexed::NeedMoreInput);
+//! lexer.finish();
+//! assert_eq!(lexer.next_token(), Lexed::Token(Token::END));
+//! assert_eq!(lexer.next_token(), Lexed::Ended);
+//! ```
+//!
 //! [`LexError`]: ../error/enum.LexError.html
 //! [`Lexer`]: enum.Lexer.html
+//! [`PushLexer`]: struct.PushLexer.html
 //! [`Iterator`]: ../../std/iter/trait.Iterator.html
-//! [`Token`]: ../token/enum.Token.html
-//! [`Start`]: ../token/enum.Token.html#variant.Start
-//! [`End`]: ../token/enum.Token.html#variant.End
+//! [`Token`]: ../token/struct.Token.html
+//! [`Start`]: ../token/enum.TokenKind.html#variant.Start
+//! [`End`]: ../token/enum.TokenKind.html#variant.End
 //!
 //!
 
 use crate::error::LexError;
-use crate::token::{Location, Token};
+use crate::token::{Location, Token, TokenKind};
 use log::debug;
 use regex::Regex;
 use std::iter::Iterator;
@@ -85,7 +110,7 @@ use std::str::CharIndices;
 /// A Lexer will also keep track of its possition in the string. This allows for more robust
 /// messages about where in the string a certain token or error is.
 ///
-/// [`Tokens`]: ../token/enum.Token.html
+/// [`Tokens`]: ../token/struct.Token.html
 /// [`error`]: ../error/enum.LexError.html
 #[derive(Debug)]
 pub struct Lexer<'a> {
@@ -96,6 +121,7 @@ pub struct Lexer<'a> {
     position: usize,
     line: usize,
     col: usize,
+    ignored: usize,
 }
 
 type LexerItem<'a> = Result<Token<'a>, LexError>;
@@ -103,7 +129,7 @@ type LexerItem<'a> = Result<Token<'a>, LexError>;
 impl<'a> Lexer<'a> {
     /// Creates a new lexer that passes over the provided input string.
     /// The token series will
-    pub fn new(input: &str) -> Lexer {
+    pub fn new(input: &str) -> Lexer<'_> {
         Lexer {
             raw: input,
             input: input.char_indices().peekable(),
@@ -112,32 +138,50 @@ impl<'a> Lexer<'a> {
             position: 0,
             line: 1,
             col: 1,
+            ignored: 0,
         }
     }
 
+    /// Starts over on a new input, as if the lexer had just been created for it.
+    pub fn reset(&mut self, input: &'a str) {
+        *self = Lexer::new(input);
+    }
+
+    /// The number of characters skipped so far as whitespace, commas, line terminators or
+    /// comments.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// Numbers lines from `line` instead of 1, for input that is a slice of a larger document.
+    pub(crate) fn starting_at_line(mut self, line: usize) -> Lexer<'a> {
+        self.line = line;
+        self
+    }
+
     fn get_next_token(&mut self) -> LexerItem<'a> {
+        self.skip_ignored();
         if let Some((i, next)) = self.input.peek() {
             let index = *i;
+            // Positions are byte offsets, which counting characters loses track of.
+            self.position = index;
             match next {
-                '!' => self.lex_bang(),
-                '$' => self.lex_dollar(),
-                '&' => self.lex_ampersand(),
-                '|' => self.lex_pipe(),
-                '@' => self.lex_at(),
-                ':' => self.lex_colon(),
-                '=' => self.lex_equals(),
-                '{' => self.lex_open_brace(),
-                '}' => self.lex_close_brace(),
-                '(' => self.lex_open_paren(),
-                ')' => self.lex_close_paren(),
-                '[' => self.lex_open_square(),
-                ']' => self.lex_close_square(),
-                '#' => self.ignore_comments(),
-                ' ' | '\t' | ',' => self.ignore_whitespace(),
-                '\n' => self.ignore_newline(),
+                '!' => self.lex_punctuator(TokenKind::Bang, "!"),
+                '$' => self.lex_punctuator(TokenKind::Dollar, "$"),
+                '&' => self.lex_punctuator(TokenKind::Amp, "&"),
+                '|' => self.lex_punctuator(TokenKind::Pipe, "|"),
+                '@' => self.lex_punctuator(TokenKind::At, "@"),
+                ':' => self.lex_punctuator(TokenKind::Colon, ":"),
+                '=' => self.lex_punctuator(TokenKind::Equals, "="),
+                '{' => self.lex_punctuator(TokenKind::OpenBrace, "{"),
+                '}' => self.lex_punctuator(TokenKind::CloseBrace, "}"),
+                '(' => self.lex_punctuator(TokenKind::OpenParen, "("),
+                ')' => self.lex_punctuator(TokenKind::CloseParen, ")"),
+                '[' => self.lex_punctuator(TokenKind::OpenSquare, "["),
+                ']' => self.lex_punctuator(TokenKind::CloseSquare, "]"),
                 '"' => self.lex_string(index),
                 // TODO Make this multilingual
-                'a'..='z' | 'A'..='Z' => self.lex_name(index),
+                'a'..='z' | 'A'..='Z' | '_' => self.lex_name(index),
                 // TODO Make this handle scientific notation
                 '0'..='9' | '-' => self.lex_number(index),
                 '.' => self.lex_ellipsis(index),
@@ -146,40 +190,53 @@ impl<'a> Lexer<'a> {
         } else {
             // This occurs when we have hit an extra newline at the end of the file
             self.ended = true;
-            Ok(Token::End)
+            Ok(Token::END)
         }
     }
 
     fn lex_ellipsis(&mut self, index: usize) -> LexerItem<'a> {
-        lazy_static! {
-            static ref SPREAD: Regex = Regex::new("...").unwrap();
-        }
-        if SPREAD.is_match_at(self.raw, index) {
+        if self.slice(index, self.raw.len())?.starts_with("...") {
             let cur_col = self.col;
             let cur_pos = self.position;
             self.advance_n(3);
-            Ok(Token::Spread(Location::new(cur_pos, self.line, cur_col)))
+            Ok(Token::new(
+                TokenKind::Spread,
+                Location::new(cur_pos, self.line, cur_col),
+                "...",
+            ))
         } else {
             self.make_unexpected_character_error()
         }
     }
 
+    /// `Regex::is_match_at` searches from the position onwards, so check the match starts there.
+    fn matches_at(regex: &Regex, raw: &str, position: usize) -> bool {
+        regex
+            .find_at(raw, position)
+            .is_some_and(|found| found.start() == position)
+    }
+
     fn lex_number(&mut self, init_pos: usize) -> LexerItem<'a> {
         lazy_static! {
-            static ref FLOAT: Regex = Regex::new(r#"-?[0-9]+\.[0-9]+"#).unwrap();
+            static ref FLOAT: Regex =
+                Regex::new(r#"-?[0-9]+(?:\.[0-9]+(?:[eE][+-]?[0-9]+)?|[eE][+-]?[0-9]+)"#).unwrap();
             static ref INT: Regex = Regex::new(r#"-?[0-9]+"#).unwrap();
         }
-        if FLOAT.is_match_at(self.raw, init_pos) {
+        if Self::matches_at(&FLOAT, self.raw, init_pos) {
             let mut locations = FLOAT.capture_locations();
             match FLOAT.captures_read_at(&mut locations, self.raw, init_pos) {
                 Some(_) => match locations.get(0) {
                     Some((start, end)) => {
                         let cur_col = self.col;
-                        let substr = self.raw.get(start..end).unwrap();
+                        let substr = self.slice(start, end)?;
                         match substr.parse::<f64>() {
-                            Ok(f) => {
+                            Ok(_) => {
                                 self.advance_to(end);
-                                Ok(Token::Float(Location::new(init_pos, self.line, cur_col), f))
+                                Ok(Token::new(
+                                    TokenKind::Float,
+                                    Location::new(init_pos, self.line, cur_col),
+                                    substr,
+                                ))
                             }
                             Err(_) => self.make_conversion_error("Float"),
                         }
@@ -188,20 +245,16 @@ impl<'a> Lexer<'a> {
                 },
                 None => self.make_unexpected_character_error(),
             }
-        } else if INT.is_match_at(self.raw, init_pos) {
+        } else if Self::matches_at(&INT, self.raw, init_pos) {
             let mut locations = INT.capture_locations();
             match INT.captures_read_at(&mut locations, self.raw, init_pos) {
                 Some(_) => match locations.get(0) {
                     Some((start, end)) => {
-                        let substr = self.raw.get(start..end).unwrap();
-                        match substr.parse::<i64>() {
-                            Ok(i) => {
-                                let tok = Token::Int(self.get_current_location(), i);
-                                self.advance_to(end);
-                                Ok(tok)
-                            }
-                            Err(_) => self.make_conversion_error("Int"),
-                        }
+                        // The validator checks the range, which depends on the type given
+                        let substr = self.slice(start, end)?;
+                        let tok = Token::new(TokenKind::Int, self.get_current_location(), substr);
+                        self.advance_to(end);
+                        Ok(tok)
                     }
                     None => self.make_unknown_character_error(),
                 },
@@ -215,7 +268,7 @@ impl<'a> Lexer<'a> {
     fn lex_name(&mut self, init_pos: usize) -> LexerItem<'a> {
         let mut end_pos = 0;
         while let Some((_, c)) = self.input.peek() {
-            if c.is_alphanumeric() || *c == '_' {
+            if c.is_ascii_alphanumeric() || *c == '_' {
                 self.input.next();
                 end_pos += 1;
             } else {
@@ -226,39 +279,44 @@ impl<'a> Lexer<'a> {
         let init_col = self.col;
         self.col += end_pos;
         end_pos += init_pos;
-        Ok(Token::Name(
+        let name = self.slice(init_pos, end_pos)?;
+        Ok(Token::new(
+            TokenKind::Name,
             Location::new(init_pos, self.line, init_col),
-            self.raw.get(init_pos..end_pos).unwrap(),
+            name,
         ))
     }
 
     fn lex_string(&mut self, init_pos: usize) -> LexerItem<'a> {
         lazy_static! {
-            static ref BLOCK_START: Regex = Regex::new(r#"""""#).unwrap();
-            static ref BLOCK: Regex = Regex::new(r#""""((?:\\.|[^"\\])*)""""#).unwrap();
+            static ref BLOCK: Regex =
+                Regex::new(r#""""((?:\\"""|\\|[^"\\]|"[^"]|""[^"])*)""""#).unwrap();
             static ref SINGLE: Regex = Regex::new(r#""((?:\\.|[^"\\])*)""#).unwrap();
         }
-        if BLOCK_START.is_match_at(self.raw, init_pos) {
+        if self.slice(init_pos, self.raw.len())?.starts_with(r#"""""#) {
             let mut locations = BLOCK.capture_locations();
             match BLOCK.captures_read_at(&mut locations, self.raw, init_pos) {
-                Some(_) => match locations.get(1) {
-                    Some((start_off, end_off)) => {
-                        let (start, end) = locations.get(0).unwrap();
-                        match self.input.position(|(i, _)| i == end) {
-                            Some(pos) => self.position = pos,
-                            None => (),
-                        }
-                        let tok = Token::BlockStr(
+                Some(_) => match (locations.get(0), locations.get(1)) {
+                    (Some((start, end)), Some((start_off, end_off))) => {
+                        let value = self.slice(start_off, end_off)?;
+                        let substr = self.slice(start, end)?;
+                        while self.input.next_if(|(i, _)| *i < end).is_some() {}
+                        self.position = end;
+                        let tok = Token::new(
+                            TokenKind::BlockStr,
                             Location::new(start, self.line, self.col),
-                            self.raw.get(start_off..end_off).unwrap(),
+                            value,
                         );
-
-                        let substr = self.raw.get(start..end).unwrap();
-                        let newlines = substr.lines().count();
-                        self.line += newlines;
+                        let newlines = substr.matches('\n').count();
+                        if newlines > 0 {
+                            self.line += newlines;
+                            self.col = substr.chars().rev().take_while(|c| *c != '\n').count() + 1;
+                        } else {
+                            self.col += substr.chars().count();
+                        }
                         Ok(tok)
                     }
-                    None => self.make_unmatched_quote_error(),
+                    _ => self.make_unmatched_quote_error(),
                 },
                 None => self.make_unmatched_quote_error(),
             }
@@ -267,17 +325,16 @@ impl<'a> Lexer<'a> {
             match SINGLE.captures_read_at(&mut locations, self.raw, init_pos) {
                 Some(_) => match locations.get(1) {
                     Some((start_off, end_off)) => {
+                        let value = self.slice(start_off, end_off)?;
                         let cur_col = self.col;
-                        match self.input.position(|(i, _)| i == end_off) {
-                            Some(pos) => {
-                                self.position += pos + 1;
-                                self.col += pos + 1;
-                            }
-                            None => (),
+                        if let Some(pos) = self.input.position(|(i, _)| i == end_off) {
+                            self.position += pos + 1;
+                            self.col += pos + 1;
                         }
-                        Ok(Token::Str(
+                        Ok(Token::new(
+                            TokenKind::Str,
                             Location::new(init_pos, self.line, cur_col),
-                            self.raw.get(start_off..end_off).unwrap(),
+                            value,
                         ))
                     }
                     None => self.make_unmatched_quote_error(),
@@ -287,103 +344,50 @@ impl<'a> Lexer<'a> {
         }
     }
 
-    fn lex_bang(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Bang(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_dollar(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Dollar(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_ampersand(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Amp(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_pipe(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Pipe(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_at(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::At(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_close_square(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::CloseSquare(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_open_square(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::OpenSquare(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_close_paren(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::CloseParen(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_open_paren(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::OpenParen(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_close_brace(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::CloseBrace(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_open_brace(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::OpenBrace(self.get_current_location()));
-        self.advance();
-        tok
-    }
-
-    fn lex_equals(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Equals(self.get_current_location()));
+    fn lex_punctuator(&mut self, kind: TokenKind, text: &'static str) -> LexerItem<'a> {
+        let tok = Ok(Token::new(kind, self.get_current_location(), text));
         self.advance();
         tok
     }
 
-    fn lex_colon(&mut self) -> LexerItem<'a> {
-        let tok = Ok(Token::Colon(self.get_current_location()));
-        self.advance();
-        tok
+    fn skip_ignored(&mut self) {
+        while let Some((_, c)) = self.input.peek() {
+            match c {
+                '#' => self.ignore_comment(),
+                ' ' | '\t' | ',' => self.ignore_whitespace(),
+                '\n' => self.ignore_newline(),
+                // A carriage return ends a line on its own, or with the line feed after it
+                '\r' if self.raw.as_bytes().get(self.position + 1) == Some(&b'\n') => {
+                    self.ignore_whitespace()
+                }
+                '\r' => self.ignore_newline(),
+                _ => break,
+            }
+        }
     }
 
-    fn ignore_newline(&mut self) -> LexerItem<'a> {
+    fn ignore_newline(&mut self) {
         self.line += 1;
         self.col = 1;
         self.position += 1;
+        self.ignored += 1;
         self.input.next();
-        self.get_next_token()
     }
 
-    fn ignore_whitespace(&mut self) -> LexerItem<'a> {
+    fn ignore_whitespace(&mut self) {
         self.advance();
-        self.get_next_token()
+        self.ignored += 1;
     }
 
-    fn ignore_comments(&mut self) -> LexerItem<'a> {
-        self.input.next(); // Consume #
-        if let Some((new_line_index, _new_line)) = self.input.find(|(_index, c)| *c == '\n') {
-            self.advance_to(new_line_index);
+    fn ignore_comment(&mut self) {
+        // Leave the line terminator for `skip_ignored`, so that the line is counted.
+        while let Some((_, c)) = self.input.peek() {
+            if *c == '\n' || *c == '\r' {
+                break;
+            }
+            self.advance();
+            self.ignored += 1;
         }
-        self.get_next_token()
     }
 
     fn make_unexpected_character_error(&mut self) -> LexerItem<'a> {
@@ -413,6 +417,17 @@ impl<'a> Lexer<'a> {
         )))
     }
 
+    /// Slices the input, failing instead of panicking when a position falls inside a character.
+    fn slice(&mut self, start: usize, end: usize) -> Result<&'a str, LexError> {
+        match self.raw.get(start..end) {
+            Some(slice) => Ok(slice),
+            None => {
+                self.ended = true;
+                Err(LexError::UnexpectedCharacter(self.get_current_location()))
+            }
+        }
+    }
+
     fn get_current_location(&mut self) -> Location {
         Location::new(self.position, self.line, self.col)
     }
@@ -431,8 +446,8 @@ impl<'a> Lexer<'a> {
     }
 
     fn advance_to(&mut self, pos: usize) {
+        self.col += pos - self.position;
         self.position = pos;
-        self.col = pos;
         self.input.position(|(i, _)| i == pos - 1);
     }
 }
@@ -457,8 +472,8 @@ impl<'a> Iterator for Lexer<'a> {
         } else if !self.initialized {
             debug!("Uninizialized");
             self.initialized = true;
-            Some(Ok(Token::Start))
-        } else if let Some(_) = self.input.peek() {
+            Some(Ok(Token::START))
+        } else if self.input.peek().is_some() {
             let tok = self.get_next_token();
             debug!("Next Token: {:?}", tok);
             debug!("Next char: {:?}", self.input.peek());
@@ -467,7 +482,7 @@ impl<'a> Iterator for Lexer<'a> {
             debug!("Found a None in the string: Ending? {}", self.ended);
             if !self.ended {
                 self.ended = true;
-                Some(Ok(Token::End))
+                Some(Ok(Token::END))
             } else {
                 None
             }
@@ -488,23 +503,198 @@ impl<'a> Iterator for Lexer<'a> {
 /// assert!(tokens.is_ok());
 /// println!("Tokens: {:?}", tokens);
 /// ````
-pub fn tokenize(input: &str) -> Result<Vec<Token>, LexError> {
+pub fn tokenize(input: &str) -> Result<Vec<Token<'_>>, LexError> {
     let state = Lexer::new(input);
     let results: Result<Vec<Token>, LexError> = state.collect();
     results
 }
 
+/// Destruct the string into the tokens of `buffer`, reusing its allocation, and return the number
+/// of ignored characters.
+///
+/// The buffer is cleared first. If an error occurs, it holds the tokens lexed before the error.
+///
+/// # Examples
+/// ```
+/// use syntax::lexer::tokenize_into;
+/// let queries = ["{ hero { name } }", "{ droid(id: 1) { name } }"];
+/// let mut tokens = Vec::new();
+/// for query in &queries {
+///     let ignored = tokenize_into(query, &mut tokens).unwrap();
+///     assert!(ignored > 0);
+/// }
+/// assert_eq!(tokens.len(), 13);
+/// ```
+pub fn tokenize_into<'a>(input: &'a str, buffer: &mut Vec<Token<'a>>) -> Result<usize, LexError> {
+    buffer.clear();
+    let mut lexer = Lexer::new(input);
+    for token in &mut lexer {
+        buffer.push(token?);
+    }
+    Ok(lexer.ignored())
+}
+
+/// What a [`PushLexer`] makes of the input fed to it so far.
+///
+/// [`PushLexer`]: struct.PushLexer.html
+#[derive(Debug, PartialEq)]
+pub enum Lexed<'a> {
+    /// The next token.
+    Token(Token<'a>),
+    /// The error that ends the input.
+    Error(LexError),
+    /// The input fed so far has no complete token left. Feed it more, or finish it.
+    NeedMoreInput,
+    /// The [`End`] token or an error was returned already.
+    ///
+    /// [`End`]: ../token/enum.TokenKind.html#variant.End
+    Ended,
+}
+
+/// A lexer that is fed its input in chunks instead of being given all of it, and lexes across the
+/// ends of the chunks.
+///
+/// Its tokens are the ones a [`Lexer`] makes of the whole input, locations included. They borrow
+/// the lexer, which keeps the input fed to it until the tokens in it are returned.
+///
+/// [`Lexer`]: struct.Lexer.html
+#[derive(Debug, Default)]
+pub struct PushLexer {
+    buffer: String,
+    /// The bytes of the buffer lexed already.
+    offset: usize,
+    /// The bytes dropped from the front of the buffer, to locate its tokens in the whole input.
+    dropped: usize,
+    started: bool,
+    finished: bool,
+    ended: bool,
+    line: usize,
+    col: usize,
+    ignored: usize,
+}
+
+impl PushLexer {
+    /// Creates a lexer that has not been fed yet.
+    pub fn new() -> PushLexer {
+        PushLexer {
+            line: 1,
+            col: 1,
+            ..PushLexer::default()
+        }
+    }
+
+    /// Appends a chunk to the input.
+    pub fn feed(&mut self, chunk: &str) {
+        self.buffer.drain(..self.offset);
+        self.dropped += self.offset;
+        self.offset = 0;
+        self.buffer.push_str(chunk);
+    }
+
+    /// Marks the input as complete, so that the token it ends with and then the [`End`] token are
+    /// returned instead of waiting for more.
+    ///
+    /// [`End`]: ../token/enum.TokenKind.html#variant.End
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// The number of characters skipped so far as whitespace, commas, line terminators or
+    /// comments.
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// Lexes the next token of the input fed so far, unless it may still continue in the next
+    /// chunk.
+    pub fn next_token(&mut self) -> Lexed<'_> {
+        if self.ended {
+            return Lexed::Ended;
+        }
+        if !self.started {
+            self.started = true;
+            return Lexed::Token(Token::START);
+        }
+        let rest = &self.buffer[self.offset..];
+        let mut lexer = Lexer::new(rest);
+        lexer.initialized = true;
+        lexer.line = self.line;
+        lexer.col = self.col;
+        let item = lexer.next().unwrap_or(Ok(Token::END));
+        let end = lexer.input.peek().map_or(rest.len(), |(i, _)| *i);
+        let shift = self.dropped + self.offset;
+        match item {
+            Ok(token) if !self.finished && !Self::is_complete(&token, &rest[end..]) => {
+                Lexed::NeedMoreInput
+            }
+            Err(error) if !self.finished && Self::may_continue(&error, rest) => {
+                Lexed::NeedMoreInput
+            }
+            Ok(mut token) => {
+                self.offset += end;
+                self.line = lexer.line;
+                self.col = lexer.col;
+                self.ignored += lexer.ignored();
+                self.ended = token.kind == TokenKind::End;
+                if token.kind != TokenKind::End {
+                    token.span.absolute_position += shift;
+                }
+                Lexed::Token(token)
+            }
+            Err(mut error) => {
+                self.ended = true;
+                if let LexError::UnmatchedQuote(location)
+                | LexError::UnknownCharacter(location)
+                | LexError::UnexpectedCharacter(location)
+                | LexError::UnableToConvert(location, _) = &mut error
+                {
+                    location.absolute_position += shift;
+                }
+                Lexed::Error(error)
+            }
+        }
+    }
+
+    /// Whether no more input can change a token, given what follows it so far.
+    fn is_complete(token: &Token, after: &str) -> bool {
+        let next = after.chars().next();
+        match token.kind {
+            TokenKind::End => false,
+            TokenKind::Name => next.is_some_and(|c| !c.is_ascii_alphanumeric() && c != '_'),
+            TokenKind::Int | TokenKind::Float => next
+                .is_some_and(|c| !c.is_ascii_alphanumeric() && !matches!(c, '_' | '.' | '+' | '-')),
+            // `""` may be the start of a block string
+            TokenKind::Str if token.text.is_empty() => next.is_some_and(|c| c != '"'),
+            _ => true,
+        }
+    }
+
+    /// Whether an error may be the start of a token cut by the end of the input so far.
+    fn may_continue(error: &LexError, rest: &str) -> bool {
+        match error {
+            LexError::UnmatchedQuote(_) => true,
+            LexError::UnexpectedCharacter(location) | LexError::UnableToConvert(location, _) => {
+                rest.get(location.absolute_position..)
+                    .unwrap_or_default()
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '+' | '-'))
+            }
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::LexError;
-    use crate::token::Token;
+    use crate::token::{Token, TokenKind};
 
     #[test]
     fn lex_empty() {
         let empty = tokenize("");
         assert!(empty.is_ok());
-        assert_eq!(empty.unwrap(), vec![Token::Start, Token::End,]);
+        assert_eq!(empty.unwrap(), vec![Token::START, Token::END,]);
     }
 
     #[test]
@@ -515,9 +705,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Bang(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Bang, Location::new(0, 1, 1), "!"),
+                Token::END,
             ]
         );
     }
@@ -530,9 +720,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Dollar(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Dollar, Location::new(0, 1, 1), "$"),
+                Token::END,
             ]
         );
     }
@@ -544,7 +734,11 @@ mod tests {
         assert!(one.is_ok());
         assert_eq!(
             one.unwrap(),
-            vec![Token::Start, Token::Amp(Location::new(0, 1, 1)), Token::End,]
+            vec![
+                Token::START,
+                Token::new(TokenKind::Amp, Location::new(0, 1, 1), "&"),
+                Token::END,
+            ]
         );
     }
 
@@ -555,7 +749,11 @@ mod tests {
         assert!(one.is_ok());
         assert_eq!(
             one.unwrap(),
-            vec![Token::Start, Token::At(Location::new(0, 1, 1)), Token::End,]
+            vec![
+                Token::START,
+                Token::new(TokenKind::At, Location::new(0, 1, 1), "@"),
+                Token::END,
+            ]
         );
     }
 
@@ -567,9 +765,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Pipe(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Pipe, Location::new(0, 1, 1), "|"),
+                Token::END,
             ]
         );
     }
@@ -582,9 +780,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Colon(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Colon, Location::new(0, 1, 1), ":"),
+                Token::END,
             ]
         );
     }
@@ -597,9 +795,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Equals(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Equals, Location::new(0, 1, 1), "="),
+                Token::END,
             ]
         );
     }
@@ -612,9 +810,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::OpenBrace(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::OpenBrace, Location::new(0, 1, 1), "{"),
+                Token::END,
             ]
         );
     }
@@ -626,9 +824,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::CloseBrace(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::CloseBrace, Location::new(0, 1, 1), "}"),
+                Token::END,
             ]
         );
     }
@@ -641,9 +839,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::OpenParen(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::OpenParen, Location::new(0, 1, 1), "("),
+                Token::END,
             ]
         );
     }
@@ -655,9 +853,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::CloseParen(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::CloseParen, Location::new(0, 1, 1), ")"),
+                Token::END,
             ]
         );
     }
@@ -670,9 +868,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::OpenSquare(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::OpenSquare, Location::new(0, 1, 1), "["),
+                Token::END,
             ]
         );
     }
@@ -684,9 +882,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::CloseSquare(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::CloseSquare, Location::new(0, 1, 1), "]"),
+                Token::END,
             ]
         );
     }
@@ -700,9 +898,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Spread(Location::new(0, 1, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Spread, Location::new(0, 1, 1), "..."),
+                Token::END,
             ]
         );
     }
@@ -715,9 +913,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Int(Location::new(0, 1, 1), 123456i64),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Int, Location::new(0, 1, 1), "123456"),
+                Token::END,
             ]
         );
         let one = tokenize("-9876");
@@ -725,9 +923,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Int(Location::new(0, 1, 1), -9876i64),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Int, Location::new(0, 1, 1), "-9876"),
+                Token::END,
             ]
         );
     }
@@ -740,9 +938,9 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Float(Location::new(0, 1, 1), 1.23456789f64),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Float, Location::new(0, 1, 1), "1.23456789"),
+                Token::END,
             ]
         );
         let one = tokenize("-0.987654321");
@@ -750,9 +948,20 @@ mod tests {
         assert_eq!(
             one.unwrap(),
             vec![
-                Token::Start,
-                Token::Float(Location::new(0, 1, 1), -0.987654321f64),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Float, Location::new(0, 1, 1), "-0.987654321"),
+                Token::END,
+            ]
+        );
+        let exponents = tokenize("1.5e3 2E-2 -3e+1").unwrap();
+        assert_eq!(
+            exponents,
+            vec![
+                Token::START,
+                Token::new(TokenKind::Float, Location::new(0, 1, 1), "1.5e3"),
+                Token::new(TokenKind::Float, Location::new(6, 1, 7), "2E-2"),
+                Token::new(TokenKind::Float, Location::new(11, 1, 12), "-3e+1"),
+                Token::END,
             ]
         );
     }
@@ -765,9 +974,9 @@ mod tests {
         assert_eq!(
             text.unwrap(),
             vec![
-                Token::Start,
-                Token::Str(Location::new(0, 1, 1), "text"),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Str, Location::new(0, 1, 1), "text"),
+                Token::END,
             ]
         );
     }
@@ -784,11 +993,14 @@ text""""#,
         assert_eq!(
             text.unwrap(),
             vec![
-                Token::Start,
-                Token::BlockStr(Location::new(0, 1, 1), "test\n\ntext"),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::BlockStr, Location::new(0, 1, 1), "test\n\ntext"),
+                Token::END,
             ]
         );
+        let quoted = tokenize(r#""""say "hi" or ""bye"" """ a"#).unwrap();
+        assert_eq!(quoted[1].text, r#"say "hi" or ""bye"" "#);
+        assert_eq!(quoted[2].text, "a");
     }
 
     #[test]
@@ -799,14 +1011,66 @@ text""""#,
         assert_eq!(
             text.unwrap(),
             vec![
-                Token::Start,
-                Token::Name(Location::new(0, 1, 1), "name"),
-                Token::Name(Location::new(5, 2, 1), "name_with_underscore"),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "name"),
+                Token::new(
+                    TokenKind::Name,
+                    Location::new(5, 2, 1),
+                    "name_with_underscore"
+                ),
+                Token::END,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_name_with_leading_underscores() {
+        let text = tokenize("__schema _private");
+        assert!(text.is_ok());
+        assert_eq!(
+            text.unwrap(),
+            vec![
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "__schema"),
+                Token::new(TokenKind::Name, Location::new(9, 1, 10), "_private"),
+                Token::END,
             ]
         );
     }
 
+    #[test]
+    fn lex_int_followed_by_float() {
+        let text = tokenize("10 1.5");
+        assert!(text.is_ok());
+        let tokens = text.unwrap();
+        assert_eq!(
+            tokens[1],
+            Token::new(TokenKind::Int, Location::new(0, 1, 1), "10")
+        );
+        assert_eq!(
+            tokens[2],
+            Token::new(TokenKind::Float, Location::new(3, 1, 4), "1.5")
+        );
+    }
+
+    #[test]
+    fn lex_dots_are_not_a_spread() {
+        assert!(tokenize("{ a.b }").is_err());
+    }
+
+    #[test]
+    fn lex_string_followed_by_block_string() {
+        let text = tokenize(r#""a" """b""""#);
+        assert!(text.is_ok());
+        let tokens = text.unwrap();
+        assert_eq!(
+            tokens[1],
+            Token::new(TokenKind::Str, Location::new(0, 1, 1), "a")
+        );
+        assert_eq!(tokens[2].kind, TokenKind::BlockStr);
+        assert_eq!(tokens[2].text, "b");
+    }
+
     #[test]
     fn lex_comment() {
         println!("Test comment");
@@ -816,7 +1080,73 @@ text""""#,
 "#,
         );
         assert!(comments.is_ok());
-        assert_eq!(comments.unwrap(), vec![Token::Start, Token::End,])
+        assert_eq!(comments.unwrap(), vec![Token::START, Token::END,])
+    }
+
+    #[test]
+    fn lex_into_a_buffer() {
+        let mut buffer = Vec::with_capacity(16);
+        let ignored = tokenize_into("a, b # c\n\td", &mut buffer).unwrap();
+        assert_eq!(ignored, 8);
+        assert_eq!(buffer.len(), 5);
+        let capacity = buffer.capacity();
+        assert_eq!(tokenize_into("e", &mut buffer), Ok(0));
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.capacity(), capacity);
+        assert!(tokenize_into("f ~", &mut buffer).is_err());
+        assert_eq!(
+            buffer,
+            vec![
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "f")
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_again_after_reset() {
+        let mut lexer = Lexer::new("a b");
+        assert_eq!(lexer.by_ref().count(), 4);
+        assert_eq!(lexer.ignored(), 1);
+        lexer.reset("c");
+        assert_eq!(lexer.ignored(), 0);
+        let tokens: Result<Vec<Token>, LexError> = lexer.collect();
+        assert_eq!(tokens.unwrap().len(), 3);
+    }
+
+    #[test]
+    fn lex_long_runs_of_whitespace() {
+        let input = format!("a{}b", " \n,".repeat(1_000_000));
+        assert_eq!(tokenize(&input).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn lex_carriage_returns_as_line_terminators() {
+        let tokens = tokenize("a\r\nb # c\rd").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "a"),
+                Token::new(TokenKind::Name, Location::new(3, 2, 1), "b"),
+                Token::new(TokenKind::Name, Location::new(9, 3, 1), "d"),
+                Token::END,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_after_comment() {
+        let tokens = tokenize("a # first\n# second\nb").unwrap();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "a"),
+                Token::new(TokenKind::Name, Location::new(19, 3, 1), "b"),
+                Token::END,
+            ]
+        );
     }
 
     #[test]
@@ -836,24 +1166,24 @@ text""""#,
         assert_eq!(
             query.unwrap(),
             vec![
-                Token::Start,
-                Token::Name(Location::new(0, 1, 1), "query"),
-                Token::OpenBrace(Location::new(6, 1, 7)),
-                Token::Name(Location::new(10, 2, 3), "hero"),
-                Token::OpenBrace(Location::new(15, 2, 8)),
-                Token::Name(Location::new(21, 3, 5), "name"),
-                Token::CloseBrace(Location::new(28, 4, 3)),
-                Token::Name(Location::new(32, 5, 3), "droid"),
-                Token::OpenParen(Location::new(37, 5, 8)),
-                Token::Name(Location::new(38, 5, 9), "id"),
-                Token::Colon(Location::new(40, 5, 11)),
-                Token::Str(Location::new(42, 5, 13), "2000"),
-                Token::CloseParen(Location::new(48, 5, 19)),
-                Token::OpenBrace(Location::new(50, 5, 21)),
-                Token::Name(Location::new(56, 6, 5), "name"),
-                Token::CloseBrace(Location::new(63, 7, 3)),
-                Token::CloseBrace(Location::new(65, 8, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "query"),
+                Token::new(TokenKind::OpenBrace, Location::new(6, 1, 7), "{"),
+                Token::new(TokenKind::Name, Location::new(10, 2, 3), "hero"),
+                Token::new(TokenKind::OpenBrace, Location::new(15, 2, 8), "{"),
+                Token::new(TokenKind::Name, Location::new(21, 3, 5), "name"),
+                Token::new(TokenKind::CloseBrace, Location::new(28, 4, 3), "}"),
+                Token::new(TokenKind::Name, Location::new(32, 5, 3), "droid"),
+                Token::new(TokenKind::OpenParen, Location::new(37, 5, 8), "("),
+                Token::new(TokenKind::Name, Location::new(38, 5, 9), "id"),
+                Token::new(TokenKind::Colon, Location::new(40, 5, 11), ":"),
+                Token::new(TokenKind::Str, Location::new(42, 5, 13), "2000"),
+                Token::new(TokenKind::CloseParen, Location::new(48, 5, 19), ")"),
+                Token::new(TokenKind::OpenBrace, Location::new(50, 5, 21), "{"),
+                Token::new(TokenKind::Name, Location::new(56, 6, 5), "name"),
+                Token::new(TokenKind::CloseBrace, Location::new(63, 7, 3), "}"),
+                Token::new(TokenKind::CloseBrace, Location::new(65, 8, 1), "}"),
+                Token::END,
             ]
         )
     }
@@ -872,29 +1202,29 @@ text""""#,
         assert_eq!(
             t.unwrap(),
             vec![
-                Token::Start,
-                Token::Name(Location::new(0, 1, 1), "type"),
-                Token::Name(Location::new(5, 1, 6), "Query"),
-                Token::OpenBrace(Location::new(11, 1, 12)),
-                Token::Name(Location::new(15, 2, 3), "hero"),
-                Token::OpenParen(Location::new(19, 2, 7)),
-                Token::Name(Location::new(20, 2, 8), "episode"),
-                Token::Colon(Location::new(27, 2, 15)),
-                Token::Name(Location::new(29, 2, 17), "Episode"),
-                Token::CloseParen(Location::new(36, 2, 24)),
-                Token::Colon(Location::new(37, 2, 25)),
-                Token::Name(Location::new(39, 2, 27), "Character"),
-                Token::Name(Location::new(51, 3, 3), "droid"),
-                Token::OpenParen(Location::new(56, 3, 8)),
-                Token::Name(Location::new(57, 3, 9), "id"),
-                Token::Colon(Location::new(59, 3, 11)),
-                Token::Name(Location::new(61, 3, 13), "ID"),
-                Token::Bang(Location::new(63, 3, 15)),
-                Token::CloseParen(Location::new(64, 3, 16)),
-                Token::Colon(Location::new(65, 3, 17)),
-                Token::Name(Location::new(67, 3, 19), "Droid"),
-                Token::CloseBrace(Location::new(73, 4, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "type"),
+                Token::new(TokenKind::Name, Location::new(5, 1, 6), "Query"),
+                Token::new(TokenKind::OpenBrace, Location::new(11, 1, 12), "{"),
+                Token::new(TokenKind::Name, Location::new(15, 2, 3), "hero"),
+                Token::new(TokenKind::OpenParen, Location::new(19, 2, 7), "("),
+                Token::new(TokenKind::Name, Location::new(20, 2, 8), "episode"),
+                Token::new(TokenKind::Colon, Location::new(27, 2, 15), ":"),
+                Token::new(TokenKind::Name, Location::new(29, 2, 17), "Episode"),
+                Token::new(TokenKind::CloseParen, Location::new(36, 2, 24), ")"),
+                Token::new(TokenKind::Colon, Location::new(37, 2, 25), ":"),
+                Token::new(TokenKind::Name, Location::new(39, 2, 27), "Character"),
+                Token::new(TokenKind::Name, Location::new(51, 3, 3), "droid"),
+                Token::new(TokenKind::OpenParen, Location::new(56, 3, 8), "("),
+                Token::new(TokenKind::Name, Location::new(57, 3, 9), "id"),
+                Token::new(TokenKind::Colon, Location::new(59, 3, 11), ":"),
+                Token::new(TokenKind::Name, Location::new(61, 3, 13), "ID"),
+                Token::new(TokenKind::Bang, Location::new(63, 3, 15), "!"),
+                Token::new(TokenKind::CloseParen, Location::new(64, 3, 16), ")"),
+                Token::new(TokenKind::Colon, Location::new(65, 3, 17), ":"),
+                Token::new(TokenKind::Name, Location::new(67, 3, 19), "Droid"),
+                Token::new(TokenKind::CloseBrace, Location::new(73, 4, 1), "}"),
+                Token::END,
             ]
         )
     }
@@ -915,21 +1245,21 @@ text""""#,
         assert_eq!(
             fragment.unwrap(),
             vec![
-                Token::Start,
-                Token::Name(Location::new(0, 1, 1), "query"),
-                Token::OpenBrace(Location::new(6, 1, 7)),
-                Token::Name(Location::new(10, 2, 3), "hero"),
-                Token::OpenBrace(Location::new(15, 2, 8)),
-                Token::Name(Location::new(21, 3, 5), "name"),
-                Token::Spread(Location::new(30, 4, 5)),
-                Token::Name(Location::new(34, 4, 9), "on"),
-                Token::Name(Location::new(37, 4, 12), "Human"),
-                Token::OpenBrace(Location::new(43, 4, 18)),
-                Token::Name(Location::new(51, 5, 7), "height"),
-                Token::CloseBrace(Location::new(62, 6, 5)),
-                Token::CloseBrace(Location::new(66, 7, 3)),
-                Token::CloseBrace(Location::new(68, 8, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::Name, Location::new(0, 1, 1), "query"),
+                Token::new(TokenKind::OpenBrace, Location::new(6, 1, 7), "{"),
+                Token::new(TokenKind::Name, Location::new(10, 2, 3), "hero"),
+                Token::new(TokenKind::OpenBrace, Location::new(15, 2, 8), "{"),
+                Token::new(TokenKind::Name, Location::new(21, 3, 5), "name"),
+                Token::new(TokenKind::Spread, Location::new(30, 4, 5), "..."),
+                Token::new(TokenKind::Name, Location::new(34, 4, 9), "on"),
+                Token::new(TokenKind::Name, Location::new(37, 4, 12), "Human"),
+                Token::new(TokenKind::OpenBrace, Location::new(43, 4, 18), "{"),
+                Token::new(TokenKind::Name, Location::new(51, 5, 7), "height"),
+                Token::new(TokenKind::CloseBrace, Location::new(62, 6, 5), "}"),
+                Token::new(TokenKind::CloseBrace, Location::new(66, 7, 3), "}"),
+                Token::new(TokenKind::CloseBrace, Location::new(68, 8, 1), "}"),
+                Token::END,
             ]
         );
     }
@@ -979,13 +1309,13 @@ text""""#,
         assert_eq!(
             query.unwrap(),
             vec![
-                Token::Start,
-                Token::OpenBrace(Location::new(0, 1, 1)),
-                Token::Name(Location::new(4, 2, 3), "one"),
-                Token::Name(Location::new(10, 3, 3), "two"),
-                Token::Name(Location::new(17, 4, 3), "three"),
-                Token::CloseBrace(Location::new(26, 5, 1)),
-                Token::End,
+                Token::START,
+                Token::new(TokenKind::OpenBrace, Location::new(0, 1, 1), "{"),
+                Token::new(TokenKind::Name, Location::new(4, 2, 3), "one"),
+                Token::new(TokenKind::Name, Location::new(10, 3, 3), "two"),
+                Token::new(TokenKind::Name, Location::new(17, 4, 3), "three"),
+                Token::new(TokenKind::CloseBrace, Location::new(26, 5, 1), "}"),
+                Token::END,
             ]
         );
     }
@@ -1009,23 +1339,28 @@ type Obj {
         assert_eq!(
             strings.unwrap(),
             vec![
-                Token::Start,
-                Token::BlockStr(
+                Token::START,
+                Token::new(
+                    TokenKind::BlockStr,
                     Location::new(1, 2, 1),
                     r#"
 This is a generic object comment
 They can be multiple lines
 "#
                 ),
-                Token::Name(Location::new(70, 6, 1), "type"),
-                Token::Name(Location::new(75, 6, 6), "Obj"),
-                Token::OpenBrace(Location::new(79, 6, 10)),
-                Token::Str(Location::new(83, 7, 3), "This is the name of the object"),
-                Token::Name(Location::new(108, 8, 3), "name"),
-                Token::Colon(Location::new(112, 8, 7)),
-                Token::Name(Location::new(114, 8, 9), "String"),
-                Token::CloseBrace(Location::new(121, 9, 1)),
-                Token::End,
+                Token::new(TokenKind::Name, Location::new(70, 6, 1), "type"),
+                Token::new(TokenKind::Name, Location::new(75, 6, 6), "Obj"),
+                Token::new(TokenKind::OpenBrace, Location::new(79, 6, 10), "{"),
+                Token::new(
+                    TokenKind::Str,
+                    Location::new(83, 7, 3),
+                    "This is the name of the object"
+                ),
+                Token::new(TokenKind::Name, Location::new(108, 8, 3), "name"),
+                Token::new(TokenKind::Colon, Location::new(112, 8, 7), ":"),
+                Token::new(TokenKind::Name, Location::new(114, 8, 9), "String"),
+                Token::new(TokenKind::CloseBrace, Location::new(121, 9, 1), "}"),
+                Token::END,
             ]
         );
     }
@@ -1049,14 +1384,195 @@ And a final multiline string
         assert_eq!(
             strings.unwrap(),
             vec![
-                Token::Start,
-                Token::BlockStr(Location::new(1, 2, 1), "\nThis is a multiline string\n"),
-                Token::Name(Location::new(36, 5, 1), "name"),
-                Token::BlockStr(Location::new(41, 6, 1), "Followed by a single line"),
-                Token::Name(Location::new(73, 7, 1), "id"),
-                Token::BlockStr(Location::new(76, 8, 1), "\nAnd a final multiline string\n"),
-                Token::End,
+                Token::START,
+                Token::new(
+                    TokenKind::BlockStr,
+                    Location::new(1, 2, 1),
+                    "\nThis is a multiline string\n"
+                ),
+                Token::new(TokenKind::Name, Location::new(36, 5, 1), "name"),
+                Token::new(
+                    TokenKind::BlockStr,
+                    Location::new(41, 6, 1),
+                    "Followed by a single line"
+                ),
+                Token::new(TokenKind::Name, Location::new(73, 7, 1), "id"),
+                Token::new(
+                    TokenKind::BlockStr,
+                    Location::new(76, 8, 1),
+                    "\nAnd a final multiline string\n"
+                ),
+                Token::END,
             ]
         )
     }
+
+    /// The tokens, locations included, of a push lexer fed `chunks`.
+    fn push(chunks: &[&str]) -> Vec<String> {
+        let mut lexer = PushLexer::new();
+        let mut lexed = Vec::new();
+        for chunk in chunks.iter().map(Some).chain(Some(None)) {
+            match chunk {
+                Some(chunk) => lexer.feed(chunk),
+                None => lexer.finish(),
+            }
+            loop {
+                match lexer.next_token() {
+                    Lexed::Token(token) => lexed.push(format!("{:?}", token)),
+                    Lexed::Error(error) => lexed.push(format!("{:?}", error)),
+                    Lexed::NeedMoreInput | Lexed::Ended => break,
+                }
+            }
+        }
+        lexed
+    }
+
+    #[test]
+    fn it_lexes_across_the_ends_of_chunks() {
+        let input = r#"query Hero($id: ID = 12, $rate: Float = 1.5) @cached {
+  # The hero
+  hero(id: $id, name: "R2", bio: """Beep""", empty: "") { ...Parts }
+}"#;
+        let whole = Lexer::new(input)
+            .map(|item| match item {
+                Ok(token) => format!("{:?}", token),
+                Err(error) => format!("{:?}", error),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(push(&[input]), whole);
+        for split in 0..=input.len() {
+            assert_eq!(
+                push(&[&input[..split], &input[split..]]),
+                whole,
+                "split at {}",
+                split
+            );
+        }
+        let characters = (0..input.len())
+            .map(|i| &input[i..i + 1])
+            .collect::<Vec<_>>();
+        assert_eq!(push(&characters), whole);
+    }
+
+    #[test]
+    fn it_waits_for_the_end_of_cut_tokens() {
+        let mut lexer = PushLexer::new();
+        lexer.feed(r#"{ a(b: "one"#);
+        assert_eq!(lexer.next_token(), Lexed::Token(Token::START));
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(
+                TokenKind::OpenBrace,
+                Location::new(0, 1, 1),
+                "{"
+            ))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(TokenKind::Name, Location::new(2, 1, 3), "a"))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(
+                TokenKind::OpenParen,
+                Location::new(3, 1, 4),
+                "("
+            ))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(TokenKind::Name, Location::new(4, 1, 5), "b"))
+        );
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(TokenKind::Colon, Location::new(5, 1, 6), ":"))
+        );
+        assert_eq!(lexer.next_token(), Lexed::NeedMoreInput);
+        assert_eq!(lexer.next_token(), Lexed::NeedMoreInput);
+        lexer.feed(r#"" ..."#);
+        match lexer.next_token() {
+            Lexed::Token(token) => {
+                assert_eq!(
+                    token,
+                    Token::new(TokenKind::Str, Location::ignored(), "one")
+                );
+                assert_eq!(token.location(), Location::new(7, 1, 8));
+            }
+            lexed => panic!("Lexed {:?}", lexed),
+        }
+        assert_eq!(
+            lexer.next_token(),
+            Lexed::Token(Token::new(
+                TokenKind::Spread,
+                Location::new(13, 1, 14),
+                "..."
+            ))
+        );
+        assert_eq!(lexer.next_token(), Lexed::NeedMoreInput);
+        lexer.finish();
+        assert_eq!(lexer.next_token(), Lexed::Token(Token::END));
+        assert_eq!(lexer.next_token(), Lexed::Ended);
+        assert_eq!(lexer.ignored(), 3);
+    }
+
+    #[test]
+    fn it_reports_errors_once_the_input_cannot_complete_them() {
+        assert_eq!(
+            push(&["{ %", " }"]).last().unwrap(),
+            "UnknownCharacter(Location { absolute_position: 2, line: 1, column: 3 })"
+        );
+        assert_eq!(
+            push(&["a ..", " b"]).last().unwrap(),
+            "UnexpectedCharacter(Location { absolute_position: 2, line: 1, column: 3 })"
+        );
+        assert_eq!(
+            push(&["a \"unm", "atched"]).last().unwrap(),
+            "UnmatchedQuote(Location { absolute_position: 2, line: 1, column: 4 })"
+        );
+    }
+
+    #[test]
+    fn lex_multi_byte_characters() {
+        let tokens = tokenize(r#"{ a(b: "héllo ☃", c: """ünï 🎉""") d }"#).unwrap();
+        assert_eq!(
+            tokens[6],
+            Token::new(TokenKind::Str, Location::new(7, 1, 8), "héllo ☃")
+        );
+        assert_eq!(tokens[9].text, "ünï 🎉");
+        assert_eq!(tokens[9].location(), Location::new(24, 1, 22));
+        assert_eq!(
+            tokens[11],
+            Token::new(TokenKind::Name, Location::new(43, 1, 35), "d")
+        );
+        assert_eq!(
+            tokenize("a é"),
+            Err(LexError::UnknownCharacter(Location::new(2, 1, 3)))
+        );
+    }
+
+    #[test]
+    fn lex_never_panics_on_multi_byte_characters() {
+        let inputs = [
+            "é",
+            "\"é",
+            "\"\"\"é",
+            "1é",
+            "-é",
+            "1.é",
+            ".é",
+            "..é",
+            "\"a\u{301}\"b",
+            "{ é: \"ö\" }",
+            "\"\"\"🎉\"\"\"🎉",
+            "# é\né",
+            "$é @é",
+        ];
+        for input in inputs.iter() {
+            for (i, _) in input.char_indices() {
+                let _ = tokenize(&input[..i]);
+                let _ = tokenize(&input[i..]);
+            }
+            let _ = crate::parse(input);
+        }
+    }
 }
@@ -7,6 +7,22 @@
 //! A syntax package for GraphQL parsing and manipulation tokens into a GraphQL Document.
 //! This package adheres to the [GraphQL Spec](http://spec.graphql.org/June2018/).
 //!
+//! # Features
+//!
+//! - `json` (default): introspection, conversions from introspection results and between
+//!   values and JSON, the coercion of variable values, mock responses and the JSON of token
+//!   streams, using `serde_json`.
+//! - `default-schema`: `Document::default()` and the `DocumentBuilder` for the database's default
+//!   schema with its custom scalars.
+//! - `cache`: `cache::DocumentCache`, a least recently used cache of parsed documents.
+//! - `config`: `config::GraphQLConfig`, which finds and reads `.graphqlrc` and
+//!   `graphql.config.*` files.
+//! - `persisted`: `persisted::PersistedOperations`, manifests of allowed operations. Implies
+//!   `json`.
+//! - `serde`: `serde::Serialize` for the document and its nodes, and `serde::Serialize` and
+//!   `serde::Deserialize` for `token::OwnedToken`.
+//! - `wasm`: `wasm`, `wasm-bindgen` bindings to parse, validate and print documents from
+//!   JavaScript. Implies `serde` and `json`.
 //!
 
 #![warn(trivial_casts, trivial_numeric_casts, unstable_features)]
@@ -15,33 +31,109 @@
 #[macro_use]
 extern crate lazy_static;
 mod ast;
+#[cfg(feature = "cache")]
+pub mod cache;
+pub mod codegen;
+#[cfg(feature = "json")]
+pub mod coercion;
+pub mod completion;
+#[cfg(feature = "config")]
+pub mod config;
+pub mod coordinate;
+pub mod corpus;
+pub mod diff;
 pub mod document;
 pub mod error;
+pub mod federation;
+#[doc(hidden)]
+pub mod fuzz;
+pub mod graphql_type;
+pub mod incremental;
+pub mod intern;
+#[cfg(feature = "json")]
+pub mod introspection;
+#[cfg(feature = "json")]
+pub mod json;
 pub mod lexer;
+pub mod line_index;
+pub mod lossless;
 pub mod macros;
-mod nodes;
+pub mod merge;
+#[cfg(feature = "json")]
+pub mod mock;
+// Public for the code generated by the `syntax-macros` crate, but not part of the public API.
+#[doc(hidden)]
+pub mod nodes;
+pub mod options;
+#[cfg(feature = "persisted")]
+pub mod persisted;
+pub mod printer;
+pub mod schema;
+pub mod shape;
+pub mod stats;
+pub mod stream;
 pub mod token;
+pub mod transform;
+pub mod usage;
 mod validation;
+pub mod validator;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
-use ast::AST;
+use ast::Ast;
 use document::Document;
 use error::ParseResult;
+use lexer::Lexer;
+use options::ParseOptions;
+use stats::ParseMetrics;
+use std::time::Instant;
 
 /// Parse a string into a GraphQL Document.
 /// This is a potentially heavy, synchronous operation.
-pub fn parse<'a>(query: &'a str) -> ParseResult<Document> {
-    let mut ast = AST::new(query)?;
-    let document = ast.parse()?;
-    Ok(document)
+pub fn parse(query: &str) -> ParseResult<Document> {
+    intern::with_interner(|| {
+        let mut ast = Ast::new(query)?;
+        let document = ast.parse()?;
+        Ok(document)
+    })
+}
+
+/// Parse a string into a GraphQL Document, with [`ParseOptions`] mirroring graphql-js.
+///
+/// [`ParseOptions`]: options/struct.ParseOptions.html
+pub fn parse_with_options(query: &str, options: &ParseOptions) -> ParseResult<Document> {
+    parse_with_metrics(query, options).map(|(document, _)| document)
+}
+
+/// Parse a string into a GraphQL Document like [`parse_with_options`], along with the
+/// [`ParseMetrics`] of the document when the `metrics` option is set, for servers to log and
+/// alert on expensive documents.
+///
+/// [`parse_with_options`]: fn.parse_with_options.html
+/// [`ParseMetrics`]: stats/struct.ParseMetrics.html
+pub fn parse_with_metrics(
+    query: &str,
+    options: &ParseOptions,
+) -> ParseResult<(Document, Option<ParseMetrics>)> {
+    intern::with_interner(|| {
+        let start = Instant::now();
+        let mut ast = Ast::with_options(Lexer::new(query), *options);
+        let document = ast.parse()?;
+        let metrics = options
+            .metrics
+            .then(|| ParseMetrics::new(&document, ast.token_count(), start.elapsed()));
+        Ok((document, metrics))
+    })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::ParseError;
+    use crate::intern::Symbol;
     use crate::nodes::object_type_extension::*;
     use crate::nodes::*;
-    use crate::token::{Location, Token};
+    use crate::token::{Location, Token, TokenKind};
     use std::sync::Arc;
 
     #[test]
@@ -77,6 +169,7 @@ mod tests {
                         directives: None,
                         fields: vec![
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("name"),
                                 arguments: None,
@@ -85,6 +178,7 @@ mod tests {
                                 })
                             },
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("id"),
                                 arguments: None,
@@ -95,6 +189,7 @@ mod tests {
                                 )))
                             },
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("strs"),
                                 arguments: None,
@@ -105,6 +200,7 @@ mod tests {
                                 })
                             },
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("refIds"),
                                 arguments: None,
@@ -117,6 +213,7 @@ mod tests {
                                 )))
                             },
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("someIds"),
                                 arguments: None,
@@ -127,6 +224,7 @@ mod tests {
                                 )))
                             },
                             FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("arg"),
                                 arguments: Some(vec![
@@ -136,9 +234,7 @@ mod tests {
                                         input_type: TypeNode::Named(NamedTypeNode {
                                             name: NameNode::from("Int")
                                         }),
-                                        default_value: Some(ValueNode::Int(IntValueNode {
-                                            value: 42
-                                        })),
+                                        default_value: Some(ValueNode::Int(IntValueNode::from(42))),
                                         directives: None,
                                     },
                                     InputValueDefinitionNode {
@@ -184,32 +280,35 @@ type Obj {
                 definitions: vec![DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
                     TypeDefinitionNode::Object(ObjectTypeDefinitionNode {
                         description: Some(
-                            StringValueNode::new(Token::BlockStr(
+                            StringValueNode::new(Token::new(
+                                TokenKind::BlockStr,
                                 Location::ignored(),
                                 "\nThis is a generic object comment\nThey can be multiple lines\n"
                             ))
                             .unwrap()
                         ),
                         name: NameNode {
-                            value: String::from("Obj")
+                            value: Symbol::from("Obj")
                         },
                         interfaces: None,
                         directives: None,
                         fields: vec![FieldDefinitionNode {
+                            directives: None,
                             description: Some(
-                                StringValueNode::new(Token::BlockStr(
+                                StringValueNode::new(Token::new(
+                                    TokenKind::BlockStr,
                                     Location::ignored(),
                                     "This is the name of the object"
                                 ))
                                 .unwrap()
                             ),
                             name: NameNode {
-                                value: String::from("name")
+                                value: Symbol::from("name")
                             },
                             arguments: None,
                             field_type: TypeNode::Named(NamedTypeNode {
                                 name: NameNode {
-                                    value: String::from("String")
+                                    value: Symbol::from("String")
                                 }
                             })
                         },],
@@ -240,42 +339,42 @@ type Obj {
                     TypeDefinitionNode::Enum(EnumTypeDefinitionNode {
                         description: None,
                         name: NameNode {
-                            value: String::from("VEHICLE_TYPE")
+                            value: Symbol::from("VEHICLE_TYPE")
                         },
                         directives: None,
                         values: vec![
                             EnumValueDefinitionNode {
                                 description: None,
                                 name: NameNode {
-                                    value: String::from("SEDAN")
+                                    value: Symbol::from("SEDAN")
                                 },
                                 directives: None,
                             },
                             EnumValueDefinitionNode {
                                 description: None,
                                 name: NameNode {
-                                    value: String::from("SUV")
+                                    value: Symbol::from("SUV")
                                 },
                                 directives: None,
                             },
                             EnumValueDefinitionNode {
                                 description: None,
                                 name: NameNode {
-                                    value: String::from("COMPACT")
+                                    value: Symbol::from("COMPACT")
                                 },
                                 directives: None,
                             },
                             EnumValueDefinitionNode {
                                 description: None,
                                 name: NameNode {
-                                    value: String::from("TRUCK")
+                                    value: Symbol::from("TRUCK")
                                 },
                                 directives: None,
                             },
                             EnumValueDefinitionNode {
                                 description: None,
                                 name: NameNode {
-                                    value: String::from("HYBRID")
+                                    value: Symbol::from("HYBRID")
                                 },
                                 directives: None,
                             },
@@ -351,6 +450,7 @@ union Pic =
                         ]),
                         directives: None,
                         fields: vec![FieldDefinitionNode {
+                            directives: None,
                             description: None,
                             arguments: None,
                             name: NameNode::from("id"),
@@ -390,6 +490,7 @@ union Pic =
                             },
                         ]),
                         fields: vec![FieldDefinitionNode {
+                            directives: None,
                             description: None,
                             arguments: None,
                             name: NameNode::from("id"),
@@ -432,6 +533,7 @@ interface Void @depricated {
                             description: None,
                             directives: None,
                             fields: vec![FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("name"),
                                 arguments: None,
@@ -448,6 +550,7 @@ interface Void @depricated {
                                 arguments: None
                             }]),
                             fields: vec![FieldDefinitionNode {
+                                directives: None,
                                 description: None,
                                 name: NameNode::from("void"),
                                 arguments: None,
@@ -480,6 +583,7 @@ input Point {
                     TypeDefinitionNode::Input(InputTypeDefinitionNode {
                         description: None,
                         name: NameNode::from("Point"),
+                        directives: None,
                         fields: vec![
                             InputValueDefinitionNode {
                                 description: None,
@@ -567,12 +671,14 @@ scalar Time @format(pattern: "HH:mm:ss")"#,
                             }]),
                             fields: Some(vec![
                                 FieldDefinitionNode {
+                                    directives: None,
                                     arguments: None,
                                     description: None,
                                     name: NameNode::from("createdOn"),
                                     field_type: TypeNode::Named(NamedTypeNode::from("DateTime")),
                                 },
                                 FieldDefinitionNode {
+                                    directives: None,
                                     arguments: None,
                                     description: None,
                                     name: NameNode::from("updatedOn"),
@@ -655,11 +761,11 @@ scalar Time @format(pattern: "HH:mm:ss")"#,
                                     arguments: Some(vec![
                                         Argument {
                                             name: NameNode::from("height"),
-                                            value: ValueNode::Int(IntValueNode { value: 100 }),
+                                            value: ValueNode::Int(IntValueNode::from(100)),
                                         },
                                         Argument {
                                             name: NameNode::from("width"),
-                                            value: ValueNode::Int(IntValueNode { value: 100 }),
+                                            value: ValueNode::Int(IntValueNode::from(100)),
                                         }
                                     ]),
                                     directives: None,
@@ -884,6 +990,7 @@ fragment friendFields on User @traverse(depth: 1) {
                     DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(
                         FragmentDefinitionNode {
                             name: NameNode::from("Name"),
+                            variables: None,
                             node_type: NamedTypeNode::from("User"),
                             directives: None,
                             selections: vec![Selection::Field(FieldNode::from("name"))],
@@ -892,12 +999,13 @@ fragment friendFields on User @traverse(depth: 1) {
                     DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(
                         FragmentDefinitionNode {
                             name: NameNode::from("friendFields"),
+                            variables: None,
                             node_type: NamedTypeNode::from("User"),
                             directives: Some(vec![DirectiveNode {
                                 name: NameNode::from("traverse"),
                                 arguments: Some(vec![Argument {
                                     name: NameNode::from("depth"),
-                                    value: ValueNode::Int(IntValueNode { value: 1 })
+                                    value: ValueNode::Int(IntValueNode::from(1))
                                 }])
                             }]),
                             selections: vec![
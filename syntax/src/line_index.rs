@@ -0,0 +1,210 @@
+//! Conversion between absolute byte offsets and line/column positions.
+//!
+//! A [`LineIndex`] is built once per input string and can then answer position queries without
+//! re-lexing. This is useful for tools that only hold on to [`Location::absolute_position`], or
+//! for editors that speak in terms of zero based, UTF-16 encoded positions (such as LSP clients).
+//!
+//! Lines and columns follow the same convention as the [`Lexer`]: both start at 1 and columns are
+//! counted in characters. `\n`, `\r\n` and `\r` are all treated as line terminators, as defined by
+//! the GraphQL Spec.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::line_index::LineIndex;
+//! use syntax::token::Location;
+//!
+//! let index = LineIndex::new("type Obj {\n  id: ID\n}");
+//! assert_eq!(index.location(13), Some(Location::new(13, 2, 3)));
+//! assert_eq!(index.offset(2, 3), Some(13));
+//! ```
+//!
+//! [`LineIndex`]: struct.LineIndex.html
+//! [`Location::absolute_position`]: ../token/struct.Location.html#structfield.absolute_position
+//! [`Lexer`]: ../lexer/struct.Lexer.html
+
+use crate::token::Location;
+
+/// An index of the line starts of an input string.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    input: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds the index for the provided input string.
+    pub fn new(input: &'a str) -> LineIndex<'a> {
+        let bytes = input.as_bytes();
+        let mut line_starts = vec![0];
+        for (i, b) in bytes.iter().enumerate() {
+            match b {
+                b'\n' => line_starts.push(i + 1),
+                b'\r' if bytes.get(i + 1) != Some(&b'\n') => line_starts.push(i + 1),
+                _ => (),
+            }
+        }
+        LineIndex { input, line_starts }
+    }
+
+    /// The number of lines in the input. An empty string has a single line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the [`Location`] of a byte offset, or `None` if the offset is past the end of the
+    /// input or does not fall on a character boundary.
+    ///
+    /// [`Location`]: ../token/struct.Location.html
+    pub fn location(&self, offset: usize) -> Option<Location> {
+        let line = self.line_of(offset)?;
+        let line_start = self.line_starts[line];
+        let column = self.input[line_start..offset].chars().count() + 1;
+        Some(Location::new(offset, line + 1, column))
+    }
+
+    /// Returns the byte offset of a 1 based line and column, or `None` if the position is not in
+    /// the input.
+    pub fn offset(&self, line: usize, column: usize) -> Option<usize> {
+        if column == 0 {
+            return None;
+        }
+        let (start, end) = self.line_bounds(line.checked_sub(1)?)?;
+        let text = &self.input[start..end];
+        if column - 1 == text.chars().count() {
+            return Some(end);
+        }
+        text.char_indices()
+            .nth(column - 1)
+            .map(|(offset, _)| start + offset)
+    }
+
+    /// Returns the zero based line and UTF-16 column of a byte offset, which is how LSP clients
+    /// describe positions.
+    pub fn utf16_position(&self, offset: usize) -> Option<(usize, usize)> {
+        let line = self.line_of(offset)?;
+        let line_start = self.line_starts[line];
+        let column = self.input[line_start..offset]
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        Some((line, column))
+    }
+
+    /// The inverse of [`utf16_position`]: converts a zero based line and UTF-16 column to a byte
+    /// offset. Columns pointing into the middle of a surrogate pair are rejected.
+    ///
+    /// [`utf16_position`]: #method.utf16_position
+    pub fn utf16_offset(&self, line: usize, column: usize) -> Option<usize> {
+        let (start, end) = self.line_bounds(line)?;
+        let mut units = 0;
+        for (offset, c) in self.input[start..end].char_indices() {
+            if units == column {
+                return Some(start + offset);
+            }
+            units += c.len_utf16();
+            if units > column {
+                return None;
+            }
+        }
+        if units == column {
+            Some(end)
+        } else {
+            None
+        }
+    }
+
+    fn line_of(&self, offset: usize) -> Option<usize> {
+        if offset > self.input.len() || !self.input.is_char_boundary(offset) {
+            return None;
+        }
+        match self.line_starts.binary_search(&offset) {
+            Ok(line) => Some(line),
+            Err(next_line) => Some(next_line - 1),
+        }
+    }
+
+    /// The byte range of a zero based line, excluding its terminator.
+    fn line_bounds(&self, line: usize) -> Option<(usize, usize)> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(next) => {
+                let terminated = &self.input[start..*next];
+                next - (terminated.len() - terminated.trim_end_matches(&['\r', '\n'][..]).len())
+            }
+            None => self.input.len(),
+        };
+        Some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_indexes_an_empty_string() {
+        let index = LineIndex::new("");
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(index.location(0), Some(Location::new(0, 1, 1)));
+        assert_eq!(index.location(1), None);
+        assert_eq!(index.offset(1, 1), Some(0));
+    }
+
+    #[test]
+    fn it_maps_offsets_to_locations() {
+        let index = LineIndex::new("type Obj {\n  id: ID\n}\n");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.location(5), Some(Location::new(5, 1, 6)));
+        assert_eq!(index.location(10), Some(Location::new(10, 1, 11)));
+        assert_eq!(index.location(11), Some(Location::new(11, 2, 1)));
+        assert_eq!(index.location(20), Some(Location::new(20, 3, 1)));
+        assert_eq!(index.location(22), Some(Location::new(22, 4, 1)));
+    }
+
+    #[test]
+    fn it_maps_locations_to_offsets() {
+        let index = LineIndex::new("type Obj {\n  id: ID\n}");
+        assert_eq!(index.offset(1, 1), Some(0));
+        assert_eq!(index.offset(2, 3), Some(13));
+        assert_eq!(index.offset(2, 9), Some(19));
+        assert_eq!(index.offset(2, 10), None);
+        assert_eq!(index.offset(4, 1), None);
+        assert_eq!(index.offset(0, 1), None);
+        assert_eq!(index.offset(1, 0), None);
+    }
+
+    #[test]
+    fn it_handles_carriage_returns() {
+        let index = LineIndex::new("a\r\nb\rc");
+        assert_eq!(index.line_count(), 3);
+        assert_eq!(index.location(3), Some(Location::new(3, 2, 1)));
+        assert_eq!(index.location(5), Some(Location::new(5, 3, 1)));
+        assert_eq!(index.offset(1, 2), Some(1));
+        assert_eq!(index.offset(2, 2), Some(4));
+    }
+
+    #[test]
+    fn it_counts_columns_in_characters() {
+        let input = "\"héllo\" 😀 x";
+        let index = LineIndex::new(input);
+        let x = input.find('x').unwrap();
+        assert_eq!(index.location(x), Some(Location::new(x, 1, 11)));
+        assert_eq!(index.offset(1, 11), Some(x));
+        // Inside of the multi-byte `é`
+        assert_eq!(index.location(3), None);
+    }
+
+    #[test]
+    fn it_converts_utf16_positions() {
+        let input = "query {\n  😀: a\n}";
+        let index = LineIndex::new(input);
+        let colon = input.find(':').unwrap();
+        assert_eq!(index.utf16_position(colon), Some((1, 4)));
+        assert_eq!(index.utf16_offset(1, 4), Some(colon));
+        // The middle of the surrogate pair
+        assert_eq!(index.utf16_offset(1, 3), None);
+        assert_eq!(index.utf16_offset(1, 7), Some(input.rfind('\n').unwrap()));
+        assert_eq!(index.utf16_offset(3, 0), None);
+    }
+}
@@ -0,0 +1,273 @@
+//! Parses a document along with its comments and commas, and prints it back with them.
+//!
+//! [`parse`] drops the comments and commas of a document, which the GraphQL Spec ignores, so the
+//! [`printer`] can only print it without them. [`parse_lossless`] keeps them apart as the
+//! [`Trivia`] of the document, and [`print_lossless`] prints the document in the layout of the
+//! printer with its comments and commas back where they were, which makes
+//! `print_lossless(parse_lossless(x))` return `x` for a document already in that layout. Strings
+//! are printed as they were written, escape sequences included, either way.
+//!
+//! A comment after a token on the same line stays at the end of the line it is printed on, and
+//! a comment on its own line stays on its own line, indented like the line that follows it. A
+//! comma stays right after the token it follows, and the printer's own commas are left out where
+//! the document had none.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::lossless::{parse_lossless, print_lossless};
+//!
+//! let source = "# The users\ntype User {\n  id: ID! # never null\n  name: String,\n}\n";
+//! let (document, trivia) = parse_lossless(source).unwrap();
+//! assert_eq!(print_lossless(&document, &trivia), source);
+//!
+//! let (document, trivia) = parse_lossless("type User { # the user\n id: ID! }").unwrap();
+//! assert_eq!(
+//!     print_lossless(&document, &trivia),
+//!     "type User { # the user\n  id: ID!\n}\n"
+//! );
+//! ```
+//!
+//! [`parse`]: ../fn.parse.html
+//! [`printer`]: ../printer/index.html
+//! [`parse_lossless`]: fn.parse_lossless.html
+//! [`Trivia`]: struct.Trivia.html
+//! [`print_lossless`]: fn.print_lossless.html
+
+use crate::document::Document;
+use crate::error::ParseResult;
+use crate::printer::print;
+use std::ops::Range;
+
+/// The comments and commas of a document, which its nodes do not hold.
+///
+/// They are kept by the position of the tokens they sit between, so a document changed after it
+/// was parsed may have them printed next to other tokens.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Trivia {
+    gaps: Vec<Gap>,
+}
+
+/// The comments and commas between two tokens.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Gap {
+    /// Whether a comma follows the token before
+    comma: bool,
+    /// The comment on the line of the token before
+    trailing: Option<String>,
+    /// The comments on lines of their own, and the blank lines among and after them
+    lines: Vec<Option<String>>,
+}
+
+/// Parses a document like [`parse`], keeping its comments and commas apart.
+///
+/// [`parse`]: ../fn.parse.html
+pub fn parse_lossless(source: &str) -> ParseResult<(Document, Trivia)> {
+    let document = crate::parse(source)?;
+    let gaps = gaps(source)
+        .into_iter()
+        .enumerate()
+        .map(|(index, gap)| Gap::new(&source[gap], index == 0))
+        .collect();
+    Ok((document, Trivia { gaps }))
+}
+
+/// Prints a document like [`print`], with the comments and commas of its trivia.
+///
+/// [`print`]: ../printer/fn.print.html
+pub fn print_lossless(document: &Document, trivia: &Trivia) -> String {
+    let printed = print(document);
+    let mut output = String::with_capacity(printed.len());
+    let mut token_start = 0;
+    for (index, printed_gap) in gaps(&printed).into_iter().enumerate() {
+        output.push_str(&printed[token_start..printed_gap.start]);
+        match trivia.gaps.get(index) {
+            Some(gap) => gap.merge(&printed[printed_gap.clone()], &mut output),
+            None => output.push_str(&printed[printed_gap.clone()]),
+        }
+        token_start = printed_gap.end;
+    }
+    output
+}
+
+impl Gap {
+    /// Reads the comments and commas of the text between two tokens, or before the first one.
+    fn new(text: &str, first: bool) -> Gap {
+        let mut gap = Gap::default();
+        let segments: Vec<&str> = text.split('\n').collect();
+        let last = segments.len() - 1;
+        for (index, segment) in segments.iter().enumerate() {
+            let (blank, comment) = match segment.find('#') {
+                Some(start) => (&segment[..start], Some(segment[start..].trim_end())),
+                None => (*segment, None),
+            };
+            gap.comma |= blank.contains(',');
+            match comment {
+                Some(comment) if index == 0 && !first => gap.trailing = Some(comment.to_string()),
+                Some(comment) => gap.lines.push(Some(comment.to_string())),
+                // A blank line, the last segment being the indentation of the next token
+                None if index > 0
+                    && index < last
+                    && blank.trim().is_empty()
+                    && gap.lines.last() != Some(&None) =>
+                {
+                    gap.lines.push(None)
+                }
+                None => {}
+            }
+        }
+        gap
+    }
+
+    /// Writes the text the printer put between two tokens, with the comments and commas of the
+    /// gap.
+    fn merge(&self, printed: &str, output: &mut String) {
+        let printed = printed.replace(',', "");
+        let line_start = output.rfind('\n').map_or(0, |newline| newline + 1);
+        let line = &output[line_start..];
+        let indent = line[..line.len() - line.trim_start().len()].to_string();
+        let line_is_blank = line.trim().is_empty();
+        if self.comma {
+            output.push(',');
+        }
+        if let Some(comment) = &self.trailing {
+            output.push(' ');
+            output.push_str(comment);
+        }
+        let (head, indent) = match printed.rfind('\n') {
+            Some(newline) => (&printed[..=newline], &printed[newline + 1..]),
+            None if self.trailing.is_none() && self.lines.iter().all(Option::is_none) => {
+                output.push_str(&printed);
+                return;
+            }
+            // A comment ends the line, so the next token goes on another
+            None if self.trailing.is_some() || !line_is_blank => ("\n", indent.as_str()),
+            None => ("", indent.as_str()),
+        };
+        output.push_str(head);
+        for line in &self.lines {
+            match line {
+                Some(comment) => {
+                    output.push_str(indent);
+                    output.push_str(comment);
+                    output.push('\n');
+                }
+                // The printer may have left a blank line already
+                None if output.ends_with("\n\n") => {}
+                None => output.push('\n'),
+            }
+        }
+        output.push_str(indent);
+    }
+}
+
+/// The texts between the tokens of a source, starting with the one before the first token and
+/// ending with the one after the last: whitespace, commas and comments.
+///
+/// Tokens are told apart from what is between them, without being lexed: strings, names and
+/// numbers, `...` and punctuation.
+fn gaps(source: &str) -> Vec<Range<usize>> {
+    let bytes = source.as_bytes();
+    let mut gaps = Vec::new();
+    let (mut gap_start, mut i) = (0, 0);
+    while i < bytes.len() {
+        match bytes[i] {
+            b' ' | b'\t' | b'\r' | b'\n' | b',' => i += 1,
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            _ => {
+                gaps.push(gap_start..i);
+                i = token_end(source, i);
+                gap_start = i;
+            }
+        }
+    }
+    gaps.push(gap_start..bytes.len());
+    gaps
+}
+
+/// Where the token starting at `start` ends.
+fn token_end(source: &str, start: usize) -> usize {
+    let bytes = source.as_bytes();
+    let rest = &source[start..];
+    if rest.starts_with("\"\"\"") {
+        let mut i = start + 3;
+        while i < bytes.len() {
+            if bytes[i..].starts_with(b"\\\"\"\"") {
+                i += 4;
+            } else if bytes[i..].starts_with(b"\"\"\"") {
+                return i + 3;
+            } else {
+                i += 1;
+            }
+        }
+        bytes.len()
+    } else if rest.starts_with('"') {
+        let mut i = start + 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' => i += 2,
+                b'"' => return i + 1,
+                b'\n' => return i,
+                _ => i += 1,
+            }
+        }
+        bytes.len()
+    } else if rest.starts_with("...") {
+        start + 3
+    } else if rest.starts_with(|c| "!$&():=@[]{}|".contains(c)) {
+        start + 1
+    } else {
+        let length = rest
+            .find(|c| " \t\r\n,#\"!$&():=@[]{}|".contains(c))
+            .unwrap_or(rest.len());
+        start + length.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(source: &str) -> String {
+        let (document, trivia) = parse_lossless(source).unwrap();
+        print_lossless(&document, &trivia)
+    }
+
+    #[test]
+    fn it_prints_formatted_documents_with_their_trivia() {
+        let source = r##"# The schema
+# of the users
+
+"A user"
+type User implements Node { # trailing
+  # The id
+  id: ID!
+  friends(first: Int = 10 after: String): [User] # last
+}
+
+query Users($first: Int, $escaped: String = "a \"#\" b") {
+  users(first: $first, filter: {tags: ["a", "b"] role: ADMIN}) {
+    ...user
+
+    # after a blank line
+    name
+  }
+}
+# at the end
+"##;
+        assert_eq!(round_trip(source), source);
+    }
+
+    #[test]
+    fn it_formats_documents_around_their_comments() {
+        assert_eq!(
+            round_trip("{ user(id: 1 # the id\n) { name } } # done"),
+            "{\n  user(id: 1 # the id\n  ) {\n    name\n  }\n} # done\n"
+        );
+        assert_eq!(round_trip("{ a,\n# b\n b }"), "{\n  a,\n  # b\n  b\n}\n");
+    }
+}
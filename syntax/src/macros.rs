@@ -3,6 +3,9 @@
 /// gql  will take a document string and turn it into
 /// a [`Document`].
 ///
+/// The document is parsed at runtime. The `gql!` macro of the `syntax-macros` crate parses it at
+/// compile time instead, and reports syntax errors as compile errors.
+///
 /// [`Document`]: ../document/struct.Document.html
 ///
 /// # Examples
@@ -0,0 +1,209 @@
+//! Merges the fields selected under the same response key.
+//!
+//! A selection set may select a field several times under the same response key, directly or
+//! through fragments, and the response has a single entry for them. The execution algorithm of
+//! the GraphQL Spec executes such fields once, with the selections of all of them:
+//! [`merge_selection_sets`] is its `MergeSelectionSets`, which executors use, and the validator
+//! checks with [`can_merge`] that the fields it merges are the same field given the same
+//! arguments. [`merge_fields`] rewrites a selection set with each of these fields written once.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::merge::merge_fields;
+//! use syntax::nodes::{DefinitionNode, ExecutableDefinitionNode};
+//! use syntax::printer::print;
+//!
+//! let mut document = syntax::parse("{ me { id } me { name } me { id } }").unwrap();
+//! if let DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) =
+//!     &mut document.definitions[0]
+//! {
+//!     let query = operation.definition_mut();
+//!     query.selections = merge_fields(&query.selections);
+//! }
+//! assert_eq!(print(&document), "{\n  me {\n    id\n    name\n  }\n}\n");
+//! ```
+//!
+//! [`merge_selection_sets`]: fn.merge_selection_sets.html
+//! [`can_merge`]: fn.can_merge.html
+//! [`merge_fields`]: fn.merge_fields.html
+
+use crate::nodes::*;
+
+/// The key of a field in the response: its alias, or its name when it has none.
+pub fn response_key(field: &FieldNode) -> &str {
+    match &field.alias {
+        Some(alias) => &alias.value,
+        None => &field.name.value,
+    }
+}
+
+/// The selection sets of fields selected under the same response key, in the order of the
+/// fields, which are executed together as one.
+pub fn merge_selection_sets<'a>(fields: &[&'a FieldNode]) -> Vec<&'a [Selection]> {
+    fields
+        .iter()
+        .filter_map(|field| field.selections.as_deref())
+        .collect()
+}
+
+/// Whether two fields selected under the same response key can be executed as one: they are the
+/// same field, given the same arguments, in any order.
+///
+/// The fields of their selection sets, which are merged in turn, are not compared.
+pub fn can_merge(field: &FieldNode, other: &FieldNode) -> bool {
+    field.name.value == other.name.value && same_arguments(&field.arguments, &other.arguments)
+}
+
+/// Whether two lists of arguments give the same values to the same arguments.
+fn same_arguments(arguments: &Option<Arguments>, others: &Option<Arguments>) -> bool {
+    let arguments = arguments.as_deref().unwrap_or_default();
+    let others = others.as_deref().unwrap_or_default();
+    arguments.len() == others.len()
+        && arguments.iter().all(|argument| {
+            others.iter().any(|other| {
+                other.name.value == argument.name.value && other.value == argument.value
+            })
+        })
+}
+
+/// A selection set in which the fields selected more than once under the same response key are
+/// written once, with the selections of all of them merged in turn, where they first are.
+///
+/// Only fields that [`can_merge`] and have the same directives are merged, as the others do not
+/// stand for a single field. Fragments are kept, the selection sets within them merged.
+///
+/// [`can_merge`]: fn.can_merge.html
+pub fn merge_fields(selections: &[Selection]) -> Vec<Selection> {
+    let mut merged: Vec<Selection> = Vec::with_capacity(selections.len());
+    for selection in selections {
+        let field = match selection {
+            Selection::Field(field) => field,
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                let mut inline = inline.clone();
+                inline.selections = merge_fields(&inline.selections);
+                merged.push(Selection::Fragment(FragmentSpread::Inline(inline)));
+                continue;
+            }
+            Selection::Fragment(FragmentSpread::Node(_)) => {
+                merged.push(selection.clone());
+                continue;
+            }
+        };
+        let same = merged.iter_mut().find_map(|selection| match selection {
+            Selection::Field(existing)
+                if response_key(existing) == response_key(field)
+                    && can_merge(existing, field)
+                    && existing.directives == field.directives =>
+            {
+                Some(existing)
+            }
+            _ => None,
+        });
+        match same {
+            Some(existing) => {
+                if let Some(selections) = &field.selections {
+                    existing
+                        .selections
+                        .get_or_insert_with(Vec::new)
+                        .extend(selections.iter().cloned());
+                }
+            }
+            None => merged.push(selection.clone()),
+        }
+    }
+    for selection in &mut merged {
+        if let Selection::Field(field) = selection {
+            if let Some(selections) = &field.selections {
+                field.selections = Some(merge_fields(selections));
+            }
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::print;
+
+    fn selections(source: &str) -> Vec<Selection> {
+        match parse(source).unwrap().definitions.remove(0) {
+            DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                operation.definition().selections.clone()
+            }
+            _ => panic!("Expected an operation"),
+        }
+    }
+
+    fn fields(selections: &[Selection]) -> Vec<&FieldNode> {
+        selections
+            .iter()
+            .filter_map(|selection| match selection {
+                Selection::Field(field) => Some(field),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn it_merges_the_selection_sets_of_fields() {
+        let selections = selections("{ me { id } me { name } me }");
+        let merged = merge_selection_sets(&fields(&selections));
+        assert_eq!(merged.len(), 2);
+        assert_eq!(fields(merged[0])[0].name.value, "id");
+        assert_eq!(fields(merged[1])[0].name.value, "name");
+    }
+
+    #[test]
+    fn it_tells_which_fields_can_merge() {
+        let selections = selections(
+            "{ a: user(id: 1, name: \"x\") a: user(name: \"x\", id: 1) a: user(id: 2) a: users }",
+        );
+        let fields = fields(&selections);
+        assert!(can_merge(fields[0], fields[1]));
+        assert!(!can_merge(fields[0], fields[2]));
+        assert!(!can_merge(fields[0], fields[3]));
+    }
+
+    #[test]
+    fn it_writes_merged_fields_once() {
+        let mut document = parse(
+            "{ me { id friends { id } } me { friends { name } id } other: me { id } me(x: 1) { id }
+              ... on Query { me { id } me { name } } }",
+        )
+        .unwrap();
+        if let DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) =
+            &mut document.definitions[0]
+        {
+            let query = operation.definition_mut();
+            query.selections = merge_fields(&query.selections);
+        }
+        assert_eq!(
+            print(&document),
+            "{
+  me {
+    id
+    friends {
+      id
+      name
+    }
+  }
+  other: me {
+    id
+  }
+  me(x: 1) {
+    id
+  }
+  ... on Query {
+    me {
+      id
+      name
+    }
+  }
+}
+"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+//! Generates mock data for the response to an operation, to stub a GraphQL backend in tests.
+//!
+//! A [`Mocker`] gives every field an operation selects a value of its type, with the [shape] of
+//! the response: lists have [`MockOptions::list_length`] items, fields of abstract types are
+//! objects of one of their possible types, with `__typename` and the fields selected on it, and
+//! enums take one of their values. Scalars are generated by the function registered for them
+//! with [`Mocker::register_scalar`], or else by a default one. The schema may be built from an
+//! introspection result, which is all a client has of the schema of a server.
+//!
+//! The values are pseudorandom but deterministic: each follows from the [`MockOptions::seed`] and
+//! from its path in the response, so the same operation gets the same data every time, and
+//! selecting another field does not change the values of the others.
+//!
+//! # Example
+//!
+//! ```
+//! use serde_json::json;
+//! use syntax::mock::Mocker;
+//! use syntax::schema::Schema;
+//!
+//! let schema_document =
+//!     syntax::parse("type Query { users: [User!]! } type User { id: ID! email: Email } scalar Email")
+//!         .unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let mut mocker = Mocker::new(&schema);
+//! mocker.register_scalar("Email", |random| json!(format!("user{}@example.com", random % 100)));
+//!
+//! let query = syntax::parse("{ users { id email } }").unwrap();
+//! let data = mocker.mock(&query.operations()[0]).unwrap();
+//! assert_eq!(data["users"].as_array().unwrap().len(), 2);
+//! assert!(data["users"][0]["email"].as_str().unwrap().ends_with("@example.com"));
+//! assert_eq!(data, mocker.mock(&query.operations()[0]).unwrap());
+//! ```
+//!
+//! [`Mocker`]: struct.Mocker.html
+//! [shape]: ../shape/index.html
+//! [`MockOptions::list_length`]: struct.MockOptions.html#structfield.list_length
+//! [`MockOptions::seed`]: struct.MockOptions.html#structfield.seed
+//! [`Mocker::register_scalar`]: struct.Mocker.html#method.register_scalar
+
+use crate::document::OperationWithFragments;
+use crate::error::ValidationError;
+use crate::nodes::*;
+use crate::schema::Schema;
+use crate::shape::{response_shape, FieldShape};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Options for the [`Mocker`].
+///
+/// [`Mocker`]: struct.Mocker.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MockOptions {
+    /// The seed the values follow from: other seeds give other values
+    pub seed: u64,
+    /// The number of items of every list
+    pub list_length: usize,
+}
+
+impl Default for MockOptions {
+    fn default() -> Self {
+        MockOptions {
+            seed: 0,
+            list_length: 2,
+        }
+    }
+}
+
+/// Generates the values of a scalar from a pseudorandom number. Functions from a number to a
+/// JSON value are scalar generators.
+pub trait ScalarGenerator {
+    /// Generates a value of the scalar from a pseudorandom number, which is the same for the
+    /// same seed and path in the response.
+    fn generate(&self, random: u64) -> Value;
+}
+
+impl<F> ScalarGenerator for F
+where
+    F: Fn(u64) -> Value,
+{
+    fn generate(&self, random: u64) -> Value {
+        self(random)
+    }
+}
+
+/// Generates mock responses to the operations of a schema.
+pub struct Mocker<'a> {
+    schema: &'a Schema<'a>,
+    options: MockOptions,
+    scalars: HashMap<String, Box<dyn ScalarGenerator>>,
+}
+
+impl fmt::Debug for Mocker<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut scalars: Vec<&String> = self.scalars.keys().collect();
+        scalars.sort();
+        f.debug_struct("Mocker")
+            .field("schema", &self.schema)
+            .field("options", &self.options)
+            .field("scalars", &scalars)
+            .finish()
+    }
+}
+
+impl<'a> Mocker<'a> {
+    /// A mocker for the schema, with the default options.
+    pub fn new(schema: &'a Schema<'a>) -> Mocker<'a> {
+        Mocker::with_options(schema, MockOptions::default())
+    }
+
+    /// A mocker for the schema, with options.
+    pub fn with_options(schema: &'a Schema<'a>, options: MockOptions) -> Mocker<'a> {
+        Mocker {
+            schema,
+            options,
+            scalars: HashMap::new(),
+        }
+    }
+
+    /// Generates the values of a scalar, built-in or custom, with a generator, in place of the
+    /// one registered before.
+    pub fn register_scalar<G>(&mut self, name: &str, generator: G)
+    where
+        G: ScalarGenerator + 'static,
+    {
+        self.scalars.insert(name.into(), Box::new(generator));
+    }
+
+    /// The `data` of a response to an operation, as returned by [`Document::operations`].
+    ///
+    /// The operation is expected to be valid against the schema, as for its [`response_shape`],
+    /// which reports the fields the schema does not define.
+    ///
+    /// [`Document::operations`]: ../document/struct.Document.html#method.operations
+    /// [`response_shape`]: ../shape/fn.response_shape.html
+    pub fn mock(&self, operation: &OperationWithFragments<'a>) -> Result<Value, ValidationError> {
+        let shape = response_shape(self.schema, operation)?;
+        let root = shape.first().map_or("", |field| field.parent_type);
+        Ok(self.object(root, &shape, ""))
+    }
+
+    /// The fields selected on an object of a type.
+    fn object(&self, type_name: &str, fields: &[FieldShape<'a>], path: &str) -> Value {
+        let mut object = Map::new();
+        for field in fields {
+            let applies = field
+                .type_condition
+                .is_none_or(|condition| self.schema.is_subtype(type_name, condition));
+            if !applies {
+                continue;
+            }
+            let path = format!("{}.{}", path, field.response_key);
+            let value = if field.name == "__typename" {
+                json!(type_name)
+            } else {
+                self.value(field, field.field_type, &path)
+            };
+            object.insert(field.response_key.to_string(), value);
+        }
+        Value::Object(object)
+    }
+
+    fn value(&self, field: &FieldShape<'a>, field_type: &TypeNode, path: &str) -> Value {
+        let random = self.random(path);
+        match field_type {
+            TypeNode::NonNull(inner) => self.value(field, inner, path),
+            TypeNode::List(list) => Value::Array(
+                (0..self.options.list_length)
+                    .map(|index| self.value(field, &list.list_type, &format!("{}.{}", path, index)))
+                    .collect(),
+            ),
+            TypeNode::Named(named) => {
+                let type_name = named.name.value.as_str();
+                if let Some(generator) = self.scalars.get(type_name) {
+                    return generator.generate(random);
+                }
+                match self.schema.get_type(type_name) {
+                    Some(TypeDefinitionNode::Object(_)) => {
+                        self.object(type_name, &field.children, path)
+                    }
+                    Some(TypeDefinitionNode::Interface(_)) | Some(TypeDefinitionNode::Union(_)) => {
+                        let possible = self.schema.possible_types(type_name);
+                        let concrete = match possible.len() {
+                            0 => type_name,
+                            count => possible[(random % count as u64) as usize],
+                        };
+                        self.object(concrete, &field.children, path)
+                    }
+                    Some(TypeDefinitionNode::Enum(enum_type)) if !enum_type.values.is_empty() => {
+                        let count = enum_type.values.len() as u64;
+                        json!(enum_type.values[(random % count) as usize]
+                            .name
+                            .value
+                            .as_str())
+                    }
+                    _ => scalar(type_name, field.name, random),
+                }
+            }
+        }
+    }
+
+    /// The pseudorandom number of a path in the response: its FNV-1a hash, mixed with the seed
+    /// by the finalizer of SplitMix64.
+    fn random(&self, path: &str) -> u64 {
+        let hash = path.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        let mut random = (hash ^ self.options.seed).wrapping_add(0x9e37_79b9_7f4a_7c15);
+        random = (random ^ (random >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        random = (random ^ (random >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        random ^ (random >> 31)
+    }
+}
+
+/// The default value of a scalar with no generator: small numbers, strings naming the field and
+/// custom scalars their scalar.
+fn scalar(type_name: &str, field_name: &str, random: u64) -> Value {
+    match type_name {
+        "Int" => json!(random % 100),
+        "Float" => json!((random % 10_000) as f64 / 100.0),
+        "Boolean" => json!(random.is_multiple_of(2)),
+        "ID" => json!((random % 100_000).to_string()),
+        "String" => json!(format!("{} {}", field_name, random % 1000)),
+        _ => json!(format!("{} {}", type_name, random % 1000)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SCHEMA: &str =
+        "type Query { node(id: ID!): Node users(first: Int): [User!]! search: [Result] }
+        interface Node { id: ID! }
+        type User implements Node { id: ID! name: String role: Role age: Int score: Float }
+        type Admin implements Node { id: ID! level: Int! }
+        union Result = User | Admin
+        enum Role { ADMIN USER }
+        scalar Date";
+
+    #[test]
+    fn it_mocks_the_shape_of_the_response() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let options = MockOptions {
+            seed: 7,
+            list_length: 3,
+        };
+        let mocker = Mocker::with_options(&schema, options);
+        let query = parse(
+            "{ users { id name role age score } search { __typename ... on User { name } ... on Admin { level } } }",
+        )
+        .unwrap();
+        let data = mocker.mock(&query.operations()[0]).unwrap();
+        let users = data["users"].as_array().unwrap();
+        assert_eq!(users.len(), 3);
+        for user in users {
+            assert!(user["id"].is_string());
+            assert!(user["name"].as_str().unwrap().starts_with("name "));
+            assert!(["ADMIN", "USER"].contains(&user["role"].as_str().unwrap()));
+            assert!(user["age"].as_u64().unwrap() < 100);
+            assert!(user["score"].is_f64());
+        }
+        let results = data["search"].as_array().unwrap();
+        for result in results {
+            match result["__typename"].as_str().unwrap() {
+                "User" => assert_eq!(result.as_object().unwrap().len(), 2),
+                "Admin" => assert!(result["level"].is_u64()),
+                other => panic!("Unexpected type {}", other),
+            }
+        }
+        assert_eq!(data, mocker.mock(&query.operations()[0]).unwrap());
+        let other_seed = Mocker::with_options(&schema, MockOptions { seed: 8, ..options });
+        assert_ne!(data, other_seed.mock(&query.operations()[0]).unwrap());
+    }
+
+    #[test]
+    fn it_generates_scalars_with_the_registered_generators() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let mut mocker = Mocker::new(&schema);
+        mocker.register_scalar("ID", |random: u64| json!(format!("id-{}", random % 10)));
+        let query = parse("{ node(id: 1) { id ... on User { name } } users { id } }").unwrap();
+        let data = mocker.mock(&query.operations()[0]).unwrap();
+        assert!(data["node"]["id"].as_str().unwrap().starts_with("id-"));
+        assert!(data["users"][1]["id"].as_str().unwrap().starts_with("id-"));
+
+        let only_users = parse("{ users { id } }").unwrap();
+        assert_eq!(
+            mocker.mock(&only_users.operations()[0]).unwrap()["users"],
+            data["users"]
+        );
+    }
+}
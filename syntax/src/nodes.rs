@@ -1,7 +1,9 @@
 use crate::error::{ParseError, ParseResult, ValidationError};
-use crate::token::Token;
+use crate::intern::{self, Symbol};
+use crate::token::{Token, TokenKind};
 use crate::validation::{self, ValidExtensionNode, ValidNode, ValidationResult};
 use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 pub mod object_type_extension;
@@ -13,18 +15,23 @@ pub trait NodeWithFields {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NameNode {
-    pub value: String,
+    pub value: Symbol,
 }
 impl NameNode {
+    pub fn as_str(&self) -> &str {
+        self.value.as_str()
+    }
+
     /// Generates a new name node from the token.
-    /// If the token is not of type Token::Name,
+    /// If the token is not of kind `TokenKind::Name`,
     /// an error is thrown
     pub fn new(token: Token) -> ParseResult<NameNode> {
-        match token {
-            Token::Name(_, value) => Ok(NameNode {
-                value: value.to_owned(),
+        match token.kind {
+            TokenKind::Name => Ok(NameNode {
+                value: intern::intern(token.text),
             }),
             _ => Err(ParseError::UnexpectedToken {
                 expected: "Token<Name>".into(),
@@ -38,7 +45,7 @@ impl NameNode {
 impl From<&str> for NameNode {
     fn from(name: &str) -> NameNode {
         NameNode {
-            value: String::from(name),
+            value: Symbol::from(name),
         }
     }
 }
@@ -46,9 +53,9 @@ impl From<&str> for NameNode {
 impl<'a> TryFrom<Token<'a>> for NameNode {
     type Error = ParseError;
     fn try_from(token: Token<'a>) -> Result<Self, Self::Error> {
-        match token {
-            Token::Name(_, value) => Ok(NameNode {
-                value: value.to_owned(),
+        match token.kind {
+            TokenKind::Name => Ok(NameNode {
+                value: intern::intern(token.text),
             }),
             _ => Err(ParseError::UnexpectedToken {
                 expected: "Token<Name>".into(),
@@ -59,7 +66,8 @@ impl<'a> TryFrom<Token<'a>> for NameNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct StringValueNode {
     pub value: String,
     block: bool,
@@ -67,14 +75,10 @@ pub struct StringValueNode {
 
 impl StringValueNode {
     pub fn new(token: Token) -> ParseResult<StringValueNode> {
-        match token {
-            Token::Str(_, val) => Ok(StringValueNode {
-                value: val.to_owned(),
-                block: false,
-            }),
-            Token::BlockStr(_, val) => Ok(StringValueNode {
-                value: val.to_owned(),
-                block: true,
+        match token.kind {
+            TokenKind::Str | TokenKind::BlockStr => Ok(StringValueNode {
+                value: token.text.to_owned(),
+                block: token.kind == TokenKind::BlockStr,
             }),
             _ => Err(ParseError::UnexpectedToken {
                 expected: "Token<Str> or Token<BlockStr>".into(),
@@ -90,9 +94,14 @@ impl StringValueNode {
             block,
         }
     }
+
+    pub fn is_block(&self) -> bool {
+        self.block
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct NamedTypeNode {
     pub name: NameNode,
 }
@@ -100,7 +109,7 @@ pub struct NamedTypeNode {
 impl NamedTypeNode {
     /// Generates a NamedTypeNode from the token.
     /// NameNode will throw an error if the token is not
-    /// of type Token::Name
+    /// of kind `TokenKind::Name`
     pub fn new(tok: Token) -> ParseResult<NamedTypeNode> {
         Ok(NamedTypeNode {
             name: NameNode::try_from(tok)?,
@@ -123,7 +132,8 @@ impl<'a> TryFrom<Token<'a>> for NamedTypeNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ListTypeNode {
     pub list_type: Arc<TypeNode>,
 }
@@ -136,14 +146,16 @@ impl ListTypeNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeNode {
     Named(NamedTypeNode),
     List(ListTypeNode),
     NonNull(Arc<TypeNode>),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VariableNode {
     pub name: NameNode,
 }
@@ -164,43 +176,124 @@ impl From<&str> for VariableNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// An integer literal.
+///
+/// GraphQL integers may be written wider than any Rust integer, so the literal keeps its digits,
+/// with their sign, and the validator checks them against the range of the type they are given
+/// for.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct IntValueNode {
-    pub value: i64,
+    pub value: String,
+}
+
+impl IntValueNode {
+    /// The value of the literal, when it fits an `i64`.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.value.parse().ok()
+    }
+
+    /// The value of the literal, when it fits an `i128`.
+    pub fn as_i128(&self) -> Option<i128> {
+        self.value.parse().ok()
+    }
+
+    /// The value of the literal, when it fits a `u128`.
+    pub fn as_u128(&self) -> Option<u128> {
+        self.value.parse().ok()
+    }
 }
 
-#[derive(Debug, PartialEq)]
+impl From<i64> for IntValueNode {
+    fn from(value: i64) -> Self {
+        Self {
+            value: value.to_string(),
+        }
+    }
+}
+
+/// A float literal.
+///
+/// Two floats are equal, and hash the same, when they are the same number, however they are
+/// written: `1.10` equals `1.1`, `-0.0` equals `0.0`, and every `NaN` equals every other.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FloatValueNode {
     pub value: f64,
+    /// The literal as it is written in the source, which the printer prints as is. `None` for the
+    /// floats that were not parsed
+    pub lexeme: Option<String>,
+}
+
+impl FloatValueNode {
+    /// The bits of the value, the same for every zero and for every `NaN`.
+    fn bits(&self) -> u64 {
+        if self.value == 0.0 {
+            0
+        } else if self.value.is_nan() {
+            f64::NAN.to_bits()
+        } else {
+            self.value.to_bits()
+        }
+    }
+}
+
+impl From<f64> for FloatValueNode {
+    fn from(value: f64) -> Self {
+        Self {
+            value,
+            lexeme: None,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+impl PartialEq for FloatValueNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl Eq for FloatValueNode {}
+
+impl Hash for FloatValueNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.bits().hash(state)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct BooleanValueNode {
     pub value: bool,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct EnumValueNode {
     pub value: String,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ListValueNode {
     pub values: Vec<ValueNode>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObjectFieldNode {
     pub name: NameNode,
     pub value: ValueNode,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObjectValueNode {
     pub fields: Vec<ObjectFieldNode>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ValueNode {
     Variable(VariableNode),
     Int(IntValueNode),
@@ -213,7 +306,8 @@ pub enum ValueNode {
     Object(ObjectValueNode),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DirectiveNode {
     pub name: NameNode,
     pub arguments: Option<Arguments>,
@@ -228,7 +322,8 @@ impl DirectiveNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InputValueDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -263,14 +358,16 @@ impl InputValueDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct VariableDefinitionNode {
     pub variable: VariableNode,
     pub variable_type: TypeNode,
     pub default_value: Option<ValueNode>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Argument {
     pub name: NameNode,
     pub value: ValueNode,
@@ -282,13 +379,14 @@ pub type ArgumentDefinitions = Vec<InputValueDefinitionNode>;
 pub type Directives = Vec<DirectiveNode>;
 pub type Variables = Vec<VariableDefinitionNode>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FieldDefinitionNode {
     pub description: Description,
     pub name: NameNode,
     pub arguments: Option<ArgumentDefinitions>,
     pub field_type: TypeNode,
-    // directives: Vec<DirectiveDefinitionNode>,
+    pub directives: Option<Directives>,
 }
 
 impl FieldDefinitionNode {
@@ -303,11 +401,18 @@ impl FieldDefinitionNode {
             name: NameNode::new(name)?,
             arguments,
             field_type,
+            directives: None,
         })
     }
+
+    pub fn with_directives(&mut self, directives: Option<Directives>) -> &mut Self {
+        self.directives = directives;
+        self
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct EnumValueDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -328,20 +433,23 @@ impl EnumValueDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Operation {
     Query,
     Mutation,
     Subscription,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct OperationTypeDefinitionNode {
     pub operation: Operation,
     pub node_type: NamedTypeNode,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SchemaDefinitionNode {
     pub description: Description,
     pub directives: Option<Directives>,
@@ -357,7 +465,21 @@ impl SchemaDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl Default for SchemaDefinitionNode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SchemaExtensionNode {
+    pub directives: Option<Directives>,
+    pub operations: Option<Vec<OperationTypeDefinitionNode>>,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ScalarTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -390,7 +512,8 @@ impl From<&str> for ScalarTypeDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObjectTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -440,10 +563,12 @@ impl NodeWithFields for ObjectTypeDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InputTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
+    pub directives: Option<Directives>,
     pub fields: Vec<InputValueDefinitionNode>,
 }
 
@@ -452,17 +577,24 @@ impl InputTypeDefinitionNode {
         Ok(InputTypeDefinitionNode {
             name: NameNode::new(name_tok)?,
             description,
+            directives: None,
             fields: Vec::new(),
         })
     }
 
+    pub fn with_directives(&mut self, directives: Option<Directives>) -> &mut Self {
+        self.directives = directives;
+        self
+    }
+
     pub fn with_fields(&mut self, fields: Vec<InputValueDefinitionNode>) -> &mut Self {
         self.fields = fields;
         self
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InterfaceTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -490,7 +622,8 @@ impl InterfaceTypeDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct EnumTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -514,7 +647,8 @@ impl EnumTypeDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct UnionTypeDefinitionNode {
     pub description: Description,
     pub name: NameNode,
@@ -538,7 +672,8 @@ impl UnionTypeDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeDefinitionNode {
     Scalar(ScalarTypeDefinitionNode),
     Object(ObjectTypeDefinitionNode),
@@ -548,21 +683,169 @@ pub enum TypeDefinitionNode {
     Input(InputTypeDefinitionNode),
 }
 
-#[derive(Debug, PartialEq)]
+impl TypeDefinitionNode {
+    pub fn name(&self) -> &str {
+        match self {
+            TypeDefinitionNode::Scalar(node) => &node.name.value,
+            TypeDefinitionNode::Object(node) => &node.name.value,
+            TypeDefinitionNode::Interface(node) => &node.name.value,
+            TypeDefinitionNode::Union(node) => &node.name.value,
+            TypeDefinitionNode::Enum(node) => &node.name.value,
+            TypeDefinitionNode::Input(node) => &node.name.value,
+        }
+    }
+
+    pub fn description(&self) -> &Description {
+        match self {
+            TypeDefinitionNode::Scalar(node) => &node.description,
+            TypeDefinitionNode::Object(node) => &node.description,
+            TypeDefinitionNode::Interface(node) => &node.description,
+            TypeDefinitionNode::Union(node) => &node.description,
+            TypeDefinitionNode::Enum(node) => &node.description,
+            TypeDefinitionNode::Input(node) => &node.description,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DirectiveLocation {
+    Query,
+    Mutation,
+    Subscription,
+    Field,
+    FragmentDefinition,
+    FragmentSpread,
+    InlineFragment,
+    VariableDefinition,
+    Schema,
+    Scalar,
+    Object,
+    FieldDefinition,
+    ArgumentDefinition,
+    Interface,
+    Union,
+    Enum,
+    EnumValue,
+    InputObject,
+    InputFieldDefinition,
+}
+
+impl DirectiveLocation {
+    pub const ALL: [DirectiveLocation; 19] = [
+        DirectiveLocation::Query,
+        DirectiveLocation::Mutation,
+        DirectiveLocation::Subscription,
+        DirectiveLocation::Field,
+        DirectiveLocation::FragmentDefinition,
+        DirectiveLocation::FragmentSpread,
+        DirectiveLocation::InlineFragment,
+        DirectiveLocation::VariableDefinition,
+        DirectiveLocation::Schema,
+        DirectiveLocation::Scalar,
+        DirectiveLocation::Object,
+        DirectiveLocation::FieldDefinition,
+        DirectiveLocation::ArgumentDefinition,
+        DirectiveLocation::Interface,
+        DirectiveLocation::Union,
+        DirectiveLocation::Enum,
+        DirectiveLocation::EnumValue,
+        DirectiveLocation::InputObject,
+        DirectiveLocation::InputFieldDefinition,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DirectiveLocation::Query => "QUERY",
+            DirectiveLocation::Mutation => "MUTATION",
+            DirectiveLocation::Subscription => "SUBSCRIPTION",
+            DirectiveLocation::Field => "FIELD",
+            DirectiveLocation::FragmentDefinition => "FRAGMENT_DEFINITION",
+            DirectiveLocation::FragmentSpread => "FRAGMENT_SPREAD",
+            DirectiveLocation::InlineFragment => "INLINE_FRAGMENT",
+            DirectiveLocation::VariableDefinition => "VARIABLE_DEFINITION",
+            DirectiveLocation::Schema => "SCHEMA",
+            DirectiveLocation::Scalar => "SCALAR",
+            DirectiveLocation::Object => "OBJECT",
+            DirectiveLocation::FieldDefinition => "FIELD_DEFINITION",
+            DirectiveLocation::ArgumentDefinition => "ARGUMENT_DEFINITION",
+            DirectiveLocation::Interface => "INTERFACE",
+            DirectiveLocation::Union => "UNION",
+            DirectiveLocation::Enum => "ENUM",
+            DirectiveLocation::EnumValue => "ENUM_VALUE",
+            DirectiveLocation::InputObject => "INPUT_OBJECT",
+            DirectiveLocation::InputFieldDefinition => "INPUT_FIELD_DEFINITION",
+        }
+    }
+}
+
+impl TryFrom<&str> for DirectiveLocation {
+    type Error = ();
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        DirectiveLocation::ALL
+            .iter()
+            .find(|location| location.as_str() == name)
+            .copied()
+            .ok_or(())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DirectiveDefinitionNode {
+    pub description: Description,
+    pub name: NameNode,
+    pub arguments: Option<ArgumentDefinitions>,
+    pub repeatable: bool,
+    pub locations: Vec<DirectiveLocation>,
+}
+
+impl DirectiveDefinitionNode {
+    pub fn new(
+        tok: Token,
+        description: Description,
+        locations: Vec<DirectiveLocation>,
+    ) -> ParseResult<DirectiveDefinitionNode> {
+        Ok(DirectiveDefinitionNode {
+            description,
+            name: NameNode::new(tok)?,
+            arguments: None,
+            repeatable: false,
+            locations,
+        })
+    }
+
+    pub fn with_arguments(&mut self, arguments: Option<ArgumentDefinitions>) -> &mut Self {
+        self.arguments = arguments;
+        self
+    }
+
+    pub fn with_repeatable(&mut self, repeatable: bool) -> &mut Self {
+        self.repeatable = repeatable;
+        self
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeSystemDefinitionNode {
     Schema(SchemaDefinitionNode),
     Type(TypeDefinitionNode),
-    // Directive(DirectiveDefinitionNode),
+    Directive(DirectiveDefinitionNode),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TypeSystemExtensionNode {
+    Schema(SchemaExtensionNode),
     Object(ObjectTypeExtensionNode),
 }
 
 type Selections = Vec<Selection>;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FieldNode {
     pub name: NameNode,
     pub alias: Option<NameNode>,
@@ -628,7 +911,8 @@ impl<'a> TryFrom<Token<'a>> for FieldNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FragmentSpreadNode {
     pub name: NameNode,
     pub directives: Option<Directives>,
@@ -643,22 +927,28 @@ impl From<&str> for FragmentSpreadNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InlineFragmentSpreadNode {
     pub node_type: Option<NamedTypeNode>,
     pub directives: Option<Directives>,
     pub selections: Selections,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FragmentSpread {
     Node(FragmentSpreadNode),
     Inline(InlineFragmentSpreadNode),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FragmentDefinitionNode {
     pub name: NameNode,
+    /// The variables of the fragment, only parsed with the `allow_legacy_fragment_variables`
+    /// parse option
+    pub variables: Option<Variables>,
     pub node_type: NamedTypeNode,
     pub directives: Option<Directives>,
     pub selections: Selections,
@@ -668,12 +958,18 @@ impl FragmentDefinitionNode {
     pub fn new(name: Token, node_type: Token) -> ParseResult<Self> {
         Ok(Self {
             name: NameNode::new(name)?,
+            variables: None,
             node_type: NamedTypeNode::new(node_type)?,
             directives: None,
             selections: Vec::new(),
         })
     }
 
+    pub fn with_variables(mut self, variables: Option<Variables>) -> Self {
+        self.variables = variables;
+        self
+    }
+
     pub fn with_directives(mut self, directives: Option<Directives>) -> Self {
         self.directives = directives;
         self
@@ -685,33 +981,66 @@ impl FragmentDefinitionNode {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Selection {
     Field(FieldNode),
     Fragment(FragmentSpread),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct QueryDefinitionNode {
     pub name: Option<NameNode>,
     pub variables: Option<Variables>,
     pub selections: Selections,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum OperationTypeNode {
     Query(QueryDefinitionNode),
-    // Mutation,
-    // Subscription,
+    Mutation(QueryDefinitionNode),
+    Subscription(QueryDefinitionNode),
+}
+
+impl OperationTypeNode {
+    /// Whether the operation is a query, a mutation or a subscription.
+    pub fn operation(&self) -> Operation {
+        match self {
+            OperationTypeNode::Query(_) => Operation::Query,
+            OperationTypeNode::Mutation(_) => Operation::Mutation,
+            OperationTypeNode::Subscription(_) => Operation::Subscription,
+        }
+    }
+
+    /// The name, variables and selections of the operation, whatever its kind.
+    pub fn definition(&self) -> &QueryDefinitionNode {
+        match self {
+            OperationTypeNode::Query(definition)
+            | OperationTypeNode::Mutation(definition)
+            | OperationTypeNode::Subscription(definition) => definition,
+        }
+    }
+
+    pub fn definition_mut(&mut self) -> &mut QueryDefinitionNode {
+        match self {
+            OperationTypeNode::Query(definition)
+            | OperationTypeNode::Mutation(definition)
+            | OperationTypeNode::Subscription(definition) => definition,
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ExecutableDefinitionNode {
     Operation(OperationTypeNode),
     Fragment(FragmentDefinitionNode),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum DefinitionNode {
     Executable(ExecutableDefinitionNode),
     TypeSystem(TypeSystemDefinitionNode),
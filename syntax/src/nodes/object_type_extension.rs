@@ -1,7 +1,8 @@
 use crate::error::ParseResult;
 use crate::nodes::*;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ObjectTypeExtensionNode {
     pub description: Description,
     pub name: NameNode,
@@ -40,7 +41,7 @@ impl ObjectTypeExtensionNode {
 impl NodeWithFields for ObjectTypeExtensionNode {
     fn get_fields(&self) -> &[FieldDefinitionNode] {
         if let Some(fields) = &self.fields {
-            &fields
+            fields
         } else {
             &[]
         }
@@ -99,6 +100,7 @@ mod tests {
 
         extension.with_interfaces(None);
         extension.with_fields(vec![FieldDefinitionNode {
+            directives: None,
             arguments: None,
             description: None,
             name: NameNode::from("someField"),
@@ -118,6 +120,7 @@ mod tests {
             }]),
             interfaces: Some(vec![NamedTypeNode::from("Timestamped")]),
             fields: Some(vec![FieldDefinitionNode {
+                directives: None,
                 name: NameNode::from("someField"),
                 description: None,
                 arguments: None,
@@ -134,6 +137,7 @@ mod tests {
             directives: None,
             interfaces: None,
             fields: vec![FieldDefinitionNode {
+                directives: None,
                 name: NameNode::from("initial"),
                 description: None,
                 arguments: None,
@@ -144,6 +148,7 @@ mod tests {
         assert!(extension.validate_extension(Some(&object)).is_ok());
 
         object.with_fields(vec![FieldDefinitionNode {
+            directives: None,
             name: NameNode::from("someField"),
             description: None,
             arguments: None,
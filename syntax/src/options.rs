@@ -0,0 +1,107 @@
+//! Options changing how [`parse_with_options`] parses a document.
+//!
+//! The options mirror the ones accepted by graphql-js's `parse`, so test suites shared with other
+//! implementations can be run with the same settings.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::options::ParseOptions;
+//! use syntax::parse_with_options;
+//!
+//! let options = ParseOptions {
+//!     max_tokens: Some(4),
+//!     ..ParseOptions::default()
+//! };
+//! assert!(parse_with_options("{ a }", &options).is_ok());
+//! assert!(parse_with_options("{ a b c }", &options).is_err());
+//! ```
+//!
+//! [`parse_with_options`]: ../fn.parse_with_options.html
+
+/// Options for [`parse_with_options`]. The default options parse like [`parse`].
+///
+/// [`parse_with_options`]: ../fn.parse_with_options.html
+/// [`parse`]: ../fn.parse.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Do not record where nodes were found in the source. Nodes never record their location, so
+    /// this is accepted for compatibility and has no effect.
+    pub no_location: bool,
+    /// Accept variable definitions on fragment definitions, as in
+    /// `fragment Friends($first: Int) on User { ... }`. The syntax is deprecated and is rejected
+    /// by default.
+    pub allow_legacy_fragment_variables: bool,
+    /// Stop parsing with [`ParseError::TooManyTokens`] once the document has more tokens than
+    /// this, to bound the work done on untrusted input.
+    ///
+    /// [`ParseError::TooManyTokens`]: ../error/enum.ParseError.html#variant.TooManyTokens
+    pub max_tokens: Option<usize>,
+    /// Measure the document and the time taken to parse it, for [`parse_with_metrics`] to
+    /// return.
+    ///
+    /// [`parse_with_metrics`]: ../fn.parse_with_metrics.html
+    pub metrics: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseError;
+    use crate::nodes::*;
+    use crate::{parse, parse_with_options};
+
+    const LEGACY_FRAGMENT: &str = "fragment Friends($first: Int = 10) on User { id }";
+
+    #[test]
+    fn it_parses_like_parse_by_default() {
+        let input = "query Q($id: ID!) { user(id: $id) { ...F } } fragment F on User { id }";
+        assert_eq!(
+            parse_with_options(input, &ParseOptions::default()),
+            parse(input)
+        );
+    }
+
+    #[test]
+    fn it_rejects_legacy_fragment_variables_by_default() {
+        match parse(LEGACY_FRAGMENT) {
+            Err(ParseError::UnexpectedKeyword { expected, .. }) => assert_eq!(expected, "on"),
+            other => panic!("Expected an unexpected keyword error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_parses_legacy_fragment_variables() {
+        let options = ParseOptions {
+            allow_legacy_fragment_variables: true,
+            ..ParseOptions::default()
+        };
+        let document = parse_with_options(LEGACY_FRAGMENT, &options).unwrap();
+        match &document.definitions[0] {
+            DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                let variables = fragment.variables.as_ref().unwrap();
+                assert_eq!(variables[0].variable.name, NameNode::from("first"));
+                assert_eq!(fragment.node_type, NamedTypeNode::from("User"));
+            }
+            other => panic!("Expected a fragment, got {:?}", other),
+        }
+        assert_eq!(
+            crate::printer::print(&document),
+            "fragment Friends($first: Int = 10) on User {\n  id\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_limits_the_number_of_tokens() {
+        let options = ParseOptions {
+            max_tokens: Some(5),
+            ..ParseOptions::default()
+        };
+        assert!(parse_with_options("{ a { b } }", &options).is_err());
+        match parse_with_options("{ a b c d e }", &options) {
+            Err(ParseError::TooManyTokens(location)) => assert_eq!(location.column, 11),
+            other => panic!("Expected too many tokens, got {:?}", other),
+        }
+        assert!(parse_with_options("{ a b c }", &options).is_ok());
+    }
+}
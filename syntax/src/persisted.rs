@@ -0,0 +1,181 @@
+//! Persisted operations: a manifest of the only operations a server accepts.
+//!
+//! [`PersistedOperations::from_documents`] hashes every operation of a set of documents, such as
+//! the documents of a client, and [`PersistedOperations::to_json`] writes the manifest as a JSON
+//! map from hash to operation. A server reads it back with [`PersistedOperations::from_json`],
+//! and rejects the documents that [`PersistedOperations::allows`] refuses.
+//!
+//! Every operation is stored with the fragments it uses, printed by the [`printer`], so that
+//! formatting, comments and unused fragments do not matter. Its hash is the hex encoded SHA-256
+//! of that text. Manifests written by other tools may use other hashes: they are still looked up
+//! by their own hash with [`PersistedOperations::get`], and their operations are allowed all the
+//! same.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::persisted::PersistedOperations;
+//!
+//! let client = syntax::parse("query Me { me { ...User } } fragment User on User { id }").unwrap();
+//! let manifest = PersistedOperations::from_documents(&[client]).to_json();
+//!
+//! let allowed = PersistedOperations::from_json(&manifest).unwrap();
+//! assert!(allowed.allows(&syntax::parse("query Me { me { ... User } }\nfragment User on User {\n  id\n}").unwrap()));
+//! assert!(!allowed.allows(&syntax::parse("query Me { me { id email } }").unwrap()));
+//! ```
+//!
+//! [`PersistedOperations::from_documents`]: struct.PersistedOperations.html#method.from_documents
+//! [`PersistedOperations::to_json`]: struct.PersistedOperations.html#method.to_json
+//! [`PersistedOperations::from_json`]: struct.PersistedOperations.html#method.from_json
+//! [`PersistedOperations::allows`]: struct.PersistedOperations.html#method.allows
+//! [`PersistedOperations::get`]: struct.PersistedOperations.html#method.get
+//! [`printer`]: ../printer/index.html
+
+use crate::document::Document;
+use crate::error::ConversionError;
+use crate::printer::print;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+/// A set of persisted operations, by hash.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PersistedOperations {
+    /// The operations of the manifest, by the hash it gives them
+    operations: BTreeMap<String, String>,
+    /// The hashes of the printed operations of the manifest
+    allowed: HashSet<String>,
+}
+
+impl PersistedOperations {
+    /// Creates an empty manifest, which allows no operation.
+    pub fn new() -> PersistedOperations {
+        PersistedOperations::default()
+    }
+
+    /// Builds the manifest of every operation of the documents.
+    pub fn from_documents<'a>(
+        documents: impl IntoIterator<Item = &'a Document>,
+    ) -> PersistedOperations {
+        let mut operations = PersistedOperations::new();
+        for document in documents {
+            operations.insert(document);
+        }
+        operations
+    }
+
+    /// Reads a manifest written as a JSON map from hash to operation.
+    pub fn from_json(json: &str) -> Result<PersistedOperations, ConversionError> {
+        let entries: BTreeMap<String, String> =
+            serde_json::from_str(json).map_err(|error| ConversionError::new(&error.to_string()))?;
+        let mut operations = PersistedOperations::new();
+        for (hash, operation) in entries {
+            let document = crate::parse(&operation).map_err(|error| {
+                ConversionError::new(&format!("The operation {} does not parse: {}", hash, error))
+            })?;
+            for operation in document.separate_operations() {
+                operations.allowed.insert(operation_hash(&operation));
+            }
+            operations.operations.insert(hash, operation);
+        }
+        Ok(operations)
+    }
+
+    /// Adds the operations of a document, and returns their hashes.
+    pub fn insert(&mut self, document: &Document) -> Vec<String> {
+        document
+            .separate_operations()
+            .iter()
+            .map(|operation| {
+                let text = print(operation);
+                let hash = hash(&text);
+                self.allowed.insert(hash.clone());
+                self.operations.insert(hash.clone(), text);
+                hash
+            })
+            .collect()
+    }
+
+    /// The operation persisted under `hash`.
+    pub fn get(&self, hash: &str) -> Option<&str> {
+        self.operations.get(hash).map(String::as_str)
+    }
+
+    /// Returns true if every operation of the document is persisted. Type system definitions are
+    /// not checked.
+    pub fn allows(&self, document: &Document) -> bool {
+        document
+            .separate_operations()
+            .iter()
+            .all(|operation| self.allowed.contains(&operation_hash(operation)))
+    }
+
+    /// The number of persisted operations.
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Returns true if no operation is persisted.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Writes the manifest as a JSON map from hash to operation, sorted by hash.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.operations).expect("A map of strings serializes")
+    }
+}
+
+/// The hash of a document holding a single operation and its fragments, as returned by
+/// `Document::separate_operations`.
+pub fn operation_hash(operation: &Document) -> String {
+    hash(&print(operation))
+}
+
+fn hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .fold(String::with_capacity(64), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_hashes_printed_operations() {
+        let document =
+            crate::parse("query A { a } query B { b ...F } fragment F on B { c }").unwrap();
+        let mut operations = PersistedOperations::new();
+        let hashes = operations.insert(&document);
+        assert_eq!(hashes.len(), 2);
+        assert_eq!(
+            hashes[0],
+            hash("query A {\n  a\n}\n"),
+            "The hash is that of the printed operation"
+        );
+        assert_eq!(hashes[0].len(), 64);
+        assert_eq!(
+            operations.get(&hashes[1]),
+            Some("query B {\n  b\n  ...F\n}\n\nfragment F on B {\n  c\n}\n")
+        );
+        assert!(
+            operations.allows(&crate::parse("query B { b ...F } fragment F on B { c }").unwrap())
+        );
+        assert!(!operations.allows(&crate::parse("query A { a } query C { c }").unwrap()));
+    }
+
+    #[test]
+    fn it_reads_manifests_of_other_tools() {
+        let manifest = r#"{ "abc": "query Me { me { id } }" }"#;
+        let operations = PersistedOperations::from_json(manifest).unwrap();
+        assert_eq!(operations.len(), 1);
+        assert_eq!(operations.get("abc"), Some("query Me { me { id } }"));
+        assert!(operations.allows(&crate::parse("query Me {\n  me {\n    id\n  }\n}").unwrap()));
+        assert!(PersistedOperations::from_json(r#"{ "abc": "query {" }"#).is_err());
+        assert!(PersistedOperations::from_json("[]").is_err());
+    }
+}
@@ -0,0 +1,844 @@
+//! Prints a [`Document`] back into a GraphQL string.
+//!
+//! The output uses two space indentation, puts every field and enum value on its own line and
+//! separates definitions with a blank line. Strings are printed as they were lexed, so escape
+//! sequences and block strings are preserved.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::printer::print;
+//!
+//! let document = syntax::parse("type User { id: ID!, friends(first: Int = 10): [User] }").unwrap();
+//! assert_eq!(
+//!     print(&document),
+//!     "type User {\n  id: ID!\n  friends(first: Int = 10): [User]\n}\n"
+//! );
+//! ```
+//!
+//! Type references, values, directives and definitions also implement `Display` with the
+//! printer, which makes them easy to quote in error messages:
+//!
+//! ```
+//! use syntax::nodes::{DefinitionNode, ExecutableDefinitionNode};
+//!
+//! let document = syntax::parse("query Users($first: [Int!]!) { users @include(if: $x) }").unwrap();
+//! if let DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) =
+//!     &document.definitions[0]
+//! {
+//!     let query = operation.definition();
+//!     let variable = &query.variables.as_ref().unwrap()[0];
+//!     assert_eq!(variable.variable_type.to_string(), "[Int!]!");
+//!     assert_eq!(operation.to_string(), "query Users($first: [Int!]!) {\n  users @include(if: $x)\n}");
+//! }
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+
+use crate::document::Document;
+use crate::nodes::object_type_extension::ObjectTypeExtensionNode;
+use crate::nodes::*;
+use std::fmt;
+
+const INDENT: &str = "  ";
+
+/// Prints the document as a GraphQL string.
+pub fn print(document: &Document) -> String {
+    print_definitions(&document.definitions)
+}
+
+/// Prints a selection of definitions as if they were a document.
+pub(crate) fn print_definitions<'d, I>(definitions: I) -> String
+where
+    I: IntoIterator<Item = &'d DefinitionNode>,
+{
+    let mut printer = Printer::default();
+    for (i, definition) in definitions.into_iter().enumerate() {
+        if i > 0 {
+            printer.output.push('\n');
+        }
+        printer.print_definition(definition);
+    }
+    printer.output
+}
+
+/// Prints a type reference, e.g. `[Int!]!`.
+pub(crate) fn print_type(type_node: &TypeNode) -> String {
+    match type_node {
+        TypeNode::Named(named) => named.name.value.to_string(),
+        TypeNode::List(list) => format!("[{}]", print_type(&list.list_type)),
+        TypeNode::NonNull(inner) => format!("{}!", print_type(inner)),
+    }
+}
+
+/// Prints a value literal on a single line.
+pub(crate) fn print_value(value: &ValueNode) -> String {
+    match value {
+        ValueNode::Variable(variable) => format!("${}", variable.name.value),
+        ValueNode::Int(int) => int.value.clone(),
+        ValueNode::Float(float) => match &float.lexeme {
+            Some(lexeme) => lexeme.clone(),
+            None => print_float(float.value),
+        },
+        ValueNode::Str(string) => print_string(string),
+        ValueNode::Bool(boolean) => boolean.value.to_string(),
+        ValueNode::Null => "null".into(),
+        ValueNode::Enum(enum_value) => enum_value.value.clone(),
+        ValueNode::List(list) => format!(
+            "[{}]",
+            list.values
+                .iter()
+                .map(print_value)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        ValueNode::Object(object) => format!(
+            "{{{}}}",
+            object
+                .fields
+                .iter()
+                .map(|field| format!("{}: {}", field.name.value, print_value(&field.value)))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn print_float(value: f64) -> String {
+    let printed = value.to_string();
+    if printed.contains('.') || printed.contains('e') || !value.is_finite() {
+        printed
+    } else {
+        format!("{}.0", printed)
+    }
+}
+
+pub(crate) fn print_string(string: &StringValueNode) -> String {
+    if string.is_block() {
+        format!("\"\"\"{}\"\"\"", string.value)
+    } else {
+        format!("\"{}\"", string.value)
+    }
+}
+
+/// Prints the arguments of a field or directive, e.g. `(if: $skip)`.
+pub(crate) fn print_arguments(arguments: &Option<Arguments>) -> String {
+    match arguments {
+        Some(arguments) if !arguments.is_empty() => format!(
+            "({})",
+            arguments
+                .iter()
+                .map(|argument| format!(
+                    "{}: {}",
+                    argument.name.value,
+                    print_value(&argument.value)
+                ))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Prints a single directive application, e.g. `@include(if: $x)`.
+pub(crate) fn print_directive(directive: &DirectiveNode) -> String {
+    format!(
+        "@{}{}",
+        directive.name.value,
+        print_arguments(&directive.arguments)
+    )
+}
+
+/// Prints a list of directives, each preceded by a space.
+pub(crate) fn print_directives(directives: &Option<Directives>) -> String {
+    match directives {
+        Some(directives) => directives
+            .iter()
+            .map(|directive| format!(" {}", print_directive(directive)))
+            .collect(),
+        None => String::new(),
+    }
+}
+
+fn print_input_value(input_value: &InputValueDefinitionNode) -> String {
+    let mut printed = format!(
+        "{}: {}",
+        input_value.name.value,
+        print_type(&input_value.input_type)
+    );
+    if let Some(default_value) = &input_value.default_value {
+        printed.push_str(" = ");
+        printed.push_str(&print_value(default_value));
+    }
+    printed.push_str(&print_directives(&input_value.directives));
+    printed
+}
+
+fn print_argument_definitions(arguments: &Option<ArgumentDefinitions>) -> String {
+    match arguments {
+        Some(arguments) => format!(
+            "({})",
+            arguments
+                .iter()
+                .map(|argument| match &argument.description {
+                    Some(description) => {
+                        format!(
+                            "{} {}",
+                            print_string(description),
+                            print_input_value(argument)
+                        )
+                    }
+                    None => print_input_value(argument),
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        None => String::new(),
+    }
+}
+
+fn print_interfaces(interfaces: &Option<Vec<NamedTypeNode>>) -> String {
+    match interfaces {
+        Some(interfaces) if !interfaces.is_empty() => format!(
+            " implements {}",
+            interfaces
+                .iter()
+                .map(|interface| interface.name.value.as_str())
+                .collect::<Vec<&str>>()
+                .join(" & ")
+        ),
+        _ => String::new(),
+    }
+}
+
+fn operation_keyword(operation: &Operation) -> &'static str {
+    match operation {
+        Operation::Query => "query",
+        Operation::Mutation => "mutation",
+        Operation::Subscription => "subscription",
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    output: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, content: &str) {
+        for _ in 0..self.depth {
+            self.output.push_str(INDENT);
+        }
+        self.output.push_str(content);
+        self.output.push('\n');
+    }
+
+    fn description(&mut self, description: &Description) {
+        if let Some(description) = description {
+            self.line(&print_string(description));
+        }
+    }
+
+    fn open_block(&mut self, header: &str) {
+        self.line(&format!("{} {{", header));
+        self.depth += 1;
+    }
+
+    fn close_block(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    fn print_definition(&mut self, definition: &DefinitionNode) {
+        match definition {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(schema)) => {
+                self.print_schema(schema)
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                self.print_type_definition(type_definition)
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+                self.print_directive_definition(directive)
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Schema(extension)) => {
+                self.print_schema_extension(extension)
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                self.print_object_extension(extension)
+            }
+            DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                self.print_operation(operation)
+            }
+            DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                self.print_fragment(fragment)
+            }
+        }
+    }
+
+    fn print_schema(&mut self, schema: &SchemaDefinitionNode) {
+        self.description(&schema.description);
+        self.open_block(&format!("schema{}", print_directives(&schema.directives)));
+        self.print_operation_types(&schema.operations);
+        self.close_block();
+    }
+
+    fn print_schema_extension(&mut self, extension: &SchemaExtensionNode) {
+        let header = format!("extend schema{}", print_directives(&extension.directives));
+        match &extension.operations {
+            Some(operations) => {
+                self.open_block(&header);
+                self.print_operation_types(operations);
+                self.close_block();
+            }
+            None => self.line(&header),
+        }
+    }
+
+    fn print_operation_types(&mut self, operations: &[OperationTypeDefinitionNode]) {
+        for operation in operations {
+            self.line(&format!(
+                "{}: {}",
+                operation_keyword(&operation.operation),
+                operation.node_type.name.value
+            ));
+        }
+    }
+
+    fn print_type_definition(&mut self, type_definition: &TypeDefinitionNode) {
+        self.description(type_definition.description());
+        match type_definition {
+            TypeDefinitionNode::Scalar(scalar) => self.line(&format!(
+                "scalar {}{}",
+                scalar.name.value,
+                print_directives(&scalar.directives)
+            )),
+            TypeDefinitionNode::Object(object) => {
+                self.open_block(&format!(
+                    "type {}{}{}",
+                    object.name.value,
+                    print_interfaces(&object.interfaces),
+                    print_directives(&object.directives)
+                ));
+                self.print_fields(&object.fields);
+                self.close_block();
+            }
+            TypeDefinitionNode::Interface(interface) => {
+                self.open_block(&format!(
+                    "interface {}{}",
+                    interface.name.value,
+                    print_directives(&interface.directives)
+                ));
+                self.print_fields(&interface.fields);
+                self.close_block();
+            }
+            TypeDefinitionNode::Union(union) => self.line(&format!(
+                "union {}{} = {}",
+                union.name.value,
+                print_directives(&union.directives),
+                union
+                    .types
+                    .iter()
+                    .map(|member| member.name.value.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" | ")
+            )),
+            TypeDefinitionNode::Enum(enum_type) => {
+                self.open_block(&format!(
+                    "enum {}{}",
+                    enum_type.name.value,
+                    print_directives(&enum_type.directives)
+                ));
+                for value in &enum_type.values {
+                    self.description(&value.description);
+                    self.line(&format!(
+                        "{}{}",
+                        value.name.value,
+                        print_directives(&value.directives)
+                    ));
+                }
+                self.close_block();
+            }
+            TypeDefinitionNode::Input(input) => {
+                self.open_block(&format!(
+                    "input {}{}",
+                    input.name.value,
+                    print_directives(&input.directives)
+                ));
+                for field in &input.fields {
+                    self.description(&field.description);
+                    self.line(&print_input_value(field));
+                }
+                self.close_block();
+            }
+        }
+    }
+
+    fn print_directive_definition(&mut self, directive: &DirectiveDefinitionNode) {
+        self.description(&directive.description);
+        self.line(&format!(
+            "directive @{}{}{} on {}",
+            directive.name.value,
+            print_argument_definitions(&directive.arguments),
+            if directive.repeatable {
+                " repeatable"
+            } else {
+                ""
+            },
+            directive
+                .locations
+                .iter()
+                .map(DirectiveLocation::as_str)
+                .collect::<Vec<&str>>()
+                .join(" | ")
+        ));
+    }
+
+    fn print_object_extension(&mut self, extension: &ObjectTypeExtensionNode) {
+        let header = format!(
+            "extend type {}{}{}",
+            extension.name.value,
+            print_interfaces(&extension.interfaces),
+            print_directives(&extension.directives)
+        );
+        match &extension.fields {
+            Some(fields) => {
+                self.open_block(&header);
+                self.print_fields(fields);
+                self.close_block();
+            }
+            None => self.line(&header),
+        }
+    }
+
+    fn print_fields(&mut self, fields: &[FieldDefinitionNode]) {
+        for field in fields {
+            self.description(&field.description);
+            self.line(&format!(
+                "{}{}: {}{}",
+                field.name.value,
+                print_argument_definitions(&field.arguments),
+                print_type(&field.field_type),
+                print_directives(&field.directives)
+            ));
+        }
+    }
+
+    fn print_operation(&mut self, operation: &OperationTypeNode) {
+        let query = operation.definition();
+        let keyword = match operation {
+            OperationTypeNode::Query(_) => "query",
+            OperationTypeNode::Mutation(_) => "mutation",
+            OperationTypeNode::Subscription(_) => "subscription",
+        };
+        if keyword == "query" && query.name.is_none() && query.variables.is_none() {
+            self.line("{");
+            self.depth += 1;
+        } else {
+            let name = match &query.name {
+                Some(name) => format!(" {}", name.value),
+                None => String::new(),
+            };
+            self.open_block(&format!(
+                "{}{}{}",
+                keyword,
+                name,
+                print_variable_definitions(&query.variables)
+            ));
+        }
+        self.print_selections(&query.selections);
+        self.close_block();
+    }
+
+    fn print_fragment(&mut self, fragment: &FragmentDefinitionNode) {
+        self.open_block(&format!(
+            "fragment {}{} on {}{}",
+            fragment.name.value,
+            print_variable_definitions(&fragment.variables),
+            fragment.node_type.name.value,
+            print_directives(&fragment.directives)
+        ));
+        self.print_selections(&fragment.selections);
+        self.close_block();
+    }
+
+    fn print_selections(&mut self, selections: &[Selection]) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    let mut header = String::new();
+                    if let Some(alias) = &field.alias {
+                        header.push_str(&alias.value);
+                        header.push_str(": ");
+                    }
+                    header.push_str(&field.name.value);
+                    header.push_str(&print_arguments(&field.arguments));
+                    header.push_str(&print_directives(&field.directives));
+                    match &field.selections {
+                        Some(selections) => {
+                            self.open_block(&header);
+                            self.print_selections(selections);
+                            self.close_block();
+                        }
+                        None => self.line(&header),
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => self.line(&format!(
+                    "...{}{}",
+                    spread.name.value,
+                    print_directives(&spread.directives)
+                )),
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let type_condition = match &inline.node_type {
+                        Some(node_type) => format!(" on {}", node_type.name.value),
+                        None => String::new(),
+                    };
+                    self.open_block(&format!(
+                        "...{}{}",
+                        type_condition,
+                        print_directives(&inline.directives)
+                    ));
+                    self.print_selections(&inline.selections);
+                    self.close_block();
+                }
+            }
+        }
+    }
+}
+
+fn print_variable_definitions(variables: &Option<Variables>) -> String {
+    match variables {
+        Some(variables) if !variables.is_empty() => format!(
+            "({})",
+            variables
+                .iter()
+                .map(|variable| {
+                    let mut printed = format!(
+                        "${}: {}",
+                        variable.variable.name.value,
+                        print_type(&variable.variable_type)
+                    );
+                    if let Some(default_value) = &variable.default_value {
+                        printed.push_str(" = ");
+                        printed.push_str(&print_value(default_value));
+                    }
+                    printed
+                })
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Writes what a printer prints, without the newline ending its last line.
+fn display<F>(f: &mut fmt::Formatter<'_>, print: F) -> fmt::Result
+where
+    F: FnOnce(&mut Printer),
+{
+    let mut printer = Printer::default();
+    print(&mut printer);
+    f.write_str(printer.output.trim_end_matches('\n'))
+}
+
+impl fmt::Display for TypeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&print_type(self))
+    }
+}
+
+impl fmt::Display for ValueNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&print_value(self))
+    }
+}
+
+impl fmt::Display for DirectiveNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&print_directive(self))
+    }
+}
+
+impl fmt::Display for DefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_definition(self))
+    }
+}
+
+impl fmt::Display for TypeSystemDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeSystemDefinitionNode::Schema(schema) => schema.fmt(f),
+            TypeSystemDefinitionNode::Type(type_definition) => type_definition.fmt(f),
+            TypeSystemDefinitionNode::Directive(directive) => directive.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for TypeSystemExtensionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypeSystemExtensionNode::Schema(extension) => extension.fmt(f),
+            TypeSystemExtensionNode::Object(extension) => extension.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for ExecutableDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutableDefinitionNode::Operation(operation) => operation.fmt(f),
+            ExecutableDefinitionNode::Fragment(fragment) => fragment.fmt(f),
+        }
+    }
+}
+
+impl fmt::Display for SchemaDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_schema(self))
+    }
+}
+
+impl fmt::Display for SchemaExtensionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_schema_extension(self))
+    }
+}
+
+impl fmt::Display for TypeDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_type_definition(self))
+    }
+}
+
+impl fmt::Display for DirectiveDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_directive_definition(self))
+    }
+}
+
+impl fmt::Display for ObjectTypeExtensionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_object_extension(self))
+    }
+}
+
+impl fmt::Display for FieldDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| {
+            printer.print_fields(std::slice::from_ref(self))
+        })
+    }
+}
+
+impl fmt::Display for OperationTypeNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_operation(self))
+    }
+}
+
+impl fmt::Display for FragmentDefinitionNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        display(f, |printer| printer.print_fragment(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn assert_round_trip(input: &str) {
+        let document = parse(input).unwrap();
+        let printed = print(&document);
+        assert_eq!(printed, input);
+        assert_eq!(parse(&printed).unwrap(), document);
+    }
+
+    #[test]
+    fn prints_type_system_definitions() {
+        assert_round_trip(
+            r#""""The schema"""
+schema {
+  query: Query
+  mutation: Mutation
+}
+
+"Some scalar"
+scalar Date @specifiedBy(url: "https://example.com")
+
+type Query implements Node & Entity @key(fields: "id") {
+  """The id"""
+  id: ID!
+  users(first: Int = 10, filter: UserFilter = {name: "x", tags: [A, B]}): [User!]! @deprecated
+}
+
+interface Node {
+  id: ID!
+}
+
+union Pet = Dog | Cat
+
+enum Direction @flags {
+  NORTH
+  "down"
+  SOUTH @deprecated(reason: "no")
+}
+
+input UserFilter {
+  name: String = "any"
+  limit: Float = 1.5
+}
+
+"Marks a cached field"
+directive @cached(ttl: Int = 60) repeatable on FIELD_DEFINITION | OBJECT
+
+extend type Query implements Other @dir
+
+extend type User {
+  age: Int
+}
+
+extend schema @costLimit(max: 1000)
+
+extend schema {
+  subscription: Subscription
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn prints_executable_definitions() {
+        assert_round_trip(
+            r#"{
+  user {
+    name
+  }
+}
+
+query Users($first: Int = 3, $after: String) {
+  users(first: $first, after: $after) @include(if: true) {
+    id
+    best: friend {
+      ...UserFields
+    }
+    ... on Admin {
+      level
+    }
+    ... @skip(if: false) {
+      email
+    }
+  }
+}
+
+fragment UserFields on User @dir {
+  name
+}
+
+mutation {
+  createUser(input: {name: "alice"}) {
+    id
+  }
+}
+
+mutation Rename($id: ID!) {
+  updateUser(id: $id, input: {name: "bob"}) {
+    name
+  }
+}
+
+query($first: Int) {
+  users(first: $first) {
+    id
+  }
+}
+
+subscription OnUpdate($id: ID) {
+  userUpdated(id: $id) {
+    name
+  }
+}
+"#,
+        );
+    }
+
+    #[test]
+    fn prints_type_references() {
+        let document = parse("type A { a: [[Int!]]! }").unwrap();
+        if let DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+            TypeDefinitionNode::Object(object),
+        )) = &document.definitions[0]
+        {
+            assert_eq!(print_type(&object.fields[0].field_type), "[[Int!]]!");
+        } else {
+            panic!("Expected an object type");
+        }
+    }
+
+    #[test]
+    fn prints_float_values() {
+        assert_eq!(print_float(1.0), "1.0");
+        assert_eq!(print_float(-2.5), "-2.5");
+    }
+
+    #[test]
+    fn prints_floats_as_they_are_written() {
+        let query = "{\n  products(above: 1.10, below: 2.50, ratio: -0.0)\n}\n";
+        assert_eq!(print(&parse(query).unwrap()), query);
+    }
+
+    #[test]
+    fn displays_nodes_as_graphql() {
+        let document = parse(
+            r#"type User @key(fields: "id") { id: ID! friends(first: Int = 10): [User!]! }
+            query Users($ids: [ID!] = [1, 2]) { users(filter: {ids: $ids}) @include(if: $x) { id } }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            document.definitions[0].to_string(),
+            "type User @key(fields: \"id\") {\n  id: ID!\n  friends(first: Int = 10): [User!]!\n}"
+        );
+        let object = match &document.definitions[0] {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+                TypeDefinitionNode::Object(object),
+            )) => object,
+            _ => panic!("Expected an object type"),
+        };
+        assert_eq!(
+            object.directives.as_ref().unwrap()[0].to_string(),
+            "@key(fields: \"id\")"
+        );
+        assert_eq!(
+            object.fields[1].to_string(),
+            "friends(first: Int = 10): [User!]!"
+        );
+        assert_eq!(object.fields[1].field_type.to_string(), "[User!]!");
+        let operation = match &document.definitions[1] {
+            DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => operation,
+            _ => panic!("Expected an operation"),
+        };
+        let query = operation.definition();
+        let variable = &query.variables.as_ref().unwrap()[0];
+        assert_eq!(
+            variable.default_value.as_ref().unwrap().to_string(),
+            "[1, 2]"
+        );
+        let field = match &query.selections[0] {
+            Selection::Field(field) => field,
+            _ => panic!("Expected a field"),
+        };
+        assert_eq!(
+            field.arguments.as_ref().unwrap()[0].value.to_string(),
+            "{ids: $ids}"
+        );
+        assert_eq!(
+            field.directives.as_ref().unwrap()[0].to_string(),
+            "@include(if: $x)"
+        );
+        assert_eq!(
+            operation.to_string(),
+            "query Users($ids: [ID!] = [1, 2]) {\n  users(filter: {ids: $ids}) @include(if: $x) {\n    id\n  }\n}"
+        );
+    }
+}
@@ -0,0 +1,1261 @@
+//! An index over the type system definitions of a [`Document`].
+//!
+//! A [`Document`] is a flat list of definitions. The [`Schema`] indexes the type definitions by
+//! name, resolves the root operation types and merges type extensions into their original
+//! definitions so that consumers (introspection, validation, execution) can answer questions like
+//! "what are the fields of `User`?" without walking the whole document.
+//!
+//! The built-in scalars and the directives defined by the GraphQL Spec (`@include`, `@skip`,
+//! `@deprecated` and `@specifiedBy`), along with `@oneOf` of its draft, are available in every
+//! schema unless the document defines them itself. Use [`Schema::with_builtins`] to leave them out.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::schema::Schema;
+//!
+//! let document = syntax::parse(r#"
+//! type Query { user: User }
+//! type User { id: ID! }
+//! extend type User { name: String }
+//! "#).unwrap();
+//! let schema = Schema::new(&document);
+//! assert_eq!(schema.query_type(), Some("Query"));
+//! assert_eq!(schema.fields("User").len(), 2);
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+//! [`Schema`]: struct.Schema.html
+//! [`Schema::with_builtins`]: struct.Schema.html#method.with_builtins
+
+use crate::coordinate::{SchemaCoordinate, SchemaElement};
+use crate::document::Document;
+use crate::error::ValidationError;
+use crate::nodes::object_type_extension::ObjectTypeExtensionNode;
+use crate::nodes::*;
+use crate::printer::print_type;
+use crate::validation::{ValidExtensionNode, ValidNode};
+use crate::validator::{Validator, ValidatorOptions};
+use std::collections::{HashMap, HashSet};
+
+/// The names of the scalars defined by the GraphQL Spec.
+pub const BUILTIN_SCALARS: [&str; 5] = ["Int", "Float", "String", "Boolean", "ID"];
+
+/// The names of the directives defined by the GraphQL Spec, and `@oneOf` of its draft.
+pub const BUILTIN_DIRECTIVES: [&str; 5] = ["include", "skip", "deprecated", "specifiedBy", "oneOf"];
+
+/// The SDL of the scalars and directives defined by the GraphQL Spec.
+pub const BUILTIN_DEFINITIONS: &str = r#"
+"The `Int` scalar type represents non-fractional signed whole numeric values between -2^31 and 2^31 - 1."
+scalar Int
+
+"The `Float` scalar type represents signed double-precision fractional values."
+scalar Float
+
+"The `String` scalar type represents textual data as UTF-8 character sequences."
+scalar String
+
+"The `Boolean` scalar type represents `true` or `false`."
+scalar Boolean
+
+"The `ID` scalar type represents a unique identifier, serialized as a String."
+scalar ID
+
+"Directs the executor to include this field or fragment only when the `if` argument is true."
+directive @include("Included when true." if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Directs the executor to skip this field or fragment when the `if` argument is true."
+directive @skip("Skipped when true." if: Boolean!) on FIELD | FRAGMENT_SPREAD | INLINE_FRAGMENT
+
+"Marks an element of a GraphQL schema as no longer supported."
+directive @deprecated(reason: String = "No longer supported") on FIELD_DEFINITION | ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION | ENUM_VALUE
+
+"Exposes a URL that specifies the behavior of this scalar."
+directive @specifiedBy("The URL that specifies the behavior of this scalar." url: String!) on SCALAR
+
+"Indicates exactly one field must be supplied and this field must not be `null`."
+directive @oneOf on INPUT_OBJECT
+"#;
+
+lazy_static! {
+    static ref BUILTINS: Document =
+        crate::parse(BUILTIN_DEFINITIONS).expect("The built-in definitions are valid");
+}
+
+/// A read-only index of the type system definitions in a [`Document`].
+///
+/// If a type is defined more than once, the first definition wins.
+///
+/// [`Document`]: ../document/struct.Document.html
+#[derive(Debug)]
+pub struct Schema<'a> {
+    description: Option<&'a str>,
+    types: HashMap<&'a str, &'a TypeDefinitionNode>,
+    type_order: Vec<&'a str>,
+    extensions: HashMap<&'a str, Vec<&'a ObjectTypeExtensionNode>>,
+    /// The object types of every union and interface, in the order of the document
+    possible_types: HashMap<&'a str, Vec<&'a str>>,
+    directives: HashMap<&'a str, &'a DirectiveDefinitionNode>,
+    directive_order: Vec<&'a str>,
+    query: Option<&'a str>,
+    mutation: Option<&'a str>,
+    subscription: Option<&'a str>,
+    schema_directives: Vec<&'a DirectiveNode>,
+}
+
+impl<'a> Schema<'a> {
+    /// Builds the index for the type system definitions in the document. Executable definitions
+    /// are ignored.
+    ///
+    /// If the document contains a `schema` definition, it determines the root operation types.
+    /// Otherwise the types named `Query`, `Mutation` and `Subscription` are used when defined.
+    ///
+    /// The built-in scalars and directives are added after the definitions of the document.
+    pub fn new(document: &'a Document) -> Schema<'a> {
+        Schema::with_builtins(document, true)
+    }
+
+    /// Builds the index like [`Schema::new`], only adding the built-in scalars and directives
+    /// when `include_builtins` is true.
+    ///
+    /// [`Schema::new`]: struct.Schema.html#method.new
+    pub fn with_builtins(document: &'a Document, include_builtins: bool) -> Schema<'a> {
+        let mut schema = Schema {
+            description: None,
+            types: HashMap::new(),
+            type_order: Vec::new(),
+            extensions: HashMap::new(),
+            possible_types: HashMap::new(),
+            directives: HashMap::new(),
+            directive_order: Vec::new(),
+            query: None,
+            mutation: None,
+            subscription: None,
+            schema_directives: Vec::new(),
+        };
+        let mut schema_definition = None;
+        let mut schema_extensions = Vec::new();
+        let builtins: &'a [DefinitionNode] = if include_builtins {
+            &BUILTINS.definitions
+        } else {
+            &[]
+        };
+        for definition in document.definitions.iter().chain(builtins) {
+            match definition {
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                    let name = type_definition.name();
+                    if !schema.types.contains_key(name) {
+                        schema.types.insert(name, type_definition);
+                        schema.type_order.push(name);
+                    }
+                }
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+                    let name = directive.name.value.as_str();
+                    if !schema.directives.contains_key(name) {
+                        schema.directives.insert(name, directive);
+                        schema.directive_order.push(name);
+                    }
+                }
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(definition)) => {
+                    schema_definition.get_or_insert(definition);
+                }
+                DefinitionNode::Extension(TypeSystemExtensionNode::Schema(extension)) => {
+                    schema_extensions.push(extension);
+                }
+                DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                    schema
+                        .extensions
+                        .entry(extension.name.value.as_str())
+                        .or_default()
+                        .push(extension);
+                }
+                DefinitionNode::Executable(_) => (),
+            }
+        }
+
+        if let Some(definition) = schema_definition {
+            schema.description = definition.description.as_ref().map(|d| d.value.as_str());
+            schema.operation_types(&definition.operations);
+            schema
+                .schema_directives
+                .extend(definition.directives.iter().flatten());
+        } else {
+            schema.query = schema.defined_name("Query");
+            schema.mutation = schema.defined_name("Mutation");
+            schema.subscription = schema.defined_name("Subscription");
+        }
+        for extension in schema_extensions {
+            schema.operation_types(extension.operations.iter().flatten());
+            schema
+                .schema_directives
+                .extend(extension.directives.iter().flatten());
+        }
+        schema.index_possible_types();
+        schema
+    }
+
+    /// Indexes the members of the unions and the implementations of the interfaces, once the
+    /// extensions are known.
+    fn index_possible_types(&mut self) {
+        for name in &self.type_order {
+            match self.types[name] {
+                TypeDefinitionNode::Union(union) => {
+                    let members = union.types.iter().map(|member| member.name.value.as_str());
+                    self.possible_types.entry(name).or_default().extend(members);
+                }
+                TypeDefinitionNode::Object(_) => {
+                    for interface in self.interfaces(name) {
+                        if let Some(TypeDefinitionNode::Interface(_)) = self.get_type(interface) {
+                            self.possible_types.entry(interface).or_default().push(name);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn operation_types(
+        &mut self,
+        operations: impl IntoIterator<Item = &'a OperationTypeDefinitionNode>,
+    ) {
+        for operation in operations {
+            let name = Some(operation.node_type.name.value.as_str());
+            match operation.operation {
+                Operation::Query => self.query = name,
+                Operation::Mutation => self.mutation = name,
+                Operation::Subscription => self.subscription = name,
+            }
+        }
+    }
+
+    fn defined_name(&self, name: &str) -> Option<&'a str> {
+        self.types.get(name).map(|definition| definition.name())
+    }
+
+    /// The directives applied to the `schema` definition, followed by the ones `extend schema`
+    /// applies.
+    pub fn schema_directives(&self) -> &[&'a DirectiveNode] {
+        &self.schema_directives
+    }
+
+    /// The description of the `schema` definition, if any.
+    pub fn description(&self) -> Option<&'a str> {
+        self.description
+    }
+
+    /// The name of the root query type.
+    pub fn query_type(&self) -> Option<&'a str> {
+        self.query
+    }
+
+    /// The name of the root mutation type.
+    pub fn mutation_type(&self) -> Option<&'a str> {
+        self.mutation
+    }
+
+    /// The name of the root subscription type.
+    pub fn subscription_type(&self) -> Option<&'a str> {
+        self.subscription
+    }
+
+    /// Looks up a type definition by name.
+    pub fn get_type(&self, name: &str) -> Option<&'a TypeDefinitionNode> {
+        self.types.get(name).copied()
+    }
+
+    /// Returns true if the name refers to a defined type.
+    pub fn has_type(&self, name: &str) -> bool {
+        self.types.contains_key(name)
+    }
+
+    /// Looks up a directive definition by name, without the leading `@`.
+    pub fn get_directive(&self, name: &str) -> Option<&'a DirectiveDefinitionNode> {
+        self.directives.get(name).copied()
+    }
+
+    /// All directive definitions in the order they appear in the document.
+    pub fn directives(&self) -> impl Iterator<Item = &'a DirectiveDefinitionNode> + '_ {
+        self.directive_order
+            .iter()
+            .map(move |name| self.directives[name])
+    }
+
+    /// All type definitions in the order they appear in the document, followed by the built-in
+    /// scalars the document does not define.
+    pub fn types(&self) -> impl Iterator<Item = &'a TypeDefinitionNode> + '_ {
+        self.type_order.iter().map(move |name| self.types[name])
+    }
+
+    /// The fields of an object or interface type, including the fields added by extensions.
+    /// Returns an empty list for any other kind of type.
+    pub fn fields(&self, type_name: &str) -> Vec<&'a FieldDefinitionNode> {
+        let mut fields: Vec<&'a FieldDefinitionNode> = match self.get_type(type_name) {
+            Some(TypeDefinitionNode::Object(object)) => object.fields.iter().collect(),
+            Some(TypeDefinitionNode::Interface(interface)) => interface.fields.iter().collect(),
+            _ => Vec::new(),
+        };
+        for extension in self.extensions_of(type_name) {
+            if let Some(extension_fields) = &extension.fields {
+                fields.extend(extension_fields.iter());
+            }
+        }
+        fields
+    }
+
+    /// Looks up a single field of an object or interface type.
+    pub fn field(&self, type_name: &str, field_name: &str) -> Option<&'a FieldDefinitionNode> {
+        self.fields(type_name)
+            .into_iter()
+            .find(|field| field.name.value == field_name)
+    }
+
+    /// The interfaces implemented by an object type, including the ones added by extensions.
+    pub fn interfaces(&self, type_name: &str) -> Vec<&'a str> {
+        let mut interfaces = Vec::new();
+        if let Some(TypeDefinitionNode::Object(object)) = self.get_type(type_name) {
+            if let Some(names) = &object.interfaces {
+                interfaces.extend(names.iter().map(|name| name.name.value.as_str()));
+            }
+        }
+        for extension in self.extensions_of(type_name) {
+            if let Some(names) = &extension.interfaces {
+                interfaces.extend(names.iter().map(|name| name.name.value.as_str()));
+            }
+        }
+        interfaces
+    }
+
+    /// The object types that can be returned in place of an abstract type: the members of a
+    /// union or the implementations of an interface.
+    pub fn possible_types(&self, type_name: &str) -> Vec<&'a str> {
+        self.possible_types
+            .get(type_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Whether an object type implements an interface, directly or through an extension.
+    pub fn implements(&self, object: &str, interface: &str) -> bool {
+        match self.get_type(interface) {
+            Some(TypeDefinitionNode::Interface(_)) => self.is_possible_type(interface, object),
+            _ => false,
+        }
+    }
+
+    /// Whether a type can be returned in place of another: it is the same type, or an object type
+    /// of the union or the interface the other is. A fragment on `abstract_type` applies to the
+    /// objects of every type for which `is_subtype(object, abstract_type)` holds.
+    pub fn is_subtype(&self, maybe_subtype: &str, super_type: &str) -> bool {
+        maybe_subtype == super_type || self.is_possible_type(super_type, maybe_subtype)
+    }
+
+    fn is_possible_type(&self, abstract_type: &str, object: &str) -> bool {
+        self.possible_types
+            .get(abstract_type)
+            .is_some_and(|objects| objects.contains(&object))
+    }
+
+    /// The types no operation can reach, in the order of the document.
+    ///
+    /// A type is reachable from the root operation types, and from the arguments of the
+    /// directives defined, through the types of fields, arguments and input fields, the
+    /// interfaces an object implements and the possible types of an abstract type. The built-in
+    /// scalars are never unreachable, and every other type is when the schema has no root
+    /// operation type.
+    pub fn unreachable_types(&self) -> Vec<&'a str> {
+        let mut pending: Vec<&'a str> = [self.query, self.mutation, self.subscription]
+            .iter()
+            .flatten()
+            .copied()
+            .collect();
+        for directive in self.directives() {
+            for argument in directive.arguments.iter().flatten() {
+                pending.push(named_type(&argument.input_type));
+            }
+        }
+        let mut reached = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if !reached.insert(name) {
+                continue;
+            }
+            match self.get_type(name) {
+                Some(TypeDefinitionNode::Object(_)) | Some(TypeDefinitionNode::Interface(_)) => {
+                    for field in self.fields(name) {
+                        pending.push(named_type(&field.field_type));
+                        for argument in field.arguments.iter().flatten() {
+                            pending.push(named_type(&argument.input_type));
+                        }
+                    }
+                    pending.extend(self.interfaces(name));
+                    pending.extend(self.possible_types(name));
+                }
+                Some(TypeDefinitionNode::Union(_)) => pending.extend(self.possible_types(name)),
+                Some(TypeDefinitionNode::Input(input)) => pending.extend(
+                    input
+                        .fields
+                        .iter()
+                        .map(|field| named_type(&field.input_type)),
+                ),
+                _ => {}
+            }
+        }
+        self.type_order
+            .iter()
+            .filter(|name| !reached.contains(*name) && !BUILTIN_SCALARS.contains(name))
+            .copied()
+            .collect()
+    }
+
+    /// The extensions applied to a type.
+    pub fn extensions_of(&self, type_name: &str) -> &[&'a ObjectTypeExtensionNode] {
+        self.extensions
+            .get(type_name)
+            .map(|extensions| extensions.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// The URL of the specification of a scalar, given by its `@specifiedBy` directive.
+    pub fn scalar_spec_url(&self, scalar: &str) -> Option<&'a str> {
+        let directives = match self.get_type(scalar)? {
+            TypeDefinitionNode::Scalar(scalar) => scalar.directives.as_ref()?,
+            _ => return None,
+        };
+        let directive = directives
+            .iter()
+            .find(|directive| directive.name.value == "specifiedBy")?;
+        match self.resolved_arguments(directive).get("url")? {
+            ValueNode::Str(url) => Some(url.value.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Whether an input object is a OneOf Input Object, marked with the `@oneOf` directive, of
+    /// which exactly one field is given, and is not null.
+    pub fn is_one_of(&self, input: &str) -> bool {
+        match self.get_type(input) {
+            Some(TypeDefinitionNode::Input(input)) => input
+                .directives
+                .iter()
+                .flatten()
+                .any(|directive| directive.name.value == "oneOf"),
+            _ => false,
+        }
+    }
+
+    /// Finds the definition a [schema coordinate] names, or `None` when the schema does not
+    /// define it. The fields of objects include the ones added by extensions.
+    ///
+    /// [schema coordinate]: ../coordinate/index.html
+    pub fn resolve_coordinate(&self, coordinate: &SchemaCoordinate) -> Option<SchemaElement<'a>> {
+        match coordinate {
+            SchemaCoordinate::Type(type_name) => self.get_type(type_name).map(SchemaElement::Type),
+            SchemaCoordinate::Member { type_name, member } => match self.get_type(type_name)? {
+                TypeDefinitionNode::Object(_) | TypeDefinitionNode::Interface(_) => {
+                    self.field(type_name, member).map(SchemaElement::Field)
+                }
+                TypeDefinitionNode::Input(input) => input
+                    .fields
+                    .iter()
+                    .find(|field| field.name.value == *member)
+                    .map(SchemaElement::InputField),
+                TypeDefinitionNode::Enum(enum_type) => enum_type
+                    .values
+                    .iter()
+                    .find(|value| value.name.value == *member)
+                    .map(SchemaElement::EnumValue),
+                TypeDefinitionNode::Scalar(_) | TypeDefinitionNode::Union(_) => None,
+            },
+            SchemaCoordinate::Argument {
+                type_name,
+                field,
+                argument,
+            } => self
+                .field(type_name, field)?
+                .arguments
+                .iter()
+                .flatten()
+                .find(|defined| defined.name.value == *argument)
+                .map(SchemaElement::Argument),
+            SchemaCoordinate::Directive(directive) => {
+                self.get_directive(directive).map(SchemaElement::Directive)
+            }
+            SchemaCoordinate::DirectiveArgument {
+                directive,
+                argument,
+            } => self
+                .get_directive(directive)?
+                .arguments
+                .iter()
+                .flatten()
+                .find(|defined| defined.name.value == *argument)
+                .map(SchemaElement::Argument),
+        }
+    }
+
+    /// The arguments a directive is applied with, and the default values of the arguments of its
+    /// definition it is not given, by name. Only the given arguments are known for an undefined
+    /// directive.
+    pub fn resolved_arguments<'d>(
+        &self,
+        directive: &'d DirectiveNode,
+    ) -> HashMap<&'d str, &'d ValueNode>
+    where
+        'a: 'd,
+    {
+        let mut arguments: HashMap<&'d str, &'d ValueNode> = directive
+            .arguments
+            .iter()
+            .flatten()
+            .map(|argument| (argument.name.value.as_str(), &argument.value))
+            .collect();
+        let definitions = self
+            .get_directive(&directive.name.value)
+            .and_then(|definition| definition.arguments.as_ref());
+        for definition in definitions.into_iter().flatten() {
+            if let Some(default_value) = &definition.default_value {
+                arguments
+                    .entry(definition.name.value.as_str())
+                    .or_insert(default_value);
+            }
+        }
+        arguments
+    }
+
+    /// Checks that the schema is complete: the root operation types are defined object types,
+    /// every type referred to is defined, every extension extends a defined object type without
+    /// redefining its fields, and no input object requires itself through a chain of non-null
+    /// fields, which no value could satisfy. The fields of OneOf Input Objects are nullable and
+    /// have no default value.
+    ///
+    /// The directives applied to the definitions of the schema are given the arguments their
+    /// definition has, with values of their type once the default values are merged in, and
+    /// every argument they require.
+    ///
+    /// The types, fields, arguments and directives the schema defines must not have a name
+    /// beginning with `__`, which is reserved for introspection.
+    ///
+    /// Returns every issue found, in the order of the document.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        self.validate_with_options(&ValidatorOptions::default())
+    }
+
+    /// Checks that the schema is complete like [`Schema::validate`], with options.
+    ///
+    /// [`Schema::validate`]: struct.Schema.html#method.validate
+    pub fn validate_with_options(&self, options: &ValidatorOptions) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        for (operation, name) in [
+            ("query", self.query),
+            ("mutation", self.mutation),
+            ("subscription", self.subscription),
+        ] {
+            match name.map(|name| (name, self.get_type(name))) {
+                Some((_, Some(TypeDefinitionNode::Object(_)))) | None => (),
+                Some((name, _)) => errors.push(ValidationError::new(&format!(
+                    "The {} root type {} is not a defined object type",
+                    operation, name
+                ))),
+            }
+        }
+        for definition in self.types() {
+            self.validate_references(definition, &mut errors);
+        }
+        if !options.allow_reserved_names {
+            self.validate_names(&mut errors);
+        }
+        self.validate_applied_directives(options, &mut errors);
+        let mut visited = HashSet::new();
+        let mut path = Vec::new();
+        for definition in self.types() {
+            if let TypeDefinitionNode::Input(input) = definition {
+                self.validate_input_cycles(input, &mut visited, &mut path, &mut errors);
+                if self.is_one_of(&input.name.value) {
+                    self.validate_one_of(input, &mut errors);
+                }
+            }
+        }
+        let mut extended: Vec<&str> = self.extensions.keys().copied().collect();
+        extended.sort_unstable();
+        for name in extended {
+            let original = match self.get_type(name) {
+                Some(TypeDefinitionNode::Object(object)) => Some(object),
+                _ => None,
+            };
+            for extension in self.extensions_of(name) {
+                let validated = extension
+                    .validate()
+                    .and_then(|_| extension.validate_extension(original));
+                if let Err(error) = validated {
+                    errors.push(error);
+                }
+                for field in extension.fields.iter().flatten() {
+                    self.validate_field(name, field, &mut errors);
+                }
+                for interface in extension.interfaces.iter().flatten() {
+                    self.validate_reference(name, &interface.name.value, &mut errors);
+                }
+            }
+        }
+        errors
+    }
+
+    fn validate_references(
+        &self,
+        definition: &TypeDefinitionNode,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let name = definition.name();
+        match definition {
+            TypeDefinitionNode::Object(object) => {
+                for interface in object.interfaces.iter().flatten() {
+                    self.validate_reference(name, &interface.name.value, errors);
+                }
+                for field in &object.fields {
+                    self.validate_field(name, field, errors);
+                }
+            }
+            TypeDefinitionNode::Interface(interface) => {
+                for field in &interface.fields {
+                    self.validate_field(name, field, errors);
+                }
+            }
+            TypeDefinitionNode::Union(union) => {
+                for member in &union.types {
+                    self.validate_reference(name, &member.name.value, errors);
+                }
+            }
+            TypeDefinitionNode::Input(input) => {
+                for field in &input.fields {
+                    self.validate_reference(name, named_type(&field.input_type), errors);
+                }
+            }
+            TypeDefinitionNode::Scalar(_) | TypeDefinitionNode::Enum(_) => (),
+        }
+    }
+
+    fn validate_field(
+        &self,
+        type_name: &str,
+        field: &FieldDefinitionNode,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        self.validate_reference(type_name, named_type(&field.field_type), errors);
+        for argument in field.arguments.iter().flatten() {
+            self.validate_reference(type_name, named_type(&argument.input_type), errors);
+        }
+    }
+
+    fn validate_applied_directives(
+        &self,
+        options: &ValidatorOptions,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let validator = Validator::with_options(self, *options);
+        let mut check = |coordinate: String, directives: &Option<Directives>| {
+            for directive in directives.iter().flatten() {
+                self.validate_applied_directive(&validator, &coordinate, directive, errors);
+            }
+        };
+        for definition in self.types() {
+            let type_name = definition.name();
+            let (directives, fields) = match definition {
+                TypeDefinitionNode::Scalar(scalar) => (&scalar.directives, &[][..]),
+                TypeDefinitionNode::Object(object) => (&object.directives, &object.fields[..]),
+                TypeDefinitionNode::Interface(interface) => {
+                    (&interface.directives, &interface.fields[..])
+                }
+                TypeDefinitionNode::Union(union) => (&union.directives, &[][..]),
+                TypeDefinitionNode::Enum(enum_type) => {
+                    for value in &enum_type.values {
+                        check(
+                            format!("{}.{}", type_name, value.name.value),
+                            &value.directives,
+                        );
+                    }
+                    (&enum_type.directives, &[][..])
+                }
+                TypeDefinitionNode::Input(input) => {
+                    for field in &input.fields {
+                        check(
+                            format!("{}.{}", type_name, field.name.value),
+                            &field.directives,
+                        );
+                    }
+                    (&input.directives, &[][..])
+                }
+            };
+            check(type_name.to_owned(), directives);
+            let extension_fields = self
+                .extensions_of(type_name)
+                .iter()
+                .flat_map(|extension| extension.fields.iter().flatten());
+            for field in fields.iter().chain(extension_fields) {
+                check(
+                    format!("{}.{}", type_name, field.name.value),
+                    &field.directives,
+                );
+                for argument in field.arguments.iter().flatten() {
+                    check(
+                        format!(
+                            "{}.{}({}:)",
+                            type_name, field.name.value, argument.name.value
+                        ),
+                        &argument.directives,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Checks the arguments of a directive applied to the definition at a schema coordinate,
+    /// when the directive is defined.
+    fn validate_applied_directive(
+        &self,
+        validator: &Validator,
+        coordinate: &str,
+        directive: &DirectiveNode,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let definition = match self.get_directive(&directive.name.value) {
+            Some(definition) => definition,
+            None => return,
+        };
+        let definitions = definition.arguments.as_deref().unwrap_or_default();
+        let name = &directive.name.value;
+        let mut error = |message: String| {
+            errors.push(ValidationError::new(&format!(
+                "Directive @{} on {}: {}",
+                name, coordinate, message
+            )))
+        };
+        for argument in directive.arguments.iter().flatten() {
+            if !definitions
+                .iter()
+                .any(|definition| definition.name.value == argument.name.value)
+            {
+                error(format!("Unknown argument \"{}\".", argument.name.value));
+            }
+        }
+        let arguments = self.resolved_arguments(directive);
+        for definition in definitions {
+            match arguments.get(definition.name.value.as_str()) {
+                Some(value) => {
+                    for issue in validator.check_value(value, &definition.input_type) {
+                        error(issue.message);
+                    }
+                }
+                None if matches!(definition.input_type, TypeNode::NonNull(_)) => error(format!(
+                    "Argument \"{}\" of type \"{}\" is required, but it was not provided.",
+                    definition.name.value,
+                    print_type(&definition.input_type)
+                )),
+                None => {}
+            }
+        }
+    }
+
+    fn validate_names(&self, errors: &mut Vec<ValidationError>) {
+        let mut reserved = |kind: &str, name: String| {
+            errors.push(ValidationError::new(&format!(
+                "{} {} begins with \"__\", which is reserved for introspection",
+                kind, name
+            )))
+        };
+        for definition in self.types() {
+            let type_name = definition.name();
+            if type_name.starts_with("__") {
+                reserved("Type", type_name.to_owned());
+            }
+            let fields = match definition {
+                TypeDefinitionNode::Object(object) => &object.fields,
+                TypeDefinitionNode::Interface(interface) => &interface.fields,
+                TypeDefinitionNode::Input(input) => {
+                    for field in &input.fields {
+                        if field.name.value.starts_with("__") {
+                            reserved("Field", format!("{}.{}", type_name, field.name.value));
+                        }
+                    }
+                    continue;
+                }
+                _ => continue,
+            };
+            let extension_fields = self
+                .extensions_of(type_name)
+                .iter()
+                .flat_map(|extension| extension.fields.iter().flatten());
+            for field in fields.iter().chain(extension_fields) {
+                if field.name.value.starts_with("__") {
+                    reserved("Field", format!("{}.{}", type_name, field.name.value));
+                }
+                for argument in field.arguments.iter().flatten() {
+                    if argument.name.value.starts_with("__") {
+                        reserved(
+                            "Argument",
+                            format!(
+                                "{}.{}({}:)",
+                                type_name, field.name.value, argument.name.value
+                            ),
+                        );
+                    }
+                }
+            }
+        }
+        for directive in self.directives() {
+            if directive.name.value.starts_with("__") {
+                reserved("Directive", format!("@{}", directive.name.value));
+            }
+        }
+    }
+
+    /// Checks that every field of a OneOf Input Object may be left out: it is nullable and has no
+    /// default value.
+    fn validate_one_of(&self, input: &InputTypeDefinitionNode, errors: &mut Vec<ValidationError>) {
+        for field in &input.fields {
+            let coordinate = format!("{}.{}", input.name.value, field.name.value);
+            if let TypeNode::NonNull(_) = field.input_type {
+                errors.push(ValidationError::new(&format!(
+                    "OneOf input field {} must be nullable",
+                    coordinate
+                )));
+            }
+            if field.default_value.is_some() {
+                errors.push(ValidationError::new(&format!(
+                    "OneOf input field {} cannot have a default value",
+                    coordinate
+                )));
+            }
+        }
+    }
+
+    /// Follows the non-null fields of an input object to the input objects they hold, reporting
+    /// the chains that lead back to an input object of the path.
+    fn validate_input_cycles(
+        &self,
+        input: &'a InputTypeDefinitionNode,
+        visited: &mut HashSet<&'a str>,
+        path: &mut Vec<(&'a str, &'a str)>,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        if !visited.insert(&input.name.value) {
+            return;
+        }
+        for field in &input.fields {
+            let field_type = match &field.input_type {
+                TypeNode::NonNull(inner) => match &**inner {
+                    TypeNode::Named(named) => &named.name.value,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            let referenced = match self.get_type(field_type) {
+                Some(TypeDefinitionNode::Input(referenced)) => referenced,
+                _ => continue,
+            };
+            path.push((&input.name.value, &field.name.value));
+            match path
+                .iter()
+                .position(|(type_name, _)| *type_name == field_type)
+            {
+                Some(start) => {
+                    let fields: Vec<&str> = path[start..].iter().map(|(_, field)| *field).collect();
+                    errors.push(ValidationError::new(&format!(
+                        "Cannot reference input object {} within itself through the non-null fields {}",
+                        field_type,
+                        fields.join(".")
+                    )));
+                }
+                None => self.validate_input_cycles(referenced, visited, path, errors),
+            }
+            path.pop();
+        }
+    }
+
+    fn validate_reference(&self, type_name: &str, name: &str, errors: &mut Vec<ValidationError>) {
+        if !self.has_type(name) {
+            errors.push(ValidationError::new(&format!(
+                "Unknown type {} referred to by {}",
+                name, type_name
+            )));
+        }
+    }
+}
+
+/// The name of the type wrapped by list and non-null types.
+pub fn named_type(mut type_node: &TypeNode) -> &str {
+    loop {
+        match type_node {
+            TypeNode::Named(named) => return &named.name.value,
+            TypeNode::List(list) => type_node = &list.list_type,
+            TypeNode::NonNull(inner) => type_node = inner,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::print_value;
+
+    #[test]
+    fn it_indexes_types() {
+        let document = parse(
+            r#"
+type Query { pets: [Pet] }
+interface Named { name: String }
+type Dog implements Named { name: String }
+type Cat { name: String }
+extend type Cat implements Named
+union Pet = Dog | Cat
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        assert_eq!(
+            schema.types().map(|t| t.name()).collect::<Vec<&str>>(),
+            vec!["Query", "Named", "Dog", "Cat", "Pet", "Int", "Float", "String", "Boolean", "ID"]
+        );
+        assert!(schema.has_type("Pet"));
+        assert!(schema.has_type("String"));
+        assert!(!schema.has_type("Bird"));
+        assert_eq!(schema.interfaces("Cat"), vec!["Named"]);
+        assert_eq!(schema.possible_types("Named"), vec!["Dog", "Cat"]);
+        assert_eq!(schema.possible_types("Pet"), vec!["Dog", "Cat"]);
+        assert!(schema.possible_types("Dog").is_empty());
+        assert!(schema.implements("Cat", "Named"));
+        assert!(!schema.implements("Cat", "Pet"));
+        assert!(!schema.implements("Query", "Named"));
+        assert!(schema.is_subtype("Dog", "Pet"));
+        assert!(schema.is_subtype("Cat", "Named"));
+        assert!(schema.is_subtype("Named", "Named"));
+        assert!(!schema.is_subtype("Query", "Pet"));
+        assert!(!schema.is_subtype("Pet", "Dog"));
+    }
+
+    #[test]
+    fn it_resolves_root_types_by_convention() {
+        let document = parse("type Query { a: Int } type Mutation { b: Int }").unwrap();
+        let schema = Schema::new(&document);
+        assert_eq!(schema.query_type(), Some("Query"));
+        assert_eq!(schema.mutation_type(), Some("Mutation"));
+        assert_eq!(schema.subscription_type(), None);
+    }
+
+    #[test]
+    fn it_resolves_root_types_from_the_schema_definition() {
+        let document = parse(
+            r#"
+"The root"
+schema { query: Root }
+type Root { a: Int }
+type Query { b: Int }
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        assert_eq!(schema.description(), Some("The root"));
+        assert_eq!(schema.query_type(), Some("Root"));
+        assert_eq!(schema.mutation_type(), None);
+    }
+
+    #[test]
+    fn it_merges_extension_fields() {
+        let document = parse(
+            r#"
+type User { id: ID! }
+extend type User { name: String }
+extend type User @cached
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        let fields: Vec<&str> = schema
+            .fields("User")
+            .iter()
+            .map(|f| f.name.value.as_str())
+            .collect();
+        assert_eq!(fields, vec!["id", "name"]);
+        assert!(schema.field("User", "name").is_some());
+        assert!(schema.field("User", "email").is_none());
+        assert_eq!(schema.extensions_of("User").len(), 2);
+    }
+
+    #[test]
+    fn first_definition_wins() {
+        let document = parse("scalar Float scalar Float type Float { a: Int }").unwrap();
+        let schema = Schema::with_builtins(&document, false);
+        assert_eq!(schema.types().count(), 1);
+        assert!(matches!(
+            schema.get_type("Float"),
+            Some(TypeDefinitionNode::Scalar(_))
+        ));
+    }
+
+    #[test]
+    fn it_injects_builtin_definitions() {
+        let document = parse(
+            r#"
+"Only kept for one release"
+directive @deprecated(reason: String) on FIELD_DEFINITION
+directive @cached(ttl: Int) repeatable on OBJECT | FIELD_DEFINITION
+type Query { a: Int }
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        assert!(schema.has_type("Int"));
+        assert!(schema.get_directive("skip").is_some());
+        assert!(schema.get_directive("cached").unwrap().repeatable);
+        assert_eq!(
+            schema
+                .get_directive("deprecated")
+                .and_then(|d| d.description.as_ref())
+                .map(|d| d.value.as_str()),
+            Some("Only kept for one release")
+        );
+        assert_eq!(
+            schema
+                .directives()
+                .map(|d| d.name.value.as_str())
+                .collect::<Vec<&str>>(),
+            vec![
+                "deprecated",
+                "cached",
+                "include",
+                "skip",
+                "specifiedBy",
+                "oneOf"
+            ]
+        );
+
+        let schema = Schema::with_builtins(&document, false);
+        assert!(!schema.has_type("Int"));
+        assert!(schema.get_directive("skip").is_none());
+        assert_eq!(schema.directives().count(), 2);
+    }
+
+    #[test]
+    fn it_validates_a_complete_schema() {
+        let document = parse(
+            r#"
+type Query { pets(first: Int, filter: PetFilter): [Pet!]! }
+interface Named { name: String }
+type Dog implements Named { name: String }
+type Cat { name: String }
+extend type Cat implements Named { age: Int }
+union Pet = Dog | Cat
+input PetFilter { names: [String!] }
+"#,
+        )
+        .unwrap();
+        let errors = Schema::new(&document).validate();
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn it_reports_missing_types() {
+        let document = parse(
+            r#"
+schema { query: Root, mutation: Int }
+type Query { user(id: UserId!): [User!] }
+union Result = Query | Missing
+extend type Ghost { a: Int }
+"#,
+        )
+        .unwrap();
+        let messages: Vec<String> = Schema::new(&document)
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "The query root type Root is not a defined object type",
+                "The mutation root type Int is not a defined object type",
+                "Unknown type User referred to by Query",
+                "Unknown type UserId referred to by Query",
+                "Unknown type Missing referred to by Result",
+                "Invalid Object Extension Ghost: No type of name Ghost in schema",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reports_reserved_names() {
+        let document = parse(
+            r#"
+type Query { user(__id: ID): __User __typename: String }
+type __User { name: String }
+extend type Query { __rollback: Int }
+input __Filter { __name: String }
+directive @__internal on FIELD
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        let messages: Vec<String> = schema
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Argument Query.user(__id:) begins with \"__\", which is reserved for introspection",
+                "Field Query.__typename begins with \"__\", which is reserved for introspection",
+                "Field Query.__rollback begins with \"__\", which is reserved for introspection",
+                "Type __User begins with \"__\", which is reserved for introspection",
+                "Type __Filter begins with \"__\", which is reserved for introspection",
+                "Field __Filter.__name begins with \"__\", which is reserved for introspection",
+                "Directive @__internal begins with \"__\", which is reserved for introspection",
+            ]
+        );
+        let options = ValidatorOptions {
+            allow_reserved_names: true,
+            ..ValidatorOptions::default()
+        };
+        assert!(schema.validate_with_options(&options).is_empty());
+    }
+
+    #[test]
+    fn it_reports_input_objects_requiring_themselves() {
+        let document = parse(
+            r#"
+input A { b: B! }
+input B { a: A!, c: C! }
+input C { c: C!, name: String! }
+input Self { next: Self! }
+input Lists { all: [Lists!]!, optional: Lists, list: D! }
+input D { lists: [Lists!]! }
+"#,
+        )
+        .unwrap();
+        let messages: Vec<String> = Schema::new(&document)
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Cannot reference input object A within itself through the non-null fields b.a",
+                "Cannot reference input object C within itself through the non-null fields c",
+                "Cannot reference input object Self within itself through the non-null fields next",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_reads_specified_by_urls_and_one_of_input_objects() {
+        let document = parse(
+            r#"
+scalar Date @specifiedBy(url: "https://example.com/date")
+scalar Json
+input Pick @oneOf { id: ID, name: String }
+input Filter { id: ID }
+input Invalid @oneOf { id: ID!, name: String = "a" }
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        assert_eq!(
+            schema.scalar_spec_url("Date"),
+            Some("https://example.com/date")
+        );
+        assert_eq!(schema.scalar_spec_url("Json"), None);
+        assert_eq!(schema.scalar_spec_url("Pick"), None);
+        assert!(schema.is_one_of("Pick"));
+        assert!(!schema.is_one_of("Filter"));
+        assert!(!schema.is_one_of("Date"));
+        let messages: Vec<String> = schema
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "OneOf input field Invalid.id must be nullable",
+                "OneOf input field Invalid.name cannot have a default value",
+            ]
+        );
+    }
+
+    #[test]
+    fn it_resolves_the_arguments_of_applied_directives() {
+        let document = parse(
+            r#"
+type Query @cached {
+    user(id: ID @deprecated(reason: 1)): String @cached(ttl: "long", scope: PUBLIC)
+    users: [String] @cached(scope: PRIVATE, size: 10) @auth
+}
+enum Scope { PUBLIC PRIVATE }
+enum Role { ADMIN @deprecated(reason: null) }
+directive @cached(ttl: Int = 60, scope: Scope = PUBLIC) on OBJECT | FIELD_DEFINITION
+directive @auth(role: Role!, scope: Scope = "PRIVATE") on FIELD_DEFINITION
+"#,
+        )
+        .unwrap();
+        let schema = Schema::new(&document);
+        let messages: Vec<String> = schema
+            .validate()
+            .into_iter()
+            .map(|error| error.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Directive @cached on Query.user: Int cannot represent non-integer value: \"long\"",
+                "Directive @deprecated on Query.user(id:): String cannot represent a non string value: 1",
+                "Directive @cached on Query.users: Unknown argument \"size\".",
+                "Directive @auth on Query.users: Argument \"role\" of type \"Role!\" is required, but it was not provided.",
+                "Directive @auth on Query.users: Enum \"Scope\" cannot represent non-enum value: \"PRIVATE\". Did you mean the enum value \"PRIVATE\"?",
+            ]
+        );
+        let users = schema.field("Query", "users").unwrap();
+        let arguments = schema.resolved_arguments(&users.directives.as_ref().unwrap()[0]);
+        let mut arguments: Vec<(&str, String)> = arguments
+            .into_iter()
+            .map(|(name, value)| (name, print_value(value)))
+            .collect();
+        arguments.sort();
+        assert_eq!(
+            arguments,
+            [
+                ("scope", "PRIVATE".into()),
+                ("size", "10".into()),
+                ("ttl", "60".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn it_finds_unreachable_types() {
+        let document = parse(
+            "schema { query: Root }
+            type Root { node(id: ID!, filter: Filter): Node search: Result }
+            interface Node { id: ID! }
+            type User implements Node { id: ID! }
+            extend type User { role: Role }
+            enum Role { ADMIN }
+            union Result = Post
+            type Post { id: ID! }
+            input Filter { scope: Scope }
+            enum Scope { ALL }
+            type Query { orphan: Orphan }
+            type Orphan { id: ID }
+            scalar Date
+            enum Level { LOW }
+            directive @limit(level: Level) on FIELD_DEFINITION",
+        )
+        .unwrap();
+        assert_eq!(
+            Schema::new(&document).unreachable_types(),
+            ["Query", "Orphan", "Date"]
+        );
+        let document = parse("type User { id: ID }").unwrap();
+        assert_eq!(Schema::new(&document).unreachable_types(), ["User"]);
+    }
+}
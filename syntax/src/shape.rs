@@ -0,0 +1,349 @@
+//! Projects the response shape of an operation.
+//!
+//! Given a [`Schema`] and an operation, [`response_shape`] resolves every selected field against
+//! the schema and returns the tree of fields the response will contain: the response key, the
+//! field type and its nullability, and the shape of any sub-selection. Fragments are flattened;
+//! fields that only apply to some of the possible types of an abstract type keep the type
+//! condition they were selected under.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::schema::Schema;
+//! use syntax::shape::response_shape;
+//!
+//! let schema_document = syntax::parse("type Query { me: User } type User { id: ID! }").unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let query = syntax::parse("{ viewer: me { id } }").unwrap();
+//! let operations = query.operations();
+//! let shape = response_shape(&schema, &operations[0]).unwrap();
+//! assert_eq!(shape[0].response_key, "viewer");
+//! assert_eq!(shape[0].type_name(), "User");
+//! assert!(shape[0].nullable());
+//! assert_eq!(shape[0].children[0].name, "id");
+//! ```
+//!
+//! [`Schema`]: ../schema/struct.Schema.html
+//! [`response_shape`]: fn.response_shape.html
+
+use crate::document::OperationWithFragments;
+use crate::error::ValidationError;
+use crate::nodes::*;
+use crate::schema::Schema;
+
+lazy_static! {
+    static ref TYPENAME_TYPE: TypeNode = TypeNode::NonNull(std::sync::Arc::new(TypeNode::Named(
+        NamedTypeNode::from("String")
+    )));
+}
+
+/// A field of the response.
+#[derive(Debug, PartialEq)]
+pub struct FieldShape<'a> {
+    /// The key of the field in the response: its alias, or its name when it has none
+    pub response_key: &'a str,
+    /// The name of the field in the schema
+    pub name: &'a str,
+    /// The alias of the field, if any
+    pub alias: Option<&'a str>,
+    /// The type the field is selected on
+    pub parent_type: &'a str,
+    /// The concrete type the field only applies to, when it was selected through a fragment on a
+    /// narrower type than the parent
+    pub type_condition: Option<&'a str>,
+    /// The type of the field, as declared in the schema
+    pub field_type: &'a TypeNode,
+    /// The fields selected on the field's type
+    pub children: Vec<FieldShape<'a>>,
+}
+
+impl<'a> FieldShape<'a> {
+    /// The name of the innermost type of the field, without list or non-null wrappers.
+    pub fn type_name(&self) -> &'a str {
+        let mut type_node = self.field_type;
+        loop {
+            match type_node {
+                TypeNode::Named(named) => return &named.name.value,
+                TypeNode::List(list) => type_node = &list.list_type,
+                TypeNode::NonNull(inner) => type_node = inner,
+            }
+        }
+    }
+
+    /// Returns true if the field can be `null` in the response.
+    pub fn nullable(&self) -> bool {
+        !matches!(self.field_type, TypeNode::NonNull(_))
+    }
+
+    /// Returns true if the field is a list, possibly wrapped in a non-null type.
+    pub fn is_list(&self) -> bool {
+        match self.field_type {
+            TypeNode::NonNull(inner) => matches!(**inner, TypeNode::List(_)),
+            TypeNode::List(_) => true,
+            TypeNode::Named(_) => false,
+        }
+    }
+}
+
+struct Projector<'s, 'a> {
+    schema: &'s Schema<'a>,
+    operation: &'s OperationWithFragments<'a>,
+}
+
+impl<'s, 'a> Projector<'s, 'a> {
+    fn project(
+        &self,
+        parent_type: &'a str,
+        type_condition: Option<&'a str>,
+        selections: &'a [Selection],
+        shape: &mut Vec<FieldShape<'a>>,
+        visited: &mut Vec<&'a str>,
+    ) -> Result<(), ValidationError> {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    let field_shape = self.field(parent_type, type_condition, field)?;
+                    match shape.iter_mut().find(|existing| {
+                        existing.response_key == field_shape.response_key
+                            && existing.type_condition == field_shape.type_condition
+                    }) {
+                        Some(existing) => {
+                            if existing.name != field_shape.name {
+                                return Err(ValidationError::new(&format!(
+                                    "Fields `{}` and `{}` conflict because they are both selected as `{}`",
+                                    existing.name, field_shape.name, field_shape.response_key
+                                )));
+                            }
+                            existing.children.extend(field_shape.children)
+                        }
+                        None => shape.push(field_shape),
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let condition = inline
+                        .node_type
+                        .as_ref()
+                        .map(|node_type| node_type.name.value.as_str());
+                    let condition = self.narrow(parent_type, type_condition, condition);
+                    self.project(parent_type, condition, &inline.selections, shape, visited)?;
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    let name = spread.name.value.as_str();
+                    if visited.contains(&name) {
+                        continue;
+                    }
+                    let fragment = self
+                        .operation
+                        .fragments
+                        .iter()
+                        .find(|fragment| fragment.name.value == name)
+                        .ok_or_else(|| {
+                            ValidationError::new(&format!("Unknown fragment `{}`", name))
+                        })?;
+                    let condition = self.narrow(
+                        parent_type,
+                        type_condition,
+                        Some(&fragment.node_type.name.value),
+                    );
+                    visited.push(name);
+                    self.project(parent_type, condition, &fragment.selections, shape, visited)?;
+                    visited.pop();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// A fragment on the parent type or on an interface does not restrict the fields to a
+    /// narrower object type.
+    fn narrow(
+        &self,
+        parent_type: &'a str,
+        current: Option<&'a str>,
+        condition: Option<&'a str>,
+    ) -> Option<&'a str> {
+        match condition {
+            Some(condition)
+                if condition != parent_type
+                    && matches!(
+                        self.schema.get_type(condition),
+                        Some(TypeDefinitionNode::Object(_))
+                    ) =>
+            {
+                Some(condition)
+            }
+            _ => current,
+        }
+    }
+
+    fn field(
+        &self,
+        parent_type: &'a str,
+        type_condition: Option<&'a str>,
+        field: &'a FieldNode,
+    ) -> Result<FieldShape<'a>, ValidationError> {
+        let name = field.name.value.as_str();
+        let alias = field.alias.as_ref().map(|alias| alias.value.as_str());
+        let field_type: &'a TypeNode = if name == "__typename" {
+            &TYPENAME_TYPE
+        } else {
+            let owner = type_condition.unwrap_or(parent_type);
+            &self
+                .schema
+                .field(owner, name)
+                .or_else(|| self.schema.field(parent_type, name))
+                .ok_or_else(|| {
+                    ValidationError::new(&format!(
+                        "Cannot query field `{}` on type `{}`",
+                        name, owner
+                    ))
+                })?
+                .field_type
+        };
+        let mut shape = FieldShape {
+            response_key: alias.unwrap_or(name),
+            name,
+            alias,
+            parent_type,
+            type_condition,
+            field_type,
+            children: Vec::new(),
+        };
+        if let Some(selections) = &field.selections {
+            let mut children = Vec::new();
+            self.project(
+                shape.type_name(),
+                None,
+                selections,
+                &mut children,
+                &mut Vec::new(),
+            )?;
+            shape.children = children;
+        }
+        Ok(shape)
+    }
+}
+
+/// Computes the shape of the response to an operation, as returned by [`Document::operations`].
+///
+/// The operation is expected to be valid against the schema; selecting a field the schema does
+/// not define, spreading an unknown fragment or selecting two different fields under the same
+/// response key is reported as a [`ValidationError`].
+///
+/// [`Document::operations`]: ../document/struct.Document.html#method.operations
+/// [`ValidationError`]: ../error/struct.ValidationError.html
+pub fn response_shape<'a>(
+    schema: &Schema<'a>,
+    operation: &OperationWithFragments<'a>,
+) -> Result<Vec<FieldShape<'a>>, ValidationError> {
+    let root = match operation.operation.operation() {
+        Operation::Query => schema
+            .query_type()
+            .ok_or_else(|| ValidationError::new("The schema does not define a query type"))?,
+        Operation::Mutation => schema
+            .mutation_type()
+            .ok_or_else(|| ValidationError::new("The schema does not define a mutation type"))?,
+        Operation::Subscription => schema.subscription_type().ok_or_else(|| {
+            ValidationError::new("The schema does not define a subscription type")
+        })?,
+    };
+    let query = operation.operation.definition();
+    let projector = Projector { schema, operation };
+    let mut shape = Vec::new();
+    projector.project(root, None, &query.selections, &mut shape, &mut Vec::new())?;
+    Ok(shape)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SCHEMA: &str = r#"
+type Query { node(id: ID!): Node, users: [User!]! }
+interface Node { id: ID! }
+type User implements Node { id: ID!, name: String, friends: [User] }
+type Admin implements Node { id: ID!, level: Int! }
+"#;
+
+    #[test]
+    fn it_projects_fields_and_types() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let query = parse("{ users { id name friends { name } } }").unwrap();
+        let operations = query.operations();
+        let shape = response_shape(&schema, &operations[0]).unwrap();
+        assert_eq!(shape.len(), 1);
+        let users = &shape[0];
+        assert_eq!(users.type_name(), "User");
+        assert!(users.is_list());
+        assert!(!users.nullable());
+        let keys: Vec<&str> = users.children.iter().map(|f| f.response_key).collect();
+        assert_eq!(keys, vec!["id", "name", "friends"]);
+        assert!(!users.children[0].nullable());
+        assert!(users.children[1].nullable());
+        assert!(users.children[2].is_list());
+        assert_eq!(users.children[2].children[0].parent_type, "User");
+    }
+
+    #[test]
+    fn it_flattens_fragments_and_keeps_type_conditions() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let query = parse(
+            r#"
+query Node {
+  node(id: 1) {
+    __typename
+    ...NodeFields
+    ... on User { name }
+    ... on Admin { level }
+  }
+}
+fragment NodeFields on Node { id }
+"#,
+        )
+        .unwrap();
+        let operations = query.operations();
+        let shape = response_shape(&schema, &operations[0]).unwrap();
+        let children: Vec<(&str, Option<&str>, &str)> = shape[0]
+            .children
+            .iter()
+            .map(|f| (f.name, f.type_condition, f.type_name()))
+            .collect();
+        assert_eq!(
+            children,
+            vec![
+                ("__typename", None, "String"),
+                ("id", None, "ID"),
+                ("name", Some("User"), "String"),
+                ("level", Some("Admin"), "Int"),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_merges_fields_with_the_same_response_key() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let query = parse("{ users { id } users { name } }").unwrap();
+        let operations = query.operations();
+        let shape = response_shape(&schema, &operations[0]).unwrap();
+        assert_eq!(shape.len(), 1);
+        assert_eq!(shape[0].children.len(), 2);
+
+        let query = parse("{ users { id: name id } }").unwrap();
+        let operations = query.operations();
+        assert!(response_shape(&schema, &operations[0]).is_err());
+    }
+
+    #[test]
+    fn it_reports_unknown_fields() {
+        let schema_document = parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let query = parse("{ users { email } }").unwrap();
+        let operations = query.operations();
+        let error = response_shape(&schema, &operations[0]).unwrap_err();
+        assert!(error.message.contains("email"));
+    }
+}
@@ -0,0 +1,387 @@
+//! Measurements of a parse, for tracking the performance of the lexer and the parser.
+//!
+//! [`parse_stats`] parses a document again to time each step, while the [`ParseMetrics`] of
+//! [`parse_with_metrics`] and the [`ValidationMetrics`] of [`Validator::validate_with_metrics`]
+//! are taken as documents are parsed and validated, for servers to log and alert on expensive
+//! documents.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::stats::parse_stats;
+//!
+//! let stats = parse_stats("type Query { me: String }").unwrap();
+//! assert_eq!(stats.tokens, 7);
+//! assert_eq!(stats.definitions, 1);
+//! assert!(stats.nodes > stats.definitions);
+//! ```
+//!
+//! [`parse_stats`]: fn.parse_stats.html
+//! [`ParseMetrics`]: struct.ParseMetrics.html
+//! [`parse_with_metrics`]: ../fn.parse_with_metrics.html
+//! [`ValidationMetrics`]: struct.ValidationMetrics.html
+//! [`Validator::validate_with_metrics`]: ../validator/struct.Validator.html#method.validate_with_metrics
+
+use crate::document::Document;
+use crate::error::{ParseError, ParseResult};
+use crate::lexer::tokenize;
+use crate::nodes::object_type_extension::ObjectTypeExtensionNode;
+use crate::nodes::*;
+use crate::token::TokenKind;
+use std::time::{Duration, Instant};
+
+/// The size of a document and the time it took to lex and parse it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseStats {
+    /// The number of tokens in the input, not counting the start and end of the token stream
+    pub tokens: usize,
+    /// The number of nodes in the parsed document, names and descriptions included
+    pub nodes: usize,
+    /// The number of top-level definitions
+    pub definitions: usize,
+    /// The time taken to tokenize the input
+    pub lex_duration: Duration,
+    /// The time taken by [`parse`], which lexes the input as it goes
+    ///
+    /// [`parse`]: ../fn.parse.html
+    pub parse_duration: Duration,
+}
+
+/// The size of a document and the time it took to parse it, returned by [`parse_with_metrics`].
+///
+/// [`parse_with_metrics`]: ../fn.parse_with_metrics.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseMetrics {
+    /// The number of tokens in the input, not counting the start and end of the token stream
+    pub tokens: usize,
+    /// The number of nodes in the parsed document, names and descriptions included
+    pub nodes: usize,
+    /// How deeply the selection sets of the document nest, 0 when it has none
+    pub max_depth: usize,
+    /// The time taken to lex and parse the input
+    pub duration: Duration,
+}
+
+impl ParseMetrics {
+    pub(crate) fn new(document: &Document, tokens: usize, duration: Duration) -> ParseMetrics {
+        ParseMetrics {
+            tokens,
+            nodes: document.definitions.iter().map(definition).sum(),
+            max_depth: document.definitions.iter().map(depth).max().unwrap_or(0),
+            duration,
+        }
+    }
+}
+
+/// The time it took to validate a document and what was found, returned by
+/// [`Validator::validate_with_metrics`].
+///
+/// [`Validator::validate_with_metrics`]: ../validator/struct.Validator.html#method.validate_with_metrics
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationMetrics {
+    /// The number of definitions of the document, executable or not
+    pub definitions: usize,
+    /// The number of issues found
+    pub errors: usize,
+    /// The time taken to lex the source again and validate the document
+    pub duration: Duration,
+}
+
+/// Lexes and parses the input, and reports the size of the result and how long each step took.
+pub fn parse_stats(input: &str) -> ParseResult<ParseStats> {
+    let start = Instant::now();
+    let tokens = tokenize(input).map_err(ParseError::LexError)?;
+    let lex_duration = start.elapsed();
+    let tokens = tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, TokenKind::Start | TokenKind::End))
+        .count();
+
+    let start = Instant::now();
+    let document = crate::parse(input)?;
+    let parse_duration = start.elapsed();
+
+    Ok(ParseStats {
+        tokens,
+        nodes: document.definitions.iter().map(definition).sum(),
+        definitions: document.definitions.len(),
+        lex_duration,
+        parse_duration,
+    })
+}
+
+fn description(description: &Description) -> usize {
+    description.iter().count()
+}
+
+fn directives(directives: &Option<Directives>) -> usize {
+    directives
+        .iter()
+        .flatten()
+        .map(|directive| 2 + arguments(&directive.arguments))
+        .sum()
+}
+
+fn arguments(arguments: &Option<Arguments>) -> usize {
+    arguments
+        .iter()
+        .flatten()
+        .map(|argument| 2 + value(&argument.value))
+        .sum()
+}
+
+fn value(value: &ValueNode) -> usize {
+    1 + match value {
+        ValueNode::Variable(_) => 1,
+        ValueNode::List(list) => list.values.iter().map(self::value).sum(),
+        ValueNode::Object(object) => object
+            .fields
+            .iter()
+            .map(|field| 2 + self::value(&field.value))
+            .sum(),
+        _ => 0,
+    }
+}
+
+fn variables(variables: &Option<Variables>) -> usize {
+    variables
+        .iter()
+        .flatten()
+        .map(|variable| {
+            3 + type_node(&variable.variable_type)
+                + variable.default_value.as_ref().map_or(0, value)
+        })
+        .sum()
+}
+
+fn type_node(type_node: &TypeNode) -> usize {
+    1 + match type_node {
+        TypeNode::Named(_) => 1,
+        TypeNode::List(list) => self::type_node(&list.list_type),
+        TypeNode::NonNull(inner) => self::type_node(inner),
+    }
+}
+
+fn named_types(types: &[NamedTypeNode]) -> usize {
+    2 * types.len()
+}
+
+fn input_values(values: &[InputValueDefinitionNode]) -> usize {
+    values
+        .iter()
+        .map(|input| {
+            2 + description(&input.description)
+                + type_node(&input.input_type)
+                + input.default_value.as_ref().map_or(0, value)
+                + directives(&input.directives)
+        })
+        .sum()
+}
+
+fn field_definitions(fields: &[FieldDefinitionNode]) -> usize {
+    fields
+        .iter()
+        .map(|field| {
+            2 + description(&field.description)
+                + field.arguments.as_deref().map_or(0, input_values)
+                + type_node(&field.field_type)
+                + directives(&field.directives)
+        })
+        .sum()
+}
+
+fn selections(selections: &[Selection]) -> usize {
+    selections
+        .iter()
+        .map(|selection| match selection {
+            Selection::Field(field) => {
+                2 + field.alias.iter().count()
+                    + arguments(&field.arguments)
+                    + directives(&field.directives)
+                    + field.selections.as_deref().map_or(0, self::selections)
+            }
+            Selection::Fragment(FragmentSpread::Node(spread)) => 2 + directives(&spread.directives),
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                1 + inline.node_type.as_ref().map_or(0, |_| 2)
+                    + directives(&inline.directives)
+                    + self::selections(&inline.selections)
+            }
+        })
+        .sum()
+}
+
+fn type_definition(definition: &TypeDefinitionNode) -> usize {
+    match definition {
+        TypeDefinitionNode::Scalar(scalar) => {
+            description(&scalar.description) + directives(&scalar.directives)
+        }
+        TypeDefinitionNode::Object(object) => {
+            description(&object.description)
+                + object.interfaces.as_deref().map_or(0, named_types)
+                + directives(&object.directives)
+                + field_definitions(&object.fields)
+        }
+        TypeDefinitionNode::Interface(interface) => {
+            description(&interface.description)
+                + directives(&interface.directives)
+                + field_definitions(&interface.fields)
+        }
+        TypeDefinitionNode::Union(union) => {
+            description(&union.description)
+                + directives(&union.directives)
+                + named_types(&union.types)
+        }
+        TypeDefinitionNode::Enum(enum_type) => {
+            description(&enum_type.description)
+                + directives(&enum_type.directives)
+                + enum_type
+                    .values
+                    .iter()
+                    .map(|value| {
+                        2 + description(&value.description) + directives(&value.directives)
+                    })
+                    .sum::<usize>()
+        }
+        TypeDefinitionNode::Input(input) => {
+            description(&input.description)
+                + directives(&input.directives)
+                + input_values(&input.fields)
+        }
+    }
+}
+
+fn object_extension(extension: &ObjectTypeExtensionNode) -> usize {
+    description(&extension.description)
+        + extension.interfaces.as_deref().map_or(0, named_types)
+        + directives(&extension.directives)
+        + extension.fields.as_deref().map_or(0, field_definitions)
+}
+
+/// How deeply the selection sets of a definition nest.
+fn depth(definition: &DefinitionNode) -> usize {
+    match definition {
+        DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+            selection_depth(&operation.definition().selections)
+        }
+        DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+            selection_depth(&fragment.selections)
+        }
+        _ => 0,
+    }
+}
+
+fn selection_depth(selections: &[Selection]) -> usize {
+    1 + selections
+        .iter()
+        .map(|selection| match selection {
+            Selection::Field(field) => field.selections.as_deref().map_or(0, selection_depth),
+            // Inline fragments select fields of the same object, at the same depth
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                selection_depth(&inline.selections) - 1
+            }
+            Selection::Fragment(FragmentSpread::Node(_)) => 0,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Counts the definition, its name and everything it contains.
+fn definition(definition: &DefinitionNode) -> usize {
+    match definition {
+        DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+            let query = operation.definition();
+            1 + query.name.iter().count()
+                + variables(&query.variables)
+                + selections(&query.selections)
+        }
+        DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+            4 + variables(&fragment.variables)
+                + directives(&fragment.directives)
+                + selections(&fragment.selections)
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(schema)) => {
+            1 + description(&schema.description)
+                + directives(&schema.directives)
+                + 3 * schema.operations.len()
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+            3 + self::type_definition(type_definition)
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+            2 + description(&directive.description)
+                + directive.arguments.as_deref().map_or(0, input_values)
+        }
+        DefinitionNode::Extension(TypeSystemExtensionNode::Schema(extension)) => {
+            1 + directives(&extension.directives)
+                + 3 * extension.operations.as_ref().map_or(0, Vec::len)
+        }
+        DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+            3 + object_extension(extension)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ParseOptions;
+    use crate::schema::Schema;
+    use crate::validator::Validator;
+
+    #[test]
+    fn it_counts_tokens_and_nodes() {
+        let stats = parse_stats("type Query { users(first: Int = 10): [User!]! }").unwrap();
+        assert_eq!(stats.tokens, 18);
+        assert_eq!(stats.definitions, 1);
+        // Definition, object, name; field, name; argument, name, Int, name, 10; NonNull, List,
+        // NonNull, User, name
+        assert_eq!(stats.nodes, 15);
+    }
+
+    #[test]
+    fn it_counts_selections() {
+        let stats = parse_stats("query Q { a: b(c: [1, 2]) { ... on T { d } ...F } }").unwrap();
+        // Operation, name; field, name, alias; argument, name, list, 1, 2; inline fragment, T,
+        // name; field, name; spread, name
+        assert_eq!(stats.nodes, 17);
+    }
+
+    #[test]
+    fn it_measures_parses_when_asked() {
+        let options = ParseOptions {
+            metrics: true,
+            ..ParseOptions::default()
+        };
+        let (_, metrics) =
+            crate::parse_with_metrics("{ a { ... on T { b { c } } ...F } d }", &options).unwrap();
+        let metrics = metrics.unwrap();
+        assert_eq!(metrics.tokens, 17);
+        assert_eq!(metrics.max_depth, 3);
+        assert_eq!(
+            metrics.nodes,
+            parse_stats("{ a { ... on T { b { c } } ...F } d }")
+                .unwrap()
+                .nodes
+        );
+        let (_, metrics) =
+            crate::parse_with_metrics("type Query { a: Int }", &ParseOptions::default()).unwrap();
+        assert_eq!(metrics, None);
+    }
+
+    #[test]
+    fn it_measures_validations() {
+        let schema_document = crate::parse("type Query { a: Int }").unwrap();
+        let schema = Schema::new(&schema_document);
+        let source = "query A { a } query A { a }";
+        let document = crate::parse(source).unwrap();
+        let (errors, metrics) = Validator::new(&schema).validate_with_metrics(source, &document);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(metrics.definitions, 2);
+        assert_eq!(metrics.errors, 1);
+    }
+
+    #[test]
+    fn it_reports_errors() {
+        assert!(parse_stats("type Query {").is_err());
+    }
+}
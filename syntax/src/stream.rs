@@ -0,0 +1,368 @@
+//! Parses the definitions of a document one at a time.
+//!
+//! [`parse`] builds the whole [`Document`] before returning it. For large SDL dumps,
+//! [`DefinitionStream`] instead yields every top-level definition as soon as it is parsed, so
+//! they can be processed and dropped without holding the whole document in memory.
+//!
+//! [`DefinitionStream::new`] parses a string. [`DefinitionStream::from_reader`] reads the input
+//! line by line and only keeps the text of the definition being parsed. It splits the input
+//! before lines that start a new definition at the top level (a definition keyword or a
+//! description), so definitions written on a single line are parsed together.
+//!
+//! Names are interned once for the whole stream, as they are by [`parse`].
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::stream::DefinitionStream;
+//!
+//! let input = "type Query { me: User }\ntype User { id: ID! }\n";
+//! let mut stream = DefinitionStream::from_reader(input.as_bytes());
+//! assert!(stream.next().unwrap().is_ok());
+//! assert!(stream.next().unwrap().is_ok());
+//! assert!(stream.next().is_none());
+//! ```
+//!
+//! [`parse`]: ../fn.parse.html
+//! [`Document`]: ../document/struct.Document.html
+//! [`DefinitionStream`]: struct.DefinitionStream.html
+//! [`DefinitionStream::new`]: struct.DefinitionStream.html#method.new
+//! [`DefinitionStream::from_reader`]: struct.DefinitionStream.html#method.from_reader
+
+use crate::ast::Ast;
+use crate::error::{ParseError, ParseResult};
+use crate::intern::{self, Interner};
+use crate::lexer::Lexer;
+use crate::nodes::DefinitionNode;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+
+const DEFINITION_KEYWORDS: [&str; 13] = [
+    "type",
+    "enum",
+    "union",
+    "interface",
+    "input",
+    "scalar",
+    "schema",
+    "directive",
+    "extend",
+    "query",
+    "mutation",
+    "subscription",
+    "fragment",
+];
+
+/// An iterator over the top-level definitions of a document.
+///
+/// The stream ends after the first error.
+pub struct DefinitionStream<'i> {
+    source: Source<'i>,
+    interner: Interner,
+    done: bool,
+}
+
+enum Source<'i> {
+    Str { ast: Ast<'i>, started: bool },
+    Reader(Chunks<'i>),
+}
+
+impl<'i> DefinitionStream<'i> {
+    /// Streams the definitions of a string.
+    pub fn new(input: &'i str) -> DefinitionStream<'i> {
+        DefinitionStream {
+            source: Source::Str {
+                ast: Ast::from_lexer(Lexer::new(input)),
+                started: false,
+            },
+            interner: Interner::new(),
+            done: false,
+        }
+    }
+
+    /// Streams the definitions read from `reader`.
+    ///
+    /// Line numbers in errors refer to the whole input; absolute positions are relative to the
+    /// definition being parsed.
+    pub fn from_reader<R: Read + 'i>(reader: R) -> DefinitionStream<'i> {
+        DefinitionStream {
+            source: Source::Reader(Chunks {
+                reader: Box::new(BufReader::new(reader)),
+                line: 0,
+                scanner: Scanner::default(),
+                pending: None,
+                parsed: VecDeque::new(),
+                eof: false,
+            }),
+            interner: Interner::new(),
+            done: false,
+        }
+    }
+
+    fn parse_next(&mut self) -> ParseResult<Option<DefinitionNode>> {
+        match &mut self.source {
+            Source::Str { ast, started } => {
+                if !*started {
+                    *started = true;
+                    ast.parse_start()?;
+                }
+                ast.parse_next_definition()
+            }
+            Source::Reader(chunks) => chunks.next_definition(),
+        }
+    }
+}
+
+impl Iterator for DefinitionStream<'_> {
+    type Item = ParseResult<DefinitionNode>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut interner = std::mem::take(&mut self.interner);
+        let next = intern::with_interner_in(&mut interner, || self.parse_next());
+        self.interner = interner;
+        match next {
+            Ok(Some(definition)) => Some(Ok(definition)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(error) => {
+                self.done = true;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// The text of consecutive definitions, and the line it starts on.
+struct Chunk {
+    text: String,
+    first_line: usize,
+    started: bool,
+}
+
+/// Splits the text read from a reader into chunks of whole definitions.
+struct Chunks<'i> {
+    reader: Box<dyn BufRead + 'i>,
+    line: usize,
+    scanner: Scanner,
+    pending: Option<Chunk>,
+    parsed: VecDeque<DefinitionNode>,
+    eof: bool,
+}
+
+enum LineKind {
+    Keyword,
+    Description,
+    Content,
+    /// Whitespace, comments and the inside of block strings
+    Blank,
+}
+
+impl Chunks<'_> {
+    fn next_definition(&mut self) -> ParseResult<Option<DefinitionNode>> {
+        loop {
+            if let Some(definition) = self.parsed.pop_front() {
+                return Ok(Some(definition));
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            match self.read_chunk()? {
+                Some(chunk) => self.parse_chunk(chunk)?,
+                None => self.eof = true,
+            }
+        }
+    }
+
+    /// Reads lines until the next line that starts a definition, and returns the text before it.
+    fn read_chunk(&mut self) -> ParseResult<Option<Chunk>> {
+        loop {
+            let mut line = String::new();
+            let read = self
+                .reader
+                .read_line(&mut line)
+                .map_err(|error| ParseError::Io(error.to_string()))?;
+            if read == 0 {
+                return Ok(self.pending.take());
+            }
+            self.line += 1;
+            let kind = self.scanner.classify(&line);
+            self.scanner.scan(&line);
+            let boundary = matches!(kind, LineKind::Keyword | LineKind::Description)
+                && self
+                    .pending
+                    .as_ref()
+                    .is_some_and(|chunk| chunk.started && !ends_with_extend(&chunk.text));
+            let finished = if boundary { self.pending.take() } else { None };
+            let first_line = self.line;
+            let chunk = self.pending.get_or_insert_with(|| Chunk {
+                text: String::new(),
+                first_line,
+                started: false,
+            });
+            chunk.text.push_str(&line);
+            chunk.started |= matches!(kind, LineKind::Keyword | LineKind::Content);
+            if finished.is_some() {
+                return Ok(finished);
+            }
+        }
+    }
+
+    fn parse_chunk(&mut self, chunk: Chunk) -> ParseResult<()> {
+        let mut ast = Ast::from_lexer(Lexer::new(&chunk.text).starting_at_line(chunk.first_line));
+        ast.parse_start()?;
+        while let Some(definition) = ast.parse_next_definition()? {
+            self.parsed.push_back(definition);
+        }
+        Ok(())
+    }
+}
+
+fn ends_with_extend(text: &str) -> bool {
+    let text = text.trim_end();
+    text.strip_suffix("extend").is_some_and(|before| {
+        !before
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_')
+    })
+}
+
+/// Tracks whether the start of a line is at the top level of the document.
+#[derive(Default)]
+struct Scanner {
+    depth: usize,
+    in_block_string: bool,
+}
+
+impl Scanner {
+    fn classify(&self, line: &str) -> LineKind {
+        if self.in_block_string {
+            return LineKind::Blank;
+        }
+        let line = line.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if line.is_empty() || line.starts_with('#') {
+            return LineKind::Blank;
+        }
+        if self.depth > 0 {
+            return LineKind::Content;
+        }
+        if line.starts_with('"') {
+            return LineKind::Description;
+        }
+        let word_end = line
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(line.len());
+        if DEFINITION_KEYWORDS.contains(&&line[..word_end]) {
+            LineKind::Keyword
+        } else {
+            LineKind::Content
+        }
+    }
+
+    fn scan(&mut self, line: &str) {
+        let mut chars = line.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            if self.in_block_string {
+                if c == '\\' && line[index..].starts_with("\\\"\"\"") {
+                    chars.nth(2);
+                } else if line[index..].starts_with("\"\"\"") {
+                    chars.nth(1);
+                    self.in_block_string = false;
+                }
+                continue;
+            }
+            match c {
+                '#' => return,
+                '"' if line[index..].starts_with("\"\"\"") => {
+                    chars.nth(1);
+                    self.in_block_string = true;
+                }
+                '"' => {
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '{' | '(' | '[' => self.depth += 1,
+                '}' | ')' | ']' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const SDL: &str = r#""""
+A user of the service
+"""
+type User {
+  id: ID!
+  "The name, as entered"
+  name(
+    format: String = "{ full }"
+  ): String
+}
+
+extend
+type User @key(fields: "id")
+
+# Comments are kept with the next definition
+union Result = User | Error
+
+"A failure"
+type Error { message: String! } enum Color { RED GREEN }
+"#;
+
+    #[test]
+    fn it_streams_a_string() {
+        let definitions: Vec<DefinitionNode> = DefinitionStream::new(SDL)
+            .collect::<ParseResult<_>>()
+            .unwrap();
+        assert_eq!(definitions.len(), 5);
+        assert_eq!(definitions, parse(SDL).unwrap().definitions);
+    }
+
+    #[test]
+    fn it_streams_a_reader() {
+        let definitions: Vec<DefinitionNode> = DefinitionStream::from_reader(SDL.as_bytes())
+            .collect::<ParseResult<_>>()
+            .unwrap();
+        assert_eq!(definitions, parse(SDL).unwrap().definitions);
+    }
+
+    #[test]
+    fn it_stops_after_an_error() {
+        let input = "type A { a: Int }\ntype B {\n}\ntype C { c: Int }\n";
+        let results: Vec<ParseResult<DefinitionNode>> =
+            DefinitionStream::from_reader(input.as_bytes()).collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(ParseError::ObjectEmpty(location)) => assert_eq!(location.line, 2),
+            other => panic!("Expected an empty object error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn it_streams_nothing_from_an_empty_input() {
+        assert_eq!(DefinitionStream::new("").count(), 0);
+        assert_eq!(
+            DefinitionStream::from_reader("\n# only\n".as_bytes()).count(),
+            0
+        );
+    }
+}
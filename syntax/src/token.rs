@@ -1,16 +1,26 @@
 //! Tokens represent parts of the string as they are parsed.
 //!
-//! Some simply point to the position in the input string where they reside. Others will contain a
-//! value based on what was parsed. The [`Start`] and [`End`] tokens represent the beginning and
-//! end of the string respectively and do not correlate to an character in the input string.
-//!
-//! [`Start`]: enum.Token.html#variant.Start
-//! [`End`]: enum.Token.html#variant.End
+//! Each has a [`TokenKind`], the position in the input string where it resides, and the text it
+//! was made of. Matching and comparing kinds is cheap, so code that only cares about what a token
+//! is uses its kind. The [`Start`] and [`End`] tokens represent the beginning and end of the
+//! string respectively and do not correlate to an character in the input string.
 //!
+//! A [`Token`] borrows its text from the input string. An [`OwnedToken`] holds its own, to be kept
+//! past the input, serialized with the `serde` feature, or compared with its location, as golden
+//! tests of the lexer do. [`tokens_to_json`] gives the JSON of a token stream.
 //!
+//! [`TokenKind`]: enum.TokenKind.html
+//! [`Start`]: enum.TokenKind.html#variant.Start
+//! [`End`]: enum.TokenKind.html#variant.End
+//! [`Token`]: struct.Token.html
+//! [`OwnedToken`]: struct.OwnedToken.html
+//! [`tokens_to_json`]: fn.tokens_to_json.html
+
+use std::fmt;
 
 /// Contains the information on the location of a lexer error relative to the input string.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Location {
     /// The absolute position in the string. Disregards lines and columns.
     pub absolute_position: usize,
@@ -42,221 +52,335 @@ impl Location {
     }
 }
 
-/// Enumeration of the possible tokens that can be found in a GraphQL String.
-#[derive(Debug, Clone)]
-pub enum Token<'a> {
-    // (position, line, col, value)
-    /// Represents the start of the token stream
+/// The kinds of tokens that can be found in a GraphQL string, without the text they were made of.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind {
+    /// The start of the token stream
     Start,
-    /// Represents the end of the token stream
+    /// The end of the token stream
     End,
-    /// Represents the `!` character and it's position
-    Bang(Location),
-    /// Represents the `$` character and it's position
-    Dollar(Location),
-    /// Represents the `&` character and it's position
-    Amp(Location),
-    /// Represents the `...` series of characters and it's position
-    Spread(Location),
-    /// Represents the `:` character and it's position
-    Colon(Location),
-    /// Represents the `=` character and it's position
-    Equals(Location),
-    /// Represents the `@` character and it's position
-    At(Location),
-    /// Represents the `(` character and it's position
-    OpenParen(Location),
-    /// Represents the `)` character and it's position
-    CloseParen(Location),
-    /// Represents the `[` character and it's position
-    OpenSquare(Location),
-    /// Represents the `]` character and it's position
-    CloseSquare(Location),
-    /// Represents the `{` character and it's position
-    OpenBrace(Location),
-    /// Represents the `}` character and it's position
-    CloseBrace(Location),
-    /// Represents the `|` character and it's position
-    Pipe(Location),
-    /// Represents a series of alphanumeric and/or `_` characters. These characters are NOT
-    /// surrouned in quotes.
-    Name(Location, &'a str),
-    /// Represents an parsed integer and it's location in the string
-    Int(Location, i64),
-    /// Represents an parsed float and it's location in the string
-    Float(Location, f64),
-    /// Represents a quoted series of characters. These characters can be any valid unicode
-    /// character. It will capture all characters within a pair of double quotes
-    Str(Location, &'a str),
-    /// Represents a triple quoted series of characters. These characters can be any valid unicode
-    /// character. It will capture all characters within a pair of triple double quotes (i.e. """A BlockStr is in here""")
-    BlockStr(Location, &'a str),
-    /// Represents a GraphQL Comment string.
-    Comment(Location, &'a str),
+    /// The `!` character
+    Bang,
+    /// The `$` character
+    Dollar,
+    /// The `&` character
+    Amp,
+    /// The `...` series of characters
+    Spread,
+    /// The `:` character
+    Colon,
+    /// The `=` character
+    Equals,
+    /// The `@` character
+    At,
+    /// The `(` character
+    OpenParen,
+    /// The `)` character
+    CloseParen,
+    /// The `[` character
+    OpenSquare,
+    /// The `]` character
+    CloseSquare,
+    /// The `{` character
+    OpenBrace,
+    /// The `}` character
+    CloseBrace,
+    /// The `|` character
+    Pipe,
+    /// A series of alphanumeric and/or `_` characters. These characters are NOT surrouned in
+    /// quotes.
+    Name,
+    /// An integer
+    Int,
+    /// A float
+    Float,
+    /// A quoted series of characters. These characters can be any valid unicode character.
+    Str,
+    /// A triple quoted series of characters (i.e. """A BlockStr is in here""")
+    BlockStr,
+    /// A GraphQL comment
+    Comment,
 }
 
-use std::mem;
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Token<{:?}>", self)
+    }
+}
+
+/// A token found in a GraphQL string: its kind, where it is, and the text it was made of.
+///
+/// The text of a [`Str`] or a [`BlockStr`] is what is between its quotes. The text of [`Start`]
+/// and [`End`] is empty.
+///
+/// [`Start`]: enum.TokenKind.html#variant.Start
+/// [`End`]: enum.TokenKind.html#variant.End
+/// [`Str`]: enum.TokenKind.html#variant.Str
+/// [`BlockStr`]: enum.TokenKind.html#variant.BlockStr
+#[derive(Debug, Copy, Clone)]
+pub struct Token<'a> {
+    /// The kind of the token
+    pub kind: TokenKind,
+    /// Where the token starts in the input string
+    pub span: Location,
+    /// The text of the token in the input string
+    pub text: &'a str,
+}
 
 impl<'a> Token<'a> {
-    /// Helper function to determine if to tokens are of the same
-    /// Enum variant.
+    /// The start of the token stream
+    pub const START: Token<'a> = Token {
+        kind: TokenKind::Start,
+        span: IGNORED_LOCATION,
+        text: "",
+    };
+
+    /// The end of the token stream
+    pub const END: Token<'a> = Token {
+        kind: TokenKind::End,
+        span: IGNORED_LOCATION,
+        text: "",
+    };
+
+    /// Creates a token of a kind, found at `span`, made of `text`.
+    pub fn new(kind: TokenKind, span: Location, text: &'a str) -> Token<'a> {
+        Token { kind, span, text }
+    }
+
+    /// Helper function to determine if to tokens are of the same kind.
     ///
     /// ```
     /// use syntax::token::Token;
     ///
-    /// assert!(Token::Start.is_same_type(&Token::Start));
-    /// assert!(!Token::Start.is_same_type(&Token::End));
+    /// assert!(Token::START.is_same_type(&Token::START));
+    /// assert!(!Token::START.is_same_type(&Token::END));
     /// ```
     pub fn is_same_type(&self, other: &Token) -> bool {
-        return mem::discriminant(self) == mem::discriminant(other);
+        self.kind == other.kind
     }
 
-    /// Extracts the token's location from the enum variant.
+    /// Extracts the token's location.
     ///
     /// ```
-    /// use syntax::token::{Token, Location};
+    /// use syntax::token::{Location, Token, TokenKind};
     ///
     /// let location = Location::new(42, 4, 2);
-    /// let pipe = Token::Pipe(Location::new(42, 4, 2));
+    /// let pipe = Token::new(TokenKind::Pipe, Location::new(42, 4, 2), "|");
     /// assert_eq!(pipe.location(), location);
-    /// assert_eq!(Token::Start.location(), Location {
+    /// assert_eq!(Token::START.location(), Location {
     ///   absolute_position: 0,
     ///   line: 0,
     ///   column: 0,
     /// });
     /// ```
     pub fn location(&self) -> Location {
-        match self {
-            Token::Start | Token::End => Location::ignored(),
-            Token::Bang(location)
-            | Token::Dollar(location)
-            | Token::Amp(location)
-            | Token::Spread(location)
-            | Token::Colon(location)
-            | Token::Equals(location)
-            | Token::At(location)
-            | Token::Pipe(location)
-            | Token::OpenParen(location)
-            | Token::CloseParen(location)
-            | Token::OpenSquare(location)
-            | Token::CloseSquare(location)
-            | Token::OpenBrace(location)
-            | Token::CloseBrace(location)
-            | Token::Name(location, _)
-            | Token::Int(location, _)
-            | Token::Float(location, _)
-            | Token::Str(location, _)
-            | Token::BlockStr(location, _)
-            | Token::Comment(location, _) => *location,
-        }
+        self.span
     }
 }
 
-use std::fmt;
-
 impl<'a> fmt::Display for Token<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "Token<{:?}>", self)
+        match self.kind {
+            TokenKind::Start | TokenKind::End => write!(f, "{}", self.kind),
+            _ => write!(f, "Token<{:?} {:?}>", self.kind, self.text),
+        }
     }
 }
 
 use std::cmp::{Eq, PartialEq};
 
+/// Tokens are equal when they are of the same kind and made of the same text, wherever they are.
 impl<'a> PartialEq for Token<'a> {
     fn eq(&self, other: &Token) -> bool {
-        match self {
-            Token::Name(_, value) => matches!(other, Token::Name(_, value2) if *value2 == *value),
-            Token::Str(_, value) => matches!(other, Token::Str(_, value2) if *value2 == *value),
-            Token::BlockStr(_, value) => {
-                matches!(other, Token::BlockStr(_, value2) if *value2 == *value)
-            }
-            Token::Int(_, value) => matches!(other, Token::Int(_, value2) if value2 == value),
-            Token::Float(_, value) => matches!(other, Token::Float(_, value2) if value2 == value),
-            _ => mem::discriminant(self) == mem::discriminant(other),
-        }
+        self.kind == other.kind && self.text == other.text
     }
 }
 
 impl<'a> Eq for Token<'a> {}
 
+/// A token which holds its text, rather than borrowing it from the input string.
+///
+/// Unlike tokens, owned tokens are only equal when they are also at the same location.
+///
+/// ```
+/// use syntax::token::{OwnedToken, Token};
+///
+/// let tokens: Vec<OwnedToken> = syntax::lexer::tokenize("{ hero }")
+///     .unwrap()
+///     .into_iter()
+///     .map(OwnedToken::from)
+///     .collect();
+/// assert_eq!(tokens[2].text, "hero");
+/// assert_eq!(tokens[2].span.column, 3);
+/// assert_eq!(tokens[0].as_token(), Token::START);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OwnedToken {
+    /// The kind of the token
+    pub kind: TokenKind,
+    /// Where the token starts in the input string
+    pub span: Location,
+    /// The text of the token in the input string
+    pub text: String,
+}
+
+impl OwnedToken {
+    /// The token borrowing its text from this one.
+    pub fn as_token(&self) -> Token<'_> {
+        Token::new(self.kind, self.span, &self.text)
+    }
+}
+
+impl From<Token<'_>> for OwnedToken {
+    fn from(token: Token<'_>) -> Self {
+        OwnedToken {
+            kind: token.kind,
+            span: token.span,
+            text: token.text.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for OwnedToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_token().fmt(f)
+    }
+}
+
+/// The JSON of a token stream: an array of the tokens as objects with their `kind`, `span` and
+/// `text`, as the `serde` feature serializes an [`OwnedToken`].
+///
+/// ```
+/// use serde_json::json;
+/// use syntax::token::tokens_to_json;
+///
+/// let tokens = syntax::lexer::tokenize("a").unwrap();
+/// assert_eq!(
+///     tokens_to_json(&tokens)[1],
+///     json!({
+///         "kind": "Name",
+///         "span": {"absolute_position": 0, "line": 1, "column": 1},
+///         "text": "a",
+///     })
+/// );
+/// ```
+///
+/// [`OwnedToken`]: struct.OwnedToken.html
+#[cfg(feature = "json")]
+pub fn tokens_to_json(tokens: &[Token<'_>]) -> serde_json::Value {
+    tokens
+        .iter()
+        .map(|token| {
+            serde_json::json!({
+                "kind": format!("{:?}", token.kind),
+                "span": {
+                    "absolute_position": token.span.absolute_position,
+                    "line": token.span.line,
+                    "column": token.span.column,
+                },
+                "text": token.text,
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn compare_type() {
-        assert_eq!(Token::Start, Token::Start);
-        assert_ne!(Token::End, Token::Start);
+        assert_eq!(Token::START, Token::START);
+        assert_ne!(Token::END, Token::START);
 
         assert_eq!(
-            Token::Bang(Location::new(0, 0, 0)),
-            Token::Bang(Location::new(12, 2, 3))
-        );
-        assert_ne!(
-            Token::Amp(Location::new(0, 0, 0)),
-            Token::Float(Location::new(0, 0, 0), 0.0)
-        );
-        assert_ne!(
-            Token::Dollar(Location::new(0, 0, 0)),
-            Token::OpenBrace(Location::new(0, 1, 1))
+            Token::new(TokenKind::Bang, Location::new(0, 0, 0), "!"),
+            Token::new(TokenKind::Bang, Location::new(12, 2, 3), "!")
         );
         assert_ne!(
-            Token::Int(Location::new(0, 0, 0), 0),
-            Token::Float(Location::new(0, 0, 0), 0.0)
+            Token::new(TokenKind::Int, Location::new(0, 0, 0), "0"),
+            Token::new(TokenKind::Float, Location::new(0, 0, 0), "0")
         );
+        assert_ne!(TokenKind::Dollar, TokenKind::OpenBrace);
+        assert_eq!(TokenKind::Float, TokenKind::Float);
     }
 
     #[test]
     fn compare_value() {
         assert_eq!(
-            Token::Int(Location::new(0, 0, 0), 10),
-            Token::Int(Location::new(12, 3, 14), 10)
+            Token::new(TokenKind::Int, Location::new(0, 0, 0), "10"),
+            Token::new(TokenKind::Int, Location::new(12, 3, 14), "10")
         );
         assert_eq!(
-            Token::Float(Location::new(0, 0, 0), 3.14),
-            Token::Float(Location::new(3, 1, 4), 3.14)
+            Token::new(TokenKind::Name, Location::new(0, 0, 0), "id"),
+            Token::new(TokenKind::Name, Location::new(3, 3, 3), "id")
         );
         assert_eq!(
-            Token::Name(Location::new(0, 0, 0), "id"),
-            Token::Name(Location::new(3, 3, 3), "id")
-        );
-        assert_eq!(
-            Token::Str(Location::new(0, 0, 0), "Comment"),
-            Token::Str(Location::new(1, 2, 1), "Comment")
-        );
-        assert_eq!(
-            Token::BlockStr(Location::new(0, 0, 0), "Comment"),
-            Token::BlockStr(Location::new(1, 2, 1), "Comment")
+            Token::new(TokenKind::BlockStr, Location::new(0, 0, 0), "Comment"),
+            Token::new(TokenKind::BlockStr, Location::new(1, 2, 1), "Comment")
         );
 
         assert_ne!(
-            Token::Int(Location::new(0, 0, 0), 10),
-            Token::Int(Location::new(12, 3, 14), 11)
+            Token::new(TokenKind::Float, Location::new(0, 0, 0), "3.14"),
+            Token::new(TokenKind::Float, Location::new(3, 1, 4), "3.14159")
         );
         assert_ne!(
-            Token::Float(Location::new(0, 0, 0), 3.14),
-            Token::Float(Location::new(3, 1, 4), 3.14159)
+            Token::new(TokenKind::Name, Location::new(0, 0, 0), "id"),
+            Token::new(TokenKind::Name, Location::new(3, 3, 3), "val")
         );
         assert_ne!(
-            Token::Name(Location::new(0, 0, 0), "id"),
-            Token::Name(Location::new(3, 3, 3), "val")
-        );
-        assert_ne!(
-            Token::Str(Location::new(0, 0, 0), "Comment"),
-            Token::Str(Location::new(1, 2, 1), "Your comment here")
-        );
-        assert_ne!(
-            Token::BlockStr(Location::new(0, 0, 0), "Comment"),
-            Token::BlockStr(Location::new(1, 2, 1), "Your comment here")
+            Token::new(TokenKind::Str, Location::new(0, 0, 0), "Comment"),
+            Token::new(TokenKind::BlockStr, Location::new(1, 2, 1), "Comment")
         );
     }
 
     #[test]
     fn get_location() {
         let loc = Location::new(42, 3, 4);
-        assert_eq!(Token::Start.location(), Location::ignored());
-        assert_eq!(Token::End.location(), Location::ignored());
-        assert_eq!(Token::Bang(loc).location(), loc);
-        assert_eq!(Token::Str(loc, "Some str value").location(), loc);
+        assert_eq!(Token::START.location(), Location::ignored());
+        assert_eq!(Token::END.location(), Location::ignored());
+        assert_eq!(Token::new(TokenKind::Bang, loc, "!").location(), loc);
+        assert_eq!(
+            Token::new(TokenKind::Str, loc, "Some str value").location(),
+            loc
+        );
+    }
+
+    #[test]
+    fn it_owns_the_text_of_tokens() {
+        let source = String::from("hero");
+        let owned = OwnedToken::from(Token::new(TokenKind::Name, Location::new(3, 1, 4), &source));
+        drop(source);
+        assert_eq!(owned.to_string(), r#"Token<Name "hero">"#);
+        assert_eq!(
+            owned.as_token(),
+            Token::new(TokenKind::Name, Location::ignored(), "hero")
+        );
+        let elsewhere = OwnedToken {
+            span: Location::new(0, 1, 1),
+            ..owned.clone()
+        };
+        assert_ne!(owned, elsewhere);
+    }
+
+    #[cfg(all(feature = "json", feature = "serde"))]
+    #[test]
+    fn it_serializes_tokens_as_tokens_to_json_does() {
+        let tokens = crate::lexer::tokenize(r#"{ a(b: "c") }"#).unwrap();
+        let owned: Vec<OwnedToken> = tokens.iter().copied().map(OwnedToken::from).collect();
+        let json = serde_json::to_value(&owned).unwrap();
+        assert_eq!(json, tokens_to_json(&tokens));
+        let read: Vec<OwnedToken> = serde_json::from_value(json).unwrap();
+        assert_eq!(read, owned);
+    }
+
+    #[test]
+    fn it_displays_the_kind_and_text() {
+        let name = Token::new(TokenKind::Name, Location::new(0, 1, 1), "hero");
+        assert_eq!(name.to_string(), r#"Token<Name "hero">"#);
+        assert_eq!(Token::END.to_string(), "Token<End>");
+        assert_eq!(TokenKind::Colon.to_string(), "Token<Colon>");
     }
 }
@@ -0,0 +1,804 @@
+//! Renames the types and root fields of a [`Document`] and merges several schemas into one.
+//!
+//! A [`Transform`] rewrites every definition of a type and every reference to it, so that the
+//! same name can be used by two schemas without clashing. [`stitch`] then combines the root types
+//! of several documents into a single `Query`, `Mutation` and `Subscription`, which is enough for a
+//! naive gateway that forwards each root field to the schema it came from. [`strip`] publishes a
+//! schema without the parts marked with a directive such as `@internal`, and [`prune`] removes
+//! the types no operation can reach.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::printer::print;
+//! use syntax::transform::{stitch, Transform};
+//!
+//! let mut billing = syntax::parse("type Query { user: User } type User { id: ID! }").unwrap();
+//! Transform::new()
+//!     .with_type_prefix("Billing_")
+//!     .with_root_field_prefix("billing_")
+//!     .apply(&mut billing);
+//! assert_eq!(
+//!     print(&billing),
+//!     "type Query {\n  billing_user: Billing_User\n}\n\ntype Billing_User {\n  id: ID!\n}\n"
+//! );
+//!
+//! let accounts = syntax::parse("type Query { me: ID }").unwrap();
+//! let stitched = stitch(vec![billing, accounts]);
+//! assert!(print(&stitched).starts_with("type Query {\n  billing_user: Billing_User\n  me: ID\n}"));
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+//! [`Transform`]: struct.Transform.html
+//! [`stitch`]: fn.stitch.html
+//! [`strip`]: fn.strip.html
+//! [`prune`]: fn.prune.html
+
+use crate::document::Document;
+use crate::intern::Symbol;
+use crate::nodes::object_type_extension::ObjectTypeExtensionNode;
+use crate::nodes::*;
+use crate::schema::{named_type, Schema, BUILTIN_SCALARS};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Rewrites the names of types and root fields in a document.
+///
+/// Root operation types and built-in scalars keep their names, so the result is still a valid
+/// schema that can be merged with others.
+#[derive(Debug, Default)]
+pub struct Transform {
+    type_prefix: Option<String>,
+    root_field_prefix: Option<String>,
+    types: HashMap<String, String>,
+    root_fields: HashMap<String, String>,
+}
+
+impl Transform {
+    /// Creates a transform that does not rename anything.
+    pub fn new() -> Transform {
+        Transform::default()
+    }
+
+    /// Prefixes the name of every type defined or extended in the document, e.g. `User` becomes
+    /// `Billing_User` with the prefix `Billing_`.
+    pub fn with_type_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.type_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Prefixes the name of every field of the root operation types.
+    pub fn with_root_field_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.root_field_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Renames a single type. Takes precedence over the type prefix.
+    pub fn rename_type(&mut self, from: &str, to: &str) -> &mut Self {
+        self.types.insert(from.into(), to.into());
+        self
+    }
+
+    /// Renames a single field of the root operation types. Takes precedence over the root field
+    /// prefix.
+    pub fn rename_root_field(&mut self, from: &str, to: &str) -> &mut Self {
+        self.root_fields.insert(from.into(), to.into());
+        self
+    }
+
+    /// Applies the transform to the document.
+    pub fn apply(&self, document: &mut Document) {
+        let roots: Vec<Symbol> = {
+            let schema = Schema::with_builtins(document, false);
+            vec![
+                schema.query_type(),
+                schema.mutation_type(),
+                schema.subscription_type(),
+            ]
+            .into_iter()
+            .flatten()
+            .map(Symbol::from)
+            .collect()
+        };
+
+        let mut types = HashMap::new();
+        if let Some(prefix) = &self.type_prefix {
+            for definition in &document.definitions {
+                let name = match definition {
+                    DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                        type_definition.name()
+                    }
+                    DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                        &extension.name.value
+                    }
+                    _ => continue,
+                };
+                if !roots.iter().any(|root| root == name) && !BUILTIN_SCALARS.contains(&name) {
+                    types.insert(name.to_string(), format!("{}{}", prefix, name));
+                }
+            }
+        }
+        types.extend(
+            self.types
+                .iter()
+                .map(|(from, to)| (from.clone(), to.clone())),
+        );
+
+        let renamer = Renamer {
+            types,
+            roots,
+            root_field_prefix: self.root_field_prefix.as_deref(),
+            root_fields: &self.root_fields,
+        };
+        for definition in &mut document.definitions {
+            renamer.definition(definition);
+        }
+    }
+}
+
+struct Renamer<'t> {
+    types: HashMap<String, String>,
+    roots: Vec<Symbol>,
+    root_field_prefix: Option<&'t str>,
+    root_fields: &'t HashMap<String, String>,
+}
+
+impl<'t> Renamer<'t> {
+    fn name(&self, name: &mut NameNode) {
+        if let Some(renamed) = self.types.get(name.as_str()) {
+            name.value = renamed.as_str().into();
+        }
+    }
+
+    fn named_types(&self, named_types: &mut [NamedTypeNode]) {
+        for named_type in named_types {
+            self.name(&mut named_type.name);
+        }
+    }
+
+    fn type_node(&self, type_node: &TypeNode) -> TypeNode {
+        match type_node {
+            TypeNode::Named(named) => {
+                let mut name = NameNode::from(named.name.value.as_str());
+                self.name(&mut name);
+                TypeNode::Named(NamedTypeNode { name })
+            }
+            TypeNode::List(list) => {
+                TypeNode::List(ListTypeNode::new(self.type_node(&list.list_type)))
+            }
+            TypeNode::NonNull(inner) => TypeNode::NonNull(Arc::new(self.type_node(inner))),
+        }
+    }
+
+    fn input_values(&self, input_values: &mut [InputValueDefinitionNode]) {
+        for input_value in input_values {
+            input_value.input_type = self.type_node(&input_value.input_type);
+        }
+    }
+
+    fn fields(&self, fields: &mut [FieldDefinitionNode], is_root: bool) {
+        for field in fields {
+            field.field_type = self.type_node(&field.field_type);
+            if let Some(arguments) = &mut field.arguments {
+                self.input_values(arguments);
+            }
+            if is_root {
+                if let Some(renamed) = self.root_fields.get(field.name.as_str()) {
+                    field.name.value = renamed.as_str().into();
+                } else if let Some(prefix) = self.root_field_prefix {
+                    field.name.value = format!("{}{}", prefix, field.name.value).into();
+                }
+            }
+        }
+    }
+
+    fn is_root(&self, name: &NameNode) -> bool {
+        self.roots.contains(&name.value)
+    }
+
+    fn definition(&self, definition: &mut DefinitionNode) {
+        match definition {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(schema)) => {
+                for operation in &mut schema.operations {
+                    self.name(&mut operation.node_type.name);
+                }
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                self.type_definition(type_definition)
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+                if let Some(arguments) = &mut directive.arguments {
+                    self.input_values(arguments);
+                }
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Schema(extension)) => {
+                for operation in extension.operations.iter_mut().flatten() {
+                    self.name(&mut operation.node_type.name);
+                }
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                self.object_extension(extension)
+            }
+            DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                let query = operation.definition_mut();
+                for variable in query.variables.iter_mut().flatten() {
+                    variable.variable_type = self.type_node(&variable.variable_type);
+                }
+                self.selections(&mut query.selections);
+            }
+            DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                self.name(&mut fragment.node_type.name);
+                self.selections(&mut fragment.selections);
+            }
+        }
+    }
+
+    fn type_definition(&self, type_definition: &mut TypeDefinitionNode) {
+        match type_definition {
+            TypeDefinitionNode::Scalar(scalar) => self.name(&mut scalar.name),
+            TypeDefinitionNode::Object(object) => {
+                let is_root = self.is_root(&object.name);
+                self.name(&mut object.name);
+                if let Some(interfaces) = &mut object.interfaces {
+                    self.named_types(interfaces);
+                }
+                self.fields(&mut object.fields, is_root);
+            }
+            TypeDefinitionNode::Interface(interface) => {
+                self.name(&mut interface.name);
+                self.fields(&mut interface.fields, false);
+            }
+            TypeDefinitionNode::Union(union) => {
+                self.name(&mut union.name);
+                self.named_types(&mut union.types);
+            }
+            TypeDefinitionNode::Enum(enum_type) => self.name(&mut enum_type.name),
+            TypeDefinitionNode::Input(input) => {
+                self.name(&mut input.name);
+                self.input_values(&mut input.fields);
+            }
+        }
+    }
+
+    fn object_extension(&self, extension: &mut ObjectTypeExtensionNode) {
+        let is_root = self.is_root(&extension.name);
+        self.name(&mut extension.name);
+        if let Some(interfaces) = &mut extension.interfaces {
+            self.named_types(interfaces);
+        }
+        if let Some(fields) = &mut extension.fields {
+            self.fields(fields, is_root);
+        }
+    }
+
+    fn selections(&self, selections: &mut [Selection]) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    if let Some(selections) = &mut field.selections {
+                        self.selections(selections);
+                    }
+                }
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    if let Some(node_type) = &mut inline.node_type {
+                        self.name(&mut node_type.name);
+                    }
+                    self.selections(&mut inline.selections);
+                }
+                Selection::Fragment(FragmentSpread::Node(_)) => (),
+            }
+        }
+    }
+}
+
+/// Merges the type system definitions of several documents into one.
+///
+/// The fields of the root operation types of every document are gathered into a single `Query`,
+/// `Mutation` and `Subscription` type, in the order of the documents. Root types with other
+/// names are renamed to the conventional ones and `schema` definitions are dropped. All other
+/// definitions are kept as they are, so the documents should be namespaced with a [`Transform`]
+/// first if their type names overlap.
+///
+/// [`Transform`]: struct.Transform.html
+pub fn stitch(documents: Vec<Document>) -> Document {
+    const ROOTS: [&str; 3] = ["Query", "Mutation", "Subscription"];
+    let mut roots: Vec<Option<ObjectTypeDefinitionNode>> = vec![None, None, None];
+    let mut definitions = Vec::new();
+
+    for mut document in documents {
+        let names: Vec<Option<String>> = {
+            let schema = Schema::with_builtins(&document, false);
+            vec![
+                schema.query_type(),
+                schema.mutation_type(),
+                schema.subscription_type(),
+            ]
+            .into_iter()
+            .map(|name| name.map(String::from))
+            .collect()
+        };
+        let mut conventional = Transform::new();
+        for (name, root) in names.iter().zip(ROOTS.iter()) {
+            if let Some(name) = name {
+                if name != root {
+                    conventional.rename_type(name, root);
+                }
+            }
+        }
+        conventional.apply(&mut document);
+
+        for definition in document.definitions {
+            match definition {
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(_)) => (),
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(
+                    TypeDefinitionNode::Object(object),
+                )) if ROOTS.contains(&object.name.value.as_str()) => {
+                    let index = ROOTS
+                        .iter()
+                        .position(|root| *root == object.name.value)
+                        .unwrap();
+                    match &mut roots[index] {
+                        Some(root) => root.fields.extend(object.fields),
+                        None => roots[index] = Some(object),
+                    }
+                }
+                definition => definitions.push(definition),
+            }
+        }
+    }
+
+    let mut stitched: Vec<DefinitionNode> = roots
+        .into_iter()
+        .flatten()
+        .map(|root| {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(TypeDefinitionNode::Object(
+                root,
+            )))
+        })
+        .collect();
+    stitched.extend(definitions);
+    Document::new(stitched)
+}
+
+/// Removes the parts of a schema marked with a directive, such as `@internal`, to publish the
+/// rest of it.
+///
+/// The types, fields, arguments, input fields and enum values the directive is applied to are
+/// removed along with the definition of the directive, then everything that can no longer be
+/// kept: the fields, arguments and input fields of a removed type, the union members and
+/// interfaces that were removed, and the types left without fields, values or members. The types
+/// only referred to by what was removed are pruned last, while the root operation types and the
+/// types nothing referred to in the first place are kept.
+pub fn strip(document: &mut Document, directive: &str) {
+    let roots: Vec<String> = {
+        let schema = Schema::with_builtins(document, false);
+        vec![
+            schema.query_type(),
+            schema.mutation_type(),
+            schema.subscription_type(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(String::from)
+        .collect()
+    };
+    let referenced_before = referenced_types(document);
+    let mut removed: HashSet<String> = HashSet::new();
+    document.definitions.retain(|definition| match definition {
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+            let marked = match type_definition {
+                TypeDefinitionNode::Scalar(scalar) => marks(&scalar.directives, directive),
+                TypeDefinitionNode::Object(object) => marks(&object.directives, directive),
+                TypeDefinitionNode::Interface(interface) => marks(&interface.directives, directive),
+                TypeDefinitionNode::Union(union) => marks(&union.directives, directive),
+                TypeDefinitionNode::Enum(enum_type) => marks(&enum_type.directives, directive),
+                TypeDefinitionNode::Input(input) => marks(&input.directives, directive),
+            };
+            if marked {
+                removed.insert(type_definition.name().to_string());
+            }
+            !marked
+        }
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(definition)) => {
+            definition.name.value != directive
+        }
+        _ => true,
+    });
+    // Removing a member may empty a type, whose removal may in turn remove members of others
+    loop {
+        let emptied = strip_members(document, directive, &removed);
+        if emptied.is_empty() {
+            break;
+        }
+        removed.extend(emptied);
+    }
+    loop {
+        let referenced = referenced_types(document);
+        let unreferenced: HashSet<String> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                    Some(type_definition.name())
+                }
+                _ => None,
+            })
+            .filter(|name| {
+                referenced_before.contains(*name)
+                    && !referenced.contains(*name)
+                    && !roots.iter().any(|root| root == name)
+            })
+            .map(String::from)
+            .collect();
+        if unreferenced.is_empty() {
+            break;
+        }
+        remove_types(document, &unreferenced);
+    }
+}
+
+/// Removes the types no operation can reach, along with their extensions.
+///
+/// Which types are unreachable is told by [`Schema::unreachable_types`], so that a document
+/// without any root operation type is left with only its directive definitions and schema
+/// definition.
+///
+/// [`Schema::unreachable_types`]: ../schema/struct.Schema.html#method.unreachable_types
+pub fn prune(document: &mut Document) {
+    let unreachable: HashSet<String> = Schema::with_builtins(document, false)
+        .unreachable_types()
+        .into_iter()
+        .map(String::from)
+        .collect();
+    remove_types(document, &unreachable);
+}
+
+/// Whether a directive is applied.
+fn marks(directives: &Option<Directives>, directive: &str) -> bool {
+    directives
+        .iter()
+        .flatten()
+        .any(|applied| applied.name.value == directive)
+}
+
+/// Removes the members marked with a directive or referring to a removed type, and the types
+/// left empty, whose names are returned.
+fn strip_members(
+    document: &mut Document,
+    directive: &str,
+    removed: &HashSet<String>,
+) -> HashSet<String> {
+    let kept = |directives: &Option<Directives>, type_node: &TypeNode| {
+        !marks(directives, directive) && !removed.contains(named_type(type_node))
+    };
+    let strip_fields = |fields: &mut Vec<FieldDefinitionNode>| {
+        fields.retain(|field| kept(&field.directives, &field.field_type));
+        for field in fields.iter_mut() {
+            if let Some(arguments) = &mut field.arguments {
+                arguments.retain(|argument| kept(&argument.directives, &argument.input_type));
+            }
+            if field.arguments.as_ref().is_some_and(Vec::is_empty) {
+                field.arguments = None;
+            }
+        }
+    };
+    let strip_named = |named_types: &mut Vec<NamedTypeNode>| {
+        named_types.retain(|named| !removed.contains(named.name.value.as_str()))
+    };
+    let mut emptied = HashSet::new();
+    for definition in &mut document.definitions {
+        match definition {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                let empty = match type_definition {
+                    TypeDefinitionNode::Scalar(_) => false,
+                    TypeDefinitionNode::Object(object) => {
+                        if let Some(interfaces) = &mut object.interfaces {
+                            strip_named(interfaces);
+                        }
+                        strip_fields(&mut object.fields);
+                        object.fields.is_empty()
+                    }
+                    TypeDefinitionNode::Interface(interface) => {
+                        strip_fields(&mut interface.fields);
+                        interface.fields.is_empty()
+                    }
+                    TypeDefinitionNode::Union(union) => {
+                        strip_named(&mut union.types);
+                        union.types.is_empty()
+                    }
+                    TypeDefinitionNode::Enum(enum_type) => {
+                        enum_type
+                            .values
+                            .retain(|value| !marks(&value.directives, directive));
+                        enum_type.values.is_empty()
+                    }
+                    TypeDefinitionNode::Input(input) => {
+                        input
+                            .fields
+                            .retain(|field| kept(&field.directives, &field.input_type));
+                        input.fields.is_empty()
+                    }
+                };
+                if empty {
+                    emptied.insert(type_definition.name().to_string());
+                }
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(definition)) => {
+                if let Some(arguments) = &mut definition.arguments {
+                    arguments.retain(|argument| kept(&argument.directives, &argument.input_type));
+                }
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                if let Some(interfaces) = &mut extension.interfaces {
+                    strip_named(interfaces);
+                }
+                if let Some(fields) = &mut extension.fields {
+                    strip_fields(fields);
+                }
+            }
+            _ => {}
+        }
+    }
+    remove_types(document, &emptied);
+    emptied
+}
+
+/// Removes the definitions and the extensions of types.
+fn remove_types(document: &mut Document, names: &HashSet<String>) {
+    document.definitions.retain(|definition| match definition {
+        DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+            !names.contains(type_definition.name())
+        }
+        DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+            !names.contains(extension.name.value.as_str())
+        }
+        _ => true,
+    });
+}
+
+/// The names of the types the type system definitions of a document refer to, other than by
+/// defining or extending them.
+fn referenced_types(document: &Document) -> HashSet<String> {
+    let mut referenced = HashSet::new();
+    let mut refer = |type_node: &TypeNode| {
+        referenced.insert(named_type(type_node).to_string());
+    };
+    let fields = |fields: &[FieldDefinitionNode], refer: &mut dyn FnMut(&TypeNode)| {
+        for field in fields {
+            refer(&field.field_type);
+            for argument in field.arguments.iter().flatten() {
+                refer(&argument.input_type);
+            }
+        }
+    };
+    let mut named = Vec::new();
+    for definition in &document.definitions {
+        match definition {
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Schema(schema)) => {
+                named.extend(
+                    schema
+                        .operations
+                        .iter()
+                        .map(|operation| &operation.node_type),
+                );
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Schema(extension)) => {
+                let operations = extension.operations.iter().flatten();
+                named.extend(operations.map(|operation| &operation.node_type));
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Type(type_definition)) => {
+                match type_definition {
+                    TypeDefinitionNode::Scalar(_) | TypeDefinitionNode::Enum(_) => {}
+                    TypeDefinitionNode::Object(object) => {
+                        named.extend(object.interfaces.iter().flatten());
+                        fields(&object.fields, &mut refer);
+                    }
+                    TypeDefinitionNode::Interface(interface) => {
+                        fields(&interface.fields, &mut refer)
+                    }
+                    TypeDefinitionNode::Union(union) => named.extend(&union.types),
+                    TypeDefinitionNode::Input(input) => {
+                        for field in &input.fields {
+                            refer(&field.input_type);
+                        }
+                    }
+                }
+            }
+            DefinitionNode::TypeSystem(TypeSystemDefinitionNode::Directive(directive)) => {
+                for argument in directive.arguments.iter().flatten() {
+                    refer(&argument.input_type);
+                }
+            }
+            DefinitionNode::Extension(TypeSystemExtensionNode::Object(extension)) => {
+                named.extend(extension.interfaces.iter().flatten());
+                fields(extension.fields.as_deref().unwrap_or_default(), &mut refer);
+            }
+            DefinitionNode::Executable(_) => {}
+        }
+    }
+    referenced.extend(named.into_iter().map(|named| named.name.value.to_string()));
+    referenced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::printer::print;
+
+    #[test]
+    fn it_prefixes_types_and_references() {
+        let mut document = parse(
+            r#"
+schema { query: Root }
+type Root { node(id: ID!, filter: Filter): Node }
+interface Node { id: ID! }
+type User implements Node { id: ID!, friends: [User!]! }
+union Result = User
+input Filter { kind: Kind = USER }
+enum Kind { USER }
+extend type User { email: Email }
+scalar Email
+fragment UserFields on User { id ... on Node { id } }
+"#,
+        )
+        .unwrap();
+        Transform::new()
+            .with_type_prefix("Accounts_")
+            .with_root_field_prefix("accounts_")
+            .apply(&mut document);
+        assert_eq!(
+            print(&document),
+            r#"schema {
+  query: Root
+}
+
+type Root {
+  accounts_node(id: ID!, filter: Accounts_Filter): Accounts_Node
+}
+
+interface Accounts_Node {
+  id: ID!
+}
+
+type Accounts_User implements Accounts_Node {
+  id: ID!
+  friends: [Accounts_User!]!
+}
+
+union Accounts_Result = Accounts_User
+
+input Accounts_Filter {
+  kind: Accounts_Kind = USER
+}
+
+enum Accounts_Kind {
+  USER
+}
+
+extend type Accounts_User {
+  email: Accounts_Email
+}
+
+scalar Accounts_Email
+
+fragment UserFields on Accounts_User {
+  id
+  ... on Accounts_Node {
+    id
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn explicit_renames_win_over_prefixes() {
+        let mut document =
+            parse("type Query { me: User, you: User } type User { id: ID }").unwrap();
+        Transform::new()
+            .with_type_prefix("A_")
+            .rename_type("User", "Account")
+            .with_root_field_prefix("a_")
+            .rename_root_field("me", "viewer")
+            .apply(&mut document);
+        assert_eq!(
+            print(&document),
+            "type Query {\n  viewer: Account\n  a_you: Account\n}\n\ntype Account {\n  id: ID\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_stitches_root_types() {
+        let first = parse("schema { query: Root } type Root { a: Int } type A { a: Int }").unwrap();
+        let second = parse(
+            "type Query { b: Int } type Mutation { setB(b: Int): Int } extend type Query { c: Int }",
+        )
+        .unwrap();
+        let stitched = stitch(vec![first, second]);
+        assert_eq!(
+            print(&stitched),
+            r#"type Query {
+  a: Int
+  b: Int
+}
+
+type Mutation {
+  setB(b: Int): Int
+}
+
+type A {
+  a: Int
+}
+
+extend type Query {
+  c: Int
+}
+"#
+        );
+    }
+
+    #[test]
+    fn it_prunes_unreachable_types() {
+        let mut document = parse(
+            "type Query { user: User }
+            type User { id: ID! }
+            type Audit { entries: [Entry] }
+            type Entry { message: String }
+            extend type Audit { count: Int }",
+        )
+        .unwrap();
+        prune(&mut document);
+        assert_eq!(
+            print(&document),
+            "type Query {\n  user: User\n}\n\ntype User {\n  id: ID!\n}\n"
+        );
+    }
+
+    #[test]
+    fn it_strips_what_is_marked_internal() {
+        let mut document = parse(
+            "directive @internal on OBJECT | FIELD_DEFINITION | ARGUMENT_DEFINITION | ENUM_VALUE
+            type Query {
+                user(id: ID!, debug: Boolean @internal): User
+                audit: Audit
+                metrics: Metrics @internal
+                flags: Flags
+            }
+            type User { id: ID! role: Role secret: Secret }
+            enum Role { USER ADMIN @internal }
+            type Audit @internal { entries: [Entry] }
+            type Entry { message: String }
+            type Metrics { count: Stat }
+            type Stat { value: Int }
+            type Flags { debug: Boolean @internal }
+            type Secret @internal { value: String }
+            union Result = User | Secret
+            type Unused { id: ID }",
+        )
+        .unwrap();
+        strip(&mut document, "internal");
+        assert_eq!(
+            print(&document),
+            r#"type Query {
+  user(id: ID!): User
+}
+
+type User {
+  id: ID!
+  role: Role
+}
+
+enum Role {
+  USER
+}
+
+union Result = User
+
+type Unused {
+  id: ID
+}
+"#
+        );
+    }
+}
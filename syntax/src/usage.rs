@@ -0,0 +1,226 @@
+//! Counts how often the fields of a schema are selected by a corpus of operations.
+//!
+//! Before a field is deprecated and removed, [`field_usage`] tells how many times the operations
+//! clients send select it, and which fields none of them select at all. Each field is named by
+//! its [schema coordinate].
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::schema::Schema;
+//! use syntax::usage::field_usage;
+//!
+//! let schema_document =
+//!     syntax::parse("type Query { me: User } type User { id: ID! name: String }").unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let operations = [
+//!     syntax::parse("{ me { id } }").unwrap(),
+//!     syntax::parse("query Me { me { id } }").unwrap(),
+//! ];
+//! let report = field_usage(&schema, &operations);
+//! assert_eq!(report.count(&"User.id".parse().unwrap()), 2);
+//! assert_eq!(report.unused[0].to_string(), "User.name");
+//! ```
+//!
+//! [`field_usage`]: fn.field_usage.html
+//! [schema coordinate]: ../coordinate/index.html
+
+use crate::coordinate::SchemaCoordinate;
+use crate::document::{Document, OperationWithFragments};
+use crate::nodes::*;
+use crate::schema::{named_type, Schema};
+use std::collections::HashMap;
+
+/// How often the fields of a schema are selected, returned by [`field_usage`].
+///
+/// [`field_usage`]: fn.field_usage.html
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UsageReport {
+    /// Every field of the objects and interfaces of the schema, extensions included, and the
+    /// number of times it is selected, in the order of the schema
+    pub fields: Vec<(SchemaCoordinate, usize)>,
+    /// The fields no operation selects, in the order of the schema
+    pub unused: Vec<SchemaCoordinate>,
+}
+
+impl UsageReport {
+    /// The number of times a field is selected, 0 for a field the schema does not define.
+    pub fn count(&self, coordinate: &SchemaCoordinate) -> usize {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == coordinate)
+            .map_or(0, |(_, count)| *count)
+    }
+}
+
+/// Counts the selections of the fields of a schema by the operations of several documents.
+///
+/// A field is counted once each time an operation selects it, the selections of the fragments it
+/// spreads included, on the type it is selected on: selecting `node { id }` where `node` is a
+/// `Node` interface counts `Node.id`. As the objects implementing an interface cannot drop its
+/// fields, a field of an object is not unused while the field of an interface it implements is
+/// selected. The introspection fields and the fields the schema does not define are ignored.
+pub fn field_usage(schema: &Schema<'_>, documents: &[Document]) -> UsageReport {
+    let mut counts: HashMap<(&str, &str), usize> = HashMap::new();
+    for document in documents {
+        for operation in document.operations() {
+            let root = match operation.operation.operation() {
+                Operation::Query => schema.query_type(),
+                Operation::Mutation => schema.mutation_type(),
+                Operation::Subscription => schema.subscription_type(),
+            };
+            if let Some(root) = root {
+                let selections = &operation.operation.definition().selections;
+                count(
+                    schema,
+                    &operation,
+                    root,
+                    selections,
+                    &mut Vec::new(),
+                    &mut counts,
+                );
+            }
+        }
+    }
+    let mut report = UsageReport::default();
+    for type_definition in schema.types() {
+        let type_name = type_definition.name();
+        for field in schema.fields(type_name) {
+            let name = field.name.value.as_str();
+            let selected = counts.get(&(type_name, name)).copied().unwrap_or(0);
+            let implemented = schema
+                .interfaces(type_name)
+                .iter()
+                .any(|interface| counts.get(&(*interface, name)).copied().unwrap_or(0) > 0);
+            let coordinate = SchemaCoordinate::Member {
+                type_name: type_name.to_string(),
+                member: name.to_string(),
+            };
+            if selected == 0 && !implemented {
+                report.unused.push(coordinate.clone());
+            }
+            report.fields.push((coordinate, selected));
+        }
+    }
+    report
+}
+
+/// Counts the fields of a selection set on a type, following the fragments it spreads.
+fn count<'a>(
+    schema: &Schema<'a>,
+    operation: &OperationWithFragments<'a>,
+    parent_type: &'a str,
+    selections: &'a [Selection],
+    spread: &mut Vec<&'a str>,
+    counts: &mut HashMap<(&'a str, &'a str), usize>,
+) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => {
+                let name = field.name.value.as_str();
+                let definition = match schema.field(parent_type, name) {
+                    Some(definition) if !name.starts_with("__") => definition,
+                    _ => continue,
+                };
+                *counts.entry((parent_type, name)).or_insert(0) += 1;
+                if let Some(selections) = &field.selections {
+                    let field_type = schema
+                        .get_type(named_type(&definition.field_type))
+                        .map(|definition| definition.name());
+                    if let Some(field_type) = field_type {
+                        count(schema, operation, field_type, selections, spread, counts);
+                    }
+                }
+            }
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                let condition = inline
+                    .node_type
+                    .as_ref()
+                    .and_then(|node_type| schema.get_type(&node_type.name.value))
+                    .map_or(parent_type, |condition| condition.name());
+                count(
+                    schema,
+                    operation,
+                    condition,
+                    &inline.selections,
+                    spread,
+                    counts,
+                );
+            }
+            Selection::Fragment(FragmentSpread::Node(node)) => {
+                let name = node.name.value.as_str();
+                let fragment = operation
+                    .fragments
+                    .iter()
+                    .find(|fragment| fragment.name.value == name);
+                let fragment = match fragment {
+                    Some(fragment) if !spread.contains(&name) => fragment,
+                    _ => continue,
+                };
+                let condition = schema
+                    .get_type(&fragment.node_type.name.value)
+                    .map_or(parent_type, |condition| condition.name());
+                spread.push(name);
+                count(
+                    schema,
+                    operation,
+                    condition,
+                    &fragment.selections,
+                    spread,
+                    counts,
+                );
+                spread.pop();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn it_counts_selected_fields_and_lists_the_unused_ones() {
+        let schema_document = parse(
+            "type Query { node(id: ID!): Node users: [User] legacy: String }
+            interface Node { id: ID! }
+            type User implements Node { id: ID! name: String email: String }
+            extend type User { age: Int }",
+        )
+        .unwrap();
+        let schema = Schema::new(&schema_document);
+        let operations = [
+            parse(
+                "query Users { users { ...user } node(id: 1) { id ... on User { name } } }
+                fragment user on User { name ...user }
+                query Me { users { name __typename } __schema { types { name } } }",
+            )
+            .unwrap(),
+            parse("{ node(id: 2) { id } unknown }").unwrap(),
+        ];
+        let report = field_usage(&schema, &operations);
+        let counts: Vec<(String, usize)> = report
+            .fields
+            .iter()
+            .map(|(coordinate, count)| (coordinate.to_string(), *count))
+            .collect();
+        assert_eq!(
+            counts,
+            [
+                ("Query.node".to_string(), 2),
+                ("Query.users".to_string(), 2),
+                ("Query.legacy".to_string(), 0),
+                ("Node.id".to_string(), 2),
+                ("User.id".to_string(), 0),
+                ("User.name".to_string(), 3),
+                ("User.email".to_string(), 0),
+                ("User.age".to_string(), 0),
+            ]
+        );
+        let unused: Vec<String> = report.unused.iter().map(ToString::to_string).collect();
+        assert_eq!(unused, ["Query.legacy", "User.email", "User.age"]);
+        assert_eq!(report.count(&"User.name".parse().unwrap()), 3);
+        assert_eq!(report.count(&"User.unknown".parse().unwrap()), 0);
+    }
+}
@@ -52,7 +52,7 @@ pub fn validate_extension_fields_against_original<E: NodeWithFields, O: NodeWith
 /// used to determine the validity of the extension.
 pub trait ValidExtensionNode<T> {
     fn validate_extension(&self, original: Option<&T>) -> ValidationResult {
-        if let Some(_) = original {
+        if original.is_some() {
             Ok(())
         } else {
             Err(ValidationError::new("Invalid Extension: No root element"))
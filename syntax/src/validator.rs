@@ -0,0 +1,2061 @@
+//! Validation of executable documents against a schema.
+//!
+//! A [`Validator`] checks the operations and fragments of a document against the types of a
+//! [`Schema`] and reports every issue it finds as a [`ValidationError`], with the locations in
+//! the source it is about.
+//!
+//! # Operations
+//!
+//! The names of the operations of a document are unique, and an anonymous operation is the only
+//! operation of its document.
+//!
+//! # Subscriptions
+//!
+//! A subscription selects a single field at its root, directly or through fragments, which is not
+//! an introspection field such as `__typename`.
+//!
+//! # Cost
+//!
+//! Operations may be limited in cost, by [`ValidatorOptions::max_cost`] or, when the options do
+//! not set it, by the schema itself, with `extend schema @costLimit(max: 1000)` or the same
+//! directive on its `schema` definition. Every field selected costs the weight its definition
+//! is given with `@cost(weight: 10)`, or 1, and the cost of its selections besides, the fields of
+//! every fragment included. The schema defines both directives:
+//!
+//! ```graphql
+//! directive @cost(weight: Int!) on FIELD_DEFINITION
+//! directive @costLimit(max: Int!) on SCHEMA
+//! ```
+//!
+//! # Field merging
+//!
+//! The fields selected under the same response key, directly or through fragments, can be
+//! [merged] into one: they are the same field given the same arguments, unless they are selected
+//! on different object types, which no object is both of, and the fields of their selection sets
+//! can be merged in turn.
+//!
+//! # Fragments
+//!
+//! The names of the fragments of a document are unique, every fragment is spread by an operation,
+//! directly or through other fragments, and the types fragments are defined on exist.
+//!
+//! # Arguments
+//!
+//! The arguments given to a field or a directive have unique names, and so do the fields of an
+//! input object literal. The arguments a field or a directive requires, which are non-null and
+//! have no default value, are given.
+//!
+//! # Values
+//!
+//! The values given to arguments, input fields and variable defaults have the type they are
+//! given for: lists and input object literals are checked item by item and field by field, the
+//! fields an input object literal gives are defined by its type and the ones it requires are
+//! given, and `null` is only given for nullable types. A string naming a value of the enum it is
+//! given for, a frequent mistake of clients, is pointed out as such. OneOf Input Objects, marked
+//! with `@oneOf`, are given exactly one field, which is not `null`. The values of custom scalars
+//! are not checked, and neither are variables.
+//!
+//! # Custom scalars
+//!
+//! The literals given for a scalar are checked beyond their structure by the [`ScalarValidator`]
+//! registered for it with [`Validator::register_scalar`], such as a function telling whether a
+//! `DateTime` string follows RFC 3339.
+//!
+//! # Int literals
+//!
+//! GraphQL's `Int` is a signed 32-bit integer, while the parser keeps every integer literal it
+//! reads, however wide, as its digits. The validator rejects the literals outside of the range of `Int`, unless
+//! they are given for a `Float` or an `ID`, which accept any integer. With
+//! [`IntOverflow::WideScalars`], the literals given for the integer scalars of the default
+//! schema, such as `LongInt` or `BigInt`, are checked against the range of their scalar instead.
+//!
+//! # Example
+//!
+//! ```
+//! use syntax::schema::Schema;
+//! use syntax::validator::{IntOverflow, Validator, ValidatorOptions};
+//!
+//! let schema_document = syntax::parse("type Query { user(id: LongInt): String } scalar LongInt")
+//!     .unwrap();
+//! let schema = Schema::new(&schema_document);
+//! let source = "{ user(id: 3000000000) }";
+//! let document = syntax::parse(source).unwrap();
+//!
+//! let errors = Validator::new(&schema).validate(source, &document);
+//! assert_eq!(
+//!     errors[0].message,
+//!     "Int cannot represent non 32-bit signed integer value: 3000000000"
+//! );
+//! assert_eq!(errors[0].locations[0].column, 12);
+//!
+//! let options = ValidatorOptions {
+//!     int_overflow: IntOverflow::WideScalars,
+//!     ..ValidatorOptions::default()
+//! };
+//! let validator = Validator::with_options(&schema, options);
+//! assert!(validator.validate(source, &document).is_empty());
+//! ```
+//!
+//! [`Validator`]: struct.Validator.html
+//! [`Schema`]: ../schema/struct.Schema.html
+//! [`ValidationError`]: ../error/struct.ValidationError.html
+//! [`IntOverflow::WideScalars`]: enum.IntOverflow.html#variant.WideScalars
+//! [`ScalarValidator`]: trait.ScalarValidator.html
+//! [`Validator::register_scalar`]: struct.Validator.html#method.register_scalar
+//! [`ValidatorOptions::max_cost`]: struct.ValidatorOptions.html#structfield.max_cost
+//! [merged]: ../merge/index.html
+
+use crate::ast::Ast;
+use crate::document::Document;
+use crate::error::ValidationError;
+use crate::lexer::{self, Lexer};
+use crate::merge::{can_merge, merge_selection_sets, response_key};
+use crate::nodes::*;
+use crate::printer::{print_type, print_value};
+use crate::schema::{named_type, Schema};
+use crate::stats::ValidationMetrics;
+use crate::token::{Location, Token, TokenKind};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Range;
+use std::time::Instant;
+
+/// How the [`Validator`] checks the range of integer literals.
+///
+/// [`Validator`]: struct.Validator.html
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntOverflow {
+    /// Reject the literals outside of the 32-bit range of `Int`.
+    #[default]
+    Reject,
+    /// Check the literals given for the integer scalars of the default schema, such as `LongInt`
+    /// and `BigInt`, against the range of their scalar, and the others like `Reject`.
+    WideScalars,
+}
+
+/// Options for the [`Validator`] and for [`Schema::validate_with_options`]. The default options
+/// follow the spec.
+///
+/// [`Validator`]: struct.Validator.html
+/// [`Schema::validate_with_options`]: ../schema/struct.Schema.html#method.validate_with_options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ValidatorOptions {
+    /// How the range of integer literals is checked
+    pub int_overflow: IntOverflow,
+    /// Accept the types, fields, arguments and directives of a schema whose names begin with
+    /// `__`, which the spec reserves for introspection, for the tools that define introspection
+    /// types themselves
+    pub allow_reserved_names: bool,
+    /// The cost operations may not exceed, in place of the one the schema sets with
+    /// `@costLimit`
+    pub max_cost: Option<usize>,
+}
+
+/// Checks the literals given for a scalar beyond their structure, such as the format of a
+/// `DateTime` string. Functions from a value to a result are scalar validators.
+pub trait ScalarValidator {
+    /// Checks a literal given for the scalar, which is neither `null` nor a variable, telling
+    /// what is wrong with it when it is not a value of the scalar.
+    fn validate(&self, value: &ValueNode) -> Result<(), String>;
+}
+
+impl<F> ScalarValidator for F
+where
+    F: Fn(&ValueNode) -> Result<(), String>,
+{
+    fn validate(&self, value: &ValueNode) -> Result<(), String> {
+        self(value)
+    }
+}
+
+/// Validates executable documents against a schema.
+pub struct Validator<'a> {
+    schema: &'a Schema<'a>,
+    options: ValidatorOptions,
+    max_cost: Option<usize>,
+    scalars: HashMap<String, Box<dyn ScalarValidator>>,
+}
+
+impl fmt::Debug for Validator<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut scalars: Vec<&String> = self.scalars.keys().collect();
+        scalars.sort();
+        f.debug_struct("Validator")
+            .field("schema", &self.schema)
+            .field("options", &self.options)
+            .field("max_cost", &self.max_cost)
+            .field("scalars", &scalars)
+            .finish()
+    }
+}
+
+impl<'a> Validator<'a> {
+    /// A validator for the schema, with the default options.
+    pub fn new(schema: &'a Schema<'a>) -> Validator<'a> {
+        Validator::with_options(schema, ValidatorOptions::default())
+    }
+
+    /// A validator for the schema, with options. The operations are limited to the cost the
+    /// options set, or else the one the schema sets with `@costLimit`.
+    pub fn with_options(schema: &'a Schema<'a>, options: ValidatorOptions) -> Validator<'a> {
+        let max_cost = options.max_cost.or_else(|| {
+            let limit = schema
+                .schema_directives()
+                .iter()
+                .find(|directive| directive.name.value == "costLimit")?;
+            match schema.resolved_arguments(limit).get("max")? {
+                ValueNode::Int(max) => max.value.parse().ok(),
+                _ => None,
+            }
+        });
+        Validator {
+            schema,
+            options,
+            max_cost,
+            scalars: HashMap::new(),
+        }
+    }
+
+    /// Checks the literals given for a scalar with a validator, in place of the one registered
+    /// before. The literals given for the built-in scalars are only checked once they have the
+    /// structure of their scalar.
+    pub fn register_scalar<V>(&mut self, name: &str, validator: V)
+    where
+        V: ScalarValidator + 'static,
+    {
+        self.scalars.insert(name.into(), Box::new(validator));
+    }
+
+    /// Validates the operations and fragments of a document parsed from the source, which is
+    /// lexed again to find the locations of the issues. The type system definitions a document
+    /// also holds are skipped.
+    pub fn validate(&self, source: &str, document: &Document) -> Vec<ValidationError> {
+        let map = SourceMap::new(source, document);
+        let mut errors = Vec::new();
+        self.operations(document, &map, &mut errors);
+        self.fragments(document, &map, &mut errors);
+        self.subscriptions(document, &map, &mut errors);
+        self.field_merging(document, &map, &mut errors);
+        self.costs(document, &map, &mut errors);
+        let mut walk = Walk {
+            validator: self,
+            map,
+            errors,
+        };
+        for definition in &document.definitions {
+            match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    walk.operation(operation)
+                }
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    walk.fragment(fragment)
+                }
+                DefinitionNode::TypeSystem(_) | DefinitionNode::Extension(_) => {}
+            }
+        }
+        walk.errors
+    }
+
+    /// Validates a document like [`Validator::validate`], and measures the validation.
+    ///
+    /// [`Validator::validate`]: struct.Validator.html#method.validate
+    pub fn validate_with_metrics(
+        &self,
+        source: &str,
+        document: &Document,
+    ) -> (Vec<ValidationError>, ValidationMetrics) {
+        let start = Instant::now();
+        let errors = self.validate(source, document);
+        let metrics = ValidationMetrics {
+            definitions: document.definitions.len(),
+            errors: errors.len(),
+            duration: start.elapsed(),
+        };
+        (errors, metrics)
+    }
+
+    /// Checks the Operation Name Uniqueness and Lone Anonymous Operation rules.
+    fn operations(&self, document: &Document, map: &SourceMap, errors: &mut Vec<ValidationError>) {
+        let operations: Vec<(usize, &QueryDefinitionNode)> = document
+            .definitions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, definition)| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    Some((index, operation.definition()))
+                }
+                _ => None,
+            })
+            .collect();
+        for (position, (index, operation)) in operations.iter().enumerate() {
+            match &operation.name {
+                Some(name) => {
+                    let first = operations[..position].iter().find(|(_, previous)| {
+                        previous.name.as_ref().map(|previous| &previous.value) == Some(&name.value)
+                    });
+                    if let Some((first, _)) = first {
+                        let error = ValidationError::new(&format!(
+                            "There can be only one operation named \"{}\".",
+                            name.value
+                        ));
+                        let locations = map.name_of(*first).into_iter().chain(map.name_of(*index));
+                        errors.push(located(error, locations));
+                    }
+                }
+                None if operations.len() > 1 => {
+                    let error = ValidationError::new(
+                        "This anonymous operation must be the only defined operation.",
+                    );
+                    errors.push(located(error, map.start_of(*index)));
+                }
+                None => {}
+            }
+        }
+    }
+
+    /// Checks the Fragment Name Uniqueness and Fragments Must Be Used rules.
+    fn fragments(&self, document: &Document, map: &SourceMap, errors: &mut Vec<ValidationError>) {
+        let mut fragments: Vec<(usize, &FragmentDefinitionNode)> = Vec::new();
+        let mut used = Vec::new();
+        for (index, definition) in document.definitions.iter().enumerate() {
+            match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    fragments.push((index, fragment))
+                }
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    spreads(&operation.definition().selections, &mut used)
+                }
+                _ => {}
+            }
+        }
+        // The fragments spread by the fragments used are used too
+        let mut followed = 0;
+        while followed < used.len() {
+            let name = used[followed];
+            if let Some((_, fragment)) = fragments
+                .iter()
+                .find(|(_, fragment)| fragment.name.value == name)
+            {
+                spreads(&fragment.selections, &mut used);
+            }
+            followed += 1;
+        }
+        for (position, (index, fragment)) in fragments.iter().enumerate() {
+            let name = &fragment.name.value;
+            let first = fragments[..position]
+                .iter()
+                .find(|(_, previous)| previous.name.value == *name);
+            if let Some((first, _)) = first {
+                let error = ValidationError::new(&format!(
+                    "There can be only one fragment named \"{}\".",
+                    name
+                ));
+                let locations = map.name_of(*first).into_iter().chain(map.name_of(*index));
+                errors.push(located(error, locations));
+            } else if !used.contains(&name.as_str()) {
+                let error = ValidationError::new(&format!("Fragment \"{}\" is never used.", name));
+                errors.push(located(error, map.start_of(*index)));
+            }
+        }
+    }
+
+    /// Checks the Single Root Field rule of subscriptions, which also may not select an
+    /// introspection field at their root.
+    fn subscriptions(
+        &self,
+        document: &Document,
+        map: &SourceMap,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let fragments: Vec<&FragmentDefinitionNode> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    Some(fragment)
+                }
+                _ => None,
+            })
+            .collect();
+        for (index, definition) in document.definitions.iter().enumerate() {
+            let subscription = match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(
+                    OperationTypeNode::Subscription(subscription),
+                )) => subscription,
+                _ => continue,
+            };
+            let mut fields = Vec::new();
+            root_fields(
+                &subscription.selections,
+                &fragments,
+                &mut Vec::new(),
+                &mut fields,
+            );
+            let name = match &subscription.name {
+                Some(name) => format!("Subscription \"{}\"", name.value),
+                None => "Anonymous Subscription".to_string(),
+            };
+            let mut keys: Vec<&str> = Vec::new();
+            for field in &fields {
+                let key = field.alias.as_ref().unwrap_or(&field.name).value.as_str();
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+            if keys.len() > 1 {
+                let error = ValidationError::new(&format!(
+                    "{} must select only one top level field.",
+                    name
+                ));
+                errors.push(located(error, map.start_of(index)));
+            }
+            if fields
+                .iter()
+                .any(|field| field.name.value.starts_with("__"))
+            {
+                let error = ValidationError::new(&format!(
+                    "{} must not select an introspection top level field.",
+                    name
+                ));
+                errors.push(located(error, map.start_of(index)));
+            }
+        }
+    }
+
+    /// Checks the Field Selection Merging rule, reporting each conflict once.
+    fn field_merging(
+        &self,
+        document: &Document,
+        map: &SourceMap,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        let fragments: Vec<&FragmentDefinitionNode> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    Some(fragment)
+                }
+                _ => None,
+            })
+            .collect();
+        let mut reported: Vec<String> = Vec::new();
+        for (index, definition) in document.definitions.iter().enumerate() {
+            let (parent, selections) = match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    let root = match operation {
+                        OperationTypeNode::Query(_) => self.schema.query_type(),
+                        OperationTypeNode::Mutation(_) => self.schema.mutation_type(),
+                        OperationTypeNode::Subscription(_) => self.schema.subscription_type(),
+                    };
+                    (root, &operation.definition().selections)
+                }
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => (
+                    Some(fragment.node_type.name.value.as_str()),
+                    &fragment.selections,
+                ),
+                _ => continue,
+            };
+            let mut conflicts = Vec::new();
+            self.conflicts_within(
+                parent,
+                selections,
+                &fragments,
+                &mut Vec::new(),
+                &mut conflicts,
+            );
+            for conflict in conflicts {
+                if !reported.contains(&conflict) {
+                    let error = ValidationError::new(&conflict);
+                    errors.push(located(error, map.start_of(index)));
+                    reported.push(conflict);
+                }
+            }
+        }
+    }
+
+    /// Adds the conflicts between the fields a selection set selects, and within the selection
+    /// sets of its fields, not following the fragments already followed.
+    fn conflicts_within<'d>(
+        &self,
+        parent: Option<&'d str>,
+        selections: &'d [Selection],
+        fragments: &[&'d FragmentDefinitionNode],
+        followed: &mut Vec<&'d str>,
+        conflicts: &mut Vec<String>,
+    ) where
+        'a: 'd,
+    {
+        let depth = followed.len();
+        let mut fields = Vec::new();
+        self.fields_in_set(parent, selections, fragments, followed, &mut fields);
+        for (index, field) in fields.iter().enumerate() {
+            for other in &fields[index + 1..] {
+                if response_key(field.1) != response_key(other.1) {
+                    continue;
+                }
+                if let Some(reason) = self.conflict(*field, *other, false, fragments, followed) {
+                    conflicts.push(format!(
+                        "Fields \"{}\" conflict because {}. Use different aliases on the fields to fetch both if this was intentional.",
+                        response_key(field.1),
+                        reason
+                    ));
+                }
+            }
+            if let Some(selections) = &field.1.selections {
+                let field_type = self.field_type(field.0, field.1);
+                self.conflicts_within(field_type, selections, fragments, followed, conflicts);
+            }
+        }
+        followed.truncate(depth);
+    }
+
+    /// Why two fields selected under the same response key, with the types they are selected on,
+    /// cannot be merged, if they cannot. Fields selected on different object types, or within
+    /// fields that are, are not the same field, and only their selection sets are compared.
+    fn conflict<'d>(
+        &self,
+        (parent, field): (Option<&'d str>, &'d FieldNode),
+        (other_parent, other): (Option<&'d str>, &'d FieldNode),
+        exclusive: bool,
+        fragments: &[&'d FragmentDefinitionNode],
+        followed: &[&'d str],
+    ) -> Option<String>
+    where
+        'a: 'd,
+    {
+        let is_object = |parent: Option<&str>| {
+            matches!(
+                parent.and_then(|parent| self.schema.get_type(parent)),
+                Some(TypeDefinitionNode::Object(_))
+            )
+        };
+        let exclusive =
+            exclusive || (parent != other_parent && is_object(parent) && is_object(other_parent));
+        if !exclusive && field.name.value != other.name.value {
+            return Some(format!(
+                "\"{}\" and \"{}\" are different fields",
+                field.name.value, other.name.value
+            ));
+        }
+        if !exclusive && !can_merge(field, other) {
+            return Some("they have differing arguments".to_string());
+        }
+        let selection_sets = merge_selection_sets(&[field, other]);
+        if selection_sets.len() < 2 {
+            return None;
+        }
+        let mut fields = Vec::new();
+        let field_type = self.field_type(parent, field);
+        self.fields_in_set(
+            field_type,
+            selection_sets[0],
+            fragments,
+            &mut followed.to_vec(),
+            &mut fields,
+        );
+        let mut others = Vec::new();
+        let other_type = self.field_type(other_parent, other);
+        self.fields_in_set(
+            other_type,
+            selection_sets[1],
+            fragments,
+            &mut followed.to_vec(),
+            &mut others,
+        );
+        fields.iter().find_map(|subfield| {
+            let key = response_key(subfield.1);
+            others
+                .iter()
+                .filter(|other| response_key(other.1) == key)
+                .find_map(|other| self.conflict(*subfield, *other, exclusive, fragments, followed))
+                .map(|reason| format!("subfields \"{}\" conflict because {}", key, reason))
+        })
+    }
+
+    /// Adds the fields a selection set selects, through the fragments it spreads that are not
+    /// followed yet, with the type each is selected on.
+    fn fields_in_set<'d>(
+        &self,
+        parent: Option<&'d str>,
+        selections: &'d [Selection],
+        fragments: &[&'d FragmentDefinitionNode],
+        followed: &mut Vec<&'d str>,
+        fields: &mut Vec<(Option<&'d str>, &'d FieldNode)>,
+    ) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => fields.push((parent, field)),
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let parent = match &inline.node_type {
+                        Some(node_type) => Some(node_type.name.value.as_str()),
+                        None => parent,
+                    };
+                    self.fields_in_set(parent, &inline.selections, fragments, followed, fields);
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    let name = spread.name.value.as_str();
+                    if followed.contains(&name) {
+                        continue;
+                    }
+                    followed.push(name);
+                    if let Some(fragment) = fragments
+                        .iter()
+                        .find(|fragment| fragment.name.value == name)
+                    {
+                        let parent = Some(fragment.node_type.name.value.as_str());
+                        self.fields_in_set(
+                            parent,
+                            &fragment.selections,
+                            fragments,
+                            followed,
+                            fields,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// The name of the type of a field selected on a type, when the schema defines it.
+    fn field_type<'d>(&self, parent: Option<&'d str>, field: &FieldNode) -> Option<&'d str>
+    where
+        'a: 'd,
+    {
+        let definition = self.schema.field(parent?, &field.name.value)?;
+        Some(named_type(&definition.field_type))
+    }
+
+    /// Checks that the operations do not exceed the maximum cost, if any.
+    fn costs(&self, document: &Document, map: &SourceMap, errors: &mut Vec<ValidationError>) {
+        let max_cost = match self.max_cost {
+            Some(max_cost) => max_cost,
+            None => return,
+        };
+        let fragments: Vec<&FragmentDefinitionNode> = document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Fragment(fragment)) => {
+                    Some(fragment)
+                }
+                _ => None,
+            })
+            .collect();
+        let mut fragment_costs = HashMap::new();
+        for (index, definition) in document.definitions.iter().enumerate() {
+            let operation = match definition {
+                DefinitionNode::Executable(ExecutableDefinitionNode::Operation(operation)) => {
+                    operation
+                }
+                _ => continue,
+            };
+            let root = match operation.operation() {
+                Operation::Query => self.schema.query_type(),
+                Operation::Mutation => self.schema.mutation_type(),
+                Operation::Subscription => self.schema.subscription_type(),
+            };
+            let cost = self.cost(
+                root.unwrap_or_default(),
+                &operation.definition().selections,
+                &fragments,
+                &mut Vec::new(),
+                &mut fragment_costs,
+            );
+            if cost > max_cost {
+                let name = match &operation.definition().name {
+                    Some(name) => format!("Operation \"{}\"", name.value),
+                    None => "Anonymous Operation".to_string(),
+                };
+                let error = ValidationError::new(&format!(
+                    "{} has a cost of {}, which exceeds the maximum cost of {}.",
+                    name, cost, max_cost
+                ));
+                errors.push(located(error, map.start_of(index)));
+            }
+        }
+    }
+
+    /// The cost of a selection set on a type. The cost of each fragment is kept, as a fragment
+    /// may be spread many times.
+    fn cost<'d>(
+        &self,
+        parent_type: &str,
+        selections: &'d [Selection],
+        fragments: &[&'d FragmentDefinitionNode],
+        followed: &mut Vec<&'d str>,
+        fragment_costs: &mut HashMap<&'d str, usize>,
+    ) -> usize {
+        let mut cost: usize = 0;
+        for selection in selections {
+            let selection_cost = match selection {
+                Selection::Field(field) => {
+                    let definition = self.schema.field(parent_type, &field.name.value);
+                    let weight: usize = definition
+                        .and_then(|definition| definition.directives.as_ref())
+                        .into_iter()
+                        .flatten()
+                        .find(|directive| directive.name.value == "cost")
+                        .and_then(|directive| {
+                            match self.schema.resolved_arguments(directive).get("weight")? {
+                                ValueNode::Int(weight) => weight.value.parse().ok(),
+                                _ => None,
+                            }
+                        })
+                        .unwrap_or(1);
+                    let field_type = definition
+                        .map(|definition| named_type(&definition.field_type))
+                        .unwrap_or_default();
+                    let selections = field.selections.as_deref().unwrap_or_default();
+                    weight.saturating_add(self.cost(
+                        field_type,
+                        selections,
+                        fragments,
+                        followed,
+                        fragment_costs,
+                    ))
+                }
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    let condition = inline
+                        .node_type
+                        .as_ref()
+                        .map_or(parent_type, |condition| &condition.name.value);
+                    self.cost(
+                        condition,
+                        &inline.selections,
+                        fragments,
+                        followed,
+                        fragment_costs,
+                    )
+                }
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    let name = spread.name.value.as_str();
+                    if let Some(cost) = fragment_costs.get(name) {
+                        *cost
+                    } else if followed.contains(&name) {
+                        0
+                    } else {
+                        let fragment = fragments
+                            .iter()
+                            .find(|fragment| fragment.name.value == name);
+                        let fragment = match fragment {
+                            Some(fragment) => fragment,
+                            None => continue,
+                        };
+                        followed.push(name);
+                        let fragment_cost = self.cost(
+                            &fragment.node_type.name.value,
+                            &fragment.selections,
+                            fragments,
+                            followed,
+                            fragment_costs,
+                        );
+                        followed.pop();
+                        fragment_costs.insert(name, fragment_cost);
+                        fragment_cost
+                    }
+                }
+            };
+            cost = cost.saturating_add(selection_cost);
+        }
+        cost
+    }
+
+    /// Checks a value against the type it is given for, like the values of a document but
+    /// without locations.
+    pub(crate) fn check_value(
+        &self,
+        value: &ValueNode,
+        expected: &TypeNode,
+    ) -> Vec<ValidationError> {
+        let mut walk = Walk {
+            validator: self,
+            map: SourceMap::default(),
+            errors: Vec::new(),
+        };
+        walk.check(value, Some(expected), None);
+        walk.errors
+    }
+
+    /// The range of the integers a literal given for a type may hold, and the name of the
+    /// scalar the range is the one of, or `None` when any integer is accepted.
+    fn int_range(&self, type_name: Option<&str>) -> Option<(&'static str, i128, u128)> {
+        match type_name {
+            Some("Float") | Some("ID") => return None,
+            Some(name) if self.options.int_overflow == IntOverflow::WideScalars => {
+                if let Some(range) = WIDE_SCALARS.iter().find(|range| range.0 == name) {
+                    return Some(*range);
+                }
+            }
+            _ => {}
+        }
+        Some(("Int", i32::MIN.into(), i32::MAX as u128))
+    }
+}
+
+/// The integer scalars of the default schema wider than `Int`, and their ranges.
+const WIDE_SCALARS: [(&str, i128, u128); 9] = [
+    ("TinyInt", i8::MIN as i128, i8::MAX as u128),
+    ("ShortInt", i16::MIN as i128, i16::MAX as u128),
+    ("LongInt", i64::MIN as i128, i64::MAX as u128),
+    ("BigInt", i128::MIN, i128::MAX as u128),
+    ("Uint", 0, u32::MAX as u128),
+    ("TinyUint", 0, u8::MAX as u128),
+    ("ShortUint", 0, u16::MAX as u128),
+    ("LongUint", 0, u64::MAX as u128),
+    ("BigUint", 0, u128::MAX),
+];
+
+/// A walk through the executable definitions of a document, in the order they are written.
+struct Walk<'v, 'a> {
+    validator: &'v Validator<'a>,
+    map: SourceMap,
+    errors: Vec<ValidationError>,
+}
+
+/// A field or a directive, which arguments are given to.
+#[derive(Clone, Copy)]
+enum Owner<'n> {
+    Field(&'n str),
+    Directive(&'n str),
+}
+
+impl fmt::Display for Owner<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Owner::Field(name) => write!(f, "Field \"{}\"", name),
+            Owner::Directive(name) => write!(f, "Directive \"@{}\"", name),
+        }
+    }
+}
+
+impl<'v, 'a> Walk<'v, 'a> {
+    fn operation(&mut self, operation: &OperationTypeNode) {
+        let schema = self.validator.schema;
+        let root = match operation {
+            OperationTypeNode::Query(_) => schema.query_type(),
+            OperationTypeNode::Mutation(_) => schema.mutation_type(),
+            OperationTypeNode::Subscription(_) => schema.subscription_type(),
+        };
+        let definition = operation.definition();
+        self.variables(&definition.variables);
+        self.selections(root, &definition.selections);
+    }
+
+    fn fragment(&mut self, fragment: &FragmentDefinitionNode) {
+        self.type_condition(&fragment.node_type);
+        self.variables(&fragment.variables);
+        self.directives(&fragment.directives);
+        self.selections(Some(&fragment.node_type.name.value), &fragment.selections);
+    }
+
+    /// Checks the Fragment Spread Type Existence rule.
+    fn type_condition(&mut self, node_type: &NamedTypeNode) {
+        let location = self.map.type_conditions.take();
+        if !self.validator.schema.has_type(&node_type.name.value) {
+            let error =
+                ValidationError::new(&format!("Unknown type \"{}\".", node_type.name.value));
+            self.error(error, location);
+        }
+    }
+
+    fn variables(&mut self, variables: &Option<Variables>) {
+        for variable in variables.iter().flatten() {
+            if let Some(default_value) = &variable.default_value {
+                self.value(default_value, Some(&variable.variable_type));
+            }
+        }
+    }
+
+    fn selections(&mut self, parent: Option<&str>, selections: &[Selection]) {
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => self.field(parent, field),
+                Selection::Fragment(FragmentSpread::Node(spread)) => {
+                    self.directives(&spread.directives)
+                }
+                Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                    if let Some(node_type) = &inline.node_type {
+                        self.type_condition(node_type);
+                    }
+                    self.directives(&inline.directives);
+                    let parent = match &inline.node_type {
+                        Some(node_type) => Some(node_type.name.value.as_str()),
+                        None => parent,
+                    };
+                    self.selections(parent, &inline.selections);
+                }
+            }
+        }
+    }
+
+    fn field(&mut self, parent: Option<&str>, field: &FieldNode) {
+        let location = self.map.fields.take();
+        let definition =
+            parent.and_then(|parent| self.validator.schema.field(parent, &field.name.value));
+        self.arguments(
+            Owner::Field(&field.name.value),
+            location,
+            definition.map(|definition| &definition.arguments),
+            &field.arguments,
+        );
+        self.directives(&field.directives);
+        if let Some(selections) = &field.selections {
+            let field_type = definition.map(|definition| named_type(&definition.field_type));
+            self.selections(field_type, selections);
+        }
+    }
+
+    fn directives(&mut self, directives: &Option<Directives>) {
+        for directive in directives.iter().flatten() {
+            let location = self.map.directives.take();
+            let definition = self.validator.schema.get_directive(&directive.name.value);
+            self.arguments(
+                Owner::Directive(&directive.name.value),
+                location,
+                definition.map(|definition| &definition.arguments),
+                &directive.arguments,
+            );
+        }
+    }
+
+    /// Checks the arguments given to a field or a directive against their definitions, when the
+    /// field or the directive is defined.
+    fn arguments(
+        &mut self,
+        owner: Owner<'_>,
+        location: Option<Location>,
+        definitions: Option<&Option<ArgumentDefinitions>>,
+        arguments: &Option<Arguments>,
+    ) {
+        let definitions = definitions.map(|definitions| definitions.as_deref().unwrap_or_default());
+        let mut given: Vec<(&str, Option<Location>)> = Vec::new();
+        for argument in arguments.iter().flatten() {
+            let argument_location = self.map.arguments.take();
+            let name = argument.name.value.as_str();
+            if let Some((_, first)) = given.iter().find(|(given, _)| *given == name) {
+                let error = ValidationError::new(&format!(
+                    "There can be only one argument named \"{}\".",
+                    name
+                ));
+                self.errors.push(located(
+                    error,
+                    first.iter().copied().chain(argument_location),
+                ));
+            } else {
+                given.push((name, argument_location));
+            }
+            let definition = definitions
+                .into_iter()
+                .flatten()
+                .find(|definition| definition.name.value == argument.name.value);
+            self.value(
+                &argument.value,
+                definition.map(|definition| &definition.input_type),
+            );
+        }
+        for definition in definitions.into_iter().flatten() {
+            let required = matches!(definition.input_type, TypeNode::NonNull(_))
+                && definition.default_value.is_none();
+            if required && !given.iter().any(|(name, _)| *name == definition.name.value) {
+                let error = ValidationError::new(&format!(
+                    "{} argument \"{}\" of type \"{}\" is required, but it was not provided.",
+                    owner,
+                    definition.name.value,
+                    print_type(&definition.input_type)
+                ));
+                self.error(error, location);
+            }
+        }
+    }
+
+    /// Checks a value given for a type, or for an unknown type when `None`.
+    fn value(&mut self, value: &ValueNode, expected: Option<&TypeNode>) {
+        let location = self.map.values.take();
+        self.check(value, expected, location);
+    }
+
+    /// Checks the Values of Correct Type rule, for a value or for the items of a list given for a
+    /// list type.
+    fn check(
+        &mut self,
+        value: &ValueNode,
+        expected: Option<&TypeNode>,
+        location: Option<Location>,
+    ) {
+        let expected = match (value, expected) {
+            // The variables are checked against the types of their definitions
+            (ValueNode::Variable(_), _) | (_, None) => return self.unchecked(value, location),
+            (_, Some(expected)) => expected,
+        };
+        match (expected, value) {
+            (TypeNode::NonNull(_), ValueNode::Null) => {
+                let error = ValidationError::new(&format!(
+                    "Expected value of type \"{}\", found null.",
+                    print_type(expected)
+                ));
+                self.error(error, location)
+            }
+            (TypeNode::NonNull(inner), _) => self.check(value, Some(inner), location),
+            (_, ValueNode::Null) => {}
+            (TypeNode::List(list), ValueNode::List(items)) => {
+                for item in &items.values {
+                    self.value(item, Some(&list.list_type));
+                }
+            }
+            // A single value is given for a list of one item
+            (TypeNode::List(list), _) => self.check(value, Some(&list.list_type), location),
+            (TypeNode::Named(named), _) => {
+                let schema = self.validator.schema;
+                match schema.get_type(&named.name.value) {
+                    Some(TypeDefinitionNode::Scalar(_)) => {
+                        self.scalar(value, &named.name.value, location)
+                    }
+                    Some(TypeDefinitionNode::Enum(enum_type)) => {
+                        self.enum_value(value, enum_type, location)
+                    }
+                    Some(TypeDefinitionNode::Input(input)) => match value {
+                        ValueNode::Object(object) => self.object(object, Some(input), location),
+                        _ => self.expected(value, expected, location),
+                    },
+                    _ => self.unchecked(value, location),
+                }
+            }
+        }
+    }
+
+    /// Checks a value given for a scalar. Custom scalars accept any value.
+    fn scalar(&mut self, value: &ValueNode, scalar: &str, location: Option<Location>) {
+        let message = match (scalar, value) {
+            ("Int", ValueNode::Int(_))
+            | ("Float", ValueNode::Int(_))
+            | ("Float", ValueNode::Float(_))
+            | ("String", ValueNode::Str(_))
+            | ("Boolean", ValueNode::Bool(_))
+            | ("ID", ValueNode::Str(_))
+            | ("ID", ValueNode::Int(_)) => None,
+            ("Int", _) => Some("Int cannot represent non-integer value"),
+            ("Float", _) => Some("Float cannot represent non numeric value"),
+            ("String", _) => Some("String cannot represent a non string value"),
+            ("Boolean", _) => Some("Boolean cannot represent a non boolean value"),
+            ("ID", _) => Some("ID cannot represent a non-string and non-integer value"),
+            _ => None,
+        };
+        let validated = match (message, self.validator.scalars.get(scalar)) {
+            (None, Some(validator)) => validator.validate(value),
+            _ => Ok(()),
+        };
+        match (message, validated, value) {
+            (Some(message), _, _) => {
+                let message = format!("{}: {}", message, print_value(value));
+                self.error(ValidationError::new(&message), location);
+                self.children(value);
+            }
+            (None, Err(reason), _) => {
+                let error = ValidationError::new(&format!(
+                    "Expected value of type \"{}\", found {}; {}",
+                    scalar,
+                    print_value(value),
+                    reason
+                ));
+                self.error(error, location);
+                self.children(value);
+            }
+            (None, Ok(()), ValueNode::Int(int)) => self.int(int, Some(scalar), location),
+            (None, Ok(()), _) => self.unchecked(value, location),
+        }
+    }
+
+    /// Checks a value given for an enum, pointing out the strings that name one of its values.
+    fn enum_value(
+        &mut self,
+        value: &ValueNode,
+        enum_type: &EnumTypeDefinitionNode,
+        location: Option<Location>,
+    ) {
+        let name = &enum_type.name.value;
+        let defines = |value: &str| {
+            enum_type
+                .values
+                .iter()
+                .any(|defined| defined.name.value == value)
+        };
+        let message = match value {
+            ValueNode::Enum(enum_value) if defines(&enum_value.value) => return,
+            ValueNode::Enum(enum_value) => format!(
+                "Value \"{}\" does not exist in \"{}\" enum.",
+                enum_value.value, name
+            ),
+            ValueNode::Str(string) if defines(&string.value) => format!(
+                "Enum \"{}\" cannot represent non-enum value: {}. Did you mean the enum value \"{}\"?",
+                name,
+                print_value(value),
+                string.value
+            ),
+            _ => format!(
+                "Enum \"{}\" cannot represent non-enum value: {}.",
+                name,
+                print_value(value)
+            ),
+        };
+        self.error(ValidationError::new(&message), location);
+        self.children(value);
+    }
+
+    /// Checks the fields of an input object literal given for an input object type, or for an
+    /// unknown type when `None`.
+    fn object(
+        &mut self,
+        object: &ObjectValueNode,
+        input: Option<&InputTypeDefinitionNode>,
+        location: Option<Location>,
+    ) {
+        let mut given: Vec<(&str, Option<Location>)> = Vec::new();
+        for field in &object.fields {
+            let field_location = self.map.object_fields.take();
+            let name = field.name.value.as_str();
+            if let Some((_, first)) = given.iter().find(|(given, _)| *given == name) {
+                let error = ValidationError::new(&format!(
+                    "There can be only one input field named \"{}\".",
+                    name
+                ));
+                self.errors
+                    .push(located(error, first.iter().copied().chain(field_location)));
+            } else {
+                given.push((name, field_location));
+            }
+            let definition = input.and_then(|input| {
+                input
+                    .fields
+                    .iter()
+                    .find(|definition| definition.name.value == field.name.value)
+            });
+            if let (Some(input), None) = (input, definition) {
+                let error = ValidationError::new(&format!(
+                    "Field \"{}\" is not defined by type \"{}\".",
+                    name, input.name.value
+                ));
+                self.error(error, field_location);
+            }
+            self.value(
+                &field.value,
+                definition.map(|definition| &definition.input_type),
+            );
+        }
+        if let Some(input) =
+            input.filter(|input| self.validator.schema.is_one_of(&input.name.value))
+        {
+            let message = match &object.fields[..] {
+                [field] if field.value == ValueNode::Null => Some(format!(
+                    "Field \"{}.{}\" must be non-null.",
+                    input.name.value, field.name.value
+                )),
+                [_] => None,
+                _ => Some(format!(
+                    "OneOf Input Object \"{}\" must specify exactly one key.",
+                    input.name.value
+                )),
+            };
+            if let Some(message) = message {
+                self.error(ValidationError::new(&message), location);
+            }
+        }
+        for definition in input.iter().flat_map(|input| &input.fields) {
+            let required = matches!(definition.input_type, TypeNode::NonNull(_))
+                && definition.default_value.is_none();
+            if required && !given.iter().any(|(name, _)| *name == definition.name.value) {
+                let error = ValidationError::new(&format!(
+                    "Field \"{}.{}\" of required type \"{}\" was not provided.",
+                    input
+                        .map(|input| input.name.value.as_str())
+                        .unwrap_or_default(),
+                    definition.name.value,
+                    print_type(&definition.input_type)
+                ));
+                self.error(error, location);
+            }
+        }
+    }
+
+    /// Reports a value that is not of the type it is given for.
+    fn expected(&mut self, value: &ValueNode, expected: &TypeNode, location: Option<Location>) {
+        let error = ValidationError::new(&format!(
+            "Expected value of type \"{}\", found {}.",
+            print_type(expected),
+            print_value(value)
+        ));
+        self.error(error, location);
+        self.children(value);
+    }
+
+    /// Checks the range of an integer literal given for a type, or for an unknown type when
+    /// `None`.
+    fn int(&mut self, int: &IntValueNode, type_name: Option<&str>, location: Option<Location>) {
+        if let Some((scalar, min, max)) = self.validator.int_range(type_name) {
+            // Literals wider than an `i128` can only be positive ones that fit a `u128`
+            let fits = match (int.as_i128(), int.as_u128()) {
+                (Some(value), _) => {
+                    value >= min && u128::try_from(value).map_or(true, |value| value <= max)
+                }
+                (None, Some(value)) => value <= max,
+                (None, None) => false,
+            };
+            if !fits {
+                let message = if scalar == "Int" {
+                    format!(
+                        "Int cannot represent non 32-bit signed integer value: {}",
+                        int.value
+                    )
+                } else {
+                    format!("{} cannot represent integer value: {}", scalar, int.value)
+                };
+                self.error(ValidationError::new(&message), location);
+            }
+        }
+    }
+
+    /// Checks a value given for an unknown type, which can only be told apart from the others by
+    /// the range of its integers.
+    fn unchecked(&mut self, value: &ValueNode, location: Option<Location>) {
+        match value {
+            ValueNode::Int(int) => self.int(int, None, location),
+            value => self.children(value),
+        }
+    }
+
+    /// Checks the items of a list or the fields of an input object given for an unknown type.
+    fn children(&mut self, value: &ValueNode) {
+        match value {
+            ValueNode::List(list) => {
+                for item in &list.values {
+                    self.value(item, None);
+                }
+            }
+            ValueNode::Object(object) => self.object(object, None, None),
+            _ => {}
+        }
+    }
+
+    fn error(&mut self, error: ValidationError, location: Option<Location>) {
+        self.errors.push(located(error, location));
+    }
+}
+
+/// Where the nodes of an executable document are in its source.
+///
+/// The nodes of a document do not keep their position in the source, so the source is lexed again
+/// and its nodes are found in the order they are written, which is the order the parser builds
+/// them in. The tokens of the type system definitions are skipped, once the source is parsed again
+/// to find where they are: only their first token is kept, as their start.
+#[derive(Debug, Default)]
+struct SourceMap {
+    /// The location of the first token of every definition, and of its name
+    definitions: Vec<(Location, Option<Location>)>,
+    /// The fields, where their alias or their name is
+    fields: Locations,
+    /// The arguments, where their name is
+    arguments: Locations,
+    /// The fields of input objects, where their name is
+    object_fields: Locations,
+    /// The directives, where their `@` is
+    directives: Locations,
+    /// The type conditions of fragments, where their type is
+    type_conditions: Locations,
+    /// The values, where their first token is
+    values: Locations,
+}
+
+/// The locations of one kind of node, taken in the order the nodes are written.
+#[derive(Debug, Default)]
+struct Locations {
+    all: Vec<Location>,
+    next: usize,
+}
+
+impl Locations {
+    fn take(&mut self) -> Option<Location> {
+        self.next += 1;
+        self.all.get(self.next - 1).copied()
+    }
+}
+
+/// What the tokens between a pair of braces, brackets or parentheses are.
+#[derive(Clone, Copy, PartialEq)]
+enum Nesting {
+    Selections,
+    /// The arguments of a field or a directive, or the variables of an operation
+    Arguments,
+    List,
+    Object,
+    /// A list type, in the definition of a variable
+    Type,
+}
+
+impl SourceMap {
+    fn new(source: &str, document: &Document) -> SourceMap {
+        let executable =
+            |definition: &DefinitionNode| matches!(definition, DefinitionNode::Executable(_));
+        let spans = if document.definitions.iter().all(executable) {
+            Vec::new()
+        } else {
+            match definition_spans(source) {
+                Some(spans) if spans.len() == document.definitions.len() => spans,
+                _ => return SourceMap::default(),
+            }
+        };
+        let skipped: Vec<&Range<usize>> = spans
+            .iter()
+            .zip(&document.definitions)
+            .filter(|(_, definition)| !executable(definition))
+            .map(|(span, _)| span)
+            .collect();
+        let all: Vec<Token> = match lexer::tokenize(source) {
+            Ok(tokens) => tokens
+                .into_iter()
+                .filter(|token| {
+                    !matches!(
+                        token.kind,
+                        TokenKind::Start | TokenKind::End | TokenKind::Comment
+                    )
+                })
+                .collect(),
+            Err(_) => return SourceMap::default(),
+        };
+        let in_span =
+            |span: &Range<usize>, token: &Token| span.contains(&token.location().absolute_position);
+        let tokens: Vec<Token> = all
+            .iter()
+            .filter(|token| !skipped.iter().any(|span| in_span(span, token)))
+            .copied()
+            .collect();
+        let mut map = SourceMap::default();
+        let mut nesting = Vec::new();
+        let (mut open, mut in_type) = (false, false);
+        for (index, token) in tokens.iter().enumerate() {
+            let kind_at = |index: Option<usize>| {
+                index
+                    .and_then(|index| tokens.get(index))
+                    .map(|token| token.kind)
+            };
+            let previous = kind_at(index.checked_sub(1));
+            let next = kind_at(Some(index + 1));
+            if !open {
+                // Executable definitions end with the brace closing their selection set
+                open = true;
+                let name = match token.text {
+                    "query" | "mutation" | "subscription" | "fragment"
+                        if token.kind == TokenKind::Name && next == Some(TokenKind::Name) =>
+                    {
+                        Some(tokens[index + 1].location())
+                    }
+                    _ => None,
+                };
+                map.definitions.push((token.location(), name));
+            }
+            let location = token.location();
+            let top = nesting.last().copied();
+            if top == Some(Nesting::Arguments)
+                && matches!(
+                    token.kind,
+                    TokenKind::Equals | TokenKind::CloseParen | TokenKind::Dollar | TokenKind::At
+                )
+            {
+                in_type = false;
+            }
+            let in_value = matches!(top, Some(Nesting::List) | Some(Nesting::Object));
+            let value_start = match top {
+                _ if in_type => false,
+                _ if previous == Some(TokenKind::Equals) => true,
+                Some(Nesting::Arguments) | Some(Nesting::Object) => {
+                    previous == Some(TokenKind::Colon)
+                }
+                Some(Nesting::List) => {
+                    token.kind != TokenKind::CloseSquare
+                        && !(token.kind == TokenKind::Name && previous == Some(TokenKind::Dollar))
+                }
+                _ => false,
+            };
+            if value_start {
+                map.values.all.push(location);
+            }
+            match token.kind {
+                TokenKind::OpenSquare if in_type => nesting.push(Nesting::Type),
+                TokenKind::OpenSquare => nesting.push(Nesting::List),
+                TokenKind::OpenBrace if in_value || value_start => nesting.push(Nesting::Object),
+                TokenKind::OpenBrace => nesting.push(Nesting::Selections),
+                TokenKind::OpenParen => nesting.push(Nesting::Arguments),
+                TokenKind::CloseBrace | TokenKind::CloseSquare | TokenKind::CloseParen => {
+                    let closed = nesting.pop();
+                    open = !nesting.is_empty() || closed != Some(Nesting::Selections);
+                }
+                // The type of a variable follows its name
+                TokenKind::Colon
+                    if top == Some(Nesting::Arguments)
+                        && index >= 2
+                        && tokens[index - 2].kind == TokenKind::Dollar =>
+                {
+                    in_type = true
+                }
+                TokenKind::At => map.directives.all.push(location),
+                TokenKind::Name
+                    if index >= 2
+                        && tokens[index - 1].kind == TokenKind::Name
+                        && tokens[index - 1].text == "on"
+                        && (top.is_none() || tokens[index - 2].kind == TokenKind::Spread) =>
+                {
+                    map.type_conditions.all.push(location)
+                }
+                TokenKind::Name => match top {
+                    Some(Nesting::Selections) => {
+                        let named = matches!(
+                            previous,
+                            Some(TokenKind::Colon) | Some(TokenKind::At) | Some(TokenKind::Spread)
+                        );
+                        if !named {
+                            map.fields.all.push(location);
+                        }
+                    }
+                    Some(Nesting::Arguments)
+                        if !in_type
+                            && next == Some(TokenKind::Colon)
+                            && previous != Some(TokenKind::Dollar) =>
+                    {
+                        map.arguments.all.push(location)
+                    }
+                    Some(Nesting::Object) if next == Some(TokenKind::Colon) => {
+                        map.object_fields.all.push(location)
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        if !skipped.is_empty() {
+            // The type system definitions start at their first token, and have no name
+            let mut executables = std::mem::take(&mut map.definitions).into_iter();
+            for (span, definition) in spans.iter().zip(&document.definitions) {
+                let start = if executable(definition) {
+                    executables.next()
+                } else {
+                    all.iter()
+                        .find(|token| in_span(span, token))
+                        .map(|token| (token.location(), None))
+                };
+                match start {
+                    Some(start) => map.definitions.push(start),
+                    None => return SourceMap::default(),
+                }
+            }
+        }
+        map
+    }
+
+    /// The location of the first token of a definition, by its index in the document.
+    fn start_of(&self, definition: usize) -> Option<Location> {
+        self.definitions.get(definition).map(|(start, _)| *start)
+    }
+
+    /// The location of the name of a definition, by its index in the document.
+    fn name_of(&self, definition: usize) -> Option<Location> {
+        self.definitions.get(definition).and_then(|(_, name)| *name)
+    }
+}
+
+/// The spans of the definitions of a source, each up to the start of the next one, or `None` when
+/// the source does not parse.
+fn definition_spans(source: &str) -> Option<Vec<Range<usize>>> {
+    let mut ast = Ast::from_lexer(Lexer::new(source));
+    ast.parse_start().ok()?;
+    let mut spans = Vec::new();
+    let mut start = 0;
+    while ast.parse_next_definition().ok()?.is_some() {
+        let end = ast.peek_position().unwrap_or(source.len());
+        spans.push(start..end);
+        start = end;
+    }
+    Some(spans)
+}
+
+/// Adds the names of the fragments spread in selections, and in their sub-selections, to the
+/// names not already there.
+fn spreads<'d>(selections: &'d [Selection], names: &mut Vec<&'d str>) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => {
+                spreads(field.selections.as_deref().unwrap_or_default(), names)
+            }
+            Selection::Fragment(FragmentSpread::Node(spread)) => {
+                if !names.contains(&spread.name.value.as_str()) {
+                    names.push(&spread.name.value);
+                }
+            }
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                spreads(&inline.selections, names)
+            }
+        }
+    }
+}
+
+/// Adds the fields selected by selections, through the fragments they spread but not in the
+/// sub-selections of their fields, following each fragment once.
+fn root_fields<'d>(
+    selections: &'d [Selection],
+    fragments: &[&'d FragmentDefinitionNode],
+    followed: &mut Vec<&'d str>,
+    fields: &mut Vec<&'d FieldNode>,
+) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => fields.push(field),
+            Selection::Fragment(FragmentSpread::Node(spread)) => {
+                let name = spread.name.value.as_str();
+                if followed.contains(&name) {
+                    continue;
+                }
+                followed.push(name);
+                if let Some(fragment) = fragments
+                    .iter()
+                    .find(|fragment| fragment.name.value == name)
+                {
+                    root_fields(&fragment.selections, fragments, followed, fields);
+                }
+            }
+            Selection::Fragment(FragmentSpread::Inline(inline)) => {
+                root_fields(&inline.selections, fragments, followed, fields)
+            }
+        }
+    }
+}
+
+/// Adds the locations of an issue to its error.
+fn located(
+    error: ValidationError,
+    locations: impl IntoIterator<Item = Location>,
+) -> ValidationError {
+    locations.into_iter().fold(error, ValidationError::at)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCHEMA: &str = r#"
+        scalar LongInt
+        scalar TinyUint
+        scalar LongUint
+        scalar BigInt
+        scalar BigUint
+        input Page { first: Int, after: LongInt }
+        input Filter { color: Color, page: Page!, tags: [String!]! = [] }
+        input Pick @oneOf { id: ID, name: String }
+        enum Color { RED GREEN }
+        type Query {
+            user(id: Int!): User
+            users(ids: [LongInt!], page: Page, size: TinyUint): [User]
+            average(of: Float): Float
+            search(filter: Filter, colors: [Color!]): [User]
+            pick(by: Pick): User
+            pet: Pet
+            wide(long: LongUint, big: BigInt, huge: BigUint): Int
+        }
+        interface Pet { name: String }
+        type Dog implements Pet { name: String barks: Boolean }
+        type Cat implements Pet { name: String meows: Boolean }
+        type Subscription { users: [User] user(id: Int!): User }
+        type User { id: ID! friends(first: Int): [User] }
+    "#;
+
+    fn validate(source: &str, options: ValidatorOptions) -> Vec<(String, Vec<Location>)> {
+        let schema_document = crate::parse(SCHEMA).unwrap();
+        let schema = Schema::new(&schema_document);
+        let document = crate::parse(source).unwrap();
+        Validator::with_options(&schema, options)
+            .validate(source, &document)
+            .into_iter()
+            .map(|error| (error.message, error.locations))
+            .collect()
+    }
+
+    #[test]
+    fn it_rejects_ints_outside_of_32_bits() {
+        let source = "query($page: Page = { first: 2147483648 }) {
+            user(id: 2147483647) { friends(first: -2147483649) { id } }
+            average(of: 3000000000)
+            ... on Query { users(ids: [1, 3000000000]) { id } }
+        }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "Int cannot represent non 32-bit signed integer value: 2147483648".into(),
+                    vec![Location::new(29, 1, 30)]
+                ),
+                (
+                    "Int cannot represent non 32-bit signed integer value: -2147483649".into(),
+                    vec![Location::new(95, 2, 51)]
+                ),
+                (
+                    "Int cannot represent non 32-bit signed integer value: 3000000000".into(),
+                    vec![Location::new(195, 4, 43)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_the_range_of_the_wide_scalars() {
+        let options = ValidatorOptions {
+            int_overflow: IntOverflow::WideScalars,
+            ..ValidatorOptions::default()
+        };
+        let source = "{
+            users(ids: [3000000000], page: { first: 1, after: -9000000000 }, size: 256) { id }
+        }";
+        assert_eq!(
+            validate(source, options),
+            [(
+                "TinyUint cannot represent integer value: 256".into(),
+                vec![Location::new(85, 2, 84)]
+            )]
+        );
+        assert_eq!(
+            validate("{ user(id: 3000000000) { id } }", options)[0].0,
+            "Int cannot represent non 32-bit signed integer value: 3000000000"
+        );
+    }
+
+    #[test]
+    fn it_checks_the_wide_scalars_beyond_64_bits() {
+        let options = ValidatorOptions {
+            int_overflow: IntOverflow::WideScalars,
+            ..ValidatorOptions::default()
+        };
+        let source = "{
+            wide(long: 18446744073709551615, big: -9223372036854775809, huge: 340282366920938463463374607431768211455)
+            average(of: 99999999999999999999999999999999999999999)
+        }";
+        assert!(validate(source, options).is_empty());
+        let source = "{
+            wide(long: 18446744073709551616, big: 170141183460469231731687303715884105728, huge: -1)
+        }";
+        assert_eq!(
+            validate(source, options),
+            [
+                (
+                    "LongUint cannot represent integer value: 18446744073709551616".into(),
+                    vec![Location::new(25, 2, 24)]
+                ),
+                (
+                    "BigInt cannot represent integer value: 170141183460469231731687303715884105728"
+                        .into(),
+                    vec![Location::new(52, 2, 51)]
+                ),
+                (
+                    "BigUint cannot represent integer value: -1".into(),
+                    vec![Location::new(99, 2, 98)]
+                ),
+            ]
+        );
+        assert_eq!(
+            validate(
+                "{ wide(big: 9223372036854775808) }",
+                ValidatorOptions::default()
+            )[0]
+            .0,
+            "Int cannot represent non 32-bit signed integer value: 9223372036854775808"
+        );
+    }
+
+    #[test]
+    fn it_requires_unique_operation_names() {
+        let source = "query Users { users { id } }
+            mutation Users { users { id } }
+            query Page($size: TinyUint = 10) { users(size: $size) { id } }
+            query Users { users { id } }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "There can be only one operation named \"Users\".".into(),
+                    vec![Location::new(6, 1, 7), Location::new(50, 2, 22)]
+                ),
+                (
+                    "There can be only one operation named \"Users\".".into(),
+                    vec![Location::new(6, 1, 7), Location::new(166, 4, 19)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_requires_anonymous_operations_to_be_alone() {
+        let source = "{ users { id } } query Users { users { id } } fragment F on User { id } { users { ...F } }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "This anonymous operation must be the only defined operation.".into(),
+                    vec![Location::new(0, 1, 1)]
+                ),
+                (
+                    "This anonymous operation must be the only defined operation.".into(),
+                    vec![Location::new(72, 1, 73)]
+                ),
+            ]
+        );
+        assert!(validate(
+            "{ users { ...F } } fragment F on User { id }",
+            ValidatorOptions::default()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn it_requires_unique_arguments_and_input_fields() {
+        let source = "query($page: Page = { first: 1, first: 2 }) {
+            user(id: 1, id: 2) @include(if: true, if: false) { id }
+            alias: users(page: { after: 1, first: 2, after: 3 }) { id }
+        }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "There can be only one input field named \"first\".".into(),
+                    vec![Location::new(22, 1, 23), Location::new(32, 1, 33)]
+                ),
+                (
+                    "There can be only one argument named \"id\".".into(),
+                    vec![Location::new(63, 2, 18), Location::new(70, 2, 25)]
+                ),
+                (
+                    "There can be only one argument named \"if\".".into(),
+                    vec![Location::new(86, 2, 41), Location::new(96, 2, 51)]
+                ),
+                (
+                    "There can be only one input field named \"after\".".into(),
+                    vec![Location::new(147, 3, 34), Location::new(167, 3, 54)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_requires_the_required_arguments() {
+        let source = "{
+            user { id }
+            alias: user(id: 1) { friends { id @skip } }
+            users { id @include(if: false) }
+        }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "Field \"user\" argument \"id\" of type \"Int!\" is required, but it was not provided.".into(),
+                    vec![Location::new(14, 2, 13)]
+                ),
+                (
+                    "Directive \"@skip\" argument \"if\" of type \"Boolean!\" is required, but it was not provided.".into(),
+                    vec![Location::new(72, 3, 47)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_validates_fragments() {
+        let source = "query { user(id: 1) { ...Friends ... on Person { id } } }
+            fragment Friends on User { friends { ...Names } }
+            fragment Names on User { id }
+            fragment Friends on User { id }
+            fragment Unused on User { ...Unknown }
+            fragment Unknown on Unknown { id }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "There can be only one fragment named \"Friends\".".into(),
+                    vec![Location::new(79, 2, 22), Location::new(183, 4, 22)]
+                ),
+                (
+                    "Fragment \"Unused\" is never used.".into(),
+                    vec![Location::new(218, 5, 13)]
+                ),
+                (
+                    "Fragment \"Unknown\" is never used.".into(),
+                    vec![Location::new(269, 6, 13)]
+                ),
+                (
+                    "Unknown type \"Person\".".into(),
+                    vec![Location::new(40, 1, 41)]
+                ),
+                (
+                    "Unknown type \"Unknown\".".into(),
+                    vec![Location::new(289, 6, 33)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_the_types_of_values() {
+        let source = r#"{
+            search(filter: { color: "RED", page: { first: "1" }, tags: null, size: 1 }) { id }
+            alias: search(filter: { tags: "a" }, colors: [RED, PURPLE, null]) { id }
+            average(of: true)
+            users(page: 1, ids: 1) { id }
+        }"#;
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "Enum \"Color\" cannot represent non-enum value: \"RED\". Did you mean the enum value \"RED\"?".into(),
+                    vec![Location::new(38, 2, 37)]
+                ),
+                (
+                    "Int cannot represent non-integer value: \"1\"".into(),
+                    vec![Location::new(60, 2, 59)]
+                ),
+                (
+                    "Expected value of type \"[String!]!\", found null.".into(),
+                    vec![Location::new(73, 2, 72)]
+                ),
+                (
+                    "Field \"size\" is not defined by type \"Filter\".".into(),
+                    vec![Location::new(79, 2, 78)]
+                ),
+                (
+                    "Field \"Filter.page\" of required type \"Page!\" was not provided.".into(),
+                    vec![Location::new(131, 3, 35)]
+                ),
+                (
+                    "Value \"PURPLE\" does not exist in \"Color\" enum.".into(),
+                    vec![Location::new(160, 3, 64)]
+                ),
+                (
+                    "Expected value of type \"Color!\", found null.".into(),
+                    vec![Location::new(168, 3, 72)]
+                ),
+                (
+                    "Float cannot represent non numeric value: true".into(),
+                    vec![Location::new(206, 4, 25)]
+                ),
+                (
+                    "Expected value of type \"Page\", found 1.".into(),
+                    vec![Location::new(236, 5, 25)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_requires_the_fields_of_a_response_key_to_merge() {
+        let source = "query Conflicts { user(id: 1) { id } user(id: 2) { id } me: average me: pet }
+            query Nested { user(id: 1) { friends { id } ...Friends } }
+            fragment Friends on User { friends(first: 1) { id } }
+            query Pets { pet { ... on Dog { sound: barks } ... on Cat { sound: meows } ...Names } }
+            fragment Names on Pet { name ... on Pet { name: name } }
+            query Same { user(id: 1) { id } ... on Query { user(id: 1) { friends { id } } } }
+            query Deep { user(id: 1) { friends { id } } user(id: 1) { friends { id: friends { id } } } }";
+        let reason = "Use different aliases on the fields to fetch both if this was intentional.";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    format!("Fields \"user\" conflict because they have differing arguments. {}", reason),
+                    vec![Location::new(0, 1, 1)]
+                ),
+                (
+                    format!("Fields \"me\" conflict because \"average\" and \"pet\" are different fields. {}", reason),
+                    vec![Location::new(0, 1, 1)]
+                ),
+                (
+                    format!("Fields \"friends\" conflict because they have differing arguments. {}", reason),
+                    vec![Location::new(90, 2, 13)]
+                ),
+                (
+                    format!(
+                        "Fields \"user\" conflict because subfields \"friends\" conflict because subfields \"id\" conflict because \"id\" and \"friends\" are different fields. {}",
+                        reason
+                    ),
+                    vec![Location::new(490, 7, 13)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_requires_subscriptions_to_select_a_single_root_field() {
+        let source = "subscription Users { users { id } ...Root }
+            subscription Aliases { ... on Subscription { user: users { id } users { id } } }
+            subscription Typename { __typename }
+            fragment Root on Subscription { user(id: 1) { id } }";
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "Subscription \"Users\" must select only one top level field.".into(),
+                    vec![Location::new(0, 1, 1)]
+                ),
+                (
+                    "Subscription \"Aliases\" must select only one top level field.".into(),
+                    vec![Location::new(56, 2, 13)]
+                ),
+                (
+                    "Subscription \"Typename\" must not select an introspection top level field."
+                        .into(),
+                    vec![Location::new(149, 3, 13)]
+                ),
+            ]
+        );
+        assert_eq!(
+            validate("subscription { users { id } }", ValidatorOptions::default()),
+            []
+        );
+        assert_eq!(
+            validate(
+                "subscription { __schema { types { name } } }",
+                ValidatorOptions::default()
+            )[0]
+            .0,
+            "Anonymous Subscription must not select an introspection top level field."
+        );
+    }
+
+    #[test]
+    fn it_limits_the_cost_of_operations_to_the_one_the_schema_sets() {
+        let schema_document = crate::parse(
+            "directive @cost(weight: Int!) on FIELD_DEFINITION
+            directive @costLimit(max: Int!) on SCHEMA
+            type Query { users: [User] @cost(weight: 5) me: User }
+            type User { id: ID! friends: [User] @cost(weight: 3) }
+            extend schema @costLimit(max: 10)",
+        )
+        .unwrap();
+        let schema = Schema::new(&schema_document);
+        let source = "query Cheap { me { id } users { id } }
+            query Costly { users { friends { ...friend } } }
+            fragment friend on User { id friends { id } }";
+        let document = crate::parse(source).unwrap();
+        let errors: Vec<(String, Vec<Location>)> = Validator::new(&schema)
+            .validate(source, &document)
+            .into_iter()
+            .map(|error| (error.message, error.locations))
+            .collect();
+        assert_eq!(
+            errors,
+            [(
+                "Operation \"Costly\" has a cost of 13, which exceeds the maximum cost of 10."
+                    .into(),
+                vec![Location::new(51, 2, 13)]
+            )]
+        );
+        let options = ValidatorOptions {
+            max_cost: Some(13),
+            ..ValidatorOptions::default()
+        };
+        let validator = Validator::with_options(&schema, options);
+        assert!(validator.validate(source, &document).is_empty());
+    }
+
+    #[test]
+    fn it_requires_a_single_field_of_one_of_input_objects() {
+        let source = r#"{
+            pick(by: { id: 1 }) { id }
+            a: pick(by: { id: 1, name: "a" }) { id }
+            b: pick(by: { name: null }) { id }
+        }"#;
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "OneOf Input Object \"Pick\" must specify exactly one key.".into(),
+                    vec![Location::new(65, 3, 25)]
+                ),
+                (
+                    "Field \"Pick.name\" must be non-null.".into(),
+                    vec![Location::new(118, 4, 25)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_checks_scalars_with_their_validators() {
+        let schema_document = crate::parse(
+            "scalar DateTime type Query { at(time: DateTime, times: [DateTime], id: ID): Int }",
+        )
+        .unwrap();
+        let schema = Schema::new(&schema_document);
+        let mut validator = Validator::new(&schema);
+        validator.register_scalar("DateTime", |value: &ValueNode| match value {
+            ValueNode::Str(string) if string.value.contains('T') => Ok(()),
+            _ => Err("not an RFC 3339 date-time".to_string()),
+        });
+        validator.register_scalar("ID", |value: &ValueNode| match value {
+            ValueNode::Str(string) if string.value.is_empty() => Err("empty".to_string()),
+            _ => Ok(()),
+        });
+        let source = r#"{
+            at(time: "2020-01-31T12:30:00Z", times: ["2020-01-31", 1], id: "")
+            b: at(time: null, id: true)
+        }"#;
+        let document = crate::parse(source).unwrap();
+        let errors: Vec<(String, Vec<Location>)> = validator
+            .validate(source, &document)
+            .into_iter()
+            .map(|error| (error.message, error.locations))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (
+                    "Expected value of type \"DateTime\", found \"2020-01-31\"; not an RFC 3339 date-time".into(),
+                    vec![Location::new(55, 2, 54)]
+                ),
+                (
+                    "Expected value of type \"DateTime\", found 1; not an RFC 3339 date-time"
+                        .into(),
+                    vec![Location::new(69, 2, 68)]
+                ),
+                (
+                    "Expected value of type \"ID\", found \"\"; empty".into(),
+                    vec![Location::new(77, 2, 76)]
+                ),
+                (
+                    "ID cannot represent a non-string and non-integer value: true".into(),
+                    vec![Location::new(115, 3, 35)]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_skips_the_type_system_definitions_to_locate_the_issues() {
+        let source = r#"type Extra { user(id: Int = 1): Int }
+query Users { user(id: 3000000000) { id } }
+"A scalar" scalar Date @specifiedBy(url: "x")
+query Users { users { id } }"#;
+        assert_eq!(
+            validate(source, ValidatorOptions::default()),
+            [
+                (
+                    "There can be only one operation named \"Users\".".into(),
+                    vec![Location::new(44, 2, 7), Location::new(134, 4, 7)]
+                ),
+                (
+                    "Int cannot represent non 32-bit signed integer value: 3000000000".into(),
+                    vec![Location::new(61, 2, 24)]
+                ),
+            ]
+        );
+        assert_eq!(
+            validate(
+                "type Extra { id: ID } { user(id: 3000000000) { id } }",
+                ValidatorOptions::default()
+            )[0]
+            .1,
+            [Location::new(33, 1, 34)]
+        );
+    }
+}
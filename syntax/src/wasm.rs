@@ -0,0 +1,103 @@
+//! Bindings for JavaScript, built with `wasm-bindgen`.
+//!
+//! The functions take GraphQL strings and return strings, so they are easy to call from a web
+//! playground or from Node tooling:
+//!
+//! - `parse` returns the document as JSON, in the shape of the [`Document`] and its nodes.
+//! - `validate` checks the operations of a document against a schema and returns a JSON array of
+//!   error messages, which is empty when the document is valid.
+//! - `print` formats a document with the [`printer`].
+//!
+//! Parse errors are thrown as strings.
+//!
+//! The module is built with `wasm-pack build --target web -- --features wasm`.
+//!
+//! ```js
+//! import { parse, validate, print } from "syntax";
+//!
+//! const ast = JSON.parse(parse("{ me { id } }"));
+//! const errors = JSON.parse(validate("type Query { me: User } type User { id: ID! }", "{ me { name } }"));
+//! console.log(print("type User{id:ID!}"));
+//! ```
+//!
+//! [`Document`]: ../document/struct.Document.html
+//! [`printer`]: ../printer/index.html
+
+use crate::schema::Schema;
+use crate::shape::response_shape;
+use wasm_bindgen::prelude::*;
+
+/// Parses a document and returns it as JSON.
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<String, JsValue> {
+    parse_json(source).map_err(|error| JsValue::from_str(&error))
+}
+
+/// Validates the operations of `document` against `schema`, and returns the error messages as a
+/// JSON array.
+#[wasm_bindgen]
+pub fn validate(schema: &str, document: &str) -> Result<String, JsValue> {
+    validate_json(schema, document).map_err(|error| JsValue::from_str(&error))
+}
+
+/// Parses a document and prints it back with the [`printer`].
+///
+/// [`printer`]: ../printer/index.html
+#[wasm_bindgen]
+pub fn print(source: &str) -> Result<String, JsValue> {
+    print_source(source).map_err(|error| JsValue::from_str(&error))
+}
+
+fn parse_json(source: &str) -> Result<String, String> {
+    let document = crate::parse(source).map_err(|error| error.to_string())?;
+    serde_json::to_string(&document).map_err(|error| error.to_string())
+}
+
+fn validate_json(schema: &str, document: &str) -> Result<String, String> {
+    let schema_document = crate::parse(schema).map_err(|error| error.to_string())?;
+    let schema = Schema::new(&schema_document);
+    let document = crate::parse(document).map_err(|error| error.to_string())?;
+    let errors: Vec<String> = document
+        .operations()
+        .iter()
+        .filter_map(|operation| response_shape(&schema, operation).err())
+        .map(|error| error.message)
+        .collect();
+    serde_json::to_string(&errors).map_err(|error| error.to_string())
+}
+
+fn print_source(source: &str) -> Result<String, String> {
+    let document = crate::parse(source).map_err(|error| error.to_string())?;
+    Ok(crate::printer::print(&document))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn it_parses_to_json() {
+        let json: Value = serde_json::from_str(&parse_json("{ me { id } }").unwrap()).unwrap();
+        let selections = &json["definitions"][0]["Executable"]["Operation"]["Query"]["selections"];
+        assert_eq!(selections[0]["Field"]["name"]["value"], "me");
+        assert!(parse_json("{ me").is_err());
+    }
+
+    #[test]
+    fn it_validates_operations() {
+        let schema = "type Query { me: User } type User { id: ID! }";
+        assert_eq!(validate_json(schema, "{ me { id } }").unwrap(), "[]");
+        let errors: Vec<String> =
+            serde_json::from_str(&validate_json(schema, "{ me { name } }").unwrap()).unwrap();
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn it_prints() {
+        assert_eq!(
+            print_source("type User{id:ID!}").unwrap(),
+            "type User {\n  id: ID!\n}\n"
+        );
+    }
+}
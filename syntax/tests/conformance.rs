@@ -0,0 +1,161 @@
+//! Conformance with the language test fixtures of graphql-js.
+//!
+//! Every fixture file is a list of cases, each starting with a `# case: <name>` comment. A case
+//! is expected to parse, and to parse to the same document once printed, unless it is marked
+//! with `# expect: reject`, in which case it is expected to fail to parse.
+//!
+//! The cases the parser does not handle yet are listed in `KNOWN_FAILURES`. The test fails when
+//! a case starts failing, and also when a known failure starts passing, so the list and
+//! `CONFORMANCE_PERCENT` are kept up to date as the parser improves.
+
+use syntax::printer::print;
+
+const FIXTURES: [(&str, &str); 3] = [
+    (
+        "kitchen_sink.graphql",
+        include_str!("fixtures/kitchen_sink.graphql"),
+    ),
+    (
+        "schema_kitchen_sink.graphql",
+        include_str!("fixtures/schema_kitchen_sink.graphql"),
+    ),
+    (
+        "syntax_errors.graphql",
+        include_str!("fixtures/syntax_errors.graphql"),
+    ),
+];
+
+const KNOWN_FAILURES: &[&str] = &[
+    // Directives on operations, and subscriptions
+    "named query with fragments",
+    "mutation",
+    "subscription with variable directives",
+    // Types without fields, values or members
+    "object without fields",
+    "interface without fields",
+    "union without members",
+    "enum without values",
+    "input without fields",
+    // Interfaces implementing interfaces
+    "interface implementing interfaces",
+    // Extensions of anything but object types
+    "interface extension with fields",
+    "interface extension with directives",
+    "union extension with members",
+    "union extension with directives",
+    "scalar extension",
+    "enum extension with values",
+    "enum extension with directives",
+    "input extension with fields",
+    "input extension with directives",
+    // Documents that should be rejected
+    "fragment named on",
+    "variable in a constant value",
+];
+
+/// The share of cases that pass, rounded down.
+const CONFORMANCE_PERCENT: usize = 67;
+
+struct Case<'a> {
+    name: &'a str,
+    source: String,
+    reject: bool,
+}
+
+fn cases(fixture: &str) -> Vec<Case<'_>> {
+    let mut cases: Vec<Case> = Vec::new();
+    for line in fixture.lines() {
+        if let Some(name) = line.strip_prefix("# case: ") {
+            cases.push(Case {
+                name,
+                source: String::new(),
+                reject: false,
+            });
+        } else if let Some(case) = cases.last_mut() {
+            if line == "# expect: reject" {
+                case.reject = true;
+            } else {
+                case.source.push_str(line);
+                case.source.push('\n');
+            }
+        }
+    }
+    cases
+}
+
+/// Runs a case, and describes how it failed.
+fn run(case: &Case) -> Result<(), String> {
+    match (syntax::parse(&case.source), case.reject) {
+        (Ok(_), true) => Err("parsed, but should have been rejected".into()),
+        (Err(_), true) => Ok(()),
+        (Err(error), false) => Err(error.to_string()),
+        (Ok(document), false) => {
+            let printed = print(&document);
+            match syntax::parse(&printed) {
+                Ok(reparsed) if reparsed == document => Ok(()),
+                Ok(_) => Err(format!("printed as a different document:\n{}", printed)),
+                Err(error) => Err(format!(
+                    "printed as\n{}\nwhich fails with {}",
+                    printed, error
+                )),
+            }
+        }
+    }
+}
+
+#[test]
+fn it_conforms_to_the_graphql_js_fixtures() {
+    let mut total = 0;
+    let mut unexpected = Vec::new();
+    let mut fixed = Vec::new();
+    for (file, fixture) in FIXTURES.iter() {
+        for case in cases(fixture) {
+            total += 1;
+            let known = KNOWN_FAILURES.contains(&case.name);
+            match run(&case) {
+                Ok(()) if known => fixed.push(format!("{}: {}", file, case.name)),
+                Err(reason) if !known => {
+                    unexpected.push(format!("{}: {}: {}", file, case.name, reason))
+                }
+                _ => {}
+            }
+        }
+    }
+    assert!(
+        unexpected.is_empty(),
+        "Cases failed:\n{}",
+        unexpected.join("\n")
+    );
+    assert!(
+        fixed.is_empty(),
+        "Cases now pass, remove them from KNOWN_FAILURES:\n{}",
+        fixed.join("\n")
+    );
+    let passed = total - KNOWN_FAILURES.len();
+    assert_eq!(
+        passed * 100 / total,
+        CONFORMANCE_PERCENT,
+        "{} of {} cases pass",
+        passed,
+        total
+    );
+}
+
+#[test]
+fn it_splits_fixtures_into_cases() {
+    let split = cases("# header\n# case: a\n{ a }\n# case: b\n# expect: reject\n{\n");
+    assert_eq!(split.len(), 2);
+    assert_eq!(split[0].name, "a");
+    assert_eq!(split[0].source, "{ a }\n");
+    assert!(!split[0].reject);
+    assert!(split[1].reject);
+    // Every known failure names a case.
+    let names: Vec<&str> = FIXTURES
+        .iter()
+        .flat_map(|(_, fixture)| cases(fixture))
+        .map(|case| case.name)
+        .collect();
+    for name in KNOWN_FAILURES {
+        assert!(names.contains(name), "Unknown case {}", name);
+    }
+}
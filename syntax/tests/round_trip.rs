@@ -0,0 +1,310 @@
+//! Property tests of the round trip from a document to its source and back.
+//!
+//! Documents are generated as source, then formatted by printing them once. Printing a formatted
+//! document again gives it back unchanged, and so does printing it losslessly once comments are
+//! added to it, at the end of its lines and on lines of their own.
+
+use proptest::prelude::*;
+use proptest::sample::select;
+use syntax::lossless::{parse_lossless, print_lossless};
+use syntax::printer::print;
+
+fn name() -> impl Strategy<Value = String> {
+    select(vec![
+        "id", "name", "user", "first", "after", "_private", "node2",
+    ])
+    .prop_map(String::from)
+}
+
+fn type_name() -> impl Strategy<Value = String> {
+    select(vec!["User", "Node", "Query", "Role", "Filter", "Date"]).prop_map(String::from)
+}
+
+fn type_reference() -> impl Strategy<Value = String> {
+    (type_name(), 0..4).prop_map(|(name, wrapping)| match wrapping {
+        0 => name,
+        1 => format!("{}!", name),
+        2 => format!("[{}]", name),
+        _ => format!("[{}!]!", name),
+    })
+}
+
+fn string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z #,]{0,8}".prop_map(|content| format!("\"{}\"", content)),
+        Just(r#""a \"b\" \\ é \n""#.to_string()),
+        "[a-z #,]{0,8}".prop_map(|content| format!("\"\"\"{}\"\"\"", content)),
+    ]
+}
+
+fn value(variables: bool) -> impl Strategy<Value = String> {
+    let leaf = prop_oneof![
+        any::<i32>().prop_map(|int| int.to_string()),
+        (0..1000u32, 0..100u32).prop_map(|(int, fraction)| format!("{}.{}", int, fraction)),
+        string(),
+        select(vec!["true", "false", "null", "ADMIN"]).prop_map(String::from),
+        name().prop_map(move |name| if variables {
+            format!("${}", name)
+        } else {
+            "RED".into()
+        }),
+    ];
+    leaf.prop_recursive(3, 16, 3, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..3)
+                .prop_map(|values| format!("[{}]", values.join(", "))),
+            prop::collection::vec((name(), inner), 1..3).prop_map(|fields| {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+                format!("{{{}}}", fields.join(", "))
+            }),
+        ]
+    })
+}
+
+fn arguments(variables: bool) -> impl Strategy<Value = String> {
+    prop::collection::vec((name(), value(variables)), 0..3).prop_map(|arguments| {
+        if arguments.is_empty() {
+            return String::new();
+        }
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        format!("({})", arguments.join(", "))
+    })
+}
+
+fn directives(variables: bool) -> impl Strategy<Value = String> {
+    prop::collection::vec((name(), arguments(variables)), 0..2).prop_map(|directives| {
+        directives
+            .iter()
+            .map(|(name, arguments)| format!(" @{}{}", name, arguments))
+            .collect()
+    })
+}
+
+fn field_definition() -> BoxedStrategy<String> {
+    let argument = (name(), type_reference(), prop::option::of(value(false)));
+    (
+        prop::option::of(string()),
+        name(),
+        prop::collection::vec(argument, 0..3),
+        type_reference(),
+        directives(false),
+    )
+        .prop_map(|(description, name, arguments, field_type, directives)| {
+            let arguments: Vec<String> = arguments
+                .iter()
+                .map(|(name, input_type, default)| match default {
+                    Some(default) => format!("{}: {} = {}", name, input_type, default),
+                    None => format!("{}: {}", name, input_type),
+                })
+                .collect();
+            let arguments = if arguments.is_empty() {
+                String::new()
+            } else {
+                format!("({})", arguments.join(", "))
+            };
+            format!(
+                "{} {}{}: {}{}",
+                description.unwrap_or_default(),
+                name,
+                arguments,
+                field_type,
+                directives
+            )
+        })
+        .boxed()
+}
+
+fn type_definition() -> impl Strategy<Value = String> {
+    let fields = prop::collection::vec(field_definition(), 1..4);
+    prop_oneof![
+        (
+            prop::option::of(string()),
+            type_name(),
+            prop::collection::vec(type_name(), 0..3),
+            directives(false),
+            fields.clone(),
+        )
+            .prop_map(|(description, name, interfaces, directives, fields)| {
+                let interfaces = if interfaces.is_empty() {
+                    String::new()
+                } else {
+                    format!(" implements {}", interfaces.join(" & "))
+                };
+                format!(
+                    "{} type {}{}{} {{ {} }}",
+                    description.unwrap_or_default(),
+                    name,
+                    interfaces,
+                    directives,
+                    fields.join(" ")
+                )
+            }),
+        (type_name(), fields.clone()).prop_map(|(name, fields)| format!(
+            "interface {} {{ {} }}",
+            name,
+            fields.join(" ")
+        )),
+        (type_name(), fields).prop_map(|(name, fields)| format!(
+            "extend type {} {{ {} }}",
+            name,
+            fields.join(" ")
+        )),
+        (type_name(), prop::collection::vec(type_name(), 1..4))
+            .prop_map(|(name, members)| format!("union {} = {}", name, members.join(" | "))),
+        (type_name(), prop::collection::vec("[A-Z]{2,5}", 1..4))
+            .prop_map(|(name, values)| format!("enum {} {{ {} }}", name, values.join(" "))),
+        (type_name(), directives(false))
+            .prop_map(|(name, directives)| format!("scalar {}{}", name, directives)),
+        (
+            type_name(),
+            prop::collection::vec((name(), type_reference()), 1..3)
+        )
+            .prop_map(|(name, fields)| {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, input_type)| format!("{}: {}", name, input_type))
+                    .collect();
+                format!("input {} {{ {} }}", name, fields.join(" "))
+            }),
+        (name(), type_reference()).prop_map(|(name, input_type)| format!(
+            "directive @{}(value: {}) repeatable on FIELD_DEFINITION | OBJECT",
+            name, input_type
+        )),
+    ]
+}
+
+fn selections() -> impl Strategy<Value = String> {
+    let field = (
+        prop::option::of(name()),
+        name(),
+        arguments(true),
+        directives(true),
+    )
+        .prop_map(|(alias, name, arguments, directives)| match alias {
+            Some(alias) => format!("{}: {}{}{}", alias, name, arguments, directives),
+            None => format!("{}{}{}", name, arguments, directives),
+        })
+        .boxed();
+    let leaf = prop_oneof![
+        field.clone(),
+        type_name().prop_map(|name| format!("...{}Fields", name)),
+    ];
+    let selection = leaf.prop_recursive(3, 12, 3, move |inner| {
+        let selection_set =
+            prop::collection::vec(inner, 1..3).prop_map(|selections| selections.join(" "));
+        prop_oneof![
+            (field.clone(), selection_set.clone())
+                .prop_map(|(field, selections)| format!("{} {{ {} }}", field, selections)),
+            (prop::option::of(type_name()), selection_set).prop_map(|(condition, selections)| {
+                match condition {
+                    Some(condition) => format!("... on {} {{ {} }}", condition, selections),
+                    None => format!("... @include(if: true) {{ {} }}", selections),
+                }
+            }),
+        ]
+    });
+    prop::collection::vec(selection, 1..4).prop_map(|selections| selections.join(" "))
+}
+
+fn executable_definition() -> impl Strategy<Value = String> {
+    let variable = (name(), type_reference(), prop::option::of(value(false)));
+    prop_oneof![
+        selections().prop_map(|selections| format!("{{ {} }}", selections)),
+        (
+            select(vec!["query", "mutation", "subscription"]),
+            type_name(),
+            prop::collection::vec(variable, 0..3),
+            selections(),
+        )
+            .prop_map(|(operation, name, variables, selections)| {
+                let variables: Vec<String> = variables
+                    .iter()
+                    .map(|(name, variable_type, default)| match default {
+                        Some(default) => format!("${}: {} = {}", name, variable_type, default),
+                        None => format!("${}: {}", name, variable_type),
+                    })
+                    .collect();
+                let variables = if variables.is_empty() {
+                    String::new()
+                } else {
+                    format!("({})", variables.join(", "))
+                };
+                format!("{} {}{} {{ {} }}", operation, name, variables, selections)
+            }),
+        (type_name(), directives(true), selections()).prop_map(
+            |(condition, directives, selections)| format!(
+                "fragment {}Fields on {}{} {{ {} }}",
+                condition, condition, directives, selections
+            )
+        ),
+    ]
+}
+
+/// A document, formatted by the printer.
+fn formatted_document() -> impl Strategy<Value = String> {
+    let definition = prop_oneof![type_definition(), executable_definition()];
+    prop::collection::vec(definition, 1..4).prop_map(|definitions| {
+        let source = definitions.join("\n");
+        let document = syntax::parse(&source)
+            .unwrap_or_else(|e| panic!("Unable to parse {}: {:?}", source, e));
+        print(&document)
+    })
+}
+
+fn comment() -> impl Strategy<Value = String> {
+    "#( [a-z#,\"]{1,5}){0,3}"
+}
+
+/// A formatted document with comments added at the end of lines and on lines of their own,
+/// indented like the line they come before.
+fn commented_document() -> impl Strategy<Value = String> {
+    formatted_document().prop_flat_map(|formatted| {
+        let lines = formatted.lines().count();
+        let comments = prop::collection::vec(
+            (prop::option::of(comment()), prop::option::of(comment())),
+            lines,
+        );
+        (Just(formatted), comments).prop_map(|(formatted, comments)| {
+            let mut commented = String::new();
+            for (line, (before, trailing)) in formatted.lines().zip(comments) {
+                if line.is_empty() {
+                    commented.push('\n');
+                    continue;
+                }
+                if let Some(before) = before {
+                    let indent = &line[..line.len() - line.trim_start().len()];
+                    commented.push_str(&format!("{}{}\n", indent, before));
+                }
+                commented.push_str(line);
+                if let Some(trailing) = trailing {
+                    commented.push_str(&format!(" {}", trailing));
+                }
+                commented.push('\n');
+            }
+            commented
+        })
+    })
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+
+    #[test]
+    fn printing_a_formatted_document_gives_it_back(formatted in formatted_document()) {
+        let document = syntax::parse(&formatted).unwrap();
+        prop_assert_eq!(print(&document), formatted);
+    }
+
+    #[test]
+    fn printing_a_commented_document_losslessly_gives_it_back(source in commented_document()) {
+        let (document, trivia) = parse_lossless(&source).unwrap();
+        prop_assert_eq!(&document, &syntax::parse(&source).unwrap());
+        prop_assert_eq!(print_lossless(&document, &trivia), source);
+    }
+}